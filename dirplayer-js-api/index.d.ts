@@ -55,6 +55,7 @@ type TVmCallbacks = {
   onFlashMemberLoaded?: (castLib: number, castMember: number, swfData: Uint8Array, width: number, height: number) => void,
   onFlashMemberUnloaded?: (castLib: number, castMember: number) => void,
   onStageSizeChanged?: (width: number, height: number, center: boolean) => void,
+  onFileSaved?: (path: string, data: Uint8Array) => void,
 }
 declare let vmCallbacks: TVmCallbacks | undefined;
 
@@ -119,6 +119,14 @@ export function onFlashMemberUnloaded(castLib, castMember) {
   }
 }
 
+export function onFileSaved(path, data) {
+  if (vmCallbacks?.onFileSaved) {
+    vmCallbacks.onFileSaved(path, data);
+  } else {
+    console.log('File saved:', path, data.length, 'bytes');
+  }
+}
+
 export function onStageSizeChanged(width, height, center) {
   if (vmCallbacks?.onStageSizeChanged) {
     vmCallbacks.onStageSizeChanged(width, height, center);
@@ -293,6 +293,16 @@ export class McpServer {
       case 'list_breakpoints':
         return this.wasm.mcp_list_breakpoints();
 
+      case 'get_xtra_report':
+        return this.wasm.mcp_get_xtra_report();
+
+      // Memory tools
+      case 'get_memory_stats':
+        return this.wasm.mcp_get_memory_stats();
+
+      case 'collect_cycles':
+        return this.wasm.mcp_collect_cycles();
+
       default:
         throw new Error(`Unknown tool: ${name}`);
     }
@@ -268,6 +268,35 @@ export const mcpTools: McpTool[] = [
       properties: {},
       required: []
     }
+  },
+  {
+    name: 'get_xtra_report',
+    description: 'List the Xtras the movie requires (from its XTRl chunk) and which of them are missing from the player',
+    inputSchema: {
+      type: 'object',
+      properties: {},
+      required: []
+    }
+  },
+
+  // Memory tools
+  {
+    name: 'get_memory_stats',
+    description: 'Get live datum and script instance counts and cycle collector statistics',
+    inputSchema: {
+      type: 'object',
+      properties: {},
+      required: []
+    }
+  },
+  {
+    name: 'collect_cycles',
+    description: 'Run the cycle collector now and report how many datums and script instances it freed',
+    inputSchema: {
+      type: 'object',
+      properties: {},
+      required: []
+    }
   }
 ];
 
@@ -295,4 +324,7 @@ export type McpToolName =
   | 'inspect_cast_member'
   | 'set_breakpoint'
   | 'remove_breakpoint'
-  | 'list_breakpoints';
+  | 'list_breakpoints'
+  | 'get_xtra_report'
+  | 'get_memory_stats'
+  | 'collect_cycles';
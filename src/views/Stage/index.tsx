@@ -7,6 +7,10 @@ import {
   mouse_move_delta,
   mouse_down,
   mouse_up,
+  right_mouse_down,
+  right_mouse_up,
+  activate_application,
+  deactivate_application,
   key_down,
   key_up,
   wants_pointer_lock,
@@ -271,6 +275,18 @@ export default function Stage({ showControls, enableGestures }: { showControls?:
     prevOuterSizeRef.current = { w: outerWidth, h: outerHeight };
   }, [outerWidth, outerHeight, stageWidth, stageHeight]);
 
+  // Movies pause or resume on activateApplication/deactivateApplication.
+  useEffect(() => {
+    const onFocus = () => activate_application();
+    const onBlur = () => deactivate_application();
+    window.addEventListener("focus", onFocus);
+    window.addEventListener("blur", onBlur);
+    return () => {
+      window.removeEventListener("focus", onFocus);
+      window.removeEventListener("blur", onBlur);
+    };
+  }, []);
+
   // Trackpad pinch-to-zoom and two-finger pan via wheel events.
   // Must be a non-passive listener so we can call preventDefault().
   useEffect(() => {
@@ -515,6 +531,15 @@ export default function Stage({ showControls, enableGestures }: { showControls?:
       return;
     }
 
+    // Right mouse button goes straight to the VM as rightMouseDown.
+    if (e.button === 2 && e.pointerType === 'mouse') {
+      const c = outerToCanvas(pointerOuterPos(e));
+      if (!pickingMode && isInsideCanvas(c)) {
+        right_mouse_down(c.x, c.y);
+      }
+      return;
+    }
+
     const p = pointerOuterPos(e);
     activePointersRef.current.set(e.pointerId, p);
     (e.currentTarget as HTMLElement).setPointerCapture(e.pointerId);
@@ -631,6 +656,14 @@ export default function Stage({ showControls, enableGestures }: { showControls?:
       return;
     }
 
+    if (e.button === 2 && e.pointerType === 'mouse') {
+      const c = outerToCanvas(pointerOuterPos(e));
+      if (!pickingMode) {
+        right_mouse_up(c.x, c.y);
+      }
+      return;
+    }
+
     if (!activePointersRef.current.has(e.pointerId)) return;
     const lastP = pointerOuterPos(e);
     const wasMultiTouch = activePointersRef.current.size >= 2;
@@ -698,6 +731,7 @@ export default function Stage({ showControls, enableGestures }: { showControls?:
       onPointerMove={onPointerMove}
       onPointerUp={onPointerUp}
       onPointerCancel={onPointerUp}
+      onContextMenu={e => e.preventDefault()}
       onKeyDown={e => {
         // When the hidden input is focused (editable field tapped), let its
         // own handlers + onInput drive key dispatch. Otherwise we'd double-fire
@@ -167,5 +167,15 @@ export function initVmCallbacks() {
         }
       }
     },
+    onFileSaved: (path: string, data: Uint8Array) => {
+      // Copy immediately - data is a view into WASM memory
+      const blob = new Blob([new Uint8Array(data)], { type: 'application/octet-stream' });
+      const url = URL.createObjectURL(blob);
+      const link = document.createElement('a');
+      link.href = url;
+      link.download = path.split(/[\\/]/).pop() || 'movie.dir';
+      link.click();
+      URL.revokeObjectURL(url);
+    },
   });
 }
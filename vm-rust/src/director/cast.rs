@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use binary_reader::BinaryReader;
 use itertools::Itertools;
@@ -153,7 +154,7 @@ impl CastDef {
             members: members,
             lctx: lctx.map(|_| ScriptContext {
                 scripts,
-                names: script_names.map_or(Vec::new(), |x| x.names),
+                names: Rc::new(script_names.map_or(Vec::new(), |x| x.names)),
             }),
             capital_x,
             dir_version: rifx.dir_version,
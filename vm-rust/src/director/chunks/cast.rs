@@ -1,4 +1,5 @@
 use binary_reader::BinaryReader;
+use binary_rw::{BinaryError, BinaryWriter};
 
 use crate::io::reader::DirectorExt;
 
@@ -20,3 +21,13 @@ impl CastChunk {
         });
     }
 }
+
+impl CastChunk {
+    pub fn write(&self, writer: &mut BinaryWriter) -> Result<usize, BinaryError> {
+        let mut written = 0;
+        for id in &self.member_ids {
+            written += writer.write_u32(*id)?;
+        }
+        Ok(written)
+    }
+}
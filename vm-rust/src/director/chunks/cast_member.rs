@@ -4,7 +4,7 @@ use log::debug;
 
 use crate::director::{
     chunks::cast_member_info::CastMemberInfoChunk,
-    enums::{BitmapInfo, FilmLoopInfo, FlashInfo, FontInfo, MemberType, ScriptType, ShapeInfo, SoundInfo, FieldInfo, TextInfo},
+    enums::{BitmapInfo, FilmLoopInfo, FlashInfo, FontInfo, MemberType, ScriptType, ShapeInfo, SoundInfo, FieldInfo, TextInfo, TransitionInfo},
 };
 
 use super::Chunk;
@@ -147,6 +147,10 @@ impl CastMemberChunk {
             MemberType::Sound => {
                 specific_data_parsed = CastMemberSpecificData::None;
             }
+            MemberType::Transition => {
+                specific_data_parsed =
+                    CastMemberSpecificData::Transition(TransitionInfo::from(specific_data.as_slice()));
+            }
             MemberType::Text => {
                 // Check if this is D6+ format with "text" FourCC header
                 if TextInfo::looks_like_text_info(specific_data.as_slice()) {
@@ -182,6 +186,7 @@ pub enum CastMemberSpecificData {
     Field(FieldInfo),
     Text(TextInfo),  // D6+ text member with "text" FourCC header
     Flash(FlashInfo),
+    Transition(TransitionInfo),
     None,
 }
 
@@ -226,6 +231,14 @@ impl CastMemberSpecificData {
         }
     }
 
+    pub fn transition_info(&self) -> Option<&TransitionInfo> {
+        if let CastMemberSpecificData::Transition(info) = self {
+            Some(info)
+        } else {
+            None
+        }
+    }
+
     pub fn sound_info(&self) -> Option<&SoundInfo> {
         if let CastMemberSpecificData::Sound(sound_info) = self {
             Some(sound_info)
@@ -1,5 +1,6 @@
 use crate::director::utils::{human_version, FOURCC};
 use binary_reader::{BinaryReader, Endian};
+use binary_rw::{BinaryError, BinaryWriter};
 use log::debug;
 
 pub struct ConfigChunk {
@@ -221,4 +222,67 @@ impl ConfigChunk {
 
         (check & 0xFFFFFFFF) as u32
     }
+
+    /// Protected movies carry a protection value divisible by 23, which
+    /// Director uses to refuse opening them in the authoring tool.
+    pub fn is_protected(&self) -> bool {
+        self.protection.is_multiple_of(23)
+    }
+
+    /// Clear the protection flag and refresh the checksum to match.
+    pub fn unprotect(&mut self, dir_endian: Endian) {
+        if self.is_protected() {
+            self.protection += 1;
+        }
+        self.checksum = self.compute_checksum(dir_endian);
+    }
+
+    /// Serialize as a `DRCF` chunk. The config is always big endian.
+    pub fn write(&self, writer: &mut BinaryWriter) -> Result<usize, BinaryError> {
+        let dir_version = human_version(self.director_version);
+
+        let mut written = writer.write_u16(self.len)?;
+        written += writer.write_u16(self.file_version)?;
+        written += writer.write_u16(self.movie_top)?;
+        written += writer.write_u16(self.movie_left)?;
+        written += writer.write_u16(self.movie_bottom)?;
+        written += writer.write_u16(self.movie_right)?;
+        written += writer.write_u16(self.min_member)?;
+        written += writer.write_u16(self.max_member)?;
+        written += writer.write_u8(self.field9)?;
+        written += writer.write_u8(self.field10)?;
+        if dir_version < 700 {
+            written += writer.write_u16(self.pre_d77field11)?;
+        } else {
+            written += writer.write_u8(self.d7_stage_color_g)?;
+            written += writer.write_u8(self.d7_stage_color_b)?;
+        }
+        written += writer.write_u16(self.comment_font)?;
+        written += writer.write_u16(self.comment_size)?;
+        written += writer.write_u16(self.comment_style)?;
+        if dir_version < 700 {
+            written += writer.write_u16(self.pre_d7_stage_color)?;
+        } else {
+            written += writer.write_u8(self.d7_stage_color_is_rgb)?;
+            written += writer.write_u8(self.d7_stage_color_r)?;
+        }
+        written += writer.write_u16(self.bit_depth)?;
+        written += writer.write_u8(self.field17)?;
+        written += writer.write_u8(self.field18)?;
+        written += writer.write_u32(self.field19)?;
+        written += writer.write_u16(self.director_version)?;
+        written += writer.write_u16(self.field21)?;
+        written += writer.write_u32(self.field22)?;
+        written += writer.write_u32(self.field23)?;
+        written += writer.write_u32(self.field24)?;
+        written += writer.write_u8(self.field25)?;
+        written += writer.write_u8(self.field26)?;
+        written += writer.write_u16(self.frame_rate)?;
+        written += writer.write_u16(self.platform)?;
+        written += writer.write_u16(self.protection)?;
+        written += writer.write_u32(self.field29)?;
+        written += writer.write_u32(self.checksum)?;
+        written += writer.write_bytes(self.remnants.as_slice())?;
+        Ok(written)
+    }
 }
@@ -0,0 +1,190 @@
+use binary_reader::{BinaryReader, Endian};
+
+/// A named marker inside a sound, stored as an offset in sample frames.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CuePoint {
+    pub time: u32,
+    pub name: String,
+}
+
+/// `cupt` chunk attached to sound cast members.
+///
+/// Layout (always big endian):
+/// - u16 unknown
+/// - u16 count
+/// - count × { u32 time (sample frames), 32-byte Pascal string name }
+#[derive(Clone, Debug, Default)]
+pub struct CuePointsChunk {
+    pub points: Vec<CuePoint>,
+    /// Sample rate of the sound the times were read from, when it differs
+    /// from the member's. Only set for cue points embedded in the media.
+    pub sample_rate: Option<u32>,
+}
+
+const CUE_POINT_NAME_SIZE: usize = 32;
+
+impl CuePointsChunk {
+    pub fn from_reader(reader: &mut BinaryReader) -> Result<CuePointsChunk, String> {
+        let original_endian = reader.endian;
+        reader.endian = Endian::Big;
+
+        let result = Self::read_points(reader);
+
+        reader.endian = original_endian;
+        result
+    }
+
+    fn read_points(reader: &mut BinaryReader) -> Result<CuePointsChunk, String> {
+        let _unk = reader
+            .read_u16()
+            .map_err(|e| format!("Failed to read cupt header: {}", e))?;
+        let count = reader
+            .read_u16()
+            .map_err(|e| format!("Failed to read cupt count: {}", e))?;
+
+        let mut points = Vec::with_capacity(count as usize);
+        for i in 0..count {
+            let time = reader
+                .read_u32()
+                .map_err(|e| format!("Failed to read time of cue point {}: {}", i, e))?;
+            let name_bytes = reader
+                .read_bytes(CUE_POINT_NAME_SIZE)
+                .map_err(|e| format!("Failed to read name of cue point {}: {}", i, e))?;
+            let len = (name_bytes[0] as usize).min(CUE_POINT_NAME_SIZE - 1);
+            let name = String::from_utf8_lossy(&name_bytes[1..1 + len]).into_owned();
+            points.push(CuePoint { time, name });
+        }
+
+        Ok(CuePointsChunk { points, sample_rate: None })
+    }
+
+    /// Cue points embedded in the sound data instead of a `cupt` chunk.
+    ///
+    /// Sounds imported from WAV or AIFF files can keep the whole file as
+    /// their media. WAV files store markers in a `cue ` chunk, named by the
+    /// `labl` entries of a `LIST`/`adtl` chunk; AIFF files store them in a
+    /// `MARK` chunk. Returns `None` for other data or when there are no
+    /// markers.
+    pub fn from_sound_data(data: &[u8]) -> Option<CuePointsChunk> {
+        if data.len() < 12 {
+            return None;
+        }
+        let chunk = match (&data[0..4], &data[8..12]) {
+            (b"RIFF", b"WAVE") => Self::from_wave(&data[12..]),
+            (b"FORM", b"AIFF" | b"AIFC") => Self::from_aiff(&data[12..]),
+            _ => return None,
+        };
+        (!chunk.points.is_empty()).then_some(chunk)
+    }
+
+    fn from_wave(data: &[u8]) -> CuePointsChunk {
+        let mut sample_rate = None;
+        let mut cues: Vec<(u32, u32)> = vec![];
+        let mut labels: Vec<(u32, String)> = vec![];
+        for (id, body) in iff_chunks(data, Endian::Little) {
+            match id {
+                b"fmt " if body.len() >= 8 => sample_rate = Some(le_u32(&body[4..8])),
+                b"cue " if body.len() >= 4 => {
+                    let count = le_u32(&body[0..4]) as usize;
+                    cues = body[4..]
+                        .chunks_exact(24)
+                        .take(count)
+                        .map(|cue| (le_u32(&cue[0..4]), le_u32(&cue[20..24])))
+                        .collect();
+                }
+                b"LIST" if body.starts_with(b"adtl") => {
+                    for (id, label) in iff_chunks(&body[4..], Endian::Little) {
+                        if id == b"labl" && label.len() >= 4 {
+                            let text = &label[4..];
+                            let end = text.iter().position(|&b| b == 0).unwrap_or(text.len());
+                            labels.push((
+                                le_u32(&label[0..4]),
+                                String::from_utf8_lossy(&text[..end]).into_owned(),
+                            ));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        let points = cues
+            .into_iter()
+            .map(|(cue_id, time)| CuePoint {
+                time,
+                name: labels
+                    .iter()
+                    .find(|(label_id, _)| *label_id == cue_id)
+                    .map(|(_, name)| name.clone())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        CuePointsChunk { points, sample_rate }
+    }
+
+    fn from_aiff(data: &[u8]) -> CuePointsChunk {
+        let mut sample_rate = None;
+        let mut points = vec![];
+        for (id, body) in iff_chunks(data, Endian::Big) {
+            match id {
+                b"COMM" if body.len() >= 18 => sample_rate = extended_to_u32(&body[8..18]),
+                b"MARK" if body.len() >= 2 => {
+                    let count = u16::from_be_bytes([body[0], body[1]]);
+                    let mut pos = 2;
+                    for _ in 0..count {
+                        // id (u16), position (u32), then a Pascal string
+                        // padded to an even length.
+                        let Some(header) = body.get(pos..pos + 7) else {
+                            break;
+                        };
+                        let time = u32::from_be_bytes([header[2], header[3], header[4], header[5]]);
+                        let len = header[6] as usize;
+                        let Some(name) = body.get(pos + 7..pos + 7 + len) else {
+                            break;
+                        };
+                        points.push(CuePoint {
+                            time,
+                            name: String::from_utf8_lossy(name).into_owned(),
+                        });
+                        pos += 6 + (1 + len).next_multiple_of(2);
+                    }
+                }
+                _ => {}
+            }
+        }
+        CuePointsChunk { points, sample_rate }
+    }
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// The (id, body) pairs of a sequence of RIFF/IFF chunks. Bodies are padded
+/// to an even length; a truncated last chunk is cut at the end of the data.
+fn iff_chunks(data: &[u8], endian: Endian) -> impl Iterator<Item = (&[u8; 4], &[u8])> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let header = data.get(pos..pos + 8)?;
+        let id: &[u8; 4] = header[0..4].try_into().unwrap();
+        let size_bytes = [header[4], header[5], header[6], header[7]];
+        let size = match endian {
+            Endian::Little => u32::from_le_bytes(size_bytes),
+            _ => u32::from_be_bytes(size_bytes),
+        } as usize;
+        let start = pos + 8;
+        let end = start.saturating_add(size).min(data.len());
+        pos = start.saturating_add(size.next_multiple_of(2));
+        Some((id, &data[start..end]))
+    })
+}
+
+/// Integer part of an 80-bit IEEE extended float, as used for AIFF sample
+/// rates.
+fn extended_to_u32(bytes: &[u8]) -> Option<u32> {
+    let exponent = (((bytes[0] & 0x7F) as i32) << 8 | bytes[1] as i32) - 16383;
+    let mantissa = u64::from_be_bytes(bytes[2..10].try_into().unwrap());
+    if bytes[0] & 0x80 != 0 || !(0..32).contains(&exponent) {
+        return None;
+    }
+    Some((mantissa >> (63 - exponent)) as u32)
+}
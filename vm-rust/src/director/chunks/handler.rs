@@ -34,6 +34,34 @@ pub struct Bytecode {
 }
 
 impl Bytecode {
+    pub fn new(opcode: OpCode, obj: i64, pos: usize, line_number: Option<u16>) -> Bytecode {
+        Bytecode {
+            opcode,
+            obj,
+            pos,
+            owner_loop: u32::MAX,
+            translation: None,
+            line_number,
+        }
+    }
+
+    /// Number of operand bytes `opcode` takes when encoded with `obj`.
+    /// Jumps always use two bytes so their targets can be patched in place.
+    pub fn operand_size(opcode: OpCode, obj: i64) -> usize {
+        if (opcode as u16) < 0x40 {
+            return 0;
+        }
+        match opcode {
+            OpCode::Jmp | OpCode::JmpIfZ | OpCode::EndRepeat | OpCode::PushInt16 => 2,
+            OpCode::PushInt32 | OpCode::PushFloat32 => 4,
+            OpCode::PushInt8 if (i8::MIN as i64..=i8::MAX as i64).contains(&obj) => 1,
+            OpCode::PushInt8 => 2,
+            _ if (0..=0xff).contains(&obj) => 1,
+            _ if (0..=0xffff).contains(&obj) => 2,
+            _ => 4,
+        }
+    }
+
     pub fn pos_to_str(pos: usize) -> String {
         format_args!("[{}]", pos).to_string()
     }
@@ -260,14 +288,7 @@ impl HandlerRecord {
                 }
             }
 
-            let bytecode = Bytecode {
-                opcode,
-                obj,
-                pos,
-                owner_loop: u32::MAX,
-                translation: None,
-                line_number: None,
-            };
+            let bytecode = Bytecode::new(opcode, obj, pos, None);
 
             bytecode_array.push(bytecode);
             bytecode_index_map.insert(pos, bytecode_array.len() - 1);
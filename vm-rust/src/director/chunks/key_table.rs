@@ -1,4 +1,5 @@
 use binary_reader::BinaryReader;
+use binary_rw::{BinaryError, BinaryWriter};
 
 
 #[derive(Clone)]
@@ -54,3 +55,23 @@ impl KeyTableChunk {
         });
     }
 }
+
+impl KeyTableChunk {
+    /// Serialize the table in the file's endianness. Unused slots up to
+    /// `entry_count` are written as zeroes.
+    pub fn write(&self, writer: &mut BinaryWriter) -> Result<usize, BinaryError> {
+        let used_count = self.entries.len() as u32;
+        let entry_count = self.entry_count.max(used_count);
+        let mut written = writer.write_u16(12u16)?;
+        written += writer.write_u16(12u16)?;
+        written += writer.write_u32(entry_count)?;
+        written += writer.write_u32(used_count)?;
+        for entry in &self.entries {
+            written += writer.write_u32(entry.section_id)?;
+            written += writer.write_u32(entry.cast_id)?;
+            written += writer.write_u32(entry.fourcc)?;
+        }
+        written += writer.write_bytes(vec![0u8; (entry_count - used_count) as usize * 12])?;
+        Ok(written)
+    }
+}
@@ -5,6 +5,7 @@ pub mod cast_list;
 pub mod cast_member;
 pub mod cast_member_info;
 pub mod config;
+pub mod cue_points;
 pub mod effect;
 pub mod handler;
 pub mod imap;
@@ -23,6 +24,7 @@ pub mod sound;
 pub mod text;
 pub mod thum;
 pub mod xmedia;
+pub mod xtra_list;
 pub mod w3d;
 pub mod xmedia_styled_text;
 
@@ -30,6 +32,7 @@ use std::collections::HashMap;
 
 use binary_reader::{BinaryReader, Endian};
 use config::ConfigChunk;
+use cue_points::CuePointsChunk;
 use imap::InitialMapChunk;
 use key_table::KeyTableChunk;
 use score::FrameLabelsChunk;
@@ -42,7 +45,7 @@ use self::{
     lctx::ScriptContextChunk, palette::PaletteChunk, score::ScoreChunk, script::ScriptChunk,
     script_names::ScriptNamesChunk, text::TextChunk,
 };
-use self::{cast_info::CastInfoChunk, effect::EffectChunk, thum::ThumChunk, xmedia::XMediaChunk};
+use self::{cast_info::CastInfoChunk, effect::EffectChunk, thum::ThumChunk, xmedia::XMediaChunk, xtra_list::XtraListChunk};
 use super::{
     guid::MoaID,
     rifx::RIFXReaderContext,
@@ -80,6 +83,8 @@ pub enum Chunk {
     CstInfo(CastInfoChunk),
     Effect(EffectChunk),
     Thum(ThumChunk),
+    CuePoints(CuePointsChunk),
+    XtraList(XtraListChunk),
     Raw(Vec<u8>),
 }
 
@@ -119,6 +124,13 @@ impl Chunk {
         }
     }
 
+    pub fn as_cue_points(&self) -> Option<&CuePointsChunk> {
+        match self {
+            Self::CuePoints(data) => Some(data),
+            _ => None,
+        }
+    }
+
     pub fn as_bytes(&self) -> Option<&Vec<u8>> {
         match self {
             Self::Raw(data) => { Some(data) }
@@ -143,6 +155,9 @@ pub struct ChunkContainer {
     pub deserialized_chunks: HashMap<u32, Chunk>,
     pub chunk_info: HashMap<u32, ChunkInfo>,
     pub cached_chunk_views: HashMap<u32, Vec<u8>>,
+    /// Original bytes of chunks whose cached view was transcoded on load
+    /// (SND-compressed sounds are cached as WAV). Used when writing the file back out.
+    pub raw_chunk_views: HashMap<u32, Vec<u8>>,
 }
 
 #[allow(dead_code)]
@@ -267,6 +282,8 @@ pub fn make_chunk(
         }
         "FXmp" => return Ok(Chunk::Effect(EffectChunk::from_reader(&mut chunk_reader)?)),
         "Thum" => return Ok(Chunk::Thum(ThumChunk::from_reader(&mut chunk_reader)?)),
+        "XTRl" => Ok(Chunk::XtraList(XtraListChunk::from_reader(&mut chunk_reader)?)),
+        "cupt" => Ok(Chunk::CuePoints(CuePointsChunk::from_reader(&mut chunk_reader)?)),
         "CLUT" => Ok(Chunk::Palette(palette::PaletteChunk::from_reader(
             &mut chunk_reader,
             version,
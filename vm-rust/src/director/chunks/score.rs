@@ -1,4 +1,5 @@
 use binary_reader::{BinaryReader, Endian};
+use binary_rw::{BinaryError, BinaryWriter};
 use log::{debug, error, warn};
 
 use crate::io::reader::DirectorExt;
@@ -351,6 +352,8 @@ pub struct ScoreFrameData {
     pub sound_channel_data: Vec<(u32, u16, SoundChannelData)>,
     pub tempo_channel_data: Vec<(u32, TempoChannelData)>,
     pub palette_channel_data: Vec<(u32, i16, i16)>,
+    pub palette_effect_data: Vec<(u32, PaletteChannelData)>,
+    pub transition_channel_data: Vec<(u32, i16, i16)>,
 }
 
 impl Default for ScoreFrameData {
@@ -362,6 +365,8 @@ impl Default for ScoreFrameData {
             sound_channel_data: Vec::new(),
             tempo_channel_data: Vec::new(),
             palette_channel_data: Vec::new(),
+            palette_effect_data: Vec::new(),
+            transition_channel_data: Vec::new(),
         }
     }
 }
@@ -466,6 +471,30 @@ impl TempoChannelData {
         })
     }
     
+    /// Build tempo data from a D5 frame header tempo byte. D5 encodes waits
+    /// differently from D6+: 128 = wait for click, 135/134 = wait for sound
+    /// 1/2, 161-255 = delay of (256 - tempo) seconds. They are normalised to
+    /// the D6+ codes (247 delay, 248 click, 254/255 sound).
+    pub fn from_d5_tempo(tempo: u8) -> Option<TempoChannelData> {
+        let (tempo, tempo_cue_point) = match tempo {
+            0 => return None,
+            128 => (248, 0),
+            135 => (254, 0),
+            134 => (255, 0),
+            161..=255 => (247, 256 - tempo as u16),
+            _ => (tempo, 0),
+        };
+        Some(TempoChannelData {
+            tempo,
+            tempo_cue_point,
+            sprite_list_idx: 0,
+            color_tempo: 0,
+            wait_flags: 0,
+            channel_flags: 0,
+            frame_data: 0,
+        })
+    }
+
     pub fn is_default(&self) -> bool {
         // Check if this is a "no change" marker (0xFFFE in high 16 bits)
         (self.sprite_list_idx >> 16) == 0xFFFE
@@ -477,6 +506,73 @@ impl TempoChannelData {
     }
 }
 
+/// Effect settings stored after the castLib/member pair of a palette channel
+/// record: transition speed, fade flags and the colour cycling range.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PaletteChannelData {
+    pub speed: u8,
+    pub flags: u8,
+    pub first_color: u8,
+    pub last_color: u8,
+    pub frame_count: u16,
+    pub cycle_count: u16,
+}
+
+impl PaletteChannelData {
+    pub const FADE_TO_BLACK: u8 = 0x01;
+    pub const FADE_TO_WHITE: u8 = 0x02;
+    pub const OVER_TIME: u8 = 0x04;
+    pub const AUTO_REVERSE: u8 = 0x10;
+    pub const COLOR_CYCLING: u8 = 0x80;
+
+    pub fn read(reader: &mut BinaryReader) -> Result<PaletteChannelData, String> {
+        let speed = reader
+            .read_u8()
+            .map_err(|e| format!("Failed to read palette speed: {:?}", e))?;
+        let flags = reader
+            .read_u8()
+            .map_err(|e| format!("Failed to read palette flags: {:?}", e))?;
+        let first_color = reader
+            .read_u8()
+            .map_err(|e| format!("Failed to read palette first_color: {:?}", e))?;
+        let last_color = reader
+            .read_u8()
+            .map_err(|e| format!("Failed to read palette last_color: {:?}", e))?;
+        let frame_count = reader
+            .read_u16()
+            .map_err(|e| format!("Failed to read palette frame_count: {:?}", e))?;
+        let cycle_count = reader
+            .read_u16()
+            .map_err(|e| format!("Failed to read palette cycle_count: {:?}", e))?;
+
+        Ok(PaletteChannelData {
+            speed,
+            flags,
+            first_color,
+            last_color,
+            frame_count,
+            cycle_count,
+        })
+    }
+
+    pub fn is_color_cycling(&self) -> bool {
+        self.flags & Self::COLOR_CYCLING != 0
+    }
+
+    /// The fade target colour, if the record fades to black or white.
+    pub fn fade_color(&self) -> Option<(u8, u8, u8)> {
+        if self.is_color_cycling() {
+            None
+        } else if self.flags & Self::FADE_TO_BLACK != 0 {
+            Some((0, 0, 0))
+        } else if self.flags & Self::FADE_TO_WHITE != 0 {
+            Some((255, 255, 255))
+        } else {
+            None
+        }
+    }
+}
+
 impl ScoreFrameData {
     #[allow(unused_variables)]
     pub fn read(reader: &mut BinaryReader) -> Result<ScoreFrameData, String> {
@@ -589,11 +685,13 @@ impl ScoreFrameData {
         let main_channels_size: usize = if header.frames_version <= 7 { 48 } else { 0 };
         let is_d5 = main_channels_size > 0;
 
-        let (decompressed_data, frame_channel_data, sound_channel_data, tempo_channel_data, palette_channel_data) = {
+        let (decompressed_data, frame_channel_data, sound_channel_data, tempo_channel_data, palette_channel_data, palette_effect_data, transition_channel_data) = {
             let mut frame_channel_data = vec![];
             let mut sound_channel_data = vec![];
             let mut tempo_channel_data = vec![];
             let mut palette_channel_data: Vec<(u32, i16, i16)> = vec![];
+            let mut palette_effect_data: Vec<(u32, PaletteChannelData)> = vec![];
+            let mut transition_channel_data: Vec<(u32, i16, i16)> = vec![];
             let decompressed_data = channel_data;
             let mut channel_reader = BinaryReader::from_vec(&decompressed_data);
             channel_reader.set_endian(Endian::Big);
@@ -650,19 +748,19 @@ impl ScoreFrameData {
                         }));
                     }
 
+                    // Transition: castLib at bytes 12-13, member at bytes 14-15
+                    channel_reader.jmp(frame_start + 12);
+                    let trans_cast_lib = channel_reader.read_i16().unwrap_or(0);
+                    let trans_member = channel_reader.read_i16().unwrap_or(0);
+                    if trans_member != 0 {
+                        transition_channel_data.push((frame_index, trans_cast_lib, trans_member));
+                    }
+
                     // Tempo at byte 21
                     channel_reader.jmp(frame_start + 21);
                     let tempo_val = channel_reader.read_u8().unwrap_or(0);
-                    if tempo_val > 0 {
-                        tempo_channel_data.push((frame_index, TempoChannelData {
-                            tempo: tempo_val,
-                            tempo_cue_point: 0,
-                            sprite_list_idx: 0,
-                            color_tempo: 0,
-                            wait_flags: 0,
-                            channel_flags: 0,
-                            frame_data: 0,
-                        }));
+                    if let Some(tempo_data) = TempoChannelData::from_d5_tempo(tempo_val) {
+                        tempo_channel_data.push((frame_index, tempo_data));
                     }
 
                     // Sound 1: castLib at bytes 4-5, member at bytes 6-7
@@ -692,6 +790,10 @@ impl ScoreFrameData {
                     if palette_cast_lib != 0 || palette_member != 0 {
                         palette_channel_data.push((frame_index, palette_cast_lib, palette_member));
                     }
+                    let palette_effect = PaletteChannelData::read(&mut channel_reader).unwrap_or_default();
+                    if palette_effect != PaletteChannelData::default() {
+                        palette_effect_data.push((frame_index, palette_effect));
+                    }
 
                     // Sprite channels start at byte 48 within the frame
                     let num_sprites = (frame_size - main_channels_size) / (header.sprite_record_size as usize);
@@ -723,8 +825,17 @@ impl ScoreFrameData {
                         let pos = frame_start + (channel_index as usize) * (header.sprite_record_size as usize);
                         channel_reader.jmp(pos);
 
-                        if channel_index == 0 || channel_index == 2 {
-                            // Channel 0 = Script, Channel 2 = Transition (skip)
+                        if channel_index == 0 {
+                            // Channel 0 = Script (skip)
+                        } else if channel_index == 2 {
+                            // Channel 2 = Transition: castLib at bytes 0-1, member at bytes 2-3
+                            let trans_cast_lib = channel_reader.read_i16()
+                                .map_err(|e| format!("Failed to read transition castLib: {:?}", e))?;
+                            let trans_member = channel_reader.read_i16()
+                                .map_err(|e| format!("Failed to read transition member: {:?}", e))?;
+                            if trans_member != 0 {
+                                transition_channel_data.push((frame_index, trans_cast_lib, trans_member));
+                            }
                         } else if channel_index == 1 {
                             // Channel 1 = Tempo
                             let tempo_data = TempoChannelData::read(&mut channel_reader)?;
@@ -749,6 +860,10 @@ impl ScoreFrameData {
                             if palette_cast_lib != 0 || palette_member != 0 {
                                 palette_channel_data.push((frame_index, palette_cast_lib, palette_member));
                             }
+                            let palette_effect = PaletteChannelData::read(&mut channel_reader)?;
+                            if palette_effect != PaletteChannelData::default() {
+                                palette_effect_data.push((frame_index, palette_effect));
+                            }
                         } else {
                             let data = ScoreFrameChannelData::read_with_size(&mut channel_reader, header.sprite_record_size)?;
 
@@ -785,7 +900,7 @@ impl ScoreFrameData {
                 header.frame_count, frame_channel_data.len(), sound_channel_data.len(), tempo_channel_data.len(), palette_channel_data.len()
             );
 
-            (decompressed_data, frame_channel_data, sound_channel_data, tempo_channel_data, palette_channel_data)
+            (decompressed_data, frame_channel_data, sound_channel_data, tempo_channel_data, palette_channel_data, palette_effect_data, transition_channel_data)
         };
 
         Ok(ScoreFrameData {
@@ -795,6 +910,8 @@ impl ScoreFrameData {
             sound_channel_data,
             tempo_channel_data,
             palette_channel_data,
+            palette_effect_data,
+            transition_channel_data,
         })
     }
 
@@ -1189,7 +1306,14 @@ impl ScoreChunk {
             let sprite_details = Self::parse_sprite_details_from_entries(&entries);
 
             Ok(ScoreChunk {
-                header: ScoreChunkHeader::default(),
+                header: ScoreChunkHeader {
+                    total_length: frames_stream_size,
+                    unk1: ver,
+                    unk2: list_start as u32,
+                    entry_count: num_entries as u32,
+                    unk3: list_size as u32,
+                    entry_size_sum: max_data_len as u32,
+                },
                 entries,
                 frame_intervals,
                 frame_data,
@@ -1216,6 +1340,32 @@ impl ScoreChunk {
         }
     }
 
+    /// Serialize a D6+ `VWSC` chunk from its entry list. D4/D5 scores keep no
+    /// entries and are written back from their original bytes instead.
+    pub fn write(&self, writer: &mut BinaryWriter) -> Result<usize, BinaryError> {
+        const LIST_START: u32 = 12;
+        let list_size = self.entries.len() as u32 + 1;
+        let data_len: usize = self.entries.iter().map(|entry| entry.len()).sum();
+        let total_length = LIST_START as usize + 12 + list_size as usize * 4 + data_len;
+
+        let mut written = writer.write_u32(total_length as u32)?;
+        written += writer.write_u32(self.header.unk1)?;
+        written += writer.write_u32(LIST_START)?;
+        written += writer.write_u32(self.entries.len() as u32)?;
+        written += writer.write_u32(list_size)?;
+        written += writer.write_u32(self.header.entry_size_sum)?;
+        let mut offset = 0u32;
+        for entry in &self.entries {
+            written += writer.write_u32(offset)?;
+            offset += entry.len() as u32;
+        }
+        written += writer.write_u32(offset)?;
+        for entry in &self.entries {
+            written += writer.write_bytes(entry.as_slice())?;
+        }
+        Ok(written)
+    }
+
     /// Parse sprite details directly from extracted VWSC entries.
     /// For spriteListIdx = N, entries[N] is the 44-byte sprite info,
     /// and entries[N+1] contains behaviors (8 bytes each: cast_lib u16, cast_member u16, initializer_idx u32).
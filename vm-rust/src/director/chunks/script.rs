@@ -1,9 +1,12 @@
 use binary_reader::BinaryReader;
+use binary_rw::{BinaryError, BinaryWriter};
 use itertools::Itertools;
 
 use crate::director::{chunks::literal::{LiteralStore, LiteralType}, lingo::datum::Datum};
 
-use super::handler::{HandlerDef, HandlerRecord};
+use super::handler::{Bytecode, HandlerDef, HandlerRecord};
+use crate::director::lingo::opcode::OpCode;
+use crate::io::encoding::encode_win1252;
 use crate::director::static_datum::StaticDatum;
 use std::collections::{hash_map::Entry, HashMap};
 
@@ -112,6 +115,189 @@ impl ScriptChunk {
     }
 }
 
+impl ScriptChunk {
+    /// Serialize as an `Lscr` chunk. Lingo scripts are always big endian.
+    ///
+    /// Handler line tables and vectors are not retained by the reader, so
+    /// they are written empty; the player and decompiler never consult them.
+    pub fn write(
+        &self,
+        writer: &mut BinaryWriter,
+        dir_version: u16,
+        capital_x: bool,
+    ) -> Result<usize, BinaryError> {
+        const HEADER_LENGTH: usize = 92;
+        let handler_record_len = if capital_x { 46 } else { 42 };
+        let literal_record_len = if dir_version >= 500 { 8 } else { 6 };
+
+        let properties_offset = HEADER_LENGTH;
+        let globals_offset = properties_offset + self.property_name_ids.len() * 2;
+        let handlers_offset = globals_offset;
+        let mut pos = handlers_offset + self.handlers.len() * handler_record_len;
+
+        // (compiled, compiled_offset, arguments_offset, locals_offset, globals_offset)
+        let mut handler_data = Vec::with_capacity(self.handlers.len());
+        for handler in &self.handlers {
+            let compiled = encode_bytecode(&handler.bytecode_array);
+            let compiled_offset = pos;
+            pos += compiled.len() + compiled.len() % 2;
+            let arguments_offset = pos;
+            pos += handler.argument_name_ids.len() * 2;
+            let locals_offset = pos;
+            pos += handler.local_name_ids.len() * 2;
+            let globals_offset = pos;
+            pos += handler.global_name_ids.len() * 2;
+            handler_data.push((compiled, compiled_offset, arguments_offset, locals_offset, globals_offset));
+        }
+
+        let literals_offset = pos;
+        let literals_data_offset = literals_offset + self.literals.len() * literal_record_len;
+        let mut literal_records = Vec::with_capacity(self.literals.len());
+        let mut literal_data: Vec<u8> = Vec::new();
+        for literal in &self.literals {
+            let offset = literal_data.len() as u32;
+            let record = match literal {
+                Datum::Int(value) => (LiteralType::Int, *value as u32),
+                Datum::String(value) => {
+                    let bytes = encode_win1252(value);
+                    literal_data.extend_from_slice(&(bytes.len() as u32 + 1).to_be_bytes());
+                    literal_data.extend_from_slice(&bytes);
+                    literal_data.push(0);
+                    (LiteralType::String, offset)
+                }
+                Datum::Float(value) => {
+                    literal_data.extend_from_slice(&8u32.to_be_bytes());
+                    literal_data.extend_from_slice(&value.to_be_bytes());
+                    (LiteralType::Float, offset)
+                }
+                Datum::JavaScript(data) => {
+                    literal_data.extend_from_slice(&(data.len() as u32).to_be_bytes());
+                    literal_data.extend_from_slice(data);
+                    (LiteralType::JavaScript, offset)
+                }
+                _ => {
+                    literal_data.extend_from_slice(&0u32.to_be_bytes());
+                    (LiteralType::Invalid, offset)
+                }
+            };
+            if !literal_data.len().is_multiple_of(2) {
+                literal_data.push(0);
+            }
+            literal_records.push(record);
+        }
+        let total_length = (literals_data_offset + literal_data.len()) as u32;
+
+        let mut written = writer.write_u32(0u32)?;
+        written += writer.write_u32(0u32)?;
+        written += writer.write_u32(total_length)?;
+        written += writer.write_u32(total_length)?;
+        written += writer.write_u16(HEADER_LENGTH as u16)?;
+        written += writer.write_u16(self.script_number)?;
+        written += writer.write_u16(0u16)?;
+        written += writer.write_u16(u16::MAX)?;
+        written += writer.write_bytes([0u8; 14])?;
+        written += writer.write_u32(0u32)?; // script flags
+        written += writer.write_u16(0u16)?;
+        written += writer.write_u32(0u32)?; // cast id
+        written += writer.write_u16(u16::MAX)?; // factory name id
+        written += writer.write_u16(0u16)?; // handler vectors
+        written += writer.write_u32(0u32)?;
+        written += writer.write_u32(0u32)?;
+        written += writer.write_u16(self.property_name_ids.len() as u16)?;
+        written += writer.write_u32(properties_offset as u32)?;
+        written += writer.write_u16(0u16)?;
+        written += writer.write_u32(globals_offset as u32)?;
+        written += writer.write_u16(self.handlers.len() as u16)?;
+        written += writer.write_u32(handlers_offset as u32)?;
+        written += writer.write_u16(self.literals.len() as u16)?;
+        written += writer.write_u32(literals_offset as u32)?;
+        written += writer.write_u32(literal_data.len() as u32)?;
+        written += writer.write_u32(literals_data_offset as u32)?;
+
+        for id in &self.property_name_ids {
+            written += writer.write_u16(*id)?;
+        }
+
+        for (handler, (compiled, compiled_offset, arguments_offset, locals_offset, globals_offset)) in
+            self.handlers.iter().zip(&handler_data)
+        {
+            written += writer.write_u16(handler.name_id)?;
+            written += writer.write_u16(0u16)?; // vector pos
+            written += writer.write_u32(compiled.len() as u32)?;
+            written += writer.write_u32(*compiled_offset as u32)?;
+            written += writer.write_u16(handler.argument_name_ids.len() as u16)?;
+            written += writer.write_u32(*arguments_offset as u32)?;
+            written += writer.write_u16(handler.local_name_ids.len() as u16)?;
+            written += writer.write_u32(*locals_offset as u32)?;
+            written += writer.write_u16(handler.global_name_ids.len() as u16)?;
+            written += writer.write_u32(*globals_offset as u32)?;
+            written += writer.write_u32(0u32)?;
+            written += writer.write_u16(0u16)?;
+            written += writer.write_u16(0u16)?; // line count
+            written += writer.write_u32(0u32)?; // line offset
+            if capital_x {
+                written += writer.write_u32(0u32)?; // stack height
+            }
+        }
+
+        for (handler, (compiled, ..)) in self.handlers.iter().zip(&handler_data) {
+            written += writer.write_bytes(compiled.as_slice())?;
+            if compiled.len() % 2 != 0 {
+                written += writer.write_u8(0u8)?;
+            }
+            for id in handler
+                .argument_name_ids
+                .iter()
+                .chain(&handler.local_name_ids)
+                .chain(&handler.global_name_ids)
+            {
+                written += writer.write_u16(*id)?;
+            }
+        }
+
+        for (literal_type, offset) in literal_records {
+            if dir_version >= 500 {
+                written += writer.write_u32(literal_type as u32)?;
+            } else {
+                written += writer.write_u16(literal_type as u16)?;
+            }
+            written += writer.write_u32(offset)?;
+        }
+        written += writer.write_bytes(literal_data)?;
+        Ok(written)
+    }
+}
+
+/// Encode a handler's bytecode, choosing the operand width the same way the
+/// reader decodes it: `op` for one byte, `op + 0x40` for two, `op + 0x80` for four.
+fn encode_bytecode(bytecode_array: &[Bytecode]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for bytecode in bytecode_array {
+        let op = bytecode.opcode as u16;
+        match Bytecode::operand_size(bytecode.opcode, bytecode.obj) {
+            0 => out.push(op as u8),
+            1 => {
+                out.push(op as u8);
+                out.push(bytecode.obj as u8);
+            }
+            2 => {
+                out.push((op + 0x40) as u8);
+                match bytecode.opcode {
+                    OpCode::PushInt8 | OpCode::PushInt16 => {
+                        out.extend_from_slice(&(bytecode.obj as i16).to_be_bytes())
+                    }
+                    _ => out.extend_from_slice(&(bytecode.obj as u16).to_be_bytes()),
+                }
+            }
+            _ => {
+                out.push((op + 0x80) as u8);
+                out.extend_from_slice(&(bytecode.obj as i32).to_be_bytes());
+            }
+        }
+    }
+    out
+}
+
 /// JavaScript Lscr chunks store ONE compiled JS script in the literal data area.
 /// The on-wire format is Mozilla SpiderMonkey 1.5's XDR-serialized JSScript
 /// (magic `0xDEAD0003`), produced by `js_XDRScript` in jsdmx/src/jsscript.c.
@@ -1,6 +1,7 @@
 use binary_reader::BinaryReader;
+use binary_rw::{BinaryError, BinaryWriter};
 
-use crate::io::reader::DirectorExt;
+use crate::io::{encoding::encode_win1252, reader::DirectorExt};
 
 pub struct ScriptNamesChunk {
     pub names: Vec<String>,
@@ -29,3 +30,32 @@ impl ScriptNamesChunk {
         return Ok(ScriptNamesChunk { names });
     }
 }
+
+impl ScriptNamesChunk {
+    /// Serialize as an `Lnam` chunk. Lingo chunks are always big endian.
+    pub fn write(&self, writer: &mut BinaryWriter) -> Result<usize, BinaryError> {
+        let names: Vec<Vec<u8>> = self
+            .names
+            .iter()
+            .map(|name| {
+                let mut bytes = encode_win1252(name);
+                bytes.truncate(u8::MAX as usize);
+                bytes
+            })
+            .collect();
+        let names_offset: u16 = 20;
+        let len = names_offset as u32 + names.iter().map(|n| n.len() as u32 + 1).sum::<u32>();
+
+        let mut written = writer.write_u32(0u32)?;
+        written += writer.write_u32(0u32)?;
+        written += writer.write_u32(len)?;
+        written += writer.write_u32(len)?;
+        written += writer.write_u16(names_offset)?;
+        written += writer.write_u16(names.len() as u16)?;
+        for name in &names {
+            written += writer.write_u8(name.len() as u8)?;
+            written += writer.write_bytes(name)?;
+        }
+        Ok(written)
+    }
+}
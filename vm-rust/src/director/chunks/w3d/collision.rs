@@ -0,0 +1,327 @@
+//! Collision detection for the #collision modifier.
+//!
+//! Models are tested in world space using the same mesh data and transform
+//! accumulation as ray picking (see `raycast.rs`). Each model is reduced to
+//! the shape selected by `collision.mode`:
+//! - `#sphere`: bounding sphere of the transformed vertices
+//! - `#box`: world-aligned bounding box of the transformed vertices
+//! - `#mesh`: the transformed triangles, tested against the other model's
+//!   bounding sphere
+
+use std::collections::HashMap;
+
+use super::raycast::{cross, dot, invert_4x4, multiply_4x4, node_world_transform, normalize, sub, transform_point_4x4};
+use super::types::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CollisionMode {
+    #[default]
+    Sphere,
+    Box,
+    Mesh,
+}
+
+impl CollisionMode {
+    pub fn from_symbol(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("sphere") {
+            Some(Self::Sphere)
+        } else if name.eq_ignore_ascii_case("box") {
+            Some(Self::Box)
+        } else if name.eq_ignore_ascii_case("mesh") {
+            Some(Self::Mesh)
+        } else {
+            None
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Sphere => "sphere",
+            Self::Box => "box",
+            Self::Mesh => "mesh",
+        }
+    }
+}
+
+/// World-space collision volume of a model.
+#[derive(Clone, Debug)]
+pub struct CollisionShape {
+    pub mode: CollisionMode,
+    pub center: [f32; 3],
+    pub radius: f32,
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+    /// Only filled for `CollisionMode::Mesh`.
+    pub triangles: Vec<[[f32; 3]; 3]>,
+}
+
+/// Positions and faces of one mesh, in model space.
+type MeshGeometry<'a> = (&'a [[f32; 3]], &'a [[u32; 3]]);
+
+/// A contact between two shapes. `normal` points from the first shape
+/// towards the second; moving the second shape by `normal * depth` (or the
+/// first by the opposite) separates them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    pub point: [f32; 3],
+    pub normal: [f32; 3],
+    pub depth: f32,
+}
+
+/// Build the world-space collision shape of a model node.
+/// Returns None when the node has no mesh geometry.
+pub fn model_collision_shape(
+    scene: &W3dScene,
+    node: &W3dNode,
+    node_transforms: Option<&HashMap<String, [f32; 16]>>,
+    mode: CollisionMode,
+) -> Option<CollisionShape> {
+    let resource = if !node.model_resource_name.is_empty() {
+        &node.model_resource_name
+    } else {
+        &node.resource_name
+    };
+    let world = node_world_transform(scene, node, node_transforms);
+
+    let mut meshes: Vec<MeshGeometry> = Vec::new();
+    if let Some(clod) = scene.clod_meshes.get(resource.as_str()) {
+        meshes.extend(clod.iter().map(|m| (m.positions.as_slice(), m.faces.as_slice())));
+    }
+    meshes.extend(
+        scene.raw_meshes.iter()
+            .filter(|m| m.name == *resource)
+            .map(|m| (m.positions.as_slice(), m.faces.as_slice())),
+    );
+
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    let mut world_positions: Vec<Vec<[f32; 3]>> = Vec::with_capacity(meshes.len());
+    for (positions, _) in &meshes {
+        let transformed: Vec<[f32; 3]> = positions.iter()
+            .map(|p| transform_point_4x4(&world, p[0], p[1], p[2]))
+            .collect();
+        for p in &transformed {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+        world_positions.push(transformed);
+    }
+    if min[0] > max[0] {
+        return None;
+    }
+
+    let center = [
+        (min[0] + max[0]) * 0.5,
+        (min[1] + max[1]) * 0.5,
+        (min[2] + max[2]) * 0.5,
+    ];
+    let radius = world_positions.iter()
+        .flatten()
+        .map(|p| length(sub(*p, center)))
+        .fold(0.0f32, f32::max);
+
+    let mut triangles = Vec::new();
+    if mode == CollisionMode::Mesh {
+        for ((_, faces), positions) in meshes.iter().zip(&world_positions) {
+            for face in faces.iter() {
+                let (Some(a), Some(b), Some(c)) = (
+                    positions.get(face[0] as usize),
+                    positions.get(face[1] as usize),
+                    positions.get(face[2] as usize),
+                ) else { continue; };
+                triangles.push([*a, *b, *c]);
+            }
+        }
+    }
+
+    Some(CollisionShape { mode, center, radius, min, max, triangles })
+}
+
+/// Test two shapes for overlap.
+pub fn detect_contact(a: &CollisionShape, b: &CollisionShape) -> Option<Contact> {
+    // Bounding spheres enclose every shape mode, so they are a safe early out.
+    if length(sub(b.center, a.center)) >= a.radius + b.radius {
+        return None;
+    }
+    match (a.mode, b.mode) {
+        (CollisionMode::Mesh, CollisionMode::Mesh) => {
+            let ab = mesh_sphere_contact(&a.triangles, b.center, b.radius);
+            let ba = mesh_sphere_contact(&b.triangles, a.center, a.radius).map(flip);
+            match (ab, ba) {
+                (Some(x), Some(y)) => Some(if x.depth >= y.depth { x } else { y }),
+                (x, y) => x.or(y),
+            }
+        }
+        (CollisionMode::Mesh, _) => mesh_sphere_contact(&a.triangles, b.center, b.radius),
+        (_, CollisionMode::Mesh) => mesh_sphere_contact(&b.triangles, a.center, a.radius).map(flip),
+        (CollisionMode::Sphere, CollisionMode::Sphere) => sphere_sphere_contact(a.center, a.radius, b.center, b.radius),
+        (CollisionMode::Box, CollisionMode::Box) => box_box_contact(a, b),
+        (CollisionMode::Sphere, CollisionMode::Box) => sphere_box_contact(a.center, a.radius, b),
+        (CollisionMode::Box, CollisionMode::Sphere) => sphere_box_contact(b.center, b.radius, a).map(flip),
+    }
+}
+
+/// Local transform that moves `node` by `delta` in world space.
+pub fn translated_local_transform(
+    scene: &W3dScene,
+    node: &W3dNode,
+    node_transforms: Option<&HashMap<String, [f32; 16]>>,
+    delta: [f32; 3],
+) -> [f32; 16] {
+    let mut world = node_world_transform(scene, node, node_transforms);
+    world[12] += delta[0];
+    world[13] += delta[1];
+    world[14] += delta[2];
+    let parent = scene.nodes.iter()
+        .find(|n| !node.parent_name.eq_ignore_ascii_case("World") && n.name.eq_ignore_ascii_case(&node.parent_name));
+    match parent {
+        Some(parent) => multiply_4x4(&invert_4x4(&node_world_transform(scene, parent, node_transforms)), &world),
+        None => world,
+    }
+}
+
+fn flip(contact: Contact) -> Contact {
+    Contact { normal: scale(contact.normal, -1.0), ..contact }
+}
+
+fn sphere_sphere_contact(ca: [f32; 3], ra: f32, cb: [f32; 3], rb: f32) -> Option<Contact> {
+    let d = sub(cb, ca);
+    let dist = length(d);
+    if dist >= ra + rb {
+        return None;
+    }
+    let normal = if dist > 1e-6 { scale(d, 1.0 / dist) } else { [0.0, 1.0, 0.0] };
+    let depth = ra + rb - dist;
+    Some(Contact { point: add(ca, scale(normal, ra - depth * 0.5)), normal, depth })
+}
+
+fn box_box_contact(a: &CollisionShape, b: &CollisionShape) -> Option<Contact> {
+    let mut best_axis = 0;
+    let mut best_depth = f32::MAX;
+    for axis in 0..3 {
+        let overlap = a.max[axis].min(b.max[axis]) - a.min[axis].max(b.min[axis]);
+        if overlap <= 0.0 {
+            return None;
+        }
+        if overlap < best_depth {
+            best_depth = overlap;
+            best_axis = axis;
+        }
+    }
+    let mut normal = [0.0; 3];
+    normal[best_axis] = if b.center[best_axis] >= a.center[best_axis] { 1.0 } else { -1.0 };
+    let mut point = [0.0; 3];
+    for (axis, p) in point.iter_mut().enumerate() {
+        *p = (a.max[axis].min(b.max[axis]) + a.min[axis].max(b.min[axis])) * 0.5;
+    }
+    Some(Contact { point, normal, depth: best_depth })
+}
+
+/// Sphere against a box; the normal points from the sphere to the box.
+fn sphere_box_contact(center: [f32; 3], radius: f32, b: &CollisionShape) -> Option<Contact> {
+    let mut closest = center;
+    for (axis, c) in closest.iter_mut().enumerate() {
+        *c = c.clamp(b.min[axis], b.max[axis]);
+    }
+    let d = sub(closest, center);
+    let dist = length(d);
+    if dist > 1e-6 {
+        if dist >= radius {
+            return None;
+        }
+        return Some(Contact { point: closest, normal: scale(d, 1.0 / dist), depth: radius - dist });
+    }
+
+    // Center inside the box: push out through the nearest face.
+    let mut best = (f32::MAX, 0, 1.0);
+    for (axis, c) in center.iter().enumerate() {
+        let to_min = c - b.min[axis];
+        let to_max = b.max[axis] - c;
+        if to_min < best.0 {
+            best = (to_min, axis, 1.0);
+        }
+        if to_max < best.0 {
+            best = (to_max, axis, -1.0);
+        }
+    }
+    let mut normal = [0.0; 3];
+    normal[best.1] = best.2;
+    Some(Contact { point: center, normal, depth: best.0 + radius })
+}
+
+/// Deepest contact between a triangle soup and a sphere; the normal points
+/// from the mesh to the sphere.
+fn mesh_sphere_contact(triangles: &[[[f32; 3]; 3]], center: [f32; 3], radius: f32) -> Option<Contact> {
+    let mut best: Option<Contact> = None;
+    for tri in triangles {
+        let closest = closest_point_on_triangle(center, tri);
+        let d = sub(center, closest);
+        let dist = length(d);
+        if dist >= radius {
+            continue;
+        }
+        let normal = if dist > 1e-6 {
+            scale(d, 1.0 / dist)
+        } else {
+            normalize(cross(sub(tri[1], tri[0]), sub(tri[2], tri[0])))
+        };
+        let depth = radius - dist;
+        if best.is_none_or(|b| depth > b.depth) {
+            best = Some(Contact { point: closest, normal, depth });
+        }
+    }
+    best
+}
+
+/// Closest point on triangle `t` to `p` (Ericson, Real-Time Collision Detection 5.1.5).
+fn closest_point_on_triangle(p: [f32; 3], t: &[[f32; 3]; 3]) -> [f32; 3] {
+    let [a, b, c] = *t;
+    let ab = sub(b, a);
+    let ac = sub(c, a);
+    let ap = sub(p, a);
+    let d1 = dot(ab, ap);
+    let d2 = dot(ac, ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+    let bp = sub(p, b);
+    let d3 = dot(ab, bp);
+    let d4 = dot(ac, bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return add(a, scale(ab, d1 / (d1 - d3)));
+    }
+    let cp = sub(p, c);
+    let d5 = dot(ab, cp);
+    let d6 = dot(ac, cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return add(a, scale(ac, d2 / (d2 - d6)));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        return add(b, scale(sub(c, b), (d4 - d3) / ((d4 - d3) + (d5 - d6))));
+    }
+    let denom = 1.0 / (va + vb + vc);
+    add(a, add(scale(ab, vb * denom), scale(ac, vc * denom)))
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(v: [f32; 3], s: f32) -> [f32; 3] {
+    [v[0] * s, v[1] * s, v[2] * s]
+}
+
+fn length(v: [f32; 3]) -> f32 {
+    dot(v, v).sqrt()
+}
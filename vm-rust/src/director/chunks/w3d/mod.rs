@@ -16,6 +16,9 @@ pub mod raycast;
 pub mod skeleton;
 pub mod gltf_export;
 pub mod subdivision;
+pub mod texture;
+pub mod render_helpers;
+pub mod collision;
 
 pub use types::W3dScene;
 
@@ -146,36 +146,7 @@ pub fn raycast_scene_multi(
         };
 
         // Get model WORLD transform by accumulating parent chain
-        let world_transform = {
-            let local = if let Some(nt) = node_transforms {
-                nt.get(&node.name).cloned()
-                    .or_else(|| {
-                        // Case-insensitive fallback
-                        nt.iter().find(|(k, _)| k.eq_ignore_ascii_case(&node.name)).map(|(_, v)| *v)
-                    })
-                    .unwrap_or(node.transform)
-            } else {
-                node.transform
-            };
-            // Walk parent chain to accumulate world transform
-            let mut result = local;
-            let mut current_parent = &node.parent_name;
-            for _ in 0..20 {
-                if current_parent.is_empty() || current_parent.eq_ignore_ascii_case("World") { break; }
-                if let Some(pn) = scene.nodes.iter().find(|n| n.name.eq_ignore_ascii_case(current_parent)) {
-                    let pt = if let Some(nt) = node_transforms {
-                        nt.get(&pn.name).cloned()
-                            .or_else(|| nt.iter().find(|(k, _)| k.eq_ignore_ascii_case(&pn.name)).map(|(_, v)| *v))
-                            .unwrap_or(pn.transform)
-                    } else {
-                        pn.transform
-                    };
-                    result = multiply_4x4(&pt, &result);
-                    current_parent = &pn.parent_name;
-                } else { break; }
-            }
-            result
-        };
+        let world_transform = node_world_transform(scene, node, node_transforms);
         // Debug: log MainA transform
         if node.name == "MainA" {
             static MA_LOG: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
@@ -322,6 +293,34 @@ pub fn raycast_scene_multi(
     all_hits
 }
 
+/// World transform of a node: its local transform (runtime override from
+/// `node_transforms` when present, matched case-insensitively) multiplied
+/// through the parent chain up to World.
+pub fn node_world_transform(
+    scene: &W3dScene,
+    node: &W3dNode,
+    node_transforms: Option<&std::collections::HashMap<String, [f32; 16]>>,
+) -> [f32; 16] {
+    let local_of = |n: &W3dNode| {
+        node_transforms
+            .and_then(|nt| {
+                nt.get(&n.name).cloned()
+                    .or_else(|| nt.iter().find(|(k, _)| k.eq_ignore_ascii_case(&n.name)).map(|(_, v)| *v))
+            })
+            .unwrap_or(n.transform)
+    };
+    let mut result = local_of(node);
+    let mut current_parent = &node.parent_name;
+    for _ in 0..20 {
+        if current_parent.is_empty() || current_parent.eq_ignore_ascii_case("World") { break; }
+        if let Some(pn) = scene.nodes.iter().find(|n| n.name.eq_ignore_ascii_case(current_parent)) {
+            result = multiply_4x4(&local_of(pn), &result);
+            current_parent = &pn.parent_name;
+        } else { break; }
+    }
+    result
+}
+
 /// Transform a direction vector (no translation) by a 4x4 matrix
 fn transform_dir_4x4(m: &[f32; 16], x: f32, y: f32, z: f32) -> [f32; 3] {
     normalize([
@@ -633,11 +632,11 @@ fn interpolate_uv(tex_coords: Option<&[[f32; 2]]>, i0: usize, i1: usize, i2: usi
 
 // ─── Vector math helpers ───
 
-fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+pub(super) fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
     [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
 }
 
-fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+pub(super) fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
     [
         a[1] * b[2] - a[2] * b[1],
         a[2] * b[0] - a[0] * b[2],
@@ -645,16 +644,16 @@ fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
     ]
 }
 
-fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+pub(super) fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
     a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
 }
 
-fn normalize(v: [f32; 3]) -> [f32; 3] {
+pub(super) fn normalize(v: [f32; 3]) -> [f32; 3] {
     let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
     if len > 1e-8 { [v[0] / len, v[1] / len, v[2] / len] } else { [0.0, 0.0, 1.0] }
 }
 
-fn transform_point_4x4(m: &[f32; 16], x: f32, y: f32, z: f32) -> [f32; 3] {
+pub(super) fn transform_point_4x4(m: &[f32; 16], x: f32, y: f32, z: f32) -> [f32; 3] {
     // Column-major matrix * point with perspective divide
     let w = m[3] * x + m[7] * y + m[11] * z + m[15];
     let w = if w.abs() > 1e-8 { w } else { 1.0 };
@@ -667,7 +666,7 @@ fn transform_point_4x4(m: &[f32; 16], x: f32, y: f32, z: f32) -> [f32; 3] {
 
 /// General 4x4 matrix inverse (column-major)
 /// Column-major 4x4 matrix multiply: C = A * B
-fn multiply_4x4(a: &[f32; 16], b: &[f32; 16]) -> [f32; 16] {
+pub(super) fn multiply_4x4(a: &[f32; 16], b: &[f32; 16]) -> [f32; 16] {
     let mut r = [0.0f32; 16];
     for col in 0..4 {
         for row in 0..4 {
@@ -681,7 +680,7 @@ fn multiply_4x4(a: &[f32; 16], b: &[f32; 16]) -> [f32; 16] {
     r
 }
 
-fn invert_4x4(m: &[f32; 16]) -> [f32; 16] {
+pub(super) fn invert_4x4(m: &[f32; 16]) -> [f32; 16] {
     let mut inv = [0.0f32; 16];
 
     inv[0] = m[5]*m[10]*m[15] - m[5]*m[11]*m[14] - m[9]*m[6]*m[15] + m[9]*m[7]*m[14] + m[13]*m[6]*m[11] - m[13]*m[7]*m[10];
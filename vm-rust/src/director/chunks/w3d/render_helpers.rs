@@ -0,0 +1,110 @@
+//! Scene graph queries, shader lookup and matrix math shared by the WebGL2
+//! and software Shockwave 3D renderers.
+
+use std::collections::HashSet;
+
+use crate::player::cast_member::Shockwave3dRuntimeState;
+
+use super::types::{W3dScene, W3dShader};
+
+/// Check if a node is a child (direct or indirect) of a given root node.
+pub fn is_child_of(scene: &W3dScene, node_name: &str, root_name: &str) -> bool {
+    if node_name == root_name { return true; }
+    let mut current = node_name;
+    for _ in 0..20 { // max depth to prevent infinite loops
+        match scene.nodes.iter().find(|n| n.name == current) {
+            Some(node) => {
+                if node.parent_name == root_name { return true; }
+                if node.parent_name.is_empty() { return false; }
+                current = &node.parent_name;
+            }
+            None => return false,
+        }
+    }
+    false
+}
+
+/// Check if any ancestor in the parent chain, starting at `parent_name`,
+/// is in the detached set.
+pub fn has_detached_ancestor(scene: &W3dScene, parent_name: &str, detached: &HashSet<&str>) -> bool {
+    let mut current = parent_name;
+    for _ in 0..20 {
+        if current.is_empty() || current == "World" { return false; }
+        if detached.contains(current) { return true; }
+        match scene.nodes.iter().find(|n| n.name == current) {
+            Some(node) => current = &node.parent_name,
+            None => return false,
+        }
+    }
+    false
+}
+
+/// Case-insensitive lookup in node_transforms (Director is case-insensitive for node names).
+pub fn get_runtime_transform(rs: &Shockwave3dRuntimeState, name: &str) -> Option<[f32; 16]> {
+    if let Some(m) = rs.node_transforms.get(name) {
+        return Some(*m);
+    }
+    rs.node_transforms.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, val)| *val)
+}
+
+/// Look up a per-model shader override.  Returns the first available:
+/// mesh-specific index → index 0 fallback → None.
+pub fn node_shader_override<'r>(rs: &'r Shockwave3dRuntimeState, node_name: &str, mesh_idx: Option<usize>) -> Option<&'r String> {
+    rs.node_shaders.get(node_name).and_then(|m| {
+        mesh_idx.and_then(|idx| m.get(&idx)).or_else(|| m.get(&0))
+    })
+}
+
+/// Case-insensitive shader lookup (W3D files have inconsistent casing).
+pub fn find_shader_ci<'s>(shaders: &'s [W3dShader], name: &str) -> Option<&'s W3dShader> {
+    shaders.iter().find(|s| s.name.eq_ignore_ascii_case(name))
+}
+
+/// Resolve a candidate name to a shader, allowing either shader names or material names.
+pub fn resolve_shader_candidate_ci<'s>(scene: &'s W3dScene, candidate: &str) -> Option<&'s W3dShader> {
+    find_shader_ci(&scene.shaders, candidate)
+        .or_else(|| scene.shaders.iter().find(|s| s.material_name.eq_ignore_ascii_case(candidate)))
+}
+
+// ─── Math helpers (column-major 4x4) ───
+
+pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> [f32; 16] {
+    let f = 1.0 / (fov_y / 2.0).tan();
+    let nf = 1.0 / (near - far);
+    [
+        f / aspect, 0.0, 0.0, 0.0,
+        0.0, f, 0.0, 0.0,
+        0.0, 0.0, (far + near) * nf, -1.0,
+        0.0, 0.0, 2.0 * far * near * nf, 0.0,
+    ]
+}
+
+/// Invert an affine transform with an orthonormal rotation, e.g. camera
+/// world → view. Director/IFX transforms are column-major: m[0..3] is the
+/// X axis, m[4..7] the Y axis, m[8..11] the Z axis and m[12..15] the
+/// translation. The result is R^T with translation -R^T * t.
+pub fn invert_transform(m: &[f32; 16]) -> [f32; 16] {
+    let (tx, ty, tz) = (m[12], m[13], m[14]);
+    [
+        m[0], m[4], m[8], 0.0,
+        m[1], m[5], m[9], 0.0,
+        m[2], m[6], m[10], 0.0,
+        -(m[0] * tx + m[1] * ty + m[2] * tz),
+        -(m[4] * tx + m[5] * ty + m[6] * tz),
+        -(m[8] * tx + m[9] * ty + m[10] * tz),
+        1.0,
+    ]
+}
+
+/// Multiply two column-major 4x4 matrices: result = A * B
+pub fn mat4_multiply_col_major(a: &[f32; 16], b: &[f32; 16]) -> [f32; 16] {
+    let mut r = [0.0f32; 16];
+    for col in 0..4 {
+        for row in 0..4 {
+            r[col * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[col * 4 + k]).sum();
+        }
+    }
+    r
+}
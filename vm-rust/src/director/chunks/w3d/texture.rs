@@ -0,0 +1,244 @@
+//! Texture image decoding and texture coordinate generation shared by the
+//! WebGL2 and software Shockwave 3D renderers.
+
+/// A texture image decoded to straight (non-premultiplied) RGBA8 pixels.
+#[derive(Clone, Debug)]
+pub struct DecodedTexture {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+    /// True when any pixel has meaningful alpha (alpha < 250).
+    pub has_alpha: bool,
+}
+
+/// Decode texture image data from `W3dScene::texture_images`.
+///
+/// Detection priority: JPEG/PNG magic → DXT header → raw RGBA (our own format).
+/// Raw RGBA must be checked LAST because its 8-byte header (u32 w, u32 h) can
+/// accidentally match the first bytes of DXT/JPEG/PNG data.
+pub fn decode_texture_rgba(data: &[u8]) -> Result<DecodedTexture, String> {
+    if data.len() < 4 {
+        return Err(format!("texture data too short ({} bytes)", data.len()));
+    }
+
+    let (width, height, rgba) = if data[0] == 0xFF && data[1] == 0xD8 // JPEG magic
+        || data[0] == 0x89 && data[1] == 0x50 // PNG magic
+    {
+        decode_with_image_crate(data)?
+    } else if is_dxt_texture(data) {
+        decode_dxt_to_rgba(data).ok_or_else(|| "invalid DXT texture".to_string())?
+    } else if data.len() >= 8 {
+        // Raw RGBA format (from newTexture #fromImageObject):
+        // first 4 bytes = width LE, next 4 bytes = height LE, rest = RGBA
+        let w = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let h = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+        let expected = 8 + (w as usize) * (h as usize) * 4;
+        if w > 0 && w <= 4096 && h > 0 && h <= 4096 && data.len() == expected {
+            (w, h, data[8..].to_vec())
+        } else {
+            // Last resort: try image library decode for other formats
+            decode_with_image_crate(data)?
+        }
+    } else {
+        return Err(format!("unrecognized texture data ({} bytes)", data.len()));
+    };
+
+    let expected_size = (width as usize) * (height as usize) * 4;
+    if rgba.len() != expected_size {
+        return Err(format!(
+            "{}x{} expects {} bytes but got {}",
+            width, height, expected_size, rgba.len()
+        ));
+    }
+
+    let has_alpha = rgba.chunks(4).any(|p| p[3] < 250);
+    Ok(DecodedTexture { width, height, rgba, has_alpha })
+}
+
+fn decode_with_image_crate(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    let img = image::load_from_memory(data)
+        .map_err(|e| {
+            let header: Vec<String> = data.iter().take(8).map(|b| format!("{:02X}", b)).collect();
+            format!("failed to decode {} bytes, header=[{}]: {}", data.len(), header.join(" "), e)
+        })?
+        .to_rgba8();
+    let w = img.width();
+    let h = img.height();
+    Ok((w, h, img.into_raw()))
+}
+
+// ─── DXT texture decompression ───
+
+/// Check if data looks like a DXT compressed texture.
+/// IFX stores DXT textures with a small header: width(u16), height(u16), format(u8), then blocks.
+fn is_dxt_texture(data: &[u8]) -> bool {
+    if data.len() < 5 { return false; }
+    let w = u16::from_le_bytes([data[0], data[1]]) as u32;
+    let h = u16::from_le_bytes([data[2], data[3]]) as u32;
+    if w == 0 || h == 0 || w > 4096 || h > 4096 { return false; }
+    // DXT1: 8 bytes per 4x4 block = 0.5 bytes per pixel
+    let blocks_w = w.div_ceil(4);
+    let blocks_h = h.div_ceil(4);
+    let dxt1_size = (blocks_w * blocks_h * 8) as usize;
+    let dxt3_5_size = (blocks_w * blocks_h * 16) as usize;
+    // Check if data matches DXT1 or DXT3/5 size (with 5-byte header)
+    data.len() == 5 + dxt1_size || data.len() == 5 + dxt3_5_size
+}
+
+/// Decode DXT compressed texture to RGBA. Returns (width, height, rgba_pixels).
+fn decode_dxt_to_rgba(data: &[u8]) -> Option<(u32, u32, Vec<u8>)> {
+    if data.len() < 5 { return None; }
+    let w = u16::from_le_bytes([data[0], data[1]]) as u32;
+    let h = u16::from_le_bytes([data[2], data[3]]) as u32;
+    let block_data = &data[5..];
+
+    let blocks_w = w.div_ceil(4);
+    let blocks_h = h.div_ceil(4);
+    let dxt1_expected = (blocks_w * blocks_h * 8) as usize;
+    let is_dxt1 = block_data.len() == dxt1_expected;
+
+    let mut rgba = vec![0u8; (w * h * 4) as usize];
+
+    for by in 0..blocks_h {
+        for bx in 0..blocks_w {
+            let block_idx = (by * blocks_w + bx) as usize;
+            if is_dxt1 {
+                let offset = block_idx * 8;
+                if offset + 8 > block_data.len() { break; }
+                decode_dxt1_block(&block_data[offset..offset + 8], &mut rgba, bx * 4, by * 4, w, h);
+            } else {
+                // DXT3/DXT5: skip 8-byte alpha block, decode 8-byte color block
+                let offset = block_idx * 16;
+                if offset + 16 > block_data.len() { break; }
+                decode_dxt1_block(&block_data[offset + 8..offset + 16], &mut rgba, bx * 4, by * 4, w, h);
+            }
+        }
+    }
+
+    Some((w, h, rgba))
+}
+
+/// Decode a single DXT1 4x4 color block into RGBA pixels.
+fn decode_dxt1_block(block: &[u8], rgba: &mut [u8], start_x: u32, start_y: u32, img_w: u32, img_h: u32) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+
+    let r0 = ((c0 >> 11) & 0x1F) as u8;
+    let g0 = ((c0 >> 5) & 0x3F) as u8;
+    let b0 = (c0 & 0x1F) as u8;
+    let r1 = ((c1 >> 11) & 0x1F) as u8;
+    let g1 = ((c1 >> 5) & 0x3F) as u8;
+    let b1 = (c1 & 0x1F) as u8;
+
+    // Expand to 8-bit
+    let colors: [[u8; 4]; 4] = if c0 > c1 {
+        [
+            [(r0 << 3) | (r0 >> 2), (g0 << 2) | (g0 >> 4), (b0 << 3) | (b0 >> 2), 255],
+            [(r1 << 3) | (r1 >> 2), (g1 << 2) | (g1 >> 4), (b1 << 3) | (b1 >> 2), 255],
+            [((2 * r0 as u16 + r1 as u16) / 3) as u8, ((2 * g0 as u16 + g1 as u16) / 3) as u8, ((2 * b0 as u16 + b1 as u16) / 3) as u8, 255],
+            [((r0 as u16 + 2 * r1 as u16) / 3) as u8, ((g0 as u16 + 2 * g1 as u16) / 3) as u8, ((b0 as u16 + 2 * b1 as u16) / 3) as u8, 255],
+        ]
+    } else {
+        [
+            [(r0 << 3) | (r0 >> 2), (g0 << 2) | (g0 >> 4), (b0 << 3) | (b0 >> 2), 255],
+            [(r1 << 3) | (r1 >> 2), (g1 << 2) | (g1 >> 4), (b1 << 3) | (b1 >> 2), 255],
+            [((r0 as u16 + r1 as u16) / 2) as u8, ((g0 as u16 + g1 as u16) / 2) as u8, ((b0 as u16 + b1 as u16) / 2) as u8, 255],
+            [0, 0, 0, 0], // Transparent black for DXT1 with alpha
+        ]
+    };
+
+    for py in 0..4u32 {
+        for px in 0..4u32 {
+            let x = start_x + px;
+            let y = start_y + py;
+            if x >= img_w || y >= img_h { continue; }
+            let bit_idx = (py * 4 + px) * 2;
+            let byte_idx = 4 + (bit_idx / 8) as usize;
+            let bit_offset = bit_idx % 8;
+            let color_idx = ((block[byte_idx] >> bit_offset) & 3) as usize;
+            let pixel_offset = ((y * img_w + x) * 4) as usize;
+            rgba[pixel_offset..pixel_offset + 4].copy_from_slice(&colors[color_idx]);
+        }
+    }
+}
+
+// ─── Texture coordinate generation ───
+
+/// Generate UVs using the specified mode: 0=planar, 1=spherical, 2=cylindrical, 3=reflection.
+pub fn generate_uvs_by_mode(positions: &[[f32; 3]], mode: Option<u8>) -> Vec<[f32; 2]> {
+    match mode {
+        Some(1) => generate_spherical_uvs(positions),
+        Some(2) => generate_cylindrical_uvs(positions),
+        _ => generate_planar_uvs(positions), // 0=planar or default
+    }
+}
+
+/// Generate spherical UV coordinates from vertex positions.
+fn generate_spherical_uvs(positions: &[[f32; 3]]) -> Vec<[f32; 2]> {
+    if positions.is_empty() {
+        return Vec::new();
+    }
+    // Compute center
+    let n = positions.len() as f32;
+    let cx = positions.iter().map(|p| p[0]).sum::<f32>() / n;
+    let cy = positions.iter().map(|p| p[1]).sum::<f32>() / n;
+    let cz = positions.iter().map(|p| p[2]).sum::<f32>() / n;
+
+    positions.iter().map(|p| {
+        let dx = p[0] - cx;
+        let dy = p[1] - cy;
+        let dz = p[2] - cz;
+        let len = (dx * dx + dy * dy + dz * dz).sqrt().max(1e-8);
+        let nx = dx / len;
+        let ny = dy / len;
+        let nz = dz / len;
+        let u = 0.5 + nz.atan2(nx) / (2.0 * std::f32::consts::PI);
+        let v = 0.5 - ny.asin() / std::f32::consts::PI;
+        [u, v]
+    }).collect()
+}
+
+/// Generate cylindrical UV coordinates from vertex positions.
+fn generate_cylindrical_uvs(positions: &[[f32; 3]]) -> Vec<[f32; 2]> {
+    if positions.is_empty() {
+        return Vec::new();
+    }
+    let n = positions.len() as f32;
+    let cx = positions.iter().map(|p| p[0]).sum::<f32>() / n;
+    let cz = positions.iter().map(|p| p[2]).sum::<f32>() / n;
+    let min_y = positions.iter().map(|p| p[1]).fold(f32::MAX, f32::min);
+    let max_y = positions.iter().map(|p| p[1]).fold(f32::MIN, f32::max);
+    let height = (max_y - min_y).max(0.001);
+
+    positions.iter().map(|p| {
+        let dx = p[0] - cx;
+        let dz = p[2] - cz;
+        let u = 0.5 + dz.atan2(dx) / (2.0 * std::f32::consts::PI);
+        let v = (p[1] - min_y) / height;
+        [u, v]
+    }).collect()
+}
+
+/// Generate planar UV coordinates from vertex positions (bounding-box normalized).
+fn generate_planar_uvs(positions: &[[f32; 3]]) -> Vec<[f32; 2]> {
+    if positions.is_empty() {
+        return Vec::new();
+    }
+    // Find bounding box
+    let mut min_x = f32::MAX;
+    let mut max_x = f32::MIN;
+    let mut min_y = f32::MAX;
+    let mut max_y = f32::MIN;
+    for p in positions {
+        min_x = min_x.min(p[0]);
+        max_x = max_x.max(p[0]);
+        min_y = min_y.min(p[1]);
+        max_y = max_y.max(p[1]);
+    }
+    let range_x = (max_x - min_x).max(0.001);
+    let range_y = (max_y - min_y).max(0.001);
+
+    positions.iter().map(|p| {
+        [(p[0] - min_x) / range_x, (p[1] - min_y) / range_y]
+    }).collect()
+}
@@ -90,6 +90,15 @@ pub struct ParRun {
     pub par_info_index: u16,
 }
 
+/// Hypertext link over text positions `start..end` (Paige `pg_hyperlink`
+/// applied range), with the link data string authored for it.
+#[derive(Debug, Clone)]
+pub struct XmedHyperlink {
+    pub start: u32,
+    pub end: u32,
+    pub data: String,
+}
+
 /// Parsed XMED styled text with all formatting information
 pub struct XmedStyledText {
     pub text: String,
@@ -127,6 +136,8 @@ pub struct XmedStyledText {
     /// `None` when 0x0005 is absent — the consumer should fall back to the
     /// existing per-span font size.
     pub default_font_size: Option<u16>,
+    /// Hypertext links from Section 0x0019, sorted by `start`.
+    pub hyperlinks: Vec<XmedHyperlink>,
 }
 
 /// Section 1 data - document header with page/field properties
@@ -510,6 +521,18 @@ pub fn parse_xmed(data: &[u8]) -> Result<XmedStyledText, String> {
         }
     }
 
+    // Hyperlink ranges use the same text positions as the char runs, so
+    // they get the same Section 2 boundary shift.
+    let mut hyperlinks = match sections.get(&0x0019) {
+        Some(section) => parse_hyperlink_section(section),
+        None => Vec::new(),
+    };
+    let shift = |pos: u32| pos + section2_boundaries.iter().filter(|&&b| b <= pos).count() as u32;
+    for link in &mut hyperlinks {
+        link.start = shift(link.start);
+        link.end = shift(link.end);
+    }
+
     // Member-level alignment: look up the par_info referenced by the
     // FIRST par_run (Section 0x0005 entry at position 0) — that's the
     // alignment of the first paragraph, which Director reports as
@@ -776,6 +799,7 @@ pub fn parse_xmed(data: &[u8]) -> Result<XmedStyledText, String> {
         par_runs,
         bg_color: section1_data.bg_color,
         default_font_size,
+        hyperlinks,
     })
 }
 
@@ -970,6 +994,37 @@ fn parse_section_6(data: &[u8]) -> Result<Section6Data, String> {
     Ok(Section6Data { char_runs })
 }
 
+/// Parse Section 0x0019 - Hyperlinks (Paige `hyperlink_key`)
+/// Each link packs its applied range (begin, end), the link type and refcon,
+/// then the link data as PtrBytes (0x00 marker, decimal size, comma, bytes).
+fn parse_hyperlink_section(data: &[u8]) -> Vec<XmedHyperlink> {
+    let mut packer = Packer::new(data.to_vec());
+    let mut hyperlinks = Vec::new();
+
+    while packer.remaining() >= 2 {
+        let start = packer.unpack_num();
+        let end = packer.unpack_num();
+        let _link_type = packer.unpack_num();
+        let _refcon = packer.unpack_num();
+        let Some(bytes) = packer.unpack_ptr_bytes() else {
+            break;
+        };
+        if start < 0 || end <= start {
+            continue;
+        }
+        let data: String = bytes
+            .iter()
+            .take_while(|&&b| b != 0)
+            .map(|&b| win1252_byte_to_char(b))
+            .collect();
+        debug!("    Hyperlink: {}..{} -> '{}'", start, end, data);
+        hyperlinks.push(XmedHyperlink { start: start as u32, end: end as u32, data });
+    }
+
+    hyperlinks.sort_by_key(|link| link.start);
+    hyperlinks
+}
+
 /// Packer for unpacking variable-length encoded data
 struct Packer {
     data: Vec<u8>,
@@ -1100,6 +1155,26 @@ impl Packer {
         val
     }
 
+    /// PgUnpackPtrBytes: 0x00 marker, decimal size, comma, then `size` raw bytes.
+    /// Returns None when the marker or the bytes are missing.
+    fn unpack_ptr_bytes(&mut self) -> Option<Vec<u8>> {
+        if self.data.get(self.pos) != Some(&0x00) {
+            return None;
+        }
+        self.pos += 1;
+        let size_start = self.pos;
+        while self.pos < self.data.len() && self.data[self.pos].is_ascii_digit() {
+            self.pos += 1;
+        }
+        let size: usize = std::str::from_utf8(&self.data[size_start..self.pos]).ok()?.parse().ok()?;
+        if self.data.get(self.pos) == Some(&b',') {
+            self.pos += 1;
+        }
+        let bytes = self.data.get(self.pos..self.pos + size)?.to_vec();
+        self.pos += size;
+        Some(bytes)
+    }
+
     /// UnpackRefcon
     /// If typeCode == 65547 -> use PgUnpackPtrBytes (read size + raw bytes)
     /// Otherwise -> just read one UnpackNum value
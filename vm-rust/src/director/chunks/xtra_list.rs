@@ -0,0 +1,80 @@
+use binary_reader::{BinaryReader, Endian};
+
+use crate::io::reader::DirectorExt;
+
+/// An Xtra the movie was saved with a dependency on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct XtraListEntry {
+    /// Class ID of the Xtra.
+    pub guid: [u8; 16],
+    pub flags: u32,
+    /// Xtra file name without its extension, e.g. `Multiusr`.
+    pub name: String,
+    /// Download locations recorded for Shockwave auto-install.
+    pub urls: Vec<String>,
+}
+
+/// `XTRl` chunk: the Xtras a movie requires.
+///
+/// Big-endian: a `u32` entry count, then per entry a `u32` byte length of
+/// the rest of the entry, the Xtra's 16-byte class GUID, `u32` flags and
+/// its file name as a Pascal string. Any Pascal strings after the name,
+/// up to the end of the entry, are the download URLs.
+#[derive(Clone, Debug, Default)]
+pub struct XtraListChunk {
+    pub entries: Vec<XtraListEntry>,
+}
+
+impl XtraListChunk {
+    pub fn from_reader(reader: &mut BinaryReader) -> Result<XtraListChunk, String> {
+        let original_endian = reader.endian;
+        reader.endian = Endian::Big;
+        let result = Self::read_entries(reader);
+        reader.endian = original_endian;
+        result
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<XtraListChunk, String> {
+        Self::from_reader(&mut BinaryReader::from_u8(data))
+    }
+
+    fn read_entries(reader: &mut BinaryReader) -> Result<XtraListChunk, String> {
+        let error = |e: std::io::Error| format!("Invalid XTRl chunk: {}", e);
+        let count = reader.read_u32().map_err(error)?;
+        let mut entries = vec![];
+        for _ in 0..count {
+            let len = reader.read_u32().map_err(error)? as usize;
+            let end = reader.pos + len;
+            if end > reader.length {
+                return Err(format!("Invalid XTRl chunk: entry runs past the end ({} > {})", end, reader.length));
+            }
+            let guid: [u8; 16] = reader.read_bytes(16).map_err(error)?.try_into().unwrap();
+            let flags = reader.read_u32().map_err(error)?;
+            let name = strip_xtra_extension(&read_pascal_string(reader, end)?).to_string();
+            let mut urls = vec![];
+            while reader.pos < end {
+                urls.push(read_pascal_string(reader, end)?);
+            }
+            entries.push(XtraListEntry { guid, flags, name, urls });
+        }
+        Ok(XtraListChunk { entries })
+    }
+}
+
+/// A Pascal string that must end by `end`.
+fn read_pascal_string(reader: &mut BinaryReader, end: usize) -> Result<String, String> {
+    let len = reader.read_u8().map_err(|e| format!("Invalid XTRl chunk: {}", e))? as usize;
+    if reader.pos + len > end {
+        return Err("Invalid XTRl chunk: string runs past the end of its entry".to_string());
+    }
+    reader.read_string(len).map_err(|e| format!("Invalid XTRl chunk: {}", e))
+}
+
+fn strip_xtra_extension(name: &str) -> &str {
+    for extension in [".x32", ".xtr", ".cpt", ".dll"] {
+        if name.len() > extension.len() && name.to_lowercase().ends_with(extension) {
+            return &name[..name.len() - extension.len()];
+        }
+    }
+    name
+}
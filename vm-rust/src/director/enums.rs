@@ -547,6 +547,115 @@ impl From<&[u8]> for FilmLoopInfo {
     }
 }
 
+/// Director's built-in transition codes, as stored in transition cast
+/// members and accepted by `puppetTransition`. Codes above 52 are reserved
+/// for Xtra transitions, which we do not implement.
+#[derive(Debug, Copy, Clone, FromPrimitive, PartialEq, Eq)]
+pub enum TransitionType {
+    None = 0,
+    WipeRight = 1,
+    WipeLeft = 2,
+    WipeDown = 3,
+    WipeUp = 4,
+    CenterOutHorizontal = 5,
+    EdgesInHorizontal = 6,
+    CenterOutVertical = 7,
+    EdgesInVertical = 8,
+    CenterOutSquare = 9,
+    EdgesInSquare = 10,
+    PushLeft = 11,
+    PushRight = 12,
+    PushDown = 13,
+    PushUp = 14,
+    RevealUp = 15,
+    RevealUpRight = 16,
+    RevealRight = 17,
+    RevealDownRight = 18,
+    RevealDown = 19,
+    RevealDownLeft = 20,
+    RevealLeft = 21,
+    RevealUpLeft = 22,
+    DissolvePixelsFast = 23,
+    DissolveBoxyRects = 24,
+    DissolveBoxySquares = 25,
+    DissolvePatterns = 26,
+    RandomRows = 27,
+    RandomColumns = 28,
+    CoverDown = 29,
+    CoverDownLeft = 30,
+    CoverDownRight = 31,
+    CoverLeft = 32,
+    CoverRight = 33,
+    CoverUp = 34,
+    CoverUpLeft = 35,
+    CoverUpRight = 36,
+    VenetianBlinds = 37,
+    Checkerboard = 38,
+    StripsBottomBuildLeft = 39,
+    StripsBottomBuildRight = 40,
+    StripsLeftBuildDown = 41,
+    StripsLeftBuildUp = 42,
+    StripsRightBuildDown = 43,
+    StripsRightBuildUp = 44,
+    StripsTopBuildLeft = 45,
+    StripsTopBuildRight = 46,
+    ZoomOpen = 47,
+    ZoomClose = 48,
+    VerticalBlinds = 49,
+    DissolveBitsFast = 50,
+    DissolvePixels = 51,
+    DissolveBits = 52,
+}
+
+impl TransitionType {
+    pub fn from(val: u32) -> TransitionType {
+        num::FromPrimitive::from_u32(val).unwrap_or(TransitionType::None)
+    }
+}
+
+/// Specific data of a transition cast member (D5+ layout):
+///   unk(u8) | chunkSize(u8) | transType(u8) | flags(u8) | duration(u16, ms)
+/// Bit 0 of `flags` is set when the transition covers the whole stage;
+/// when clear, only the changing area is transitioned.
+#[derive(Clone, Debug)]
+pub struct TransitionInfo {
+    pub chunk_size: u8,
+    pub transition_type: TransitionType,
+    pub change_area: bool,
+    pub duration: u16,
+}
+
+impl Default for TransitionInfo {
+    fn default() -> Self {
+        TransitionInfo {
+            chunk_size: 1,
+            transition_type: TransitionType::None,
+            change_area: false,
+            duration: 250,
+        }
+    }
+}
+
+impl From<&[u8]> for TransitionInfo {
+    fn from(bytes: &[u8]) -> TransitionInfo {
+        let mut reader = BinaryReader::from_u8(bytes);
+        reader.set_endian(binary_reader::Endian::Big);
+
+        let _unk = reader.read_u8().unwrap_or(0);
+        let chunk_size = reader.read_u8().unwrap_or(1);
+        let transition_type = reader.read_u8().unwrap_or(0);
+        let flags = reader.read_u8().unwrap_or(1);
+        let duration = reader.read_u16().unwrap_or(250);
+
+        TransitionInfo {
+            chunk_size: chunk_size.max(1),
+            transition_type: TransitionType::from(transition_type as u32),
+            change_area: (flags & 0x01) == 0,
+            duration,
+        }
+    }
+}
+
 /// One vertex of a Director Vector Shape, with its outgoing (handle1) and
 /// incoming (handle2) Bezier control-point offsets. For plain polygon
 /// vertices both handles are (0, 0). Coordinates are in member-vertex
@@ -33,6 +33,7 @@ use super::chunks::script_names::ScriptNamesChunk;
 use super::chunks::sound::SoundChunk;
 use super::chunks::thum::ThumChunk;
 use super::chunks::xmedia::XMediaChunk;
+use super::chunks::xtra_list::XtraListChunk;
 use super::chunks::Chunk;
 use super::chunks::ChunkContainer;
 use super::chunks::ChunkInfo;
@@ -43,6 +44,8 @@ pub struct DirectorFile {
     pub file_name: String,
     pub endian: Endian,
     pub after_burned: bool,
+    /// Container codec: `MV93`/`MC95` for movies and casts, `FGDM`/`FGDC` when afterburned
+    pub codec: u32,
     pub version: u16,
     pub cast_entries: Vec<CastListEntry>,
     pub casts: Vec<CastDef>,
@@ -55,6 +58,8 @@ pub struct DirectorFile {
     pub cast_info: Option<CastInfoChunk>,
     pub effect: Option<EffectChunk>,
     pub thum: Option<ThumChunk>,
+    /// Xtras the movie declares it needs (`XTRl`).
+    pub xtra_list: Option<XtraListChunk>,
     pub key_table: Option<KeyTableChunk>,
     pub chunk_container: ChunkContainer,
     pub font_table: HashMap<u16, String>,
@@ -79,6 +84,7 @@ impl DirectorFile {
             cached_chunk_views: HashMap::new(),
             chunk_info: HashMap::new(),
             deserialized_chunks: HashMap::new(),
+            raw_chunk_views: HashMap::new(),
         };
 
         let meta_fourcc = reader.read_u32().map_err(|e| format!("Failed to read file header: {}", e))?;
@@ -145,11 +151,16 @@ impl DirectorFile {
 
         let thum = get_thum_chunk(reader, &mut chunk_container, &mut rifx);
 
+        let xtra_list = get_xtra_list_chunk(reader, &mut chunk_container, &mut rifx)?;
+
+        preload_chunk_data(reader, &mut chunk_container, &rifx);
+
         return Ok(DirectorFile {
             base_path,
             file_name,
             endian,
             after_burned,
+            codec,
             version: rifx.dir_version,
             casts,
             cast_entries,
@@ -162,6 +173,7 @@ impl DirectorFile {
             cast_info,
             effect,
             thum,
+            xtra_list,
             key_table: Some(key_table),
             chunk_container,
             font_table,
@@ -581,6 +593,18 @@ pub fn get_effect_chunk(
     }
 }
 
+pub fn get_xtra_list_chunk(
+    reader: &mut BinaryReader,
+    chunk_container: &mut ChunkContainer,
+    rifx: &mut RIFXReaderContext,
+) -> Result<Option<XtraListChunk>, String> {
+    match get_first_chunk(reader, chunk_container, rifx, FOURCC("XTRl")) {
+        Some(Chunk::XtraList(chunk_data)) => Ok(Some(chunk_data)),
+        Some(_) => Err("Not a XTRl chunk".to_string()),
+        None => Ok(None),
+    }
+}
+
 pub fn get_score_order_chunk(
     reader: &mut BinaryReader,
     chunk_container: &mut ChunkContainer,
@@ -686,6 +710,31 @@ pub fn get_script_chunk(
     }
 }
 
+/// Load the bytes of every chunk that hasn't been touched yet, so the file can
+/// be written back out after the source buffer is gone. Reclaimed entries are
+/// only kept if the key table already pulled them in.
+fn preload_chunk_data(
+    reader: &mut BinaryReader,
+    chunk_container: &mut ChunkContainer,
+    rifx: &RIFXReaderContext,
+) {
+    let skipped = ["RIFX", "XFIR", "imap", "mmap", "ILS ", "free", "junk"].map(FOURCC);
+    let pending = chunk_container
+        .chunk_info
+        .values()
+        .filter(|info| !skipped.contains(&info.fourcc))
+        .filter(|info| !chunk_container.cached_chunk_views.contains_key(&info.id))
+        .filter(|info| rifx.after_burned || info.offset + 8 + info.len <= reader.length)
+        .map(|info| (info.id, info.fourcc))
+        .sorted()
+        .collect_vec();
+    for (id, fourcc) in pending {
+        if let Err(e) = get_chunk_data(reader, chunk_container, rifx, fourcc, id) {
+            warn!("Failed to preload '{}' chunk (id={}): {}", fourcc_to_string(fourcc), id, e);
+        }
+    }
+}
+
 fn read_config(
     reader: &mut BinaryReader,
     chunk_container: &mut ChunkContainer,
@@ -1165,6 +1214,7 @@ fn get_chunk_data(
                             .to_vec();
 
                         // Create a temporary BinaryReader over those bytes
+                        chunk_container.raw_chunk_views.insert(id, snd_bytes.clone());
                         let mut chunk_reader = BinaryReader::from_vec(&snd_bytes);
                         chunk_reader.endian = Endian::Big;
 
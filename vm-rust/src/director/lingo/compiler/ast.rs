@@ -0,0 +1,89 @@
+// Lingo compiler syntax tree
+
+use crate::director::lingo::decompiler::enums::{ChunkExprType, PutType};
+use crate::director::lingo::opcode::OpCode;
+
+#[derive(Clone, Debug)]
+pub enum Expr {
+    Int(i32),
+    Float(f64),
+    Str(String),
+    Symbol(String),
+    Void,
+    Var(String),
+    /// `_movie`, `_player` and friends
+    TopLevel(String),
+    /// `the foo`
+    The(String),
+    /// `the foo of obj`, compiled like `obj.foo`
+    TheOf { prop: String, obj: Box<Expr> },
+    /// `the number of chars in x`
+    ChunkCount { chunk_type: ChunkExprType, string: Box<Expr> },
+    /// `the last word of x`
+    LastChunk { chunk_type: ChunkExprType, string: Box<Expr> },
+    /// `char 1 to 3 of x`
+    Chunk { chunk_type: ChunkExprType, first: Box<Expr>, last: Option<Box<Expr>>, string: Box<Expr> },
+    /// `field "name" of castLib 2`
+    Field { id: Box<Expr>, cast_lib: Option<Box<Expr>> },
+    Call { name: String, args: Vec<Expr> },
+    ObjProp { obj: Box<Expr>, prop: String },
+    ObjCall { obj: Box<Expr>, method: String, args: Vec<Expr> },
+    Index { obj: Box<Expr>, index: Box<Expr> },
+    List(Vec<Expr>),
+    PropList(Vec<(Expr, Expr)>),
+    Binary { op: OpCode, left: Box<Expr>, right: Box<Expr> },
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    /// `sprite a intersects b`
+    SpriteIntersects(Box<Expr>, Box<Expr>),
+    /// `sprite a within b`
+    SpriteWithin(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Debug)]
+pub struct CaseLabel {
+    pub values: Vec<Expr>,
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Clone, Debug)]
+pub enum StmtKind {
+    Assign { target: Expr, value: Expr },
+    /// `put x`, `put x into y`, `put x after char 1 of y`
+    Put { put_type: PutType, value: Expr, target: Option<Expr> },
+    /// A handler call whose result is discarded
+    Call(Expr),
+    If { condition: Expr, then_block: Vec<Stmt>, else_block: Vec<Stmt> },
+    RepeatWhile { condition: Expr, body: Vec<Stmt> },
+    RepeatWithTo { var: String, start: Expr, end: Expr, up: bool, body: Vec<Stmt> },
+    RepeatWithIn { var: String, list: Expr, body: Vec<Stmt> },
+    Case { value: Expr, labels: Vec<CaseLabel>, otherwise: Option<Vec<Stmt>> },
+    Tell { target: Expr, body: Vec<Stmt> },
+    Delete(Expr),
+    Global(Vec<String>),
+    Return(Option<Expr>),
+    Exit,
+    ExitRepeat,
+    NextRepeat,
+}
+
+#[derive(Clone, Debug)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub line: u16,
+}
+
+#[derive(Clone, Debug)]
+pub struct HandlerAst {
+    pub name: String,
+    pub args: Vec<String>,
+    pub body: Vec<Stmt>,
+    pub line: u16,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ScriptAst {
+    pub properties: Vec<String>,
+    pub globals: Vec<String>,
+    pub handlers: Vec<HandlerAst>,
+}
@@ -0,0 +1,743 @@
+// Lingo bytecode generator
+
+use std::collections::HashMap;
+
+use fxhash::FxHashMap;
+
+use crate::director::chunks::handler::{Bytecode, HandlerDef};
+use crate::director::chunks::script::ScriptChunk;
+use crate::director::lingo::datum::Datum;
+use crate::director::lingo::decompiler::enums::{ChunkExprType, PutType};
+use crate::director::lingo::opcode::OpCode;
+
+use super::ast::{CaseLabel, Expr, HandlerAst, ScriptAst, Stmt, StmtKind};
+
+#[derive(Clone, Copy)]
+enum VarRef {
+    Global(u16),
+    Prop(u16),
+    Param(usize),
+    Local(usize),
+}
+
+impl VarRef {
+    /// Variable type used by the `put`, `putChunk` and `deleteChunk` opcodes.
+    fn context_var_type(&self) -> i64 {
+        match self {
+            VarRef::Global(_) => 0x1,
+            VarRef::Prop(_) => 0x3,
+            VarRef::Param(_) => 0x4,
+            VarRef::Local(_) => 0x5,
+        }
+    }
+}
+
+type Label = usize;
+
+struct LoopLabels {
+    next: Label,
+    exit: Label,
+}
+
+#[derive(Default)]
+struct HandlerState {
+    code: Vec<Bytecode>,
+    pos: usize,
+    line: u16,
+    args: Vec<String>,
+    locals: Vec<String>,
+    globals: Vec<String>,
+    labels: Vec<Option<usize>>,
+    fixups: Vec<(usize, Label)>,
+    loops: Vec<LoopLabels>,
+}
+
+pub struct CodeGenerator<'a> {
+    names: &'a mut Vec<String>,
+    version: u16,
+    multiplier: u32,
+    literals: Vec<Datum>,
+    properties: Vec<String>,
+    globals: Vec<String>,
+    handler_names: Vec<String>,
+    state: HandlerState,
+}
+
+impl<'a> CodeGenerator<'a> {
+    pub fn new(names: &'a mut Vec<String>, version: u16, multiplier: u32) -> CodeGenerator<'a> {
+        CodeGenerator {
+            names,
+            version,
+            multiplier,
+            literals: vec![],
+            properties: vec![],
+            globals: vec![],
+            handler_names: vec![],
+            state: HandlerState::default(),
+        }
+    }
+
+    pub fn generate(mut self, script: &ScriptAst) -> Result<ScriptChunk, String> {
+        self.properties = script.properties.clone();
+        self.globals = script.globals.clone();
+        self.handler_names = script.handlers.iter().map(|h| h.name.clone()).collect();
+
+        let mut handlers = vec![];
+        for handler in &script.handlers {
+            handlers.push(self.generate_handler(handler)?);
+        }
+        let property_name_ids = script.properties.iter().map(|p| self.name_id(p)).collect();
+
+        Ok(ScriptChunk {
+            script_number: 0,
+            literals: self.literals,
+            handlers,
+            property_name_ids,
+            property_defaults: HashMap::new(),
+        })
+    }
+
+    /// Director keeps one case-insensitive name table per context, so an
+    /// existing spelling wins over the one used in the new script.
+    fn name_id(&mut self, name: &str) -> u16 {
+        let existing = self
+            .names
+            .iter()
+            .position(|n| n == name)
+            .or_else(|| self.names.iter().position(|n| n.eq_ignore_ascii_case(name)));
+        match existing {
+            Some(index) => index as u16,
+            None => {
+                self.names.push(name.to_string());
+                (self.names.len() - 1) as u16
+            }
+        }
+    }
+
+    fn literal_id(&mut self, literal: Datum) -> usize {
+        let existing = self.literals.iter().position(|l| match (l, &literal) {
+            (Datum::String(a), Datum::String(b)) => a == b,
+            (Datum::Float(a), Datum::Float(b)) => a.to_bits() == b.to_bits(),
+            (Datum::Void, Datum::Void) => true,
+            _ => false,
+        });
+        existing.unwrap_or_else(|| {
+            self.literals.push(literal);
+            self.literals.len() - 1
+        })
+    }
+
+    fn generate_handler(&mut self, handler: &HandlerAst) -> Result<HandlerDef, String> {
+        self.state = HandlerState {
+            line: handler.line,
+            args: handler.args.clone(),
+            ..Default::default()
+        };
+        // Handler-level globals apply to the whole handler, wherever they are declared
+        collect_globals(&handler.body, &mut self.state.globals);
+
+        self.gen_block(&handler.body)?;
+        self.emit(OpCode::Ret, 0);
+
+        let mut state = std::mem::take(&mut self.state);
+        for (index, label) in state.fixups.drain(..) {
+            let target = state.labels[label].ok_or("Unresolved jump target".to_string())?;
+            let bytecode = &mut state.code[index];
+            bytecode.obj = if bytecode.opcode == OpCode::EndRepeat {
+                bytecode.pos as i64 - target as i64
+            } else {
+                target as i64 - bytecode.pos as i64
+            };
+        }
+
+        let bytecode_index_map: FxHashMap<usize, usize> =
+            state.code.iter().enumerate().map(|(i, b)| (b.pos, i)).collect();
+        let mut global_names = self.globals.clone();
+        for global in &state.globals {
+            if !global_names.iter().any(|g| g.eq_ignore_ascii_case(global)) {
+                global_names.push(global.clone());
+            }
+        }
+
+        Ok(HandlerDef {
+            name_id: self.name_id(&handler.name),
+            bytecode_array: state.code,
+            bytecode_index_map,
+            argument_name_ids: state.args.iter().map(|a| self.name_id(a)).collect(),
+            local_name_ids: state.locals.iter().map(|l| self.name_id(l)).collect(),
+            global_name_ids: global_names.iter().map(|g| self.name_id(g)).collect(),
+        })
+    }
+
+    fn emit(&mut self, opcode: OpCode, obj: i64) -> usize {
+        let state = &mut self.state;
+        state.code.push(Bytecode::new(opcode, obj, state.pos, Some(state.line)));
+        state.pos += 1 + Bytecode::operand_size(opcode, obj);
+        state.code.len() - 1
+    }
+
+    fn new_label(&mut self) -> Label {
+        self.state.labels.push(None);
+        self.state.labels.len() - 1
+    }
+
+    fn place_label(&mut self, label: Label) {
+        self.state.labels[label] = Some(self.state.pos);
+    }
+
+    fn emit_jump(&mut self, opcode: OpCode, label: Label) {
+        let index = self.emit(opcode, 0);
+        self.state.fixups.push((index, label));
+    }
+
+    fn emit_name_op(&mut self, opcode: OpCode, name: &str) {
+        let id = self.name_id(name);
+        self.emit(opcode, id as i64);
+    }
+
+    fn push_int(&mut self, value: i64) {
+        match value {
+            0 => self.emit(OpCode::PushZero, 0),
+            v if (i16::MIN as i64..=i16::MAX as i64).contains(&v) => self.emit(OpCode::PushInt8, v),
+            v => self.emit(OpCode::PushInt32, v),
+        };
+    }
+
+    fn push_literal(&mut self, literal: Datum) {
+        let id = self.literal_id(literal);
+        self.emit(OpCode::PushCons, (id as u32 * self.multiplier) as i64);
+    }
+
+    fn resolve_var(&mut self, name: &str) -> VarRef {
+        if let Some(index) = self.state.args.iter().position(|a| a.eq_ignore_ascii_case(name)) {
+            return VarRef::Param(index);
+        }
+        let is_global = self.state.globals.iter().chain(self.globals.iter()).any(|g| g.eq_ignore_ascii_case(name));
+        if is_global {
+            return VarRef::Global(self.name_id(name));
+        }
+        if self.properties.iter().any(|p| p.eq_ignore_ascii_case(name)) {
+            return VarRef::Prop(self.name_id(name));
+        }
+        let index = match self.state.locals.iter().position(|l| l.eq_ignore_ascii_case(name)) {
+            Some(index) => index,
+            None => {
+                self.state.locals.push(name.to_string());
+                self.state.locals.len() - 1
+            }
+        };
+        VarRef::Local(index)
+    }
+
+    fn var_operand(&self, index: usize) -> i64 {
+        (index as u32 * self.multiplier) as i64
+    }
+
+    fn gen_get_var(&mut self, var: VarRef) {
+        match var {
+            VarRef::Global(id) => self.emit(OpCode::GetGlobal, id as i64),
+            VarRef::Prop(id) => self.emit(OpCode::GetProp, id as i64),
+            VarRef::Param(index) => self.emit(OpCode::GetParam, self.var_operand(index)),
+            VarRef::Local(index) => self.emit(OpCode::GetLocal, self.var_operand(index)),
+        };
+    }
+
+    fn gen_set_var(&mut self, var: VarRef) {
+        match var {
+            VarRef::Global(id) => self.emit(OpCode::SetGlobal, id as i64),
+            VarRef::Prop(id) => self.emit(OpCode::SetProp, id as i64),
+            VarRef::Param(index) => self.emit(OpCode::SetParam, self.var_operand(index)),
+            VarRef::Local(index) => self.emit(OpCode::SetLocal, self.var_operand(index)),
+        };
+    }
+
+    /// Push the id of a variable for the context var opcodes.
+    fn push_context_var_id(&mut self, var: VarRef) {
+        match var {
+            VarRef::Global(id) | VarRef::Prop(id) => self.emit(OpCode::PushSymb, id as i64),
+            VarRef::Param(index) | VarRef::Local(index) => self.emit(OpCode::PushInt8, self.var_operand(index)),
+        };
+    }
+
+    fn push_field_id(&mut self, id: &Expr, cast_lib: &Option<Box<Expr>>) -> Result<(), String> {
+        self.gen_expr(id)?;
+        if self.version >= 500 {
+            match cast_lib {
+                Some(cast_lib) => self.gen_expr(cast_lib)?,
+                None => {
+                    self.emit(OpCode::PushZero, 0);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn gen_block(&mut self, block: &[Stmt]) -> Result<(), String> {
+        for stmt in block {
+            self.state.line = stmt.line;
+            self.gen_stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn gen_stmt(&mut self, stmt: &Stmt) -> Result<(), String> {
+        let error = |message: &str| Err(format!("Line {}: {}", stmt.line, message));
+        match &stmt.kind {
+            StmtKind::Assign { target, value } => self.gen_assign(target, value)?,
+            StmtKind::Put { value, target: None, .. } => {
+                self.gen_expr(value)?;
+                self.emit(OpCode::PushArgListNoRet, 1);
+                self.emit_name_op(OpCode::ExtCall, "put");
+            }
+            StmtKind::Put { put_type, value, target: Some(target) } => self.gen_put(*put_type, value, target)?,
+            StmtKind::Call(call) => match call {
+                Expr::Call { name, args } => self.gen_call(name, args, true)?,
+                Expr::ObjCall { obj, method, args } => self.gen_obj_call(obj, method, args, true)?,
+                _ => return error("expected handler call"),
+            },
+            StmtKind::If { condition, then_block, else_block } => {
+                let else_label = self.new_label();
+                self.gen_expr(condition)?;
+                self.emit_jump(OpCode::JmpIfZ, else_label);
+                self.gen_block(then_block)?;
+                if else_block.is_empty() {
+                    self.place_label(else_label);
+                } else {
+                    let end_label = self.new_label();
+                    self.emit_jump(OpCode::Jmp, end_label);
+                    self.place_label(else_label);
+                    self.gen_block(else_block)?;
+                    self.place_label(end_label);
+                }
+            }
+            StmtKind::RepeatWhile { condition, body } => {
+                let start = self.new_label();
+                let next = self.new_label();
+                let exit = self.new_label();
+                self.place_label(start);
+                self.gen_expr(condition)?;
+                self.emit_jump(OpCode::JmpIfZ, exit);
+                self.gen_loop_body(body, next, exit)?;
+                self.place_label(next);
+                self.emit_jump(OpCode::EndRepeat, start);
+                self.place_label(exit);
+            }
+            StmtKind::RepeatWithTo { var, start, end, up, body } => {
+                let var = self.resolve_var(var);
+                let cond = self.new_label();
+                let next = self.new_label();
+                let exit = self.new_label();
+                self.gen_expr(start)?;
+                self.gen_set_var(var);
+                self.place_label(cond);
+                self.gen_get_var(var);
+                self.gen_expr(end)?;
+                self.emit(if *up { OpCode::LtEq } else { OpCode::GtEq }, 0);
+                self.emit_jump(OpCode::JmpIfZ, exit);
+                self.gen_loop_body(body, next, exit)?;
+                self.place_label(next);
+                self.push_int(if *up { 1 } else { -1 });
+                self.gen_get_var(var);
+                self.emit(OpCode::Add, 0);
+                self.gen_set_var(var);
+                self.emit_jump(OpCode::EndRepeat, cond);
+                self.place_label(exit);
+            }
+            StmtKind::RepeatWithIn { var, list, body } => {
+                let var = self.resolve_var(var);
+                let cond = self.new_label();
+                let next = self.new_label();
+                let exit = self.new_label();
+                self.gen_expr(list)?;
+                self.emit(OpCode::Peek, 0);
+                self.emit(OpCode::PushArgList, 1);
+                self.emit_name_op(OpCode::ExtCall, "count");
+                self.push_int(1);
+                self.place_label(cond);
+                self.emit(OpCode::Peek, 0);
+                self.emit(OpCode::Peek, 2);
+                self.emit(OpCode::LtEq, 0);
+                self.emit_jump(OpCode::JmpIfZ, exit);
+                self.emit(OpCode::Peek, 2);
+                self.emit(OpCode::Peek, 1);
+                self.emit(OpCode::PushArgList, 2);
+                self.emit_name_op(OpCode::ExtCall, "getAt");
+                self.gen_set_var(var);
+                self.gen_loop_body(body, next, exit)?;
+                self.place_label(next);
+                self.push_int(1);
+                self.emit(OpCode::Add, 0);
+                self.emit_jump(OpCode::EndRepeat, cond);
+                self.place_label(exit);
+                self.emit(OpCode::Pop, 3);
+            }
+            StmtKind::Case { value, labels, otherwise } => self.gen_case(value, labels, otherwise)?,
+            StmtKind::Tell { target, body } => {
+                self.gen_expr(target)?;
+                self.emit(OpCode::StartTell, 0);
+                self.gen_block(body)?;
+                self.emit(OpCode::EndTell, 0);
+            }
+            StmtKind::Delete(target) => {
+                let Expr::Chunk { .. } = target else {
+                    return error("delete expects a chunk expression");
+                };
+                let string = self.gen_chunk_bounds(target)?;
+                let var_type = self.push_chunk_container(string, stmt.line)?;
+                self.emit(OpCode::DeleteChunk, var_type);
+            }
+            // Declarations are collected before code generation
+            StmtKind::Global(_) => {}
+            StmtKind::Return(value) => {
+                match value {
+                    Some(value) => {
+                        self.gen_expr(value)?;
+                        self.emit(OpCode::PushArgListNoRet, 1);
+                    }
+                    None => {
+                        self.emit(OpCode::PushArgListNoRet, 0);
+                    }
+                }
+                self.emit_name_op(OpCode::ExtCall, "return");
+            }
+            StmtKind::Exit => {
+                self.emit(OpCode::Ret, 0);
+            }
+            StmtKind::ExitRepeat => match self.state.loops.last() {
+                Some(labels) => {
+                    let exit = labels.exit;
+                    self.emit_jump(OpCode::Jmp, exit);
+                }
+                None => return error("exit repeat outside of a repeat loop"),
+            },
+            StmtKind::NextRepeat => match self.state.loops.last() {
+                Some(labels) => {
+                    let next = labels.next;
+                    self.emit_jump(OpCode::Jmp, next);
+                }
+                None => return error("next repeat outside of a repeat loop"),
+            },
+        }
+        Ok(())
+    }
+
+    fn gen_loop_body(&mut self, body: &[Stmt], next: Label, exit: Label) -> Result<(), String> {
+        self.state.loops.push(LoopLabels { next, exit });
+        let result = self.gen_block(body);
+        self.state.loops.pop();
+        result
+    }
+
+    fn gen_case(&mut self, value: &Expr, labels: &[CaseLabel], otherwise: &Option<Vec<Stmt>>) -> Result<(), String> {
+        let end = self.new_label();
+        self.gen_expr(value)?;
+        for (label_index, label) in labels.iter().enumerate() {
+            let is_last_label = label_index + 1 == labels.len();
+            let body = self.new_label();
+            let next_label = self.new_label();
+            for (value_index, case_value) in label.values.iter().enumerate() {
+                self.emit(OpCode::Peek, 0);
+                self.gen_expr(case_value)?;
+                if value_index + 1 < label.values.len() {
+                    self.emit(OpCode::NtEq, 0);
+                    self.emit_jump(OpCode::JmpIfZ, body);
+                } else {
+                    self.emit(OpCode::Eq, 0);
+                    self.emit_jump(OpCode::JmpIfZ, next_label);
+                }
+            }
+            self.place_label(body);
+            self.gen_block(&label.body)?;
+            if !is_last_label || otherwise.is_some() {
+                self.emit_jump(OpCode::Jmp, end);
+            }
+            self.place_label(next_label);
+        }
+        if let Some(otherwise) = otherwise {
+            self.gen_block(otherwise)?;
+        }
+        self.place_label(end);
+        self.emit(OpCode::Pop, 1);
+        Ok(())
+    }
+
+    fn gen_assign(&mut self, target: &Expr, value: &Expr) -> Result<(), String> {
+        match target {
+            Expr::Var(name) => {
+                let var = self.resolve_var(name);
+                self.gen_expr(value)?;
+                self.gen_set_var(var);
+            }
+            Expr::The(prop) => {
+                self.gen_expr(value)?;
+                self.emit_name_op(OpCode::SetMovieProp, prop);
+            }
+            Expr::TheOf { prop, obj } | Expr::ObjProp { obj, prop } => {
+                self.gen_expr(obj)?;
+                self.gen_expr(value)?;
+                self.emit_name_op(OpCode::SetObjProp, prop);
+            }
+            Expr::Index { obj, index } => {
+                let args = vec![index.as_ref().clone(), value.clone()];
+                self.gen_obj_call(obj, "setAt", &args, true)?;
+            }
+            Expr::Field { .. } | Expr::Chunk { .. } => self.gen_put(PutType::Into, value, target)?,
+            _ => return Err("Invalid assignment target".to_string()),
+        }
+        Ok(())
+    }
+
+    fn gen_put(&mut self, put_type: PutType, value: &Expr, target: &Expr) -> Result<(), String> {
+        let line = self.state.line;
+        let put_op = (put_type as i64) << 4;
+        match target {
+            Expr::Var(name) => match self.resolve_var(name) {
+                var @ (VarRef::Param(_) | VarRef::Local(_)) => {
+                    self.gen_expr(value)?;
+                    self.push_context_var_id(var);
+                    self.emit(OpCode::Put, put_op | var.context_var_type());
+                }
+                var => self.gen_concat_assign(put_type, value, target, |codegen| codegen.gen_set_var(var))?,
+            },
+            Expr::Field { id, cast_lib } => {
+                self.gen_expr(value)?;
+                self.push_field_id(id, cast_lib)?;
+                self.emit(OpCode::Put, put_op | 0x6);
+            }
+            Expr::Chunk { .. } => {
+                self.gen_expr(value)?;
+                let string = self.gen_chunk_bounds(target)?;
+                let var_type = self.push_chunk_container(string, line)?;
+                self.emit(OpCode::PutChunk, put_op | var_type);
+            }
+            _ if put_type == PutType::Into => self.gen_assign(target, value)?,
+            _ => {
+                let joined = match put_type {
+                    PutType::After => join(target.clone(), value.clone()),
+                    _ => join(value.clone(), target.clone()),
+                };
+                self.gen_assign(target, &joined)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `put x after g` for globals and properties, compiled as `g = g & x`.
+    fn gen_concat_assign(
+        &mut self,
+        put_type: PutType,
+        value: &Expr,
+        target: &Expr,
+        store: impl FnOnce(&mut Self),
+    ) -> Result<(), String> {
+        match put_type {
+            PutType::Into => self.gen_expr(value)?,
+            PutType::After => self.gen_expr(&join(target.clone(), value.clone()))?,
+            PutType::Before => self.gen_expr(&join(value.clone(), target.clone()))?,
+        }
+        store(self);
+        Ok(())
+    }
+
+    /// Push the container of a chunk for `putChunk`/`deleteChunk` and return
+    /// its variable type.
+    fn push_chunk_container(&mut self, string: &Expr, line: u16) -> Result<i64, String> {
+        match string {
+            Expr::Var(name) => {
+                let var = self.resolve_var(name);
+                self.push_context_var_id(var);
+                Ok(var.context_var_type())
+            }
+            Expr::Field { id, cast_lib } => {
+                self.push_field_id(id, cast_lib)?;
+                Ok(0x6)
+            }
+            _ => Err(format!("Line {}: chunks can only be changed in variables and fields", line)),
+        }
+    }
+
+    /// Push the eight chunk bounds for `expr`, merging nested chunks of
+    /// increasing size (`char 1 of word 2 of x`), and return the expression
+    /// holding the string.
+    fn gen_chunk_bounds<'e>(&mut self, expr: &'e Expr) -> Result<&'e Expr, String> {
+        let mut bounds: [Option<(&Expr, Option<&Expr>)>; 4] = [None; 4];
+        let mut current = expr;
+        let mut min_index = 0;
+        while let Expr::Chunk { chunk_type, first, last, string } = current {
+            let index = chunk_index(*chunk_type);
+            if index < min_index || bounds[index].is_some() {
+                break;
+            }
+            bounds[index] = Some((first.as_ref(), last.as_deref()));
+            min_index = index + 1;
+            current = string.as_ref();
+        }
+        for bound in bounds {
+            match bound {
+                Some((first, last)) => {
+                    self.gen_expr(first)?;
+                    match last {
+                        Some(last) => self.gen_expr(last)?,
+                        None => self.push_int(0),
+                    }
+                }
+                None => {
+                    self.push_int(0);
+                    self.push_int(0);
+                }
+            }
+        }
+        Ok(current)
+    }
+
+    fn gen_call(&mut self, name: &str, args: &[Expr], no_ret: bool) -> Result<(), String> {
+        for arg in args {
+            self.gen_expr(arg)?;
+        }
+        self.emit(
+            if no_ret { OpCode::PushArgListNoRet } else { OpCode::PushArgList },
+            args.len() as i64,
+        );
+        match self.handler_names.iter().position(|h| h.eq_ignore_ascii_case(name)) {
+            Some(index) => {
+                self.emit(OpCode::LocalCall, index as i64);
+            }
+            None => self.emit_name_op(OpCode::ExtCall, name),
+        }
+        Ok(())
+    }
+
+    fn gen_obj_call(&mut self, obj: &Expr, method: &str, args: &[Expr], no_ret: bool) -> Result<(), String> {
+        self.gen_expr(obj)?;
+        for arg in args {
+            self.gen_expr(arg)?;
+        }
+        self.emit(
+            if no_ret { OpCode::PushArgListNoRet } else { OpCode::PushArgList },
+            args.len() as i64 + 1,
+        );
+        self.emit_name_op(OpCode::ObjCall, method);
+        Ok(())
+    }
+
+    fn gen_expr(&mut self, expr: &Expr) -> Result<(), String> {
+        match expr {
+            Expr::Int(value) => self.push_int(*value as i64),
+            Expr::Float(value) => self.push_literal(Datum::Float(*value)),
+            Expr::Str(value) => self.push_literal(Datum::String(value.clone())),
+            Expr::Symbol(name) => self.emit_name_op(OpCode::PushSymb, name),
+            Expr::Void => self.push_literal(Datum::Void),
+            Expr::Var(name) => {
+                let var = self.resolve_var(name);
+                self.gen_get_var(var);
+            }
+            Expr::TopLevel(name) => self.emit_name_op(OpCode::GetTopLevelProp, name),
+            Expr::The(prop) => self.emit_name_op(OpCode::GetMovieProp, prop),
+            Expr::TheOf { prop, obj } | Expr::ObjProp { obj, prop } => {
+                self.gen_expr(obj)?;
+                self.emit_name_op(OpCode::GetObjProp, prop);
+            }
+            Expr::ChunkCount { chunk_type, string } => {
+                self.gen_expr(string)?;
+                self.push_int(*chunk_type as i64);
+                self.emit(OpCode::Get, 1);
+            }
+            Expr::LastChunk { chunk_type, string } => {
+                self.gen_expr(string)?;
+                self.push_int(0x0b + *chunk_type as i64);
+                self.emit(OpCode::Get, 0);
+            }
+            Expr::Chunk { .. } => {
+                let string = self.gen_chunk_bounds(expr)?;
+                self.gen_expr(string)?;
+                self.emit(OpCode::GetChunk, 0);
+            }
+            Expr::Field { id, cast_lib } => {
+                self.push_field_id(id, cast_lib)?;
+                self.emit(OpCode::GetField, 0);
+            }
+            Expr::Call { name, args } => self.gen_call(name, args, false)?,
+            Expr::ObjCall { obj, method, args } => self.gen_obj_call(obj, method, args, false)?,
+            Expr::Index { obj, index } => self.gen_obj_call(obj, "getAt", std::slice::from_ref(index.as_ref()), false)?,
+            Expr::List(items) => {
+                for item in items {
+                    self.gen_expr(item)?;
+                }
+                self.emit(OpCode::PushArgList, items.len() as i64);
+                self.emit(OpCode::PushList, 0);
+            }
+            Expr::PropList(pairs) => {
+                for (key, value) in pairs {
+                    self.gen_expr(key)?;
+                    self.gen_expr(value)?;
+                }
+                self.emit(OpCode::PushArgList, pairs.len() as i64 * 2);
+                self.emit(OpCode::PushPropList, 0);
+            }
+            Expr::Binary { op, left, right } => {
+                self.gen_expr(left)?;
+                self.gen_expr(right)?;
+                self.emit(*op, 0);
+            }
+            Expr::Neg(operand) => {
+                self.gen_expr(operand)?;
+                self.emit(OpCode::Inv, 0);
+            }
+            Expr::Not(operand) => {
+                self.gen_expr(operand)?;
+                self.emit(OpCode::Not, 0);
+            }
+            Expr::SpriteIntersects(a, b) => {
+                self.gen_expr(a)?;
+                self.gen_expr(b)?;
+                self.emit(OpCode::OntoSpr, 0);
+            }
+            Expr::SpriteWithin(a, b) => {
+                self.gen_expr(a)?;
+                self.gen_expr(b)?;
+                self.emit(OpCode::IntoSpr, 0);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn join(left: Expr, right: Expr) -> Expr {
+    Expr::Binary { op: OpCode::JoinStr, left: Box::new(left), right: Box::new(right) }
+}
+
+/// Chunk slots in the order the bounds are pushed: char, word, item, line.
+fn chunk_index(chunk_type: ChunkExprType) -> usize {
+    chunk_type as usize - 1
+}
+
+fn collect_globals(block: &[Stmt], globals: &mut Vec<String>) {
+    for stmt in block {
+        match &stmt.kind {
+            StmtKind::Global(names) => {
+                for name in names {
+                    if !globals.iter().any(|g| g.eq_ignore_ascii_case(name)) {
+                        globals.push(name.clone());
+                    }
+                }
+            }
+            StmtKind::If { then_block, else_block, .. } => {
+                collect_globals(then_block, globals);
+                collect_globals(else_block, globals);
+            }
+            StmtKind::RepeatWhile { body, .. }
+            | StmtKind::RepeatWithTo { body, .. }
+            | StmtKind::RepeatWithIn { body, .. }
+            | StmtKind::Tell { body, .. } => collect_globals(body, globals),
+            StmtKind::Case { labels, otherwise, .. } => {
+                for label in labels {
+                    collect_globals(&label.body, globals);
+                }
+                if let Some(otherwise) = otherwise {
+                    collect_globals(otherwise, globals);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,202 @@
+// Lingo source tokenizer
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Ident(String),
+    Int(i32),
+    Float(f64),
+    Str(String),
+    Symbol(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Colon,
+    Dot,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Amp,
+    AmpAmp,
+    Eq,
+    NtEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Newline,
+    Eof,
+}
+
+#[derive(Clone, Debug)]
+pub struct Lexeme {
+    pub token: Token,
+    pub line: u16,
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Split Lingo source into tokens. Line breaks are significant in Lingo so
+/// they are kept as `Newline` tokens; comments and line continuations
+/// (`\` or `¬` at the end of a line) are dropped.
+pub fn tokenize(source: &str) -> Result<Vec<Lexeme>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line: u16 = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start_line = line;
+        let token = match c {
+            ' ' | '\t' => {
+                i += 1;
+                continue;
+            }
+            '\r' | '\n' => {
+                if c == '\r' && chars.get(i + 1) == Some(&'\n') {
+                    i += 1;
+                }
+                i += 1;
+                line += 1;
+                Token::Newline
+            }
+            '\\' | '¬' => {
+                // Line continuation: skip to the start of the next line
+                let mut j = i + 1;
+                while j < chars.len() && (chars[j] == ' ' || chars[j] == '\t') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j] != '\r' && chars[j] != '\n' {
+                    return Err(format!("Line {}: unexpected character '{}'", line, c));
+                }
+                if chars.get(j) == Some(&'\r') && chars.get(j + 1) == Some(&'\n') {
+                    j += 1;
+                }
+                i = j + 1;
+                line += 1;
+                continue;
+            }
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                while i < chars.len() && chars[i] != '\r' && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            '"' => {
+                let mut j = i + 1;
+                let mut value = String::new();
+                while j < chars.len() && chars[j] != '"' {
+                    if chars[j] == '\r' || chars[j] == '\n' {
+                        return Err(format!("Line {}: unterminated string", line));
+                    }
+                    value.push(chars[j]);
+                    j += 1;
+                }
+                if j >= chars.len() {
+                    return Err(format!("Line {}: unterminated string", line));
+                }
+                i = j + 1;
+                Token::Str(value)
+            }
+            '#' => {
+                let mut j = i + 1;
+                while j < chars.len() && is_ident_char(chars[j]) {
+                    j += 1;
+                }
+                if j == i + 1 {
+                    return Err(format!("Line {}: expected symbol name after '#'", line));
+                }
+                let name: String = chars[i + 1..j].iter().collect();
+                i = j;
+                Token::Symbol(name)
+            }
+            c if c.is_ascii_digit() => {
+                let mut j = i;
+                while j < chars.len() && chars[j].is_ascii_digit() {
+                    j += 1;
+                }
+                let mut is_float = false;
+                if j + 1 < chars.len() && chars[j] == '.' && chars[j + 1].is_ascii_digit() {
+                    is_float = true;
+                    j += 1;
+                    while j < chars.len() && chars[j].is_ascii_digit() {
+                        j += 1;
+                    }
+                }
+                if j < chars.len() && (chars[j] == 'e' || chars[j] == 'E') {
+                    let mut k = j + 1;
+                    if k < chars.len() && (chars[k] == '+' || chars[k] == '-') {
+                        k += 1;
+                    }
+                    if k < chars.len() && chars[k].is_ascii_digit() {
+                        is_float = true;
+                        j = k;
+                        while j < chars.len() && chars[j].is_ascii_digit() {
+                            j += 1;
+                        }
+                    }
+                }
+                let text: String = chars[i..j].iter().collect();
+                i = j;
+                if is_float {
+                    Token::Float(text.parse().map_err(|_| format!("Line {}: invalid number {}", line, text))?)
+                } else {
+                    match text.parse::<i32>() {
+                        Ok(value) => Token::Int(value),
+                        // Director turns out-of-range integer literals into floats
+                        Err(_) => Token::Float(text.parse().map_err(|_| format!("Line {}: invalid number {}", line, text))?),
+                    }
+                }
+            }
+            c if is_ident_start(c) => {
+                let mut j = i;
+                while j < chars.len() && is_ident_char(chars[j]) {
+                    j += 1;
+                }
+                let name: String = chars[i..j].iter().collect();
+                i = j;
+                Token::Ident(name)
+            }
+            _ => {
+                let next = chars.get(i + 1).copied();
+                let (token, len) = match (c, next) {
+                    ('&', Some('&')) => (Token::AmpAmp, 2),
+                    ('<', Some('>')) => (Token::NtEq, 2),
+                    ('<', Some('=')) => (Token::LtEq, 2),
+                    ('>', Some('=')) => (Token::GtEq, 2),
+                    ('(', _) => (Token::LParen, 1),
+                    (')', _) => (Token::RParen, 1),
+                    ('[', _) => (Token::LBracket, 1),
+                    (']', _) => (Token::RBracket, 1),
+                    (',', _) => (Token::Comma, 1),
+                    (':', _) => (Token::Colon, 1),
+                    ('.', _) => (Token::Dot, 1),
+                    ('+', _) => (Token::Plus, 1),
+                    ('-', _) => (Token::Minus, 1),
+                    ('*', _) => (Token::Star, 1),
+                    ('/', _) => (Token::Slash, 1),
+                    ('&', _) => (Token::Amp, 1),
+                    ('=', _) => (Token::Eq, 1),
+                    ('<', _) => (Token::Lt, 1),
+                    ('>', _) => (Token::Gt, 1),
+                    _ => return Err(format!("Line {}: unexpected character '{}'", line, c)),
+                };
+                i += len;
+                token
+            }
+        };
+        tokens.push(Lexeme { token, line: start_line });
+    }
+
+    tokens.push(Lexeme { token: Token::Eof, line });
+    Ok(tokens)
+}
@@ -0,0 +1,23 @@
+// Lingo source compiler
+// Produces the same script chunk layout the VM loads from Lscr chunks, so
+// compiled scripts run and decompile like ones authored in Director.
+
+pub mod ast;
+pub mod codegen;
+pub mod lexer;
+pub mod parser;
+
+use crate::director::chunks::script::ScriptChunk;
+
+/// Compile the full text of a script member. New names are appended to
+/// `names`, the name table of the script context the chunk will live in.
+pub fn compile_script(
+    source: &str,
+    names: &mut Vec<String>,
+    version: u16,
+    multiplier: u32,
+) -> Result<ScriptChunk, String> {
+    let tokens = lexer::tokenize(source)?;
+    let script = parser::Parser::new(tokens).parse_script()?;
+    codegen::CodeGenerator::new(names, version, multiplier).generate(&script)
+}
@@ -0,0 +1,859 @@
+// Lingo source parser
+
+use std::f64::consts::PI;
+
+use crate::director::lingo::decompiler::enums::{ChunkExprType, PutType};
+use crate::director::lingo::opcode::OpCode;
+
+use super::ast::{CaseLabel, Expr, HandlerAst, ScriptAst, Stmt, StmtKind};
+use super::lexer::{Lexeme, Token};
+
+/// Words that continue an expression or a statement and therefore can never
+/// start the argument of a parenthesis-less call such as `member "x"`.
+const CONTINUATION_WORDS: &[&str] = &[
+    "and", "or", "mod", "of", "to", "in", "into", "after", "before", "then", "else", "contains",
+    "starts", "down", "intersects", "within",
+];
+
+const TOP_LEVEL_PROPS: &[&str] = &["_player", "_movie", "_mouse", "_system", "_sound", "_key"];
+
+fn chunk_type_from_name(name: &str) -> Option<ChunkExprType> {
+    match name.to_ascii_lowercase().as_str() {
+        "char" | "chars" => Some(ChunkExprType::Char),
+        "word" | "words" => Some(ChunkExprType::Word),
+        "item" | "items" => Some(ChunkExprType::Item),
+        "line" | "lines" => Some(ChunkExprType::Line),
+        _ => None,
+    }
+}
+
+pub struct Parser {
+    tokens: Vec<Lexeme>,
+    pos: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Lexeme>) -> Parser {
+        Parser { tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> &Token {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> &Token {
+        self.tokens
+            .get(self.pos + offset)
+            .map(|lexeme| &lexeme.token)
+            .unwrap_or(&Token::Eof)
+    }
+
+    fn line(&self) -> u16 {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map(|lexeme| lexeme.line)
+            .unwrap_or(1)
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token != Token::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, String> {
+        Err(format!("Line {}: {}", self.line(), message))
+    }
+
+    fn is_word_at(&self, offset: usize, word: &str) -> bool {
+        matches!(self.peek_at(offset), Token::Ident(name) if name.eq_ignore_ascii_case(word))
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        self.is_word_at(0, word)
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        if self.is_word(word) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), String> {
+        if self.eat_word(word) {
+            Ok(())
+        } else {
+            self.error(&format!("expected '{}'", word))
+        }
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == token {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<(), String> {
+        if self.eat(&token) {
+            Ok(())
+        } else {
+            self.error(&format!("expected {}", what))
+        }
+    }
+
+    fn ident(&mut self, what: &str) -> Result<String, String> {
+        match self.peek().clone() {
+            Token::Ident(name) => {
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => self.error(&format!("expected {}", what)),
+        }
+    }
+
+    fn at_line_end(&self) -> bool {
+        matches!(self.peek(), Token::Newline | Token::Eof)
+    }
+
+    fn expect_line_end(&mut self) -> Result<(), String> {
+        match self.peek() {
+            Token::Newline => {
+                self.pos += 1;
+                Ok(())
+            }
+            Token::Eof => Ok(()),
+            token => self.error(&format!("unexpected {:?} at end of statement", token)),
+        }
+    }
+
+    fn skip_newlines(&mut self) {
+        while *self.peek() == Token::Newline {
+            self.pos += 1;
+        }
+    }
+
+    /// Whether the token at `offset` can begin the argument of a
+    /// parenthesis-less builtin such as `sprite 1` or `char 2 of x`.
+    fn starts_argument_at(&self, offset: usize) -> bool {
+        match self.peek_at(offset) {
+            Token::Int(_) | Token::Float(_) | Token::Str(_) | Token::Symbol(_) | Token::LParen => true,
+            Token::Ident(name) => !CONTINUATION_WORDS.iter().any(|w| name.eq_ignore_ascii_case(w)),
+            _ => false,
+        }
+    }
+
+    /// Offset of the token after the parenthesised group starting at `offset`.
+    fn skip_group_at(&self, offset: usize) -> usize {
+        let mut depth = 0;
+        let mut i = offset;
+        loop {
+            match self.peek_at(i) {
+                Token::LParen | Token::LBracket => depth += 1,
+                Token::RParen | Token::RBracket => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return i + 1;
+                    }
+                }
+                Token::Newline | Token::Eof => return i,
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    /// Case labels are told apart from statements by a `:` outside brackets.
+    fn line_is_case_label(&self) -> bool {
+        let mut depth = 0;
+        let mut i = 0;
+        loop {
+            match self.peek_at(i) {
+                Token::LParen | Token::LBracket => depth += 1,
+                Token::RParen | Token::RBracket => depth -= 1,
+                Token::Colon if depth == 0 => return true,
+                Token::Newline | Token::Eof => return false,
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    pub fn parse_script(&mut self) -> Result<ScriptAst, String> {
+        let mut script = ScriptAst::default();
+        loop {
+            self.skip_newlines();
+            if *self.peek() == Token::Eof {
+                break;
+            }
+            if self.eat_word("property") {
+                script.properties.extend(self.parse_name_list()?);
+                self.expect_line_end()?;
+            } else if self.eat_word("global") {
+                script.globals.extend(self.parse_name_list()?);
+                self.expect_line_end()?;
+            } else if self.is_word("on") {
+                let handler = self.parse_handler()?;
+                if script.handlers.iter().any(|h| h.name.eq_ignore_ascii_case(&handler.name)) {
+                    return Err(format!("Line {}: handler {} is defined twice", handler.line, handler.name));
+                }
+                script.handlers.push(handler);
+            } else {
+                return self.error("expected handler definition");
+            }
+        }
+        Ok(script)
+    }
+
+    fn parse_name_list(&mut self) -> Result<Vec<String>, String> {
+        let mut names = vec![self.ident("variable name")?];
+        while self.eat(&Token::Comma) {
+            names.push(self.ident("variable name")?);
+        }
+        Ok(names)
+    }
+
+    fn parse_handler(&mut self) -> Result<HandlerAst, String> {
+        let line = self.line();
+        self.expect_word("on")?;
+        let name = self.ident("handler name")?;
+        let mut args = vec![];
+        let parens = self.eat(&Token::LParen);
+        if !(self.at_line_end() || parens && *self.peek() == Token::RParen) {
+            args = self.parse_name_list()?;
+        }
+        if parens {
+            self.expect(Token::RParen, "')'")?;
+        }
+        self.expect_line_end()?;
+
+        // `end` is optional when another handler or the end of the script follows
+        let body = self.parse_block(|p| p.is_word("end") || p.is_word("on"))?;
+        if self.eat_word("end") {
+            if !self.at_line_end() {
+                self.ident("handler name")?;
+            }
+            self.expect_line_end()?;
+        }
+        Ok(HandlerAst { name, args, body, line })
+    }
+
+    fn parse_block(&mut self, stop: fn(&Parser) -> bool) -> Result<Vec<Stmt>, String> {
+        let mut stmts = vec![];
+        loop {
+            self.skip_newlines();
+            if *self.peek() == Token::Eof || stop(self) {
+                return Ok(stmts);
+            }
+            stmts.push(self.parse_statement()?);
+            self.expect_line_end()?;
+        }
+    }
+
+    fn parse_statement(&mut self) -> Result<Stmt, String> {
+        let line = self.line();
+        let kind = if self.is_word("if") {
+            let (kind, needs_end) = self.parse_if()?;
+            if needs_end {
+                self.expect_word("end")?;
+                self.expect_word("if")?;
+            }
+            kind
+        } else if self.is_word("repeat") {
+            self.parse_repeat()?
+        } else if self.is_word("case") {
+            self.parse_case()?
+        } else if self.is_word("tell") {
+            self.parse_tell()?
+        } else if self.eat_word("exit") {
+            if self.eat_word("repeat") {
+                StmtKind::ExitRepeat
+            } else {
+                StmtKind::Exit
+            }
+        } else if self.is_word("next") && self.is_word_at(1, "repeat") {
+            self.pos += 2;
+            StmtKind::NextRepeat
+        } else if self.eat_word("return") {
+            if self.at_line_end() || self.is_word("else") {
+                StmtKind::Return(None)
+            } else {
+                StmtKind::Return(Some(self.parse_expr()?))
+            }
+        } else if self.eat_word("global") {
+            StmtKind::Global(self.parse_name_list()?)
+        } else if self.eat_word("set") {
+            let target = self.parse_postfix()?;
+            if !self.eat_word("to") && !self.eat(&Token::Eq) {
+                return self.error("expected 'to' or '=' in set statement");
+            }
+            StmtKind::Assign { target, value: self.parse_expr()? }
+        } else if self.eat_word("put") {
+            self.parse_put()?
+        } else if self.eat_word("delete") {
+            StmtKind::Delete(self.parse_expr()?)
+        } else if self.is_word("go") && *self.peek_at(1) != Token::LParen {
+            self.parse_go()?
+        } else if self.is_word("end") || self.is_word("else") || self.is_word("otherwise") {
+            return self.error(&format!("unexpected '{:?}'", self.peek()));
+        } else {
+            self.parse_command_or_assignment()?
+        };
+        Ok(Stmt { kind, line })
+    }
+
+    /// Parse an `if` statement. The flag tells the caller whether the
+    /// statement has to be closed by `end if`; an `else if` chain shares a
+    /// single `end if` with the `if` it belongs to.
+    fn parse_if(&mut self) -> Result<(StmtKind, bool), String> {
+        self.expect_word("if")?;
+        let condition = self.parse_expr()?;
+        self.skip_newlines();
+        self.expect_word("then")?;
+
+        if self.at_line_end() {
+            let then_block = self.parse_block(|p| p.is_word("else") || p.is_word("end"))?;
+            let else_block = if self.eat_word("else") { self.parse_else(true)?.0 } else { vec![] };
+            return Ok((StmtKind::If { condition, then_block, else_block }, true));
+        }
+
+        let then_block = vec![self.parse_statement()?];
+        // a single-line `if` may still take its `else` from the next line
+        let has_else = if self.is_word("else") {
+            true
+        } else {
+            *self.peek() == Token::Newline && self.is_word_at(1, "else")
+        };
+        if !has_else {
+            return Ok((StmtKind::If { condition, then_block, else_block: vec![] }, false));
+        }
+        self.skip_newlines();
+        self.expect_word("else")?;
+        let (else_block, needs_end) = self.parse_else(false)?;
+        Ok((StmtKind::If { condition, then_block, else_block }, needs_end))
+    }
+
+    fn parse_else(&mut self, multi_line: bool) -> Result<(Vec<Stmt>, bool), String> {
+        if self.is_word("if") {
+            let line = self.line();
+            let (kind, needs_end) = self.parse_if()?;
+            return Ok((vec![Stmt { kind, line }], multi_line || needs_end));
+        }
+        if self.at_line_end() {
+            return Ok((self.parse_block(|p| p.is_word("end"))?, true));
+        }
+        let stmt = self.parse_statement()?;
+        if !multi_line {
+            return Ok((vec![stmt], false));
+        }
+        self.expect_line_end()?;
+        let mut block = vec![stmt];
+        block.extend(self.parse_block(|p| p.is_word("end"))?);
+        Ok((block, true))
+    }
+
+    fn parse_repeat(&mut self) -> Result<StmtKind, String> {
+        self.expect_word("repeat")?;
+        let kind = if self.eat_word("while") {
+            let condition = self.parse_expr()?;
+            self.expect_line_end()?;
+            StmtKind::RepeatWhile { condition, body: self.parse_block(|p| p.is_word("end"))? }
+        } else if self.eat_word("with") {
+            let var = self.ident("loop variable")?;
+            if self.eat(&Token::Eq) {
+                let start = self.parse_expr()?;
+                let up = !self.eat_word("down");
+                self.expect_word("to")?;
+                let end = self.parse_expr()?;
+                self.expect_line_end()?;
+                let body = self.parse_block(|p| p.is_word("end"))?;
+                StmtKind::RepeatWithTo { var, start, end, up, body }
+            } else if self.eat_word("in") {
+                let list = self.parse_expr()?;
+                self.expect_line_end()?;
+                StmtKind::RepeatWithIn { var, list, body: self.parse_block(|p| p.is_word("end"))? }
+            } else {
+                return self.error("expected '=' or 'in' in repeat with");
+            }
+        } else {
+            return self.error("expected 'while' or 'with' after repeat");
+        };
+        self.expect_word("end")?;
+        self.expect_word("repeat")?;
+        Ok(kind)
+    }
+
+    fn parse_case(&mut self) -> Result<StmtKind, String> {
+        self.expect_word("case")?;
+        let value = self.parse_expr()?;
+        self.expect_word("of")?;
+        self.expect_line_end()?;
+
+        let mut labels = vec![];
+        let mut otherwise = None;
+        loop {
+            self.skip_newlines();
+            if self.eat_word("end") {
+                self.expect_word("case")?;
+                break;
+            }
+            if *self.peek() == Token::Eof {
+                return self.error("expected 'end case'");
+            }
+            if otherwise.is_none() && self.eat_word("otherwise") {
+                self.eat(&Token::Colon);
+                otherwise = Some(self.parse_case_body(|p| p.is_word("end"))?);
+                continue;
+            }
+            if otherwise.is_some() || !self.line_is_case_label() {
+                return self.error("expected case label");
+            }
+            let mut values = vec![self.parse_expr()?];
+            while self.eat(&Token::Comma) {
+                values.push(self.parse_expr()?);
+            }
+            self.expect(Token::Colon, "':'")?;
+            let body = self.parse_case_body(|p| {
+                p.is_word("end") || p.is_word("otherwise") || p.line_is_case_label()
+            })?;
+            labels.push(CaseLabel { values, body });
+        }
+        Ok(StmtKind::Case { value, labels, otherwise })
+    }
+
+    fn parse_case_body(&mut self, stop: fn(&Parser) -> bool) -> Result<Vec<Stmt>, String> {
+        let mut body = vec![];
+        if !self.at_line_end() {
+            body.push(self.parse_statement()?);
+        }
+        self.expect_line_end()?;
+        body.extend(self.parse_block(stop)?);
+        Ok(body)
+    }
+
+    fn parse_tell(&mut self) -> Result<StmtKind, String> {
+        self.expect_word("tell")?;
+        let target = self.parse_expr()?;
+        if self.eat_word("to") {
+            return Ok(StmtKind::Tell { target, body: vec![self.parse_statement()?] });
+        }
+        self.expect_line_end()?;
+        let body = self.parse_block(|p| p.is_word("end"))?;
+        self.expect_word("end")?;
+        self.expect_word("tell")?;
+        Ok(StmtKind::Tell { target, body })
+    }
+
+    fn parse_put(&mut self) -> Result<StmtKind, String> {
+        let value = self.parse_expr()?;
+        let put_type = if self.eat_word("into") {
+            PutType::Into
+        } else if self.eat_word("after") {
+            PutType::After
+        } else if self.eat_word("before") {
+            PutType::Before
+        } else {
+            return Ok(StmtKind::Put { put_type: PutType::Into, value, target: None });
+        };
+        let target = self.parse_expr()?;
+        Ok(StmtKind::Put { put_type, value, target: Some(target) })
+    }
+
+    fn parse_go(&mut self) -> Result<StmtKind, String> {
+        self.expect_word("go")?;
+        self.eat_word("to");
+        let marker = |offset: i32| Expr::Call { name: "marker".to_string(), args: vec![Expr::Int(offset)] };
+        let args = if self.eat_word("loop") {
+            vec![marker(0)]
+        } else if self.eat_word("next") {
+            vec![marker(1)]
+        } else if self.eat_word("previous") {
+            vec![marker(-1)]
+        } else if self.eat_word("movie") {
+            vec![Expr::Int(1), self.parse_expr()?]
+        } else {
+            self.eat_word("frame");
+            let frame = self.parse_expr()?;
+            if self.eat_word("of") {
+                self.expect_word("movie")?;
+                vec![frame, self.parse_expr()?]
+            } else {
+                vec![frame]
+            }
+        };
+        Ok(StmtKind::Call(Expr::Call { name: "go".to_string(), args }))
+    }
+
+    fn parse_command_or_assignment(&mut self) -> Result<StmtKind, String> {
+        if let Token::Ident(name) = self.peek().clone() {
+            let is_special = name.starts_with('_')
+                || chunk_type_from_name(&name).is_some()
+                || ["the", "field", "member", "cast", "sprite", "castlib", "script", "window", "xtra", "not"]
+                    .iter()
+                    .any(|w| name.eq_ignore_ascii_case(w));
+            let next = self.peek_at(1);
+            if !is_special && (*next == Token::Newline || *next == Token::Eof || self.is_word_at(1, "else")) {
+                self.pos += 1;
+                return Ok(StmtKind::Call(Expr::Call { name, args: vec![] }));
+            }
+            let starts_args = *next == Token::Minus || (*next != Token::LParen && self.starts_argument_at(1));
+            if !is_special && starts_args {
+                self.pos += 1;
+                let mut args = vec![self.parse_expr()?];
+                while self.eat(&Token::Comma) {
+                    args.push(self.parse_expr()?);
+                }
+                return Ok(StmtKind::Call(Expr::Call { name, args }));
+            }
+        }
+
+        let target = self.parse_postfix()?;
+        if self.eat(&Token::Eq) {
+            return Ok(StmtKind::Assign { target, value: self.parse_expr()? });
+        }
+        match target {
+            Expr::Call { .. } | Expr::ObjCall { .. } => Ok(StmtKind::Call(target)),
+            Expr::ObjProp { obj, prop } => Ok(StmtKind::Call(Expr::ObjCall { obj, method: prop, args: vec![] })),
+            _ => self.error("expected statement"),
+        }
+    }
+
+    pub fn parse_expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while self.eat_word("or") {
+            let right = self.parse_and()?;
+            left = Expr::Binary { op: OpCode::Or, left: Box::new(left), right: Box::new(right) };
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_not()?;
+        while self.eat_word("and") {
+            let right = self.parse_not()?;
+            left = Expr::Binary { op: OpCode::And, left: Box::new(left), right: Box::new(right) };
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.eat_word("not") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_contains()
+    }
+
+    fn parse_contains(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_comparison()?;
+        loop {
+            let op = if self.eat_word("contains") {
+                OpCode::ContainsStr
+            } else if self.eat_word("starts") {
+                OpCode::Contains0Str
+            } else {
+                return Ok(left);
+            };
+            let right = self.parse_comparison()?;
+            left = Expr::Binary { op, left: Box::new(left), right: Box::new(right) };
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_concat()?;
+        loop {
+            let op = match self.peek() {
+                Token::Eq => OpCode::Eq,
+                Token::NtEq => OpCode::NtEq,
+                Token::Lt => OpCode::Lt,
+                Token::LtEq => OpCode::LtEq,
+                Token::Gt => OpCode::Gt,
+                Token::GtEq => OpCode::GtEq,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.parse_concat()?;
+            left = Expr::Binary { op, left: Box::new(left), right: Box::new(right) };
+        }
+    }
+
+    fn parse_concat(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_additive()?;
+        loop {
+            let op = match self.peek() {
+                Token::Amp => OpCode::JoinStr,
+                Token::AmpAmp => OpCode::JoinPadStr,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.parse_additive()?;
+            left = Expr::Binary { op, left: Box::new(left), right: Box::new(right) };
+        }
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Token::Plus => OpCode::Add,
+                Token::Minus => OpCode::Sub,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.parse_multiplicative()?;
+            left = Expr::Binary { op, left: Box::new(left), right: Box::new(right) };
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                Token::Star => OpCode::Mul,
+                Token::Slash => OpCode::Div,
+                _ if self.is_word("mod") => OpCode::Mod,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            left = Expr::Binary { op, left: Box::new(left), right: Box::new(right) };
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.eat(&Token::Minus) {
+            // fold negative literals the way the Director compiler does
+            return Ok(match self.peek().clone() {
+                Token::Int(value) => {
+                    self.pos += 1;
+                    Expr::Int(-value)
+                }
+                Token::Float(value) => {
+                    self.pos += 1;
+                    Expr::Float(-value)
+                }
+                _ => Expr::Neg(Box::new(self.parse_unary()?)),
+            });
+        }
+        if self.eat(&Token::Plus) {
+            return self.parse_unary();
+        }
+        if self.eat_word("not") {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat(&Token::Dot) {
+                let name = self.ident("property or method name")?;
+                expr = if *self.peek() == Token::LParen {
+                    let args = self.parse_call_args()?;
+                    Expr::ObjCall { obj: Box::new(expr), method: name, args }
+                } else {
+                    Expr::ObjProp { obj: Box::new(expr), prop: name }
+                };
+            } else if self.eat(&Token::LBracket) {
+                let index = self.parse_expr()?;
+                self.expect(Token::RBracket, "']'")?;
+                expr = Expr::Index { obj: Box::new(expr), index: Box::new(index) };
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_call_args(&mut self) -> Result<Vec<Expr>, String> {
+        self.expect(Token::LParen, "'('")?;
+        let mut args = vec![];
+        if self.eat(&Token::RParen) {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.parse_expr()?);
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(Token::RParen, "')'")?;
+        Ok(args)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        match self.advance() {
+            Token::Int(value) => Ok(Expr::Int(value)),
+            Token::Float(value) => Ok(Expr::Float(value)),
+            Token::Str(value) => Ok(Expr::Str(value)),
+            Token::Symbol(name) => Ok(Expr::Symbol(name)),
+            Token::LParen => {
+                let expr = self.parse_expr()?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(expr)
+            }
+            Token::LBracket => self.parse_list(),
+            Token::Ident(name) => self.parse_ident_expr(name),
+            token => {
+                self.pos -= 1;
+                self.error(&format!("unexpected {:?}", token))
+            }
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<Expr, String> {
+        if self.eat(&Token::RBracket) {
+            return Ok(Expr::List(vec![]));
+        }
+        if *self.peek() == Token::Colon && *self.peek_at(1) == Token::RBracket {
+            self.pos += 2;
+            return Ok(Expr::PropList(vec![]));
+        }
+        let first = self.parse_expr()?;
+        if self.eat(&Token::Colon) {
+            let mut pairs = vec![(first, self.parse_expr()?)];
+            while self.eat(&Token::Comma) {
+                let key = self.parse_expr()?;
+                self.expect(Token::Colon, "':'")?;
+                pairs.push((key, self.parse_expr()?));
+            }
+            self.expect(Token::RBracket, "']'")?;
+            return Ok(Expr::PropList(pairs));
+        }
+        let mut items = vec![first];
+        while self.eat(&Token::Comma) {
+            items.push(self.parse_expr()?);
+        }
+        self.expect(Token::RBracket, "']'")?;
+        Ok(Expr::List(items))
+    }
+
+    fn parse_ident_expr(&mut self, name: String) -> Result<Expr, String> {
+        let lower = name.to_ascii_lowercase();
+        match lower.as_str() {
+            "true" => return Ok(Expr::Int(1)),
+            "false" => return Ok(Expr::Int(0)),
+            "void" => return Ok(Expr::Void),
+            "empty" => return Ok(Expr::Str(String::new())),
+            "return" => return Ok(Expr::Str("\r".to_string())),
+            "quote" => return Ok(Expr::Str("\"".to_string())),
+            "tab" => return Ok(Expr::Str("\t".to_string())),
+            "space" => return Ok(Expr::Str(" ".to_string())),
+            "backspace" => return Ok(Expr::Str("\u{8}".to_string())),
+            "enter" => return Ok(Expr::Str("\u{3}".to_string())),
+            "pi" => return Ok(Expr::Float(PI)),
+            "the" => return self.parse_the(),
+            _ => {}
+        }
+        if TOP_LEVEL_PROPS.contains(&lower.as_str()) {
+            return Ok(Expr::TopLevel(lower));
+        }
+
+        if let Some(chunk_type) = chunk_type_from_name(&lower).filter(|_| !lower.ends_with('s')) {
+            let is_chunk = if *self.peek() == Token::LParen {
+                let after = self.skip_group_at(0);
+                self.is_word_at(after, "of") || self.is_word_at(after, "to")
+            } else {
+                self.starts_argument_at(0)
+            };
+            if is_chunk {
+                return self.parse_chunk(chunk_type);
+            }
+        }
+
+        let is_builtin = ["field", "member", "cast", "sprite", "castlib", "script", "window", "xtra"]
+            .contains(&lower.as_str());
+        if is_builtin && *self.peek() != Token::LParen && self.starts_argument_at(0) {
+            let arg = self.parse_postfix()?;
+            match lower.as_str() {
+                "sprite" if self.eat_word("intersects") => {
+                    let other = self.parse_postfix()?;
+                    return Ok(Expr::SpriteIntersects(Box::new(arg), Box::new(other)));
+                }
+                "sprite" if self.eat_word("within") => {
+                    let other = self.parse_postfix()?;
+                    return Ok(Expr::SpriteWithin(Box::new(arg), Box::new(other)));
+                }
+                _ => {}
+            }
+            let cast_lib = if ["field", "member", "cast"].contains(&lower.as_str())
+                && self.is_word("of")
+                && self.is_word_at(1, "castlib")
+            {
+                self.pos += 2;
+                Some(self.parse_postfix()?)
+            } else {
+                None
+            };
+            if lower == "field" {
+                return Ok(Expr::Field { id: Box::new(arg), cast_lib: cast_lib.map(Box::new) });
+            }
+            let name = match lower.as_str() {
+                "cast" => "member".to_string(),
+                "castlib" => "castLib".to_string(),
+                _ => lower.clone(),
+            };
+            let mut args = vec![arg];
+            args.extend(cast_lib);
+            return Ok(Expr::Call { name, args });
+        }
+
+        if *self.peek() == Token::LParen {
+            let args = self.parse_call_args()?;
+            if lower == "field" {
+                let mut args = args.into_iter();
+                return match (args.next(), args.next(), args.next()) {
+                    (Some(id), cast_lib, None) => Ok(Expr::Field { id: Box::new(id), cast_lib: cast_lib.map(Box::new) }),
+                    _ => self.error("field takes one or two arguments"),
+                };
+            }
+            return Ok(Expr::Call { name, args });
+        }
+        Ok(Expr::Var(name))
+    }
+
+    fn parse_chunk(&mut self, chunk_type: ChunkExprType) -> Result<Expr, String> {
+        let first = self.parse_expr()?;
+        let last = if self.eat_word("to") { Some(Box::new(self.parse_expr()?)) } else { None };
+        self.expect_word("of")?;
+        let string = self.parse_postfix()?;
+        Ok(Expr::Chunk { chunk_type, first: Box::new(first), last, string: Box::new(string) })
+    }
+
+    fn parse_the(&mut self) -> Result<Expr, String> {
+        let prop = self.ident("property name after 'the'")?;
+        if prop.eq_ignore_ascii_case("number")
+            && self.is_word("of")
+            && let Token::Ident(plural) = self.peek_at(1).clone()
+            && let Some(chunk_type) = chunk_type_from_name(&plural).filter(|_| plural.ends_with('s'))
+        {
+            self.pos += 2;
+            if !self.eat_word("in") {
+                self.expect_word("of")?;
+            }
+            let string = self.parse_postfix()?;
+            return Ok(Expr::ChunkCount { chunk_type, string: Box::new(string) });
+        }
+        if prop.eq_ignore_ascii_case("last")
+            && let Token::Ident(chunk) = self.peek().clone()
+            && let Some(chunk_type) = chunk_type_from_name(&chunk).filter(|_| !chunk.ends_with('s'))
+            && (self.is_word_at(1, "of") || self.is_word_at(1, "in"))
+        {
+            self.pos += 2;
+            let string = self.parse_postfix()?;
+            return Ok(Expr::LastChunk { chunk_type, string: Box::new(string) });
+        }
+        if self.eat_word("of") {
+            let obj = self.parse_postfix()?;
+            return Ok(Expr::TheOf { prop, obj: Box::new(obj) });
+        }
+        Ok(Expr::The(prop))
+    }
+}
@@ -20,6 +20,7 @@ pub enum DatumType {
     CastLibRef,
     CastMemberRef,
     StageRef,
+    WindowRef,
     SpriteRef,
     StringChunk,
     String,
@@ -195,6 +196,8 @@ pub enum Datum {
     Symbol(String),
     CastLib(u32),
     Stage,
+    /// A movie-in-a-window, by window name.
+    Window(String),
     ScriptRef(CastMemberRef),
     ScriptInstanceRef(ScriptInstanceRef),
     CastMember(CastMemberRef),
@@ -254,6 +257,7 @@ impl DatumType {
             DatumType::Symbol => "symbol",
             DatumType::CastLibRef => "cast_lib",
             DatumType::StageRef => "stage",
+            DatumType::WindowRef => "window",
             DatumType::ScriptRef => "script_ref",
             DatumType::ScriptInstanceRef => "script_instance",
             DatumType::CastMemberRef => "cast_member",
@@ -307,6 +311,7 @@ impl Datum {
             Datum::Symbol(_) => DatumType::Symbol,
             Datum::CastLib(_) => DatumType::CastLibRef,
             Datum::Stage => DatumType::StageRef,
+            Datum::Window(_) => DatumType::WindowRef,
             Datum::ScriptRef(_) => DatumType::ScriptRef,
             Datum::ScriptInstanceRef(_) => DatumType::ScriptInstanceRef,
             Datum::CastMember(_) => DatumType::CastMemberRef,
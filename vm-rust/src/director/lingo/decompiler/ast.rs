@@ -465,8 +465,9 @@ impl AstNode {
             }
             AstNode::ChunkExpr { chunk_type, first, last, string } => {
                 let chunk_name = chunk_type.name();
-                // Check if first == last for single chunk reference
+                // Single chunk reference when last is 0 or equal to first
                 let is_single = match (first.as_ref(), last.as_ref()) {
+                    (_, AstNode::Literal(d2)) if d2.datum_type == DatumType::Int && d2.int_value == 0 => true,
                     (AstNode::Literal(d1), AstNode::Literal(d2)) => {
                         d1.datum_type == DatumType::Int && d2.datum_type == DatumType::Int && d1.int_value == d2.int_value
                     }
@@ -509,6 +510,10 @@ impl AstNode {
                 second.write_script_with_depth(code, dot, sum, depth + 1);
             }
             AstNode::Member { member_type, member_id, cast_id } => {
+                // A literal castLib 0 means "search every cast"
+                let cast_id = cast_id.as_ref().filter(|cast| {
+                    !matches!(cast.as_ref(), AstNode::Literal(d) if d.datum_type == DatumType::Int && d.int_value == 0)
+                });
                 if dot {
                     code.write(member_type);
                     code.write("(");
@@ -533,8 +533,11 @@ impl<'a> DecompilerState<'a> {
                                     // We need to find the case statement in the current block's children
                                     let children = self.current_block.borrow().children.clone();
                                     for child in children.iter().rev() {
-                                        if let AstNode::Case { otherwise, potential_otherwise_pos, .. } = child.node.as_ref() {
+                                        if let AstNode::Case { otherwise, potential_otherwise_pos, end_pos, .. } = child.node.as_ref() {
                                             let ow = Rc::new(RefCell::new(OtherwiseNode::new()));
+                                            if end_pos.get() >= 0 {
+                                                ow.borrow().block.borrow_mut().end_pos = end_pos.get() as u32;
+                                            }
                                             otherwise.borrow_mut().replace(ow.clone());
                                             // Tag the otherwise position
                                             let ow_pos = potential_otherwise_pos.get();
@@ -1028,10 +1031,9 @@ impl<'a> DecompilerState<'a> {
                         }
                         BlockContext::CaseLabel => {
                             // Case statement jmp - find ancestor case to set end position
-                            // The case statement is in the grandparent block (block_stack[-2])
-                            if self.block_stack.len() >= 2 {
-                                let grandparent = &self.block_stack[self.block_stack.len() - 2];
-                                let children = grandparent.borrow().children.clone();
+                            // The case statement is in the label block's parent
+                            if let Some(parent) = self.block_stack.last() {
+                                let children = parent.borrow().children.clone();
                                 for child in children.iter().rev() {
                                     if let AstNode::Case { end_pos, potential_otherwise_pos, .. } = child.node.as_ref() {
                                         potential_otherwise_pos.set(bytecode.pos as i32);
@@ -1064,6 +1066,7 @@ impl<'a> DecompilerState<'a> {
                 self.bytecode_tags[target_index].tag = BytecodeTag::EndCase;
                 // Add otherwise
                 let ow = Rc::new(RefCell::new(OtherwiseNode::new()));
+                ow.borrow().block.borrow_mut().end_pos = target_pos as u32;
                 if let AstNode::Case { otherwise, .. } = case_stmt.as_ref() {
                     otherwise.borrow_mut().replace(ow.clone());
                 }
@@ -1792,9 +1795,17 @@ impl<'a> DecompilerState<'a> {
                 // Case labels
                 let mut current_label = first_label.borrow().clone();
                 while let Some(label) = current_label {
-                    let label_ref = label.borrow();
-                    Self::collect_case_label_lines(&label_ref, dot, indent + 1, lines, bytecode_to_line);
-                    current_label = label_ref.next_label.clone();
+                    Self::collect_case_label_lines(&label.borrow(), dot, indent + 1, lines, bytecode_to_line);
+                    // The body and the next label hang off the last value of an "or" chain
+                    let mut last = label;
+                    loop {
+                        let next_or = last.borrow().next_or.clone();
+                        match next_or {
+                            Some(next_or) => last = next_or,
+                            None => break,
+                        }
+                    }
+                    current_label = last.borrow().next_label.clone();
                 }
 
                 // Otherwise
@@ -1822,10 +1833,12 @@ impl<'a> DecompilerState<'a> {
         label.value.write_script(&mut code, dot, false);
 
         // Chained "or" values
+        let mut block = label.block.clone();
         let mut current_or = label.next_or.clone();
         while let Some(or_label) = current_or {
             code.write(", ");
             or_label.borrow().value.write_script(&mut code, dot, false);
+            block = or_label.borrow().block.clone();
             current_or = or_label.borrow().next_or.clone();
         }
 
@@ -1833,7 +1846,7 @@ impl<'a> DecompilerState<'a> {
         Self::push_line(code.into_string(), vec![], indent, lines, bytecode_to_line);
 
         // Case label block contents
-        Self::collect_block_lines(&label.block.borrow(), dot, indent + 1, lines, bytecode_to_line);
+        Self::collect_block_lines(&block.borrow(), dot, indent + 1, lines, bytecode_to_line);
     }
 }
 
@@ -1925,3 +1938,37 @@ pub fn decompile_handler(
     state.parse();
     state.generate_output()
 }
+
+/// Decompile a whole script back to source text: a `property` declaration
+/// followed by each handler. Used to restore the text of protected movies.
+pub fn decompile_script(
+    chunk: &ScriptChunk,
+    lctx: &ScriptContext,
+    version: u16,
+    multiplier: u32,
+) -> String {
+    let name = |id: &u16| lctx.names.get(*id as usize).cloned().unwrap_or_default();
+    let mut out = String::new();
+    if !chunk.property_name_ids.is_empty() {
+        let properties: Vec<String> = chunk.property_name_ids.iter().map(name).collect();
+        out.push_str(&format!("property {}\n", properties.join(", ")));
+    }
+    for handler in &chunk.handlers {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let decompiled = decompile_handler(handler, chunk, lctx, version, multiplier);
+        out.push_str(&format!("on {}", decompiled.name));
+        if !decompiled.arguments.is_empty() {
+            out.push_str(&format!(" {}", decompiled.arguments.join(", ")));
+        }
+        out.push('\n');
+        for line in &decompiled.lines {
+            out.push_str(&"  ".repeat(line.indent as usize + 1));
+            out.push_str(&line.text);
+            out.push('\n');
+        }
+        out.push_str("end\n");
+    }
+    out
+}
@@ -8,5 +8,5 @@ pub mod handler;
 pub mod code_writer;
 pub mod tokenizer;
 
-pub use handler::{decompile_handler, DecompiledHandler, DecompiledLine};
+pub use handler::{decompile_handler, decompile_script, DecompiledHandler, DecompiledLine};
 pub use tokenizer::{tokenize_line, Span, TokenType};
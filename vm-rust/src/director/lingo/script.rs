@@ -1,8 +1,11 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use crate::director::chunks::script::ScriptChunk;
 #[derive(Clone)]
 pub struct ScriptContext {
-    pub names: Vec<String>,
+    /// Shared with running handlers, which resolve names through their own
+    /// reference while compiling new scripts may append to the table.
+    pub names: Rc<Vec<String>>,
     pub scripts: HashMap<u32, ScriptChunk>,
 }
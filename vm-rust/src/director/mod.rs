@@ -7,4 +7,5 @@ pub mod lingo;
 pub mod rifx;
 pub mod static_datum;
 pub mod utils;
+pub mod writer;
 pub mod media;
@@ -0,0 +1,492 @@
+use std::collections::{BTreeMap, HashMap};
+
+use binary_reader::{BinaryReader, Endian};
+use binary_rw::{BinaryError, BinaryWriter, MemoryStream};
+
+use super::{
+    chunks::{
+        cast::CastChunk,
+        cast_member::CastMemberChunk,
+        config::ConfigChunk,
+        key_table::{KeyTableChunk, KeyTableEntry},
+        script::ScriptChunk,
+        script_names::ScriptNamesChunk,
+    },
+    enums::MemberType,
+    file::{get_variable_multiplier, DirectorFile},
+    lingo::decompiler::decompile_script,
+    utils::{fourcc_to_string, human_version, FOURCC},
+};
+use crate::io::encoding::encode_win1252;
+
+/// A chunk payload as it will appear in the written file, without its
+/// fourcc/length header.
+pub struct WriterChunk {
+    pub fourcc: u32,
+    pub data: Vec<u8>,
+}
+
+/// Builds an uncompressed RIFX container (`.dir` / `.cst`). Resource ids 0-2
+/// belong to the RIFX header, `imap` and `mmap`, which are regenerated on
+/// every write along with the `KEY*` table.
+pub struct RIFXWriter {
+    endian: Endian,
+    codec: u32,
+    director_version: u16,
+    key_table_id: u32,
+    pub key_table: KeyTableChunk,
+    chunks: BTreeMap<u32, WriterChunk>,
+    /// Ids moved out of the reserved 0-2 range, keyed by their id in the source file
+    remapped_ids: HashMap<u32, u32>,
+}
+
+const FIRST_CHUNK_ID: u32 = 3;
+
+fn container_codec(codec: u32) -> u32 {
+    if codec == FOURCC("FGDC") || codec == FOURCC("MC95") {
+        FOURCC("MC95")
+    } else {
+        FOURCC("MV93")
+    }
+}
+
+fn rw_endian(endian: Endian) -> binary_rw::Endian {
+    match endian {
+        Endian::Little => binary_rw::Endian::Little,
+        _ => binary_rw::Endian::Big,
+    }
+}
+
+/// Run `write` against a fresh in-memory stream and return the bytes.
+pub fn write_to_vec<F>(endian: binary_rw::Endian, write: F) -> Result<Vec<u8>, String>
+where
+    F: FnOnce(&mut BinaryWriter) -> Result<usize, BinaryError>,
+{
+    let mut stream = MemoryStream::new();
+    {
+        let mut writer = BinaryWriter::new(&mut stream, endian);
+        write(&mut writer).map_err(|e| e.to_string())?;
+    }
+    Ok(stream.into())
+}
+
+impl RIFXWriter {
+    /// An empty container. `director_version` is the raw `DRCF` value.
+    pub fn new(endian: Endian, codec: u32, director_version: u16) -> RIFXWriter {
+        RIFXWriter {
+            endian,
+            codec: container_codec(codec),
+            director_version,
+            key_table_id: FIRST_CHUNK_ID,
+            key_table: KeyTableChunk {
+                entry_size: 12,
+                entry_size2: 12,
+                entry_count: 0,
+                used_count: 0,
+                entries: vec![],
+            },
+            chunks: BTreeMap::new(),
+            remapped_ids: HashMap::new(),
+        }
+    }
+
+    /// Collect every chunk of a loaded file. Afterburned movies and casts are
+    /// converted: their chunks are stored uncompressed and the `ILS ` slot is
+    /// given back to `mmap`.
+    pub fn from_director_file(file: &DirectorFile) -> Result<RIFXWriter, String> {
+        let container = &file.chunk_container;
+        let key_table = file.key_table.clone().ok_or("File has no key table")?;
+        let mut writer = RIFXWriter::new(file.endian, file.codec, file.config.director_version);
+
+        let skipped = ["RIFX", "XFIR", "imap", "mmap", "ILS "].map(FOURCC);
+        let mut key_table_id = None;
+        let mut ids: Vec<&u32> = container.chunk_info.keys().collect();
+        ids.sort();
+        for id in ids {
+            let info = &container.chunk_info[id];
+            if skipped.contains(&info.fourcc) {
+                continue;
+            }
+            if info.fourcc == FOURCC("KEY*") {
+                key_table_id = Some(*id);
+                continue;
+            }
+            let Some(data) = container
+                .raw_chunk_views
+                .get(id)
+                .or_else(|| container.cached_chunk_views.get(id))
+            else {
+                continue;
+            };
+            // Reclaimed slots still referenced by the key table keep the
+            // fourcc the key table knows them by.
+            let fourcc = if info.fourcc == FOURCC("free") || info.fourcc == FOURCC("junk") {
+                match key_table.entries.iter().find(|entry| entry.section_id == *id) {
+                    Some(entry) => entry.fourcc,
+                    None => continue,
+                }
+            } else {
+                info.fourcc
+            };
+            writer.chunks.insert(*id, WriterChunk { fourcc, data: data.clone() });
+        }
+
+        writer.key_table = key_table;
+        writer.key_table.entries.retain(|entry| writer.chunks.contains_key(&entry.section_id));
+
+        let reserved: Vec<u32> = writer.chunks.keys().copied().filter(|id| *id < FIRST_CHUNK_ID).collect();
+        for id in reserved {
+            let new_id = writer.next_free_id();
+            writer.remap_id(id, new_id)?;
+        }
+        writer.key_table_id = match key_table_id {
+            Some(id) if id >= FIRST_CHUNK_ID && !writer.chunks.contains_key(&id) => id,
+            _ => writer.next_free_id(),
+        };
+
+        writer.rebuild_chunks(file)?;
+        Ok(writer)
+    }
+
+    /// Re-serialize the chunks the reader fully understands: `CAS*`, `Lnam`
+    /// and `VWSC`. Every other chunk, member data (`CASt`) and media
+    /// included, keeps the bytes it was loaded with, so runtime changes to
+    /// it are lost unless the caller replaces it with
+    /// [`set_chunk`](Self::set_chunk), as `saveMovie` does for scripts.
+    fn rebuild_chunks(&mut self, file: &DirectorFile) -> Result<(), String> {
+        let dir_version = human_version(self.director_version);
+        for chunk in self.chunks.values_mut() {
+            let mut reader = BinaryReader::from_vec(&chunk.data);
+            reader.set_endian(self.endian);
+            match fourcc_to_string(chunk.fourcc).as_str() {
+                "CAS*" => {
+                    let cast = CastChunk::from_reader(&mut reader, dir_version)?;
+                    chunk.data = write_to_vec(binary_rw::Endian::Big, |w| cast.write(w))?;
+                }
+                "Lnam" => {
+                    let names = ScriptNamesChunk::from_reader(&mut reader, dir_version)?;
+                    chunk.data = write_to_vec(binary_rw::Endian::Big, |w| names.write(w))?;
+                }
+                "VWSC" => {
+                    if let Some(score) = file.score.as_ref().filter(|score| !score.entries.is_empty()) {
+                        chunk.data = write_to_vec(binary_rw::Endian::Big, |w| score.write(w))?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn next_free_id(&self) -> u32 {
+        let used = self.chunks.keys().copied().chain([self.key_table_id]);
+        used.max().map_or(FIRST_CHUNK_ID, |id| id + 1).max(FIRST_CHUNK_ID)
+    }
+
+    /// Move chunk `from` to id `to`, updating the key table and the chunks
+    /// that refer to resources by id (`CAS*` member lists and `Lctx` maps).
+    fn remap_id(&mut self, from: u32, to: u32) -> Result<(), String> {
+        let chunk = self.chunks.remove(&from).ok_or(format!("No chunk {}", from))?;
+        self.chunks.insert(to, chunk);
+        self.remapped_ids.insert(from, to);
+        for entry in self.key_table.entries.iter_mut() {
+            if entry.section_id == from {
+                entry.section_id = to;
+            }
+            if entry.cast_id == from {
+                entry.cast_id = to;
+            }
+        }
+        for chunk in self.chunks.values_mut() {
+            match fourcc_to_string(chunk.fourcc).as_str() {
+                "CAS*" => {
+                    for slot in chunk.data.chunks_exact_mut(4) {
+                        if u32::from_be_bytes(slot.try_into().unwrap()) == from {
+                            slot.copy_from_slice(&to.to_be_bytes());
+                        }
+                    }
+                }
+                "Lctx" | "LctX" => {
+                    let data = &mut chunk.data;
+                    if data.len() < 36 {
+                        continue;
+                    }
+                    let entry_count = u32::from_be_bytes(data[8..12].try_into().unwrap()) as usize;
+                    let entries_offset = u16::from_be_bytes(data[16..18].try_into().unwrap()) as usize;
+                    if u32::from_be_bytes(data[32..36].try_into().unwrap()) == from {
+                        data[32..36].copy_from_slice(&to.to_be_bytes());
+                    }
+                    for i in 0..entry_count {
+                        let pos = entries_offset + i * 12 + 4;
+                        if pos + 4 <= data.len() && i32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) == from as i32 {
+                            data[pos..pos + 4].copy_from_slice(&to.to_be_bytes());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The id a resource of the source file has in this container.
+    pub fn section_id(&self, source_id: u32) -> u32 {
+        self.remapped_ids.get(&source_id).copied().unwrap_or(source_id)
+    }
+
+    pub fn chunk(&self, id: u32) -> Option<&WriterChunk> {
+        self.chunks.get(&id)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = (&u32, &WriterChunk)> {
+        self.chunks.iter()
+    }
+
+    pub fn set_chunk(&mut self, id: u32, fourcc: u32, data: Vec<u8>) {
+        self.chunks.insert(id, WriterChunk { fourcc, data });
+    }
+
+    /// Append a chunk and return its id. When `owner` is given the chunk is
+    /// registered in the key table as belonging to that resource (or cast id).
+    pub fn add_chunk(&mut self, fourcc: u32, data: Vec<u8>, owner: Option<u32>) -> u32 {
+        let id = self.next_free_id();
+        self.chunks.insert(id, WriterChunk { fourcc, data });
+        if let Some(owner) = owner {
+            self.key_table.entries.push(KeyTableEntry { section_id: id, cast_id: owner, fourcc });
+        }
+        id
+    }
+
+    fn config(&self) -> Result<(u32, ConfigChunk), String> {
+        let (id, chunk) = self
+            .chunks
+            .iter()
+            .find(|(_, chunk)| chunk.fourcc == FOURCC("DRCF") || chunk.fourcc == FOURCC("VWCF"))
+            .ok_or("No config chunk!")?;
+        let mut reader = BinaryReader::from_vec(&chunk.data);
+        let config = ConfigChunk::from_reader(&mut reader, 0, self.endian)?;
+        Ok((*id, config))
+    }
+
+    /// Clear the protection flag so the result opens in the authoring tool.
+    pub fn unprotect(&mut self) -> Result<(), String> {
+        let (id, mut config) = self.config()?;
+        if !config.is_protected() {
+            return Ok(());
+        }
+        config.unprotect(self.endian);
+        self.chunks.get_mut(&id).unwrap().data = write_to_vec(binary_rw::Endian::Big, |w| config.write(w))?;
+        Ok(())
+    }
+
+    /// Replace the script text stored in the info of the `CASt` chunk `section_id`.
+    pub fn set_member_script_text(&mut self, section_id: u32, text: &str) -> Result<(), String> {
+        let dir_version = human_version(self.director_version);
+        let chunk = self
+            .chunks
+            .get_mut(&section_id)
+            .filter(|chunk| chunk.fourcc == FOURCC("CASt"))
+            .ok_or(format!("No cast member chunk {}", section_id))?;
+        chunk.data = with_script_text(&chunk.data, dir_version, &encode_win1252(text))?;
+        Ok(())
+    }
+
+    /// Decompile the scripts of script members whose source text was
+    /// stripped, as it is in protected movies, and store it back in their `CASt` info.
+    pub fn restore_script_text(&mut self, file: &DirectorFile) -> Result<(), String> {
+        let dir_version = human_version(self.director_version);
+        let mut restored = vec![];
+        for (section_id, chunk) in &self.chunks {
+            if chunk.fourcc != FOURCC("CASt") {
+                continue;
+            }
+            let mut reader = BinaryReader::from_vec(&chunk.data);
+            let member = CastMemberChunk::from_reader(&mut reader, dir_version)?;
+            let Some(info) = member.member_info.as_ref() else {
+                continue;
+            };
+            if !matches!(member.member_type, MemberType::Script) || info.header.script_id == 0 || !info.script_src_text.is_empty() {
+                continue;
+            }
+            let Some(cast) = file
+                .casts
+                .iter()
+                .find(|cast| cast.section_to_member.keys().any(|id| self.section_id(*id) == *section_id))
+            else {
+                continue;
+            };
+            let Some(lctx) = cast.lctx.as_ref() else {
+                continue;
+            };
+            let Some(script) = lctx.scripts.get(&info.header.script_id) else {
+                continue;
+            };
+            let multiplier = get_variable_multiplier(cast.capital_x, cast.dir_version);
+            let text = decompile_script(script, lctx, cast.dir_version, multiplier).replace('\n', "\r");
+            restored.push((*section_id, text));
+        }
+        for (section_id, text) in restored {
+            self.set_member_script_text(section_id, &text)?;
+        }
+        Ok(())
+    }
+
+    /// Serialize a compiled script into the `Lscr` chunk `section_id`.
+    pub fn set_script(&mut self, section_id: u32, script: &ScriptChunk, capital_x: bool) -> Result<(), String> {
+        let dir_version = human_version(self.director_version);
+        let data = write_to_vec(binary_rw::Endian::Big, |w| script.write(w, dir_version, capital_x))?;
+        self.set_chunk(section_id, FOURCC("Lscr"), data);
+        Ok(())
+    }
+
+    pub fn write(&self) -> Result<Vec<u8>, String> {
+        let mut chunks: BTreeMap<u32, (u32, &[u8])> = self
+            .chunks
+            .iter()
+            .map(|(id, chunk)| (*id, (chunk.fourcc, chunk.data.as_slice())))
+            .collect();
+        let key_table = write_to_vec(rw_endian(self.endian), |w| self.key_table.write(w))?;
+        chunks.insert(self.key_table_id, (FOURCC("KEY*"), key_table.as_slice()));
+
+        let count = chunks.keys().max().map_or(FIRST_CHUNK_ID, |id| id + 1);
+        const IMAP_OFFSET: u32 = 12;
+        const MMAP_OFFSET: u32 = IMAP_OFFSET + 8 + 24;
+        let mmap_len = 24 + 20 * count;
+        let mut offsets: HashMap<u32, u32> = HashMap::new();
+        let mut offset = MMAP_OFFSET + 8 + mmap_len;
+        for (id, (_, data)) in &chunks {
+            offsets.insert(*id, offset);
+            offset += 8 + data.len() as u32 + data.len() as u32 % 2;
+        }
+        let file_len = offset;
+
+        write_to_vec(rw_endian(self.endian), |w| {
+            let mut written = w.write_u32(FOURCC("RIFX"))?;
+            written += w.write_u32(file_len - 8)?;
+            written += w.write_u32(self.codec)?;
+
+            written += w.write_u32(FOURCC("imap"))?;
+            written += w.write_u32(24u32)?;
+            written += w.write_u32(1u32)?;
+            written += w.write_u32(MMAP_OFFSET)?;
+            written += w.write_u32(self.director_version as u32)?;
+            written += w.write_bytes([0u8; 12])?;
+
+            written += w.write_u32(FOURCC("mmap"))?;
+            written += w.write_u32(mmap_len)?;
+            written += w.write_u16(24u16)?;
+            written += w.write_u16(20u16)?;
+            written += w.write_u32(count)?;
+            written += w.write_u32(count)?;
+            written += w.write_i32(-1)?;
+            written += w.write_i32(-1)?;
+            written += w.write_i32(-1)?;
+            for id in 0..count {
+                let (fourcc, len, offset) = match id {
+                    0 => (FOURCC("RIFX"), file_len - 8, 0),
+                    1 => (FOURCC("imap"), 24, IMAP_OFFSET),
+                    2 => (FOURCC("mmap"), mmap_len, MMAP_OFFSET),
+                    _ => match chunks.get(&id) {
+                        Some((fourcc, data)) => (*fourcc, data.len() as u32, offsets[&id]),
+                        None => (FOURCC("free"), 0, 0),
+                    },
+                };
+                written += w.write_u32(fourcc)?;
+                written += w.write_u32(len)?;
+                written += w.write_u32(offset)?;
+                written += w.write_u16(0u16)?;
+                written += w.write_u16(0u16)?;
+                written += w.write_i32(-1)?;
+            }
+
+            for (fourcc, data) in chunks.values() {
+                written += w.write_u32(*fourcc)?;
+                written += w.write_u32(data.len() as u32)?;
+                written += w.write_bytes(data)?;
+                if data.len() % 2 != 0 {
+                    written += w.write_u8(0u8)?;
+                }
+            }
+            Ok(written)
+        })
+    }
+}
+
+/// Rebuild a `CASt` chunk with `text` as item 0 (the script text) of its info list.
+fn with_script_text(data: &[u8], dir_version: u16, text: &[u8]) -> Result<Vec<u8>, String> {
+    let read_u16 = |pos: usize| -> Result<u16, String> {
+        data.get(pos..pos + 2)
+            .map(|b| u16::from_be_bytes(b.try_into().unwrap()))
+            .ok_or("Cast member chunk is truncated".to_string())
+    };
+    let read_u32 = |pos: usize| -> Result<u32, String> {
+        data.get(pos..pos + 4)
+            .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+            .ok_or("Cast member chunk is truncated".to_string())
+    };
+
+    let (info_start, info_len) = if dir_version >= 500 {
+        (12, read_u32(4)? as usize)
+    } else {
+        (6 + read_u16(0)? as usize, read_u32(2)? as usize)
+    };
+    let info = data
+        .get(info_start..info_start + info_len)
+        .ok_or("Cast member info is truncated")?;
+
+    let mut items: Vec<Vec<u8>> = vec![];
+    let mut header = vec![0u8; 20];
+    header[3] = 20;
+    if info_len > 0 {
+        let data_offset = u32::from_be_bytes(info[0..4].try_into().unwrap()) as usize;
+        header = info.get(..data_offset).ok_or("Cast member info is truncated")?.to_vec();
+        let mut reader = BinaryReader::from_u8(info);
+        reader.set_endian(Endian::Big);
+        reader.jmp(data_offset);
+        let count = reader.read_u16().map_err(|e| e.to_string())? as usize;
+        let offsets = (0..count)
+            .map(|_| reader.read_u32().map(|o| o as usize))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        let items_len = reader.read_u32().map_err(|e| e.to_string())? as usize;
+        let items_start = reader.pos;
+        for (i, start) in offsets.iter().enumerate() {
+            let end = offsets.get(i + 1).copied().unwrap_or(items_len);
+            let item = info
+                .get(items_start + start..items_start + end)
+                .ok_or("Cast member info item is out of bounds")?;
+            items.push(item.to_vec());
+        }
+    }
+    if items.is_empty() {
+        items.push(vec![]);
+    }
+    items[0] = text.to_vec();
+
+    let mut new_info = header;
+    new_info.extend_from_slice(&(items.len() as u16).to_be_bytes());
+    let mut offset = 0u32;
+    for item in &items {
+        new_info.extend_from_slice(&offset.to_be_bytes());
+        offset += item.len() as u32;
+    }
+    new_info.extend_from_slice(&offset.to_be_bytes());
+    for item in &items {
+        new_info.extend_from_slice(item);
+    }
+
+    let mut out = data[..info_start].to_vec();
+    if dir_version >= 500 {
+        out[4..8].copy_from_slice(&(new_info.len() as u32).to_be_bytes());
+    } else {
+        out[2..6].copy_from_slice(&(new_info.len() as u32).to_be_bytes());
+    }
+    out.extend_from_slice(&new_info);
+    out.extend_from_slice(&data[info_start + info_len..]);
+    Ok(out)
+}
+
+/// Write a loaded movie or cast back out as an uncompressed `.dir` / `.cst`.
+pub fn write_director_file(file: &DirectorFile) -> Result<Vec<u8>, String> {
+    RIFXWriter::from_director_file(file)?.write()
+}
@@ -101,6 +101,12 @@ pub fn decode_win1252(bytes: &[u8]) -> String {
     s
 }
 
+/// Encode a `String` as Windows-1252 bytes for writing back into a
+/// Director file. Characters with no CP1252 mapping become `?`.
+pub fn encode_win1252(s: &str) -> Vec<u8> {
+    s.chars().map(|c| char_to_win1252_byte(c).unwrap_or(b'?')).collect()
+}
+
 /// Decode bytes that came from an external source (HTTP response body,
 /// local FileIO read, XML payload) where the encoding isn't recorded
 /// in-band. Strategy:
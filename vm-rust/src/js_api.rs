@@ -21,6 +21,7 @@ use crate::{
         cast_member::{CastMember, CastMemberType, ScriptMember},
         datum_formatting::{format_concrete_datum, format_datum, format_float_with_precision, format_numeric_value},
         datum_ref::{DatumId, DatumRef},
+        debug::BreakpointOptions,
         handlers::datum_handlers::cast_member_ref::CastMemberRefHandlers,
         score::get_channel_number_from_index,
         score::Score,
@@ -65,6 +66,9 @@ fn format_atom_summary(a: &crate::player::js_lingo::xdr::JsAtom) -> String {
                 .filter(|b| b.kind == crate::player::js_lingo::xdr::JsBindingKind::Argument)
                 .map(|b| b.name.as_str()).collect::<Vec<_>>().join(", ")
         ),
+        JsAtom::RegExp { source, flags } => format!(
+            "/{}/{}", source, crate::player::js_lingo::regexp::xdr_flag_letters(*flags)
+        ),
         JsAtom::Unsupported(t) => format!("<unsupported tag={}>", t),
     }
 }
@@ -136,14 +140,56 @@ pub struct JsBridgeBreakpoint {
     pub script_name: String,
     pub handler_name: String,
     pub bytecode_index: usize,
+    /// Breaks on entry to every handler with this name; `script_name` is empty.
+    pub handler_entry: bool,
+    pub condition: Option<String>,
+    pub hit_condition: String,
+    pub ignore_count: u32,
+    pub log_message: Option<String>,
+    pub one_shot: bool,
+    pub hit_count: u32,
+}
+
+impl JsBridgeBreakpoint {
+    pub fn from_options(
+        script_name: &str,
+        handler_name: &str,
+        bytecode_index: usize,
+        options: &BreakpointOptions,
+        hit_count: u32,
+    ) -> JsBridgeBreakpoint {
+        JsBridgeBreakpoint {
+            script_name: script_name.to_owned(),
+            handler_name: handler_name.to_owned(),
+            bytecode_index,
+            handler_entry: false,
+            condition: options.condition.clone(),
+            hit_condition: options.hit_condition.to_string(),
+            ignore_count: options.ignore_count,
+            log_message: options.log_message.clone(),
+            one_shot: options.one_shot,
+            hit_count,
+        }
+    }
 }
 
 impl Into<js_sys::Map> for JsBridgeBreakpoint {
     fn into(self) -> js_sys::Map {
+        let optional = |value: &Option<String>| match value {
+            Some(value) => safe_js_string(value),
+            None => JsValue::NULL,
+        };
         let map = js_sys::Map::new();
         map.str_set("script_name", &safe_js_string(&self.script_name));
         map.str_set("handler_name", &safe_js_string(&self.handler_name));
         map.str_set("bytecode_index", &JsValue::from(self.bytecode_index as u32));
+        map.str_set("handler_entry", &JsValue::from_bool(self.handler_entry));
+        map.str_set("condition", &optional(&self.condition));
+        map.str_set("hit_condition", &safe_js_string(&self.hit_condition));
+        map.str_set("ignore_count", &JsValue::from(self.ignore_count));
+        map.str_set("log_message", &optional(&self.log_message));
+        map.str_set("one_shot", &JsValue::from_bool(self.one_shot));
+        map.str_set("hit_count", &JsValue::from(self.hit_count));
         map
     }
 }
@@ -253,6 +299,7 @@ extern "C" {
     pub fn onFlashMemberLoaded(cast_lib: i32, cast_member: i32, swf_data: &[u8], width: u32, height: u32);
     pub fn onFlashMemberUnloaded(cast_lib: i32, cast_member: i32);
     pub fn onStageSizeChanged(width: u32, height: u32, center: bool);
+    pub fn onFileSaved(path: &str, data: &[u8]);
 }
 
 pub struct JsApi {}
@@ -294,6 +341,9 @@ impl JsApi {
     pub fn dispatch_stage_size_changed(width: u32, height: u32, center: bool) {
         onStageSizeChanged(width, height, center);
     }
+    pub fn dispatch_file_saved(path: &str, data: &[u8]) {
+        onFileSaved(path, data);
+    }
     pub fn dispatch_movie_loaded(dir_file: &DirectorFile) {
         let test = dir_file
             .cast_entries
@@ -1218,6 +1268,13 @@ impl JsApi {
                 }
                 member_map.str_set("colors", &colors_array);
             }
+            CastMemberType::Transition(transition) => {
+                let info = &transition.info;
+                member_map.str_set("transitionType", &JsValue::from(info.transition_type as i32));
+                member_map.str_set("duration", &JsValue::from(info.duration));
+                member_map.str_set("chunkSize", &JsValue::from(info.chunk_size));
+                member_map.str_set("changeArea", &JsValue::from(info.change_area));
+            }
             CastMemberType::Shockwave3d(s3d_data) => {
                 let info = &s3d_data.info;
                 member_map.str_set("regX", &JsValue::from(info.reg_point.0));
@@ -1839,16 +1896,23 @@ impl JsApi {
     pub fn dispatch_breakpoint_list_changed() {
         async_std::task::spawn_local(async move {
             let player = unsafe { PLAYER_OPT.as_ref().unwrap() };
-            let breakpoints = player
-                .breakpoint_manager
-                .breakpoints
-                .iter()
-                .map(|x| {
-                    let breakpoint = JsBridgeBreakpoint {
-                        script_name: x.script_name.to_owned(),
-                        handler_name: x.handler_name.to_owned(),
-                        bytecode_index: x.bytecode_index,
-                    };
+            let manager = &player.breakpoint_manager;
+            let bytecode_breakpoints = manager.breakpoints.iter().map(|x| {
+                JsBridgeBreakpoint::from_options(
+                    &x.script_name,
+                    &x.handler_name,
+                    x.bytecode_index,
+                    &x.options,
+                    x.hit_count,
+                )
+            });
+            let handler_breakpoints = manager.handler_breakpoints.iter().map(|x| JsBridgeBreakpoint {
+                handler_entry: true,
+                ..JsBridgeBreakpoint::from_options("", &x.handler_name, 0, &x.options, x.hit_count)
+            });
+            let breakpoints = bytecode_breakpoints
+                .chain(handler_breakpoints)
+                .map(|breakpoint| {
                     let breakpoint_js: js_sys::Map = breakpoint.into();
                     breakpoint_js.to_js_object()
                 })
@@ -1879,6 +1943,7 @@ impl JsApi {
     pub fn dispatch_flash_member_loaded(_: i32, _: i32, _: &[u8], _: u32, _: u32) {}
     pub fn dispatch_flash_member_unloaded(_: i32, _: i32) {}
     pub fn dispatch_stage_size_changed(_: u32, _: u32, _: bool) {}
+    pub fn dispatch_file_saved(_: &str, _: &[u8]) {}
     pub fn dispatch_cast_name_changed(_: u32) {}
     pub fn dispatch_cast_list_changed() {}
     pub fn dispatch_cast_member_list_changed(_: u32) {}
@@ -1993,6 +2058,10 @@ fn concrete_datum_to_js_bridge(datum: &Datum, player: &DirPlayer, depth: u8) ->
         Datum::Stage => {
             map.str_set("type", &safe_js_string("stage"));
         }
+        Datum::Window(name) => {
+            map.str_set("type", &safe_js_string("window"));
+            map.str_set("value", &safe_js_string(name));
+        }
         Datum::PropList(properties, sorted) => {
             map.str_set("type", &safe_js_string("propList"));
             let props_map = js_sys::Map::new();
@@ -3,6 +3,7 @@ pub mod io;
 pub mod js_api;
 pub mod player;
 pub mod rendering;
+pub mod rendering_cpu;
 pub mod rendering_gpu;
 pub mod utils;
 
@@ -21,10 +22,16 @@ pub mod director;
 use player::{
     cast_lib::{cast_member_ref, CastMemberRef},
     cast_member::CastMemberType,
-    commands::{player_dispatch, PlayerVMCommand},
+    commands::{player_dispatch, player_dispatch_input, PlayerVMCommand},
     datum_ref::DatumId,
+    debug::{
+        watch::{WatchAction, WatchTarget},
+        BreakpointOptions, HitCondition,
+    },
     eval::eval_lingo_command,
-    init_player, reserve_player_mut, reserve_player_ref,
+    init_player, keyboard_events,
+    replay::{self, ReplayInput},
+    reserve_player_mut, reserve_player_ref, save_state,
     score::get_sprite_at,
     PLAYER_OPT,
 };
@@ -146,6 +153,140 @@ pub fn toggle_breakpoint(script_name: String, handler_name: String, bytecode_ind
     });
 }
 
+/// Collect the breakpoint settings the debugger UI sends. Empty strings
+/// mean "not set".
+fn breakpoint_options(
+    condition: Option<String>,
+    hit_condition: Option<String>,
+    ignore_count: u32,
+    log_message: Option<String>,
+    one_shot: bool,
+) -> Result<BreakpointOptions, JsValue> {
+    let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+    let hit_condition = HitCondition::parse(hit_condition.as_deref().unwrap_or(""))
+        .map_err(|e| JsValue::from_str(&e))?;
+    Ok(BreakpointOptions {
+        condition: non_empty(condition),
+        hit_condition,
+        ignore_count,
+        log_message: non_empty(log_message),
+        one_shot,
+    })
+}
+
+/// Set the condition, hit count rule (`5`, `>=5` or `%5`), ignore count,
+/// logpoint message and one-shot flag of a breakpoint, adding it if needed.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn set_breakpoint_options(
+    script_name: String,
+    handler_name: String,
+    bytecode_index: usize,
+    condition: Option<String>,
+    hit_condition: Option<String>,
+    ignore_count: u32,
+    log_message: Option<String>,
+    one_shot: bool,
+) -> Result<(), JsValue> {
+    let options = breakpoint_options(condition, hit_condition, ignore_count, log_message, one_shot)?;
+    reserve_player_mut(|player| {
+        player.breakpoint_manager.set_breakpoint_options(
+            script_name,
+            handler_name,
+            bytecode_index,
+            options,
+        );
+    });
+    Ok(())
+}
+
+/// Break on entry to every handler called `handler_name`, in any script.
+#[wasm_bindgen]
+pub fn add_handler_breakpoint(
+    handler_name: String,
+    condition: Option<String>,
+    hit_condition: Option<String>,
+    ignore_count: u32,
+    log_message: Option<String>,
+    one_shot: bool,
+) -> Result<(), JsValue> {
+    let options = breakpoint_options(condition, hit_condition, ignore_count, log_message, one_shot)?;
+    reserve_player_mut(|player| {
+        player.breakpoint_manager.add_handler_breakpoint(handler_name, options);
+    });
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn remove_handler_breakpoint(handler_name: String) {
+    reserve_player_mut(|player| {
+        player.breakpoint_manager.remove_handler_breakpoint(&handler_name);
+    });
+}
+
+#[wasm_bindgen]
+pub fn reset_breakpoint_hit_counts() {
+    reserve_player_mut(|player| {
+        player.breakpoint_manager.reset_hit_counts();
+    });
+}
+
+fn watch_action(log: bool) -> WatchAction {
+    if log { WatchAction::Log } else { WatchAction::Break }
+}
+
+/// Watch a global variable. Changes pause execution, or with `log` go to
+/// the message window instead. Returns the watchpoint's id.
+#[wasm_bindgen]
+pub fn add_global_watchpoint(name: String, log: bool) -> u32 {
+    reserve_player_mut(|player| {
+        player.watch_manager.add_watchpoint(WatchTarget::Global(name), watch_action(log))
+    })
+}
+
+/// Watch a property of the script instance with id `instance_id`,
+/// including properties it inherits from its ancestors.
+#[wasm_bindgen]
+pub fn add_property_watchpoint(instance_id: u32, prop_name: String, log: bool) -> Result<u32, JsValue> {
+    reserve_player_mut(|player| {
+        let instance = player
+            .allocator
+            .get_script_instance_ref(instance_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Script instance {} not found", instance_id)))?;
+        let target = WatchTarget::Property { instance, prop_name };
+        Ok(player.watch_manager.add_watchpoint(target, watch_action(log)))
+    })
+}
+
+#[wasm_bindgen]
+pub fn add_sprite_watchpoint(channel: i16, prop_name: String, log: bool) -> u32 {
+    reserve_player_mut(|player| {
+        let target = WatchTarget::Sprite { channel, prop_name };
+        player.watch_manager.add_watchpoint(target, watch_action(log))
+    })
+}
+
+/// Watch the elements of the list or prop list with datum id `datum_id`.
+#[wasm_bindgen]
+pub fn add_list_watchpoint(datum_id: DatumId, log: bool) -> Result<u32, JsValue> {
+    use director::lingo::datum::Datum;
+    reserve_player_mut(|player| {
+        let list_ref = player
+            .allocator
+            .get_datum_ref(datum_id)
+            .filter(|list_ref| matches!(player.get_datum(list_ref), Datum::List(..) | Datum::PropList(..)))
+            .ok_or_else(|| JsValue::from_str(&format!("Datum {} is not a list", datum_id)))?;
+        Ok(player.watch_manager.add_watchpoint(WatchTarget::List(list_ref), watch_action(log)))
+    })
+}
+
+#[wasm_bindgen]
+pub fn remove_watchpoint(id: u32) {
+    reserve_player_mut(|player| {
+        player.watch_manager.remove_watchpoint(id);
+    });
+}
+
 #[wasm_bindgen]
 pub fn resume_breakpoint() {
     reserve_player_mut(|player| {
@@ -200,6 +341,14 @@ pub fn get_break_on_error() -> bool {
     reserve_player_ref(|player| player.break_on_error)
 }
 
+/// Run the cycle collector every `frames` frames, or never with 0.
+#[wasm_bindgen]
+pub fn set_cycle_collection_interval(frames: u32) {
+    reserve_player_mut(|player| {
+        player.cycle_collector.interval_frames = frames;
+    });
+}
+
 /// Returns the trace log file path and content as a JS object { path, content },
 /// or null if no trace log file is set or empty.
 #[wasm_bindgen]
@@ -232,12 +381,12 @@ pub fn get_trace_log() -> JsValue {
 
 #[wasm_bindgen]
 pub fn set_stage_size(width: u32, height: u32) {
-    player_dispatch(PlayerVMCommand::SetStageSize(width, height));
+    player_dispatch_input(PlayerVMCommand::SetStageSize(width, height));
 }
 
 #[wasm_bindgen]
 pub fn trigger_timeout(name: &str) {
-    player_dispatch(PlayerVMCommand::TimeoutTriggered(name.to_string()));
+    player_dispatch_input(PlayerVMCommand::TimeoutTriggered(name.to_string()));
 }
 
 #[wasm_bindgen]
@@ -395,32 +544,47 @@ pub fn mouse_down(x: f64, y: f64) {
     // matching where sprites live in Lingo-facing state. No-op when scale=1.
     let (mx, my) = reserve_player_ref(|p| crate::player::stage::canvas_to_movie_coords(p, x, y));
     let (ix, iy) = (mx.to_i32().unwrap(), my.to_i32().unwrap());
-    reserve_player_mut(|player| {
-        player.mouse_loc = (ix, iy);
-        player.movie.mouse_down = true;
-    });
-    player_dispatch(PlayerVMCommand::MouseDown((ix, iy)));
+    player_dispatch_input(PlayerVMCommand::MouseDown((ix, iy)));
 }
 
 #[wasm_bindgen]
 pub fn mouse_up(x: f64, y: f64) {
     let (mx, my) = reserve_player_ref(|p| crate::player::stage::canvas_to_movie_coords(p, x, y));
     let (ix, iy) = (mx.to_i32().unwrap(), my.to_i32().unwrap());
-    reserve_player_mut(|player| {
-        player.mouse_loc = (ix, iy);
-        player.movie.mouse_down = false;
-    });
-    player_dispatch(PlayerVMCommand::MouseUp((ix, iy)));
+    player_dispatch_input(PlayerVMCommand::MouseUp((ix, iy)));
 }
 
 #[wasm_bindgen]
 pub fn mouse_move(x: f64, y: f64) {
     let (mx, my) = reserve_player_ref(|p| crate::player::stage::canvas_to_movie_coords(p, x, y));
     let (ix, iy) = (mx.to_i32().unwrap(), my.to_i32().unwrap());
-    reserve_player_mut(|player| {
-        player.mouse_loc = (ix, iy);
-    });
-    player_dispatch(PlayerVMCommand::MouseMove((ix, iy)));
+    player_dispatch_input(PlayerVMCommand::MouseMove((ix, iy)));
+}
+
+#[wasm_bindgen]
+pub fn right_mouse_down(x: f64, y: f64) {
+    let (mx, my) = reserve_player_ref(|p| crate::player::stage::canvas_to_movie_coords(p, x, y));
+    let (ix, iy) = (mx.to_i32().unwrap(), my.to_i32().unwrap());
+    player_dispatch_input(PlayerVMCommand::RightMouseDown((ix, iy)));
+}
+
+#[wasm_bindgen]
+pub fn right_mouse_up(x: f64, y: f64) {
+    let (mx, my) = reserve_player_ref(|p| crate::player::stage::canvas_to_movie_coords(p, x, y));
+    let (ix, iy) = (mx.to_i32().unwrap(), my.to_i32().unwrap());
+    player_dispatch_input(PlayerVMCommand::RightMouseUp((ix, iy)));
+}
+
+/// The page hosting the player gained focus (sends activateApplication).
+#[wasm_bindgen]
+pub fn activate_application() {
+    player_dispatch_input(PlayerVMCommand::ActivateApplication);
+}
+
+/// The page hosting the player lost focus (sends deactivateApplication).
+#[wasm_bindgen]
+pub fn deactivate_application() {
+    player_dispatch_input(PlayerVMCommand::DeactivateApplication);
 }
 
 /// Check if the game wants pointer lock (for FPS mouse look)
@@ -433,32 +597,69 @@ pub fn wants_pointer_lock() -> bool {
 /// The delta is added to the current mouse_loc (which the game resets to center each frame)
 #[wasm_bindgen]
 pub fn mouse_move_delta(dx: f64, dy: f64) {
-    reserve_player_mut(|player| {
-        player.mouse_loc.0 -= dx.to_i32().unwrap();
-        player.mouse_loc.1 += dy.to_i32().unwrap();
-    });
     let (x, y) = reserve_player_ref(|player| player.mouse_loc);
-    player_dispatch(PlayerVMCommand::MouseMove((x, y)));
+    let (x, y) = (x - dx.to_i32().unwrap(), y + dy.to_i32().unwrap());
+    player_dispatch_input(PlayerVMCommand::MouseMove((x, y)));
 }
 
 #[wasm_bindgen]
 pub fn key_down(key: String, code: u16) {
-    // Update keyboard state immediately so keyPressed() reflects
+    // Keyboard state is updated immediately so keyPressed() reflects
     // real state even during long-running script handlers
-    reserve_player_mut(|player| {
-        player.keyboard_manager.key_down(key.clone(), code);
-    });
-    player_dispatch(PlayerVMCommand::KeyDown(key, code));
+    player_dispatch_input(PlayerVMCommand::KeyDown(key, code));
 }
 
 #[wasm_bindgen]
 pub fn key_up(key: String, code: u16) {
-    // Update keyboard state immediately so keyPressed() reflects
+    // Keyboard state is updated immediately so keyPressed() reflects
     // real state even during long-running script handlers
-    reserve_player_mut(|player| {
-        player.keyboard_manager.key_up(&key, code);
-    });
-    player_dispatch(PlayerVMCommand::KeyUp(key, code));
+    player_dispatch_input(PlayerVMCommand::KeyUp(key, code));
+}
+
+/// Record inputs from here on for a deterministic replay, with a fresh
+/// random seed (see `player::replay`).
+#[wasm_bindgen]
+pub fn start_input_recording() {
+    let rng_seed = rand::random::<u64>();
+    reserve_player_mut(|player| replay::start_recording(player, rng_seed));
+}
+
+/// Stop recording and return the replay file, or nothing if not recording.
+#[wasm_bindgen]
+pub fn stop_input_recording() -> Option<Vec<u8>> {
+    reserve_player_mut(replay::stop_recording)
+}
+
+/// Drive the player from a replay file, ignoring live input until
+/// `stop_input_replay`. Load the movie the recording was made with first.
+#[wasm_bindgen]
+pub fn start_input_replay(replay_file: Vec<u8>) -> Result<(), JsValue> {
+    reserve_player_mut(|player| replay::start_replay(player, &replay_file))
+        .map_err(|err| JsValue::from_str(&err))
+}
+
+#[wasm_bindgen]
+pub fn stop_input_replay() {
+    reserve_player_mut(replay::stop_replay);
+}
+
+/// Whether the replay has applied all of its recorded inputs and frames.
+#[wasm_bindgen]
+pub fn is_input_replay_finished() -> bool {
+    replay::is_replay_finished()
+}
+
+/// Snapshot the VM state between frames (see `player::save_state`).
+#[wasm_bindgen]
+pub fn save_vm_state() -> Result<Vec<u8>, JsValue> {
+    reserve_player_mut(save_state::save_state).map_err(|err| JsValue::from_str(&err.message))
+}
+
+/// Restore a snapshot taken with `save_vm_state` in the same movie.
+#[wasm_bindgen]
+pub fn restore_vm_state(state: Vec<u8>) -> Result<(), JsValue> {
+    reserve_player_mut(|player| save_state::restore_state(player, &state))
+        .map_err(|err| JsValue::from_str(&err.message))
 }
 
 // Picking mode commands bypass the command queue for synchronous access.
@@ -680,32 +881,9 @@ pub fn set_clipboard_mirror(text: String) {
 /// already replaced). No-op if no editable member has focus.
 #[wasm_bindgen]
 pub fn ime_composition_start() {
-    reserve_player_mut(|player| {
-        if player.keyboard_focus_sprite < 0 { return; }
-        let sprite_id = player.keyboard_focus_sprite as i16;
-        let sprite = player.movie.score.get_sprite(sprite_id);
-        let Some(member_ref) = sprite.and_then(|s| s.member.clone()) else { return };
-        let Some(member) = player.movie.cast_manager.find_mut_member_by_ref(&member_ref) else {
-            return;
-        };
-        let (text, sel_start, sel_end, sel_anchor) = match &mut member.member_type {
-            CastMemberType::Field(f) if f.editable => (
-                &mut f.text, &mut f.sel_start, &mut f.sel_end, &mut f.sel_anchor,
-            ),
-            CastMemberType::Text(t) if t.info.as_ref().map_or(false, |i| i.editable) => (
-                &mut t.text, &mut t.sel_start, &mut t.sel_end, &mut t.sel_anchor,
-            ),
-            _ => return,
-        };
-        // Collapse any existing selection so the composition replaces it cleanly.
-        if *sel_start != *sel_end {
-            crate::player::keyboard_events::apply_text_insertion(text, sel_start, sel_end, sel_anchor, "");
-        }
-        let pos = (*sel_start).max(0);
-        player.ime_composition = Some((pos, pos));
-        player.text_selection_start = pos.max(0) as u16;
-        player.text_selection_end = pos.max(0) as u16;
-    });
+    if replay::accept_input(ReplayInput::ImeCompositionStart) {
+        reserve_player_mut(keyboard_events::ime_composition_start);
+    }
 }
 
 /// IME composition update — replace the current provisional run with `text`.
@@ -713,76 +891,27 @@ pub fn ime_composition_start() {
 /// composition is active.
 #[wasm_bindgen]
 pub fn ime_composition_update(text: String) {
-    reserve_player_mut(|player| {
-        let Some((start, end)) = player.ime_composition else { return };
-        if player.keyboard_focus_sprite < 0 { return; }
-        let sprite_id = player.keyboard_focus_sprite as i16;
-        let sprite = player.movie.score.get_sprite(sprite_id);
-        let Some(member_ref) = sprite.and_then(|s| s.member.clone()) else { return };
-        let Some(member) = player.movie.cast_manager.find_mut_member_by_ref(&member_ref) else {
-            return;
-        };
-        let (text_buf, sel_start, sel_end, sel_anchor) = match &mut member.member_type {
-            CastMemberType::Field(f) if f.editable => (
-                &mut f.text, &mut f.sel_start, &mut f.sel_end, &mut f.sel_anchor,
-            ),
-            CastMemberType::Text(t) if t.info.as_ref().map_or(false, |i| i.editable) => (
-                &mut t.text, &mut t.sel_start, &mut t.sel_end, &mut t.sel_anchor,
-            ),
-            _ => return,
-        };
-        let len = text_buf.len() as i32;
-        let lo = start.clamp(0, len) as usize;
-        let hi = end.clamp(0, len) as usize;
-        let hi = hi.max(lo);
-        text_buf.replace_range(lo..hi, &text);
-        let new_end = lo as i32 + text.len() as i32;
-        *sel_start = new_end;
-        *sel_end = new_end;
-        *sel_anchor = new_end;
-        player.ime_composition = Some((start, new_end));
-        player.text_selection_start = new_end.max(0) as u16;
-        player.text_selection_end = new_end.max(0) as u16;
-    });
+    if replay::accept_input(ReplayInput::ImeCompositionUpdate { text: text.clone() }) {
+        reserve_player_mut(|player| keyboard_events::ime_composition_update(player, &text));
+    }
 }
 
 /// IME composition committed — `text` is the final string. Same replacement
 /// as update, then clears composition state. No-op if no composition is active.
 #[wasm_bindgen]
 pub fn ime_composition_end(text: String) {
-    ime_composition_update(text);
-    reserve_player_mut(|player| {
-        player.ime_composition = None;
-    });
+    if replay::accept_input(ReplayInput::ImeCompositionEnd { text: text.clone() }) {
+        reserve_player_mut(|player| keyboard_events::ime_composition_end(player, &text));
+    }
 }
 
 /// Insert text at the focused editable member's caret/selection. Used by
 /// paste and IME commit.
 #[wasm_bindgen]
 pub fn paste_text_into_focused_field(text: String) {
-    reserve_player_mut(|player| {
-        if player.keyboard_focus_sprite < 0 { return; }
-        let sprite_id = player.keyboard_focus_sprite as i16;
-        let sprite = player.movie.score.get_sprite(sprite_id);
-        let Some(member_ref) = sprite.and_then(|s| s.member.clone()) else { return };
-        let Some(member) = player.movie.cast_manager.find_mut_member_by_ref(&member_ref) else {
-            return;
-        };
-        let (target, sel_start, sel_end, sel_anchor) = match &mut member.member_type {
-            CastMemberType::Field(f) if f.editable => (
-                &mut f.text, &mut f.sel_start, &mut f.sel_end, &mut f.sel_anchor,
-            ),
-            CastMemberType::Text(t) if t.info.as_ref().map_or(false, |i| i.editable) => (
-                &mut t.text, &mut t.sel_start, &mut t.sel_end, &mut t.sel_anchor,
-            ),
-            _ => return,
-        };
-        crate::player::keyboard_events::apply_text_insertion(target, sel_start, sel_end, sel_anchor, &text);
-        let s = *sel_start;
-        let e = *sel_end;
-        player.text_selection_start = s.max(0) as u16;
-        player.text_selection_end = e.max(0) as u16;
-    });
+    if replay::accept_input(ReplayInput::PasteText { text: text.clone() }) {
+        reserve_player_mut(|player| keyboard_events::paste_text(player, &text));
+    }
 }
 
 // Inspector commands bypass the command queue to allow inspecting state
@@ -921,7 +1050,7 @@ pub fn provide_net_task_error(task_id: u32) {
     async_std::task::spawn_local(async move {
         let shared_state_arc =
             reserve_player_ref(|player| std::sync::Arc::clone(&player.net_manager.shared_state));
-        let result: player::net_task::NetResult = Err(4);
+        let result: player::net_task::NetResult = Err(player::net_task::NET_ERROR_NOT_FOUND);
         let mut shared_state = shared_state_arc.lock().await;
         shared_state.fulfill_task(task_id, result).await;
     });
@@ -1611,6 +1740,56 @@ pub fn mcp_list_breakpoints() -> String {
     reserve_player_ref(|player| player::mcp::mcp_list_breakpoints(player))
 }
 
+#[wasm_bindgen]
+pub fn mcp_list_watchpoints() -> String {
+    reserve_player_ref(player::mcp::mcp_list_watchpoints)
+}
+
+#[wasm_bindgen]
+pub fn mcp_get_xtra_report() -> String {
+    reserve_player_ref(player::mcp::mcp_get_xtra_report)
+}
+
+#[wasm_bindgen]
+pub fn mcp_get_memory_stats() -> String {
+    reserve_player_ref(player::mcp::mcp_get_memory_stats)
+}
+
+#[wasm_bindgen]
+pub fn mcp_collect_cycles() -> String {
+    reserve_player_mut(player::mcp::mcp_collect_cycles)
+}
+
+/// Export every movie's saved files and prefs as a ZIP archive, e.g. so
+/// players can back up their saved games.
+#[wasm_bindgen]
+pub async fn export_storage() -> Result<Vec<u8>, JsValue> {
+    let archive = reserve_player_ref(|player| player.storage.export_archive());
+    archive.await.map_err(|e| JsValue::from_str(&e))
+}
+
+/// Import an archive made by `export_storage`, replacing the saved data of
+/// the movies it contains.
+#[wasm_bindgen]
+pub async fn import_storage(archive: Vec<u8>) -> Result<(), JsValue> {
+    player::reserve_player_mut_async(move |player| {
+        Box::pin(async move { player::storage::import_movie_storage(player, &archive).await })
+    })
+    .await
+    .map_err(|e| JsValue::from_str(&e))
+}
+
+/// Set the machine the Buddy API Xtra reports (user and computer name,
+/// screen, OS version) and whether its blocked operations report success,
+/// from JSON such as `{ "userName": "Ann", "blockedOperationsSucceed": false }`.
+/// Omitted fields keep their defaults.
+#[wasm_bindgen]
+pub fn set_buddy_api_config(json: &str) -> Result<(), JsValue> {
+    let config = player::xtra::buddyapi::BuddyApiConfig::from_json(json).map_err(|e| JsValue::from_str(&e))?;
+    player::xtra::buddyapi::borrow_buddyapi_manager_mut(|manager| manager.config = config);
+    Ok(())
+}
+
 /// Evaluate a Lingo expression and return the result as JSON.
 /// Unlike eval_command, this waits for completion and returns the result.
 #[wasm_bindgen]
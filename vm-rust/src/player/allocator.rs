@@ -210,6 +210,9 @@ impl<T> Arena<T> {
 
 pub struct DatumRefEntry {
     pub id: DatumId,
+    /// Distinguishes this allocation from earlier ones that used the same
+    /// arena slot. Pooled entries keep serial 0.
+    pub serial: u64,
     pub ref_count: UnsafeCell<u32>,
     pub datum: Datum,
 }
@@ -256,6 +259,7 @@ pub struct DatumAllocator {
     pub snapshot_max_id: usize,
     int_pool_ids: [DatumId; INT_POOL_SIZE],
     symbol_pool: FxHashMap<String, DatumId>,
+    datum_serial_counter: u64,
 }
 
 const MAX_SCRIPT_INSTANCE_ID: ScriptInstanceId = 0xFFFFFF;
@@ -272,6 +276,7 @@ impl DatumAllocator {
             snapshot_max_id: 0,
             int_pool_ids: [0; INT_POOL_SIZE],
             symbol_pool: FxHashMap::default(),
+            datum_serial_counter: 0,
         };
         alloc.init_int_pool();
         alloc
@@ -282,6 +287,7 @@ impl DatumAllocator {
             let n = (i as i32) + INT_POOL_MIN;
             let entry = DatumRefEntry {
                 id: 0,
+                serial: 0,
                 ref_count: UnsafeCell::new(u32::MAX),
                 datum: Datum::Int(n),
             };
@@ -295,6 +301,13 @@ impl DatumAllocator {
         self.datums.contains(id)
     }
 
+    /// Allocation serial of the datum in slot `id`, if the slot is in use.
+    /// State keyed by datum id should store this alongside the id, since a
+    /// freed slot is reused by the next allocation.
+    pub fn datum_serial(&self, id: DatumId) -> Option<u64> {
+        self.datums.get(id).map(|entry| entry.serial)
+    }
+
     pub fn get_free_script_instance_id(&self) -> ScriptInstanceId {
         if self.script_instance_count() >= MAX_SCRIPT_INSTANCE_ID as usize {
             panic!("Script instance limit reached");
@@ -500,6 +513,7 @@ impl DatumAllocatorTrait for DatumAllocator {
             let key = s.clone();
             let entry = DatumRefEntry {
                 id: 0,
+                serial: 0,
                 ref_count: UnsafeCell::new(u32::MAX),
                 datum,
             };
@@ -520,8 +534,10 @@ impl DatumAllocatorTrait for DatumAllocator {
         } else {
             None
         };
+        self.datum_serial_counter += 1;
         let entry = DatumRefEntry {
             id: 0,
+            serial: self.datum_serial_counter,
             ref_count: UnsafeCell::new(1), // Start at 1 to avoid the extra increment in from_id
             datum,
         };
@@ -0,0 +1,308 @@
+//! Pure-Rust decoding of Director sound data.
+//!
+//! Sound members store raw PCM (8-bit unsigned or 16-bit signed, in either
+//! byte order), IMA ADPCM or MP3. Everything here decodes to interleaved
+//! `f32` samples in [-1.0, 1.0] so the mixer never has to care about the
+//! source format.
+
+use log::debug;
+
+use crate::player::cast_member::SoundMember;
+
+const STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32773,
+];
+
+const INDEX_TABLE: [i32; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+/// Decoded audio, interleaved by channel.
+#[derive(Clone, Debug, Default)]
+pub struct DecodedSound {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<f32>,
+}
+
+impl DecodedSound {
+    pub fn frame_count(&self) -> usize {
+        if self.channels == 0 {
+            0
+        } else {
+            self.samples.len() / self.channels as usize
+        }
+    }
+
+    pub fn duration_ms(&self) -> f64 {
+        if self.sample_rate == 0 {
+            0.0
+        } else {
+            self.frame_count() as f64 * 1000.0 / self.sample_rate as f64
+        }
+    }
+
+    /// Sample of `channel` at `frame`, 0.0 past the end. Mono sounds return
+    /// the same sample for every channel.
+    pub fn sample(&self, frame: usize, channel: usize) -> f32 {
+        let channels = self.channels.max(1) as usize;
+        self.samples
+            .get(frame * channels + channel.min(channels - 1))
+            .copied()
+            .unwrap_or(0.0)
+    }
+}
+
+fn decode_nibble(nibble: i32, predictor: &mut i32, index: &mut i32) -> i16 {
+    let step = STEP_TABLE[*index as usize];
+    let mut diff = step >> 3;
+    if nibble & 0x1 != 0 {
+        diff += step >> 2;
+    }
+    if nibble & 0x2 != 0 {
+        diff += step >> 1;
+    }
+    if nibble & 0x4 != 0 {
+        diff += step;
+    }
+    if nibble & 0x8 != 0 {
+        *predictor -= diff;
+    } else {
+        *predictor += diff;
+    }
+    *predictor = (*predictor).clamp(-32768, 32767);
+    *index = (*index + INDEX_TABLE[nibble as usize]).clamp(0, 88);
+    *predictor as i16
+}
+
+/// Decode IMA ADPCM nibbles (low nibble first) into 16-bit PCM.
+pub fn decode_ima_adpcm(data: &[u8], mut predictor: i32, mut index: i32) -> Vec<i16> {
+    index = index.clamp(0, 88);
+    let mut samples = Vec::with_capacity(data.len() * 2);
+    for &byte in data {
+        samples.push(decode_nibble((byte & 0x0F) as i32, &mut predictor, &mut index));
+        samples.push(decode_nibble((byte >> 4) as i32, &mut predictor, &mut index));
+    }
+    samples
+}
+
+/// Convert raw PCM bytes to `f32` samples. 8-bit data is unsigned, 16-bit
+/// data is signed.
+pub fn decode_pcm(data: &[u8], bits_per_sample: u16, big_endian: bool) -> Vec<f32> {
+    match bits_per_sample {
+        8 => data.iter().map(|&b| (b as f32 - 128.0) / 128.0).collect(),
+        _ => data
+            .chunks_exact(2)
+            .map(|pair| {
+                let sample = if big_endian {
+                    i16::from_be_bytes([pair[0], pair[1]])
+                } else {
+                    i16::from_le_bytes([pair[0], pair[1]])
+                };
+                sample as f32 / 32768.0
+            })
+            .collect(),
+    }
+}
+
+/// Decode an MPEG Layer III stream.
+pub fn decode_mp3(data: &[u8]) -> Result<DecodedSound, String> {
+    let mut decoder = puremp3::Mp3Decoder::new(data);
+    let mut sound = DecodedSound::default();
+    while let Ok(frame) = decoder.next_frame() {
+        if sound.sample_rate == 0 {
+            sound.sample_rate = frame.header.sample_rate.hz();
+            sound.channels = frame.header.channels.num_channels() as u16;
+        }
+        for i in 0..frame.num_samples {
+            sound.samples.push(frame.samples[0][i]);
+            if sound.channels > 1 {
+                sound.samples.push(frame.samples[1][i]);
+            }
+        }
+    }
+    if sound.sample_rate == 0 {
+        return Err("No MP3 frames found".to_string());
+    }
+    Ok(sound)
+}
+
+/// Decode the audio of a sound member, detecting the format the same way
+/// the Web Audio path does.
+pub fn decode_sound_member(member: &SoundMember) -> Result<DecodedSound, String> {
+    let sound = &member.sound;
+    let data = sound.data();
+    if data.is_empty() {
+        return Err("Sound member has no audio data".to_string());
+    }
+    let channels = member.info.channels.max(1);
+    let sample_rate = member.info.sample_rate;
+    let bits_per_sample = member.info.sample_size;
+    let codec = sound.codec();
+
+    // sndH/sndS headers sometimes claim raw PCM for MP3 data; only look for
+    // MP3 frames when the data is too small to be the advertised PCM.
+    let bytes_per_sample = if bits_per_sample > 0 { bits_per_sample as usize / 8 } else { 2 };
+    let expected_pcm_size = member.info.sample_count as usize * channels as usize * bytes_per_sample;
+    let likely_pcm = expected_pcm_size > 0 && data.len() >= expected_pcm_size * 4 / 5;
+    if (codec == "mp3" || !likely_pcm)
+        && let Some(start) = find_mp3_start(&data)
+    {
+        return decode_mp3(&data[start..]);
+    }
+
+    let samples = if codec.contains("ima") {
+        if data.len() < 4 {
+            return Err("IMA ADPCM data too short to read initial state".to_string());
+        }
+        let predictor = i16::from_le_bytes([data[0], data[1]]) as i32;
+        let index = data[2] as i32;
+        decode_ima_adpcm(&data[4..], predictor, index)
+            .into_iter()
+            .map(|s| s as f32 / 32768.0)
+            .collect()
+    } else {
+        decode_pcm(&data, bits_per_sample, sound.big_endian_data())
+    };
+    debug!(
+        "Decoded {} samples ({} Hz, {} ch, codec {})",
+        samples.len(),
+        sample_rate,
+        channels,
+        codec
+    );
+    Ok(DecodedSound {
+        sample_rate,
+        channels,
+        samples,
+    })
+}
+
+/// Find the first offset where at least three consecutive valid MP3 frame
+/// headers follow each other.
+pub fn find_mp3_start(data: &[u8]) -> Option<usize> {
+    const MIN_FRAMES_TO_VALIDATE: usize = 3;
+    const MIN_MP3_SIZE: usize = 512; // Small enough for short Director sound effects
+
+    if data.len() < MIN_MP3_SIZE {
+        debug!("Data too small for MP3 ({} bytes < {} min)", data.len(), MIN_MP3_SIZE);
+        return None;
+    }
+
+    for i in 0..data.len().saturating_sub(4) {
+        if data[i] == 0xFF && (data[i + 1] & 0xE0) == 0xE0 {
+            let remaining = data.len() - i;
+            // An MP3 start found this late is most likely a false positive
+            if remaining < MIN_MP3_SIZE {
+                continue;
+            }
+            if validate_mp3_sequence(&data[i..], MIN_FRAMES_TO_VALIDATE) {
+                debug!(
+                    "Valid MP3 sequence found at offset {} ({} bytes remaining)",
+                    i, remaining
+                );
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+fn validate_mp3_sequence(data: &[u8], min_frames: usize) -> bool {
+    let mut offset = 0;
+    let mut frames_found = 0;
+
+    while frames_found < min_frames && offset < data.len().saturating_sub(4) {
+        if data[offset] != 0xFF || (data[offset + 1] & 0xE0) != 0xE0 {
+            return false;
+        }
+
+        let header = u32::from_be_bytes([
+            data[offset],
+            data[offset + 1],
+            data.get(offset + 2).copied().unwrap_or(0),
+            data.get(offset + 3).copied().unwrap_or(0),
+        ]);
+
+        let version = (header >> 19) & 0x3;
+        let layer = (header >> 17) & 0x3;
+        let bitrate_index = (header >> 12) & 0xF;
+        let sample_rate_index = (header >> 10) & 0x3;
+
+        if version == 1 || layer == 0 || bitrate_index == 0xF || bitrate_index == 0 || sample_rate_index == 3 {
+            return false;
+        }
+
+        let frame_size = mp3_frame_size(header);
+        if frame_size == 0 || frame_size > 4096 {
+            return false;
+        }
+
+        frames_found += 1;
+        offset += frame_size;
+
+        if offset + 4 > data.len() {
+            break;
+        }
+    }
+
+    frames_found >= min_frames || (frames_found > 0 && offset >= data.len() - 4)
+}
+
+/// Size in bytes of the MP3 frame starting with `header`, or 0 if the
+/// header is invalid.
+pub fn mp3_frame_size(header: u32) -> usize {
+    const BITRATES: [[[u32; 16]; 4]; 4] = [
+        // MPEG 2.5
+        [
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0],
+            [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0],
+            [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256, 0],
+        ],
+        // Reserved
+        [[0; 16]; 4],
+        // MPEG 2
+        [
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0],
+            [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0],
+            [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256, 0],
+        ],
+        // MPEG 1
+        [
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 0],
+            [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 0],
+            [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448, 0],
+        ],
+    ];
+
+    const SAMPLE_RATES: [[u32; 4]; 4] = [
+        [11025, 12000, 8000, 0],
+        [0, 0, 0, 0],
+        [22050, 24000, 16000, 0],
+        [44100, 48000, 32000, 0],
+    ];
+
+    let version = ((header >> 19) & 0x3) as usize;
+    let layer = ((header >> 17) & 0x3) as usize;
+    let bitrate_index = ((header >> 12) & 0xF) as usize;
+    let sample_rate_index = ((header >> 10) & 0x3) as usize;
+    let padding = (header >> 9) & 0x1;
+
+    let bitrate = BITRATES[version][layer][bitrate_index];
+    let sample_rate = SAMPLE_RATES[version][sample_rate_index];
+    if bitrate == 0 || sample_rate == 0 {
+        return 0;
+    }
+
+    let frame_size = if layer == 3 {
+        ((12 * bitrate * 1000 / sample_rate) + padding) * 4
+    } else {
+        (144 * bitrate * 1000 / sample_rate) + padding
+    };
+    frame_size as usize
+}
@@ -0,0 +1,196 @@
+//! Software mixer for sound channels that have no Web Audio context.
+//!
+//! Each playing channel owns a [`Voice`] (a decoded sound and a read
+//! position). [`render`] pulls fixed-size blocks from every voice, applies
+//! the channel volume, fades and pan, sums them and writes the block to the
+//! mixer's [`AudioOutput`]. When a voice runs out the channel decides what
+//! comes next (loop, queued sound, next playlist entry) exactly like the
+//! Web Audio `onended` handler does.
+
+use std::cell::Cell;
+use std::f32::consts::FRAC_PI_2;
+use std::rc::Rc;
+
+use super::{decode::DecodedSound, AudioOutput, NullAudioOutput, DEFAULT_SAMPLE_RATE};
+use crate::player::DirPlayer;
+
+/// Frames mixed per block. Fades are linear within a block.
+const BLOCK_FRAMES: usize = 256;
+
+#[derive(Clone)]
+pub struct Voice {
+    pub sound: Rc<DecodedSound>,
+    /// Read position in source frames
+    pub position: f64,
+    pub start_frame: f64,
+    pub end_frame: f64,
+}
+
+impl Voice {
+    /// Play `sound` from `start_ms` to `end_ms` (0 = to the end).
+    pub fn new(sound: Rc<DecodedSound>, start_ms: f64, end_ms: f64) -> Voice {
+        let rate = sound.sample_rate as f64;
+        let frame_count = sound.frame_count() as f64;
+        let start_frame = (start_ms.max(0.0) * rate / 1000.0).min(frame_count);
+        let end_frame = if end_ms > start_ms {
+            (end_ms * rate / 1000.0).min(frame_count)
+        } else {
+            frame_count
+        };
+        Voice {
+            sound,
+            position: start_frame,
+            start_frame,
+            end_frame,
+        }
+    }
+
+    pub fn rewind(&mut self) {
+        self.position = self.start_frame;
+    }
+
+    /// Move to `ms` from the start of the sound, within the played range.
+    pub fn seek_ms(&mut self, ms: f64) {
+        let frame = ms * self.sound.sample_rate as f64 / 1000.0;
+        self.position = frame.min(self.end_frame).max(self.start_frame);
+    }
+
+    pub fn position_ms(&self) -> f64 {
+        if self.sound.sample_rate == 0 {
+            0.0
+        } else {
+            self.position * 1000.0 / self.sound.sample_rate as f64
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.end_frame
+    }
+
+    /// Mix into `out` (interleaved stereo at `out_rate`), ramping the gain
+    /// from `gain.0` to `gain.1` over the whole slice. `pan` is -1.0..1.0.
+    /// Returns the number of frames written and whether the voice ended.
+    pub fn mix(&mut self, out: &mut [f32], out_rate: u32, gain: (f32, f32), pan: f32) -> (usize, bool) {
+        let frames = out.len() / 2;
+        if self.sound.sample_rate == 0 || out_rate == 0 {
+            return (0, true);
+        }
+        let step = self.sound.sample_rate as f64 / out_rate as f64;
+        let mono = self.sound.channels < 2;
+        let mut written = 0;
+        while written < frames {
+            if self.is_finished() {
+                return (written, true);
+            }
+            let index = self.position.floor() as usize;
+            let frac = (self.position - index as f64) as f32;
+            let next = ((index + 1) as f64).min(self.end_frame - 1.0).max(0.0) as usize;
+            let left = lerp(self.sound.sample(index, 0), self.sound.sample(next, 0), frac);
+            let right = lerp(self.sound.sample(index, 1), self.sound.sample(next, 1), frac);
+            let (left, right) = pan_frame(left, right, mono, pan);
+            let g = lerp(gain.0, gain.1, written as f32 / frames as f32);
+            out[written * 2] += left * g;
+            out[written * 2 + 1] += right * g;
+            written += 1;
+            self.position += step;
+        }
+        (written, self.is_finished())
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Equal-power panning as done by Web Audio's StereoPannerNode, so the
+/// headless output matches what the browser plays.
+pub fn pan_frame(left: f32, right: f32, mono: bool, pan: f32) -> (f32, f32) {
+    let pan = pan.clamp(-1.0, 1.0);
+    if mono {
+        let x = (pan + 1.0) / 2.0;
+        return (left * (x * FRAC_PI_2).cos(), left * (x * FRAC_PI_2).sin());
+    }
+    if pan <= 0.0 {
+        let x = pan + 1.0;
+        let (gain_l, gain_r) = ((x * FRAC_PI_2).cos(), (x * FRAC_PI_2).sin());
+        (left + right * gain_l, right * gain_r)
+    } else {
+        let x = pan;
+        let (gain_l, gain_r) = ((x * FRAC_PI_2).cos(), (x * FRAC_PI_2).sin());
+        (left * gain_l, right + left * gain_r)
+    }
+}
+
+pub struct Mixer {
+    output: Box<dyn AudioOutput>,
+    /// Seconds of audio mixed so far; the headless stand-in for
+    /// `AudioContext.currentTime`.
+    clock: Rc<Cell<f64>>,
+    /// Fraction of an output frame carried over between renders.
+    pending_frames: f64,
+}
+
+impl Mixer {
+    pub fn new(output: Box<dyn AudioOutput>) -> Mixer {
+        Mixer {
+            output,
+            clock: Rc::new(Cell::new(0.0)),
+            pending_frames: 0.0,
+        }
+    }
+
+    pub fn clock(&self) -> Rc<Cell<f64>> {
+        self.clock.clone()
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.output.sample_rate()
+    }
+
+    pub fn output(&self) -> &dyn AudioOutput {
+        self.output.as_ref()
+    }
+
+    pub fn output_mut(&mut self) -> &mut dyn AudioOutput {
+        self.output.as_mut()
+    }
+
+    /// Replace the output, returning the previous one.
+    pub fn set_output(&mut self, output: Box<dyn AudioOutput>) -> Box<dyn AudioOutput> {
+        self.pending_frames = 0.0;
+        std::mem::replace(&mut self.output, output)
+    }
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Mixer::new(Box::new(NullAudioOutput::new(DEFAULT_SAMPLE_RATE)))
+    }
+}
+
+/// Mix `duration_ms` of audio from every headless sound channel and write
+/// it to the mixer output.
+pub fn render(player: &mut DirPlayer, duration_ms: f64) {
+    let channels: Vec<_> = (0..player.sound_manager.num_channels())
+        .filter_map(|i| player.sound_manager.get_channel(i))
+        .collect();
+    let mixer = &mut player.sound_manager.mixer;
+    let rate = mixer.sample_rate();
+    let clock = mixer.clock();
+    let exact = mixer.pending_frames + duration_ms.max(0.0) * rate as f64 / 1000.0;
+    let mut remaining = exact.floor() as usize;
+    mixer.pending_frames = exact - remaining as f64;
+
+    let mut block = vec![0.0f32; BLOCK_FRAMES * 2];
+    while remaining > 0 {
+        let frames = remaining.min(BLOCK_FRAMES);
+        let out = &mut block[..frames * 2];
+        out.fill(0.0);
+        for channel in &channels {
+            channel.borrow_mut().mix_headless(player, out, rate);
+        }
+        clock.set(clock.get() + frames as f64 / rate as f64);
+        player.sound_manager.mixer.output.write(out);
+        remaining -= frames;
+    }
+}
@@ -0,0 +1,172 @@
+//! Audio output for DirPlayer
+//!
+//! In the browser, sound channels play through Web Audio. When no
+//! AudioContext is available (native builds, headless tests) the channels are
+//! mixed in Rust by [`mixer`] and the result is handed to an [`AudioOutput`]:
+//! - Null - discards everything (default)
+//! - Buffer - keeps the mixed samples in memory
+//! - WavFile - keeps the mixed samples and writes them to a WAV file on flush
+
+pub mod decode;
+pub mod mixer;
+
+/// Sample rate of the headless mixer output.
+pub const DEFAULT_SAMPLE_RATE: u32 = 44100;
+
+/// Common trait for all audio sinks. Samples are interleaved stereo `f32`.
+pub trait AudioOutput {
+    /// Output sample rate in Hz
+    fn sample_rate(&self) -> u32;
+
+    /// Receive the next block of mixed frames
+    fn write(&mut self, samples: &[f32]);
+
+    /// Persist anything buffered (e.g. write the WAV file). Default: no-op.
+    fn flush(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Samples kept in memory, if this output keeps any
+    fn buffered_samples(&self) -> Option<&[f32]> {
+        None
+    }
+
+    /// Take the samples kept in memory, leaving the buffer empty
+    fn take_samples(&mut self) -> Vec<f32> {
+        Vec::new()
+    }
+
+    /// Get the backend name for debugging
+    fn backend_name(&self) -> &'static str;
+}
+
+pub struct NullAudioOutput {
+    sample_rate: u32,
+}
+
+impl NullAudioOutput {
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate }
+    }
+}
+
+impl AudioOutput for NullAudioOutput {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn write(&mut self, _samples: &[f32]) {}
+
+    fn backend_name(&self) -> &'static str {
+        "null"
+    }
+}
+
+pub struct BufferAudioOutput {
+    sample_rate: u32,
+    samples: Vec<f32>,
+}
+
+impl BufferAudioOutput {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            samples: Vec::new(),
+        }
+    }
+}
+
+impl AudioOutput for BufferAudioOutput {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn write(&mut self, samples: &[f32]) {
+        self.samples.extend_from_slice(samples);
+    }
+
+    fn buffered_samples(&self) -> Option<&[f32]> {
+        Some(&self.samples)
+    }
+
+    fn take_samples(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.samples)
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "buffer"
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct WavFileAudioOutput {
+    path: std::path::PathBuf,
+    buffer: BufferAudioOutput,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl WavFileAudioOutput {
+    pub fn new(path: impl Into<std::path::PathBuf>, sample_rate: u32) -> Self {
+        Self {
+            path: path.into(),
+            buffer: BufferAudioOutput::new(sample_rate),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AudioOutput for WavFileAudioOutput {
+    fn sample_rate(&self) -> u32 {
+        self.buffer.sample_rate()
+    }
+
+    fn write(&mut self, samples: &[f32]) {
+        self.buffer.write(samples);
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        let wav = encode_wav(self.buffer.sample_rate, 2, &self.buffer.samples);
+        std::fs::write(&self.path, wav)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    fn buffered_samples(&self) -> Option<&[f32]> {
+        self.buffer.buffered_samples()
+    }
+
+    fn take_samples(&mut self) -> Vec<f32> {
+        self.buffer.take_samples()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "wav"
+    }
+}
+
+/// Encode interleaved `f32` samples as a 16-bit PCM WAV file.
+pub fn encode_wav(sample_rate: u32, channels: u16, samples: &[f32]) -> Vec<u8> {
+    let bits_per_sample: u16 = 16;
+    let block_align = channels * bits_per_sample / 8;
+    let byte_rate = sample_rate * block_align as u32;
+    let data_len = (samples.len() * 2) as u32;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&channels.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&byte_rate.to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&bits_per_sample.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for &sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * 32767.0).round() as i16;
+        wav.extend_from_slice(&value.to_le_bytes());
+    }
+    wav
+}
@@ -1169,43 +1169,63 @@ pub fn resolve_color_ref(
     match color_ref {
         ColorRef::Rgb(r, g, b) => (*r, *g, *b),
         ColorRef::PaletteIndex(color_index) => {
-            let idx = *color_index;
-            match palette_ref {
-                PaletteRef::BuiltIn(palette) => {
-                    lookup_builtin_palette(palette, idx, original_bit_depth)
-                        .unwrap_or_else(|| color_fallback(idx))
-                }
-                PaletteRef::Member(member_ref) => {
-                    // cast_lib 0 = search all cast libs by member number
-                    let palette_member = if member_ref.cast_lib == 0 {
-                        palettes.find_by_member(member_ref.cast_member as u32)
-                    } else {
-                        let slot_number = CastMemberRefHandlers::get_cast_slot_number(
-                            member_ref.cast_lib as u32,
-                            member_ref.cast_member as u32,
-                        );
-                        palettes.get(slot_number as usize)
-                            .or_else(|| palettes.find_by_member(member_ref.cast_member as u32))
-                    };
-                    if let Some(member) = palette_member {
-                        member.colors.get(idx as usize).copied()
-                            .unwrap_or_else(|| color_fallback(idx))
-                    } else if let Some(member) = palettes.find_by_cast_lib(member_ref.cast_lib as u32) {
-                        // Fallback: exact palette member not found (stale clutId from old numbering),
-                        // use any palette in the same cast library
-                        member.colors.get(idx as usize).copied()
-                            .unwrap_or_else(|| color_fallback(idx))
-                    } else {
-                        lookup_builtin_palette(&get_system_default_palette(), idx, original_bit_depth)
-                            .unwrap_or_else(|| color_fallback(idx))
-                    }
-                }
-                PaletteRef::Default => {
-                    // palette_id=0 means "no specific palette set" - use system default palette
-                    lookup_builtin_palette(&get_system_default_palette(), idx, original_bit_depth)
-                        .unwrap_or_else(|| color_fallback(idx))
-                }
+            let effect = &palettes.effect;
+            if effect.is_identity() {
+                return resolve_palette_index(palettes, *color_index, palette_ref, original_bit_depth);
             }
+            // Colour cycling only rotates 8-bit palette entries
+            let idx = if original_bit_depth == 8 {
+                effect.remap_index(*color_index)
+            } else {
+                *color_index
+            };
+            let color = resolve_palette_index(palettes, idx, palette_ref, original_bit_depth);
+            effect.apply_fade(effect.apply_blend(palette_ref, idx, color))
+        }
+    }
+}
+
+#[inline]
+fn resolve_palette_index(
+    palettes: &PaletteMap,
+    idx: u8,
+    palette_ref: &PaletteRef,
+    original_bit_depth: u8,
+) -> (u8, u8, u8) {
+    match palette_ref {
+        PaletteRef::BuiltIn(palette) => {
+            lookup_builtin_palette(palette, idx, original_bit_depth)
+                .unwrap_or_else(|| color_fallback(idx))
+        }
+        PaletteRef::Member(member_ref) => {
+            // cast_lib 0 = search all cast libs by member number
+            let palette_member = if member_ref.cast_lib == 0 {
+                palettes.find_by_member(member_ref.cast_member as u32)
+            } else {
+                let slot_number = CastMemberRefHandlers::get_cast_slot_number(
+                    member_ref.cast_lib as u32,
+                    member_ref.cast_member as u32,
+                );
+                palettes.get(slot_number as usize)
+                    .or_else(|| palettes.find_by_member(member_ref.cast_member as u32))
+            };
+            if let Some(member) = palette_member {
+                member.colors.get(idx as usize).copied()
+                    .unwrap_or_else(|| color_fallback(idx))
+            } else if let Some(member) = palettes.find_by_cast_lib(member_ref.cast_lib as u32) {
+                // Fallback: exact palette member not found (stale clutId from old numbering),
+                // use any palette in the same cast library
+                member.colors.get(idx as usize).copied()
+                    .unwrap_or_else(|| color_fallback(idx))
+            } else {
+                lookup_builtin_palette(&get_system_default_palette(), idx, original_bit_depth)
+                    .unwrap_or_else(|| color_fallback(idx))
+            }
+        }
+        PaletteRef::Default => {
+            // palette_id=0 means "no specific palette set" - use system default palette
+            lookup_builtin_palette(&get_system_default_palette(), idx, original_bit_depth)
+                .unwrap_or_else(|| color_fallback(idx))
         }
     }
 }
@@ -1,15 +1,83 @@
 use fxhash::FxHashMap;
 
 use crate::player::cast_member::PaletteMember;
+use crate::player::bitmap::bitmap::PaletteRef;
 
+#[derive(Clone)]
 pub struct PaletteEntry {
     pub number: u32,
     pub member: PaletteMember,
 }
 
+/// A palette being blended in by `puppetPalette` with a non-zero speed.
+/// `from` holds the colours of the palette it replaces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaletteBlend {
+    pub palette: PaletteRef,
+    pub from: Vec<(u8, u8, u8)>,
+    /// Weight of the new palette, 0.0-1.0.
+    pub amount: f32,
+}
+
+/// Screen-wide palette animation applied to indexed colours: colour cycling
+/// ranges, fades to black or white and palette transitions.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PaletteEffect {
+    /// (first index, last index, rotation) for each cycling range.
+    pub cycles: Vec<(u8, u8, u8)>,
+    /// Fade target colour and how far along the fade is, 0.0-1.0.
+    pub fade: Option<((u8, u8, u8), f32)>,
+    pub blend: Option<PaletteBlend>,
+}
+
+impl PaletteEffect {
+    pub fn is_identity(&self) -> bool {
+        self.cycles.is_empty() && self.fade.is_none() && self.blend.is_none()
+    }
+
+    /// The palette index whose colour is shown at `index` once cycling
+    /// ranges have rotated.
+    pub fn remap_index(&self, index: u8) -> u8 {
+        let mut index = index;
+        for &(first, last, offset) in &self.cycles {
+            if first < last && index >= first && index <= last {
+                let len = (last - first) as u16 + 1;
+                index = first + (((index - first) as u16 + offset as u16) % len) as u8;
+            }
+        }
+        index
+    }
+
+    pub fn apply_blend(&self, palette_ref: &PaletteRef, index: u8, color: (u8, u8, u8)) -> (u8, u8, u8) {
+        match &self.blend {
+            Some(blend) if blend.palette == *palette_ref => match blend.from.get(index as usize) {
+                Some(from) => mix(*from, color, blend.amount),
+                None => color,
+            },
+            _ => color,
+        }
+    }
+
+    pub fn apply_fade(&self, color: (u8, u8, u8)) -> (u8, u8, u8) {
+        match self.fade {
+            Some((target, amount)) => mix(color, target, amount),
+            None => color,
+        }
+    }
+}
+
+#[inline]
+fn mix(from: (u8, u8, u8), to: (u8, u8, u8), amount: f32) -> (u8, u8, u8) {
+    let amount = amount.clamp(0.0, 1.0);
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+    (channel(from.0, to.0), channel(from.1, to.1), channel(from.2, to.2))
+}
+
+#[derive(Clone)]
 pub struct PaletteMap {
     pub palettes: Vec<PaletteEntry>,
     lookup: FxHashMap<u32, usize>,
+    pub effect: PaletteEffect,
 }
 
 impl PaletteMap {
@@ -17,6 +85,7 @@ impl PaletteMap {
         Self {
             palettes: Vec::new(),
             lookup: FxHashMap::default(),
+            effect: PaletteEffect::default(),
         }
     }
 
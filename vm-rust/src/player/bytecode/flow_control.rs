@@ -3,7 +3,7 @@ use crate::{
     player::{
         HandlerExecutionResult, PLAYER_OPT, ScriptError, compare::datum_is_zero, datum_formatting::format_datum, datum_ref::DatumRef, handlers::datum_handlers::{
             player_call_datum_handler, script_instance::ScriptInstanceUtils,
-        }, player_call_script_handler_raw_args, player_ext_call, player_handle_scope_return, reserve_player_mut, reserve_player_ref, script::{get_current_handler_def, get_current_script, get_name}
+        }, player_call_script_handler_raw_args, player_ext_call, player_handle_scope_return, reserve_player_mut, reserve_player_ref, script::{get_current_handler_def, get_current_script, get_name}, window
     },
 };
 
@@ -295,6 +295,35 @@ impl FlowControlBytecodeHandler {
         })
     }
 
+    /// `tell <window>` blocks. The body runs with the target window's movie
+    /// (or the stage's, for `tell the stage`) swapped in until `end tell`.
+    pub fn start_tell(ctx: &BytecodeHandlerContext) -> Result<HandlerExecutionResult, ScriptError> {
+        reserve_player_mut(|player| {
+            let scope = player.scopes.get_mut(ctx.scope_ref).unwrap();
+            let target = scope.stack.pop().unwrap_or(DatumRef::Void);
+            match player.get_datum(&target).clone() {
+                Datum::Window(name) => {
+                    let id = player
+                        .windows
+                        .find(&name)
+                        .ok_or_else(|| ScriptError::new(format!("Window \"{}\" does not exist", name)))?
+                        .id;
+                    window::enter_window(player, id, Some(ctx.scope_ref))?;
+                }
+                Datum::Stage => window::enter_stage(player, Some(ctx.scope_ref)),
+                // Other targets keep the current movie; push a frame anyway
+                // so `end tell` stays balanced.
+                _ => window::enter_current(player, Some(ctx.scope_ref)),
+            }
+            Ok(HandlerExecutionResult::Advance)
+        })
+    }
+
+    pub fn end_tell(ctx: &BytecodeHandlerContext) -> Result<HandlerExecutionResult, ScriptError> {
+        reserve_player_mut(|player| window::leave_tell(player, ctx.scope_ref));
+        Ok(HandlerExecutionResult::Advance)
+    }
+
     pub fn call_javascript(
         ctx: &BytecodeHandlerContext,
     ) -> Result<HandlerExecutionResult, ScriptError> {
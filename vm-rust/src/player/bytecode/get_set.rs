@@ -9,6 +9,7 @@ use crate::{
     },
     player::{
         allocator::{DatumAllocatorTrait, ScriptInstanceAllocatorTrait},
+        debug::watch,
         handlers::datum_handlers::{
             cast_member_ref::CastMemberRefHandlers,
             string_chunk::StringChunkUtils,
@@ -446,7 +447,7 @@ impl GetSetBytecodeHandler {
                 _ => value_ref,
             };
 
-            player.globals.insert(prop_name.to_owned(), value_ref);
+            watch::set_global(player, prop_name.to_owned(), value_ref);
             Ok(HandlerExecutionResult::Advance)
         })
     }
@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use async_recursion::async_recursion;
 
 use crate::{
@@ -167,17 +169,15 @@ pub struct BytecodeHandlerContext {
     pub scope_ref: ScopeRef,
     pub handler_def_ptr: *const HandlerDef,
     pub script_ptr: *const Script,
-    pub names_ptr: *const Vec<String>,
+    pub names: Rc<Vec<String>>,
+    pub variable_multiplier: u32,
 }
 
 impl BytecodeHandlerContext {
     /// Get a name from the name table by ID without borrowing player.
     #[inline(always)]
     pub fn get_name(&self, name_id: u16) -> &str {
-        unsafe {
-            let names = &*self.names_ptr;
-            &names[name_id as usize]
-        }
+        &self.names[name_id as usize]
     }
 }
 pub struct StaticBytecodeHandlerManager {}
@@ -252,6 +252,8 @@ impl StaticBytecodeHandlerManager {
             OpCode::OntoSpr => SpriteCompareBytecodeHandler::onto_sprite(ctx),
             OpCode::IntoSpr => SpriteCompareBytecodeHandler::into_sprite(ctx),
             OpCode::CallJavaScript => FlowControlBytecodeHandler::call_javascript(ctx),
+            OpCode::StartTell => FlowControlBytecodeHandler::start_tell(ctx),
+            OpCode::EndTell => FlowControlBytecodeHandler::end_tell(ctx),
             _ => {
                 let prim = num::ToPrimitive::to_u16(&opcode).unwrap();
                 let name = get_opcode_name(opcode);
@@ -266,6 +268,7 @@ impl StaticBytecodeHandlerManager {
         match opcode {
             OpCode::NewObj => true,
             OpCode::ExtCall => true,
+            OpCode::TellCall => true,
             OpCode::ObjCall => true,
             OpCode::ObjCallV4 => true,
             OpCode::LocalCall => true,
@@ -281,7 +284,7 @@ impl StaticBytecodeHandlerManager {
     ) -> Result<HandlerExecutionResult, ScriptError> {
         match opcode {
             OpCode::NewObj => StackBytecodeHandler::new_obj(&ctx).await,
-            OpCode::ExtCall => FlowControlBytecodeHandler::ext_call(&ctx).await,
+            OpCode::ExtCall | OpCode::TellCall => FlowControlBytecodeHandler::ext_call(ctx).await,
             OpCode::ObjCall => FlowControlBytecodeHandler::obj_call(&ctx).await,
             OpCode::ObjCallV4 => FlowControlBytecodeHandler::obj_call_v4(&ctx).await,
             OpCode::LocalCall => FlowControlBytecodeHandler::local_call(&ctx).await,
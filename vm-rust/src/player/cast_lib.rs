@@ -6,8 +6,8 @@ use url::Url;
 use crate::{
     director::{
         cast::CastDef, enums::{ScriptType, BitmapInfo},
-        file::{read_director_file_bytes, DirectorFile},
-        lingo::{datum::Datum, script::ScriptContext},
+        file::{get_variable_multiplier, read_director_file_bytes, DirectorFile},
+        lingo::{compiler::compile_script, datum::Datum, script::ScriptContext},
     },
     js_api::JsApi,
     utils::{get_base_url, get_basename_no_extension, log_i},
@@ -378,6 +378,44 @@ impl CastLib {
         self.members.insert(number, member);
     }
 
+    /// Compile `text` and make it the script of member `number`, as setting
+    /// `the scriptText of member` does.
+    pub fn set_script_text(&mut self, number: u32, text: &str, dir_version: u16) -> Result<(), ScriptError> {
+        let mut member = match self.members.get(&number) {
+            Some(member) if matches!(member.member_type, CastMemberType::Script(_)) => member.clone(),
+            _ => return Err(ScriptError::new(format!("Member {} is not a script", number))),
+        };
+        let multiplier = get_variable_multiplier(self.capital_x, self.dir_version);
+        let lctx = self.lctx.get_or_insert_with(|| ScriptContext {
+            names: Rc::default(),
+            scripts: HashMap::new(),
+        });
+        // Handlers running from this cast hold their own reference to the
+        // names, so make_mut copies the table instead of growing it under them.
+        let mut chunk = compile_script(text, Rc::make_mut(&mut lctx.names), dir_version, multiplier)
+            .map_err(|err| ScriptError::new(format!("Script error in member {}: {}", number, err)))?;
+        // A fresh id keeps the replaced script distinguishable from the one
+        // in the file, which `saveMovie` relies on. The script the member
+        // had before is dropped so repeated edits don't grow the context.
+        let script_id = lctx.scripts.keys().max().map_or(1, |id| id + 1);
+        chunk.script_number = script_id as u16;
+        lctx.scripts.insert(script_id, chunk);
+
+        if let CastMemberType::Script(script_member) = &mut member.member_type {
+            let previous_id = std::mem::replace(&mut script_member.script_id, script_id);
+            script_member.script_text = text.to_string();
+            let shared = self.members.iter().any(|(other, member)| {
+                *other != number
+                    && matches!(&member.member_type, CastMemberType::Script(other) if other.script_id == previous_id)
+            });
+            if !shared {
+                lctx.scripts.remove(&previous_id);
+            }
+        }
+        self.insert_member(number, member);
+        Ok(())
+    }
+
     pub fn create_member_at(
         &mut self,
         number: u32,
@@ -424,6 +462,7 @@ impl CastLib {
                     script_id: 0,
                     script_type: ScriptType::Movie,
                     name: String::new(),
+                    script_text: String::new(),
                 }),
             )),
             _ => Err(ScriptError::new(format!(
@@ -21,7 +21,7 @@ use crate::player::font::FontRef;
 
 use super::{
     allocator::DatumAllocator,
-    bitmap::{bitmap::PaletteRef, manager::{BitmapManager, BitmapRef}, palette_map::PaletteMap},
+    bitmap::{bitmap::PaletteRef, manager::{BitmapManager, BitmapRef}, palette_map::{PaletteEffect, PaletteMap}},
     cast_lib::{CastLibState, CastMemberRef, INVALID_CAST_MEMBER_REF},
     cast_member::{CastMember, CastMemberType},
     handlers::datum_handlers::cast_member_ref::CastMemberRefHandlers,
@@ -38,6 +38,10 @@ pub struct CastManager {
     /// Version counter incremented when palette cache is invalidated.
     /// Used by renderers to know when to clear texture caches.
     pub palette_version: RefCell<u32>,
+    /// Palette animation (cycling, fades, puppetPalette transitions) applied
+    /// to the palettes handed to the renderers.
+    pub palette_effect: RefCell<PaletteEffect>,
+    pub screen_palette_cache: RefCell<Option<Rc<PaletteMap>>>,
     /// Cast-member refs whose textures must be evicted from renderer caches
     /// before the next frame. Populated when a member is erased or its slot
     /// reassigned; drained by the renderer at frame start.
@@ -60,6 +64,12 @@ impl CastManager {
             member_name_cache: RefCell::new(None),
             palette_cache: RefCell::new(None),
             palette_version: RefCell::new(0),
+            palette_effect: RefCell::new(PaletteEffect {
+                cycles: Vec::new(),
+                fade: None,
+                blend: None,
+            }),
+            screen_palette_cache: RefCell::new(None),
             pending_texture_invalidations: RefCell::new(Vec::new()),
         }
     }
@@ -311,6 +321,7 @@ impl CastManager {
 
     pub fn invalidate_palette_cache(&self) {
         self.palette_cache.replace(None);
+        self.screen_palette_cache.replace(None);
         // Increment version counter so renderers know to clear texture caches
         *self.palette_version.borrow_mut() += 1;
     }
@@ -338,6 +349,31 @@ impl CastManager {
         self.palette_cache.borrow().as_ref().unwrap().clone()
     }
 
+    /// Palettes as shown on screen: `palettes()` with the current palette
+    /// effect applied to indexed colours. Lingo pixel access keeps using
+    /// `palettes()` so it sees the authored colours.
+    pub fn screen_palettes(&self) -> Rc<PaletteMap> {
+        if self.palette_effect.borrow().is_identity() {
+            return self.palettes();
+        }
+        let has_cache = self.screen_palette_cache.borrow().is_some();
+        if !has_cache {
+            let mut result = (*self.palettes()).clone();
+            result.effect = self.palette_effect.borrow().clone();
+            self.screen_palette_cache.replace(Some(Rc::new(result)));
+        }
+        self.screen_palette_cache.borrow().as_ref().unwrap().clone()
+    }
+
+    pub fn set_palette_effect(&self, effect: PaletteEffect) {
+        if *self.palette_effect.borrow() == effect {
+            return;
+        }
+        self.palette_effect.replace(effect);
+        self.screen_palette_cache.replace(None);
+        *self.palette_version.borrow_mut() += 1;
+    }
+
     pub fn find_member_ref_by_name(&self, name: &str) -> Option<CastMemberRef> {
         if self.member_name_cache.borrow().is_none() {
             let mut cache = FxHashMap::default();
@@ -16,9 +16,9 @@ use super::{
     ScriptError,
 };
 use crate::director::{
-    chunks::{cast_member::CastMemberDef, score::{ScoreChunk, ScoreChunkHeader, ScoreFrameData}, xmedia::PfrFont, xmedia::XMediaChunk, sound::SoundChunk, Chunk, cast_member::CastMemberChunk},
+    chunks::{cast_member::CastMemberDef, score::{ScoreChunk, ScoreChunkHeader, ScoreFrameData}, xmedia::PfrFont, xmedia::XMediaChunk, sound::SoundChunk, cue_points::CuePointsChunk, Chunk, cast_member::CastMemberChunk},
     enums::{
-        BitmapInfo, FilmLoopInfo, FontInfo, MemberType, ScriptType, ShapeInfo, Shockwave3dInfo, TextMemberData, SoundInfo, FieldInfo, TextInfo,
+        BitmapInfo, FilmLoopInfo, FontInfo, MemberType, ScriptType, ShapeInfo, Shockwave3dInfo, TextMemberData, SoundInfo, FieldInfo, TextInfo, TransitionInfo,
     },
     lingo::script::ScriptContext,
 };
@@ -156,6 +156,8 @@ pub struct TextMember {
     /// given text offset is the one whose `position` is the largest
     /// value ≤ that offset. See `line_spacing_at()` helper.
     pub par_runs: Vec<crate::director::chunks::xmedia_styled_text::ParRun>,
+    /// Hypertext links over `text`, kept sorted by start position.
+    pub hyperlinks: Vec<crate::player::hyperlinks::TextHyperlink>,
     pub info: Option<TextInfo>,
     /// Embedded 3D world for Director's "3D Text" feature (text extrusion).
     /// Lazily initialized when 3D methods (.model(), .camera(), etc.) are called.
@@ -341,6 +343,7 @@ impl TextMember {
             html_styled_spans: Vec::new(),
             par_infos: Vec::new(),
             par_runs: Vec::new(),
+            hyperlinks: Vec::new(),
             info: None,
             w3d: None,
             sel_start: 0,
@@ -603,6 +606,8 @@ pub struct ScriptMember {
     pub script_id: u32,
     pub script_type: ScriptType,
     pub name: String,
+    /// Lingo source, when the movie was saved with it or it was set at runtime
+    pub script_text: String,
 }
 
 #[derive(Clone, Default)]
@@ -619,6 +624,11 @@ pub struct PaletteMember {
     pub colors: Vec<(u8, u8, u8)>,
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct TransitionMember {
+    pub info: TransitionInfo,
+}
+
 // `VectorShapeVertex` is defined in `crate::director::enums` and re-exported
 // here so existing call sites (rasterizer, Lingo handlers) can continue to
 // import it from `cast_member::*`. The FLSH payload parser is also there
@@ -795,10 +805,41 @@ pub struct FilmLoopMember {
     pub cached_total_frames: Option<u32>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoundCuePoint {
+    pub time_ms: u32,
+    pub name: String,
+}
+
 #[derive(Clone)]
 pub struct SoundMember {
     pub info: SoundInfo,
     pub sound: SoundChunk,
+    /// Cue points from the member's `cupt` chunk, or from markers embedded
+    /// in its media, sorted by time.
+    pub cue_points: Vec<SoundCuePoint>,
+}
+
+impl SoundMember {
+    /// Convert the sample-frame offsets of a `cupt` chunk to milliseconds.
+    /// Embedded cue points use the rate of the data they were read from.
+    pub fn cue_points_from_chunk(chunk: &CuePointsChunk, sample_rate: u32) -> Vec<SoundCuePoint> {
+        let sample_rate = chunk.sample_rate.unwrap_or(sample_rate);
+        let mut points: Vec<SoundCuePoint> = chunk
+            .points
+            .iter()
+            .map(|point| SoundCuePoint {
+                time_ms: if sample_rate > 0 {
+                    (point.time as u64 * 1000 / sample_rate as u64) as u32
+                } else {
+                    point.time
+                },
+                name: point.name.clone(),
+            })
+            .collect();
+        points.sort_by_key(|point| point.time_ms);
+        points
+    }
 }
 
 #[derive(Clone)]
@@ -971,6 +1012,14 @@ pub struct Shockwave3dRuntimeState {
     // ─── Subdivision Surface (SDS) state ───
     pub sds_state: std::collections::HashMap<String, SdsState>,
 
+    // ─── Collision modifier state ───
+    /// Per-model #collision modifier properties: model_name -> state
+    pub collision_state: std::collections::HashMap<String, CollisionState>,
+    /// member.registerForEvent(#collideAny, ...) handlers: (handler_name, script_object)
+    pub collide_any_handlers: Vec<(String, crate::player::DatumRef)>,
+    /// Collisions found by the last detection pass; collisionData objects index into this
+    pub collision_events: Vec<CollisionEvent>,
+
     // ─── Reset tracking ───
     pub world_reset: bool,
 
@@ -1074,6 +1123,42 @@ impl Default for SdsState {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct CollisionState {
+    pub enabled: bool,
+    pub resolve: bool,
+    pub immovable: bool,
+    pub mode: crate::director::chunks::w3d::collision::CollisionMode,
+    /// collision.setCollisionCallback / registerScript(#collideWith): (handler_name, script_object)
+    pub callback: Option<(String, crate::player::DatumRef)>,
+}
+
+impl Default for CollisionState {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            resolve: true,
+            immovable: false,
+            mode: Default::default(),
+            callback: None,
+        }
+    }
+}
+
+/// A collision between two models, exposed to Lingo as a collisionData object.
+#[derive(Clone, Debug)]
+pub struct CollisionEvent {
+    pub model_a: String,
+    pub model_b: String,
+    pub point: [f32; 3],
+    /// Points from model A towards model B
+    pub normal: [f32; 3],
+    pub depth: f32,
+    /// Whether model A / B is moved out of the collision (collisionData.resolveA/B)
+    pub resolve_a: bool,
+    pub resolve_b: bool,
+}
+
 /// Runtime mesh deform state for a model
 #[derive(Clone, Debug, Default)]
 pub struct MeshDeformState {
@@ -1744,6 +1829,7 @@ pub enum CastMemberType {
     Script(ScriptMember),
     Bitmap(BitmapMember),
     Palette(PaletteMember),
+    Transition(TransitionMember),
     Shape(ShapeMember),
     VectorShape(VectorShapeMember),
     FilmLoop(FilmLoopMember),
@@ -1763,6 +1849,7 @@ pub enum CastMemberTypeId {
     Script,
     Bitmap,
     Palette,
+    Transition,
     Shape,
     VectorShape,
     FilmLoop,
@@ -1795,6 +1882,9 @@ impl fmt::Debug for CastMemberType {
             Self::Palette(_) => {
                 write!(f, "Palette")
             }
+            Self::Transition(_) => {
+                write!(f, "Transition")
+            }
             Self::Shape(_) => {
                 write!(f, "Shape")
             }
@@ -1835,6 +1925,7 @@ impl CastMemberTypeId {
             Self::Script => Ok("script"),
             Self::Bitmap => Ok("bitmap"),
             Self::Palette => Ok("palette"),
+            Self::Transition => Ok("transition"),
             Self::Shape => Ok("shape"),
             Self::VectorShape => Ok("vectorShape"),
             Self::FilmLoop => Ok("filmLoop"),
@@ -1857,6 +1948,7 @@ impl CastMemberType {
             Self::Script(_) => CastMemberTypeId::Script,
             Self::Bitmap(_) => CastMemberTypeId::Bitmap,
             Self::Palette(_) => CastMemberTypeId::Palette,
+            Self::Transition(_) => CastMemberTypeId::Transition,
             Self::Shape(_) => CastMemberTypeId::Shape,
             Self::VectorShape(_) => CastMemberTypeId::VectorShape,
             Self::FilmLoop(_) => CastMemberTypeId::FilmLoop,
@@ -1877,6 +1969,7 @@ impl CastMemberType {
             Self::Script(_) => "script",
             Self::Bitmap(_) => "bitmap",
             Self::Palette(_) => "palette",
+            Self::Transition(_) => "transition",
             Self::Shape(_) => "shape",
             Self::VectorShape(_) => "vectorShape",
             Self::FilmLoop(_) => "filmLoop",
@@ -1960,6 +2053,20 @@ impl CastMemberType {
         };
     }
 
+    pub fn as_transition(&self) -> Option<&TransitionMember> {
+        match self {
+            Self::Transition(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn as_transition_mut(&mut self) -> Option<&mut TransitionMember> {
+        match self {
+            Self::Transition(data) => Some(data),
+            _ => None,
+        }
+    }
+
     pub fn as_film_loop(&self) -> Option<&FilmLoopMember> {
         return match self {
             Self::FilmLoop(data) => Some(data),
@@ -2054,6 +2161,8 @@ impl CastMember {
             Chunk::CstInfo(_) => "Cinf",
             Chunk::Effect(_) => "FXmp",
             Chunk::Thum(_) => "Thum",
+            Chunk::CuePoints(_) => "cupt",
+            Chunk::XtraList(_) => "XTRl",
             Chunk::Raw(_) => "Raw",
         }
     }
@@ -3393,6 +3502,11 @@ impl CastMember {
             tab_stops: Vec::new(),
             par_infos: styled_text.par_infos.clone(),
             par_runs: styled_text.par_runs.clone(),
+            hyperlinks: crate::player::hyperlinks::hyperlinks_from_ranges(
+                styled_text.hyperlinks.iter()
+                    .map(|link| (link.start as usize, link.end as usize, link.data.clone()))
+                    .collect(),
+            ),
             html_styled_spans: styled_text.styled_spans,
             info: Some(text_info),
             w3d: None,
@@ -3709,6 +3823,7 @@ impl CastMember {
                         script_id,
                         script_type,
                         name: member_info.name.clone(),
+                        script_text: member_info.script_src_text.clone(),
                     })
                 } else {
                     web_sys::console::warn_1(&format!("Script member {}: script_id {} not found in Lctx, skipping", number, script_id).into());
@@ -4091,6 +4206,14 @@ impl CastMember {
                     colors: palette_chunk.colors.clone(),
                 })
             }
+            MemberType::Transition => {
+                let info = chunk
+                    .specific_data
+                    .transition_info()
+                    .cloned()
+                    .unwrap_or_default();
+                CastMemberType::Transition(TransitionMember { info })
+            }
             MemberType::Shape => {
                 let script_id = chunk
                     .member_info
@@ -4311,15 +4434,26 @@ impl CastMember {
                         info.duration
                     );
 
+                    let cue_points = member_def
+                        .children
+                        .iter()
+                        .find_map(|c| c.as_ref().and_then(|ch| ch.as_cue_points()))
+                        .cloned()
+                        .or_else(|| CuePointsChunk::from_sound_data(&sound_chunk.data()))
+                        .map(|cupt| SoundMember::cue_points_from_chunk(&cupt, info.sample_rate))
+                        .unwrap_or_default();
+
                     CastMemberType::Sound(SoundMember {
                         info,
                         sound: sound_chunk,
+                        cue_points,
                     })
                 } else {
                     warn!("No sound chunk found for member {}", number);
                     CastMemberType::Sound(SoundMember {
                         info: SoundInfo::default(),
                         sound: SoundChunk::default(),
+                        cue_points: vec![],
                     })
                 }
             }
@@ -0,0 +1,182 @@
+use log::warn;
+
+use crate::director::chunks::w3d::collision::{
+    detect_contact, model_collision_shape, translated_local_transform, CollisionShape,
+};
+use crate::director::chunks::w3d::types::W3dNodeType;
+use crate::director::lingo::datum::{Datum, Shockwave3dObjectRef};
+use crate::player::cast_lib::CastMemberRef;
+use crate::player::cast_member::CollisionEvent;
+use crate::player::events::{player_invoke_global_event, player_wait_available};
+use crate::player::handlers::datum_handlers::player_call_datum_handler;
+use crate::player::handlers::datum_handlers::shockwave3d_object::{
+    set_node_transform, sync_persistent_transforms,
+};
+use crate::player::{reserve_player_mut, DatumRef, DirPlayer, ScriptErrorCode};
+
+struct CollisionCallback {
+    member_ref: CastMemberRef,
+    event_index: usize,
+    handler: String,
+    target: DatumRef,
+}
+
+/// Run collision detection for every Shockwave 3D member with models that
+/// carry the #collision modifier, then notify scripts and resolve contacts.
+///
+/// Each colliding pair becomes a collisionData object. Handlers registered
+/// with `member.registerForEvent(#collideAny, ...)` are called for every
+/// collision, then the #collideWith callbacks of model A and model B
+/// (`collision.setCollisionCallback` / `model.registerScript`). Handlers can
+/// change `resolveA`/`resolveB` before the models are pushed apart.
+pub async fn dispatch_collisions() {
+    let callbacks = reserve_player_mut(detect_collisions);
+    if callbacks.is_empty() {
+        return;
+    }
+
+    for callback in callbacks {
+        let args = reserve_player_mut(|player| {
+            vec![player.alloc_datum(Datum::Shockwave3dObjectRef(Shockwave3dObjectRef {
+                cast_lib: callback.member_ref.cast_lib,
+                cast_member: callback.member_ref.cast_member,
+                object_type: "collisionData".to_string(),
+                name: callback.event_index.to_string(),
+            }))]
+        });
+        let is_movie_script = reserve_player_mut(|player| {
+            matches!(player.get_datum(&callback.target), Datum::Void | Datum::Int(0))
+        });
+        let result = if is_movie_script {
+            player_invoke_global_event(&callback.handler, &args).await
+        } else {
+            player_call_datum_handler(&callback.target, &callback.handler, &args).await
+        };
+        if let Err(err) = result
+            && err.code != ScriptErrorCode::HandlerNotFound
+        {
+            warn!("Collision handler '{}' error: {}", callback.handler, err.message);
+        }
+        player_wait_available().await;
+    }
+
+    reserve_player_mut(resolve_collisions);
+}
+
+/// Detect collisions and store them in each member's runtime state.
+/// Returns the script callbacks to invoke, in dispatch order.
+fn detect_collisions(player: &mut DirPlayer) -> Vec<CollisionCallback> {
+    let mut member_refs = Vec::new();
+    for cast in &player.movie.cast_manager.casts {
+        for (member_num, member) in &cast.members {
+            let Some(w3d) = member.member_type.as_shockwave3d() else { continue; };
+            let rs = &w3d.runtime_state;
+            if !rs.collision_state.is_empty() || !rs.collision_events.is_empty() {
+                member_refs.push(CastMemberRef { cast_lib: cast.number as i32, cast_member: *member_num as i32 });
+            }
+        }
+    }
+    if member_refs.is_empty() {
+        return vec![];
+    }
+    sync_persistent_transforms(player);
+
+    let mut callbacks = Vec::new();
+    for member_ref in member_refs {
+        let Some(w3d) = player.movie.cast_manager.find_mut_member_by_ref(&member_ref)
+            .and_then(|m| m.member_type.as_shockwave3d_mut())
+        else {
+            continue;
+        };
+        let rs = &mut w3d.runtime_state;
+        rs.collision_events.clear();
+        let Some(scene) = w3d.parsed_scene.as_ref() else { continue; };
+
+        let mut shapes: Vec<(String, CollisionShape)> = Vec::new();
+        for node in scene.nodes.iter().filter(|n| n.node_type == W3dNodeType::Model) {
+            let Some(state) = rs.collision_state.get(&node.name) else { continue; };
+            if !state.enabled || rs.detached_nodes.contains(&node.name) {
+                continue;
+            }
+            if let Some(shape) = model_collision_shape(scene, node, Some(&rs.node_transforms), state.mode) {
+                shapes.push((node.name.clone(), shape));
+            }
+        }
+
+        for i in 0..shapes.len() {
+            for j in (i + 1)..shapes.len() {
+                let Some(contact) = detect_contact(&shapes[i].1, &shapes[j].1) else { continue; };
+                let (model_a, model_b) = (&shapes[i].0, &shapes[j].0);
+                let state_a = &rs.collision_state[model_a];
+                let state_b = &rs.collision_state[model_b];
+                let event_index = rs.collision_events.len();
+                rs.collision_events.push(CollisionEvent {
+                    model_a: model_a.clone(),
+                    model_b: model_b.clone(),
+                    point: contact.point,
+                    normal: contact.normal,
+                    depth: contact.depth,
+                    resolve_a: state_a.resolve,
+                    resolve_b: state_b.resolve,
+                });
+
+                let model_callbacks = [&state_a.callback, &state_b.callback];
+                for (handler, target) in rs.collide_any_handlers.iter().chain(model_callbacks.into_iter().flatten()) {
+                    callbacks.push(CollisionCallback {
+                        member_ref: member_ref.clone(),
+                        event_index,
+                        handler: handler.clone(),
+                        target: target.clone(),
+                    });
+                }
+            }
+        }
+    }
+    callbacks
+}
+
+/// Push resolved models out of each other along the collision normal.
+/// Immovable models never move; when both models move they share the
+/// penetration depth.
+fn resolve_collisions(player: &mut DirPlayer) {
+    let mut moves: Vec<(CastMemberRef, String, [f32; 16])> = Vec::new();
+    for cast in &player.movie.cast_manager.casts {
+        for (member_num, member) in &cast.members {
+            let Some(w3d) = member.member_type.as_shockwave3d() else { continue; };
+            let rs = &w3d.runtime_state;
+            let Some(scene) = w3d.parsed_scene.as_ref() else { continue; };
+            let member_ref = CastMemberRef { cast_lib: cast.number as i32, cast_member: *member_num as i32 };
+            let mut node_transforms = rs.node_transforms.clone();
+
+            for event in &rs.collision_events {
+                let movable = |name: &str, resolve: bool| {
+                    resolve && rs.collision_state.get(name).is_some_and(|s| !s.immovable)
+                };
+                let move_a = movable(&event.model_a, event.resolve_a);
+                let move_b = movable(&event.model_b, event.resolve_b);
+                let share = match (move_a, move_b) {
+                    (true, true) => event.depth * 0.5,
+                    (false, false) => continue,
+                    _ => event.depth,
+                };
+                let n = event.normal;
+                let targets = [
+                    (move_a, &event.model_a, [-n[0] * share, -n[1] * share, -n[2] * share]),
+                    (move_b, &event.model_b, [n[0] * share, n[1] * share, n[2] * share]),
+                ];
+                for (should_move, name, delta) in targets {
+                    if !should_move {
+                        continue;
+                    }
+                    let Some(node) = scene.nodes.iter().find(|n| n.name == *name) else { continue; };
+                    let local = translated_local_transform(scene, node, Some(&node_transforms), delta);
+                    node_transforms.insert(name.clone(), local);
+                    moves.push((member_ref.clone(), name.clone(), local));
+                }
+            }
+        }
+    }
+    for (member_ref, name, transform) in moves {
+        set_node_transform(player, &member_ref, &name, transform);
+    }
+}
@@ -1,7 +1,6 @@
 use std::collections::HashMap;
 
 use async_std::channel::Receiver;
-use chrono::Local;
 use log::{warn, debug};
 use manual_future::ManualFuture;
 use url::Url;
@@ -11,7 +10,7 @@ use crate::{
     director::lingo::datum::{Datum, TimeoutRef},
     js_api::JsApi,
     player::PLAYER_OPT,
-    utils::ToHexString,
+    utils::{local_now, ToHexString},
 };
 
 use super::{
@@ -23,15 +22,19 @@ use super::{
         player_dispatch_callback_event, player_dispatch_event_to_sprite,
         player_dispatch_movie_callback, player_wait_available,
         player_dispatch_event_to_sprite_targeted, player_invoke_frame_and_movie_scripts,
+        player_invoke_global_event, player_invoke_sprite_mouse_event,
     },
     font::player_load_system_font,
     keyboard_events::{player_key_down, player_key_up},
     handlers::datum_handlers::player_call_datum_handler,
+    hyperlinks,
     player_alloc_datum, player_call_script_handler, player_dispatch_global_event,
     player_is_playing, reserve_player_mut, reserve_player_ref,
+    replay::{self, ReplayInput},
     score::{concrete_sprite_hit_test, get_concrete_sprite_rect, get_sprite_at},
     script_ref::ScriptInstanceRef,
-    PlayerVMExecutionItem, ScriptError, ScriptReceiver, PLAYER_TX,
+    tempo_wait, window,
+    DirPlayer, PlayerVMExecutionItem, ScriptError, ScriptReceiver, PLAYER_TX,
 };
 
 #[allow(dead_code)]
@@ -48,6 +51,12 @@ pub enum PlayerVMCommand {
     MouseDown((i32, i32)),
     MouseUp((i32, i32)),
     MouseMove((i32, i32)),
+    RightMouseDown((i32, i32)),
+    RightMouseUp((i32, i32)),
+    /// The page hosting the player gained focus.
+    ActivateApplication,
+    /// The page hosting the player lost focus.
+    DeactivateApplication,
     KeyDown(String, u16),
     KeyUp(String, u16),
     TriggerAlertHook,
@@ -91,6 +100,10 @@ pub fn _format_player_cmd(command: &PlayerVMCommand) -> String {
         PlayerVMCommand::MouseDown((x, y)) => format!("MouseDown({}, {})", x, y),
         PlayerVMCommand::MouseUp((x, y)) => format!("MouseUp({}, {})", x, y),
         PlayerVMCommand::MouseMove((x, y)) => format!("MouseMove({}, {})", x, y),
+        PlayerVMCommand::RightMouseDown((x, y)) => format!("RightMouseDown({}, {})", x, y),
+        PlayerVMCommand::RightMouseUp((x, y)) => format!("RightMouseUp({}, {})", x, y),
+        PlayerVMCommand::ActivateApplication => "ActivateApplication".to_string(),
+        PlayerVMCommand::DeactivateApplication => "DeactivateApplication".to_string(),
         PlayerVMCommand::KeyDown(key, ..) => format!("KeyDown({})", key),
         PlayerVMCommand::KeyUp(key, ..) => format!("KeyUp({})", key),
         PlayerVMCommand::TriggerAlertHook => "TriggerAlertHook".to_string(),
@@ -120,6 +133,86 @@ fn has_executable_callback(callback: &Option<ScriptReceiver>) -> bool {
     }
 }
 
+/// Two clicks closer together than this make `the doubleClick` TRUE.
+const DOUBLE_CLICK_MS: i64 = 500;
+
+/// Record a left-button click at `click_now` (ms) and update `the doubleClick`.
+fn register_click(player: &mut DirPlayer, x: i32, y: i32, click_now: i64) {
+    player.is_double_click = (click_now - player.last_mouse_down_time) < DOUBLE_CLICK_MS;
+    player.mouse_loc = (x, y);
+    player.movie.mouse_down = true;
+    player.movie.click_loc = (x, y);
+    player.last_mouse_down_time = click_now;
+}
+
+/// `is_yield_safe()` flags and `in_mouse_command`, saved across a mouse command.
+type MouseCommandFlags = (bool, bool, bool, bool, bool, bool);
+
+/// Clear the `is_yield_safe()` flags so that updateStage() called from a mouse
+/// handler renders (but doesn't yield), and set `in_mouse_command` so the
+/// frame loop skips frame updates meanwhile. Returns the flags to restore.
+fn enter_mouse_command(player: &mut DirPlayer) -> MouseCommandFlags {
+    let saved = (
+        player.is_in_frame_update,
+        player.in_frame_script,
+        player.in_enter_frame,
+        player.in_prepare_frame,
+        player.in_event_dispatch,
+        player.in_mouse_command,
+    );
+    player.is_in_frame_update = false;
+    player.in_frame_script = false;
+    player.in_enter_frame = false;
+    player.in_prepare_frame = false;
+    player.in_event_dispatch = false;
+    player.in_mouse_command = true;
+    saved
+}
+
+fn leave_mouse_command(player: &mut DirPlayer, saved: MouseCommandFlags) {
+    player.is_in_frame_update = saved.0;
+    player.in_frame_script = saved.1;
+    player.in_enter_frame = saved.2;
+    player.in_prepare_frame = saved.3;
+    player.in_event_dispatch = saved.4;
+    player.in_mouse_command = saved.5;
+}
+
+/// Right button press or release. rightMouseDown goes to the scripted sprite
+/// under the pointer and rightMouseUp to the sprite that took the press, with
+/// the frame and movie scripts as fallback.
+async fn handle_right_mouse(x: i32, y: i32, is_down: bool) -> Result<DatumRef, ScriptError> {
+    let sprite_num = reserve_player_mut(|player| {
+        tempo_wait::notify_click(player);
+        player.mouse_loc = (x, y);
+        player.right_mouse_down = is_down;
+        if is_down {
+            player.right_mouse_down_sprite = get_sprite_at(player, x, y, true).map_or(-1, |n| n as i16);
+            player.right_mouse_down_sprite
+        } else {
+            std::mem::replace(&mut player.right_mouse_down_sprite, -1)
+        }
+    });
+    let event_name = if is_down { "rightMouseDown" } else { "rightMouseUp" };
+    let saved_yield_flags = reserve_player_mut(enter_mouse_command);
+    let result = player_invoke_sprite_mouse_event(event_name, sprite_num).await;
+    reserve_player_mut(|player| leave_mouse_command(player, saved_yield_flags));
+    result.map(|_| DatumRef::Void)
+}
+
+/// Track host page focus, sending activateApplication / deactivateApplication
+/// to the movie when it changes.
+async fn set_application_active(active: bool) -> Result<DatumRef, ScriptError> {
+    let changed = reserve_player_mut(|player| {
+        std::mem::replace(&mut player.is_application_active, active) != active
+    });
+    if changed && player_is_playing().await {
+        let event_name = if active { "activateApplication" } else { "deactivateApplication" };
+        player_invoke_global_event(event_name, &vec![]).await?;
+    }
+    Ok(DatumRef::Void)
+}
+
 pub async fn run_command_loop(rx: Receiver<PlayerVMExecutionItem>) {
     warn!("Starting command loop");
 
@@ -141,6 +234,7 @@ pub async fn run_command_loop(rx: Receiver<PlayerVMExecutionItem>) {
             warn!("Command loop stopped after recv (generation changed)");
             return;
         }
+        window::wait_for_stage_context().await;
         let result = run_player_command(item.command).await;
         match result {
             Ok(result) => {
@@ -182,6 +276,49 @@ pub fn player_dispatch(command: PlayerVMCommand) {
     }
 }
 
+/// Update the state an input changes as soon as it arrives, so that
+/// `the mouseLoc`, `the mouseDown` and `keyPressed()` are current even while
+/// a handler is running and the command is still queued.
+pub fn apply_input_state(player: &mut DirPlayer, command: &PlayerVMCommand) {
+    match command {
+        PlayerVMCommand::MouseDown(loc) => {
+            player.mouse_loc = *loc;
+            player.movie.mouse_down = true;
+        }
+        PlayerVMCommand::MouseUp(loc) => {
+            player.mouse_loc = *loc;
+            player.movie.mouse_down = false;
+        }
+        PlayerVMCommand::MouseMove(loc) => {
+            player.mouse_loc = *loc;
+        }
+        PlayerVMCommand::RightMouseDown(loc) => {
+            player.mouse_loc = *loc;
+            player.right_mouse_down = true;
+        }
+        PlayerVMCommand::RightMouseUp(loc) => {
+            player.mouse_loc = *loc;
+            player.right_mouse_down = false;
+        }
+        PlayerVMCommand::KeyDown(key, code) => {
+            player.keyboard_manager.key_down(key.clone(), *code);
+        }
+        PlayerVMCommand::KeyUp(key, code) => {
+            player.keyboard_manager.key_up(key, *code);
+        }
+        _ => {}
+    }
+}
+
+/// Queue an input from the host. Ignored while a replay supplies the inputs.
+pub fn player_dispatch_input(command: PlayerVMCommand) {
+    if replay::is_replaying() {
+        return;
+    }
+    reserve_player_mut(|player| apply_input_state(player, &command));
+    player_dispatch(command);
+}
+
 #[allow(dead_code)]
 pub async fn player_dispatch_async(command: PlayerVMCommand) -> Result<DatumRef, ScriptError> {
     let tx = unsafe { PLAYER_TX.clone() }.unwrap();
@@ -209,6 +346,20 @@ pub async fn run_player_command(command: PlayerVMCommand) -> Result<DatumRef, Sc
             "Command cancelled: player generation changed (test reset)".to_string(),
         ));
     }
+    if let Some(input) = ReplayInput::from_command(&command)
+        && !replay::accept_input(input)
+    {
+        return Ok(DatumRef::Void);
+    }
+    apply_player_command(command).await
+}
+
+/// Run a command without passing it through the input recorder. Replays
+/// use this to apply the inputs they recorded.
+pub async fn apply_player_command(command: PlayerVMCommand) -> Result<DatumRef, ScriptError> {
+    if let Some(result) = window::route_input_command(&command).await {
+        return result;
+    }
     match command {
         PlayerVMCommand::SetExternalParams(params) => {
             reserve_player_mut(|player| {
@@ -318,6 +469,20 @@ pub async fn run_player_command(command: PlayerVMCommand) -> Result<DatumRef, Sc
             if !player_is_playing().await {
                 return Ok(DatumRef::Void);
             }
+            // With emulateMultiButtonMouse, control-click stands in for the
+            // right button (for one-button mice).
+            let is_emulated_right_click = reserve_player_mut(|player| {
+                player.is_emulated_right_click =
+                    player.emulate_multi_button_mouse && player.keyboard_manager.is_control_down();
+                if player.is_emulated_right_click {
+                    player.movie.mouse_down = false;
+                }
+                player.is_emulated_right_click
+            });
+            if is_emulated_right_click {
+                return handle_right_mouse(x, y, true).await;
+            }
+            reserve_player_mut(tempo_wait::notify_click);
             // In Director, mouseDownScript intercepts BEFORE sprites get the event.
             // Only block when it contains executable content (not just a comment).
             // Comments like "--nothing" are stored but don't block propagation.
@@ -332,7 +497,7 @@ pub async fn run_player_command(command: PlayerVMCommand) -> Result<DatumRef, Sc
             // two single clicks. CS's ACTION_TIMED_ANIMATION (and similar) reads
             // `the doubleClick` to gate `toggleState()`, so a wrong flag silently
             // breaks every animation-toggling action.
-            let click_now = Local::now().timestamp_millis().abs();
+            let click_now = local_now().timestamp_millis().abs();
             // Flush actorList stepFrame so cached rollover state (e.g. oMouseSquare)
             // is fresh before the mouseDown handler reads it. On mobile/touch there's
             // no prior mouse_move, so stepFrame hasn't run at the tap position yet.
@@ -351,14 +516,7 @@ pub async fn run_player_command(command: PlayerVMCommand) -> Result<DatumRef, Sc
             }
 
             if mouse_down_script_active {
-                reserve_player_mut(|player| {
-                    let is_double_click = (click_now - player.last_mouse_down_time) < 500;
-                    player.mouse_loc = (x, y);
-                    player.movie.mouse_down = true;
-                    player.movie.click_loc = (x, y);
-                    player.is_double_click = is_double_click;
-                    player.last_mouse_down_time = click_now;
-                });
+                reserve_player_mut(|player| register_click(player, x, y, click_now));
                 player_dispatch_movie_callback("mouseDown").await?;
                 return Ok(DatumRef::Void);
             }
@@ -367,12 +525,7 @@ pub async fn run_player_command(command: PlayerVMCommand) -> Result<DatumRef, Sc
             // are detected. Non-scripted sprites (decorations, overlays) are skipped,
             // matching Director behavior.
             reserve_player_mut(|player| {
-                let is_double_click = (click_now - player.last_mouse_down_time) < 500;
-                player.mouse_loc = (x, y);
-                player.movie.mouse_down = true;
-                player.movie.click_loc = (x, y);
-                player.is_double_click = is_double_click;
-                player.last_mouse_down_time = click_now;
+                register_click(player, x, y, click_now);
 
                 // "the clickOn" should return the topmost sprite at the click point
                 // regardless of whether it has a script — use unscripted lookup.
@@ -435,6 +588,7 @@ pub async fn run_player_command(command: PlayerVMCommand) -> Result<DatumRef, Sc
                 } else {
                     player.mouse_down_sprite = -1;
                 }
+                hyperlinks::press_hyperlink(player, player.mouse_down_sprite, x, y);
             });
 
             // Temporarily clear ALL is_yield_safe() flags so that updateStage()
@@ -445,23 +599,7 @@ pub async fn run_player_command(command: PlayerVMCommand) -> Result<DatumRef, Sc
             // Set in_mouse_command so the frame loop skips frame updates/advancement
             // and updateStage renders without sleeping (preventing re-entrant event
             // dispatch and timing issues with mouseUp processing).
-            let saved_yield_flags = reserve_player_mut(|player| {
-                let saved = (
-                    player.is_in_frame_update,
-                    player.in_frame_script,
-                    player.in_enter_frame,
-                    player.in_prepare_frame,
-                    player.in_event_dispatch,
-                    player.in_mouse_command,
-                );
-                player.is_in_frame_update = false;
-                player.in_frame_script = false;
-                player.in_enter_frame = false;
-                player.in_prepare_frame = false;
-                player.in_event_dispatch = false;
-                player.in_mouse_command = true;
-                saved
-            });
+            let saved_yield_flags = reserve_player_mut(enter_mouse_command);
 
             // Dispatch to sprite behaviors if the sprite has any, otherwise
             // fall through to frame/movie scripts per Director's propagation chain.
@@ -564,14 +702,7 @@ pub async fn run_player_command(command: PlayerVMCommand) -> Result<DatumRef, Sc
 
             // Restore all is_yield_safe() flags and in_mouse_command
             // MUST happen even on error to prevent skip_frame getting stuck
-            reserve_player_mut(|player| {
-                player.is_in_frame_update = saved_yield_flags.0;
-                player.in_frame_script = saved_yield_flags.1;
-                player.in_enter_frame = saved_yield_flags.2;
-                player.in_prepare_frame = saved_yield_flags.3;
-                player.in_event_dispatch = saved_yield_flags.4;
-                player.in_mouse_command = saved_yield_flags.5;
-            });
+            reserve_player_mut(|player| leave_mouse_command(player, saved_yield_flags));
 
             if let Some(e) = handler_err {
                 return Err(e);
@@ -582,6 +713,10 @@ pub async fn run_player_command(command: PlayerVMCommand) -> Result<DatumRef, Sc
             if !player_is_playing().await {
                 return Ok(DatumRef::Void);
             }
+            if reserve_player_mut(|player| std::mem::take(&mut player.is_emulated_right_click)) {
+                return handle_right_mouse(x, y, false).await;
+            }
+            let hyperlink_click = reserve_player_mut(|player| hyperlinks::release_hyperlink(player, x, y));
             // In Director, mouseUpScript intercepts BEFORE sprites get the event.
             let mouse_up_script_active = reserve_player_ref(|player| {
                 has_executable_callback(&player.movie.mouse_up_script)
@@ -646,23 +781,13 @@ pub async fn run_player_command(command: PlayerVMCommand) -> Result<DatumRef, Sc
             // the mouseUp command is processed at a frame loop .await point.
             // Set in_mouse_command to prevent re-entrant frame updates and
             // make updateStage synchronous (render-only, no sleep).
-            let saved_yield_flags = reserve_player_mut(|player| {
-                let saved = (
-                    player.is_in_frame_update,
-                    player.in_frame_script,
-                    player.in_enter_frame,
-                    player.in_prepare_frame,
-                    player.in_event_dispatch,
-                    player.in_mouse_command,
-                );
-                player.is_in_frame_update = false;
-                player.in_frame_script = false;
-                player.in_enter_frame = false;
-                player.in_prepare_frame = false;
-                player.in_event_dispatch = false;
-                player.in_mouse_command = true;
-                saved
-            });
+            let saved_yield_flags = reserve_player_mut(enter_mouse_command);
+
+            // A click released over the link it started on reaches the
+            // sprite's behaviors ahead of mouseUp.
+            if let Some((sprite_num, args)) = hyperlink_click {
+                player_dispatch_event_to_sprite_targeted("hyperlinkClicked", &args, sprite_num as u16).await;
+            }
 
             // Dispatch to the sprite that originally received mouseDown,
             // or fall through to frame/movie scripts if no sprite was involved.
@@ -752,12 +877,7 @@ pub async fn run_player_command(command: PlayerVMCommand) -> Result<DatumRef, Sc
             // Restore all is_yield_safe() flags and command_handler_yielding
             // MUST happen even on error to prevent skip_frame getting stuck
             reserve_player_mut(|player| {
-                player.is_in_frame_update = saved_yield_flags.0;
-                player.in_frame_script = saved_yield_flags.1;
-                player.in_enter_frame = saved_yield_flags.2;
-                player.in_prepare_frame = saved_yield_flags.3;
-                player.in_event_dispatch = saved_yield_flags.4;
-                player.in_mouse_command = saved_yield_flags.5;
+                leave_mouse_command(player, saved_yield_flags);
                 player.is_double_click = false;
             });
 
@@ -847,7 +967,22 @@ pub async fn run_player_command(command: PlayerVMCommand) -> Result<DatumRef, Sc
                 }
             }
         }
+        PlayerVMCommand::RightMouseDown((x, y)) => {
+            if !player_is_playing().await {
+                return Ok(DatumRef::Void);
+            }
+            return handle_right_mouse(x, y, true).await;
+        }
+        PlayerVMCommand::RightMouseUp((x, y)) => {
+            if !player_is_playing().await {
+                return Ok(DatumRef::Void);
+            }
+            return handle_right_mouse(x, y, false).await;
+        }
+        PlayerVMCommand::ActivateApplication => return set_application_active(true).await,
+        PlayerVMCommand::DeactivateApplication => return set_application_active(false).await,
         PlayerVMCommand::KeyDown(key, code) => {
+            reserve_player_mut(tempo_wait::notify_click);
             // Set command_handler_yielding so that:
             // 1. updateStage() always yields (bypasses is_yield_safe check),
             //    letting the browser process keyUp events during repeat-while-
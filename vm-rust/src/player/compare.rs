@@ -151,6 +151,11 @@ pub fn datum_equals(
 
         (Stage, o) | (o, Stage) => Ok(matches!(o, Stage)),
 
+        (Window(a), o) | (o, Window(a)) => Ok(match o {
+            Window(b) => a.eq_ignore_ascii_case(b),
+            _ => false
+        }),
+
         (ScriptRef(a), o) | (o, ScriptRef(a)) => Ok(match o {
             ScriptRef(b) => a == b,
             _ => false
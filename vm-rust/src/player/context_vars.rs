@@ -7,6 +7,7 @@ use super::{
     DatumRef, DirPlayer, ScriptError,
 };
 use crate::director::lingo::datum::Datum;
+use crate::player::debug::watch;
 use crate::player::bytecode::string::PutType;
 use crate::player::cast_member::CastMemberType;
 use web_sys::console;
@@ -154,7 +155,7 @@ pub fn player_set_context_var(
                 let name_id = handler.global_name_ids[name_index];
                 get_name(player, ctx, name_id).unwrap().to_owned()
             };
-            player.globals.insert(global_name, value_ref.clone());
+            watch::set_global(player, global_name, value_ref.clone());
             Ok(())
         }
         // property/instance
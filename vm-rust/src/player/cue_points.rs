@@ -0,0 +1,64 @@
+use crate::director::lingo::datum::Datum;
+use crate::player::cast_member::SoundCuePoint;
+use crate::player::events::{dispatch_event_to_all_behaviors, player_wait_available};
+use crate::player::reserve_player_mut;
+
+struct CuePassedEvent {
+    channel: usize,
+    number: i32,
+    name: String,
+}
+
+/// Cue points with `from_ms < time <= to_ms`, with their 1-based index.
+/// `points` must be sorted by time.
+pub fn crossed(
+    points: &[SoundCuePoint],
+    from_ms: f64,
+    to_ms: f64,
+) -> impl Iterator<Item = (i32, &SoundCuePoint)> {
+    points
+        .iter()
+        .enumerate()
+        .filter(move |(_, point)| {
+            let time = point.time_ms as f64;
+            time > from_ms && time <= to_ms
+        })
+        .map(|(i, point)| (i as i32 + 1, point))
+}
+
+/// Dispatch cuePassed for every cue point the sound channels have crossed
+/// since the last frame. Director sends
+///   cuePassed(channelID, cuePointNumber, cuePointName)
+/// to sprite behaviors first, then to the frame and movie scripts, with
+/// channelID being #sound1, #sound2, ...
+pub async fn dispatch_cue_passed() {
+    let events: Vec<CuePassedEvent> = reserve_player_mut(|player| {
+        let mut result = vec![];
+        for channel in 0..player.sound_manager.num_channels() {
+            let Some(channel_rc) = player.sound_manager.get_channel(channel) else {
+                continue;
+            };
+            for (number, name) in channel_rc.borrow_mut().poll_cue_points() {
+                result.push(CuePassedEvent {
+                    channel: channel + 1,
+                    number,
+                    name,
+                });
+            }
+        }
+        result
+    });
+
+    for event in events {
+        let args = reserve_player_mut(|player| {
+            vec![
+                player.alloc_datum(Datum::Symbol(format!("sound{}", event.channel))),
+                player.alloc_datum(Datum::Int(event.number)),
+                player.alloc_datum(Datum::String(event.name)),
+            ]
+        });
+        // Reaches sprite behaviors, then the frame and movie scripts.
+        dispatch_event_to_all_behaviors("cuePassed", &args).await;
+        player_wait_available().await;
+    }
+}
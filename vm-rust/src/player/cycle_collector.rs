@@ -0,0 +1,268 @@
+//! Cycle collection for reference-counted datums and script instances.
+//!
+//! The allocator frees an entry as soon as its reference count drops to
+//! zero, which never happens for entries that reference each other: a
+//! parent script holding a child whose `ancestor` points back, a list that
+//! contains itself, a timeout whose target holds the timeout.
+//!
+//! The collector uses trial deletion rather than tracing from a root set.
+//! It counts the references arena entries hold to each other, and any entry
+//! whose reference count is higher than that is held from outside the
+//! arena: by a global, a scope, a sprite, a timeout, a cast member, an xtra
+//! instance or any other Rust-side holder. Those entries and everything
+//! they reach are live. What remains is only referenced from inside
+//! unreachable cycles, so the collector empties the containers in it and
+//! the ordinary reference counting frees the rest.
+
+use fxhash::{FxHashMap, FxHashSet};
+use log::debug;
+use serde::Serialize;
+
+use crate::director::lingo::datum::{Datum, StringChunkSource, VarRef};
+
+use super::{
+    allocator::DatumAllocator,
+    datum_ref::{DatumId, DatumRef},
+    script::{ScriptInstance, ScriptInstanceId},
+    script_ref::ScriptInstanceRef,
+    DirPlayer,
+};
+
+/// Frames between automatic collections.
+pub const DEFAULT_COLLECT_INTERVAL_FRAMES: u32 = 300;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Node {
+    Datum(DatumId),
+    Instance(ScriptInstanceId),
+}
+
+/// Result of a single collection.
+#[derive(Clone, Default, Serialize)]
+pub struct CycleCollection {
+    pub scanned_datums: usize,
+    pub scanned_script_instances: usize,
+    pub freed_datums: usize,
+    pub freed_script_instances: usize,
+    pub duration_ms: f64,
+}
+
+#[derive(Clone, Default, Serialize)]
+pub struct CycleCollectorStats {
+    pub runs: u64,
+    pub total_freed_datums: u64,
+    pub total_freed_script_instances: u64,
+    pub last_run: Option<CycleCollection>,
+}
+
+pub struct CycleCollector {
+    /// Frames between automatic collections. Zero turns them off.
+    pub interval_frames: u32,
+    frames_since_run: u32,
+    pub stats: CycleCollectorStats,
+}
+
+impl Default for CycleCollector {
+    fn default() -> Self {
+        CycleCollector {
+            interval_frames: DEFAULT_COLLECT_INTERVAL_FRAMES,
+            frames_since_run: 0,
+            stats: CycleCollectorStats::default(),
+        }
+    }
+}
+
+fn datum_ref_node(datum_ref: &DatumRef) -> Option<Node> {
+    match datum_ref {
+        DatumRef::Ref(id, ..) => Some(Node::Datum(*id)),
+        DatumRef::Void => None,
+    }
+}
+
+/// Call `f` for every datum and script instance `datum` holds a reference to.
+fn for_each_datum_edge(datum: &Datum, f: &mut impl FnMut(Node)) {
+    let mut visit = |datum_ref: &DatumRef| {
+        if let Some(node) = datum_ref_node(datum_ref) {
+            f(node);
+        }
+    };
+    match datum {
+        Datum::List(_, items, _) => items.iter().for_each(&mut visit),
+        Datum::PropList(pairs, _) => {
+            for (key, value) in pairs {
+                visit(key);
+                visit(value);
+            }
+        }
+        Datum::StringChunk(StringChunkSource::Datum(source), ..) => visit(source),
+        Datum::TimeoutInstance { callback, target, script_instance, .. } => {
+            visit(callback);
+            visit(target);
+            if let Some(script_instance) = script_instance {
+                visit(script_instance);
+            }
+        }
+        Datum::ScriptInstanceRef(instance_ref)
+        | Datum::VarRef(VarRef::ScriptInstance(instance_ref)) => f(Node::Instance(instance_ref.id())),
+        _ => {}
+    }
+}
+
+fn for_each_instance_edge(instance: &ScriptInstance, f: &mut impl FnMut(Node)) {
+    if let Some(ancestor) = &instance.ancestor {
+        f(Node::Instance(ancestor.id()));
+    }
+    for value in instance.properties.values() {
+        if let Some(node) = datum_ref_node(value) {
+            f(node);
+        }
+    }
+}
+
+fn for_each_edge(allocator: &DatumAllocator, node: Node, f: &mut impl FnMut(Node)) {
+    match node {
+        Node::Datum(id) => {
+            if let Some(entry) = allocator.datums.get(id) {
+                for_each_datum_edge(&entry.datum, f);
+            }
+        }
+        Node::Instance(id) => {
+            if let Some(entry) = allocator.get_script_instance_entry(id) {
+                for_each_instance_edge(&entry.script_instance, f);
+            }
+        }
+    }
+}
+
+fn ref_count(allocator: &DatumAllocator, node: Node) -> u32 {
+    let count = match node {
+        Node::Datum(id) => allocator.datums.get(id).map(|entry| entry.ref_count.get()),
+        Node::Instance(id) => allocator
+            .get_script_instance_entry(id)
+            .map(|entry| entry.ref_count.get()),
+    };
+    count.map_or(0, |count| unsafe { *count })
+}
+
+/// Entries only referenced from unreachable cycles.
+fn find_garbage(allocator: &DatumAllocator) -> Vec<Node> {
+    // References held by other arena entries, per collectable entry.
+    // Pooled ints and interned symbols are immortal and never collected.
+    let mut internal_refs: FxHashMap<Node, u32> = FxHashMap::default();
+    for (id, entry) in allocator.datums.iter() {
+        if unsafe { *entry.ref_count.get() } != u32::MAX {
+            internal_refs.insert(Node::Datum(id), 0);
+        }
+    }
+    for (id, _) in allocator.script_instances.iter() {
+        internal_refs.insert(Node::Instance(id as ScriptInstanceId), 0);
+    }
+    let nodes: Vec<Node> = internal_refs.keys().copied().collect();
+    for node in &nodes {
+        for_each_edge(allocator, *node, &mut |target| {
+            if let Some(count) = internal_refs.get_mut(&target) {
+                *count += 1;
+            }
+        });
+    }
+
+    let mut live: FxHashSet<Node> = FxHashSet::default();
+    let mut pending: Vec<Node> = nodes
+        .iter()
+        .copied()
+        .filter(|node| ref_count(allocator, *node) > internal_refs[node])
+        .collect();
+    while let Some(node) = pending.pop() {
+        if !live.insert(node) {
+            continue;
+        }
+        for_each_edge(allocator, node, &mut |target| {
+            if internal_refs.contains_key(&target) && !live.contains(&target) {
+                pending.push(target);
+            }
+        });
+    }
+
+    nodes.into_iter().filter(|node| !live.contains(node)).collect()
+}
+
+/// Free every datum and script instance that is only referenced from
+/// unreachable cycles.
+pub fn collect_cycles(player: &mut DirPlayer) -> CycleCollection {
+    let start = chrono::Local::now();
+    let scanned_datums = player.allocator.datum_count();
+    let scanned_script_instances = player.allocator.script_instance_count();
+    let garbage = find_garbage(&player.allocator);
+
+    // Move the references out of the garbage first and drop them once the
+    // allocator is no longer borrowed, since dropping the last reference to
+    // an entry frees it.
+    let mut released_datums: Vec<Datum> = Vec::new();
+    let mut released_refs: Vec<DatumRef> = Vec::new();
+    let mut released_ancestors: Vec<ScriptInstanceRef> = Vec::new();
+    for node in &garbage {
+        match *node {
+            Node::Datum(id) => {
+                if let Some(entry) = player.allocator.datums.get_mut(id) {
+                    let mut has_edges = false;
+                    for_each_datum_edge(&entry.datum, &mut |_| has_edges = true);
+                    if has_edges {
+                        released_datums.push(std::mem::replace(&mut entry.datum, Datum::Null));
+                    }
+                }
+            }
+            Node::Instance(id) => {
+                if let Some(entry) = player.allocator.get_script_instance_entry_mut(id) {
+                    let instance = &mut entry.script_instance;
+                    released_ancestors.extend(instance.ancestor.take());
+                    released_refs.extend(instance.properties.drain().map(|(_, value)| value));
+                }
+            }
+        }
+    }
+    drop(released_datums);
+    drop(released_refs);
+    drop(released_ancestors);
+
+    let mut collection = CycleCollection {
+        scanned_datums,
+        scanned_script_instances,
+        ..Default::default()
+    };
+    for node in &garbage {
+        match *node {
+            Node::Datum(id) if !player.allocator.contains_datum(id) => collection.freed_datums += 1,
+            Node::Instance(id) if player.allocator.get_script_instance_entry(id).is_none() => {
+                collection.freed_script_instances += 1
+            }
+            _ => {}
+        }
+    }
+    collection.duration_ms = (chrono::Local::now() - start)
+        .num_microseconds()
+        .map_or(0.0, |us| us as f64 / 1000.0);
+    debug!(
+        "Cycle collection freed {} datums and {} script instances in {:.2}ms",
+        collection.freed_datums, collection.freed_script_instances, collection.duration_ms
+    );
+
+    let stats = &mut player.cycle_collector.stats;
+    stats.runs += 1;
+    stats.total_freed_datums += collection.freed_datums as u64;
+    stats.total_freed_script_instances += collection.freed_script_instances as u64;
+    stats.last_run = Some(collection.clone());
+    collection
+}
+
+/// Count a played frame and collect once `interval_frames` have passed.
+pub fn on_frame_played(player: &mut DirPlayer) {
+    let collector = &mut player.cycle_collector;
+    if collector.interval_frames == 0 {
+        return;
+    }
+    collector.frames_since_run += 1;
+    if collector.frames_since_run >= collector.interval_frames {
+        collector.frames_since_run = 0;
+        collect_cycles(player);
+    }
+}
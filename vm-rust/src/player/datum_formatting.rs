@@ -24,6 +24,7 @@ pub fn format_concrete_datum_with_depth(datum: &Datum, player: &DirPlayer, depth
         Datum::Symbol(s) => format!("#{s}"),
         Datum::CastLib(n) => format!("castLib({n})"),
         Datum::Stage => "the stage".to_string(),
+        Datum::Window(name) => format!("(window \"{}\")", name),
         Datum::PropList(entries, ..) => {
             if entries.is_empty() {
                 return "[:]".to_string();
@@ -1,8 +1,18 @@
+pub mod watch;
+
+use std::fmt;
+
+use async_recursion::async_recursion;
 use manual_future::ManualFutureCompleter;
 
+use crate::director::lingo::datum::Datum;
 use crate::js_api::JsApi;
 
-use super::{cast_lib::CastMemberRef, script::ScriptHandlerRef, ScriptError};
+use super::{
+    cast_lib::CastMemberRef, datum_formatting::format_concrete_datum,
+    eval::eval_lingo_expr_runtime, reserve_player_mut, reserve_player_ref,
+    script::ScriptHandlerRef, trace_output, ScriptError,
+};
 
 /// Represents the current step debugging mode
 #[derive(Clone, PartialEq, Debug)]
@@ -27,11 +37,113 @@ impl Default for StepMode {
     }
 }
 
+/// Which hits of a breakpoint are allowed to fire, counted from 1.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum HitCondition {
+    #[default]
+    Always,
+    /// Only the Nth hit.
+    Equal(u32),
+    /// The Nth hit and every one after it.
+    AtLeast(u32),
+    /// Every Nth hit.
+    Multiple(u32),
+}
+
+impl HitCondition {
+    /// Parse the debugger's hit condition syntax: `5` or `=5`, `>=5` and
+    /// `%5`. An empty string means every hit.
+    pub fn parse(text: &str) -> Result<HitCondition, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(HitCondition::Always);
+        }
+        let (make, count): (fn(u32) -> HitCondition, &str) = if let Some(rest) = text.strip_prefix(">=") {
+            (HitCondition::AtLeast, rest)
+        } else if let Some(rest) = text.strip_prefix('%') {
+            (HitCondition::Multiple, rest)
+        } else if let Some(rest) = text.strip_prefix("==").or_else(|| text.strip_prefix('=')) {
+            (HitCondition::Equal, rest)
+        } else {
+            (HitCondition::Equal, text)
+        };
+        match count.trim().parse::<u32>() {
+            Ok(n) if n > 0 => Ok(make(n)),
+            _ => Err(format!("Invalid hit condition: {}", text)),
+        }
+    }
+
+    pub fn matches(&self, hit_count: u32) -> bool {
+        match *self {
+            HitCondition::Always => true,
+            HitCondition::Equal(n) => hit_count == n,
+            HitCondition::AtLeast(n) => hit_count >= n,
+            HitCondition::Multiple(n) => hit_count.is_multiple_of(n),
+        }
+    }
+}
+
+impl fmt::Display for HitCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HitCondition::Always => Ok(()),
+            HitCondition::Equal(n) => write!(f, "={}", n),
+            HitCondition::AtLeast(n) => write!(f, ">={}", n),
+            HitCondition::Multiple(n) => write!(f, "%{}", n),
+        }
+    }
+}
+
+/// Everything beyond "stop here" that a breakpoint can be configured with.
+#[derive(Clone, Default, Debug)]
+pub struct BreakpointOptions {
+    /// Lingo expression evaluated in the handler's scope; the breakpoint is
+    /// skipped (and not counted as a hit) unless it's true.
+    pub condition: Option<String>,
+    pub hit_condition: HitCondition,
+    /// Number of hits to let through before the breakpoint can fire.
+    pub ignore_count: u32,
+    /// Turns the breakpoint into a logpoint: `{expr}` parts are evaluated
+    /// and the message goes to the message window without pausing.
+    pub log_message: Option<String>,
+    /// Remove the breakpoint the first time it fires.
+    pub one_shot: bool,
+}
+
 #[derive(Clone)]
 pub struct Breakpoint {
     pub script_name: String,
     pub handler_name: String,
     pub bytecode_index: usize,
+    pub options: BreakpointOptions,
+    /// Hits so far, i.e. times the location was reached with the condition true.
+    pub hit_count: u32,
+}
+
+impl Breakpoint {
+    pub fn new(script_name: String, handler_name: String, bytecode_index: usize) -> Breakpoint {
+        Breakpoint {
+            script_name,
+            handler_name,
+            bytecode_index,
+            options: BreakpointOptions::default(),
+            hit_count: 0,
+        }
+    }
+}
+
+/// Breaks on entry to every handler with this name, in any script.
+#[derive(Clone)]
+pub struct HandlerBreakpoint {
+    pub handler_name: String,
+    pub options: BreakpointOptions,
+    pub hit_count: u32,
+}
+
+/// Where a breakpoint check happens.
+pub enum BreakpointLocation<'a> {
+    Bytecode { script_name: &'a str, handler_name: &'a str, bytecode_index: usize },
+    HandlerEntry { script_name: &'a str, handler_name: &'a str },
 }
 
 pub struct BreakpointContext {
@@ -41,16 +153,24 @@ pub struct BreakpointContext {
     pub bytecode_index: usize,
     pub completer: ManualFutureCompleter<()>,
     pub error: Option<ScriptError>,
+    /// The change that paused execution, for watchpoint pauses.
+    pub watch_hit: Option<watch::WatchHit>,
 }
 
 pub struct BreakpointManager {
     pub breakpoints: Vec<Breakpoint>,
+    pub handler_breakpoints: Vec<HandlerBreakpoint>,
+    /// Set while a condition or log message is being evaluated, so handlers
+    /// it calls don't hit breakpoints themselves.
+    evaluating: bool,
 }
 
 impl BreakpointManager {
     pub fn new() -> BreakpointManager {
         BreakpointManager {
             breakpoints: vec![],
+            handler_breakpoints: vec![],
+            evaluating: false,
         }
     }
 
@@ -60,14 +180,74 @@ impl BreakpointManager {
         handler_name: String,
         bytecode_index: usize,
     ) {
-        self.breakpoints.push(Breakpoint {
-            script_name,
+        self.breakpoints.push(Breakpoint::new(script_name, handler_name, bytecode_index));
+        JsApi::dispatch_breakpoint_list_changed();
+    }
+
+    /// Configure the breakpoint at this location, adding it if needed. Its
+    /// hit count starts over.
+    pub fn set_breakpoint_options(
+        &mut self,
+        script_name: String,
+        handler_name: String,
+        bytecode_index: usize,
+        options: BreakpointOptions,
+    ) {
+        let position = self.breakpoints.iter().position(|bp| {
+            bp.script_name == script_name
+                && bp.handler_name == handler_name
+                && bp.bytecode_index == bytecode_index
+        });
+        let breakpoint = match position {
+            Some(index) => &mut self.breakpoints[index],
+            None => {
+                self.breakpoints.push(Breakpoint::new(script_name, handler_name, bytecode_index));
+                self.breakpoints.last_mut().unwrap()
+            }
+        };
+        breakpoint.options = options;
+        breakpoint.hit_count = 0;
+        JsApi::dispatch_breakpoint_list_changed();
+    }
+
+    /// Break on entry to any handler called `handler_name`. Replaces an
+    /// existing breakpoint on the same handler.
+    pub fn add_handler_breakpoint(&mut self, handler_name: String, options: BreakpointOptions) {
+        self.handler_breakpoints
+            .retain(|bp| !bp.handler_name.eq_ignore_ascii_case(&handler_name));
+        self.handler_breakpoints.push(HandlerBreakpoint {
             handler_name,
-            bytecode_index,
+            options,
+            hit_count: 0,
         });
         JsApi::dispatch_breakpoint_list_changed();
     }
 
+    pub fn remove_handler_breakpoint(&mut self, handler_name: &str) {
+        self.handler_breakpoints
+            .retain(|bp| !bp.handler_name.eq_ignore_ascii_case(handler_name));
+        JsApi::dispatch_breakpoint_list_changed();
+    }
+
+    pub fn reset_hit_counts(&mut self) {
+        self.breakpoints.iter_mut().for_each(|bp| bp.hit_count = 0);
+        self.handler_breakpoints.iter_mut().for_each(|bp| bp.hit_count = 0);
+        JsApi::dispatch_breakpoint_list_changed();
+    }
+
+    /// Whether handlers need to check for bytecode breakpoints at all.
+    pub fn has_bytecode_breakpoints(&self) -> bool {
+        !self.evaluating && !self.breakpoints.is_empty()
+    }
+
+    pub fn has_handler_breakpoint(&self, handler_name: &str) -> bool {
+        !self.evaluating
+            && self
+                .handler_breakpoints
+                .iter()
+                .any(|bp| bp.handler_name.eq_ignore_ascii_case(handler_name))
+    }
+
     pub fn remove_breakpoint(
         &mut self,
         script_name: String,
@@ -120,4 +300,140 @@ impl BreakpointManager {
                 && bp.bytecode_index == bytecode_index
         })
     }
+
+    fn options_at(&self, location: &BreakpointLocation) -> Option<BreakpointOptions> {
+        if self.evaluating {
+            return None;
+        }
+        match *location {
+            BreakpointLocation::Bytecode { script_name, handler_name, bytecode_index } => self
+                .find_breakpoint_for_bytecode(script_name, handler_name, bytecode_index)
+                .map(|bp| bp.options.clone()),
+            BreakpointLocation::HandlerEntry { handler_name, .. } => self
+                .handler_breakpoints
+                .iter()
+                .find(|bp| bp.handler_name.eq_ignore_ascii_case(handler_name))
+                .map(|bp| bp.options.clone()),
+        }
+    }
+
+    /// Count a hit at `location` and apply the ignore count and hit
+    /// condition. Returns the breakpoint if it fires, removing it first if
+    /// it's one-shot.
+    fn register_hit(&mut self, location: &BreakpointLocation) -> Option<Breakpoint> {
+        let fired = match *location {
+            BreakpointLocation::Bytecode { script_name, handler_name, bytecode_index } => {
+                let index = self.breakpoints.iter().position(|bp| {
+                    bp.script_name == script_name
+                        && bp.handler_name == handler_name
+                        && bp.bytecode_index == bytecode_index
+                })?;
+                let bp = &mut self.breakpoints[index];
+                bp.hit_count += 1;
+                if !fires(&bp.options, bp.hit_count) {
+                    return None;
+                }
+                let fired = bp.clone();
+                if fired.options.one_shot {
+                    self.breakpoints.remove(index);
+                }
+                fired
+            }
+            BreakpointLocation::HandlerEntry { script_name, handler_name } => {
+                let index = self
+                    .handler_breakpoints
+                    .iter()
+                    .position(|bp| bp.handler_name.eq_ignore_ascii_case(handler_name))?;
+                let bp = &mut self.handler_breakpoints[index];
+                bp.hit_count += 1;
+                if !fires(&bp.options, bp.hit_count) {
+                    return None;
+                }
+                let fired = Breakpoint {
+                    script_name: script_name.to_owned(),
+                    handler_name: handler_name.to_owned(),
+                    bytecode_index: 0,
+                    options: bp.options.clone(),
+                    hit_count: bp.hit_count,
+                };
+                if fired.options.one_shot {
+                    self.handler_breakpoints.remove(index);
+                }
+                fired
+            }
+        };
+        JsApi::dispatch_breakpoint_list_changed();
+        Some(fired)
+    }
+}
+
+fn fires(options: &BreakpointOptions, hit_count: u32) -> bool {
+    hit_count > options.ignore_count && options.hit_condition.matches(hit_count)
+}
+
+/// Evaluate `expr` in the current scope with breakpoints suspended. Boxed
+/// because the expression can call handlers, which check breakpoints.
+#[async_recursion(?Send)]
+async fn eval_for_breakpoint(expr: &str) -> Result<Datum, ScriptError> {
+    reserve_player_mut(|player| player.breakpoint_manager.evaluating = true);
+    let result = eval_lingo_expr_runtime(expr.to_owned()).await;
+    reserve_player_mut(|player| player.breakpoint_manager.evaluating = false);
+    let value = result?;
+    Ok(reserve_player_ref(|player| player.get_datum(&value).clone()))
+}
+
+/// Expand the `{expr}` parts of a logpoint message. `{{` and `}}` are
+/// literal braces; an expression that fails shows its error instead.
+async fn interpolate_log_message(message: &str) -> String {
+    let mut output = String::new();
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let expr: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                match eval_for_breakpoint(&expr).await {
+                    Ok(Datum::String(s)) => output.push_str(&s),
+                    Ok(value) => output.push_str(&reserve_player_ref(|player| {
+                        format_concrete_datum(&value, player)
+                    })),
+                    Err(err) => output.push_str(&format!("<error: {}>", err.message)),
+                }
+            }
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+/// Decide whether execution should pause at `location`: checks the
+/// breakpoint's condition, counts the hit, prints logpoint messages and
+/// drops one-shot breakpoints. Returns the breakpoint to pause on.
+pub async fn player_check_breakpoint(location: BreakpointLocation<'_>) -> Option<Breakpoint> {
+    let options = reserve_player_ref(|player| player.breakpoint_manager.options_at(&location))?;
+    if let Some(condition) = &options.condition {
+        match eval_for_breakpoint(condition).await.and_then(|value| value.to_bool()) {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(err) => {
+                // Stop anyway, so a typo in a condition doesn't go unnoticed.
+                let message = format!("-- Breakpoint condition `{}` failed: {}", condition, err.message);
+                reserve_player_ref(|player| trace_output(player, &message));
+            }
+        }
+    }
+    let breakpoint = reserve_player_mut(|player| player.breakpoint_manager.register_hit(&location))?;
+    if let Some(message) = &breakpoint.options.log_message {
+        let message = interpolate_log_message(message).await;
+        reserve_player_ref(|player| trace_output(player, &message));
+        return None;
+    }
+    Some(breakpoint)
 }
@@ -0,0 +1,306 @@
+//! Watchpoints: report when a global, script instance property, sprite
+//! property or list changes, either by pausing like a breakpoint or by
+//! logging to the message window.
+
+use crate::director::lingo::datum::Datum;
+use crate::player::{
+    allocator::ScriptInstanceAllocatorTrait,
+    ci_string::CiStr,
+    datum_formatting::{format_concrete_datum, format_datum},
+    datum_ref::DatumRef,
+    reserve_player_mut, reserve_player_ref,
+    script_ref::ScriptInstanceRef,
+    trace_output, DirPlayer, ScriptError,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WatchAction {
+    /// Pause before the next instruction of the handler that made the change.
+    Break,
+    /// Write the change to the message window and keep running.
+    Log,
+}
+
+/// What a watchpoint observes. Property and list watchpoints hold a
+/// reference to their object, so its id can't be reused while watched.
+#[derive(Clone)]
+pub enum WatchTarget {
+    Global(String),
+    Property { instance: ScriptInstanceRef, prop_name: String },
+    Sprite { channel: i16, prop_name: String },
+    List(DatumRef),
+}
+
+impl WatchTarget {
+    pub fn describe(&self) -> String {
+        match self {
+            WatchTarget::Global(name) => format!("global {}", name),
+            WatchTarget::Property { instance, prop_name } => {
+                format!("property {} of script instance {}", prop_name, instance.id())
+            }
+            WatchTarget::Sprite { channel, prop_name } => format!("sprite({}).{}", channel, prop_name),
+            WatchTarget::List(list_ref) => format!("list {}", list_ref.unwrap()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Watchpoint {
+    pub id: u32,
+    pub target: WatchTarget,
+    pub action: WatchAction,
+    /// Changes seen so far.
+    pub hit_count: u32,
+}
+
+/// One observed change, attributed to the handler running when it happened.
+#[derive(Clone, Debug)]
+pub struct WatchHit {
+    pub watchpoint_id: u32,
+    pub target: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub script_name: Option<String>,
+    pub handler_name: Option<String>,
+    pub bytecode_index: Option<usize>,
+}
+
+impl WatchHit {
+    pub fn message(&self) -> String {
+        let location = match (&self.script_name, &self.handler_name, self.bytecode_index) {
+            (Some(script), Some(handler), Some(index)) => {
+                format!(" in {} of {} at bytecode {}", handler, script, index)
+            }
+            _ => String::new(),
+        };
+        format!(
+            "-- Watchpoint {}: {} -> {}{}",
+            self.target, self.old_value, self.new_value, location
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct WatchManager {
+    pub watchpoints: Vec<Watchpoint>,
+    /// A break-on-change hit waiting for its handler to finish the
+    /// instruction that caused it.
+    pending_break: Option<WatchHit>,
+    last_id: u32,
+}
+
+impl WatchManager {
+    pub fn add_watchpoint(&mut self, target: WatchTarget, action: WatchAction) -> u32 {
+        self.last_id += 1;
+        let id = self.last_id;
+        self.watchpoints.push(Watchpoint { id, target, action, hit_count: 0 });
+        id
+    }
+
+    pub fn remove_watchpoint(&mut self, id: u32) {
+        self.watchpoints.retain(|wp| wp.id != id);
+    }
+
+    /// Drop the watchpoints that hold references into the allocator, which
+    /// is about to be reset. Global and sprite watchpoints stay, like
+    /// breakpoints do.
+    pub fn clear_object_watchpoints(&mut self) {
+        self.watchpoints.retain(|wp| {
+            matches!(wp.target, WatchTarget::Global(..) | WatchTarget::Sprite { .. })
+        });
+        self.pending_break = None;
+    }
+
+    pub fn watches_global(&self, name: &str) -> bool {
+        self.watchpoints.iter().any(|wp| wp.target.is_global(name))
+    }
+
+    pub fn watches_property(&self, instance: &ScriptInstanceRef, prop_name: &str) -> bool {
+        self.watchpoints.iter().any(|wp| wp.target.is_property(instance, prop_name))
+    }
+
+    pub fn watches_sprite(&self, channel: i16, prop_name: &str) -> bool {
+        self.watchpoints.iter().any(|wp| wp.target.is_sprite(channel, prop_name))
+    }
+
+    pub fn watches_list(&self, list_ref: &DatumRef) -> bool {
+        self.watchpoints.iter().any(|wp| wp.target.is_list(list_ref))
+    }
+
+    pub fn take_pending_break(&mut self) -> Option<WatchHit> {
+        self.pending_break.take()
+    }
+}
+
+impl WatchTarget {
+    fn is_global(&self, name: &str) -> bool {
+        matches!(self, WatchTarget::Global(watched) if watched.eq_ignore_ascii_case(name))
+    }
+
+    fn is_property(&self, instance: &ScriptInstanceRef, name: &str) -> bool {
+        matches!(self, WatchTarget::Property { instance: watched, prop_name }
+            if watched.id() == instance.id() && prop_name.eq_ignore_ascii_case(name))
+    }
+
+    fn is_sprite(&self, sprite: i16, name: &str) -> bool {
+        matches!(self, WatchTarget::Sprite { channel, prop_name }
+            if *channel == sprite && prop_name.eq_ignore_ascii_case(name))
+    }
+
+    fn is_list(&self, list_ref: &DatumRef) -> bool {
+        matches!(self, WatchTarget::List(watched) if watched.unwrap() == list_ref.unwrap())
+    }
+}
+
+/// Report a change to every watchpoint matching `target`. Values that
+/// format the same aren't a change.
+pub fn notify_change(
+    player: &mut DirPlayer,
+    is_target: impl Fn(&WatchTarget) -> bool,
+    old_value: String,
+    new_value: String,
+) {
+    if old_value == new_value {
+        return;
+    }
+    let (script_name, handler_name, bytecode_index) = current_location(player);
+    let mut hits = vec![];
+    for wp in player.watch_manager.watchpoints.iter_mut().filter(|wp| is_target(&wp.target)) {
+        wp.hit_count += 1;
+        let hit = WatchHit {
+            watchpoint_id: wp.id,
+            target: wp.target.describe(),
+            old_value: old_value.clone(),
+            new_value: new_value.clone(),
+            script_name: script_name.clone(),
+            handler_name: handler_name.clone(),
+            bytecode_index,
+        };
+        hits.push((wp.action, hit));
+    }
+    for (action, hit) in hits {
+        // Outside a handler there's nothing to pause, so a break is logged.
+        if action == WatchAction::Break && bytecode_index.is_some() {
+            player.watch_manager.pending_break = Some(hit);
+        } else {
+            trace_output(player, &hit.message());
+        }
+    }
+}
+
+/// Script, handler and bytecode position of the innermost running handler.
+fn current_location(player: &DirPlayer) -> (Option<String>, Option<String>, Option<usize>) {
+    if player.scope_count == 0 {
+        return (None, None, None);
+    }
+    let Some(scope) = player.scopes.get(player.current_scope_ref()) else {
+        return (None, None, None);
+    };
+    let cast_manager = &player.movie.cast_manager;
+    let script_name = cast_manager.get_script_by_ref(&scope.script_ref).map(|s| s.name.clone());
+    let handler_name = cast_manager
+        .get_cast(scope.script_ref.cast_lib as u32)
+        .ok()
+        .and_then(|cast| cast.lctx.as_ref())
+        .and_then(|lctx| lctx.names.get(scope.handler_name_id as usize))
+        .cloned();
+    (script_name, handler_name, Some(scope.bytecode_index))
+}
+
+fn format_value(player: &DirPlayer, value: Option<&DatumRef>) -> String {
+    format_datum(value.unwrap_or(&DatumRef::Void), player)
+}
+
+/// Assign a global, reporting the change if it's watched.
+pub fn set_global(player: &mut DirPlayer, name: String, value_ref: DatumRef) {
+    if !player.watch_manager.watches_global(&name) {
+        player.globals.insert(name, value_ref);
+        return;
+    }
+    let old_value = format_value(player, player.globals.get(&name));
+    let new_value = format_value(player, Some(&value_ref));
+    player.globals.insert(name.clone(), value_ref);
+    notify_change(player, |target| target.is_global(&name), old_value, new_value);
+}
+
+/// A property's current value, looked up through the ancestor chain.
+pub fn instance_prop(player: &DirPlayer, instance: &ScriptInstanceRef, prop_name: &str) -> Option<DatumRef> {
+    let mut instance = instance.clone();
+    loop {
+        let script_instance = player.allocator.get_script_instance(&instance);
+        if let Some(value) = script_instance.properties.get(CiStr::new(prop_name)) {
+            return Some(value.clone());
+        }
+        instance = script_instance.ancestor.clone()?;
+    }
+}
+
+/// Run a property assignment, reporting the change if it's watched.
+pub fn observe_property(
+    player: &mut DirPlayer,
+    instance: &ScriptInstanceRef,
+    prop_name: &str,
+    set: impl FnOnce(&mut DirPlayer) -> Result<(), ScriptError>,
+) -> Result<(), ScriptError> {
+    if !player.watch_manager.watches_property(instance, prop_name) {
+        return set(player);
+    }
+    let old_value = format_value(player, instance_prop(player, instance, prop_name).as_ref());
+    set(player)?;
+    let new_value = format_value(player, instance_prop(player, instance, prop_name).as_ref());
+    notify_change(player, |target| target.is_property(instance, prop_name), old_value, new_value);
+    Ok(())
+}
+
+/// Run `mutate`, which may change the list at `list_ref`, reporting the
+/// change if the list is watched. Takes the player itself, so call it
+/// outside `reserve_player_*`.
+pub fn observe_list<T>(
+    list_ref: &DatumRef,
+    mutate: impl FnOnce() -> Result<T, ScriptError>,
+) -> Result<T, ScriptError> {
+    let old_value = reserve_player_ref(|player| {
+        player
+            .watch_manager
+            .watches_list(list_ref)
+            .then(|| format_datum(list_ref, player))
+    });
+    let Some(old_value) = old_value else {
+        return mutate();
+    };
+    let result = mutate()?;
+    reserve_player_mut(|player| {
+        let new_value = format_datum(list_ref, player);
+        notify_change(player, |target| target.is_list(list_ref), old_value, new_value);
+    });
+    Ok(result)
+}
+
+/// Run a sprite property assignment, reporting the change if it's
+/// watched. `get` reads the property's current value.
+pub fn observe_sprite(
+    channel: i16,
+    prop_name: &str,
+    get: impl Fn(&mut DirPlayer) -> Result<Datum, ScriptError>,
+    set: impl FnOnce() -> Result<(), ScriptError>,
+) -> Result<(), ScriptError> {
+    let sprite_value = |player: &mut DirPlayer| {
+        let value = get(player).unwrap_or(Datum::Void);
+        format_concrete_datum(&value, player)
+    };
+    let old_value = reserve_player_mut(|player| {
+        player
+            .watch_manager
+            .watches_sprite(channel, prop_name)
+            .then(|| sprite_value(player))
+    });
+    let Some(old_value) = old_value else {
+        return set();
+    };
+    set()?;
+    reserve_player_mut(|player| {
+        let new_value = sprite_value(player);
+        notify_change(player, |target| target.is_sprite(channel, prop_name), old_value, new_value);
+    });
+    Ok(())
+}
@@ -19,7 +19,7 @@ use crate::{
     },
 };
 
-use super::{cast_lib::INVALID_CAST_MEMBER_REF, datum_formatting::format_datum, sprite::ColorRef, DatumRef, ScriptError};
+use super::{cast_lib::INVALID_CAST_MEMBER_REF, datum_formatting::format_datum, debug::watch, sprite::ColorRef, DatumRef, ScriptError};
 
 #[derive(Parser)]
 #[grammar = "lingo.pest"]
@@ -1230,7 +1230,7 @@ fn write_chunk_source(player: &mut DirPlayer, source_expr: &LingoExpr, new_strin
     match source_expr {
         LingoExpr::Identifier(name) => {
             let new_ref = player.alloc_datum(Datum::String(new_string));
-            player.globals.insert(name.clone(), new_ref);
+            watch::set_global(player, name.clone(), new_ref);
         },
         LingoExpr::HandlerCall(handler_name, args) if handler_name.eq_ignore_ascii_case("field") => {
             // field(name_or_num) or field(name_or_num, castLib_num)
@@ -1609,7 +1609,7 @@ pub async fn eval_lingo_expr_ast_runtime(expr: &LingoExpr) -> Result<DatumRef, S
                         player.set_movie_prop(prop_name, right_datum_value)?;
                         Ok(right_datum)
                     } else {
-                        player.globals.insert(ident_name.to_owned(), right_datum.clone());
+                        watch::set_global(player, ident_name.to_owned(), right_datum.clone());
                         Ok(right_datum)
                     }
                 }),
@@ -1769,7 +1769,7 @@ pub async fn eval_lingo_expr_ast_runtime(expr: &LingoExpr) -> Result<DatumRef, S
             
             // Set the global variable
             reserve_player_mut(|player| {
-                player.globals.insert(target_name, value.clone());
+                watch::set_global(player, target_name, value.clone());
                 Ok(DatumRef::Void)
             })
         },
@@ -1803,7 +1803,7 @@ pub async fn eval_lingo_expr_ast_runtime(expr: &LingoExpr) -> Result<DatumRef, S
                 let result = Datum::String(format!("{}{}", value_str, current_str));
                 let result_ref = player.alloc_datum(result);
                 
-                player.globals.insert(target_name, result_ref);
+                watch::set_global(player, target_name, result_ref);
                 Ok(DatumRef::Void)
             })
         },
@@ -1836,7 +1836,7 @@ pub async fn eval_lingo_expr_ast_runtime(expr: &LingoExpr) -> Result<DatumRef, S
                 let result = Datum::String(format!("{}{}", current_str, value_str));
                 let result_ref = player.alloc_datum(result);
                 
-                player.globals.insert(target_name, result_ref);
+                watch::set_global(player, target_name, result_ref);
                 Ok(DatumRef::Void)
             })
         },
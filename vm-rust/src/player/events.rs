@@ -270,6 +270,26 @@ pub async fn player_invoke_frame_and_movie_scripts(
     Ok(DatumRef::Void)
 }
 
+/// Send a mouse event to a sprite's behaviors, or to the frame and movie
+/// scripts when the sprite has none or no sprite was hit (`sprite_num <= 0`).
+pub async fn player_invoke_sprite_mouse_event(
+    handler_name: &str,
+    sprite_num: i16,
+) -> Result<(), ScriptError> {
+    let has_behaviors = reserve_player_ref(|player| {
+        sprite_num > 0
+            && player.movie.score.get_sprite(sprite_num).is_some_and(|sprite| {
+                player.sprite_has_script_instance_ids(sprite_num, sprite.script_instance_list.as_slice())
+            })
+    });
+    if has_behaviors {
+        player_dispatch_event_to_sprite_targeted(handler_name, &vec![], sprite_num as u16).await;
+    } else {
+        player_invoke_frame_and_movie_scripts(handler_name, &vec![]).await?;
+    }
+    Ok(())
+}
+
 pub async fn player_invoke_static_event(
     handler_name: &str,
     args: &Vec<DatumRef>,
@@ -441,6 +461,8 @@ pub async fn run_event_loop(rx: Receiver<PlayerVMEvent>) {
             return;
         }
         player_wait_available().await;
+        // Never dispatch into a window movie swapped in by a suspended tell block
+        crate::player::window::wait_for_stage_context().await;
         // After the semaphore yield, re-check generation before touching any
         // player state — a reset during the yield would make this dispatch
         // stale and any handler call would run on the new player.
@@ -914,6 +936,11 @@ pub async fn dispatch_system_event_to_timeouts(
     handler_name: &str,
     args: &Vec<DatumRef>,
 ) {
+    // Timeouts belong to the player, not to a movie. Only relay the stage
+    // movie's events so targets see each one once, not once per window.
+    if reserve_player_ref(|player| player.windows.current_window().is_some()) {
+        return;
+    }
     // Get all timeout targets that are currently scheduled
     let timeout_targets = reserve_player_ref(|player| {
         let mut targets = Vec::new();
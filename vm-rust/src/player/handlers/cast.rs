@@ -1,6 +1,11 @@
 use crate::{
     director::lingo::datum::Datum,
-    player::{reserve_player_mut, DatumRef, ScriptError},
+    js_api::JsApi,
+    player::{
+        reserve_player_mut,
+        save::{cast_lib_file_bytes, uncompressed_file_name},
+        DatumRef, ScriptError,
+    },
 };
 
 pub struct CastHandlers {}
@@ -22,6 +27,27 @@ impl CastHandlers {
         })
     }
 
+    /// `save castLib n [, fileName]` - hands an external cast, written as an
+    /// uncompressed `.cst`, to the host page.
+    pub fn save(args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let number = match args.first().map(|arg| player.get_datum(arg)) {
+                Some(Datum::CastLib(number)) => *number,
+                _ => return Err(ScriptError::new("save: expected a castLib".to_string())),
+            };
+            let path = match args.get(1) {
+                Some(path) => player.get_datum(path).string_value()?,
+                None => {
+                    let file_name = &player.movie.cast_manager.get_cast(number)?.file_name;
+                    uncompressed_file_name(file_name.rsplit('/').next().unwrap_or_default())
+                }
+            };
+            let bytes = cast_lib_file_bytes(player, number)?;
+            JsApi::dispatch_file_saved(&path, &bytes);
+            Ok(DatumRef::Void)
+        })
+    }
+
     pub fn find_empty(args: &Vec<DatumRef>) -> Result<DatumRef, ScriptError> {
         reserve_player_mut(|player| {
             let member_ref = player.get_datum(&args[0]).to_member_ref()?;
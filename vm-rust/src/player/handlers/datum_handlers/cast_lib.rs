@@ -1,6 +1,7 @@
 use crate::{
     director::lingo::datum::Datum,
     player::{
+        handlers::cast::CastHandlers,
         cast_lib::CastMemberRef, reserve_player_mut, DatumRef, ScriptError,
         ScriptErrorCode,
     },
@@ -18,6 +19,11 @@ impl CastLibDatumHandlers {
             "getPropRef" | "getProp" => Self::get_prop_ref(datum, args),
             "count" => Self::count(datum, args),
             "findEmpty" => Self::find_empty(datum, args),
+            "save" => {
+                let mut save_args = vec![datum.clone()];
+                save_args.extend(args.iter().cloned());
+                CastHandlers::save(&save_args)
+            }
             _ => Err(ScriptError::new_code(
                 ScriptErrorCode::HandlerNotFound,
                 format!("No handler {handler_name} for castLib datum"),
@@ -17,6 +17,7 @@ use crate::{
         handlers::datum_handlers::{
             cast_member_ref::borrow_member_mut, string_chunk::StringChunkUtils,
         },
+        hyperlinks::{hyperlinks_from_ranges, TextHyperlink},
         DatumRef, DirPlayer, ScriptError,
     },
 };
@@ -76,16 +77,22 @@ pub struct HtmlParser;
 impl HtmlParser {
     /// Parse HTML into styled spans without external dependencies
     pub fn parse_html(html: &str) -> Result<Vec<StyledSpan>, String> {
+        Self::parse_html_with_links(html).map(|(spans, _)| spans)
+    }
+
+    /// Parse HTML into styled spans plus the `<a href>` links over the span text.
+    pub fn parse_html_with_links(html: &str) -> Result<(Vec<StyledSpan>, Vec<TextHyperlink>), String> {
         let mut spans = Vec::new();
+        let mut links = Vec::new();
         let mut default_style = HtmlStyle::default();
 
         // Extract body attributes for global styling
         Self::extract_body_style(html, &mut default_style);
 
         // Simple regex-free HTML parsing
-        Self::parse_html_recursive(html, &mut spans, default_style);
+        Self::parse_html_recursive(html, &mut spans, &mut links, default_style);
 
-        Ok(spans)
+        Ok((spans, hyperlinks_from_ranges(links)))
     }
 
     fn extract_body_style(html: &str, style: &mut HtmlStyle) {
@@ -130,7 +137,15 @@ impl HtmlParser {
         None
     }
 
-    fn parse_html_recursive(html: &str, spans: &mut Vec<StyledSpan>, current_style: HtmlStyle) {
+    fn parse_html_recursive(
+        html: &str,
+        spans: &mut Vec<StyledSpan>,
+        links: &mut Vec<(usize, usize, String)>,
+        current_style: HtmlStyle,
+    ) {
+        let text_len = |spans: &Vec<StyledSpan>| spans.iter().map(|s| s.text.chars().count()).sum::<usize>();
+        // Open `<a href>`: link data and the char position it starts at
+        let mut open_link: Option<(usize, String)> = None;
         let mut pos = 0;
         let mut style_stack = vec![current_style];
         let chars: Vec<char> = html.chars().collect();
@@ -155,6 +170,9 @@ impl HtmlParser {
                         } else if skip_content_depth == 0 && style_stack.len() > 1 {
                             style_stack.pop();
                         }
+                        if closing_tag == "a" && let Some((start, data)) = open_link.take() {
+                            links.push((start, text_len(spans), data));
+                        }
                     } else {
                         // Handle opening tags
                         let mut new_style = style_stack.last().unwrap().clone();
@@ -187,6 +205,11 @@ impl HtmlParser {
                                 "b" | "strong" => new_style.bold = true,
                                 "i" | "em" => new_style.italic = true,
                                 "u" => new_style.underline = true,
+                                "a" => {
+                                    if let Some(href) = Self::extract_tag_attr(&tag, "href") {
+                                        open_link = Some((text_len(spans), href));
+                                    }
+                                }
                                 "br" => {
                                     spans.push(StyledSpan {
                                         text: "\n".to_string(),
@@ -7,6 +7,7 @@ pub mod shockwave3d;
 pub mod sound;
 pub mod text;
 pub mod palette;
+pub mod transition;
 pub mod havok;
 pub mod havok_physics;
 pub mod hke_parser;
@@ -1,4 +1,13 @@
-use crate::{director::lingo::datum::Datum, player::{DirPlayer, ScriptError, cast_lib::CastMemberRef, cast_member::{CastMemberType, Media}}};
+use crate::{
+    director::lingo::datum::{Datum, DatumType},
+    player::{
+        bitmap::bitmap::{get_system_default_palette, resolve_color_ref, PaletteRef},
+        cast_lib::CastMemberRef,
+        cast_member::{CastMemberType, Media},
+        sprite::ColorRef,
+        DirPlayer, ScriptError,
+    },
+};
 
 
 pub struct PaletteMemberHandlers;
@@ -14,6 +23,18 @@ impl PaletteMemberHandlers {
                 };
                 Ok(Datum::Media(Media::Palette(palette)))
             }
+            "colors" => {
+                let palette_member = player.movie.cast_manager.find_member_by_ref(member_ref).unwrap();
+                let colors = match &palette_member.member_type {
+                    CastMemberType::Palette(palette) => palette.colors.clone(),
+                    _ => return Err(ScriptError::new(format!("Member with ref {:?} is not a palette", member_ref))),
+                };
+                let color_refs = colors
+                    .into_iter()
+                    .map(|(r, g, b)| player.alloc_datum(Datum::ColorRef(ColorRef::Rgb(r, g, b))))
+                    .collect();
+                Ok(Datum::List(DatumType::List, color_refs, false))
+            }
             _ => Err(ScriptError::new(format!("Cannot get property '{}' for palette member", prop_name))),
         }
     }
@@ -32,6 +53,23 @@ impl PaletteMemberHandlers {
                     }
                     _ => return Err(ScriptError::new(format!("Member with ref {:?} is not a palette", member_ref))),
                 };
+                _player.movie.cast_manager.invalidate_palette_cache();
+                Ok(())
+            }
+            "colors" => {
+                let palettes = _player.movie.cast_manager.palettes();
+                let system_palette = PaletteRef::BuiltIn(get_system_default_palette());
+                let mut colors = Vec::new();
+                for item in value.to_list()? {
+                    let color = _player.get_datum(item).to_color_ref()?;
+                    colors.push(resolve_color_ref(&palettes, color, &system_palette, 8));
+                }
+                let palette_member = _player.movie.cast_manager.find_mut_member_by_ref(member_ref).unwrap();
+                match &mut palette_member.member_type {
+                    CastMemberType::Palette(palette) => palette.colors = colors,
+                    _ => return Err(ScriptError::new(format!("Member with ref {:?} is not a palette", member_ref))),
+                };
+                _player.movie.cast_manager.invalidate_palette_cache();
                 Ok(())
             }
             _ => Err(ScriptError::new(format!("Cannot set property '{}' for palette member", prop_name))),
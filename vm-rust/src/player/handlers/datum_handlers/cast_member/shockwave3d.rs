@@ -925,8 +925,20 @@ impl Shockwave3dMemberHandlers {
                             ))
                         })?;
 
-                    // registerForEvent / registerScript — stub (event system not implemented)
+                    // registerForEvent(eventName, handlerName, scriptObject, ...) — only
+                    // #collideAny is dispatched; other events are accepted and ignored
                     if handler_name == "registerForEvent" || handler_name == "registerScript" {
+                        if args.len() >= 3 {
+                            let event_name = player.get_datum(&args[0]).string_value().unwrap_or_default();
+                            if event_name.eq_ignore_ascii_case("collideAny") {
+                                let handler = player.get_datum(&args[1]).string_value().unwrap_or_default();
+                                if let Some(w3d) = player.movie.cast_manager.find_mut_member_by_ref(&member_ref)
+                                    .and_then(|m| m.member_type.as_shockwave3d_mut())
+                                {
+                                    w3d.runtime_state.collide_any_handlers.push((handler, args[2].clone()));
+                                }
+                            }
+                        }
                         return Ok(player.alloc_datum(Datum::Void));
                     }
 
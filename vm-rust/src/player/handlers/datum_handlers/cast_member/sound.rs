@@ -1,5 +1,5 @@
 use crate::{
-    director::lingo::datum::Datum,
+    director::lingo::datum::{Datum, DatumType},
     player::{cast_lib::CastMemberRef, reserve_player_mut, DirPlayer, ScriptError},
 };
 
@@ -25,6 +25,22 @@ impl SoundMemberHandlers {
             "channelCount" => Ok(Datum::Int(sound.info.channels as i32)),
             "sampleCount" => Ok(Datum::Int(sound.info.sample_count as i32)),
             "loop" => Ok(Datum::Int(if sound.info.loop_enabled { 1 } else { 0 })),
+            "cuePointNames" => {
+                let names: Vec<String> = sound.cue_points.iter().map(|p| p.name.clone()).collect();
+                let items = names
+                    .into_iter()
+                    .map(|name| player.alloc_datum(Datum::String(name)))
+                    .collect();
+                Ok(Datum::List(DatumType::List, items, false))
+            }
+            "cuePointTimes" => {
+                let times: Vec<u32> = sound.cue_points.iter().map(|p| p.time_ms).collect();
+                let items = times
+                    .into_iter()
+                    .map(|time| player.alloc_datum(Datum::Int(time as i32)))
+                    .collect();
+                Ok(Datum::List(DatumType::List, items, false))
+            }
             _ => Err(ScriptError::new(format!(
                 "Cannot get castMember property {} for sound",
                 prop
@@ -17,6 +17,7 @@ use crate::{
             cast_member::font::{FontMemberHandlers, HtmlParser, HtmlStyle, StyledSpan, TextAlignment},
             cast_member_ref::borrow_member_mut, string_chunk::StringChunkUtils,
         },
+        hyperlinks::hyperlinks_from_ranges,
         DatumRef, DirPlayer, ScriptError,
     },
 };
@@ -42,6 +43,8 @@ struct ParsedRtf {
     /// Member-level `bottom_spacing` (pt), companion to
     /// `member_top_spacing` from `\sa<n>`.
     member_bottom_spacing: Option<i16>,
+    /// `HYPERLINK` fields as `(start, end, data)` char ranges over `text`.
+    hyperlinks: Vec<(usize, usize, String)>,
 }
 
 impl TextMemberHandlers {
@@ -287,8 +290,17 @@ impl TextMemberHandlers {
                     html.push_str(&format!("<font face=\"{}\">", text_data.font));
                 }
 
-                // Add text content
-                html.push_str(&text_data.text);
+                // Add text content, wrapping hyperlinks in anchors
+                let mut link_iter = text_data.hyperlinks.iter().peekable();
+                for (idx, ch) in text_data.text.chars().enumerate() {
+                    if let Some(link) = link_iter.peek() && link.start == idx {
+                        html.push_str(&format!("<a href=\"{}\">", link.data));
+                    }
+                    html.push(ch);
+                    if link_iter.next_if(|link| link.end == idx + 1).is_some() {
+                        html.push_str("</a>");
+                    }
+                }
 
                 // Close tags
                 if !text_data.font.is_empty() {
@@ -843,6 +855,11 @@ impl TextMemberHandlers {
                     Err(ScriptError::new("TextInfo not available for this member".to_string()))
                 }
             }
+            "hyperlinks" => {
+                let ranges: Vec<Datum> = text_data.hyperlinks.iter().map(|link| link.range_datum(player)).collect();
+                let items = ranges.into_iter().map(|range| player.alloc_datum(range)).collect();
+                Ok(Datum::List(DatumType::List, items, false))
+            }
             "usehypertextstyles" => {
                 if let Some(ref info) = text_data.info {
                     Ok(datum_bool(info.use_hypertext_styles))
@@ -1751,6 +1768,7 @@ impl TextMemberHandlers {
                     // setter will seed from member-level defaults — matching
                     // Director's behaviour where rewriting `.text` resets styling.
                     text_member.html_styled_spans.clear();
+                    text_member.hyperlinks.clear();
 
                     Ok(())
                 },
@@ -1893,7 +1911,7 @@ impl TextMemberHandlers {
                 |player| value.string_value(),
                 |cast_member, value| {
                     let html_string = value?;
-                    let spans = HtmlParser::parse_html(&html_string).map_err(|e| {
+                    let (spans, hyperlinks) = HtmlParser::parse_html_with_links(&html_string).map_err(|e| {
                         ScriptError::new(format!("Failed to parse HTML: {}", e))
                     })?;
                     let text_member = cast_member.member_type.as_text_mut().unwrap();
@@ -2017,6 +2035,7 @@ impl TextMemberHandlers {
 
                     // Store all styled spans for rendering
                     text_member.html_styled_spans = spans;
+                    text_member.hyperlinks = hyperlinks;
                     Ok(())
                 },
             ),
@@ -2534,6 +2553,7 @@ impl TextMemberHandlers {
                     text_member.html_styled_spans = parsed.spans;
                     text_member.par_infos = parsed.par_infos;
                     text_member.par_runs = parsed.par_runs;
+                    text_member.hyperlinks = hyperlinks_from_ranges(parsed.hyperlinks);
                     if let Some(ts) = parsed.member_top_spacing {
                         text_member.top_spacing = ts;
                     }
@@ -2986,6 +3006,12 @@ impl TextMemberHandlers {
         // commits a par_info entry and a par_run for this position.
         let mut paragraph_start_pos: u32 = 0;
 
+        // `HYPERLINK` target of the field being read, and the link opened by
+        // its `{\fldrslt ...}` group as (group depth, start char, target).
+        let mut pending_link: Option<String> = None;
+        let mut open_link: Option<(i32, usize, String)> = None;
+        let mut hyperlinks: Vec<(usize, usize, String)> = Vec::new();
+
         // Buffer for the run currently being built. Flushed into a
         // StyledSpan whenever the active style changes or a paragraph
         // break is emitted.
@@ -3039,6 +3065,14 @@ impl TextMemberHandlers {
                     depth += 1;
                     // Detect groups whose contents we should skip entirely.
                     let rest: String = chars[i + 1..len.min(i + 32)].iter().collect();
+                    if skip_depth.is_none() {
+                        if rest.starts_with("\\*\\fldinst") {
+                            pending_link = Self::rtf_field_hyperlink(&chars[i..]);
+                        } else if rest.starts_with("\\fldrslt") && let Some(target) = pending_link.take() {
+                            flush_run(&mut run_buf, &mut run_style, &mut spans);
+                            open_link = Some((depth, text.chars().count(), target));
+                        }
+                    }
                     if rest.starts_with("\\fonttbl")
                         || rest.starts_with("\\colortbl")
                         || rest.starts_with("\\stylesheet")
@@ -3063,6 +3097,10 @@ impl TextMemberHandlers {
                         // Style about to change at the `}` — flush.
                         flush_run(&mut run_buf, &mut run_style, &mut spans);
                     }
+                    if open_link.as_ref().is_some_and(|(link_depth, _, _)| *link_depth == depth) {
+                        let (_, start, target) = open_link.take().unwrap();
+                        hyperlinks.push((start, text.chars().count(), target));
+                    }
                     if stack.len() > 1 {
                         stack.pop();
                     }
@@ -3354,7 +3392,33 @@ impl TextMemberHandlers {
             par_runs,
             member_top_spacing: first_sb_twips.map(|t| (t / 20) as i16),
             member_bottom_spacing: first_sa_twips.map(|t| (t / 20) as i16),
+            hyperlinks,
+        }
+    }
+
+    /// The quoted target of a `{\*\fldinst HYPERLINK "target"}` group starting
+    /// at `chars[0]`, if the field is a hyperlink.
+    fn rtf_field_hyperlink(chars: &[char]) -> Option<String> {
+        let mut depth = 0;
+        let mut end = chars.len();
+        for (idx, ch) in chars.iter().enumerate() {
+            match ch {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = idx;
+                        break;
+                    }
+                }
+                _ => {}
+            }
         }
+        let instruction: String = chars[..end].iter().collect();
+        let after = &instruction[instruction.find("HYPERLINK")? + "HYPERLINK".len()..];
+        let start = after.find('"')? + 1;
+        let len = after[start..].find('"')?;
+        Some(after[start..start + len].to_string())
     }
 
     /// Walk an RTF string and extract `{\fonttbl{\fN\fswiss <name>;}...}`
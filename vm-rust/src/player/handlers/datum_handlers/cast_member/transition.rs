@@ -0,0 +1,56 @@
+use crate::{
+    director::{enums::TransitionType, lingo::datum::Datum},
+    player::{cast_lib::CastMemberRef, cast_member::TransitionMember, DirPlayer, ScriptError},
+};
+
+pub struct TransitionMemberHandlers;
+
+impl TransitionMemberHandlers {
+    fn get_member<'a>(player: &'a DirPlayer, member_ref: &CastMemberRef) -> Result<&'a TransitionMember, ScriptError> {
+        player
+            .movie
+            .cast_manager
+            .find_member_by_ref(member_ref)
+            .and_then(|member| member.member_type.as_transition())
+            .ok_or_else(|| ScriptError::new(format!("Member with ref {:?} is not a transition", member_ref)))
+    }
+
+    pub fn get_prop(player: &mut DirPlayer, member_ref: &CastMemberRef, prop_name: &str) -> Result<Datum, ScriptError> {
+        let info = &Self::get_member(player, member_ref)?.info;
+        match prop_name {
+            "transitionType" => Ok(Datum::Int(info.transition_type as i32)),
+            "duration" => Ok(Datum::Int(info.duration as i32)),
+            "chunkSize" => Ok(Datum::Int(info.chunk_size as i32)),
+            "changeArea" => Ok(Datum::Int(info.change_area as i32)),
+            _ => Err(ScriptError::new(format!("Cannot get property '{}' for transition member", prop_name))),
+        }
+    }
+
+    pub fn set_prop(player: &mut DirPlayer, member_ref: &CastMemberRef, prop_name: &str, value: Datum) -> Result<(), ScriptError> {
+        let member = player
+            .movie
+            .cast_manager
+            .find_mut_member_by_ref(member_ref)
+            .and_then(|member| member.member_type.as_transition_mut())
+            .ok_or_else(|| ScriptError::new(format!("Member with ref {:?} is not a transition", member_ref)))?;
+        match prop_name {
+            "transitionType" => {
+                member.info.transition_type = TransitionType::from(value.int_value()?.max(0) as u32);
+                Ok(())
+            }
+            "duration" => {
+                member.info.duration = value.int_value()?.clamp(0, u16::MAX as i32) as u16;
+                Ok(())
+            }
+            "chunkSize" => {
+                member.info.chunk_size = value.int_value()?.clamp(1, 128) as u8;
+                Ok(())
+            }
+            "changeArea" => {
+                member.info.change_area = value.to_bool()?;
+                Ok(())
+            }
+            _ => Err(ScriptError::new(format!("Cannot set property '{}' for transition member", prop_name))),
+        }
+    }
+}
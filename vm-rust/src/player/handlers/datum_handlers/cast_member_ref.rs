@@ -8,6 +8,7 @@ use super::cast_member::{
     havok::HavokPhysicsMemberHandlers,
     shockwave3d::Shockwave3dMemberHandlers,
     sound::SoundMemberHandlers, text::TextMemberHandlers, palette::PaletteMemberHandlers,
+    transition::TransitionMemberHandlers,
     vector_shape::VectorShapeMemberHandlers,
 };
 
@@ -533,6 +534,7 @@ impl CastMemberRefHandlers {
             CastMemberTypeId::Sound => SoundMemberHandlers::get_prop(player, cast_member_ref, prop),
             CastMemberTypeId::Font => FontMemberHandlers::get_prop(player, cast_member_ref, prop),
             CastMemberTypeId::Palette => PaletteMemberHandlers::get_prop(player, cast_member_ref, prop),
+            CastMemberTypeId::Transition => TransitionMemberHandlers::get_prop(player, cast_member_ref, prop),
             CastMemberTypeId::Shockwave3d => Shockwave3dMemberHandlers::get_prop(player, cast_member_ref, prop),
             CastMemberTypeId::HavokPhysics => HavokPhysicsMemberHandlers::get_prop(player, cast_member_ref, prop),
             CastMemberTypeId::Script => {
@@ -545,7 +547,7 @@ impl CastMemberRefHandlers {
                 match prop {
                     "text" => Ok(Datum::String("".to_string())),
                     "script" => Ok(Datum::ScriptRef(cast_member_ref.clone())),
-                    "scriptText" => Ok(Datum::String("".to_string())),
+                    "scriptText" => Ok(Datum::String(script_data.script_text.clone())),
                     "scriptType" => {
                         let symbol = match script_data.script_type {
                             ScriptType::Movie => "movie",
@@ -695,7 +697,19 @@ impl CastMemberRefHandlers {
         // props fall through to the wildcard arm (e.g. implicit bitmap conversion).
         if member_type == CastMemberTypeId::Script {
             match prop {
-                "scriptText" => return Ok(()), // No-op: no lingo compiler
+                "scriptText" => {
+                    let text = value.string_value()?;
+                    return reserve_player_mut(|player| {
+                        let dir_version = player.movie.dir_version;
+                        player
+                            .movie
+                            .cast_manager
+                            .get_cast_mut(member_ref.cast_lib as u32)
+                            .set_script_text(member_ref.cast_member as u32, &text, dir_version)?;
+                        player.movie.cast_manager.clear_movie_script_cache();
+                        Ok(())
+                    });
+                }
                 "scriptType" => {
                     let type_str = value.string_value()?;
                     let script_type = match type_str.to_lowercase().as_str() {
@@ -750,6 +764,9 @@ impl CastMemberRefHandlers {
             CastMemberTypeId::Palette => reserve_player_mut(|player| {
                 PaletteMemberHandlers::set_prop(player, member_ref, prop, value)
             }),
+            CastMemberTypeId::Transition => reserve_player_mut(|player| {
+                TransitionMemberHandlers::set_prop(player, member_ref, prop, value)
+            }),
             CastMemberTypeId::VectorShape => reserve_player_mut(|player| {
                 VectorShapeMemberHandlers::set_prop(player, member_ref, prop, value)
             }),
@@ -11,7 +11,7 @@ pub struct DateObject {
 impl DateObject {
     pub fn new(id: u32) -> Self {
         // Current time in milliseconds
-        let now_ms = js_sys::Date::now() as i64;
+        let now_ms = crate::player::testing_shared::now_ms() as i64;
         DateObject {
             id,
             timestamp_ms: now_ms,
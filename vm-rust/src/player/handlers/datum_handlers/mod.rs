@@ -27,6 +27,7 @@ pub mod float;
 pub mod shockwave3d_object;
 pub mod transform3d;
 pub mod havok_object;
+pub mod window;
 
 use player::PlayerDatumHandlers;
 use self::flash_object::FlashObjectDatumHandlers;
@@ -36,6 +37,7 @@ use self::date::DateDatumHandlers;
 use self::math::MathDatumHandlers;
 use self::vector::VectorDatumHandlers;
 use self::void::VoidDatumHandlers;
+use self::window::WindowDatumHandlers;
 use self::xml::XmlDatumHandlers;
 use self::{
     bitmap::BitmapDatumHandlers, list_handlers::ListDatumHandlers, point::PointDatumHandlers,
@@ -48,10 +50,10 @@ use std::collections::VecDeque;
 use crate::{
     director::lingo::datum::DatumType,
     player::{
-        format_datum, reserve_player_mut, reserve_player_ref,
+        debug::watch, format_datum, reserve_player_mut, reserve_player_ref,
         xtra::manager::{
             call_xtra_instance_async_handler, call_xtra_instance_handler,
-            has_xtra_instance_async_handler,
+            call_xtra_static_handler, has_xtra_instance_async_handler, has_xtra_static_handler,
         },
         DatumRef, ScriptError, ScriptErrorCode,
     },
@@ -97,9 +99,11 @@ pub async fn player_call_datum_handler(
 
     // let profile_token = start_profiling(format!("{}::{}", datum_type.type_str(), handler_name));
     let result = match datum_type {
-        DatumType::List => ListDatumHandlers::call(obj_ref, handler_name, args),
+        DatumType::List => watch::observe_list(obj_ref, || ListDatumHandlers::call(obj_ref, handler_name, args)),
         DatumType::XmlChildNodes => ListDatumHandlers::call(obj_ref, handler_name, args),
-        DatumType::PropList => PropListDatumHandlers::call(obj_ref, handler_name, args),
+        DatumType::PropList => {
+            watch::observe_list(obj_ref, || PropListDatumHandlers::call(obj_ref, handler_name, args))
+        }
         DatumType::String => StringDatumHandlers::call(obj_ref, handler_name, args),
         DatumType::StringChunk => StringChunkHandlers::call(obj_ref, handler_name, args),
         DatumType::ScriptRef => {
@@ -138,6 +142,13 @@ pub async fn player_call_datum_handler(
                 cast_member_ref::CastMemberRefHandlers::call(obj_ref, handler_name, args)
             }
         }
+        DatumType::WindowRef => {
+            if WindowDatumHandlers::has_async_handler(handler_name) {
+                Box::pin(WindowDatumHandlers::call_async(obj_ref, handler_name, args)).await
+            } else {
+                WindowDatumHandlers::call(obj_ref, handler_name, args)
+            }
+        }
         DatumType::Rect => RectDatumHandlers::call(obj_ref, handler_name, args),
         DatumType::Point => PointDatumHandlers::call(obj_ref, handler_name, args),
         DatumType::BitmapRef => BitmapDatumHandlers::call(obj_ref, handler_name, args),
@@ -155,6 +166,11 @@ pub async fn player_call_datum_handler(
                 full_args.extend(args.iter().cloned());
                 Box::pin(crate::player::handlers::types::TypeHandlers::new(&full_args)).await
             } else {
+                let xtra_name =
+                    reserve_player_ref(|player| player.get_datum(obj_ref).to_xtra_name().map(str::to_owned))?;
+                if has_xtra_static_handler(&xtra_name, handler_name) {
+                    return call_xtra_static_handler(&xtra_name, handler_name, args);
+                }
                 Err(ScriptError::new_code(
                     ScriptErrorCode::HandlerNotFound,
                     format!("No handler {handler_name} for Xtra datum"),
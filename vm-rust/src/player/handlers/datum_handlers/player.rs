@@ -27,7 +27,7 @@ impl PlayerDatumHandlers {
         reserve_player_mut(|player| {
             let subject = player.get_datum(&args[0]).string_value().unwrap();
             match subject.as_str() {
-                "windowList" => Ok(player.alloc_datum(Datum::Int(0))),
+                "windowList" => Ok(player.alloc_datum(Datum::Int(player.windows.windows.len() as i32))),
                 _ => Err(ScriptError::new(
                     format!("Invalid call _player.count({subject})").to_string(),
                 )),
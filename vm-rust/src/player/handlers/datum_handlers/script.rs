@@ -176,6 +176,7 @@ impl ScriptDatumHandlers {
                     script_ref.to_owned(),
                     script,
                     unsafe { &*lctx_ptr },
+                    player.windows.current_window(),
                 );
                 let instance_ref = player.allocator.alloc_script_instance(instance);
                 let datum_ref = player.alloc_datum(Datum::ScriptInstanceRef(instance_ref.clone()));
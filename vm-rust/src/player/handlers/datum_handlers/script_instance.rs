@@ -12,6 +12,7 @@ use crate::{
         script_ref::ScriptInstanceRef,
         DatumRef, DirPlayer, ScriptError, ScriptErrorCode,
         virtual_scripts::VirtualScriptRegistry,
+        window,
     },
 };
 use crate::player::script::script_get_prop_opt;
@@ -34,10 +35,8 @@ impl ScriptInstanceUtils {
                     .ok_or(ScriptError::new(format!(
                         "Script instance {instance_id} not found"
                     )))?;
-                let script = player
-                    .movie
-                    .cast_manager
-                    .get_script_by_ref(&instance.script)
+                let script = window::instance_movie(player, instance)
+                    .and_then(|movie| movie.cast_manager.get_script_by_ref(&instance.script))
                     .ok_or(ScriptError::new(format!("Script not found")))?;
                 Ok((instance_ref.clone(), script))
             }
@@ -91,10 +90,8 @@ impl ScriptInstanceUtils {
         player: &DirPlayer,
     ) -> Result<Option<ScriptHandlerRef>, ScriptError> {
         let instance = player.allocator.get_script_instance(instance_ref);
-        let script = player
-            .movie
-            .cast_manager
-            .get_script_by_ref(&instance.script);
+        let script = window::instance_movie(player, instance)
+            .and_then(|movie| movie.cast_manager.get_script_by_ref(&instance.script));
         if script.is_none() {
             // Script not found — cast library may be unloaded or empty
             return Ok(None);
@@ -188,6 +188,50 @@ impl Shockwave3dObjectDatumHandlers {
                     _ => Ok(player.alloc_datum(Datum::Void)),
                 })
             },
+            "collision" => {
+                let collision = {
+                    let member = player.movie.cast_manager.find_member_by_ref(member_ref);
+                    member.and_then(|m| m.member_type.as_shockwave3d())
+                        .and_then(|w3d| w3d.runtime_state.collision_state.get(&s3d_ref.name))
+                        .cloned()
+                        .unwrap_or_default()
+                };
+                match_ci!(prop_name, {
+                    "enabled" => Ok(player.alloc_datum(Datum::Int(if collision.enabled { 1 } else { 0 }))),
+                    "resolve" => Ok(player.alloc_datum(Datum::Int(if collision.resolve { 1 } else { 0 }))),
+                    "immovable" => Ok(player.alloc_datum(Datum::Int(if collision.immovable { 1 } else { 0 }))),
+                    "mode" => Ok(player.alloc_datum(Datum::Symbol(collision.mode.symbol().to_string()))),
+                    "name" => Ok(player.alloc_datum(Datum::Symbol("collision".to_string()))),
+                    _ => Ok(player.alloc_datum(Datum::Void)),
+                })
+            },
+            "collisionData" => {
+                // name is the index into runtime_state.collision_events
+                let event = {
+                    let idx: usize = s3d_ref.name.parse().unwrap_or(usize::MAX);
+                    let member = player.movie.cast_manager.find_member_by_ref(member_ref);
+                    member.and_then(|m| m.member_type.as_shockwave3d())
+                        .and_then(|w3d| w3d.runtime_state.collision_events.get(idx))
+                        .cloned()
+                };
+                let Some(event) = event else {
+                    return Ok(player.alloc_datum(Datum::Void));
+                };
+                use crate::director::lingo::datum::Shockwave3dObjectRef;
+                let model_ref = |name: String| Datum::Shockwave3dObjectRef(Shockwave3dObjectRef {
+                    cast_lib: member_ref.cast_lib,
+                    cast_member: member_ref.cast_member,
+                    object_type: "model".to_string(),
+                    name,
+                });
+                match_ci!(prop_name, {
+                    "modelA" => Ok(player.alloc_datum(model_ref(event.model_a))),
+                    "modelB" => Ok(player.alloc_datum(model_ref(event.model_b))),
+                    "pointOfContact" => Ok(player.alloc_datum(Datum::Vector(event.point.map(|v| v as f64)))),
+                    "collisionNormal" => Ok(player.alloc_datum(Datum::Vector(event.normal.map(|v| v as f64)))),
+                    _ => Ok(player.alloc_datum(Datum::Void)),
+                })
+            },
             "bone" => {
                 // name format is "modelName:boneIndex"
                 let parts: Vec<&str> = s3d_ref.name.splitn(2, ':').collect();
@@ -1040,6 +1084,29 @@ impl Shockwave3dObjectDatumHandlers {
                         }
                     }
                     Ok(())
+                  } else if s3d_ref.object_type == "collision" {
+                    // Collision modifier set properties
+                    let mode = match value {
+                        Datum::Symbol(s) | Datum::String(s) => crate::director::chunks::w3d::collision::CollisionMode::from_symbol(s),
+                        _ => None,
+                    };
+                    if let Some(rs) = runtime_state_mut(player, &member_ref) {
+                        let collision = rs.collision_state
+                            .entry(s3d_ref.name.clone())
+                            .or_insert_with(crate::player::cast_member::CollisionState::default);
+                        match_ci!(prop_name, {
+                            "enabled" => collision.enabled = value.int_value().unwrap_or(1) != 0,
+                            "resolve" => collision.resolve = value.int_value().unwrap_or(1) != 0,
+                            "immovable" => collision.immovable = value.int_value().unwrap_or(0) != 0,
+                            "mode" => {
+                                if let Some(mode) = mode {
+                                    collision.mode = mode;
+                                }
+                            },
+                            _ => {},
+                        })
+                    }
+                    Ok(())
                   } else if s3d_ref.object_type == "texture" && prop_name.eq_ignore_ascii_case("image") {
                     // texture("name").image = bitmapObject
                     // Convert bitmap to RGBA and store in scene.texture_images
@@ -1814,6 +1881,12 @@ impl Shockwave3dObjectDatumHandlers {
                                         .or_insert_with(crate::player::cast_member::LodState::default);
                                 }
                             }
+                        } else if mod_name.eq_ignore_ascii_case("collision") {
+                            let member_ref = CastMemberRef { cast_lib: s3d_ref.cast_lib, cast_member: s3d_ref.cast_member };
+                            if let Some(rs) = runtime_state_mut(player, &member_ref) {
+                                rs.collision_state.entry(s3d_ref.name.clone())
+                                    .or_insert_with(crate::player::cast_member::CollisionState::default);
+                            }
                         } else if mod_name == "meshDeform" {
                             let member_ref = CastMemberRef { cast_lib: s3d_ref.cast_lib, cast_member: s3d_ref.cast_member };
                             let (mesh_count, node_found, res_found) = {
@@ -1849,8 +1922,67 @@ impl Shockwave3dObjectDatumHandlers {
                     }
                     Ok(player.alloc_datum(Datum::Void))
                 },
-                "removeModifier" => Ok(player.alloc_datum(Datum::Void)),
-                "registerScript" | "registerForEvent" => Ok(player.alloc_datum(Datum::Void)),
+                "removeModifier" => {
+                    if !args.is_empty() {
+                        let mod_name = player.get_datum(&args[0]).string_value().unwrap_or_default();
+                        if mod_name.eq_ignore_ascii_case("collision")
+                            && let Some(rs) = runtime_state_mut(player, &member_ref)
+                        {
+                            rs.collision_state.remove(&s3d_ref.name);
+                        }
+                    }
+                    Ok(player.alloc_datum(Datum::Void))
+                },
+                "registerScript" | "registerForEvent" => {
+                    // node.registerScript(#collideWith | #collideAny, #handler, scriptObject)
+                    if args.len() >= 3 {
+                        let event_name = player.get_datum(&args[0]).string_value().unwrap_or_default();
+                        let handler = player.get_datum(&args[1]).string_value().unwrap_or_default();
+                        let target = args[2].clone();
+                        if let Some(rs) = runtime_state_mut(player, &member_ref) {
+                            if event_name.eq_ignore_ascii_case("collideWith") {
+                                if let Some(collision) = rs.collision_state.get_mut(&s3d_ref.name) {
+                                    collision.callback = Some((handler, target));
+                                }
+                            } else if event_name.eq_ignore_ascii_case("collideAny") {
+                                rs.collide_any_handlers.push((handler, target));
+                            }
+                        }
+                    }
+                    Ok(player.alloc_datum(Datum::Void))
+                },
+                // ─── Collision modifier methods ───
+                "setCollisionCallback" => {
+                    // model.collision.setCollisionCallback(#handler, scriptObject)
+                    if args.len() >= 2 {
+                        let handler = player.get_datum(&args[0]).string_value().unwrap_or_default();
+                        let target = args[1].clone();
+                        if let Some(rs) = runtime_state_mut(player, &member_ref) {
+                            rs.collision_state
+                                .entry(s3d_ref.name.clone())
+                                .or_insert_with(crate::player::cast_member::CollisionState::default)
+                                .callback = Some((handler, target));
+                        }
+                    }
+                    Ok(player.alloc_datum(Datum::Void))
+                },
+                "resolveA" | "resolveB" => {
+                    // collisionData.resolveA(bool) / resolveB(bool)
+                    let resolve = args.first()
+                        .map(|a| player.get_datum(a).int_value().unwrap_or(1) != 0)
+                        .unwrap_or(true);
+                    let idx: usize = s3d_ref.name.parse().unwrap_or(usize::MAX);
+                    if let Some(event) = runtime_state_mut(player, &member_ref)
+                        .and_then(|rs| rs.collision_events.get_mut(idx))
+                    {
+                        if handler_name.eq_ignore_ascii_case("resolveA") {
+                            event.resolve_a = resolve;
+                        } else {
+                            event.resolve_b = resolve;
+                        }
+                    }
+                    Ok(player.alloc_datum(Datum::Void))
+                },
                 "isInWorld" => Ok(player.alloc_datum(Datum::Int(1))),
                 // ─── Camera methods ───
                 "modelUnderLoc" => {
@@ -3940,6 +4072,22 @@ impl Shockwave3dObjectDatumHandlers {
                 )))
             },
             "debug" => Ok(player.alloc_datum(Datum::Int(0))),
+            "collision" => {
+                // Collision modifier — only present after addModifier(#collision)
+                let attached = player.movie.cast_manager.find_member_by_ref(member_ref)
+                    .and_then(|m| m.member_type.as_shockwave3d())
+                    .is_some_and(|w3d| w3d.runtime_state.collision_state.contains_key(model_name));
+                if !attached {
+                    return Ok(player.alloc_datum(Datum::Void));
+                }
+                use crate::director::lingo::datum::Shockwave3dObjectRef;
+                Ok(player.alloc_datum(Datum::Shockwave3dObjectRef(Shockwave3dObjectRef {
+                    cast_lib: member_ref.cast_lib,
+                    cast_member: member_ref.cast_member,
+                    object_type: "collision".to_string(),
+                    name: model_name.to_string(),
+                })))
+            },
             "meshDeform" => {
                 // Return a meshDeform ref pointing to this model
                 use crate::director::lingo::datum::Shockwave3dObjectRef;
@@ -4872,6 +5020,16 @@ fn canonical_node_key(
     node_name.to_string()
 }
 
+/// Mutable runtime state of a Shockwave 3D member.
+fn runtime_state_mut<'a>(
+    player: &'a mut crate::player::DirPlayer,
+    member_ref: &crate::player::cast_lib::CastMemberRef,
+) -> Option<&'a mut crate::player::cast_member::Shockwave3dRuntimeState> {
+    player.movie.cast_manager.find_mut_member_by_ref(member_ref)
+        .and_then(|m| m.member_type.as_shockwave3d_mut())
+        .map(|w3d| &mut w3d.runtime_state)
+}
+
 fn get_or_init_node_transform(
     player: &mut crate::player::DirPlayer,
     member_ref: &crate::player::cast_lib::CastMemberRef,
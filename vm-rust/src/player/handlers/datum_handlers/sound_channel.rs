@@ -20,7 +20,10 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsValue;
 use web_sys::console;
 
-use crate::player::cast_member::SoundMember;
+use crate::player::cast_member::{SoundCuePoint, SoundMember};
+use crate::player::audio::decode;
+use crate::player::audio::mixer::{Mixer, Voice};
+use crate::player::cue_points;
 use binary_reader::BinaryReader;
 use binary_reader::Endian;
 
@@ -28,27 +31,21 @@ use js_sys::Reflect;
 
 use wasm_bindgen_futures::JsFuture;
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use js_sys::Uint8Array;
 use log::{debug, error, warn};
 use wasm_bindgen_futures::spawn_local;
 
-// Standard IMA ADPCM tables
-const STEP_TABLE: [i32; 89] = [
-    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
-    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
-    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
-    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
-    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32773,
-];
-
-const INDEX_TABLE: [i32; 16] = [
-    -1, -1, -1, -1, 2, 4, 6, 8, // For the lower nibble
-    -1, -1, -1, -1, 2, 4, 6,
-    8, // (The full nibble 0-15 is used to map, but the first 8 and last 8 are often symmetrical)
-];
+/// The sound a channel plays after the current one ends.
+struct NextSound {
+    member_ref: DatumRef,
+    /// Same sound again (a loop)
+    repeat: bool,
+    /// Web Audio may replay the cached buffer instead of decoding again
+    gapless: bool,
+}
 
 #[derive(Debug, Clone)]
 pub struct SoundSegment {
@@ -240,6 +237,15 @@ impl SoundChannelDatumHandlers {
                 let is_busy = Self::handle_is_busy(player, datum)?;
                 Ok(player.alloc_datum(Datum::Int(if is_busy { 1 } else { 0 })))
             }
+            "ispastcuepoint" => {
+                if args.is_empty() {
+                    return Err(ScriptError::new(
+                        "isPastCuePoint requires a cue point argument".to_string(),
+                    ));
+                }
+                let result = Self::handle_is_past_cue_point(player, datum, &args[0])?;
+                Ok(player.alloc_datum(Datum::Int(result)))
+            }
             _ => Err(ScriptError::new(format!(
                 "No handler {handler_name} for sound channel"
             ))),
@@ -278,15 +284,8 @@ impl SoundChannelDatumHandlers {
                     None => Ok(Datum::Void),
                 }
             }
-            "currentTime" => {
-                let ct = if channel.status == SoundStatus::Playing {
-                    let elapsed = channel.audio_context.as_ref().map_or(0.0, |ctx| ctx.current_time()) - channel.playback_start_context_time;
-                    (channel.start_time + elapsed * 1000.0).min(channel.get_duration() as f64)
-                } else {
-                    channel.elapsed_time
-                };
-                Ok(Datum::Float(ct))
-            }
+            "currentTime" => Ok(Datum::Float(channel.current_time_ms())),
+            "mostRecentCuePoint" => Ok(Datum::Int(channel.most_recent_cue_point)),
             _ => Err(ScriptError::new(format!(
                 "Cannot get property {} for sound channel",
                 prop
@@ -404,6 +403,10 @@ impl SoundChannelDatumHandlers {
             ch.loops_remaining = 1;
         }
 
+        if channel_rc.borrow().is_headless() {
+            return channel_rc.borrow_mut().start_voice(player, member_ref);
+        }
+
         // Use play_file to start playback
         SoundChannel::play_file(channel_rc, member_ref.clone());
 
@@ -414,18 +417,19 @@ impl SoundChannelDatumHandlers {
         let channel = Self::get_sound_channel_mut(player, datum)?;
         let mut ch = channel.borrow_mut();
 
-        console::log_1(
-            &format!(
-                "🎬 handle_play() - Channel {} has {} items in playlist",
-                ch.channel_num,
-                ch.playlist_segments.len()
-            )
-            .into(),
+        debug!(
+            "🎬 handle_play() - Channel {} has {} items in playlist",
+            ch.channel_num,
+            ch.playlist_segments.len()
         );
 
         ch.stop_playback_nodes();
 
-        if !ch.playlist_segments.is_empty() {
+        if !ch.playlist_segments.is_empty() && ch.is_headless() {
+            ch.current_segment_index = Some(0);
+            let member_ref = ch.playlist_segments[0].member_ref.clone();
+            ch.start_voice(player, &member_ref)?;
+        } else if !ch.playlist_segments.is_empty() {
             ch.current_segment_index = Some(0);
             ch.status = SoundStatus::Playing;
             ch.playback_start_context_time = ch.context_time();
@@ -463,6 +467,13 @@ impl SoundChannelDatumHandlers {
             ch.loops_remaining = 1;
         }
 
+        if channel_rc.borrow().is_headless() {
+            let mut ch = channel_rc.borrow_mut();
+            ch.stop_playback_nodes();
+            ch.queued_members.clear();
+            return ch.start_voice(player, member);
+        }
+
         // Call the associated function with the Rc
         SoundChannel::play_file(Rc::clone(&channel_rc), member.clone());
 
@@ -647,10 +658,7 @@ impl SoundChannelDatumHandlers {
 
                     // ⚠️ loopCount negative (invalid)
                     (Some(_), loop_count) if loop_count < 0 => {
-                        console::log_1(
-                            &format!("  ⚠️ Skipped: invalid negative loopCount ({})", loop_count)
-                                .into(),
-                        );
+                        warn!("  ⚠️ Skipped: invalid negative loopCount ({})", loop_count);
                     }
 
                     // ⚠️ missing member entirely
@@ -660,9 +668,7 @@ impl SoundChannelDatumHandlers {
 
                     // 🧩 fallback (compiler exhaustiveness guard)
                     _ => {
-                        console::log_1(
-                            &"  ⚠️ Unexpected combination of properties — skipped".into(),
-                        );
+                        warn!("  ⚠️ Unexpected combination of properties — skipped");
                     }
                 }
             } else {
@@ -717,6 +723,22 @@ impl SoundChannelDatumHandlers {
         Ok(channel.is_busy())
     }
 
+    /// `isPastCuePoint(n)` returns whether cue point n has been passed;
+    /// `isPastCuePoint("name")` returns how many cue points with that name
+    /// have been passed.
+    pub fn handle_is_past_cue_point(
+        player: &DirPlayer,
+        datum: &DatumRef,
+        cue_point_ref: &DatumRef,
+    ) -> Result<i32, ScriptError> {
+        let channel_rc = Self::get_sound_channel(player, datum)?;
+        let channel = channel_rc.borrow();
+        match player.get_datum(cue_point_ref) {
+            Datum::String(name) => Ok(channel.cue_points_passed_named(name)),
+            cue_point => Ok(channel.is_past_cue_point(cue_point.int_value()?) as i32),
+        }
+    }
+
     fn set_sound_volume(
         player: &mut DirPlayer,
         datum: &DatumRef,
@@ -1015,6 +1037,8 @@ impl WebAudioBackend {
 pub struct SoundManager {
     channels: Vec<Rc<RefCell<SoundChannel>>>,
     audio_context: Option<Arc<AudioContext>>,
+    /// Mixes the channels in Rust when there is no AudioContext
+    pub mixer: Mixer,
 }
 
 impl SoundManager {
@@ -1024,17 +1048,20 @@ impl SoundManager {
         #[cfg(not(target_arch = "wasm32"))]
         let context: Option<Arc<AudioContext>> = None;
 
+        let mixer = Mixer::default();
         let mut channels = Vec::with_capacity(num_channels);
         for i in 0..num_channels {
             channels.push(Rc::new(RefCell::new(SoundChannel::new(
                 i as i32,
                 context.clone(),
+                mixer.clock(),
             ))));
         }
 
         Ok(Self {
             channels,
             audio_context: context,
+            mixer,
         })
     }
 
@@ -1050,12 +1077,12 @@ impl SoundManager {
         self.channels.get(channel).cloned()
     }
 
-    pub fn update(&mut self, delta_time: f64, player: &mut DirPlayer) -> Result<(), ScriptError> {
+    pub fn update(&mut self, delta_time: f64) -> Result<(), ScriptError> {
         for channel in &self.channels {
             // debug: log that update tick is processing this channel
             debug!("[CH{}] update tick", channel.borrow().channel_num);
 
-            channel.borrow_mut().update(delta_time, player)?;
+            channel.borrow_mut().update(delta_time)?;
         }
         Ok(())
     }
@@ -1122,6 +1149,14 @@ pub struct SoundChannel {
     pub decode_generation: Rc<RefCell<u32>>, // Incremented each time a new decode starts
     pub playback_start_context_time: f64, // AudioContext.currentTime when playback started
 
+    // Headless playback (no AudioContext)
+    pub voice: Option<Voice>,
+    audio_clock: Rc<Cell<f64>>, // Seconds mixed by the headless mixer
+
+    // Cue point tracking
+    pub most_recent_cue_point: i32,
+    cue_position_ms: Option<f64>, // Playback position at the last cue point poll
+    cue_playback_start: f64,      // playback_start_context_time of the tracked playback
 }
 
 impl SoundChannel {
@@ -1130,155 +1165,15 @@ impl SoundChannel {
     }
 
     pub fn context_time(&self) -> f64 {
-        self.audio_context.as_ref().map_or(0.0, |ctx| ctx.current_time())
-    }
-
-    /// Find MP3 start with ROBUST validation (checks 3+ consecutive frames)
-    /// Also verifies minimum data size to avoid false positives
-    fn find_mp3_start(data: &[u8]) -> Option<usize> {
-        const MIN_FRAMES_TO_VALIDATE: usize = 3;
-        const MIN_MP3_SIZE: usize = 512; // Reduced for small Director sound effects (was 4096)
-        
-        // Skip if data is suspiciously small
-        if data.len() < MIN_MP3_SIZE {
-            console::log_1(&format!(
-                "⚠️ Data too small for MP3 ({} bytes < {} min)",
-                data.len(), MIN_MP3_SIZE
-            ).into());
-            return None;
-        }
-        
-        for i in 0..data.len().saturating_sub(4) {
-            if data[i] == 0xFF && (data[i + 1] & 0xE0) == 0xE0 {
-                // Calculate expected remaining data size
-                let remaining = data.len() - i;
-                
-                // If MP3 start is found too late in the data, it's likely a false positive
-                if remaining < MIN_MP3_SIZE {
-                    continue;
-                }
-                
-                if let Some(valid) = Self::validate_mp3_sequence(&data[i..], MIN_FRAMES_TO_VALIDATE) {
-                    if valid {
-                        console::log_1(&format!(
-                            "✅ Valid MP3 sequence found at offset {} ({} bytes remaining, validated {} frames)",
-                            i, remaining, MIN_FRAMES_TO_VALIDATE
-                        ).into());
-                        return Some(i);
-                    }
-                }
-            }
-        }
-        None
-    }
-
-    /// Validate multiple consecutive MP3 frames
-    fn validate_mp3_sequence(data: &[u8], min_frames: usize) -> Option<bool> {
-        let mut offset = 0;
-        let mut frames_found = 0;
-        
-        while frames_found < min_frames && offset < data.len().saturating_sub(4) {
-            if data[offset] != 0xFF || (data[offset + 1] & 0xE0) != 0xE0 {
-                return Some(false);
-            }
-            
-            let header = u32::from_be_bytes([
-                data[offset],
-                data[offset + 1],
-                if offset + 2 < data.len() { data[offset + 2] } else { 0 },
-                if offset + 3 < data.len() { data[offset + 3] } else { 0 },
-            ]);
-            
-            let version = (header >> 19) & 0x3;
-            let layer = (header >> 17) & 0x3;
-            let bitrate_index = (header >> 12) & 0xF;
-            let sample_rate_index = (header >> 10) & 0x3;
-            
-            if version == 1 || layer == 0 || bitrate_index == 0xF || 
-            bitrate_index == 0 || sample_rate_index == 3 {
-                return Some(false);
-            }
-            
-            let frame_size = Self::calculate_mp3_frame_size(header);
-            if frame_size == 0 || frame_size > 4096 {
-                return Some(false);
-            }
-            
-            frames_found += 1;
-            offset += frame_size;
-            
-            if offset + 4 > data.len() {
-                break;
-            }
+        match self.audio_context.as_ref() {
+            Some(ctx) => ctx.current_time(),
+            None => self.audio_clock.get(),
         }
-        
-        Some(frames_found >= min_frames || (frames_found > 0 && offset >= data.len() - 4))
     }
 
-    /// Calculate MP3 frame size from header
-    fn calculate_mp3_frame_size(header: u32) -> usize {
-        const BITRATES: [[[u32; 16]; 4]; 4] = [
-            // MPEG 2.5
-            [
-                [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-                [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0],
-                [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0],
-                [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256, 0],
-            ],
-            // Reserved
-            [
-                [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-                [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-                [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-                [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-            ],
-            // MPEG 2
-            [
-                [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-                [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0],
-                [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0],
-                [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256, 0],
-            ],
-            // MPEG 1
-            [
-                [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-                [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 0],
-                [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 0],
-                [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448, 0],
-            ],
-        ];
-        
-        const SAMPLE_RATES: [[u32; 4]; 4] = [
-            [11025, 12000, 8000, 0],
-            [0, 0, 0, 0],
-            [22050, 24000, 16000, 0],
-            [44100, 48000, 32000, 0],
-        ];
-        
-        let version = ((header >> 19) & 0x3) as usize;
-        let layer = ((header >> 17) & 0x3) as usize;
-        let bitrate_index = ((header >> 12) & 0xF) as usize;
-        let sample_rate_index = ((header >> 10) & 0x3) as usize;
-        let padding = (header >> 9) & 0x1;
-        
-        if version >= 4 || layer >= 4 || bitrate_index >= 16 || sample_rate_index >= 4 {
-            return 0;
-        }
-        
-        let bitrate = BITRATES[version][layer][bitrate_index];
-        let sample_rate = SAMPLE_RATES[version][sample_rate_index];
-        
-        if bitrate == 0 || sample_rate == 0 {
-            return 0;
-        }
-        
-        let frame_size = if layer == 3 {
-            ((12 * bitrate * 1000 / sample_rate) + padding) * 4
-        } else {
-            (144 * bitrate * 1000 / sample_rate) + padding
-        };
-        
-        frame_size as usize
+    /// True when this channel is mixed in Rust instead of through Web Audio.
+    pub fn is_headless(&self) -> bool {
+        self.audio_context.is_none()
     }
 
     pub fn play_member_direct(
@@ -1304,7 +1199,11 @@ impl SoundChannel {
         Ok(())
     }
 
-    pub fn new(channel: i32, audio_context: Option<Arc<AudioContext>>) -> Self {
+    pub fn new(
+        channel: i32,
+        audio_context: Option<Arc<AudioContext>>,
+        audio_clock: Rc<Cell<f64>>,
+    ) -> Self {
         Self {
             channel_num: channel,
             member: None,
@@ -1340,6 +1239,11 @@ impl SoundChannel {
             is_decoding: Rc::new(RefCell::new(false)),
             decode_generation: Rc::new(RefCell::new(0)),
             playback_start_context_time: 0.0,
+            voice: None,
+            audio_clock,
+            most_recent_cue_point: 0,
+            cue_position_ms: None,
+            cue_playback_start: 0.0,
         }
     }
 
@@ -1621,18 +1525,35 @@ impl SoundChannel {
                 }
             }
             other => {
-                console::log_1(
-                    &format!(
-                        "⚠️ #member is not a CastMember, it's {:?}",
-                        other.type_str()
-                    )
-                    .into(),
-                );
+                warn!("⚠️ #member is not a CastMember, it's {:?}", other.type_str());
                 None
             }
         }
     }
 
+    /// Play `member_ref` from `position_ms`, as when restoring a save state.
+    /// Web Audio playback starts over from the beginning of the sound.
+    pub fn resume_at(
+        self_rc: Rc<RefCell<Self>>,
+        player: &DirPlayer,
+        member_ref: &DatumRef,
+        position_ms: f64,
+    ) -> Result<(), ScriptError> {
+        if !self_rc.borrow().is_headless() {
+            Self::play_file(self_rc, member_ref.clone());
+            return Ok(());
+        }
+        let mut channel = self_rc.borrow_mut();
+        channel.start_voice(player, member_ref)?;
+        if let Some(voice) = channel.voice.as_mut() {
+            voice.seek_ms(position_ms);
+        }
+        // Cue points before the restored position have already been passed.
+        channel.cue_position_ms = Some(channel.current_time_ms());
+        channel.cue_playback_start = channel.playback_start_context_time;
+        Ok(())
+    }
+
     pub fn play_file(self_rc: Rc<RefCell<Self>>, member_ref: DatumRef) {
         debug!("▶️ SoundChannel::play_file() called with {:?}", member_ref);
 
@@ -2569,7 +2490,7 @@ impl SoundChannel {
     /// Extract only valid MP3 frames, skipping any garbage
     fn extract_valid_mp3_frames(data: &[u8]) -> Option<Vec<u8>> {
         // First, find where MP3 data starts
-        let mp3_start = Self::find_mp3_start(data)?;
+        let mp3_start = decode::find_mp3_start(data)?;
 
         console::log_1(
             &format!(
@@ -2623,6 +2544,7 @@ impl SoundChannel {
         }
 
         self.source_node = None;
+        self.voice = None;
     }
 
     pub fn pause(&mut self) {
@@ -2853,7 +2775,7 @@ impl SoundChannel {
             .map(|s| s as usize * channels as usize * bytes_per_sample_est);
         let data_likely_pcm = expected_pcm_size
             .map_or(false, |expected| data.len() >= expected * 4 / 5);
-        let is_mp3 = if data_likely_pcm { false } else { Self::find_mp3_start(data).is_some() };
+        let is_mp3 = if data_likely_pcm { false } else { decode::find_mp3_start(data).is_some() };
         let is_probably_adpcm = !is_mp3 && codec.contains("ima");
         // Only use byte-distribution heuristic when bits_per_sample is unknown (0).
         // When metadata explicitly says 16-bit, trust it — the heuristic can false-positive
@@ -2991,81 +2913,10 @@ impl SoundChannel {
     ///   - Vec<i16> of decoded 16-bit PCM audio samples.
     pub fn decode_ima_adpcm_to_pcm(
         adpcm_data: &[u8],
-        mut predictor: i32, // Use mutable copies of the initial state
-        mut index: i32,
+        predictor: i32,
+        index: i32,
     ) -> Result<Vec<i16>, String> {
-        let mut pcm_samples: Vec<i16> = Vec::with_capacity(adpcm_data.len() * 2);
-
-        for &byte in adpcm_data.iter() {
-            // Each byte contains two 4-bit ADPCM samples (nibbles).
-
-            // --- 1. Decode Lower Nibble (bits 0-3) ---
-            let lower_nibble = (byte & 0x0F) as i32;
-
-            let step = STEP_TABLE[index as usize];
-
-            // Calculate the difference (delta) based on the nibble
-            // This formula is equivalent to: diff = step / 8 + (step * (nibble & 7) * 2 + ...)
-            let mut diff: i32 = step >> 3; // Start with step/8
-            if (lower_nibble & 0x1) != 0 {
-                diff += step;
-            }
-            if (lower_nibble & 0x2) != 0 {
-                diff += step >> 1;
-            }
-            if (lower_nibble & 0x4) != 0 {
-                diff += step >> 2;
-            }
-
-            // Apply the sign (MSB of the nibble) and update the predictor
-            if (lower_nibble & 0x8) != 0 {
-                predictor -= diff;
-            } else {
-                predictor += diff;
-            }
-
-            // CRITICAL FIX: Clamp the predictor to prevent runaway values and noise/distortion.
-            predictor = predictor.clamp(-32768, 32767);
-            pcm_samples.push(predictor as i16);
-
-            // Update the index and clamp it between 0 and 88
-            index += INDEX_TABLE[lower_nibble as usize];
-            index = index.clamp(0, 88);
-
-            // --- 2. Decode Upper Nibble (bits 4-7) ---
-            let upper_nibble = (byte >> 4) as i32;
-
-            let step = STEP_TABLE[index as usize];
-
-            // Calculate the difference (delta)
-            let mut diff: i32 = step >> 3; // Start with step/8
-            if (upper_nibble & 0x1) != 0 {
-                diff += step;
-            }
-            if (upper_nibble & 0x2) != 0 {
-                diff += step >> 1;
-            }
-            if (upper_nibble & 0x4) != 0 {
-                diff += step >> 2;
-            }
-
-            // Apply the sign and update the predictor
-            if (upper_nibble & 0x8) != 0 {
-                predictor -= diff;
-            } else {
-                predictor += diff;
-            }
-
-            // CRITICAL FIX: Clamp the predictor
-            predictor = predictor.clamp(-32768, 32767);
-            pcm_samples.push(predictor as i16);
-
-            // Update the index and clamp it
-            index += INDEX_TABLE[upper_nibble as usize];
-            index = index.clamp(0, 88);
-        }
-
-        Ok(pcm_samples)
+        Ok(decode::decode_ima_adpcm(adpcm_data, predictor, index))
     }
 
     pub fn load_director_audio_data(
@@ -3095,7 +2946,7 @@ impl SoundChannel {
         let mp3_start = if data_likely_pcm {
             None
         } else {
-            Self::find_mp3_start(sound_bytes)
+            decode::find_mp3_start(sound_bytes)
         };
 
         if let Some(mp3_start) = mp3_start {
@@ -3468,6 +3319,11 @@ impl SoundChannel {
     }
 
     pub fn rewind(&mut self) {
+        if let Some(voice) = self.voice.as_mut() {
+            voice.rewind();
+            self.playback_start_context_time = self.context_time();
+            return;
+        }
         self.elapsed_time = self.start_time;
         self.loops_remaining = self.loop_count;
 
@@ -3477,16 +3333,12 @@ impl SoundChannel {
     }
 
     pub fn queue(&mut self, datum_ref: DatumRef, player: &DirPlayer) {
-        use web_sys::console;
-
         let datum = player.get_datum(&datum_ref);
 
         let props = match datum {
             Datum::PropList(p, _) if !p.is_empty() => p,
             _ => {
-                console::log_1(
-                    &"⚠️ queue(): called with non-propList or empty list — ignored".into(),
-                );
+                warn!("⚠️ queue(): called with non-propList or empty list — ignored");
                 return;
             }
         };
@@ -3506,22 +3358,13 @@ impl SoundChannel {
         };
 
         if loop_count <= 0 {
-            console::log_1(
-                &format!(
-                    "⚠️ queue(): invalid loopCount={} — skipping entry",
-                    loop_count
-                )
-                .into(),
-            );
+            warn!("⚠️ queue(): invalid loopCount={} — skipping entry", loop_count);
             return;
         }
 
-        console::log_1(
-            &format!(
-                "➕ queue() - Adding to channel {} | loop_count={} | current status: {:?}",
-                self.channel_num, loop_count, self.status
-            )
-            .into(),
+        debug!(
+            "➕ queue() - Adding to channel {} | loop_count={} | current status: {:?}",
+            self.channel_num, loop_count, self.status
         );
 
         let segment = SoundSegment {
@@ -3533,13 +3376,10 @@ impl SoundChannel {
         self.playlist_segments.push(segment);
         self.playlist.push(datum_ref.clone());
 
-        console::log_1(
-            &format!(
-                "✅ Channel {} playlist now has {} items",
-                self.channel_num,
-                self.playlist_segments.len()
-            )
-            .into(),
+        debug!(
+            "✅ Channel {} playlist now has {} items",
+            self.channel_num,
+            self.playlist_segments.len()
         );
 
         // DON'T auto-start or change current_segment_index here
@@ -3640,20 +3480,176 @@ impl SoundChannel {
         self.sample_count as f64 / self.sample_rate as f64
     }
 
-    pub fn update(&mut self, delta_time: f64, player: &mut DirPlayer) -> Result<(), ScriptError> {
-        // Handle fading
-        if self.is_fading {
-            self.fade_elapsed += delta_time;
-            if self.fade_elapsed >= self.fade_duration {
-                self.set_volume(self.fade_target_volume);
-                self.is_fading = false;
-            } else {
-                let t = self.fade_elapsed / self.fade_duration;
-                let new_volume =
-                    self.fade_start_volume + (self.fade_target_volume - self.fade_start_volume) * t;
-                self.set_volume(new_volume);
+    /// Playback position in milliseconds.
+    pub fn current_time_ms(&self) -> f64 {
+        if let Some(voice) = self.voice.as_ref() {
+            return voice.position_ms();
+        }
+        if self.status != SoundStatus::Playing {
+            return self.elapsed_time;
+        }
+        let elapsed = self.context_time() - self.playback_start_context_time;
+        let position = self.start_time + elapsed * 1000.0;
+        let duration_ms = self.get_duration() * 1000.0;
+        if duration_ms > 0.0 {
+            position.min(duration_ms)
+        } else {
+            position
+        }
+    }
+
+    fn passed_cue_points(&self) -> &[SoundCuePoint] {
+        if !self.is_busy() {
+            return &[];
+        }
+        let Some(member) = self.sound_member.as_ref() else {
+            return &[];
+        };
+        let position = self.current_time_ms();
+        let passed = member
+            .cue_points
+            .partition_point(|point| point.time_ms as f64 <= position);
+        &member.cue_points[..passed]
+    }
+
+    /// Whether cue point `index` (1-based) of the playing sound has been passed.
+    pub fn is_past_cue_point(&self, index: i32) -> bool {
+        index >= 1 && self.passed_cue_points().len() >= index as usize
+    }
+
+    /// Number of cue points named `name` the playing sound has passed.
+    pub fn cue_points_passed_named(&self, name: &str) -> i32 {
+        self.passed_cue_points()
+            .iter()
+            .filter(|point| point.name.eq_ignore_ascii_case(name))
+            .count() as i32
+    }
+
+    /// Advance cue point tracking to the current playback position and
+    /// return the cue points crossed since the last poll as
+    /// (1-based index, name). A new playback or a position that moved
+    /// backwards (loop, rewind) restarts tracking from the start time.
+    pub fn poll_cue_points(&mut self) -> Vec<(i32, String)> {
+        if self.status != SoundStatus::Playing {
+            if self.status == SoundStatus::Idle {
+                self.cue_position_ms = None;
+            }
+            return vec![];
+        }
+        let Some(member) = self.sound_member.as_ref() else {
+            return vec![];
+        };
+        let position = self.current_time_ms();
+        let restarted = self.cue_playback_start != self.playback_start_context_time;
+        let from = match self.cue_position_ms {
+            Some(last) if !restarted && position >= last => last,
+            _ => {
+                self.most_recent_cue_point = 0;
+                // Include a cue point sitting exactly on the start time.
+                self.start_time - 0.5
+            }
+        };
+        let crossed: Vec<(i32, String)> = cue_points::crossed(&member.cue_points, from, position)
+            .map(|(index, point)| (index, point.name.clone()))
+            .collect();
+        self.cue_position_ms = Some(position);
+        self.cue_playback_start = self.playback_start_context_time;
+        if let Some((index, _)) = crossed.last() {
+            self.most_recent_cue_point = *index;
+        }
+        crossed
+    }
+
+    /// Start playing `member_ref` (a sound member, member name or playlist
+    /// entry) through the headless mixer.
+    pub fn start_voice(&mut self, player: &DirPlayer, member_ref: &DatumRef) -> Result<(), ScriptError> {
+        let datum = player.get_datum(member_ref);
+        let sound_member = Self::resolve_sound_member(player, datum)
+            .ok_or_else(|| ScriptError::new("Cannot play: not a sound member".to_string()))?;
+        let time_prop = |key: &str| match Self::get_proplist_prop(player, datum, key) {
+            Some(Datum::Int(ms)) => ms as f64,
+            Some(Datum::Float(ms)) => ms,
+            _ => 0.0,
+        };
+        self.start_time = time_prop("startTime");
+        self.end_time = time_prop("endTime");
+        if let Datum::CastMember(_) = datum {
+            self.member = Some(member_ref.clone());
+        }
+        self.start_voice_for_member(&sound_member)
+    }
+
+    fn start_voice_for_member(&mut self, sound_member: &SoundMember) -> Result<(), ScriptError> {
+        let decoded = decode::decode_sound_member(sound_member)
+            .map_err(|e| ScriptError::new(format!("Failed to load sound: {}", e)))?;
+        self.sound_member = Some(sound_member.clone());
+        self.sample_rate = decoded.sample_rate;
+        self.sample_count = decoded.frame_count() as u32;
+        self.channel_count = decoded.channels;
+        self.voice = Some(Voice::new(Rc::new(decoded), self.start_time, self.end_time));
+        self.elapsed_time = 0.0;
+        self.status = SoundStatus::Playing;
+        self.playback_start_context_time = self.context_time();
+        Ok(())
+    }
+
+    /// Headless counterpart of `handle_end_of_sound`.
+    fn on_voice_finished(&mut self, player: &DirPlayer) {
+        let finished = self.voice.take();
+        self.status = SoundStatus::Idle;
+        let Some(next) = self.next_sound() else {
+            return;
+        };
+        match finished {
+            Some(mut voice) if next.repeat => {
+                voice.rewind();
+                self.voice = Some(voice);
+                self.status = SoundStatus::Playing;
+                self.playback_start_context_time = self.context_time();
+            }
+            _ => {
+                if let Err(err) = self.start_voice(player, &next.member_ref) {
+                    warn!("Channel {}: {}", self.channel_num, err.message);
+                }
+            }
+        }
+    }
+
+    /// Mix this channel into `out` (interleaved stereo at `out_rate`),
+    /// advancing fades, loops, the queue and the playlist as it goes.
+    pub fn mix_headless(&mut self, player: &DirPlayer, out: &mut [f32], out_rate: u32) {
+        let block_frames = out.len() / 2;
+        let mut offset = 0;
+        while offset < block_frames && self.status == SoundStatus::Playing && self.voice.is_some() {
+            let pan = (self.pan / 100.0) as f32;
+            let remaining = block_frames - offset;
+            // The gain ramps linearly across the rest of the block; if the
+            // voice ends early only the frames actually played count
+            // towards the fade.
+            let fade_state = (self.volume, self.is_fading, self.fade_elapsed);
+            let gain_before = (self.volume / 255.0) as f32;
+            self.advance_fade(remaining as f64 / out_rate as f64);
+            let gain_after = (self.volume / 255.0) as f32;
+            let voice = self.voice.as_mut().unwrap();
+            let (written, finished) =
+                voice.mix(&mut out[offset * 2..], out_rate, (gain_before, gain_after), pan);
+            if written < remaining {
+                (self.volume, self.is_fading, self.fade_elapsed) = fade_state;
+                self.advance_fade(written as f64 / out_rate as f64);
+            }
+            offset += written;
+            if finished && written == 0 {
+                // Nothing left to play, e.g. a startTime past the end of the
+                // sound or a zero sample rate. Looping it would never advance.
+                self.stop();
+            } else if finished {
+                self.on_voice_finished(player);
             }
         }
+    }
+
+    pub fn update(&mut self, delta_time: f64) -> Result<(), ScriptError> {
+        self.advance_fade(delta_time);
 
         // ⭐ Remove the playback advancement logic - it's handled by onended callback now
         // Audio plays asynchronously in Web Audio thread
@@ -3679,6 +3675,23 @@ impl SoundChannel {
         Ok(())
     }
 
+    /// Move an active fade `delta_time` seconds forward.
+    fn advance_fade(&mut self, delta_time: f64) {
+        if !self.is_fading {
+            return;
+        }
+        self.fade_elapsed += delta_time;
+        if self.fade_elapsed >= self.fade_duration {
+            let _ = self.set_volume(self.fade_target_volume);
+            self.is_fading = false;
+        } else {
+            let t = self.fade_elapsed / self.fade_duration;
+            let new_volume =
+                self.fade_start_volume + (self.fade_target_volume - self.fade_start_volume) * t;
+            let _ = self.set_volume(new_volume);
+        }
+    }
+
     /// Stops the currently playing WebAudio source node.
     pub fn stop_playback_nodes(&mut self) {
         // Set status to Idle FIRST, before stopping the source
@@ -3699,6 +3712,7 @@ impl SoundChannel {
         self.source_node = None;
         self.gain_node = None;
         self.pan_node = None;
+        self.voice = None;
         
         // Cancel any in-progress async decode by clearing the flag
         *self.is_decoding.borrow_mut() = false;
@@ -3716,6 +3730,10 @@ impl SoundChannel {
 
         self.expected_sample_rate = Some(sound_member.info.sample_rate);
 
+        if self.is_headless() {
+            return self.start_voice_for_member(sound_member);
+        }
+
         let _ = self.audio_context().resume();
 
         let sound_data = &sound_member.sound.data();
@@ -4046,11 +4064,11 @@ impl SoundChannel {
     }
 
     pub fn set_pan(&mut self, pan: f64) -> Result<(), JsValue> {
-        let clamped = pan.clamp(-1.0, 1.0);
+        self.pan = pan.clamp(-100.0, 100.0);
 
         if let Some(ref pan_node) = self.pan_node {
-            let _ = pan_node.pan().set_value(clamped as f32);
-            console::log_1(&JsValue::from_str(&format!("🎚️ Pan set to {:.2}", clamped)));
+            pan_node.pan().set_value((self.pan / 100.0) as f32);
+            debug!("🎚️ Pan set to {:.2}", self.pan);
         }
 
         Ok(())
@@ -4089,139 +4107,104 @@ impl SoundChannel {
 
     /// Called by onended callback to handle loops and playlist
     pub fn start_next_segment(&mut self) {
-        use web_sys::console;
+        let Some(next) = self.next_sound() else {
+            return;
+        };
 
-        console::log_1(
-            &format!(
-                "🔄 start_next_segment called for channel {}",
-                self.channel_num
-            )
-            .into(),
-        );
+        // Try gapless replay from cached buffer first
+        if next.gapless && self.replay_cached_buffer() {
+            return;
+        }
+
+        // Fall back to full decode path
+        let channel_num = self.channel_num;
+        let member_ref = next.member_ref;
+        spawn_local(async move {
+            if let Some(player) = unsafe { crate::PLAYER_OPT.as_mut() }
+                && let Some(channel_rc) = player.sound_manager.get_channel(channel_num as usize)
+            {
+                SoundChannel::play_file(channel_rc, member_ref);
+            }
+        });
+    }
+
+    /// Advance loop counters, the queue and the playlist past the sound that
+    /// just ended and pick the sound to play next. Shared by the Web Audio
+    /// `onended` path and the headless mixer.
+    fn next_sound(&mut self) -> Option<NextSound> {
+        debug!("🔄 next_sound called for channel {}", self.channel_num);
 
         // Check for queued members first
-        let queued_ref = self.queued_members.first().cloned();
-        if let Some(queued_ref) = queued_ref {
-            self.queued_members.remove(0); // Remove from queue
-            console::log_1(
-                &format!(
-                    "▶️ Playing queued member from start_next_segment ({} remaining in queue)",
-                    self.queued_members.len()
-                )
-                .into(),
+        if !self.queued_members.is_empty() {
+            let member_ref = self.queued_members.remove(0);
+            debug!(
+                "▶️ Playing queued member ({} remaining in queue)",
+                self.queued_members.len()
             );
-            let channel_num = self.channel_num;
-            
-            spawn_local(async move {
-                if let Some(player) = unsafe { crate::PLAYER_OPT.as_mut() } {
-                    if let Some(channel_rc) = player.sound_manager.get_channel(channel_num as usize) {
-                        SoundChannel::play_file(channel_rc, queued_ref);
-                    }
-                }
+            return Some(NextSound {
+                member_ref,
+                repeat: false,
+                gapless: false,
             });
-            return;
         }
 
         // Handle direct playback looping (non-playlist sounds)
         if self.current_segment_index.is_none() {
-            console::log_1(
-                &format!(
-                    "🔁 Direct playback: loop_count={}, loops_remaining={}",
-                    self.loop_count, self.loops_remaining
-                )
-                .into(),
+            debug!(
+                "🔁 Direct playback: loop_count={}, loops_remaining={}",
+                self.loop_count, self.loops_remaining
             );
 
-            // Check if we should loop
             if self.loop_count == 0 {
                 // Loop forever
-                debug!("♾️ Looping forever (loop_count=0)");
-                
-                if let Some(ref member_ref) = self.member {
-                    let member_ref_clone = member_ref.clone();
-                    let channel_num = self.channel_num;
-                    
-                    spawn_local(async move {
-                        if let Some(player) = unsafe { crate::PLAYER_OPT.as_mut() } {
-                            if let Some(channel_rc) = player.sound_manager.get_channel(channel_num as usize) {
-                                SoundChannel::play_file(channel_rc, member_ref_clone);
-                            }
-                        }
+                if let Some(member_ref) = self.member.clone() {
+                    return Some(NextSound {
+                        member_ref,
+                        repeat: true,
+                        gapless: false,
                     });
-                    return;
                 }
             } else if self.loops_remaining > 1 {
-                // Still have loops remaining
                 self.loops_remaining -= 1;
-                console::log_1(
-                    &format!(
-                        "🔁 Looping: {} loops remaining",
-                        self.loops_remaining
-                    )
-                    .into(),
-                );
-                
-                if let Some(ref member_ref) = self.member {
-                    let member_ref_clone = member_ref.clone();
-                    let channel_num = self.channel_num;
-                    
-                    spawn_local(async move {
-                        if let Some(player) = unsafe { crate::PLAYER_OPT.as_mut() } {
-                            if let Some(channel_rc) = player.sound_manager.get_channel(channel_num as usize) {
-                                SoundChannel::play_file(channel_rc, member_ref_clone);
-                            }
-                        }
+                debug!("🔁 Looping: {} loops remaining", self.loops_remaining);
+                if let Some(member_ref) = self.member.clone() {
+                    return Some(NextSound {
+                        member_ref,
+                        repeat: true,
+                        gapless: false,
                     });
-                    return;
                 }
             }
-            
+
             // No more loops - check if there's a playlist to start
             if !self.playlist_segments.is_empty() {
                 debug!("🎵 Found {} queued sounds", self.playlist_segments.len());
                 self.current_segment_index = Some(0);
-
-                // Try gapless replay from cached buffer first
-                if !self.replay_cached_buffer() {
-                    let member_ref = self.playlist_segments[0].member_ref.clone();
-                    let channel_num = self.channel_num;
-                    spawn_local(async move {
-                        if let Some(player) = unsafe { crate::PLAYER_OPT.as_mut() } {
-                            if let Some(channel_rc) =
-                                player.sound_manager.get_channel(channel_num as usize)
-                            {
-                                SoundChannel::play_file(channel_rc, member_ref);
-                            }
-                        }
-                    });
-                }
-                return;
+                return Some(NextSound {
+                    member_ref: self.playlist_segments[0].member_ref.clone(),
+                    repeat: false,
+                    gapless: true,
+                });
             }
-            
-            // No loops left and no playlist - stop
+
             debug!("⏸️ Playback complete, no loops left");
             self.status = SoundStatus::Idle;
-            return;
+            return None;
         }
 
-        // Handle playlist-based looping (existing logic)
         let index = self.current_segment_index.unwrap();
 
         if index >= self.playlist_segments.len() {
             warn!("⚠️ Current index out of bounds");
             self.current_segment_index = None;
             self.status = SoundStatus::Idle;
-            return;
+            return None;
         }
 
         let segment = &mut self.playlist_segments[index];
-
-        console::log_1(
-            &format!(
-                "🔄 start_next_segment: index={}, loops_remaining={}/{}",
-                index, segment.loops_remaining, segment.loop_count
-            )
-            .into(),
+        debug!(
+            "🔄 next_sound: index={}, loops_remaining={}/{}",
+            index, segment.loops_remaining, segment.loop_count
         );
 
         // Loop logic - if loop_count is 0, loop forever
@@ -4229,26 +4212,12 @@ impl SoundChannel {
             if segment.loop_count != 0 {
                 segment.loops_remaining -= 1;
             }
-            // Clone member_ref before releasing the borrow on segment
-            let member_ref = segment.member_ref.clone();
             debug!("🔁 Looping segment {}", index);
-
-            // Try gapless replay from cached buffer first
-            if self.replay_cached_buffer() {
-                return;
-            }
-
-            // Fall back to full decode path
-            let channel_num = self.channel_num;
-            spawn_local(async move {
-                if let Some(player) = unsafe { crate::PLAYER_OPT.as_mut() } {
-                    if let Some(channel_rc) = player.sound_manager.get_channel(channel_num as usize)
-                    {
-                        SoundChannel::play_file(channel_rc, member_ref);
-                    }
-                }
+            return Some(NextSound {
+                member_ref: segment.member_ref.clone(),
+                repeat: true,
+                gapless: true,
             });
-            return;
         }
 
         // Segment finished - REMOVE IT
@@ -4256,33 +4225,20 @@ impl SoundChannel {
         self.playlist_segments.remove(index);
         self.playlist.remove(index);
 
-        debug!("📊 {} items remaining", self.playlist_segments.len());
-
-        // Play next segment if available
         if index < self.playlist_segments.len() {
             self.current_segment_index = Some(index);
             self.playlist_segments[index].loops_remaining = self.playlist_segments[index].loop_count;
-            let member_ref = self.playlist_segments[index].member_ref.clone();
-
             debug!("⏭️ Playing next segment at index {}", index);
-
-            // Try gapless replay from cached buffer first
-            if !self.replay_cached_buffer() {
-                // Fall back to full decode path
-                let channel_num = self.channel_num;
-                spawn_local(async move {
-                    if let Some(player) = unsafe { crate::PLAYER_OPT.as_mut() } {
-                        if let Some(channel_rc) = player.sound_manager.get_channel(channel_num as usize)
-                        {
-                            SoundChannel::play_file(channel_rc, member_ref);
-                        }
-                    }
-                });
-            }
+            Some(NextSound {
+                member_ref: self.playlist_segments[index].member_ref.clone(),
+                repeat: false,
+                gapless: true,
+            })
         } else {
             debug!("⏸️ Playlist empty, stopped");
             self.current_segment_index = None;
             self.status = SoundStatus::Idle;
+            None
         }
     }
 
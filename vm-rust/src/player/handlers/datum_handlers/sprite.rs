@@ -73,35 +73,32 @@ impl SpriteDatumUtils {
     ) -> Result<Option<(String, usize)>, ScriptError> {
         let sprite_num = player.get_datum(datum).to_sprite_ref()?;
         let (vals, _flags) = player.get_datum(point_arg).to_point_inline()?;
-        let stage_x = vals[0] as i32;
-        let stage_y = vals[1] as i32;
-
-        let sprite = match player.movie.score.get_sprite(sprite_num) {
-            Some(s) => s,
-            None => return Ok(None),
-        };
+        Ok(Self::text_char_index_at(player, sprite_num, vals[0] as i32, vals[1] as i32))
+    }
 
-        let member_ref = match &sprite.member {
-            Some(r) => r.clone(),
-            None => return Ok(None),
-        };
+    /// Same as `get_text_char_index_at_point`, for a sprite number and stage coordinates.
+    pub fn text_char_index_at(
+        player: &DirPlayer,
+        sprite_num: i16,
+        stage_x: i32,
+        stage_y: i32,
+    ) -> Option<(String, usize)> {
+        let sprite = player.movie.score.get_sprite(sprite_num)?;
+        let member_ref = sprite.member.clone()?;
 
         let sprite_rect = get_concrete_sprite_rect(player, sprite);
         let local_x = stage_x - sprite_rect.left;
         let local_y = stage_y - sprite_rect.top;
 
-        let member = match player.movie.cast_manager.find_member_by_ref(&member_ref) {
-            Some(m) => m,
-            None => return Ok(None),
-        };
+        let member = player.movie.cast_manager.find_member_by_ref(&member_ref)?;
 
         let (text, fixed_line_space, top_spacing) = match &member.member_type {
             CastMemberType::Text(t) => (t.text.clone(), t.fixed_line_space, t.top_spacing),
             CastMemberType::Field(f) => (f.text.clone(), f.fixed_line_space, f.top_spacing),
-            _ => return Ok(None),
+            _ => return None,
         };
 
-        let font = player.font_manager.get_system_font().unwrap();
+        let font = player.font_manager.get_system_font()?;
         let params = DrawTextParams {
             font: &font,
             line_height: None,
@@ -112,7 +109,7 @@ impl SpriteDatumUtils {
         };
 
         let char_index = get_text_index_at_pos(&text, &params, local_x, local_y);
-        Ok(Some((text, char_index)))
+        Some((text, char_index))
     }
 }
 
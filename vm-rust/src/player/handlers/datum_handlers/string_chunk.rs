@@ -1,7 +1,7 @@
 use itertools::Itertools;
 
 use crate::{
-    director::lingo::datum::{Datum, StringChunkExpr, StringChunkSource, StringChunkType},
+    director::lingo::datum::{Datum, DatumType, StringChunkExpr, StringChunkSource, StringChunkType},
     player::{
         cast_lib::CastMemberRef,
         cast_member::CastMemberType,
@@ -9,6 +9,7 @@ use crate::{
             cast_member::font::{HtmlStyle, StyledSpan},
             string::string_get_words,
         },
+        hyperlinks::{hyperlink_at, set_hyperlink, HyperlinkState},
         reserve_player_mut,
         sprite::ColorRef,
         DatumRef, DirPlayer, ScriptError,
@@ -839,6 +840,9 @@ impl StringChunkHandlers {
                     }
                 }
             }
+            _ if matches!(prop.to_ascii_lowercase().as_str(), "hyperlink" | "hyperlinkstate") => {
+                return Self::set_chunk_hyperlink_prop(player, datum_ref, prop, value_ref);
+            }
             _ => {
                 return Err(ScriptError::new(format!(
                     "Cannot set property {prop} for string chunk datum"
@@ -848,6 +852,70 @@ impl StringChunkHandlers {
         Ok(())
     }
 
+    /// `hyperlink`, `hyperlinkRange` and `hyperlinkState` of a text member
+    /// chunk, read from the link covering the chunk's first character.
+    pub fn get_chunk_hyperlink_prop(
+        player: &mut DirPlayer,
+        datum_ref: &DatumRef,
+        prop: &str,
+    ) -> Result<DatumRef, ScriptError> {
+        let link = Self::walk_chunk_to_member_range(player, datum_ref).and_then(|(member_ref, start, _)| {
+            let member = player.movie.cast_manager.find_member_by_ref(&member_ref)?;
+            let text = member.member_type.as_text()?;
+            let index = hyperlink_at(&text.hyperlinks, start)?;
+            Some(text.hyperlinks[index].clone())
+        });
+        let result = match prop.to_ascii_lowercase().as_str() {
+            "hyperlink" => Datum::String(link.map(|link| link.data).unwrap_or_default()),
+            "hyperlinkrange" => match link {
+                Some(link) => link.range_datum(player),
+                None => {
+                    let zero = [player.alloc_datum(Datum::Int(0)), player.alloc_datum(Datum::Int(0))];
+                    Datum::List(DatumType::List, zero.into_iter().collect(), false)
+                }
+            },
+            _ => Datum::Symbol(link.map_or(HyperlinkState::Normal, |link| link.state).symbol().to_string()),
+        };
+        Ok(player.alloc_datum(result))
+    }
+
+    /// Setting `hyperlink` links the chunk's characters to the given data (an
+    /// empty string removes links there); `hyperlinkState` changes the state
+    /// of the link covering the chunk's first character.
+    fn set_chunk_hyperlink_prop(
+        player: &mut DirPlayer,
+        datum_ref: &DatumRef,
+        prop: &str,
+        value_ref: &DatumRef,
+    ) -> Result<(), ScriptError> {
+        let Some((member_ref, start, end)) = Self::walk_chunk_to_member_range(player, datum_ref) else {
+            return Ok(());
+        };
+        let value = player.get_datum(value_ref).string_value()?;
+        let state = if prop.eq_ignore_ascii_case("hyperlinkState") {
+            Some(HyperlinkState::from_symbol(&value).ok_or_else(|| {
+                ScriptError::new(format!("Invalid hyperlinkState #{value}"))
+            })?)
+        } else {
+            None
+        };
+        let Some(member) = player.movie.cast_manager.find_mut_member_by_ref(&member_ref) else {
+            return Ok(());
+        };
+        let CastMemberType::Text(text) = &mut member.member_type else {
+            return Ok(());
+        };
+        match state {
+            Some(state) => {
+                if let Some(index) = hyperlink_at(&text.hyperlinks, start) {
+                    text.hyperlinks[index].state = state;
+                }
+            }
+            None => set_hyperlink(&mut text.hyperlinks, start, end, &value),
+        }
+        Ok(())
+    }
+
     /// Apply a font / fontStyle / color change to a nested string-chunk datum
     /// by splitting the source member's `html_styled_spans` at the chunk
     /// boundaries. Used by Coke Studios for per-line colour + bold/underline
@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+
+use crate::{
+    director::lingo::datum::{datum_bool, Datum},
+    player::{
+        geometry::IntRect,
+        handlers::datum_handlers::player_call_datum_handler,
+        reserve_player_mut, reserve_player_ref,
+        window::{self, TitlebarOptions},
+        DatumRef, DirPlayer, ScriptError, ScriptErrorCode,
+    },
+};
+
+pub struct WindowDatumHandlers {}
+
+impl WindowDatumHandlers {
+    /// `window "name"` / `window("name")`. Only refers to the window; it is
+    /// created once it is opened.
+    pub fn window(args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let name = player.get_datum(&args[0]).string_value()?;
+            let name = player.windows.find(&name).map_or(name, |w| w.name.clone());
+            Ok(player.alloc_datum(Datum::Window(name)))
+        })
+    }
+
+    /// `windowPresent("name")`
+    pub fn window_present(args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let name = player.get_datum(&args[0]).string_value()?;
+            let present = player.windows.find(&name).is_some();
+            Ok(player.alloc_datum(datum_bool(present)))
+        })
+    }
+
+    /// Global `open`, `close`, `forget`, `moveToFront` and `moveToBack`.
+    /// Window arguments act on the window; other objects get the call
+    /// forwarded as a method (e.g. `forget(timeout("t"))`).
+    pub async fn call_global(handler_name: &str, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        let Some(target) = args.first() else {
+            return Ok(DatumRef::Void);
+        };
+        match reserve_player_ref(|player| player.get_datum(target).clone()) {
+            Datum::Window(_) => Self::call_async(target, handler_name, &args[1..].to_vec()).await,
+            Datum::Void | Datum::Stage => Ok(DatumRef::Void),
+            _ => player_call_datum_handler(target, handler_name, &args[1..].to_vec()).await,
+        }
+    }
+
+    pub fn has_async_handler(name: &str) -> bool {
+        matches!(name, "open" | "close" | "forget")
+    }
+
+    pub fn call(datum: &DatumRef, handler_name: &str, _args: &Vec<DatumRef>) -> Result<DatumRef, ScriptError> {
+        let Some(id) = window::find_window_id(&Self::window_name(datum)?) else {
+            return Ok(DatumRef::Void);
+        };
+        match handler_name {
+            "moveToFront" => {
+                window::move_to_front(id);
+                Ok(DatumRef::Void)
+            }
+            "moveToBack" => {
+                window::move_to_back(id);
+                Ok(DatumRef::Void)
+            }
+            _ => Err(ScriptError::new_code(
+                ScriptErrorCode::HandlerNotFound,
+                format!("No handler {handler_name} for window"),
+            )),
+        }
+    }
+
+    pub async fn call_async(
+        datum: &DatumRef,
+        handler_name: &str,
+        args: &Vec<DatumRef>,
+    ) -> Result<DatumRef, ScriptError> {
+        let name = Self::window_name(datum)?;
+        if handler_name == "open" {
+            window::open_window(window::create_window(&name)).await?;
+            return Ok(DatumRef::Void);
+        }
+        let Some(id) = window::find_window_id(&name) else {
+            return Ok(DatumRef::Void);
+        };
+        match handler_name {
+            "close" => window::close_window(id).await,
+            "forget" => window::forget_window(id).await,
+            _ => return Self::call(datum, handler_name, args),
+        }
+        Ok(DatumRef::Void)
+    }
+
+    fn window_name(datum: &DatumRef) -> Result<String, ScriptError> {
+        reserve_player_ref(|player| match player.get_datum(datum) {
+            Datum::Window(name) => Ok(name.clone()),
+            _ => Err(ScriptError::new("Expected a window".to_string())),
+        })
+    }
+
+    pub fn get_prop(player: &mut DirPlayer, name: &str, prop: &str) -> Result<Datum, ScriptError> {
+        let Some(window) = player.windows.find(name) else {
+            return Err(ScriptError::new(format!("Window \"{}\" does not exist", name)));
+        };
+        let id = window.id;
+        match prop {
+            "name" => Ok(Datum::String(window.name.clone())),
+            "title" => Ok(Datum::String(window.title.clone())),
+            "fileName" => Ok(Datum::String(window.file_name.clone())),
+            "rect" => Ok(window.frame_rect().to_datum()),
+            "drawRect" => Ok(window.stage_draw_rect().to_datum()),
+            "sourceRect" => Ok(window::window_movie(player, id)
+                .map(|movie| movie.rect.to_datum())
+                .unwrap_or(Datum::Void)),
+            "visible" => Ok(datum_bool(window.visible)),
+            "modal" => Ok(datum_bool(window.modal)),
+            "windowType" => Ok(Datum::Int(window.window_type)),
+            "type" => Ok(Datum::Symbol(window.kind.clone())),
+            "titlebarOptions" => {
+                let options = window.titlebar_options.clone();
+                Ok(Self::titlebar_options_to_datum(player, &options))
+            }
+            _ => Err(ScriptError::new(format!("Cannot get window property {}", prop))),
+        }
+    }
+
+    pub async fn set_prop(name: &str, prop: &str, value: Datum) -> Result<(), ScriptError> {
+        let Some(id) = window::find_window_id(name) else {
+            return Err(ScriptError::new(format!("Window \"{}\" does not exist", name)));
+        };
+        match prop {
+            "rect" => {
+                let (rect, _) = value.to_rect_inline()?;
+                window::set_window_rect(id, Self::to_int_rect(rect)).await;
+                Ok(())
+            }
+            "fileName" => window::set_window_file_name(id, value.string_value()?).await,
+            _ => reserve_player_mut(|player| {
+                player.stage_dirty = true;
+                let titlebar_options = match prop {
+                    "titlebarOptions" => Some(Self::titlebar_options_from_datum(player, id, &value)?),
+                    _ => None,
+                };
+                if prop == "name" {
+                    let new_name = value.string_value()?;
+                    if player.windows.find(&new_name).is_some_and(|w| w.id != id) {
+                        return Err(ScriptError::new(format!("A window named \"{}\" already exists", new_name)));
+                    }
+                }
+                let window = player.windows.get_mut(id).unwrap();
+                match prop {
+                    "name" => window.name = value.string_value()?,
+                    "title" => window.title = value.string_value()?,
+                    "drawRect" => {
+                        let (rect, _) = value.to_rect_inline()?;
+                        let frame = window.frame_rect();
+                        let rect = Self::to_int_rect(rect);
+                        window.draw_rect = Some(IntRect::from(
+                            rect.left - frame.left,
+                            rect.top - frame.top,
+                            rect.right - frame.left,
+                            rect.bottom - frame.top,
+                        ));
+                    }
+                    "visible" => window.visible = value.to_bool()?,
+                    "modal" => window.modal = value.to_bool()?,
+                    "windowType" => window.window_type = value.int_value()?,
+                    "type" => window.kind = value.string_value()?,
+                    "titlebarOptions" => window.titlebar_options = titlebar_options.unwrap(),
+                    _ => return Err(ScriptError::new(format!("Cannot set window property {}", prop))),
+                }
+                Ok(())
+            }),
+        }
+    }
+
+    fn to_int_rect(rect: [f64; 4]) -> IntRect {
+        IntRect::from(rect[0] as i32, rect[1] as i32, rect[2] as i32, rect[3] as i32)
+    }
+
+    fn titlebar_options_to_datum(player: &mut DirPlayer, options: &TitlebarOptions) -> Datum {
+        let entries = [
+            ("visible", datum_bool(options.visible)),
+            ("closebox", datum_bool(options.closebox)),
+            ("minimizebox", datum_bool(options.minimizebox)),
+            ("maximizebox", datum_bool(options.maximizebox)),
+            ("sideTitle", datum_bool(options.side_title)),
+            ("icon", options.icon.clone()),
+        ];
+        let pairs: VecDeque<_> = entries
+            .into_iter()
+            .map(|(key, value)| {
+                (player.alloc_datum(Datum::Symbol(key.to_string())), player.alloc_datum(value))
+            })
+            .collect();
+        Datum::PropList(pairs, false)
+    }
+
+    /// Merge a property list into the window's titlebar options. Missing
+    /// keys keep their current value.
+    fn titlebar_options_from_datum(
+        player: &DirPlayer,
+        id: u32,
+        value: &Datum,
+    ) -> Result<TitlebarOptions, ScriptError> {
+        let Datum::PropList(pairs, _) = value else {
+            return Err(ScriptError::new("titlebarOptions must be a property list".to_string()));
+        };
+        let mut options = player.windows.get(id).unwrap().titlebar_options.clone();
+        for (key, value) in pairs {
+            let key = player.get_datum(key).string_value()?;
+            let value = player.get_datum(value);
+            match key.to_lowercase().as_str() {
+                "visible" => options.visible = value.to_bool()?,
+                "closebox" => options.closebox = value.to_bool()?,
+                "minimizebox" => options.minimizebox = value.to_bool()?,
+                "maximizebox" => options.maximizebox = value.to_bool()?,
+                "sidetitle" => options.side_title = value.to_bool()?,
+                "icon" => options.icon = value.clone(),
+                _ => {}
+            }
+        }
+        Ok(options)
+    }
+}
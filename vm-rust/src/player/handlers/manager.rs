@@ -11,6 +11,7 @@ use super::{
         prop_list::PropListDatumHandlers,
         script_instance::{ScriptInstanceDatumHandlers, ScriptInstanceUtils},
         sound_channel::SoundChannelDatumHandlers,
+        window::WindowDatumHandlers,
     },
     movie::MovieHandlers,
     net::NetHandlers,
@@ -21,10 +22,10 @@ use std::collections::{HashMap, VecDeque};
 use rand::Rng;
 
 use crate::{
-    director::lingo::datum::{Datum, DatumType, datum_bool},
+    director::{enums::ScriptType, lingo::datum::{Datum, DatumType, datum_bool}},
     js_api::JsApi,
     player::{
-        DatumRef, DirPlayer, ScriptError, ScriptErrorCode, bitmap::bitmap::{Bitmap, PaletteRef, get_system_default_palette}, datum_formatting::{format_concrete_datum, format_datum}, geometry::IntRect, handlers::datum_handlers::xml::XmlHelper, keyboard_map, player_alloc_datum, player_call_script_handler, reserve_player_mut, reserve_player_ref, score::get_concrete_sprite_rect, script_ref::ScriptInstanceRef, trace_output, xtra::manager::call_xtra_instance_handler
+        DatumRef, DirPlayer, ScriptError, ScriptErrorCode, cast_lib::cast_member_ref, debug::watch, cast_member::{CastMember, CastMemberType, ScriptMember}, bitmap::bitmap::{Bitmap, PaletteRef, get_system_default_palette}, datum_formatting::{format_concrete_datum, format_datum}, geometry::IntRect, handlers::datum_handlers::xml::XmlHelper, keyboard_map, player_alloc_datum, player_call_script_handler, reserve_player_mut, reserve_player_ref, score::get_concrete_sprite_rect, script_ref::ScriptInstanceRef, trace_output, xtra::manager::{call_xtra_instance_handler, call_xtra_static_handler, find_global_handler_xtra, has_xtra_static_handler}
     },
 };
 
@@ -81,6 +82,12 @@ extern "C" {
     fn ruffle_set_flash_property(cast_lib: i32, cast_member: i32, target: &str, prop_num: i32, value: &str);
 }
 
+/// Global handlers that change the list passed as their first argument.
+const LIST_MUTATORS: &[&str] = &[
+    "addprop", "deleteprop", "append", "deleteat", "deleteone", "deleteall", "setprop",
+    "setaprop", "addat", "setat", "sort", "add",
+];
+
 pub struct BuiltInHandlerManager {}
 
 impl BuiltInHandlerManager {
@@ -104,6 +111,19 @@ impl BuiltInHandlerManager {
         })
     }
 
+    /// `moveToFront window "x"` / `moveToBack window "x"`. Other targets are
+    /// ignored.
+    fn window_layer_command(name: &str, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        let Some(target) = args.first() else {
+            return Ok(DatumRef::Void);
+        };
+        if !reserve_player_ref(|player| matches!(player.get_datum(target), Datum::Window(_))) {
+            return Ok(DatumRef::Void);
+        }
+        let handler_name = if name.eq_ignore_ascii_case("moveToBack") { "moveToBack" } else { "moveToFront" };
+        WindowDatumHandlers::call(target, handler_name, &vec![])
+    }
+
     fn param(args: &Vec<DatumRef>) -> Result<DatumRef, ScriptError> {
         reserve_player_ref(|player| {
             let param_number = player.get_datum(&args[0]).int_value()?;
@@ -771,11 +791,51 @@ impl BuiltInHandlerManager {
         if code.is_empty() || code == "nothing" {
             return Ok(DatumRef::Void);
         }
+        if code.contains('\r') || code.contains('\n') {
+            return Self::do_script_text(&code).await;
+        }
 
         use crate::player::eval::eval_lingo_command;
         eval_lingo_command(code).await
     }
 
+    /// Multi-line `do` strings are compiled into a temporary score script
+    /// holding a single handler, which is called and then thrown away.
+    async fn do_script_text(code: &str) -> Result<DatumRef, ScriptError> {
+        let source = format!("on __do__\r{}\rend", code);
+        let member_ref = reserve_player_mut(|player| {
+            let dir_version = player.movie.dir_version;
+            let cast = player.movie.cast_manager.get_cast_mut(1);
+            let number = cast.first_free_member_id();
+            let member_type = CastMemberType::Script(ScriptMember {
+                script_id: 0,
+                script_type: ScriptType::Score,
+                name: String::new(),
+                script_text: String::new(),
+            });
+            cast.members.insert(number, CastMember::new(number, member_type));
+            if let Err(err) = cast.set_script_text(number, &source, dir_version) {
+                cast.members.remove(&number);
+                return Err(err);
+            }
+            Ok(cast_member_ref(cast.number as i32, number as i32))
+        })?;
+
+        let result = player_call_script_handler(None, (member_ref.clone(), "__do__".to_string()), &vec![]).await;
+
+        reserve_player_mut(|player| {
+            let cast = player.movie.cast_manager.get_cast_mut(member_ref.cast_lib as u32);
+            let number = member_ref.cast_member as u32;
+            if let Some(CastMemberType::Script(script_member)) = cast.members.remove(&number).map(|m| m.member_type)
+                && let Some(lctx) = cast.lctx.as_mut()
+            {
+                lctx.scripts.remove(&script_member.script_id);
+            }
+            cast.scripts.remove(&number);
+        });
+        Ok(result?.return_value)
+    }
+
     pub fn has_async_handler(name: &str) -> bool {
         match name {
             "call" => true,
@@ -793,6 +853,7 @@ impl BuiltInHandlerManager {
             // called as a global verb; route it to the same async impl as
             // the method form `member.importFileInto(url, props)`.
             "importFileInto" => true,
+            "open" | "close" | "forget" => true,
             _ => false,
         }
     }
@@ -814,6 +875,7 @@ impl BuiltInHandlerManager {
             "go" => MovieHandlers::go(args).await,
             "nothing" => MovieHandlers::nothing_async(args).await,
             "importFileInto" => Self::import_file_into(args).await,
+            "open" | "close" | "forget" => WindowDatumHandlers::call_global(name, args).await,
             _ => {
                 let msg = format!("No built-in async handler: {}", name);
                 return Err(ScriptError::new(msg));
@@ -822,13 +884,30 @@ impl BuiltInHandlerManager {
     }
 
     pub fn call_handler(name: &str, args: &Vec<DatumRef>) -> Result<DatumRef, ScriptError> {
-        match name.to_lowercase().as_str() {
+        let lower_name = name.to_lowercase();
+        match args.first() {
+            Some(list) if LIST_MUTATORS.contains(&lower_name.as_str()) => {
+                watch::observe_list(list, || Self::dispatch_handler(name, &lower_name, args))
+            }
+            _ => Self::dispatch_handler(name, &lower_name, args),
+        }
+    }
+
+    fn dispatch_handler(name: &str, lower_name: &str, args: &Vec<DatumRef>) -> Result<DatumRef, ScriptError> {
+        match lower_name {
             "castlib" => CastHandlers::cast_lib(args),
             "findempty" => CastHandlers::find_empty(args),
+            "save" => CastHandlers::save(args),
+            "savemovie" => MovieHandlers::save_movie(args),
             "preloadnetthing" => NetHandlers::preload_net_thing(args),
             "netdone" => NetHandlers::net_done(args),
-            "movetofront" | "preloadmember" | "preloadbuffer" | "unloadmember" | "beep" => Ok(DatumRef::Void),
+            "preloadmember" | "preloadbuffer" | "unloadmember" | "beep" => Ok(DatumRef::Void),
+            "window" => WindowDatumHandlers::window(args),
+            "windowpresent" => WindowDatumHandlers::window_present(args),
+            "movetofront" | "movetoback" => Self::window_layer_command(name, args),
             "puppettempo" => MovieHandlers::puppet_tempo(args),
+            "beginrecording" | "endrecording" | "updateframe" | "insertframe" | "duplicateframe"
+            | "deleteframe" | "clearframe" => MovieHandlers::score_recording(name, args),
             "objectp" => TypeHandlers::objectp(args),
             "voidp" => TypeHandlers::voidp(args),
             "listp" => TypeHandlers::listp(args),
@@ -938,9 +1017,13 @@ impl BuiltInHandlerManager {
                 })
             }
             "rightmousedown" => {
-                // We don't track right mouse state separately yet — return FALSE
                 reserve_player_mut(|player| {
-                    Ok(player.alloc_datum(datum_bool(false)))
+                    Ok(player.alloc_datum(datum_bool(player.right_mouse_down)))
+                })
+            }
+            "rightmouseup" => {
+                reserve_player_mut(|player| {
+                    Ok(player.alloc_datum(datum_bool(!player.right_mouse_down)))
                 })
             }
             "getrendererservices" => {
@@ -1297,6 +1380,24 @@ impl BuiltInHandlerManager {
                     SoundChannelDatumHandlers::call(player, &channel_datum, &"play".to_string(), args)
                 })
             }
+            "ispastcuepoint" => {
+                // isPastCuePoint(sound 1, cuePointID)
+                if args.len() < 2 {
+                    return Err(ScriptError::new(
+                        "isPastCuePoint requires a sound channel and a cue point".to_string(),
+                    ));
+                }
+                reserve_player_mut(|player| {
+                    let channel_datum = match player.get_datum(&args[0]) {
+                        Datum::SoundChannel(_) => args[0].clone(),
+                        other => {
+                            let channel_num = other.int_value()? as u16;
+                            player.alloc_datum(Datum::SoundChannel(channel_num))
+                        }
+                    };
+                    SoundChannelDatumHandlers::call(player, &channel_datum, "isPastCuePoint", &args[1..].to_vec())
+                })
+            }
             "spritebox" => {
                 // spriteBox(sprite, left, top, right, bottom)
                 if args.len() < 5 {
@@ -1329,10 +1430,8 @@ impl BuiltInHandlerManager {
                     Ok(DatumRef::Void)
                 })
             }
-            "puppettransition" => {
-                log::warn!("puppetTransition is not implemented");
-                Ok(DatumRef::Void)
-            }
+            "puppettransition" => MovieHandlers::puppet_transition(args),
+            "puppetpalette" => MovieHandlers::puppet_palette(args),
             "preload" => {
                 log::warn!("preload is not implemented");
                 Ok(DatumRef::Void)
@@ -1623,18 +1722,26 @@ impl BuiltInHandlerManager {
                 })
             }
             _ => {
-                // Check if first arg is an xtra instance - if so, forward to the xtra instance handler
+                // Check if first arg is an xtra instance or xtra - if so, forward to its handler
                 if !args.is_empty() {
                     if let Some(res) = reserve_player_ref(|player| {
                         if let Ok((xtra_name, instance_id)) = player.get_datum(&args[0]).to_xtra_instance() {
                             let remaining_args = args[1..].to_vec();
                             return Some(call_xtra_instance_handler(&xtra_name, *instance_id, &name.to_string(), &remaining_args));
                         }
+                        if let Ok(xtra_name) = player.get_datum(&args[0]).to_xtra_name()
+                            && has_xtra_static_handler(xtra_name, name)
+                        {
+                            return Some(call_xtra_static_handler(xtra_name, name, &args[1..]));
+                        }
                         None
                     }) {
                         return res;
                     }
                 }
+                if let Some(xtra) = find_global_handler_xtra(name) {
+                    return xtra.call_global_handler(name, args);
+                }
                 let formatted_args = reserve_player_ref(|player| {
                     let mut s = String::new();
                     for arg in args {
@@ -1884,6 +1991,7 @@ impl BuiltInHandlerManager {
                 | Datum::MovieRef
                 | Datum::MouseRef
                 | Datum::Stage
+                | Datum::Window(_)
                 | Datum::CastLib(_)
                 | Datum::DateRef(_)
                 | Datum::MathRef(_)
@@ -1900,7 +2008,7 @@ impl BuiltInHandlerManager {
     fn start_timer(_args: &Vec<DatumRef>) -> Result<DatumRef, ScriptError> {
         reserve_player_mut(|player| {
             // Reset the start_time to current time
-            player.start_time = chrono::Local::now();
+            player.start_time = crate::utils::local_now();
             Ok(DatumRef::Void)
         })
     }
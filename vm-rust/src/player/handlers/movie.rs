@@ -1,13 +1,17 @@
 use log::{debug, warn, error};
+use num::FromPrimitive;
 use crate::{
-    director::lingo::datum::{Datum, DatumType},
+    director::{enums::TransitionType, lingo::datum::{Datum, DatumType}},
     player::{
+        transition::TransitionSpec, score_recording, palette_effects,
+        bitmap::bitmap::{BuiltInPalette, PaletteRef},
         cast_lib::{CastMemberRef, INVALID_CAST_MEMBER_REF},
         datum_formatting::format_datum, ScriptInstanceRef, Score,
         reserve_player_mut, reserve_player_ref, reserve_player_mut_async,
         player_call_script_handler,
         score::{get_sprite_at, concrete_sprite_hit_test}, handlers::datum_handlers::player_call_datum_handler,
         handlers::datum_handlers::script_instance::ScriptInstanceUtils,
+        handlers::datum_handlers::cast_member_ref::CastMemberRefHandlers,
         DatumRef, ScriptError, ScriptErrorCode, get_score_sprite_mut, MovieFrameTarget,
         events::{
             player_invoke_static_event, player_wait_available,
@@ -15,6 +19,8 @@ use crate::{
             dispatch_system_event_to_timeouts, player_invoke_targeted_event
         },
     },
+    player::save::{movie_file_bytes, uncompressed_file_name},
+    js_api::JsApi,
     utils::{log_i},
 };
 
@@ -28,6 +34,118 @@ impl MovieHandlers {
         })
     }
 
+    /// Score recording commands (see `score_recording.rs`).
+    pub fn score_recording(name: &str, _args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            match name.to_lowercase().as_str() {
+                "beginrecording" => score_recording::begin_recording(player),
+                "endrecording" => score_recording::end_recording(player),
+                "updateframe" => score_recording::update_frame(player)?,
+                "insertframe" => score_recording::insert_frame(player, "insertFrame")?,
+                "duplicateframe" => score_recording::insert_frame(player, "duplicateFrame")?,
+                "deleteframe" => score_recording::delete_frame(player)?,
+                "clearframe" => score_recording::clear_frame(player)?,
+                _ => {}
+            }
+            Ok(DatumRef::Void)
+        })
+    }
+
+    /// puppetTransition whichTransition {, time, chunkSize, changeArea}
+    ///
+    /// `whichTransition` is a built-in transition code or a transition member;
+    /// `time` is in quarter seconds. The transition plays on the next frame change.
+    pub fn puppet_transition(args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let which = player.get_datum(args.first().ok_or_else(|| {
+                ScriptError::new("puppetTransition requires a transition".to_string())
+            })?);
+            let mut spec = match which {
+                Datum::CastMember(member_ref) => {
+                    let member = player
+                        .movie
+                        .cast_manager
+                        .find_member_by_ref(member_ref)
+                        .and_then(|member| member.member_type.as_transition())
+                        .ok_or_else(|| ScriptError::new(format!(
+                            "puppetTransition: member {:?} is not a transition",
+                            member_ref
+                        )))?;
+                    TransitionSpec::from(&member.info)
+                }
+                _ => TransitionSpec {
+                    transition_type: TransitionType::from(which.int_value()?.max(0) as u32),
+                    duration_ms: 250,
+                    chunk_size: 1,
+                    change_area: false,
+                },
+            };
+            if let Some(time) = args.get(1) {
+                spec.duration_ms = player.get_datum(time).int_value()?.max(0) as u32 * 250;
+            }
+            if let Some(chunk_size) = args.get(2) {
+                spec.chunk_size = player.get_datum(chunk_size).int_value()?.clamp(1, 128) as u8;
+            }
+            if let Some(change_area) = args.get(3) {
+                spec.change_area = player.get_datum(change_area).to_bool()?;
+            }
+            player.transitions.puppet = Some(spec);
+            Ok(DatumRef::Void)
+        })
+    }
+
+    /// `puppetPalette palette {, speed} {, frames}`. `0` or `""` hands the
+    /// palette back to the score's palette channel.
+    pub fn puppet_palette(args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let which = player.get_datum(args.first().ok_or_else(|| {
+                ScriptError::new("puppetPalette requires a palette".to_string())
+            })?);
+            let palette = match which {
+                Datum::Int(0) | Datum::Void => None,
+                Datum::String(name) if name.is_empty() => None,
+                Datum::Int(number) if *number < 0 => Some(PaletteRef::BuiltIn(
+                    BuiltInPalette::from_i16(*number as i16).ok_or_else(|| {
+                        ScriptError::new(format!("puppetPalette: unknown palette {}", number))
+                    })?,
+                )),
+                Datum::Int(number) => Some(PaletteRef::Member(
+                    CastMemberRefHandlers::member_ref_from_slot_number(*number as u32),
+                )),
+                Datum::Symbol(name) => Some(PaletteRef::BuiltIn(
+                    BuiltInPalette::from_symbol_string(name).ok_or_else(|| {
+                        ScriptError::new(format!("puppetPalette: unknown palette #{}", name))
+                    })?,
+                )),
+                Datum::String(name) => match BuiltInPalette::from_symbol_string(name) {
+                    Some(palette) => Some(PaletteRef::BuiltIn(palette)),
+                    None => Some(PaletteRef::Member(
+                        player.movie.cast_manager.find_member_ref_by_name(name).ok_or_else(|| {
+                            ScriptError::new(format!("puppetPalette: palette \"{}\" not found", name))
+                        })?,
+                    )),
+                },
+                Datum::CastMember(member_ref) => Some(PaletteRef::Member(member_ref.clone())),
+                _ => {
+                    return Err(ScriptError::new(format!(
+                        "puppetPalette: cannot use {} as a palette",
+                        which.type_str()
+                    )))
+                }
+            };
+            let speed = match args.get(1) {
+                Some(speed) => player.get_datum(speed).int_value()?.clamp(0, 60) as u8,
+                None => 0,
+            };
+            let frames = match args.get(2) {
+                Some(frames) => player.get_datum(frames).int_value()?.max(0) as u32,
+                None => 0,
+            };
+            palette_effects::set_puppet_palette(player, palette, speed, frames);
+            Ok(DatumRef::Void)
+        })
+    }
+
     pub fn script(args: &Vec<DatumRef>) -> Result<DatumRef, ScriptError> {
         reserve_player_mut(|player| {
             let identifier = player.get_datum(&args[0]);
@@ -755,15 +873,13 @@ impl MovieHandlers {
     pub fn get_pref(args: &Vec<DatumRef>) -> Result<DatumRef, ScriptError> {
         reserve_player_mut(|player| {
             let pref_name = player.get_datum(&args[0]).string_value()?;
-            let storage = web_sys::window()
-                .and_then(|w| w.local_storage().ok().flatten());
-            if let Some(storage) = storage {
-                let key = format!("dirplayer_pref_{}", pref_name);
-                if let Ok(Some(value)) = storage.get_item(&key) {
-                    return Ok(player.alloc_datum(Datum::String(value)));
+            match player.storage.pref(&pref_name) {
+                Some(value) => {
+                    let value = value.to_string();
+                    Ok(player.alloc_datum(Datum::String(value)))
                 }
+                None => Ok(DatumRef::Void),
             }
-            Ok(DatumRef::Void)
         })
     }
 
@@ -771,12 +887,21 @@ impl MovieHandlers {
         reserve_player_mut(|player| {
             let pref_name = player.get_datum(&args[0]).string_value()?;
             let pref_value = player.get_datum(&args[1]).string_value()?;
-            let storage = web_sys::window()
-                .and_then(|w| w.local_storage().ok().flatten());
-            if let Some(storage) = storage {
-                let key = format!("dirplayer_pref_{}", pref_name);
-                let _ = storage.set_item(&key, &pref_value);
-            }
+            player.storage.set_pref(&pref_name, &pref_value);
+            Ok(DatumRef::Void)
+        })
+    }
+
+    /// `saveMovie [fileName]` - hands the movie, written as an uncompressed
+    /// `.dir`, to the host page.
+    pub fn save_movie(args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let path = match args.first() {
+                Some(path) => player.get_datum(path).string_value()?,
+                None => uncompressed_file_name(player.movie.file_name.rsplit('/').next().unwrap_or_default()),
+            };
+            let bytes = movie_file_bytes(player)?;
+            JsApi::dispatch_file_saved(&path, &bytes);
             Ok(DatumRef::Void)
         })
     }
@@ -1105,7 +1230,7 @@ impl MovieHandlers {
             if ticks > 0 && player.delay_until.is_none() {
                 let delay_ms = (ticks as f64) * (1000.0 / 60.0);
                 player.delay_until = Some(
-                    chrono::Local::now() + chrono::Duration::milliseconds(delay_ms as i64),
+                    crate::utils::local_now() + chrono::Duration::milliseconds(delay_ms as i64),
                 );
             }
             Ok(DatumRef::Void)
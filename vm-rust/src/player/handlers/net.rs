@@ -68,19 +68,20 @@ impl NetHandlers {
             let task_state = player.net_manager.get_task_state(Some(task_id))
                 .ok_or_else(|| ScriptError::new(format!("Network task state {} not found", task_id)))?;
 
+            // #error is "" while running, "OK" when done, else the error code
             let (state, error, bytes_so_far, bytes_total) = match &task_state.result {
                 Some(Ok(bytes)) => {
                     let len = bytes.len() as i32;
-                    ("Complete", "OK", len, len)
+                    ("Complete", Datum::String("OK".to_owned()), len, len)
                 }
-                Some(Err(_code)) => {
-                    ("Error", "Error", 0i32, 0i32)
+                Some(Err(code)) => {
+                    ("Error", Datum::Int(*code), task_state.bytes_loaded as i32, task_state.bytes_total as i32)
                 }
                 None => {
                     if task_state.bytes_loaded > 0 {
-                        ("InProgress", "", task_state.bytes_loaded as i32, task_state.bytes_total as i32)
+                        ("InProgress", Datum::String("".to_owned()), task_state.bytes_loaded as i32, task_state.bytes_total as i32)
                     } else {
-                        ("Connecting", "", 0i32, 0i32)
+                        ("Connecting", Datum::String("".to_owned()), 0i32, task_state.bytes_total as i32)
                     }
                 }
             };
@@ -105,7 +106,7 @@ impl NetHandlers {
                     ),
                     (
                         player.alloc_datum(Datum::String("error".to_owned())),
-                        player.alloc_datum(Datum::String(error.to_owned())),
+                        player.alloc_datum(error),
                     ),
                 ]),
                 false,
@@ -63,6 +63,7 @@ impl TypeUtils {
             Datum::StringChunk(..) => Ok(vec!["string"]),
             Datum::CastLib(..) => Ok(vec!["castlib"]),
             Datum::Stage => Ok(vec!["stage"]),
+            Datum::Window(..) => Ok(vec!["window"]),
             Datum::SoundChannel(..) => Ok(vec!["instance"]),
             Datum::SoundRef(..) => Ok(vec!["sound"]),
             Datum::CursorRef(..) => Ok(vec!["cursor"]),
@@ -1707,7 +1708,8 @@ impl TypeHandlers {
             
             // soundBusy returns true (1) if the channel is Playing, Loading, or Queued
             let status = channel.status.clone();
-            let has_source = channel.source_node.is_some();
+            // A headless channel's mixer voice plays the part of the source node
+            let has_source = channel.source_node.is_some() || channel.voice.is_some();
             let sample_rate = channel.sample_rate;
             let sample_count = channel.sample_count;
             let elapsed_time = channel.elapsed_time;
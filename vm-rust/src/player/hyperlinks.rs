@@ -0,0 +1,227 @@
+//! Hypertext links in text members.
+//!
+//! A text member keeps its links as character ranges over `member.text`
+//! (`TextMember::hyperlinks`). They are filled from XMED styled text on load
+//! and from `<a href>` / RTF `HYPERLINK` fields when `html` or `rtf` is
+//! assigned, and can be edited through the `hyperlink` chunk property.
+//!
+//! Clicking a link is tracked across mouseDown/mouseUp: the link under the
+//! pointer goes `#active` on press, and releasing over the same link marks it
+//! `#visited` and sends `on hyperlinkClicked me, data, range` to the sprite.
+
+use std::collections::VecDeque;
+
+use crate::director::lingo::datum::{Datum, DatumType};
+
+use super::{
+    cast_lib::CastMemberRef,
+    cast_member::{CastMemberType, TextMember},
+    handlers::datum_handlers::{
+        cast_member::font::{HtmlStyle, StyledSpan},
+        sprite::SpriteDatumUtils,
+        string_chunk::StringChunkHandlers,
+    },
+    DatumRef, DirPlayer,
+};
+
+/// Link colours used when `useHypertextStyles` is on.
+const NORMAL_LINK_COLOR: u32 = 0x0000FF;
+const ACTIVE_LINK_COLOR: u32 = 0xFF0000;
+const VISITED_LINK_COLOR: u32 = 0x800080;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HyperlinkState {
+    Normal,
+    Active,
+    Visited,
+}
+
+impl HyperlinkState {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            HyperlinkState::Normal => "normal",
+            HyperlinkState::Active => "active",
+            HyperlinkState::Visited => "visited",
+        }
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<HyperlinkState> {
+        match symbol.to_ascii_lowercase().as_str() {
+            "normal" => Some(HyperlinkState::Normal),
+            "active" => Some(HyperlinkState::Active),
+            "visited" => Some(HyperlinkState::Visited),
+            _ => None,
+        }
+    }
+
+    fn color(&self) -> u32 {
+        match self {
+            HyperlinkState::Normal => NORMAL_LINK_COLOR,
+            HyperlinkState::Active => ACTIVE_LINK_COLOR,
+            HyperlinkState::Visited => VISITED_LINK_COLOR,
+        }
+    }
+}
+
+/// One link over the characters `start..end` (0-based, end exclusive).
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextHyperlink {
+    pub start: usize,
+    pub end: usize,
+    pub data: String,
+    pub state: HyperlinkState,
+}
+
+impl TextHyperlink {
+    pub fn new(start: usize, end: usize, data: String) -> TextHyperlink {
+        TextHyperlink { start, end, data, state: HyperlinkState::Normal }
+    }
+
+    /// Lingo's `[firstChar, lastChar]` form of the link range.
+    pub fn range_datum(&self, player: &mut DirPlayer) -> Datum {
+        let first = player.alloc_datum(Datum::Int(self.start as i32 + 1));
+        let last = player.alloc_datum(Datum::Int(self.end as i32));
+        Datum::List(DatumType::List, VecDeque::from([first, last]), false)
+    }
+}
+
+/// A link pressed on mouseDown, waiting for the matching mouseUp.
+#[derive(Clone, Debug)]
+pub struct PressedHyperlink {
+    pub sprite: i16,
+    pub member: CastMemberRef,
+    pub index: usize,
+    pub previous_state: HyperlinkState,
+}
+
+/// Index of the link covering character `pos`.
+pub fn hyperlink_at(links: &[TextHyperlink], pos: usize) -> Option<usize> {
+    links.iter().position(|link| link.start <= pos && pos < link.end)
+}
+
+/// Make `start..end` a link to `data`, clipping any links it overlaps. An
+/// empty `data` only removes links from the range.
+pub fn set_hyperlink(links: &mut Vec<TextHyperlink>, start: usize, end: usize, data: &str) {
+    let mut clipped = Vec::with_capacity(links.len() + 1);
+    for link in links.drain(..) {
+        if link.end <= start || link.start >= end {
+            clipped.push(link);
+            continue;
+        }
+        if link.start < start {
+            clipped.push(TextHyperlink { end: start, ..link.clone() });
+        }
+        if link.end > end {
+            clipped.push(TextHyperlink { start: end, ..link });
+        }
+    }
+    if start < end && !data.is_empty() {
+        clipped.push(TextHyperlink::new(start, end, data.to_string()));
+    }
+    clipped.sort_by_key(|link| link.start);
+    *links = clipped;
+}
+
+/// Build links from `(start, end, data)` ranges, joining ranges that touch and
+/// point at the same data (parsers emit one range per styled run).
+pub fn hyperlinks_from_ranges(mut ranges: Vec<(usize, usize, String)>) -> Vec<TextHyperlink> {
+    ranges.sort_by_key(|(start, _, _)| *start);
+    let mut links: Vec<TextHyperlink> = Vec::new();
+    for (start, end, data) in ranges {
+        if start >= end {
+            continue;
+        }
+        match links.last_mut() {
+            Some(last) if last.end == start && last.data == data => last.end = end,
+            _ => links.push(TextHyperlink::new(start, end, data)),
+        }
+    }
+    links
+}
+
+impl TextMember {
+    pub fn uses_hypertext_styles(&self) -> bool {
+        self.info.as_ref().is_some_and(|info| info.use_hypertext_styles)
+    }
+
+    /// Render-time spans with link styling applied on top of `spans`.
+    pub fn apply_hyperlink_styles(&self, mut spans: Vec<StyledSpan>) -> Vec<StyledSpan> {
+        if !self.uses_hypertext_styles() {
+            return spans;
+        }
+        for link in &self.hyperlinks {
+            StringChunkHandlers::apply_styled_span_range(
+                &self.text,
+                &mut spans,
+                link.start,
+                link.end,
+                HtmlStyle::default(),
+                |style| {
+                    style.underline = true;
+                    style.color = Some(link.state.color());
+                },
+            );
+        }
+        spans
+    }
+}
+
+fn text_member_mut<'a>(player: &'a mut DirPlayer, member: &CastMemberRef) -> Option<&'a mut TextMember> {
+    match &mut player.movie.cast_manager.find_mut_member_by_ref(member)?.member_type {
+        CastMemberType::Text(text) => Some(text),
+        _ => None,
+    }
+}
+
+/// The text member and link index under the stage point on `sprite`.
+fn hyperlink_under_point(player: &DirPlayer, sprite: i16, x: i32, y: i32) -> Option<(CastMemberRef, usize)> {
+    let member_ref = player.movie.score.get_sprite(sprite)?.member.clone()?;
+    let member = player.movie.cast_manager.find_member_by_ref(&member_ref)?;
+    let CastMemberType::Text(text) = &member.member_type else {
+        return None;
+    };
+    if text.hyperlinks.is_empty() {
+        return None;
+    }
+    let (_, char_index) = SpriteDatumUtils::text_char_index_at(player, sprite, x, y)?;
+    let index = hyperlink_at(&text.hyperlinks, char_index)?;
+    Some((member_ref, index))
+}
+
+/// On mouseDown over a link of the clicked sprite, mark it `#active`.
+pub fn press_hyperlink(player: &mut DirPlayer, sprite: i16, x: i32, y: i32) {
+    player.pressed_hyperlink = None;
+    if sprite <= 0 {
+        return;
+    }
+    let Some((member, index)) = hyperlink_under_point(player, sprite, x, y) else {
+        return;
+    };
+    let Some(link) = text_member_mut(player, &member).and_then(|text| text.hyperlinks.get_mut(index)) else {
+        return;
+    };
+    let previous_state = link.state;
+    link.state = HyperlinkState::Active;
+    player.pressed_hyperlink = Some(PressedHyperlink { sprite, member, index, previous_state });
+}
+
+/// On mouseUp, finish the press started by `press_hyperlink`. Releasing over
+/// the same link marks it `#visited` and returns the sprite together with the
+/// `data, range` arguments for `hyperlinkClicked`; releasing elsewhere puts
+/// the link back in its previous state.
+pub fn release_hyperlink(player: &mut DirPlayer, x: i32, y: i32) -> Option<(i16, Vec<DatumRef>)> {
+    let pressed = player.pressed_hyperlink.take()?;
+    let clicked = hyperlink_under_point(player, pressed.sprite, x, y)
+        .is_some_and(|(member, index)| member == pressed.member && index == pressed.index);
+    let link = text_member_mut(player, &pressed.member)?.hyperlinks.get_mut(pressed.index)?;
+    if !clicked {
+        link.state = pressed.previous_state;
+        return None;
+    }
+    link.state = HyperlinkState::Visited;
+    let link = link.clone();
+    let data = player.alloc_datum(Datum::String(link.data.clone()));
+    let range = link.range_datum(player);
+    let range = player.alloc_datum(range);
+    Some((pressed.sprite, vec![data, range]))
+}
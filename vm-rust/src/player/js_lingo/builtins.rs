@@ -1,7 +1,8 @@
 // ECMA-262 stdlib (subset) for JsRuntime.
 //
 // Covers the common surface DCRs hit: Math, parseInt/parseFloat/isNaN/
-// isFinite/NaN/Infinity, String methods, Array methods, Number constants.
+// isFinite/NaN/Infinity, String methods, Array methods, Number constants,
+// RegExp (see regexp.rs) and JSON (see json.rs).
 //
 // Method dispatch for strings and arrays is fielded specially because
 // JsValue::String / JsValue::Array don't carry per-instance prototype
@@ -17,7 +18,7 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use super::interpreter::JsRuntime;
-use super::value::{JsArray, JsError, JsObject, JsValue, NativeFn};
+use super::value::{JsError, JsObject, JsValue, NativeCall, NativeFn};
 
 /// Install the entire stdlib subset. Called by JsRuntime::with_stdlib().
 pub fn install(rt: &JsRuntime) {
@@ -28,6 +29,8 @@ pub fn install(rt: &JsRuntime) {
     install_array_object(rt);
     install_object_object(rt);
     install_date(rt);
+    install_regexp(rt);
+    install_json(rt);
 }
 
 fn install_globals(rt: &JsRuntime) {
@@ -219,10 +222,35 @@ fn install_date(rt: &JsRuntime) {
     });
 }
 
+fn install_regexp(rt: &JsRuntime) {
+    // `RegExp(pattern, flags)` and `new RegExp(...)` both construct; a
+    // RegExp passed without flags is copied, as in ES3 §15.10.3.1.
+    rt.define_native("RegExp", super::regexp::construct);
+}
+
+fn install_json(rt: &JsRuntime) {
+    let json = Rc::new(RefCell::new(JsObject::new()));
+    {
+        let mut o = json.borrow_mut();
+        o.class_name = "JSON";
+        o.set_own("parse", native_rt("parse", super::json::parse));
+        o.set_own("stringify", native_rt("stringify", super::json::stringify));
+    }
+    rt.global.borrow_mut().set_own("JSON", JsValue::Object(json));
+}
+
 // ===== Helpers =====
 
 fn native(name: &'static str, f: impl Fn(&[JsValue]) -> Result<JsValue, JsError> + 'static) -> JsValue {
-    JsValue::Native(Rc::new(NativeFn { name, call: Box::new(f) }))
+    JsValue::Native(Rc::new(NativeFn { name, call: NativeCall::Plain(Box::new(f)) }))
+}
+
+/// Like `native`, for builtins that call back into script (reviver, replacer).
+fn native_rt(
+    name: &'static str,
+    f: impl Fn(&mut JsRuntime, &[JsValue]) -> Result<JsValue, JsError> + 'static,
+) -> JsValue {
+    JsValue::Native(Rc::new(NativeFn { name, call: NativeCall::WithRuntime(Box::new(f)) }))
 }
 
 fn unary_num(args: &[JsValue], f: impl Fn(f64) -> f64) -> Result<JsValue, JsError> {
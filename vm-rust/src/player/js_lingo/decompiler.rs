@@ -1500,6 +1500,19 @@ impl<'a> DecompState<'a> {
                 i + 1
             }
 
+            // ===== Regexp literals =====
+            JsOp::Object => {
+                let idx = read_u16_operand(&ins.operand).unwrap_or(0) as usize;
+                let text = match self.ir.atoms.get(idx) {
+                    Some(JsAtom::RegExp { source, flags }) => {
+                        format!("/{}/{}", source, super::regexp::xdr_flag_letters(*flags))
+                    }
+                    other => format!("/* object #{} = {:?} */", idx, other),
+                };
+                self.push_entry(text, 100, vec![i]);
+                i + 1
+            }
+
             // ===== Anything we haven't covered: emit a comment so the line
             // mapping is preserved without crashing. =====
             other => {
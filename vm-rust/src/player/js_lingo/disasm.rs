@@ -104,6 +104,7 @@ fn format_atom(a: &JsAtom) -> String {
                 f.script.bytecode.len()
             )
         }
+        JsAtom::RegExp { source, flags } => format!("<regexp /{}/ flags=0x{:x}>", source, flags),
         JsAtom::Unsupported(tag) => format!("<unsupported tag={}>", tag),
     }
 }
@@ -461,7 +461,7 @@ fn synth_forin_array_walks_indices() {
         captured_scope: None,
     };
 
-    let arr = JsArray { items: vec![JsValue::Int(10), JsValue::Int(20), JsValue::Int(30)] };
+    let arr = JsArray::from_items(vec![JsValue::Int(10), JsValue::Int(20), JsValue::Int(30)]);
     let arr_val = JsValue::Array(Rc::new(RefCell::new(arr)));
 
     let mut rt = JsRuntime::new();
@@ -841,7 +841,7 @@ fn stdlib_isnan_recognises_nan() {
 #[test]
 fn stdlib_array_push_pop_join() {
     let mut rt = JsRuntime::with_stdlib();
-    let arr = JsValue::Array(Rc::new(std::cell::RefCell::new(super::value::JsArray { items: vec![JsValue::Int(1), JsValue::Int(2)] })));
+    let arr = JsValue::Array(Rc::new(std::cell::RefCell::new(super::value::JsArray::from_items(vec![JsValue::Int(1), JsValue::Int(2)]))));
     // push(3, 4)
     let push = match super::interpreter::JsRuntime::with_stdlib() {
         _ => match &arr {
@@ -975,3 +975,230 @@ fn synth_array_literal_via_initelem() {
     atoms.push(JsAtom::String("Array".into())); // atom[3]
     assert!(matches!(run_synth(bc, atoms).unwrap(), JsValue::Array(_)));
 }
+
+// ===== RegExp / JSON =====
+
+fn js_str(s: &str) -> JsValue { JsValue::String(Rc::new(s.to_string())) }
+
+fn call_method(rt: &mut JsRuntime, target: &JsValue, name: &str, args: Vec<JsValue>) -> Result<JsValue, super::value::JsError> {
+    let method = super::interpreter::get_property_pub(target, name);
+    rt.invoke(&method, args, target.clone())
+}
+
+fn new_regexp(rt: &mut JsRuntime, source: &str, flags: &str) -> JsValue {
+    let ctor = rt.global.borrow().get_own("RegExp").cloned().expect("RegExp installed");
+    rt.invoke(&ctor, vec![js_str(source), js_str(flags)], JsValue::Undefined).expect("valid regexp")
+}
+
+fn test_native(f: impl Fn(&[JsValue]) -> Result<JsValue, super::value::JsError> + 'static) -> JsValue {
+    use super::value::{NativeCall, NativeFn};
+    JsValue::Native(Rc::new(NativeFn { name: "test", call: NativeCall::Plain(Box::new(f)) }))
+}
+
+fn array_strings(v: &JsValue) -> Vec<String> {
+    match v {
+        JsValue::Array(a) => a.borrow().items.iter().map(|v| v.to_string()).collect(),
+        other => panic!("expected array, got {}", other.type_of()),
+    }
+}
+
+#[test]
+fn regexp_literal_from_object_atom() {
+    // OBJECT with a regexp atom yields a fresh RegExp object per evaluation.
+    let atoms = vec![JsAtom::RegExp { source: "(\\d+)-(\\d+)".into(), flags: 0x02 /* JSREG_GLOB */ }];
+    let mut bc = Vec::new();
+    bc.push(JsOp::Object as u8); bc.extend(u16_be(0));
+    bc.push(JsOp::Return as u8);
+    let re = run_with_stdlib(bc, atoms, 0).unwrap();
+    assert_eq!(re.to_string(), "/(\\d+)-(\\d+)/g");
+    let p = |name| super::interpreter::get_property_pub(&re, name);
+    assert_eq!(p("source").to_string(), "(\\d+)-(\\d+)");
+    assert!(p("global").to_bool());
+    assert!(!p("ignoreCase").to_bool());
+    assert_eq!(p("lastIndex").to_int32(), 0);
+}
+
+#[test]
+fn regexp_exec_and_test_track_last_index() {
+    let mut rt = JsRuntime::with_stdlib();
+    let re = new_regexp(&mut rt, "(\\d+)-(\\d+)", "g");
+    let input = js_str("a 12-34 b 5-6");
+
+    let m = call_method(&mut rt, &re, "exec", vec![input.clone()]).unwrap();
+    assert_eq!(array_strings(&m), vec!["12-34", "12", "34"]);
+    assert_eq!(super::interpreter::get_property_pub(&m, "index").to_int32(), 2);
+    assert_eq!(super::interpreter::get_property_pub(&m, "input").to_string(), "a 12-34 b 5-6");
+    assert_eq!(super::interpreter::get_property_pub(&re, "lastIndex").to_int32(), 7);
+
+    let m = call_method(&mut rt, &re, "exec", vec![input.clone()]).unwrap();
+    assert_eq!(array_strings(&m), vec!["5-6", "5", "6"]);
+
+    // Failure returns null and rewinds lastIndex.
+    let m = call_method(&mut rt, &re, "exec", vec![input.clone()]).unwrap();
+    assert!(matches!(m, JsValue::Null));
+    assert_eq!(super::interpreter::get_property_pub(&re, "lastIndex").to_int32(), 0);
+
+    // Non-global regexps always search from the start.
+    let re = new_regexp(&mut rt, "B", "i");
+    assert!(call_method(&mut rt, &re, "test", vec![input.clone()]).unwrap().to_bool());
+    assert!(call_method(&mut rt, &re, "test", vec![input]).unwrap().to_bool());
+    assert_eq!(super::interpreter::get_property_pub(&re, "lastIndex").to_int32(), 0);
+}
+
+#[test]
+fn regexp_constructor_rejects_bad_flags() {
+    let mut rt = JsRuntime::with_stdlib();
+    let ctor = rt.global.borrow().get_own("RegExp").cloned().unwrap();
+    let err = rt.invoke(&ctor, vec![js_str("a"), js_str("gg")], JsValue::Undefined).unwrap_err();
+    assert!(err.message.contains("SyntaxError"), "error message: {}", err.message);
+    let err = rt.invoke(&ctor, vec![js_str("a"), js_str("x")], JsValue::Undefined).unwrap_err();
+    assert!(err.message.contains("SyntaxError"), "error message: {}", err.message);
+}
+
+#[test]
+fn regexp_uses_ecma_semantics() {
+    let mut rt = JsRuntime::with_stdlib();
+    let mut matches = |source: &str, flags: &str, input: &str| {
+        let re = new_regexp(&mut rt, source, flags);
+        call_method(&mut rt, &re, "test", vec![js_str(input)]).unwrap().to_bool()
+    };
+    // `.` stops at line terminators.
+    assert!(!matches("a.b", "", "a\nb"));
+    // `$` only matches at the very end unless multiline.
+    assert!(!matches("a$", "", "a\n"));
+    assert!(matches("a$", "m", "a\nb"));
+    // `\w` is ASCII-only.
+    assert!(!matches("^\\w$", "", "é"));
+    // A backreference to a group that didn't participate matches empty.
+    assert!(matches("^(a)?b\\1$", "", "b"));
+}
+
+#[test]
+fn string_match_search_and_split_with_regexps() {
+    let mut rt = JsRuntime::with_stdlib();
+    let s = js_str("a1b22c333");
+
+    let re = new_regexp(&mut rt, "\\d+", "g");
+    let m = call_method(&mut rt, &s, "match", vec![re]).unwrap();
+    assert_eq!(array_strings(&m), vec!["1", "22", "333"]);
+
+    let re = new_regexp(&mut rt, "(\\d)(\\d)", "");
+    let m = call_method(&mut rt, &s, "match", vec![re]).unwrap();
+    assert_eq!(array_strings(&m), vec!["22", "2", "2"]);
+
+    let re = new_regexp(&mut rt, "c", "");
+    assert_eq!(call_method(&mut rt, &s, "search", vec![re]).unwrap().to_int32(), 5);
+    let re = new_regexp(&mut rt, "z", "");
+    assert_eq!(call_method(&mut rt, &s, "search", vec![re]).unwrap().to_int32(), -1);
+
+    // Captures are spliced into the result; the limit caps it.
+    let re = new_regexp(&mut rt, "(\\d)+", "");
+    let parts = call_method(&mut rt, &s, "split", vec![re.clone()]).unwrap();
+    assert_eq!(array_strings(&parts), vec!["a", "1", "b", "2", "c", "3", ""]);
+    let parts = call_method(&mut rt, &s, "split", vec![re, JsValue::Int(3)]).unwrap();
+    assert_eq!(array_strings(&parts), vec!["a", "1", "b"]);
+    let parts = call_method(&mut rt, &js_str("a,b,,c"), "split", vec![js_str(",")]).unwrap();
+    assert_eq!(array_strings(&parts), vec!["a", "b", "", "c"]);
+}
+
+#[test]
+fn string_replace_with_templates_and_functions() {
+    let mut rt = JsRuntime::with_stdlib();
+    let s = js_str("john smith, jane doe");
+
+    let re = new_regexp(&mut rt, "(\\w+) (\\w+)", "g");
+    let out = call_method(&mut rt, &s, "replace", vec![re, js_str("$2 $1 [$&] $$")]).unwrap();
+    assert_eq!(out.to_string(), "smith john [john smith] $, doe jane [jane doe] $");
+
+    // Without /g only the first match is replaced; a string pattern is literal.
+    let re = new_regexp(&mut rt, "o", "");
+    assert_eq!(call_method(&mut rt, &s, "replace", vec![re, js_str("0")]).unwrap().to_string(), "j0hn smith, jane doe");
+    assert_eq!(call_method(&mut rt, &js_str("a.b.c"), "replace", vec![js_str("."), js_str("-")]).unwrap().to_string(), "a-b.c");
+
+    // Function replacers receive (match, p1.., offset, input).
+    let re = new_regexp(&mut rt, "\\b(\\w)", "g");
+    let upper = test_native(|args| {
+        assert_eq!(args.len(), 4);
+        assert_eq!(args[3].to_string(), "john smith, jane doe");
+        Ok(JsValue::String(Rc::new(format!("{}@{}", args[1].to_string().to_uppercase(), args[2].to_int32()))))
+    });
+    let out = call_method(&mut rt, &s, "replace", vec![re, upper]).unwrap();
+    assert_eq!(out.to_string(), "J@0ohn S@5mith, J@12ane D@17oe");
+}
+
+#[test]
+fn json_parse_builds_objects_and_arrays() {
+    let mut rt = JsRuntime::with_stdlib();
+    let json = rt.global.borrow().get_own("JSON").cloned().expect("JSON installed");
+    let text = js_str(r#" {"name": "Habé\n", "n": [1, -2.5, 1e3, true, null], "o": {}} "#);
+    let v = call_method(&mut rt, &json, "parse", vec![text]).unwrap();
+    let p = |v: &JsValue, name| super::interpreter::get_property_pub(v, name);
+    assert_eq!(p(&v, "name").to_string(), "Habé\n");
+    let n = p(&v, "n");
+    assert!(matches!(p(&n, "0"), JsValue::Int(1)));
+    assert_eq!(p(&n, "1").to_number(), -2.5);
+    assert!(matches!(p(&n, "2"), JsValue::Int(1000)));
+    assert!(matches!(p(&n, "3"), JsValue::Bool(true)));
+    assert!(matches!(p(&n, "4"), JsValue::Null));
+    assert!(matches!(p(&v, "o"), JsValue::Object(_)));
+
+    for bad in ["{'a': 1}", "[1,]", "01", "\"\t\"", "{\"a\" 1}", "[] x", ""] {
+        let err = call_method(&mut rt, &json, "parse", vec![js_str(bad)]).unwrap_err();
+        assert!(err.message.contains("SyntaxError"), "{:?}: {}", bad, err.message);
+    }
+}
+
+#[test]
+fn json_parse_applies_reviver() {
+    let mut rt = JsRuntime::with_stdlib();
+    let json = rt.global.borrow().get_own("JSON").cloned().unwrap();
+    // Scale numbers, drop the "skip" key.
+    let reviver = test_native(|args| Ok(match (&args[0], &args[1]) {
+        (k, _) if k.to_string() == "skip" => JsValue::Undefined,
+        (_, JsValue::Int(n)) => JsValue::Int(n * 10),
+        (_, v) => v.clone(),
+    }));
+    let text = js_str(r#"{"a": 1, "skip": 2, "b": [3, 4]}"#);
+    let v = call_method(&mut rt, &json, "parse", vec![text, reviver]).unwrap();
+    let out = call_method(&mut rt, &json, "stringify", vec![v]).unwrap();
+    assert_eq!(out.to_string(), r#"{"a":10,"b":[30,40]}"#);
+}
+
+#[test]
+fn json_stringify_formats_values() {
+    let mut rt = JsRuntime::with_stdlib();
+    let json = rt.global.borrow().get_own("JSON").cloned().unwrap();
+    let obj = Rc::new(RefCell::new(JsObject::new()));
+    obj.borrow_mut().set_own("s", js_str("q\"\\\u{1}"));
+    obj.borrow_mut().set_own("n", JsValue::Number(f64::NAN));
+    obj.borrow_mut().set_own("u", JsValue::Undefined);
+    obj.borrow_mut().set_own("f", test_native(|_| Ok(JsValue::Undefined)));
+    obj.borrow_mut().set_own("a", JsValue::Array(Rc::new(RefCell::new(JsArray::from_items(vec![
+        JsValue::Int(1), JsValue::Undefined, JsValue::Number(0.5),
+    ])))));
+    let re = new_regexp(&mut rt, "x", "g");
+    obj.borrow_mut().set_own("r", re);
+    let v = JsValue::Object(obj);
+
+    let out = call_method(&mut rt, &json, "stringify", vec![v.clone()]).unwrap();
+    assert_eq!(out.to_string(), r#"{"s":"q\"\\\u0001","n":null,"a":[1,null,0.5],"r":{}}"#);
+
+    let keys = JsValue::Array(Rc::new(RefCell::new(JsArray::from_items(vec![js_str("a"), js_str("n")]))));
+    let out = call_method(&mut rt, &json, "stringify", vec![v, keys, JsValue::Int(2)]).unwrap();
+    assert_eq!(out.to_string(), "{\n  \"a\": [\n    1,\n    null,\n    0.5\n  ],\n  \"n\": null\n}");
+
+    let out = call_method(&mut rt, &json, "stringify", vec![JsValue::Undefined]).unwrap();
+    assert!(matches!(out, JsValue::Undefined));
+}
+
+#[test]
+fn json_stringify_rejects_cycles() {
+    let mut rt = JsRuntime::with_stdlib();
+    let json = rt.global.borrow().get_own("JSON").cloned().unwrap();
+    let obj = Rc::new(RefCell::new(JsObject::new()));
+    obj.borrow_mut().set_own("self", JsValue::Object(obj.clone()));
+    let err = call_method(&mut rt, &json, "stringify", vec![JsValue::Object(obj.clone())]).unwrap_err();
+    assert!(err.message.contains("cyclic object value"), "error message: {}", err.message);
+    // Break the cycle so the test doesn't leak it.
+    obj.borrow_mut().props.clear();
+}
@@ -15,7 +15,7 @@ use std::rc::Rc;
 use super::opcodes::JsOp;
 use super::value::{
     JsArray, JsArrayRef, JsError, JsFunction, JsFunctionRef, JsObject, JsObjectRef, JsValue,
-    NativeFn,
+    NativeCall, NativeFn,
 };
 use super::variable_length::{read_i16_operand, read_i32_operand, read_u16_operand, read_u32_operand};
 use super::xdr::{
@@ -150,7 +150,7 @@ impl JsRuntime {
         name: &'static str,
         f: impl Fn(&[JsValue]) -> Result<JsValue, JsError> + 'static,
     ) {
-        let native = NativeFn { name, call: Box::new(f) };
+        let native = NativeFn { name, call: NativeCall::Plain(Box::new(f)) };
         self.global.borrow_mut().set_own(name, JsValue::Native(Rc::new(native)));
     }
 
@@ -620,9 +620,9 @@ impl JsRuntime {
                         let cn_for_native = captured_name.clone();
                         let native = super::value::NativeFn {
                             name: "<host-call>",
-                            call: Box::new(move |args| {
+                            call: super::value::NativeCall::Plain(Box::new(move |args| {
                                 bridge.borrow_mut().call_global(&cn_for_native, args)
-                            }),
+                            })),
                         };
                         JsValue::Native(Rc::new(native))
                     }
@@ -1354,6 +1354,11 @@ impl JsRuntime {
                         atom: Rc::new((*fa).clone()),
                         captured_scope: Some(frame.scope.clone()),
                     })),
+                    // Each evaluation of a regexp literal gets its own
+                    // object, so `lastIndex` never leaks between runs.
+                    JsAtom::RegExp { source, flags } => super::regexp::new_regexp_object(
+                        super::regexp::JsRegExp::from_xdr_flags(&source, flags)?,
+                    ),
                     _ => JsValue::Object(Rc::new(RefCell::new(JsObject::new()))),
                 };
                 frame.stack.push(v);
@@ -1407,7 +1412,10 @@ impl JsRuntime {
                 let frame = build_function_frame(f, args, this_value, self.global.clone());
                 self.run_frame(frame)
             }
-            JsValue::Native(f) => (f.call)(&args),
+            JsValue::Native(f) => match &f.call {
+                NativeCall::Plain(call) => call(&args),
+                NativeCall::WithRuntime(call) => call(self, &args),
+            },
             other => Err(JsError::new(format!("not callable: {:?}", other))),
         };
         self.call_depth.set(depth);
@@ -1805,7 +1813,7 @@ fn director_ref_set_property(
     }
 }
 
-fn get_property(obj: &JsValue, name: &str) -> JsValue {
+pub(super) fn get_property(obj: &JsValue, name: &str) -> JsValue {
     // Live Director refs: route property reads through the same path the
     // Lingo VM uses, so `sprite(3).locH` returns the channel's current
     // x-coordinate (and any prior writes are visible). Falls back to
@@ -1818,6 +1826,12 @@ fn get_property(obj: &JsValue, name: &str) -> JsValue {
         JsValue::Object(o) => {
             let b = o.borrow();
             if let Some(v) = b.get_own(name) { return v.clone(); }
+            // RegExp.prototype.* methods, dispatched as bound natives.
+            if let Some(re) = b.regexp.clone()
+                && let Some(m) = super::regexp::regexp_method(o.clone(), re, name)
+            {
+                return m;
+            }
             if let Some(p) = b.proto.clone() {
                 drop(b);
                 return get_property(&JsValue::Object(p), name);
@@ -1830,6 +1844,9 @@ fn get_property(obj: &JsValue, name: &str) -> JsValue {
             if let Ok(i) = name.parse::<usize>() {
                 if i < b.items.len() { return b.items[i].clone(); }
             }
+            if let Some((_, v)) = b.props.iter().find(|(k, _)| k == name) {
+                return v.clone();
+            }
             drop(b);
             // Array.prototype.* methods, dispatched as bound natives.
             if let Some(m) = array_method(a.clone(), name) {
@@ -1861,7 +1878,7 @@ fn array_method(arr: super::value::JsArrayRef, name: &str) -> Option<JsValue> {
         ($n:expr, $f:expr) => {
             Some(JsValue::Native(Rc::new(NativeFn {
                 name: $n,
-                call: Box::new($f),
+                call: NativeCall::Plain(Box::new($f)),
             })))
         };
     }
@@ -1929,7 +1946,7 @@ fn array_method(arr: super::value::JsArrayRef, name: &str) -> Option<JsValue> {
                 let start = start.min(len) as usize;
                 let end = end.min(len) as usize;
                 let out: Vec<JsValue> = if start < end { b.items[start..end].to_vec() } else { Vec::new() };
-                Ok(JsValue::Array(Rc::new(std::cell::RefCell::new(super::value::JsArray { items: out }))))
+                Ok(JsValue::Array(Rc::new(std::cell::RefCell::new(JsArray::from_items(out)))))
             })
         }
         "concat" => {
@@ -1943,7 +1960,7 @@ fn array_method(arr: super::value::JsArrayRef, name: &str) -> Option<JsValue> {
                         out.push(v.clone());
                     }
                 }
-                Ok(JsValue::Array(Rc::new(std::cell::RefCell::new(super::value::JsArray { items: out }))))
+                Ok(JsValue::Array(Rc::new(std::cell::RefCell::new(JsArray::from_items(out)))))
             })
         }
         "indexOf" => {
@@ -1976,7 +1993,7 @@ fn string_method(s: Rc<String>, name: &str) -> Option<JsValue> {
     use super::value::NativeFn;
     macro_rules! bind {
         ($n:expr, $f:expr) => {
-            Some(JsValue::Native(Rc::new(NativeFn { name: $n, call: Box::new($f) })))
+            Some(JsValue::Native(Rc::new(NativeFn { name: $n, call: NativeCall::Plain(Box::new($f)) })))
         };
     }
     match name {
@@ -2054,30 +2071,16 @@ fn string_method(s: Rc<String>, name: &str) -> Option<JsValue> {
         }
         "toUpperCase" => { let s = s.clone(); bind!("toUpperCase", move |_| Ok(JsValue::String(Rc::new(s.to_uppercase())))) }
         "toLowerCase" => { let s = s.clone(); bind!("toLowerCase", move |_| Ok(JsValue::String(Rc::new(s.to_lowercase())))) }
-        "split" => {
-            let s = s.clone();
-            bind!("split", move |args| {
-                let sep = match args.get(0) {
-                    Some(v) => v.to_string(),
-                    None => return Ok(JsValue::Array(Rc::new(std::cell::RefCell::new(
-                        super::value::JsArray { items: vec![JsValue::String(s.clone())] }
-                    )))),
-                };
-                let items: Vec<JsValue> = if sep.is_empty() {
-                    s.chars().map(|c| JsValue::String(Rc::new(c.to_string()))).collect()
-                } else {
-                    s.split(&sep).map(|p| JsValue::String(Rc::new(p.to_string()))).collect()
-                };
-                Ok(JsValue::Array(Rc::new(std::cell::RefCell::new(super::value::JsArray { items }))))
-            })
-        }
+        "split" => { let s = s.clone(); bind!("split", move |args| super::regexp::string_split(&s, args)) }
+        "match" => { let s = s.clone(); bind!("match", move |args| super::regexp::string_match(&s, args)) }
+        "search" => { let s = s.clone(); bind!("search", move |args| super::regexp::string_search(&s, args)) }
         "replace" => {
+            // The replacement may be a function, so this one needs the runtime.
             let s = s.clone();
-            bind!("replace", move |args| {
-                let from = args.get(0).map(|v| v.to_string()).unwrap_or_default();
-                let to = args.get(1).map(|v| v.to_string()).unwrap_or_default();
-                Ok(JsValue::String(Rc::new(s.replacen(&from, &to, 1))))
-            })
+            Some(JsValue::Native(Rc::new(NativeFn {
+                name: "replace",
+                call: NativeCall::WithRuntime(Box::new(move |rt, args| super::regexp::string_replace(rt, &s, args))),
+            })))
         }
         "concat" => {
             let s = s.clone();
@@ -2121,11 +2124,12 @@ fn set_property(obj: &JsValue, name: &str, value: JsValue) -> Result<(), JsError
             }
             // Non-array-index keys (negative numbers, non-numeric strings).
             // ECMA-262 makes these legitimate string properties on the array
-            // object. We don't currently store them anywhere (our JsArray is
-            // a flat Vec); silently drop the write so a movie that does
-            // `arr[-1] = x` in an off-by-one loop doesn't error out --
-            // the value isn't observable later, but the code that wrote it
-            // wasn't planning to read it back either.
+            // object, kept beside the items.
+            let mut b = a.borrow_mut();
+            match b.props.iter_mut().find(|(k, _)| k == name) {
+                Some((_, v)) => *v = value,
+                None => b.props.push((name.to_string(), value)),
+            }
             Ok(())
         }
         _ => Err(JsError::new(format!("cannot set property on {:?}", obj))),
@@ -2264,6 +2268,9 @@ fn atom_to_value(atoms: &[JsAtom], idx: usize) -> Result<JsValue, JsError> {
             atom: Rc::new((**fa).clone()),
             captured_scope: None,
         })),
+        JsAtom::RegExp { source, flags } => {
+            super::regexp::new_regexp_object(super::regexp::JsRegExp::from_xdr_flags(source, *flags)?)
+        }
         JsAtom::Unsupported(_) => JsValue::Undefined,
     })
 }
@@ -0,0 +1,413 @@
+// JSON.parse / JSON.stringify for JsRuntime.
+//
+// SpiderMonkey 1.5 predates JSON, but the JS-syntax movies we run ship
+// json2.js-style code that expects the ES5 object (ECMA-262 5th edition
+// §15.12), so that's what this follows: strict JSON grammar, reviver and
+// replacer callbacks, `toJSON`, property-list replacers and indentation.
+// Objects keep their insertion order, matching JsObject::props.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::interpreter::{get_property, JsRuntime};
+use super::value::{JsArray, JsError, JsObject, JsObjectRef, JsValue};
+
+/// Nesting deeper than this is rejected rather than overflowing the stack.
+const MAX_DEPTH: usize = 512;
+
+fn syntax_error(message: &str, pos: usize) -> JsError {
+    JsError::new(format!("SyntaxError: JSON.parse: {} at position {}", message, pos))
+}
+
+fn new_object() -> JsObjectRef {
+    Rc::new(RefCell::new(JsObject::new()))
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while matches!(self.chars.get(self.pos), Some(' ' | '\t' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), JsError> {
+        self.skip_whitespace();
+        if self.chars.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(syntax_error(&format!("expected '{}'", c), self.pos))
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: JsValue) -> Result<JsValue, JsError> {
+        let end = self.pos + word.chars().count();
+        if self.chars.get(self.pos..end).is_some_and(|s| s.iter().copied().eq(word.chars())) {
+            self.pos = end;
+            Ok(value)
+        } else {
+            Err(syntax_error("unexpected character", self.pos))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<JsValue, JsError> {
+        if depth > MAX_DEPTH {
+            return Err(syntax_error("too much nesting", self.pos));
+        }
+        self.skip_whitespace();
+        match self.chars.get(self.pos) {
+            Some('{') => self.object(depth),
+            Some('[') => self.array(depth),
+            Some('"') => Ok(JsValue::String(Rc::new(self.string()?))),
+            Some('t') => self.literal("true", JsValue::Bool(true)),
+            Some('f') => self.literal("false", JsValue::Bool(false)),
+            Some('n') => self.literal("null", JsValue::Null),
+            Some(c) if *c == '-' || c.is_ascii_digit() => self.number(),
+            Some(_) => Err(syntax_error("unexpected character", self.pos)),
+            None => Err(syntax_error("unexpected end of data", self.pos)),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<JsValue, JsError> {
+        self.pos += 1;
+        let obj = new_object();
+        self.skip_whitespace();
+        if self.chars.get(self.pos) == Some(&'}') {
+            self.pos += 1;
+            return Ok(JsValue::Object(obj));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.chars.get(self.pos) != Some(&'"') {
+                return Err(syntax_error("expected property name", self.pos));
+            }
+            let key = self.string()?;
+            self.expect(':')?;
+            let value = self.value(depth + 1)?;
+            obj.borrow_mut().set_own(&key, value);
+            self.skip_whitespace();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(JsValue::Object(obj));
+                }
+                _ => return Err(syntax_error("expected ',' or '}'", self.pos)),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<JsValue, JsError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.chars.get(self.pos) == Some(&']') {
+            self.pos += 1;
+            return Ok(JsValue::Array(Rc::new(RefCell::new(JsArray::from_items(items)))));
+        }
+        loop {
+            items.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(JsValue::Array(Rc::new(RefCell::new(JsArray::from_items(items)))));
+                }
+                _ => return Err(syntax_error("expected ',' or ']'", self.pos)),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u16, JsError> {
+        let digits: String = self.chars.get(self.pos..self.pos + 4).map(|d| d.iter().collect()).unwrap_or_default();
+        let unit = u16::from_str_radix(&digits, 16).map_err(|_| syntax_error("bad Unicode escape", self.pos))?;
+        self.pos += 4;
+        Ok(unit)
+    }
+
+    fn string(&mut self) -> Result<String, JsError> {
+        self.pos += 1;
+        let mut units: Vec<u16> = Vec::new();
+        loop {
+            let Some(&c) = self.chars.get(self.pos) else {
+                return Err(syntax_error("unterminated string", self.pos));
+            };
+            self.pos += 1;
+            match c {
+                '"' => return Ok(String::from_utf16_lossy(&units)),
+                '\\' => {
+                    let Some(&escape) = self.chars.get(self.pos) else {
+                        return Err(syntax_error("unterminated string", self.pos));
+                    };
+                    self.pos += 1;
+                    let decoded = match escape {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\x08',
+                        'f' => '\x0C',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            // Kept as UTF-16 so escaped surrogate pairs join up.
+                            units.push(self.hex4()?);
+                            continue;
+                        }
+                        _ => return Err(syntax_error("bad escaped character", self.pos - 1)),
+                    };
+                    units.extend(decoded.encode_utf16(&mut [0; 2]).iter());
+                }
+                c if (c as u32) < 0x20 => return Err(syntax_error("bad control character in string", self.pos - 1)),
+                c => units.extend(c.encode_utf16(&mut [0; 2]).iter()),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<JsValue, JsError> {
+        let start = self.pos;
+        let digits = |p: &mut Parser| {
+            let from = p.pos;
+            while p.chars.get(p.pos).is_some_and(|c| c.is_ascii_digit()) {
+                p.pos += 1;
+            }
+            p.pos - from
+        };
+        if self.chars.get(self.pos) == Some(&'-') {
+            self.pos += 1;
+        }
+        match self.chars.get(self.pos) {
+            Some('0') => self.pos += 1,
+            Some(c) if c.is_ascii_digit() => { digits(self); }
+            _ => return Err(syntax_error("missing digits", self.pos)),
+        }
+        if self.chars.get(self.pos) == Some(&'.') {
+            self.pos += 1;
+            if digits(self) == 0 {
+                return Err(syntax_error("missing digits after decimal point", self.pos));
+            }
+        }
+        if matches!(self.chars.get(self.pos), Some('e' | 'E')) {
+            self.pos += 1;
+            if matches!(self.chars.get(self.pos), Some('+' | '-')) {
+                self.pos += 1;
+            }
+            if digits(self) == 0 {
+                return Err(syntax_error("missing digits after exponent", self.pos));
+            }
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        let n: f64 = text.parse().map_err(|_| syntax_error("bad number", start))?;
+        Ok(if n == n.trunc() && n.abs() <= i32::MAX as f64 && !(n == 0.0 && n.is_sign_negative()) {
+            JsValue::Int(n as i32)
+        } else {
+            JsValue::Number(n)
+        })
+    }
+}
+
+/// JSON.parse(text [, reviver]).
+pub fn parse(rt: &mut JsRuntime, args: &[JsValue]) -> Result<JsValue, JsError> {
+    let text = args.first().map(|v| v.to_string()).unwrap_or_else(|| "undefined".into());
+    let mut parser = Parser { chars: text.chars().collect(), pos: 0 };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.pos < parser.chars.len() {
+        return Err(syntax_error("unexpected non-whitespace character after JSON data", parser.pos));
+    }
+    match args.get(1) {
+        Some(reviver @ (JsValue::Function(_) | JsValue::Native(_))) => {
+            let root = new_object();
+            root.borrow_mut().set_own("", value);
+            internalize(rt, reviver, &JsValue::Object(root), "")
+        }
+        _ => Ok(value),
+    }
+}
+
+/// ES5 §15.12.2 Walk: revive children first, then the value itself.
+fn internalize(rt: &mut JsRuntime, reviver: &JsValue, holder: &JsValue, key: &str) -> Result<JsValue, JsError> {
+    let value = get_property(holder, key);
+    match &value {
+        JsValue::Array(a) => {
+            let len = a.borrow().items.len();
+            for i in 0..len {
+                let revived = internalize(rt, reviver, &value, &i.to_string())?;
+                a.borrow_mut().items[i] = revived;
+            }
+        }
+        JsValue::Object(o) => {
+            let keys: Vec<String> = o.borrow().props.iter().map(|(k, _)| k.clone()).collect();
+            for k in keys {
+                match internalize(rt, reviver, &value, &k)? {
+                    JsValue::Undefined => o.borrow_mut().props.retain(|(name, _)| *name != k),
+                    revived => o.borrow_mut().set_own(&k, revived),
+                }
+            }
+        }
+        _ => {}
+    }
+    rt.invoke(reviver, vec![JsValue::String(Rc::new(key.to_string())), value], holder.clone())
+}
+
+/// JSON's Quote(): escape quotes, backslashes and control characters.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\x08' => out.push_str("\\b"),
+            '\x0C' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+struct Stringifier {
+    replacer: Option<JsValue>,
+    property_list: Option<Vec<String>>,
+    gap: String,
+    indent: String,
+    /// Objects and arrays being serialised, to detect cycles.
+    stack: Vec<*const ()>,
+}
+
+fn is_callable(v: &JsValue) -> bool {
+    matches!(v, JsValue::Function(_) | JsValue::Native(_))
+}
+
+impl Stringifier {
+    /// ES5 §15.12.3 Str(key, holder). `None` means the value is skipped.
+    fn str(&mut self, rt: &mut JsRuntime, key: &str, holder: &JsValue) -> Result<Option<String>, JsError> {
+        let mut value = get_property(holder, key);
+        if matches!(value, JsValue::Object(_) | JsValue::Array(_)) {
+            let to_json = get_property(&value, "toJSON");
+            if is_callable(&to_json) {
+                value = rt.invoke(&to_json, vec![JsValue::String(Rc::new(key.to_string()))], value)?;
+            }
+        }
+        if let Some(replacer) = self.replacer.clone() {
+            let args = vec![JsValue::String(Rc::new(key.to_string())), value];
+            value = rt.invoke(&replacer, args, holder.clone())?;
+        }
+        Ok(match &value {
+            JsValue::Null => Some("null".into()),
+            JsValue::Bool(b) => Some(b.to_string()),
+            JsValue::String(s) => Some(quote(s)),
+            JsValue::Int(_) => Some(value.to_string()),
+            JsValue::Number(n) if n.is_finite() => Some(value.to_string()),
+            JsValue::Number(_) => Some("null".into()),
+            JsValue::Object(o) if o.borrow().regexp.is_some() => Some("{}".into()),
+            JsValue::Object(o) => Some(self.object(rt, o, &value)?),
+            JsValue::Array(a) => Some(self.array(rt, a, &value)?),
+            JsValue::Undefined | JsValue::Function(_) | JsValue::Native(_) | JsValue::Iterator(_)
+            | JsValue::DirectorRef(_) => None,
+        })
+    }
+
+    fn enter(&mut self, ptr: *const ()) -> Result<String, JsError> {
+        if self.stack.contains(&ptr) {
+            return Err(JsError::new("TypeError: JSON.stringify: cyclic object value"));
+        }
+        if self.stack.len() >= MAX_DEPTH {
+            return Err(JsError::new("InternalError: JSON.stringify: too much recursion"));
+        }
+        self.stack.push(ptr);
+        let stepback = self.indent.clone();
+        self.indent.push_str(&self.gap);
+        Ok(stepback)
+    }
+
+    fn leave(&mut self, stepback: String) {
+        self.stack.pop();
+        self.indent = stepback;
+    }
+
+    /// Join serialised members with the current gap and indentation.
+    fn wrap(&self, open: char, parts: Vec<String>, close: char, stepback: &str) -> String {
+        if parts.is_empty() {
+            format!("{}{}", open, close)
+        } else if self.gap.is_empty() {
+            format!("{}{}{}", open, parts.join(","), close)
+        } else {
+            let separator = format!(",\n{}", self.indent);
+            format!("{}\n{}{}\n{}{}", open, self.indent, parts.join(&separator), stepback, close)
+        }
+    }
+
+    fn object(&mut self, rt: &mut JsRuntime, o: &JsObjectRef, value: &JsValue) -> Result<String, JsError> {
+        let stepback = self.enter(Rc::as_ptr(o) as *const ())?;
+        let keys = match &self.property_list {
+            Some(list) => list.clone(),
+            None => o.borrow().props.iter().map(|(k, _)| k.clone()).collect(),
+        };
+        let colon = if self.gap.is_empty() { ":" } else { ": " };
+        let mut parts = Vec::new();
+        for key in keys {
+            if let Some(s) = self.str(rt, &key, value)? {
+                parts.push(format!("{}{}{}", quote(&key), colon, s));
+            }
+        }
+        let out = self.wrap('{', parts, '}', &stepback);
+        self.leave(stepback);
+        Ok(out)
+    }
+
+    fn array(&mut self, rt: &mut JsRuntime, a: &super::value::JsArrayRef, value: &JsValue) -> Result<String, JsError> {
+        let stepback = self.enter(Rc::as_ptr(a) as *const ())?;
+        let len = a.borrow().items.len();
+        let mut parts = Vec::with_capacity(len);
+        for i in 0..len {
+            parts.push(self.str(rt, &i.to_string(), value)?.unwrap_or_else(|| "null".into()));
+        }
+        let out = self.wrap('[', parts, ']', &stepback);
+        self.leave(stepback);
+        Ok(out)
+    }
+}
+
+/// JSON.stringify(value [, replacer [, space]]).
+pub fn stringify(rt: &mut JsRuntime, args: &[JsValue]) -> Result<JsValue, JsError> {
+    let (replacer, property_list) = match args.get(1) {
+        Some(f) if is_callable(f) => (Some(f.clone()), None),
+        Some(JsValue::Array(list)) => {
+            let mut names: Vec<String> = Vec::new();
+            for item in list.borrow().items.iter() {
+                if matches!(item, JsValue::String(_) | JsValue::Int(_) | JsValue::Number(_)) {
+                    let name = item.to_string();
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+            }
+            (None, Some(names))
+        }
+        _ => (None, None),
+    };
+    let gap = match args.get(2) {
+        Some(v @ (JsValue::Int(_) | JsValue::Number(_))) => " ".repeat(v.to_int32().clamp(0, 10) as usize),
+        Some(JsValue::String(s)) => s.chars().take(10).collect(),
+        _ => String::new(),
+    };
+    let mut stringifier = Stringifier { replacer, property_list, gap, indent: String::new(), stack: Vec::new() };
+    let wrapper = new_object();
+    wrapper.borrow_mut().set_own("", args.first().cloned().unwrap_or(JsValue::Undefined));
+    Ok(match stringifier.str(rt, "", &JsValue::Object(wrapper))? {
+        Some(s) => JsValue::String(Rc::new(s)),
+        None => JsValue::Undefined,
+    })
+}
@@ -20,7 +20,9 @@ pub mod decompiler;
 pub mod disasm;
 pub mod host_bridge;
 pub mod interpreter;
+pub mod json;
 pub mod opcodes;
+pub mod regexp;
 pub mod value;
 pub mod variable_length;
 pub mod xdr;
@@ -0,0 +1,390 @@
+// ECMA-262 3rd edition RegExp for JsRuntime.
+//
+// Patterns compile with the player's backtracking engine (player/regex.rs,
+// shared with the PRegEx Xtra) in its ECMA mode. A RegExp is a JsObject with
+// class_name "RegExp" whose `regexp` slot holds the compiled pattern;
+// `source`, `global`, `ignoreCase`, `multiline` and `lastIndex` are plain own
+// properties like in SpiderMonkey 1.5, so scripts can read and reset
+// `lastIndex` between `exec` calls.
+//
+// exec / test / toString are dispatched the same way as the String and
+// Array methods: the interpreter's get_property asks `regexp_method` for a
+// bound Native. The String.prototype methods that accept a RegExp (match,
+// replace, search, split) live here too.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::interpreter::JsRuntime;
+use super::value::{JsArray, JsError, JsObject, JsObjectRef, JsValue, NativeCall, NativeFn};
+use crate::player::regex::{Match, Regex, RegexFlags};
+
+/// JSREG_* bits of the regexp objects XDR'd into compiled scripts
+/// (jsdmx/src/jsregexp.h).
+const JSREG_FOLD: u32 = 0x01;
+const JSREG_GLOB: u32 = 0x02;
+const JSREG_MULTILINE: u32 = 0x04;
+
+/// Flag letters for XDR'd JSREG_* bits, in source order ("gim").
+pub fn xdr_flag_letters(flags: u32) -> String {
+    [(JSREG_GLOB, 'g'), (JSREG_FOLD, 'i'), (JSREG_MULTILINE, 'm')]
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, letter)| *letter)
+        .collect()
+}
+
+pub struct JsRegExp {
+    pub source: String,
+    pub global: bool,
+    pub ignore_case: bool,
+    pub multiline: bool,
+    pub regex: Regex,
+}
+
+impl JsRegExp {
+    /// Compile `source` with flag letters as written after a literal, e.g. "gi".
+    pub fn new(source: &str, flags: &str) -> Result<JsRegExp, JsError> {
+        let (mut global, mut ignore_case, mut multiline) = (false, false, false);
+        for c in flags.chars() {
+            let flag = match c {
+                'g' => &mut global,
+                'i' => &mut ignore_case,
+                'm' => &mut multiline,
+                _ => return Err(JsError::new(format!("SyntaxError: invalid regular expression flag {}", c))),
+            };
+            if *flag {
+                return Err(JsError::new(format!("SyntaxError: repeated regular expression flag {}", c)));
+            }
+            *flag = true;
+        }
+        Self::compile(source, global, ignore_case, multiline)
+    }
+
+    /// Compile a regexp literal decoded from script XDR.
+    pub fn from_xdr_flags(source: &str, flags: u32) -> Result<JsRegExp, JsError> {
+        Self::compile(
+            source,
+            flags & JSREG_GLOB != 0,
+            flags & JSREG_FOLD != 0,
+            flags & JSREG_MULTILINE != 0,
+        )
+    }
+
+    fn compile(source: &str, global: bool, ignore_case: bool, multiline: bool) -> Result<JsRegExp, JsError> {
+        let flags = RegexFlags { ignore_case, multiline, ecma: true, ..RegexFlags::default() };
+        let regex = Regex::new(source, flags).map_err(|e| {
+            JsError::new(format!("SyntaxError: invalid regular expression /{}/: {}", source, e))
+        })?;
+        Ok(JsRegExp { source: source.to_string(), global, ignore_case, multiline, regex })
+    }
+
+    pub fn flags(&self) -> String {
+        let mut flags = String::new();
+        if self.global { flags.push('g'); }
+        if self.ignore_case { flags.push('i'); }
+        if self.multiline { flags.push('m'); }
+        flags
+    }
+}
+
+impl fmt::Display for JsRegExp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "/{}/{}", self.source, self.flags())
+    }
+}
+
+/// Wrap a compiled pattern in a fresh RegExp object with `lastIndex` 0.
+pub fn new_regexp_object(re: JsRegExp) -> JsValue {
+    let mut o = JsObject::new();
+    o.class_name = "RegExp";
+    o.set_own("source", JsValue::String(Rc::new(re.source.clone())));
+    o.set_own("global", JsValue::Bool(re.global));
+    o.set_own("ignoreCase", JsValue::Bool(re.ignore_case));
+    o.set_own("multiline", JsValue::Bool(re.multiline));
+    o.set_own("lastIndex", JsValue::Int(0));
+    o.regexp = Some(Rc::new(re));
+    JsValue::Object(Rc::new(RefCell::new(o)))
+}
+
+/// The object and compiled pattern of a RegExp value.
+pub fn as_regexp(v: &JsValue) -> Option<(JsObjectRef, Rc<JsRegExp>)> {
+    match v {
+        JsValue::Object(o) => {
+            let re = o.borrow().regexp.clone()?;
+            Some((o.clone(), re))
+        }
+        _ => None,
+    }
+}
+
+/// `RegExp(pattern, flags)`, called or constructed.
+pub fn construct(args: &[JsValue]) -> Result<JsValue, JsError> {
+    let flags = match args.get(1) {
+        None | Some(JsValue::Undefined) => None,
+        Some(v) => Some(v.to_string()),
+    };
+    if let Some((_, re)) = args.first().and_then(as_regexp) {
+        if flags.is_some() {
+            return Err(JsError::new("TypeError: can't supply flags when constructing one RegExp from another"));
+        }
+        return Ok(new_regexp_object(JsRegExp::new(&re.source, &re.flags())?));
+    }
+    let source = match args.first() {
+        None | Some(JsValue::Undefined) => String::new(),
+        Some(v) => v.to_string(),
+    };
+    Ok(new_regexp_object(JsRegExp::new(&source, flags.as_deref().unwrap_or(""))?))
+}
+
+/// ECMA-262 §15.10.6.2 step 5: ToInteger(lastIndex).
+fn last_index(obj: &JsObjectRef) -> f64 {
+    let n = obj.borrow().get_own("lastIndex").map(|v| v.to_number()).unwrap_or(0.0);
+    if n.is_nan() { 0.0 } else { n.trunc() }
+}
+
+fn set_last_index(obj: &JsObjectRef, index: usize) {
+    obj.borrow_mut().set_own("lastIndex", JsValue::Int(index as i32));
+}
+
+fn string_value(s: String) -> JsValue {
+    JsValue::String(Rc::new(s))
+}
+
+fn group_value(m: &Match, chars: &[char], index: usize) -> JsValue {
+    m.group_text(chars, index).map(string_value).unwrap_or(JsValue::Undefined)
+}
+
+/// The array `exec` and non-global `match` return: the match, its
+/// captures, and `index` / `input` properties.
+fn match_array(m: &Match, chars: &[char], input: &Rc<String>) -> JsValue {
+    let mut arr = JsArray::from_items((0..m.groups.len()).map(|i| group_value(m, chars, i)).collect());
+    arr.props.push(("index".into(), JsValue::Int(m.start() as i32)));
+    arr.props.push(("input".into(), JsValue::String(input.clone())));
+    JsValue::Array(Rc::new(RefCell::new(arr)))
+}
+
+/// A match that ran out of steps, reported like SpiderMonkey's
+/// "regular expression too complex".
+fn match_error(e: String) -> JsError {
+    JsError::new(format!("InternalError: {}", e))
+}
+
+/// RegExp.prototype.exec (ECMA-262 §15.10.6.2).
+fn exec(obj: &JsObjectRef, re: &JsRegExp, input: &Rc<String>) -> Result<JsValue, JsError> {
+    let chars: Vec<char> = input.chars().collect();
+    let start = if re.global { last_index(obj) } else { 0.0 };
+    let found = if start < 0.0 || start > chars.len() as f64 {
+        None
+    } else {
+        re.regex.find_at(&chars, start as usize).map_err(match_error)?
+    };
+    Ok(match found {
+        Some(m) => {
+            if re.global {
+                set_last_index(obj, m.end());
+            }
+            match_array(&m, &chars, input)
+        }
+        None => {
+            set_last_index(obj, 0);
+            JsValue::Null
+        }
+    })
+}
+
+/// Return a bound Native for a RegExp.prototype method, or None if unknown.
+pub fn regexp_method(obj: JsObjectRef, re: Rc<JsRegExp>, name: &str) -> Option<JsValue> {
+    macro_rules! bind {
+        ($n:expr, $f:expr) => {
+            Some(JsValue::Native(Rc::new(NativeFn { name: $n, call: NativeCall::Plain(Box::new($f)) })))
+        };
+    }
+    let input = |args: &[JsValue]| Rc::new(args.first().map(|v| v.to_string()).unwrap_or_else(|| "undefined".into()));
+    match name {
+        "exec" => bind!("exec", move |args| exec(&obj, &re, &input(args))),
+        "test" => bind!("test", move |args| {
+            Ok(JsValue::Bool(!matches!(exec(&obj, &re, &input(args))?, JsValue::Null)))
+        }),
+        "toString" => bind!("toString", move |_| Ok(string_value(re.to_string()))),
+        _ => None,
+    }
+}
+
+/// ToRegExp for the String methods: RegExp values as they are, anything
+/// else compiled as a pattern (`undefined` being the empty pattern).
+fn to_regexp(v: Option<&JsValue>) -> Result<(Option<JsObjectRef>, Rc<JsRegExp>), JsError> {
+    match v {
+        Some(v) => match as_regexp(v) {
+            Some((obj, re)) => Ok((Some(obj), re)),
+            None if matches!(v, JsValue::Undefined) => Ok((None, Rc::new(JsRegExp::new("", "")?))),
+            None => Ok((None, Rc::new(JsRegExp::new(&v.to_string(), "")?))),
+        },
+        None => Ok((None, Rc::new(JsRegExp::new("", "")?))),
+    }
+}
+
+/// String.prototype.match (ECMA-262 §15.5.4.10).
+pub fn string_match(s: &Rc<String>, args: &[JsValue]) -> Result<JsValue, JsError> {
+    let (obj, re) = to_regexp(args.first())?;
+    let chars: Vec<char> = s.chars().collect();
+    if !re.global {
+        return Ok(re.regex.find_at(&chars, 0).map_err(match_error)?.map_or(JsValue::Null, |m| match_array(&m, &chars, s)));
+    }
+    if let Some(obj) = &obj {
+        set_last_index(obj, 0);
+    }
+    let matches = re.regex.find_all(&chars, 0).map_err(match_error)?;
+    if matches.is_empty() {
+        return Ok(JsValue::Null);
+    }
+    let items = matches.iter().map(|m| group_value(m, &chars, 0)).collect();
+    Ok(JsValue::Array(Rc::new(RefCell::new(JsArray::from_items(items)))))
+}
+
+/// String.prototype.search (ECMA-262 §15.5.4.12). Ignores `global` and
+/// `lastIndex`.
+pub fn string_search(s: &str, args: &[JsValue]) -> Result<JsValue, JsError> {
+    let (_, re) = to_regexp(args.first())?;
+    let chars: Vec<char> = s.chars().collect();
+    Ok(JsValue::Int(re.regex.find_at(&chars, 0).map_err(match_error)?.map_or(-1, |m| m.start() as i32)))
+}
+
+/// Expand `$$`, `$&`, `` $` ``, `$'` and `$n` / `$nn` in a replacement
+/// string (ECMA-262 §15.5.4.11, table 22). References to groups the pattern
+/// doesn't have stay literal.
+fn expand_replacement(template: &str, chars: &[char], m: &Match) -> String {
+    let t: Vec<char> = template.chars().collect();
+    let group_count = m.groups.len() - 1;
+    let mut out = String::new();
+    let mut i = 0;
+    while i < t.len() {
+        if t[i] != '$' || i + 1 == t.len() {
+            out.push(t[i]);
+            i += 1;
+            continue;
+        }
+        match t[i + 1] {
+            '$' => { out.push('$'); i += 2; }
+            '&' => { out.extend(&chars[m.start()..m.end()]); i += 2; }
+            '`' => { out.extend(&chars[..m.start()]); i += 2; }
+            '\'' => { out.extend(&chars[m.end()..]); i += 2; }
+            d if d.is_ascii_digit() => {
+                let one = d.to_digit(10).unwrap() as usize;
+                let two = t.get(i + 2).and_then(|c| c.to_digit(10)).map(|d2| one * 10 + d2 as usize);
+                let (n, len) = match two {
+                    Some(n) if (1..=group_count).contains(&n) => (n, 3),
+                    _ => (one, 2),
+                };
+                if (1..=group_count).contains(&n) {
+                    out.push_str(&m.group_text(chars, n).unwrap_or_default());
+                    i += len;
+                } else {
+                    out.push('$');
+                    i += 1;
+                }
+            }
+            _ => { out.push('$'); i += 1; }
+        }
+    }
+    out
+}
+
+/// String.prototype.replace (ECMA-262 §15.5.4.11), with a RegExp or a
+/// string to search for and a replacement string or function.
+pub fn string_replace(rt: &mut JsRuntime, s: &Rc<String>, args: &[JsValue]) -> Result<JsValue, JsError> {
+    let chars: Vec<char> = s.chars().collect();
+    let search = args.first().cloned().unwrap_or(JsValue::Undefined);
+    let matches = match as_regexp(&search) {
+        Some((obj, re)) if re.global => {
+            set_last_index(&obj, 0);
+            re.regex.find_all(&chars, 0).map_err(match_error)?
+        }
+        Some((_, re)) => re.regex.find_at(&chars, 0).map_err(match_error)?.into_iter().collect(),
+        None => {
+            let needle: Vec<char> = search.to_string().chars().collect();
+            (0..=chars.len().saturating_sub(needle.len()))
+                .find(|&i| chars[i..].starts_with(&needle))
+                .map(|i| Match { groups: vec![Some((i, i + needle.len()))] })
+                .into_iter()
+                .collect()
+        }
+    };
+    let replacement = args.get(1).cloned().unwrap_or(JsValue::Undefined);
+    let mut out = String::new();
+    let mut last = 0;
+    for m in &matches {
+        out.extend(&chars[last..m.start()]);
+        match &replacement {
+            JsValue::Function(_) | JsValue::Native(_) => {
+                let mut call_args: Vec<JsValue> = (0..m.groups.len()).map(|i| group_value(m, &chars, i)).collect();
+                call_args.push(JsValue::Int(m.start() as i32));
+                call_args.push(JsValue::String(s.clone()));
+                out.push_str(&rt.invoke(&replacement, call_args, JsValue::Undefined)?.to_string());
+            }
+            other => out.push_str(&expand_replacement(&other.to_string(), &chars, m)),
+        }
+        last = m.end();
+    }
+    out.extend(&chars[last..]);
+    Ok(string_value(out))
+}
+
+/// String.prototype.split (ECMA-262 §15.5.4.14), with a string or RegExp
+/// separator. Captures of a RegExp separator are spliced into the result.
+pub fn string_split(s: &Rc<String>, args: &[JsValue]) -> Result<JsValue, JsError> {
+    let limit = match args.get(1) {
+        None | Some(JsValue::Undefined) => u32::MAX,
+        Some(v) => v.to_int32() as u32,
+    };
+    let chars: Vec<char> = s.chars().collect();
+    let mut out: Vec<JsValue> = Vec::new();
+    let done = |out: Vec<JsValue>| Ok(JsValue::Array(Rc::new(RefCell::new(JsArray::from_items(out)))));
+    if limit == 0 {
+        return done(out);
+    }
+    let separator = match args.first() {
+        None | Some(JsValue::Undefined) => return done(vec![JsValue::String(s.clone())]),
+        Some(v) => v,
+    };
+    let regexp = as_regexp(separator).map(|(_, re)| re);
+    let needle: Vec<char> = separator.to_string().chars().collect();
+    // SplitMatch: the end of a separator match anchored at `q`, plus its captures.
+    let split_match = |q: usize| -> Result<Option<(usize, Vec<JsValue>)>, JsError> {
+        Ok(match &regexp {
+            Some(re) => re.regex.match_at(&chars, q).map_err(match_error)?.map(|m| {
+                (m.end(), (1..m.groups.len()).map(|i| group_value(&m, &chars, i)).collect())
+            }),
+            None => chars[q..].starts_with(&needle).then(|| (q + needle.len(), Vec::new())),
+        })
+    };
+    if chars.is_empty() {
+        if split_match(0)?.is_none() {
+            out.push(JsValue::String(s.clone()));
+        }
+        return done(out);
+    }
+    let (mut p, mut q) = (0, 0);
+    while q < chars.len() {
+        match split_match(q)? {
+            Some((e, captures)) if e != p => {
+                out.push(string_value(chars[p..q].iter().collect()));
+                if out.len() as u32 == limit {
+                    return done(out);
+                }
+                p = e;
+                for capture in captures {
+                    out.push(capture);
+                    if out.len() as u32 == limit {
+                        return done(out);
+                    }
+                }
+                q = p;
+            }
+            _ => q += 1,
+        }
+    }
+    out.push(string_value(chars[p..].iter().collect()));
+    done(out)
+}
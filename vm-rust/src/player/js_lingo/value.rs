@@ -12,6 +12,8 @@ use std::collections::HashMap;
 use std::fmt;
 use std::rc::Rc;
 
+use super::interpreter::JsRuntime;
+use super::regexp::JsRegExp;
 use super::xdr::JsFunctionAtom;
 
 /// A JavaScript runtime value. Compact and `Clone` — interior mutability
@@ -61,6 +63,8 @@ pub type JsObjectRef = Rc<RefCell<JsObject>>;
 pub type JsArrayRef = Rc<RefCell<JsArray>>;
 pub type JsFunctionRef = Rc<JsFunction>;
 pub type JsIteratorRef = Rc<RefCell<JsIterator>>;
+pub type PlainNative = Box<dyn Fn(&[JsValue]) -> Result<JsValue, JsError> + 'static>;
+pub type RuntimeNative = Box<dyn Fn(&mut JsRuntime, &[JsValue]) -> Result<JsValue, JsError> + 'static>;
 
 /// Snapshot-style for-in iterator: captures the target's keys at first
 /// iteration and walks the cursor through them. Object iteration yields
@@ -109,11 +113,13 @@ pub struct JsObject {
     pub props: Vec<(String, JsValue)>,
     pub proto: Option<JsObjectRef>,
     pub class_name: &'static str,
+    /// Compiled pattern of a `RegExp` object (class_name "RegExp").
+    pub regexp: Option<Rc<JsRegExp>>,
 }
 
 impl JsObject {
     pub fn new() -> Self {
-        JsObject { props: Vec::new(), proto: None, class_name: "Object" }
+        JsObject { props: Vec::new(), proto: None, class_name: "Object", regexp: None }
     }
 
     pub fn get_own(&self, key: &str) -> Option<&JsValue> {
@@ -138,11 +144,18 @@ impl JsObject {
 #[derive(Default)]
 pub struct JsArray {
     pub items: Vec<JsValue>,
+    /// Non-index properties, e.g. `index` and `input` on the arrays
+    /// `RegExp.prototype.exec` returns.
+    pub props: Vec<(String, JsValue)>,
 }
 
 impl JsArray {
     pub fn new() -> Self {
-        JsArray { items: Vec::new() }
+        JsArray { items: Vec::new(), props: Vec::new() }
+    }
+
+    pub fn from_items(items: Vec<JsValue>) -> Self {
+        JsArray { items, props: Vec::new() }
     }
 }
 
@@ -161,7 +174,15 @@ pub struct JsFunction {
 
 pub struct NativeFn {
     pub name: &'static str,
-    pub call: Box<dyn Fn(&[JsValue]) -> Result<JsValue, JsError> + 'static>,
+    pub call: NativeCall,
+}
+
+pub enum NativeCall {
+    Plain(PlainNative),
+    /// Natives that call back into script -- `replace` with a replacement
+    /// function, JSON revivers and replacers -- get the runtime to invoke
+    /// the callback with.
+    WithRuntime(RuntimeNative),
 }
 
 #[derive(Debug)]
@@ -255,7 +276,10 @@ impl JsValue {
                 else { format!("{}", n) }
             }
             JsValue::String(s) => (**s).clone(),
-            JsValue::Object(_) => "[object Object]".into(),
+            JsValue::Object(o) => match &o.borrow().regexp {
+                Some(re) => re.to_string(),
+                None => "[object Object]".into(),
+            },
             JsValue::Array(a) => {
                 a.borrow().items.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")
             }
@@ -40,7 +40,10 @@ pub enum JsAtom {
     /// Nested JSFunction (declared by `function name() {...}` or expressed
     /// as `function() {...}`). The inner script is fully decoded.
     Function(Box<JsFunctionAtom>),
-    /// Other object atoms (regex objects, object literals); not yet handled.
+    /// Regular expression literal (`/source/flags`). `flags` holds the
+    /// JSREG_* bits from jsdmx/src/jsregexp.h.
+    RegExp { source: String, flags: u32 },
+    /// Other object atoms (object literals); not yet handled.
     Unsupported(u32),
 }
 
@@ -123,11 +126,14 @@ impl std::error::Error for JsXdrError {}
 struct XdrReader<'a> {
     buf: &'a [u8],
     pos: usize,
+    /// Class names registered by object atoms so far, in registration
+    /// order (JS_XDRRegisterClass); class id N names `classes[N - 1]`.
+    classes: Vec<String>,
 }
 
 impl<'a> XdrReader<'a> {
     fn new(buf: &'a [u8]) -> Self {
-        Self { buf, pos: 0 }
+        Self { buf, pos: 0, classes: Vec::new() }
     }
 
     fn need(&self, want: usize) -> Result<(), JsXdrError> {
@@ -334,18 +340,33 @@ fn decode_object_atom(r: &mut XdrReader<'_>) -> Result<JsAtom, JsXdrError> {
     } else {
         None
     };
-    let _class_id = r.u32()?;
-
-    // The only class we currently know how to decode is "Function". Anything
-    // else we mark as unsupported and fail loud so we don't desync atom maps.
-    let is_function = match &class_name {
-        Some(n) => n == "Function",
-        None => true, // class was already registered earlier; assume Function
+    let class_id = r.u32()? as usize;
+    let class_name = match class_name {
+        Some(name) => {
+            r.classes.push(name.clone());
+            name
+        }
+        // Already registered; ids we never saw are assumed to be Function.
+        None => class_id
+            .checked_sub(1)
+            .and_then(|index| r.classes.get(index).cloned())
+            .unwrap_or_else(|| "Function".to_string()),
     };
-    if !is_function {
-        return Err(JsXdrError::BadAtomTag(0xFEED_DEAD));
+
+    // Anything but Function and RegExp fails loud so we don't desync atom maps.
+    match class_name.as_str() {
+        "Function" => decode_function_object(r),
+        "RegExp" => decode_regexp_object(r),
+        _ => Err(JsXdrError::BadAtomTag(0xFEED_DEAD)),
     }
-    decode_function_object(r)
+}
+
+/// Decode a regexp literal. Cross-reference: jsregexp.c::regexp_xdrObject.
+fn decode_regexp_object(r: &mut XdrReader<'_>) -> Result<JsAtom, JsXdrError> {
+    let source = r.js_string()?;
+    // JS_XDRUint8 is 4 bytes wide like the other small ints.
+    let flags = r.u32()? & 0xFF;
+    Ok(JsAtom::RegExp { source, flags })
 }
 
 /// Decode JSFunction body. Cross-reference: jsfun.c::fun_xdrObject.
@@ -288,7 +288,7 @@ pub fn datum_ref_to_js_value(player: &mut crate::player::DirPlayer, dref: &Datum
         Datum::Void | Datum::Null => JsValue::Undefined,
         Datum::List(_, items, _) => {
             let arr: Vec<JsValue> = items.iter().map(|r| datum_ref_to_js_value(player, r)).collect();
-            JsValue::Array(Rc::new(RefCell::new(crate::player::js_lingo::value::JsArray { items: arr })))
+            JsValue::Array(Rc::new(RefCell::new(crate::player::js_lingo::value::JsArray::from_items(arr))))
         }
         Datum::PropList(pairs, _) => {
             let mut obj = crate::player::js_lingo::value::JsObject::new();
@@ -327,7 +327,7 @@ pub fn datum_ref_to_js_value(player: &mut crate::player::DirPlayer, dref: &Datum
 /// `script("X").method(args)` and have it dispatch back into the right
 /// runtime via try_invoke_js_handler.
 fn script_ref_to_js_proxy(member_ref: CastMemberRef) -> JsValue {
-    use crate::player::js_lingo::value::{JsObject, NativeFn};
+    use crate::player::js_lingo::value::{JsObject, NativeCall, NativeFn};
     let mut obj = JsObject::new();
     obj.class_name = "ScriptRef";
     // Mirror the script-ref coordinates so the proxy round-trips back to a
@@ -357,7 +357,7 @@ fn script_ref_to_js_proxy(member_ref: CastMemberRef) -> JsValue {
             let name_owned = name.clone();
             let native = NativeFn {
                 name: "<script_method>",
-                call: Box::new(move |args| invoke_script_method(&ref_clone, &name_owned, args)),
+                call: NativeCall::Plain(Box::new(move |args| invoke_script_method(&ref_clone, &name_owned, args))),
             };
             obj.set_own(&name, JsValue::Native(Rc::new(native)));
         }
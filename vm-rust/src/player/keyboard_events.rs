@@ -599,6 +599,104 @@ pub(crate) fn apply_text_insertion(
     *sel_anchor = *sel_start;
 }
 
+/// IME composition started — record the byte offset where the provisional
+/// composition will begin (= current caret position, with any selection
+/// already replaced). No-op if no editable member has focus.
+pub fn ime_composition_start(player: &mut DirPlayer) {
+    if player.keyboard_focus_sprite < 0 { return; }
+    let sprite_id = player.keyboard_focus_sprite;
+    let sprite = player.movie.score.get_sprite(sprite_id);
+    let Some(member_ref) = sprite.and_then(|s| s.member.clone()) else { return };
+    let Some(member) = player.movie.cast_manager.find_mut_member_by_ref(&member_ref) else {
+        return;
+    };
+    let (text, sel_start, sel_end, sel_anchor) = match &mut member.member_type {
+        CastMemberType::Field(f) if f.editable => (
+            &mut f.text, &mut f.sel_start, &mut f.sel_end, &mut f.sel_anchor,
+        ),
+        CastMemberType::Text(t) if t.info.as_ref().is_some_and(|i| i.editable) => (
+            &mut t.text, &mut t.sel_start, &mut t.sel_end, &mut t.sel_anchor,
+        ),
+        _ => return,
+    };
+    // Collapse any existing selection so the composition replaces it cleanly.
+    if *sel_start != *sel_end {
+        apply_text_insertion(text, sel_start, sel_end, sel_anchor, "");
+    }
+    let pos = (*sel_start).max(0);
+    player.ime_composition = Some((pos, pos));
+    player.text_selection_start = pos.max(0) as u16;
+    player.text_selection_end = pos.max(0) as u16;
+}
+
+/// IME composition update — replace the current provisional run with `text`.
+/// Caret advances to the end of the new provisional text. No-op if no
+/// composition is active.
+pub fn ime_composition_update(player: &mut DirPlayer, text: &str) {
+    let Some((start, end)) = player.ime_composition else { return };
+    if player.keyboard_focus_sprite < 0 { return; }
+    let sprite_id = player.keyboard_focus_sprite;
+    let sprite = player.movie.score.get_sprite(sprite_id);
+    let Some(member_ref) = sprite.and_then(|s| s.member.clone()) else { return };
+    let Some(member) = player.movie.cast_manager.find_mut_member_by_ref(&member_ref) else {
+        return;
+    };
+    let (text_buf, sel_start, sel_end, sel_anchor) = match &mut member.member_type {
+        CastMemberType::Field(f) if f.editable => (
+            &mut f.text, &mut f.sel_start, &mut f.sel_end, &mut f.sel_anchor,
+        ),
+        CastMemberType::Text(t) if t.info.as_ref().is_some_and(|i| i.editable) => (
+            &mut t.text, &mut t.sel_start, &mut t.sel_end, &mut t.sel_anchor,
+        ),
+        _ => return,
+    };
+    let len = text_buf.len() as i32;
+    let lo = start.clamp(0, len) as usize;
+    let hi = end.clamp(0, len) as usize;
+    let hi = hi.max(lo);
+    text_buf.replace_range(lo..hi, text);
+    let new_end = lo as i32 + text.len() as i32;
+    *sel_start = new_end;
+    *sel_end = new_end;
+    *sel_anchor = new_end;
+    player.ime_composition = Some((start, new_end));
+    player.text_selection_start = new_end.max(0) as u16;
+    player.text_selection_end = new_end.max(0) as u16;
+}
+
+/// IME composition committed — `text` is the final string. Same replacement
+/// as update, then clears composition state. No-op if no composition is active.
+pub fn ime_composition_end(player: &mut DirPlayer, text: &str) {
+    ime_composition_update(player, text);
+    player.ime_composition = None;
+}
+
+/// Insert text at the focused editable member's caret/selection. Used by
+/// paste and IME commit.
+pub fn paste_text(player: &mut DirPlayer, text: &str) {
+    if player.keyboard_focus_sprite < 0 { return; }
+    let sprite_id = player.keyboard_focus_sprite;
+    let sprite = player.movie.score.get_sprite(sprite_id);
+    let Some(member_ref) = sprite.and_then(|s| s.member.clone()) else { return };
+    let Some(member) = player.movie.cast_manager.find_mut_member_by_ref(&member_ref) else {
+        return;
+    };
+    let (target, sel_start, sel_end, sel_anchor) = match &mut member.member_type {
+        CastMemberType::Field(f) if f.editable => (
+            &mut f.text, &mut f.sel_start, &mut f.sel_end, &mut f.sel_anchor,
+        ),
+        CastMemberType::Text(t) if t.info.as_ref().is_some_and(|i| i.editable) => (
+            &mut t.text, &mut t.sel_start, &mut t.sel_end, &mut t.sel_anchor,
+        ),
+        _ => return,
+    };
+    apply_text_insertion(target, sel_start, sel_end, sel_anchor, text);
+    let s = *sel_start;
+    let e = *sel_end;
+    player.text_selection_start = s.max(0) as u16;
+    player.text_selection_end = e.max(0) as u16;
+}
+
 pub async fn player_key_down(key: String, code: u16) -> Result<DatumRef, ScriptError> {
     if !player_is_playing().await {
         return Ok(DatumRef::Void);
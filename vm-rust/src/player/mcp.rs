@@ -15,8 +15,11 @@ use crate::{director::{
 use super::{
     allocator::{DatumAllocatorTrait, ScriptInstanceAllocatorTrait},
     cast_lib::{CastLib, CastMemberRef},
+    cycle_collector::{collect_cycles, CycleCollectorStats},
     datum_ref::DatumId,
+    debug::watch::WatchAction,
     script::Script,
+    tempo_wait::PendingTempoWait,
     DirPlayer,
 };
 
@@ -143,6 +146,8 @@ pub struct McpExecutionState {
     pub movie_title: String,
     pub stage_width: u32,
     pub stage_height: u32,
+    /// Tempo channel wait currently holding the playhead, if any.
+    pub pending_wait: Option<PendingTempoWait>,
 }
 
 #[derive(Serialize)]
@@ -210,11 +215,64 @@ pub struct McpBreakpointInfo {
     pub script_name: String,
     pub handler_name: String,
     pub bytecode_index: usize,
+    pub condition: Option<String>,
+    pub hit_condition: String,
+    pub ignore_count: u32,
+    pub log_message: Option<String>,
+    pub one_shot: bool,
+    pub hit_count: u32,
+}
+
+#[derive(Serialize)]
+pub struct McpHandlerBreakpointInfo {
+    pub handler_name: String,
+    pub condition: Option<String>,
+    pub hit_condition: String,
+    pub ignore_count: u32,
+    pub log_message: Option<String>,
+    pub one_shot: bool,
+    pub hit_count: u32,
 }
 
 #[derive(Serialize)]
 pub struct McpBreakpointList {
     pub breakpoints: Vec<McpBreakpointInfo>,
+    pub handler_breakpoints: Vec<McpHandlerBreakpointInfo>,
+}
+
+#[derive(Serialize)]
+pub struct McpWatchpointInfo {
+    pub id: u32,
+    pub target: String,
+    pub action: String,
+    pub hit_count: u32,
+}
+
+#[derive(Serialize)]
+pub struct McpWatchHit {
+    pub watchpoint_id: u32,
+    pub target: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub script_name: Option<String>,
+    pub handler_name: Option<String>,
+    pub bytecode_index: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct McpWatchpointList {
+    pub watchpoints: Vec<McpWatchpointInfo>,
+    /// The change execution is paused on, if a watchpoint paused it.
+    pub paused_on: Option<McpWatchHit>,
+}
+
+#[derive(Serialize)]
+pub struct McpMemoryStats {
+    pub live_datums: usize,
+    pub live_script_instances: usize,
+    /// Frames between automatic cycle collections, 0 when they are off.
+    pub collect_interval_frames: u32,
+    pub cycle_collector: CycleCollectorStats,
 }
 
 #[derive(Serialize)]
@@ -738,14 +796,16 @@ pub fn mcp_get_execution_state(player: &DirPlayer) -> String {
             .score
             .sprite_spans
             .iter()
-            .map(|span| span.end_frame as usize)
+            .map(|span| span.end_frame)
+            .chain(player.movie.score.frame_count)
             .max()
-            .unwrap_or(0),
+            .unwrap_or(0) as usize,
         at_breakpoint: player.current_breakpoint.is_some(),
         movie_loaded: !player.movie.score.sprite_spans.is_empty(),
         movie_title: player.title.clone(),
         stage_width: player.stage_size.0,
         stage_height: player.stage_size.1,
+        pending_wait: player.tempo_wait.clone(),
     })
 }
 
@@ -936,11 +996,87 @@ pub fn mcp_list_breakpoints(player: &DirPlayer) -> String {
                 script_name: bp.script_name.clone(),
                 handler_name: bp.handler_name.clone(),
                 bytecode_index: bp.bytecode_index,
+                condition: bp.options.condition.clone(),
+                hit_condition: bp.options.hit_condition.to_string(),
+                ignore_count: bp.options.ignore_count,
+                log_message: bp.options.log_message.clone(),
+                one_shot: bp.options.one_shot,
+                hit_count: bp.hit_count,
+            })
+            .collect(),
+        handler_breakpoints: player
+            .breakpoint_manager
+            .handler_breakpoints
+            .iter()
+            .map(|bp| McpHandlerBreakpointInfo {
+                handler_name: bp.handler_name.clone(),
+                condition: bp.options.condition.clone(),
+                hit_condition: bp.options.hit_condition.to_string(),
+                ignore_count: bp.options.ignore_count,
+                log_message: bp.options.log_message.clone(),
+                one_shot: bp.options.one_shot,
+                hit_count: bp.hit_count,
+            })
+            .collect(),
+    })
+}
+
+/// List all watchpoints and the change execution is paused on, if any
+pub fn mcp_list_watchpoints(player: &DirPlayer) -> String {
+    let paused_on = player
+        .current_breakpoint
+        .as_ref()
+        .and_then(|bp| bp.watch_hit.as_ref())
+        .map(|hit| McpWatchHit {
+            watchpoint_id: hit.watchpoint_id,
+            target: hit.target.clone(),
+            old_value: hit.old_value.clone(),
+            new_value: hit.new_value.clone(),
+            script_name: hit.script_name.clone(),
+            handler_name: hit.handler_name.clone(),
+            bytecode_index: hit.bytecode_index,
+        });
+    to_json(&McpWatchpointList {
+        watchpoints: player
+            .watch_manager
+            .watchpoints
+            .iter()
+            .map(|wp| McpWatchpointInfo {
+                id: wp.id,
+                target: wp.target.describe(),
+                action: match wp.action {
+                    WatchAction::Break => "break".to_string(),
+                    WatchAction::Log => "log".to_string(),
+                },
+                hit_count: wp.hit_count,
             })
             .collect(),
+        paused_on,
     })
 }
 
+/// Report the Xtras the movie requires and whether the player provides them
+pub fn mcp_get_xtra_report(player: &DirPlayer) -> String {
+    let xtra_list = player.movie.file.as_ref().and_then(|file| file.xtra_list.as_ref());
+    to_json(&super::xtra::manager::xtra_report(xtra_list))
+}
+
+/// Report live datum and script instance counts and what the cycle
+/// collector has freed so far
+pub fn mcp_get_memory_stats(player: &DirPlayer) -> String {
+    to_json(&McpMemoryStats {
+        live_datums: player.allocator.datum_count(),
+        live_script_instances: player.allocator.script_instance_count(),
+        collect_interval_frames: player.cycle_collector.interval_frames,
+        cycle_collector: player.cycle_collector.stats.clone(),
+    })
+}
+
+/// Run the cycle collector now and report what it freed
+pub fn mcp_collect_cycles(player: &mut DirPlayer) -> String {
+    to_json(&collect_cycles(player))
+}
+
 /// Format eval result as JSON
 pub fn mcp_format_eval_result(
     player: &DirPlayer,
@@ -8,6 +8,7 @@ macro_rules! match_ci {
 }
 
 pub mod allocator;
+pub mod audio;
 pub mod bitmap;
 pub mod bytecode;
 pub mod cast_lib;
@@ -17,6 +18,7 @@ pub mod cast_member;
 pub mod commands;
 pub mod compare;
 pub mod context_vars;
+pub mod cycle_collector;
 pub mod datum_formatting;
 pub mod datum_operations;
 pub mod datum_ref;
@@ -26,6 +28,7 @@ pub mod events;
 pub mod font;
 pub mod geometry;
 pub mod handlers;
+pub mod hyperlinks;
 pub mod js_lingo;
 pub mod js_lingo_loader;
 pub mod keyboard;
@@ -33,9 +36,11 @@ pub mod keyboard_events;
 pub mod keyboard_map;
 pub mod mcp;
 pub mod movie;
+pub mod net_backend;
 pub mod net_manager;
 pub mod net_task;
 pub mod profiling;
+pub mod replay;
 pub mod scope;
 pub mod score;
 pub mod script;
@@ -43,11 +48,22 @@ pub mod script_ref;
 pub mod sprite;
 pub mod stage;
 pub mod timeout;
+pub mod transition;
 pub mod xtra;
+pub mod save;
+pub mod save_state;
 pub mod score_keyframes;
+pub mod score_recording;
 pub mod stream_status;
+pub mod tempo_wait;
+pub mod palette_effects;
+pub mod regex;
+pub mod storage;
+pub mod cue_points;
+pub mod collision3d;
 pub mod virtual_scripts;
 pub mod console;
+pub mod window;
 pub mod testing_shared;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod testing;
@@ -87,16 +103,18 @@ use score::{get_score_sprite_mut, ScoreRef};
 use script::script_get_prop_opt;
 use script_ref::ScriptInstanceRef;
 use sprite::Sprite;
+use xtra::buddyapi::{BuddyApiXtraManager, BUDDYAPI_XTRA_MANAGER_OPT};
 use xtra::fileio::{FileIoXtraManager, FILEIO_XTRA_MANAGER_OPT};
 use xtra::multiuser::{MultiuserXtraManager, MULTIUSER_XTRA_MANAGER_OPT};
 use xtra::xmlparser::{XmlParserXtraManager, XMLPARSER_XTRA_MANAGER_OPT};
+use xtra::pregex::{PRegExXtraManager, PREGEX_XTRA_MANAGER_OPT};
 use rand::SeedableRng;
 
 use crate::{
     director::{
         chunks::handler::{Bytecode, HandlerDef},
         enums::ScriptType,
-        file::{read_director_file_bytes, DirectorFile},
+        file::{get_variable_multiplier, read_director_file_bytes, DirectorFile},
         lingo::{
             constants::{get_anim2_prop_name, get_anim_prop_name},
             datum::{datum_bool, Datum, DatumType, VarRef},
@@ -115,7 +133,7 @@ use crate::{
         player_invoke_targeted_event},
     },
     rendering::with_renderer_mut,
-    utils::{get_base_url, get_elapsed_ticks},
+    utils::{get_base_url, get_elapsed_ticks, local_now},
 };
 use url::Url;
 
@@ -125,7 +143,11 @@ use self::{
     cast_lib::CastMemberRef,
     cast_manager::CastManager,
     commands::{run_command_loop, PlayerVMCommand},
-    debug::{Breakpoint, BreakpointContext, BreakpointManager, StepMode},
+    debug::{
+        player_check_breakpoint,
+        watch::{WatchHit, WatchManager},
+        Breakpoint, BreakpointContext, BreakpointLocation, BreakpointManager, StepMode,
+    },
     events::{
         player_dispatch_global_event, player_invoke_global_event,
         player_wait_available, run_event_loop, PlayerVMEvent,
@@ -134,7 +156,6 @@ use self::{
     handlers::manager::BuiltInHandlerManager,
     keyboard::KeyboardManager,
     movie::Movie,
-    net_manager::NetManagerSharedState,
     scope::ScopeRef,
     score::{get_sprite_at, Score},
     script::{Script, ScriptHandlerRef},
@@ -216,6 +237,7 @@ pub struct DirPlayer {
     pub bytecode_handler_manager: StaticBytecodeHandlerManager,
     pub breakpoint_manager: BreakpointManager,
     pub current_breakpoint: Option<BreakpointContext>,
+    pub watch_manager: WatchManager,
     pub step_mode: StepMode,
     pub step_scope_depth: u32,
     pub break_on_error: bool,
@@ -251,6 +273,18 @@ pub struct DirPlayer {
     pub last_mouse_down_time: i64,
     pub is_double_click: bool,
     pub mouse_down_sprite: i16,
+    pub right_mouse_down: bool,
+    /// Sprite that received rightMouseDown, told about the matching rightMouseUp.
+    pub right_mouse_down_sprite: i16,
+    /// `the emulateMultiButtonMouse`: treat control-click as a right click.
+    pub emulate_multi_button_mouse: bool,
+    /// Set while a control-click is being played back as a right click, so
+    /// the release is reported as rightMouseUp too.
+    pub is_emulated_right_click: bool,
+    /// Whether the page hosting the player has focus.
+    pub is_application_active: bool,
+    /// Text link pressed on mouseDown; resolved into `hyperlinkClicked` on mouseUp.
+    pub pressed_hyperlink: Option<hyperlinks::PressedHyperlink>,
     pub drag_offset: (i32, i32),
     pub trails_bitmap: Option<bitmap::bitmap::Bitmap>,
     pub click_on_sprite: i16,
@@ -263,6 +297,7 @@ pub struct DirPlayer {
     pub hovered_sprite: Option<i16>,
     pub picking_mode: bool,
     pub allocator: DatumAllocator,
+    pub cycle_collector: cycle_collector::CycleCollector,
     pub dir_cache: HashMap<Box<str>, DirectorFile>,
     pub scope_count: u32,
     pub external_params: HashMap<String, String>,
@@ -313,12 +348,18 @@ pub struct DirPlayer {
     pub debug_datum_refs: Vec<DatumRef>,
     pub eval_scope_index: Option<u32>,
     pub delay_until: Option<chrono::DateTime<chrono::Local>>,
+    /// Tempo channel wait (delay, click, sound, cue point) holding the current frame.
+    pub tempo_wait: Option<tempo_wait::PendingTempoWait>,
     /// Pending gotoNetMovie operation: (task_id, frame_destination).
     /// Overwritten by subsequent gotoNetMovie/go-to-movie calls (cancels previous).
     pub pending_goto_net_movie: Option<(u32, MovieFrameTarget)>,
     /// True while a net movie transition is in progress.
     /// Prevents the event loop from dispatching external events during the transition.
     pub is_in_transition: bool,
+    /// Score/puppet stage transitions (see `transition.rs`).
+    pub transitions: transition::TransitionManager,
+    /// Palette channel fades/cycling and `puppetPalette` (see `palette_effects.rs`).
+    pub palette_effects: palette_effects::PaletteEffectManager,
     pub actor_list_generation: u64,
     pub behavior_channel_cache_generation: u64,
     pub active_stage_filmloop_cache_generation: u64,
@@ -358,6 +399,10 @@ pub struct DirPlayer {
     /// when both are set, this label wins for `the moviePath`.
     pub movie_path_label: Option<String>,
     pub console: console::ConsoleBuffer,
+    /// Movies in a window (see `window.rs`).
+    pub windows: window::WindowManager,
+    /// Saved files and prefs of the current movie (see `storage.rs`).
+    pub storage: storage::PlayerStorage,
 }
 
 /// Target frame for a movie transition (gotoNetMovie or go movie).
@@ -377,46 +422,8 @@ impl DirPlayer {
         let now = chrono::Local::now();
 
         let mut result = DirPlayer {
-            movie: Movie {
-                rect: IntRect::from(0, 0, 0, 0),
-                cast_manager: CastManager::empty(),
-                score: Score::empty(),
-                current_frame: 1,
-                puppet_tempo: 0,
-                random_seed: None,
-                exit_lock: false,
-                dir_version: 0,
-                item_delimiter: ',',
-                alert_hook: None,
-                base_path: "".to_string(),
-                file_name: "".to_string(),
-                stage_color: (255, 255, 255),
-                stage_color_ref: ColorRef::PaletteIndex(255),
-                frame_rate: 30,
-                file: None,
-                update_lock: false,
-                mouse_down_script: None,
-                mouse_up_script: None,
-                key_down_script: None,
-                key_up_script: None,
-                timeout_script: None,
-                allow_custom_caching: false,
-                trace_script: false,
-                trace_log_file: String::new(),
-                debug_playback_enabled: false,
-                mouse_down: false,
-                click_loc: (0,0),
-                frame_script_instance: None,
-                frame_script_member: None,
-                sound_device: String::new(),
-            },
-            net_manager: NetManager {
-                base_path: None,
-                override_base_path: None,
-                tasks: HashMap::new(),
-                task_states: HashMap::new(),
-                shared_state: Arc::new(Mutex::new(NetManagerSharedState::new())),
-            },
+            movie: Movie::empty(),
+            net_manager: NetManager::new(),
             is_playing: false,
             is_script_paused: false,
             next_frame: None,
@@ -426,6 +433,7 @@ impl DirPlayer {
             bytecode_handler_manager: StaticBytecodeHandlerManager {},
             breakpoint_manager: BreakpointManager::new(),
             current_breakpoint: None,
+            watch_manager: WatchManager::default(),
             step_mode: StepMode::None,
             step_scope_depth: 0,
             break_on_error: true,
@@ -446,6 +454,12 @@ impl DirPlayer {
             last_mouse_down_time: 0,
             is_double_click: false,
             mouse_down_sprite: 0,
+            right_mouse_down: false,
+            right_mouse_down_sprite: -1,
+            emulate_multi_button_mouse: false,
+            is_emulated_right_click: false,
+            is_application_active: true,
+            pressed_hyperlink: None,
             drag_offset: (0, 0),
             trails_bitmap: None,
             subscribed_member_refs: vec![],
@@ -461,6 +475,7 @@ impl DirPlayer {
             hovered_sprite: None,
             picking_mode: false,
             allocator: DatumAllocator::default(),
+            cycle_collector: cycle_collector::CycleCollector::default(),
             dir_cache: HashMap::new(),
             scope_count: 0,
             external_params: HashMap::new(),
@@ -503,8 +518,11 @@ impl DirPlayer {
             debug_datum_refs: vec![],
             eval_scope_index: None,
             delay_until: None,
+            tempo_wait: None,
             pending_goto_net_movie: None,
             is_in_transition: false,
+            transitions: transition::TransitionManager::default(),
+            palette_effects: palette_effects::PaletteEffectManager::default(),
             actor_list_generation: 0,
             behavior_channel_cache_generation: 0,
             active_stage_filmloop_cache_generation: 0,
@@ -519,6 +537,8 @@ impl DirPlayer {
             movie_path_override: None,
             movie_path_label: None,
             console: console::ConsoleBuffer::new(),
+            windows: window::WindowManager::default(),
+            storage: storage::PlayerStorage::default(),
             rng: rand::rngs::SmallRng::seed_from_u64(0),
         };
 
@@ -562,6 +582,13 @@ impl DirPlayer {
     }
 
     pub async fn load_movie_from_file(&mut self, path: &str) -> Result<(), String> {
+        let movie_file = self.fetch_director_file(path).await?;
+        self.load_movie_from_dir(movie_file).await;
+        Ok(())
+    }
+
+    /// Fetch and parse a movie file through the net manager.
+    pub async fn fetch_director_file(&mut self, path: &str) -> Result<DirectorFile, String> {
         let task_id = self.net_manager.preload_net_thing(path.to_owned());
         self.net_manager.await_task(task_id).await;
         let task = self.net_manager.get_task(task_id)
@@ -583,8 +610,7 @@ impl DirPlayer {
             &get_base_url(&task.resolved_url).to_string(),
         )
         .map_err(|e| format!("Failed to parse movie file '{}': {}", path, e))?;
-        self.load_movie_from_dir(movie_file).await;
-        Ok(())
+        Ok(movie_file)
     }
 
     pub(crate) async fn load_movie_from_dir(&mut self, dir: DirectorFile) {
@@ -656,6 +682,8 @@ impl DirPlayer {
 
         self.bg_color = self.movie.stage_color_ref.clone();
 
+        storage::open_movie_storage(self).await;
+
         // Load all fonts from cast members into the font manager
         log::debug!("Loading fonts from cast members...");
         self.movie
@@ -678,10 +706,23 @@ impl DirPlayer {
         // Register built-in virtual scripts
         virtual_scripts::register_virtual_scripts(self);
 
+        self.report_missing_xtras();
+
         self.begin_all_sprites();
         JsApi::dispatch_frame_changed(self.movie.current_frame);
     }
 
+    /// Warn about Xtras in the movie's `XTRl` list that the player does not
+    /// provide, so their absence is visible before a handler call fails.
+    fn report_missing_xtras(&mut self) {
+        let xtra_list = self.movie.file.as_ref().and_then(|file| file.xtra_list.as_ref());
+        let report = xtra::manager::xtra_report(xtra_list);
+        for name in &report.missing {
+            console_warn!("Movie requires unimplemented Xtra: {}", name);
+            self.console.write_line(&format!("Warning: movie requires unimplemented Xtra {}", name));
+        }
+    }
+
     pub fn play(&mut self) {
         if self.is_playing {
             return;
@@ -959,6 +1000,13 @@ impl DirPlayer {
 
         let prev_frame = self.movie.current_frame;
         let next_frame = self.get_next_frame();
+        let frame_changed = !self.movie.update_lock && prev_frame != next_frame;
+
+        // The transition captures the stage being replaced, so it starts
+        // while the playhead is still on the outgoing frame.
+        if frame_changed {
+            transition::start_frame_transition(self, next_frame);
+        }
 
         // Always advance logic (scripts, behaviors)
         self.next_frame = None;
@@ -970,9 +1018,11 @@ impl DirPlayer {
         // runs separately in the main loop.
 
         // Only dispatch and render if updateLock is off
-        if !self.movie.update_lock && prev_frame != self.movie.current_frame {
+        if frame_changed {
             JsApi::dispatch_frame_changed(self.movie.current_frame);
             self.has_player_frame_changed = true;
+            tempo_wait::arm_for_frame(self, next_frame);
+            palette_effects::start_frame_effects(self, next_frame);
         }
     }
 
@@ -991,6 +1041,8 @@ impl DirPlayer {
 
     pub fn reset(&mut self) {
         self.stop();
+        window::leave_all(self);
+        self.windows = window::WindowManager::default();
 
         // Clear all references before resetting the allocator
         // This ensures all DatumRef and ScriptInstanceRef objects are dropped properly
@@ -1000,6 +1052,10 @@ impl DirPlayer {
         self.globals.clear();
         debug!("Clearing timeout manager");
         self.timeout_manager.clear();
+        self.transitions.clear();
+        self.palette_effects.clear();
+        self.movie.cast_manager.set_palette_effect(Default::default());
+        self.tempo_wait = None;
         debug!("Clearing debug datum refs");
         self.debug_datum_refs.clear();
         // netManager.clear();
@@ -1010,6 +1066,7 @@ impl DirPlayer {
         self.movie.current_frame = 1;
         // TODO cancel breakpoints
         self.current_breakpoint = None;
+        self.watch_manager.clear_object_watchpoints();
         self.scope_count = 0;
         self.pending_goto_net_movie = None;
 
@@ -1725,11 +1782,27 @@ impl DirPlayer {
                 Ok(self.alloc_datum(Datum::DateRef(date_id)))
             },
             "stage" => Ok(self.alloc_datum(Datum::Stage)),
+            "windowList" => {
+                let names: Vec<String> = self.windows.windows.iter().map(|w| w.name.clone()).collect();
+                let items = names.into_iter().map(|name| self.alloc_datum(Datum::Window(name))).collect();
+                Ok(self.alloc_datum(Datum::List(DatumType::List, items, false)))
+            },
+            "activeWindow" => {
+                let datum = self.windows.active_window
+                    .and_then(|id| self.windows.get(id))
+                    .map_or(Datum::Stage, |w| Datum::Window(w.name.clone()));
+                Ok(self.alloc_datum(datum))
+            },
+            "frontWindow" => {
+                let datum = self.windows.front_window()
+                    .map_or(Datum::Stage, |w| Datum::Window(w.name.clone()));
+                Ok(self.alloc_datum(datum))
+            },
             "time" => Ok(self.alloc_datum(Datum::String(
-                chrono::Local::now().format("%H:%M %p").to_string(),
+                local_now().format("%H:%M %p").to_string(),
             ))),
             "milliSeconds" => Ok(self.alloc_datum(Datum::Int(
-                chrono::Local::now()
+                local_now()
                     .signed_duration_since(self.system_start_time)
                     .num_milliseconds() as i32,
             ))),
@@ -1770,18 +1843,15 @@ impl DirPlayer {
             "clipBoard" => {
                 Ok(self.alloc_datum(Datum::String(self.clipboard_mirror.clone())))
             },
-            "frameTempo" => {
-                // Get tempo from current frame in score, or use default frame_rate
-                let frame_tempo = self.movie.score.get_frame_tempo(self.movie.current_frame)
-                    .unwrap_or(self.movie.frame_rate as u32);
-                Ok(self.alloc_datum(Datum::Int(frame_tempo as i32)))
-            },
             "mouseLoc" => {
                 Ok(self.alloc_datum(Datum::Point([self.mouse_loc.0 as f64, self.mouse_loc.1 as f64], 0)))
             },
             "mouseH" => Ok(self.alloc_datum(Datum::Int(self.mouse_loc.0 as i32))),
             "mouseV" => Ok(self.alloc_datum(Datum::Int(self.mouse_loc.1 as i32))),
             "stillDown" => Ok(self.alloc_datum(datum_bool(self.movie.mouse_down))),
+            "rightMouseDown" => Ok(self.alloc_datum(datum_bool(self.right_mouse_down))),
+            "rightMouseUp" => Ok(self.alloc_datum(datum_bool(!self.right_mouse_down))),
+            "emulateMultiButtonMouse" => Ok(self.alloc_datum(datum_bool(self.emulate_multi_button_mouse))),
             "rollover" => {
                 let sprite = get_sprite_at(self, self.mouse_loc.0, self.mouse_loc.1, false);
                 Ok(self.alloc_datum(Datum::Int(sprite.unwrap_or(0) as i32)))
@@ -2066,6 +2136,7 @@ impl DirPlayer {
             "altDown" => Ok(self.alloc_datum(datum_bool(self.keyboard_manager.is_alt_down()))),
             "keyCode" => Ok(self.alloc_datum(Datum::Int(self.keyboard_manager.key_code() as i32))),
             "key" => Ok(self.alloc_datum(Datum::String(self.keyboard_manager.key()))),
+            "windowList" | "activeWindow" | "frontWindow" => self.get_movie_prop(prop),
             _ => Err(ScriptError::new(format!("Unknown player prop {}", prop))),
         }
     }
@@ -2141,6 +2212,10 @@ impl DirPlayer {
             "keyCode" => Ok(Datum::Int(self.keyboard_manager.key_code() as i32)),
             "stageColor" => Ok(Datum::Int(0)),
             "doubleClick" => Ok(datum_bool(self.is_double_click)),
+            "lastClick" if self.last_mouse_down_time > 0 => {
+                let elapsed = local_now().timestamp_millis() - self.last_mouse_down_time;
+                Ok(Datum::Int((elapsed * 60 / 1000) as i32))
+            }
             "lastClick" | "lastEvent" | "lastKey" | "lastRoll" => {
                 Ok(Datum::Int(get_elapsed_ticks(self.start_time)))
             }
@@ -2194,6 +2269,10 @@ impl DirPlayer {
                 self.clipboard_mirror = value.string_value()?;
                 Ok(())
             },
+            "emulateMultiButtonMouse" => {
+                self.emulate_multi_button_mouse = value.int_value()? != 0;
+                Ok(())
+            },
             "floatPrecision" => {
                 self.float_precision = value.int_value()? as u8;
                 Ok(())
@@ -2289,6 +2368,7 @@ impl DirPlayer {
         } else {
             warn!("pop_scope called with scope_count=0 (stale handler?)");
         }
+        window::unwind_scope(self);
     }
 
     pub fn current_scope_ref(&self) -> ScopeRef {
@@ -2667,15 +2747,13 @@ pub unsafe fn player_mut() -> &'static mut DirPlayer {
     PLAYER_OPT.as_mut().unwrap_unchecked()
 }
 
-fn reserve_player_mut_async<F, R>(callback: F) -> impl Future<Output = R>
+pub(crate) async fn reserve_player_mut_async<F, R>(callback: F) -> R
 where
     F: for<'a> FnOnce(&'a mut DirPlayer) -> Pin<Box<dyn Future<Output = R> + 'a>>,
 {
-    async move {
-        unsafe {
-            let player = PLAYER_OPT.as_mut().unwrap();
-            callback(player).await
-        }
+    unsafe {
+        let player = PLAYER_OPT.as_mut().unwrap();
+        callback(player).await
     }
 }
 
@@ -2756,30 +2834,39 @@ pub async fn player_call_script_handler_raw_args(
         }
     });
 
-    let (scope_ref, handler_ptr, script_ptr, names_ptr) = reserve_player_mut(|player| {
-        let (script_ptr, handler_ptr, handler_name_id, script_type, names_ptr) = {
+    let (scope_ref, handler_ptr, script_rc, names, variable_multiplier) = reserve_player_mut(|player| {
+        // Handlers of an instance run in the movie that created it.
+        let call_scope = player.scope_count as ScopeRef;
+        if let Some(receiver) = receiver.as_ref() {
+            window::enter_instance_movie(player, receiver)?;
+        }
+        let lookup = {
             let script_rc = player
                 .movie
                 .cast_manager
                 .get_script_by_ref(&script_member_ref)
-                .unwrap();
+                .ok_or_else(|| ScriptError::new(format!(
+                    "Script member {} of castLib {} not found",
+                    script_member_ref.cast_member, script_member_ref.cast_lib
+                )))?;
             let script = script_rc.as_ref();
-            let script_ptr = script as *const Script;
-            let names_ptr = player
+            let cast = player
                 .movie
                 .cast_manager
                 .get_cast(script.member_ref.cast_lib as u32)
-                .unwrap()
+                .unwrap();
+            let names = cast
                 .lctx
                 .as_ref()
-                .map(|lctx| &lctx.names as *const Vec<String>)
+                .map(|lctx| lctx.names.clone())
                 .unwrap();
+            let variable_multiplier = get_variable_multiplier(cast.capital_x, cast.dir_version);
             let handler = script.get_own_handler(&handler_name);
 
             if let Some(handler_rc) = handler {
                 let handler_name_id = handler_rc.name_id;
                 let handler_ptr: *const HandlerDef = handler_rc.as_ref();
-                Ok((script_ptr, handler_ptr, handler_name_id, script.script_type, names_ptr))
+                Ok((script_rc.clone(), handler_ptr, handler_name_id, script.script_type, names, variable_multiplier))
             } else {
                 Err(ScriptError::new_code(
                     ScriptErrorCode::HandlerNotFound,
@@ -2789,7 +2876,14 @@ pub async fn player_call_script_handler_raw_args(
                     ),
                 ))
             }
-        }?;
+        };
+        let (script_rc, handler_ptr, handler_name_id, script_type, names, variable_multiplier) = match lookup {
+            Ok(lookup) => lookup,
+            Err(err) => {
+                window::leave_tell(player, call_scope);
+                return Err(err);
+            }
+        };
 
         let receiver_arg = if let Some(script_instance_ref) = receiver.as_ref() {
             Some(Datum::ScriptInstanceRef(script_instance_ref.clone()))
@@ -2819,14 +2913,18 @@ pub async fn player_call_script_handler_raw_args(
         let scope = player.scopes.get_mut(scope_ref).unwrap();
         scope.args.extend_from_slice(arg_list);
 
-        Ok((scope_ref, handler_ptr, script_ptr, names_ptr))
+        Ok((scope_ref, handler_ptr, script_rc, names, variable_multiplier))
     })?;
 
+    // `script_rc` keeps the script and its handlers alive until the call
+    // returns, even if `scriptText` replaces it in the cast meanwhile.
+    let script_ptr = script_rc.as_ref() as *const Script;
     let ctx = BytecodeHandlerContext {
         scope_ref,
         handler_def_ptr: handler_ptr,
         script_ptr,
-        names_ptr,
+        names,
+        variable_multiplier,
     };
 
     // Trace handler entry if traceScript is enabled
@@ -2851,6 +2949,22 @@ pub async fn player_call_script_handler_raw_args(
         }
     });
 
+    if reserve_player_ref(|player| player.breakpoint_manager.has_handler_breakpoint(handler_name)) {
+        let location = BreakpointLocation::HandlerEntry {
+            script_name: unsafe { &(&*script_ptr).name },
+            handler_name,
+        };
+        if let Some(breakpoint) = player_check_breakpoint(location).await {
+            player_trigger_breakpoint(
+                breakpoint,
+                script_member_ref.to_owned(),
+                handler_ref.to_owned(),
+                0,
+            )
+            .await;
+        }
+    }
+
     let mut should_return = false;
     let scope_generation = reserve_player_ref(|player| {
         player.scopes.get(scope_ref).unwrap().generation
@@ -2868,23 +2982,19 @@ pub async fn player_call_script_handler_raw_args(
         // Single player access to read bytecode_index and debugger state
         let (bytecode_index, debugger_active) = reserve_player_ref(|player| {
             let bi = player.scopes.get(scope_ref).unwrap().bytecode_index;
-            let debugging = !player.breakpoint_manager.breakpoints.is_empty()
+            let debugging = player.breakpoint_manager.has_bytecode_breakpoints()
                 || !matches!(player.step_mode, StepMode::None);
             (bi, debugging)
         });
 
         // Only check breakpoints and step mode if the debugger is actually active
         if debugger_active {
-            if let Some(breakpoint) = reserve_player_ref(|player| {
-                player
-                    .breakpoint_manager
-                    .find_breakpoint_for_bytecode(
-                        unsafe { &(&*script_ptr).name },
-                        &handler_name,
-                        bytecode_index,
-                    )
-                    .cloned()
-            }) {
+            let location = BreakpointLocation::Bytecode {
+                script_name: unsafe { &(&*script_ptr).name },
+                handler_name,
+                bytecode_index,
+            };
+            if let Some(breakpoint) = player_check_breakpoint(location).await {
                 player_trigger_breakpoint(
                     breakpoint,
                     script_member_ref.to_owned(),
@@ -2911,11 +3021,11 @@ pub async fn player_call_script_handler_raw_args(
             });
 
             if should_step_break {
-                let breakpoint = Breakpoint {
-                    script_name: unsafe { (&*script_ptr).name.clone() },
-                    handler_name: handler_name.clone(),
+                let breakpoint = Breakpoint::new(
+                    unsafe { (&*script_ptr).name.clone() },
+                    handler_name.clone(),
                     bytecode_index,
-                };
+                );
                 player_trigger_breakpoint(
                     breakpoint,
                     script_member_ref.to_owned(),
@@ -2958,12 +3068,22 @@ pub async fn player_call_script_handler_raw_args(
         };
 
         // Check if scope was reused after an async yield point
-        let post_gen = reserve_player_ref(|player| {
-            player.scopes.get(scope_ref).unwrap().generation
+        let (post_gen, watch_hit) = reserve_player_mut(|player| {
+            let generation = player.scopes.get(scope_ref).unwrap().generation;
+            (generation, player.watch_manager.take_pending_break())
         });
         if post_gen != scope_generation {
             break;
         }
+        if let Some(hit) = watch_hit {
+            player_trigger_watchpoint(
+                hit,
+                script_member_ref.to_owned(),
+                handler_ref.to_owned(),
+                bytecode_index,
+            )
+            .await;
+        }
 
         match result {
             HandlerExecutionResult::Advance => {
@@ -3088,7 +3208,7 @@ async fn stop_movie_sequence() {
 /// Run the movie initialization sequence: prepareMovie, beginSprite, behavior init,
 /// stepFrame, prepareFrame, startMovie, enterFrame, exitFrame.
 /// Shared by `play()` and `transition_to_net_movie`.
-async fn run_movie_init_sequence() {
+pub(crate) async fn run_movie_init_sequence() {
     // prepareMovie
     debug!(">>> Dispatching prepareMovie");
     dispatch_system_event_to_timeouts(&"prepareMovie".to_string(), &vec![]).await;
@@ -3334,6 +3454,30 @@ async fn run_movie_init_sequence() {
     });
 }
 
+/// Switch to the movie requested by gotoNetMovie once it has been fetched.
+/// Returns whether a transition was attempted.
+pub async fn run_pending_goto_net_movie() -> bool {
+    let goto_transition = reserve_player_ref(|player| {
+        if let Some((task_id, ref target)) = player.pending_goto_net_movie {
+            if player.net_manager.is_task_done(Some(task_id)) {
+                Some((task_id, target.clone()))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    });
+
+    match goto_transition {
+        Some((task_id, target)) => {
+            transition_to_net_movie(task_id, target).await;
+            true
+        }
+        None => false,
+    }
+}
+
 /// Perform the movie transition for gotoNetMovie.
 /// Called from within the frame loop when the pending fetch is complete.
 async fn transition_to_net_movie(task_id: u32, target: MovieFrameTarget) {
@@ -3452,6 +3596,9 @@ extern "C" {
 /// Each timeout that fires is rescheduled to `now + period`. The time is captured
 /// once at the start so handlers that take time don't cause cascading re-fires.
 pub async fn fire_pending_timeouts() {
+    if replay::replays_host_timeouts() {
+        return;
+    }
     let now = testing_shared::now_ms();
     let pending_timeouts: Vec<(DatumRef, String, String)> = reserve_player_mut(|player| {
         let mut ready = Vec::new();
@@ -3483,16 +3630,46 @@ pub async fn fire_pending_timeouts() {
 }
 
 pub async fn run_single_frame() -> (bool, bool) {
-    let (mut is_playing, mut is_script_paused) = reserve_player_ref(|player| {
+    window::wait_for_stage_context().await;
+    let (is_playing, is_script_paused) = reserve_player_ref(|player| {
         (player.is_playing, player.is_script_paused)
     });
     if !is_playing {
         return (false, is_script_paused);
     }
+    replay::begin_frame().await;
 
-    // Dispatch streamStatus for any net tasks that completed since last check
+    // Apply network progress, then dispatch streamStatus for any net tasks
+    // that changed since last check
+    net_manager::poll_net_tasks().await;
     stream_status::dispatch_pending_stream_status().await;
 
+    // Dispatch cuePassed for sound cue points crossed since the last frame
+    cue_points::dispatch_cue_passed().await;
+
+    let (is_playing, is_script_paused) = run_movie_frame().await;
+
+    // Movies in a window play along with the stage
+    if is_playing && !is_script_paused {
+        window::step_windows().await;
+        reserve_player_mut(cycle_collector::on_frame_played);
+    }
+    (is_playing, is_script_paused)
+}
+
+/// Run one frame of the current movie: the stage's, or a window's while it
+/// is entered (see `window::step_windows`).
+pub(crate) async fn run_movie_frame() -> (bool, bool) {
+    let (mut is_playing, mut is_script_paused) = reserve_player_ref(|player| {
+        (player.is_playing, player.is_script_paused)
+    });
+    if !is_playing {
+        return (false, is_script_paused);
+    }
+
+    // Detect and resolve Shockwave 3D #collision modifier contacts
+    collision3d::dispatch_collisions().await;
+
     // --- Phase 1: Execute frame scripts ---
     if !is_script_paused {
         player_wait_available().await;
@@ -3553,7 +3730,7 @@ pub async fn run_single_frame() -> (bool, bool) {
     // Check if delay() is in effect
     let is_delayed = reserve_player_mut(|player| {
         if let Some(until) = player.delay_until {
-            if chrono::Local::now() < until {
+            if local_now() < until {
                 true
             } else {
                 player.delay_until = None;
@@ -3564,8 +3741,11 @@ pub async fn run_single_frame() -> (bool, bool) {
         }
     });
 
+    // Check if the tempo channel is holding the playhead on this frame
+    let is_tempo_waiting = reserve_player_mut(tempo_wait::is_waiting);
+
     let skip_frame = reserve_player_ref(|player| player.command_handler_yielding || player.in_mouse_command);
-    if skip_frame || is_delayed {
+    if skip_frame || is_delayed || is_tempo_waiting {
         return (is_playing, is_script_paused);
     }
 
@@ -3851,20 +4031,7 @@ pub async fn run_frame_loop() {
             return;
         }
         // Check for pending gotoNetMovie completion
-        let goto_transition = reserve_player_ref(|player| {
-            if let Some((task_id, ref target)) = player.pending_goto_net_movie {
-                if player.net_manager.is_task_done(Some(task_id)) {
-                    Some((task_id, target.clone()))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        });
-
-        if let Some((task_id, target)) = goto_transition {
-            transition_to_net_movie(task_id, target).await;
+        if run_pending_goto_net_movie().await {
             (is_playing, _) = reserve_player_ref(|player| {
                 (player.is_playing, player.is_script_paused)
             });
@@ -3911,14 +4078,16 @@ pub async fn run_frame_loop() {
             debug!("[Flash] Ruffle instance ready, resuming frame loop.");
         }
 
-        // Get the target frame delay based on cached tempo for current frame
+        // Get the target frame delay based on cached tempo for current frame.
+        // A running transition holds the playhead until it has finished.
         let target_delay_ms = reserve_player_ref(|player| {
             let tempo = player.current_frame_tempo;
-            if tempo == 0 {
+            let tempo_delay = if tempo == 0 {
                 1000.0 / 30.0  // Default to 30fps if tempo is 0
             } else {
                 1000.0 / tempo as f64
-            }
+            };
+            tempo_delay.max(player.transitions.remaining_ms())
         });
 
         // Wait for the frame delay using the tempo-based timing
@@ -3946,6 +4115,37 @@ pub async fn player_trigger_breakpoint(
         bytecode_index,
         completer,
         error: None,
+        watch_hit: None,
+    };
+    reserve_player_mut(|player| {
+        player.current_breakpoint = Some(breakpoint_ctx);
+        player.pause_script();
+        JsApi::dispatch_scope_list(player);
+    });
+    future.await;
+    reserve_player_mut(|player| {
+        player.resume_script();
+    });
+}
+
+/// Pause on a change reported by a break-on-change watchpoint.
+pub async fn player_trigger_watchpoint(
+    hit: WatchHit,
+    script_ref: CastMemberRef,
+    handler_ref: ScriptHandlerRef,
+    bytecode_index: usize,
+) {
+    let (future, completer) = ManualFuture::new();
+    let script_name = hit.script_name.clone().unwrap_or_default();
+    let breakpoint = Breakpoint::new(script_name, handler_ref.1.clone(), bytecode_index);
+    let breakpoint_ctx = BreakpointContext {
+        breakpoint,
+        script_ref,
+        handler_ref,
+        bytecode_index,
+        completer,
+        error: None,
+        watch_hit: Some(hit),
     };
     reserve_player_mut(|player| {
         player.current_breakpoint = Some(breakpoint_ctx);
@@ -3973,11 +4173,7 @@ pub async fn player_trigger_error_pause(
             .map(|s| s.name.clone())
             .unwrap_or_default()
     });
-    let breakpoint = Breakpoint {
-        script_name,
-        handler_name: handler_ref.1.clone(),
-        bytecode_index,
-    };
+    let breakpoint = Breakpoint::new(script_name, handler_ref.1.clone(), bytecode_index);
     let breakpoint_ctx = BreakpointContext {
         breakpoint,
         script_ref,
@@ -3985,6 +4181,7 @@ pub async fn player_trigger_error_pause(
         bytecode_index,
         completer,
         error: Some(err.clone()),
+        watch_hit: None,
     };
     reserve_player_mut(|player| {
         player.current_breakpoint = Some(breakpoint_ctx);
@@ -4026,6 +4223,8 @@ pub fn init_player() {
         FILEIO_XTRA_MANAGER_OPT = Some(FileIoXtraManager::new());
         MULTIUSER_XTRA_MANAGER_OPT = Some(MultiuserXtraManager::new());
         XMLPARSER_XTRA_MANAGER_OPT = Some(XmlParserXtraManager::new());
+        PREGEX_XTRA_MANAGER_OPT = Some(PRegExXtraManager::new());
+        BUDDYAPI_XTRA_MANAGER_OPT = Some(BuddyApiXtraManager::new());
     }
 
     unsafe {
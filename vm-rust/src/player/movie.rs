@@ -1,19 +1,18 @@
 use std::collections::{HashMap, VecDeque};
 
-use chrono::Local;
-
 use crate::{
     director::{
         file::DirectorFile,
         lingo::datum::{datum_bool, Datum},
     },
-    utils::PATH_SEPARATOR, reserve_player_ref, reserve_player_mut,
+    utils::{local_now, PATH_SEPARATOR}, reserve_player_ref, reserve_player_mut,
     player::ColorRef, player::ScriptInstanceRef, CastMemberRef,
 };
 
 use super::{
     allocator::DatumAllocator, bitmap::manager::BitmapManager, cast_manager::CastManager,
-    geometry::IntRect, net_manager::NetManager, score::Score, ScriptError, ScriptReceiver,
+    geometry::IntRect, net_manager::NetManager, score::Score,
+    score_recording::{self, ScoreRecording}, ScriptError, ScriptReceiver,
 };
 
 pub struct Movie {
@@ -48,9 +47,49 @@ pub struct Movie {
     pub frame_script_instance: Option<ScriptInstanceRef>,
     pub frame_script_member: Option<CastMemberRef>,
     pub sound_device: String,
+    /// Open `beginRecording` session, if any.
+    pub score_recording: Option<ScoreRecording>,
 }
 
 impl Movie {
+    /// A movie with no casts and an empty score, used before anything is loaded.
+    pub fn empty() -> Movie {
+        Movie {
+            rect: IntRect::from(0, 0, 0, 0),
+            cast_manager: CastManager::empty(),
+            score: Score::empty(),
+            current_frame: 1,
+            puppet_tempo: 0,
+            random_seed: None,
+            exit_lock: false,
+            dir_version: 0,
+            item_delimiter: ',',
+            alert_hook: None,
+            base_path: "".to_string(),
+            file_name: "".to_string(),
+            stage_color: (255, 255, 255),
+            stage_color_ref: ColorRef::PaletteIndex(255),
+            frame_rate: 30,
+            file: None,
+            update_lock: false,
+            mouse_down_script: None,
+            mouse_up_script: None,
+            key_down_script: None,
+            key_up_script: None,
+            timeout_script: None,
+            allow_custom_caching: false,
+            trace_script: false,
+            trace_log_file: String::new(),
+            debug_playback_enabled: false,
+            mouse_down: false,
+            click_loc: (0,0),
+            frame_script_instance: None,
+            frame_script_member: None,
+            sound_device: String::new(),
+            score_recording: None,
+        }
+    }
+
     pub async fn load_from_file(
         &mut self,
         file: DirectorFile,
@@ -96,6 +135,7 @@ impl Movie {
             .load_from_dir(&file, net_manager, bitmap_manager, dir_cache)
             .await;
         self.score.load_from_dir(&file);
+        self.score_recording = None;
         self.file_name = file.file_name.to_string();
         self.frame_rate = file.config.frame_rate;
         self.file = Some(file);
@@ -119,16 +159,20 @@ impl Movie {
             "runmode" => Ok(Datum::String("Plugin".to_string())), // Plugin / Author
             "date" => {
                 // TODO localize formatting
-                let time = Local::now();
+                let time = local_now();
                 let formatted = time.format("%m/%d/%Y").to_string();
                 Ok(Datum::String(formatted))
             },
             "long time" => {
-                let time = Local::now();
+                let time = local_now();
                 let formatted = time.format("%H:%M:%S %p").to_string();
                 Ok(Datum::String(formatted))
             },
             "lastChannel" => Ok(Datum::Int(self.score.get_channel_count() as i32)),
+            "lastFrame" => Ok(Datum::Int(self.score.frame_count.unwrap_or(1) as i32)),
+            "frameScript" | "frameTempo" | "frameSound1" | "frameSound2" | "frameTransition" | "framePalette" => {
+                score_recording::get_frame_channel_prop(self, prop)
+            },
             "moviePath" => {
                 let mut result = self.base_path.clone();
                 if !result.is_empty() && !result.ends_with(PATH_SEPARATOR) {
@@ -164,7 +208,7 @@ impl Movie {
             "allowCustomCaching" => Ok(datum_bool(self.allow_custom_caching)),
             "timer" => {
                 reserve_player_ref(|player| {
-                    let elapsed = chrono::Local::now()
+                    let elapsed = local_now()
                         .signed_duration_since(player.start_time)
                         .num_milliseconds();
                     // Convert to ticks (60ths of a second)
@@ -176,7 +220,6 @@ impl Movie {
                 Ok(datum_bool(self.mouse_down))
             },
             "traceScript" => Ok(datum_bool(self.trace_script)),
-            "activeWindow" => Ok(Datum::Stage),
             "rollOver" => {
                 reserve_player_ref(|player| {
                     let sprite = super::score::get_sprite_at(player, player.mouse_loc.0, player.mouse_loc.1, false);
@@ -268,6 +311,9 @@ impl Movie {
                 self.debug_playback_enabled = value.int_value()? != 0;
                 Ok(())
             },
+            "frameScript" | "frameTempo" | "frameSound1" | "frameSound2" | "frameTransition" | "framePalette" => {
+                score_recording::set_frame_channel_prop(self, prop, &value)
+            },
             "alertHook" => {
                 match value {
                     Datum::Int(0) => {
@@ -0,0 +1,377 @@
+//! Network backends for NetManager
+//!
+//! `preloadNetThing`, `getNetText`, `postNetText` and `gotoNetMovie` hand
+//! their requests to a [`NetBackend`]:
+//! - Browser - `fetch()` for http(s), an Electron event for file:// URLs
+//! - Native - answers from disk and from a manifest of URL -> response
+//!   mappings, on a virtual clock so delays, progress and timeouts are
+//!   deterministic in tests
+
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_std::sync::Mutex;
+use percent_encoding::percent_decode_str;
+use serde::Deserialize;
+
+use super::{
+    net_manager::NetManagerSharedState,
+    net_task::{
+        error_for_status, fetch_net_task, NetResult, NetTask, NET_ERROR_CONNECTION_FAILED,
+        NET_ERROR_EARLY_CLOSE, NET_ERROR_NOT_FOUND,
+    },
+    testing_shared::now_ms,
+};
+
+/// Something that happened to a request, reported by [`NetBackend::poll`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum NetEvent {
+    Progress {
+        task_id: u32,
+        bytes_loaded: u64,
+        bytes_total: u64,
+    },
+    Done {
+        task_id: u32,
+        result: NetResult,
+    },
+}
+
+impl NetEvent {
+    pub fn task_id(&self) -> u32 {
+        match self {
+            NetEvent::Progress { task_id, .. } | NetEvent::Done { task_id, .. } => *task_id,
+        }
+    }
+}
+
+/// Common trait for everything that can carry out net tasks.
+pub trait NetBackend {
+    /// Begin fetching `task`. Asynchronous backends may report straight to
+    /// `shared_state`; the others queue events for [`NetBackend::poll`].
+    fn start(&mut self, task: &NetTask, shared_state: &Arc<Mutex<NetManagerSharedState>>);
+
+    /// Take the events that are due. Default: none.
+    fn poll(&mut self) -> Vec<NetEvent> {
+        Vec::new()
+    }
+
+    /// Stop reporting on a task (it timed out). Default: no-op.
+    fn cancel(&mut self, _task_id: u32) {}
+
+    /// Current time of the backend clock in milliseconds
+    fn now_ms(&self) -> f64 {
+        now_ms()
+    }
+
+    /// Whether the clock only moves through [`NetBackend::advance`]
+    fn has_virtual_clock(&self) -> bool {
+        false
+    }
+
+    /// Move a virtual clock forward. Default: no-op.
+    fn advance(&mut self, _ms: f64) {}
+
+    /// When the next queued event is due, for virtual clocks
+    fn next_event_ms(&self) -> Option<f64> {
+        None
+    }
+
+    /// Get the backend name for debugging
+    fn backend_name(&self) -> &'static str;
+}
+
+#[derive(Default)]
+pub struct BrowserNetBackend {}
+
+impl NetBackend for BrowserNetBackend {
+    fn start(&mut self, task: &NetTask, shared_state: &Arc<Mutex<NetManagerSharedState>>) {
+        if task.resolved_url.scheme() == "file" {
+            // Wait for the host (Electron) to provide the file through
+            // provide_net_task_data / provide_net_task_error.
+            #[cfg(target_arch = "wasm32")]
+            {
+                let window = web_sys::window().unwrap();
+                let event_init = web_sys::CustomEventInit::new();
+                let detail = js_sys::Object::new();
+                js_sys::Reflect::set(&detail, &"taskId".into(), &task.id.into()).unwrap();
+                js_sys::Reflect::set(&detail, &"url".into(), &task.resolved_url.to_string().into()).unwrap();
+                event_init.set_detail(&detail);
+
+                let event =
+                    web_sys::CustomEvent::new_with_event_init_dict("dirplayer:netRequest", &event_init)
+                        .unwrap();
+                window.dispatch_event(&event).unwrap();
+            }
+            return;
+        }
+
+        let task = task.clone();
+        let shared_state = Arc::clone(shared_state);
+        async_std::task::spawn_local(async move {
+            let result = fetch_net_task(&task, Arc::clone(&shared_state)).await;
+            shared_state.lock().await.fulfill_task(task.id, result).await;
+        });
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "browser"
+    }
+}
+
+/// How the native backend answers requests for one URL.
+///
+/// `url` is matched against the URL as the movie wrote it, the resolved
+/// URL, or the end of the resolved URL (so relative entries like
+/// `"data/news.txt"` work). A trailing `*` matches any URL with that prefix.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NetRoute {
+    pub url: String,
+    /// Only answer this method ("GET" / "POST"); any method if unset
+    pub method: Option<String>,
+    /// HTTP status, 200 if unset
+    pub status: Option<u16>,
+    /// Response body
+    pub body: Option<String>,
+    /// Response body read from a file, relative to the manifest
+    pub file: Option<PathBuf>,
+    /// Fail with this net error code instead of responding
+    pub error: Option<i32>,
+    /// Time before the response starts
+    pub delay_ms: f64,
+    /// Deliver the body in chunks of this many bytes...
+    pub chunk_bytes: Option<usize>,
+    /// ...one every this many milliseconds
+    pub chunk_interval_ms: f64,
+    /// Close the connection after this many bytes
+    pub partial_bytes: Option<usize>,
+    /// bytesTotal to report; the body length if unset
+    pub content_length: Option<u64>,
+    /// Never respond
+    pub hang: bool,
+}
+
+impl NetRoute {
+    pub fn new(url: &str) -> NetRoute {
+        NetRoute {
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn matches(&self, task: &NetTask) -> bool {
+        if let Some(method) = &self.method
+            && !method.eq_ignore_ascii_case(task.method.as_str())
+        {
+            return false;
+        }
+        let resolved = task.resolved_url.as_str();
+        let decoded = percent_decode_str(resolved).decode_utf8_lossy();
+        if let Some(prefix) = self.url.strip_suffix('*') {
+            return task.url.starts_with(prefix) || resolved.starts_with(prefix) || decoded.starts_with(prefix);
+        }
+        let suffix = format!("/{}", self.url.trim_start_matches('/'));
+        task.url == self.url
+            || resolved == self.url
+            || decoded == self.url
+            || resolved.ends_with(&suffix)
+            || decoded.ends_with(&suffix)
+    }
+}
+
+/// A set of routes, loaded from JSON:
+///
+/// ```json
+/// { "root": "fixtures", "timeoutMs": 5000,
+///   "routes": [{ "url": "news.txt", "body": "hi", "delayMs": 100 }] }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NetManifest {
+    /// Directory that serves http(s) requests no route matches
+    pub root: Option<PathBuf>,
+    /// Fail requests that take longer than this with netError 4154
+    pub timeout_ms: Option<f64>,
+    pub routes: Vec<NetRoute>,
+}
+
+impl NetManifest {
+    pub fn from_json(json: &str) -> Result<NetManifest, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid net manifest: {}", e))
+    }
+
+    /// Load a manifest file. Relative `root` and `file` paths are taken
+    /// relative to the manifest's directory.
+    pub fn load(path: &Path) -> Result<NetManifest, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut manifest = NetManifest::from_json(&json)?;
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        if let Some(root) = &manifest.root {
+            manifest.root = Some(base_dir.join(root));
+        }
+        for route in manifest.routes.iter_mut() {
+            if let Some(file) = &route.file {
+                route.file = Some(base_dir.join(file));
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+struct ScheduledEvent {
+    at_ms: f64,
+    event: NetEvent,
+}
+
+/// Answers requests from the manifest routes, then from disk: file:// URLs
+/// are read directly and http(s) URLs are mapped into `root`. Without a
+/// matching route or a root, http(s) requests fail to connect.
+#[derive(Default)]
+pub struct NativeNetBackend {
+    pub manifest: NetManifest,
+    now_ms: f64,
+    queue: VecDeque<ScheduledEvent>,
+}
+
+impl NativeNetBackend {
+    pub fn new(manifest: NetManifest) -> NativeNetBackend {
+        NativeNetBackend {
+            manifest,
+            ..Default::default()
+        }
+    }
+
+    fn schedule(&mut self, at_ms: f64, event: NetEvent) {
+        // Keep the queue ordered by time, first come first served on ties.
+        let index = self.queue.iter().position(|queued| queued.at_ms > at_ms).unwrap_or(self.queue.len());
+        self.queue.insert(index, ScheduledEvent { at_ms, event });
+    }
+
+    fn serve_route(&mut self, task_id: u32, route: &NetRoute) {
+        let mut at_ms = self.now_ms + route.delay_ms.max(0.0);
+        if route.hang {
+            return;
+        }
+        if let Some(error) = route.error {
+            self.schedule(at_ms, NetEvent::Done { task_id, result: Err(error) });
+            return;
+        }
+        if let Some(error) = error_for_status(route.status.unwrap_or(200)) {
+            self.schedule(at_ms, NetEvent::Done { task_id, result: Err(error) });
+            return;
+        }
+        let body = match (&route.file, &route.body) {
+            (Some(file), _) => match std::fs::read(file) {
+                Ok(bytes) => bytes,
+                Err(_) => {
+                    self.schedule(at_ms, NetEvent::Done { task_id, result: Err(NET_ERROR_NOT_FOUND) });
+                    return;
+                }
+            },
+            (None, Some(body)) => body.as_bytes().to_vec(),
+            (None, None) => vec![],
+        };
+
+        let bytes_total = route.content_length.unwrap_or(body.len() as u64);
+        let limit = route.partial_bytes.map_or(body.len(), |partial| partial.min(body.len()));
+        self.schedule(at_ms, NetEvent::Progress { task_id, bytes_loaded: 0, bytes_total });
+        if let Some(chunk_bytes) = route.chunk_bytes.filter(|chunk_bytes| *chunk_bytes > 0) {
+            let mut bytes_loaded = 0;
+            while bytes_loaded < limit {
+                if bytes_loaded > 0 {
+                    at_ms += route.chunk_interval_ms.max(0.0);
+                }
+                bytes_loaded = (bytes_loaded + chunk_bytes).min(limit);
+                let bytes_loaded = bytes_loaded as u64;
+                self.schedule(at_ms, NetEvent::Progress { task_id, bytes_loaded, bytes_total });
+            }
+        } else {
+            self.schedule(at_ms, NetEvent::Progress { task_id, bytes_loaded: limit as u64, bytes_total });
+        }
+        let result = if limit < body.len() {
+            Err(NET_ERROR_EARLY_CLOSE)
+        } else {
+            Ok(body)
+        };
+        self.schedule(at_ms, NetEvent::Done { task_id, result });
+    }
+
+    fn serve_disk(&mut self, task: &NetTask) {
+        let url = &task.resolved_url;
+        let path = percent_decode_str(url.path()).decode_utf8_lossy().to_string();
+        let file_path = if url.scheme() == "file" {
+            // Windows drive paths come through as /C:/...
+            match path.strip_prefix('/') {
+                Some(rest) if rest.as_bytes().get(1) == Some(&b':') => PathBuf::from(rest),
+                _ => PathBuf::from(&path),
+            }
+        } else if let Some(root) = &self.manifest.root {
+            root.join(path.trim_start_matches('/'))
+        } else {
+            let result = Err(NET_ERROR_CONNECTION_FAILED);
+            self.schedule(self.now_ms, NetEvent::Done { task_id: task.id, result });
+            return;
+        };
+        let result = std::fs::read(&file_path).map_err(|_| NET_ERROR_NOT_FOUND);
+        self.schedule(self.now_ms, NetEvent::Done { task_id: task.id, result });
+    }
+}
+
+impl NetBackend for NativeNetBackend {
+    fn start(&mut self, task: &NetTask, _shared_state: &Arc<Mutex<NetManagerSharedState>>) {
+        let route = self.manifest.routes.iter().find(|route| route.matches(task)).cloned();
+        match route {
+            Some(route) => self.serve_route(task.id, &route),
+            None => self.serve_disk(task),
+        }
+    }
+
+    fn poll(&mut self) -> Vec<NetEvent> {
+        let mut events = vec![];
+        while self.queue.front().is_some_and(|queued| queued.at_ms <= self.now_ms) {
+            events.push(self.queue.pop_front().unwrap().event);
+        }
+        events
+    }
+
+    fn cancel(&mut self, task_id: u32) {
+        self.queue.retain(|queued| queued.event.task_id() != task_id);
+    }
+
+    fn now_ms(&self) -> f64 {
+        self.now_ms
+    }
+
+    fn has_virtual_clock(&self) -> bool {
+        true
+    }
+
+    fn advance(&mut self, ms: f64) {
+        self.now_ms += ms.max(0.0);
+    }
+
+    fn next_event_ms(&self) -> Option<f64> {
+        self.queue.front().map(|queued| queued.at_ms)
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "native"
+    }
+}
+
+/// The backend a new player starts with.
+pub fn default_net_backend() -> Box<dyn NetBackend> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        Box::new(BrowserNetBackend::default())
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Box::new(NativeNetBackend::default())
+    }
+}
@@ -1,12 +1,29 @@
 use std::{collections::HashMap, path::Path, sync::Arc};
 
 use async_std::sync::Mutex;
-use log::debug;
+use log::{debug, warn};
 use manual_future::{ManualFuture, ManualFutureCompleter};
 use percent_encoding::percent_decode_str;
 use url::Url;
 
-use super::net_task::{fetch_net_task, NetResult, NetTask, NetTaskState};
+use super::{
+    net_backend::{default_net_backend, NetBackend, NetEvent},
+    net_task::{HttpMethod, NetResult, NetTask, NetTaskState, NET_ERROR_TIMEOUT},
+    replay, reserve_player_mut,
+};
+
+/// A request as it was handed to the network backend.
+#[derive(Clone, Debug)]
+pub struct NetRequestLogEntry {
+    pub task_id: u32,
+    pub method: String,
+    pub url: String,
+    pub resolved_url: String,
+    pub body: Option<String>,
+    pub content_type: Option<String>,
+    /// Backend clock time the request was started at
+    pub time_ms: f64,
+}
 
 pub struct NetManager {
     pub base_path: Option<Url>,
@@ -16,6 +33,12 @@ pub struct NetManager {
     pub tasks: HashMap<u32, NetTask>,
     pub task_states: HashMap<u32, NetTaskState>,
     pub shared_state: Arc<Mutex<NetManagerSharedState>>,
+    pub backend: Box<dyn NetBackend>,
+    /// Fail tasks still pending after this long with `NET_ERROR_TIMEOUT`
+    pub timeout_ms: Option<f64>,
+    /// Backend clock time each pending task was started at
+    pub started_at: HashMap<u32, f64>,
+    pub request_log: Vec<NetRequestLogEntry>,
 }
 
 pub struct NetManagerSharedState {
@@ -38,7 +61,25 @@ impl NetManagerSharedState {
         }
     }
 
+    /// Store a result that arrived outside the frame loop (from a fetch or
+    /// the host) and wake the tasks waiting on it.
     pub async fn fulfill_task(&mut self, id: u32, result: NetResult) {
+        // A replay supplies the results itself.
+        if replay::is_replaying() {
+            return;
+        }
+        replay::record_net_result(id, &result, true);
+        for completer in self.resolve_task(id, result) {
+            completer.complete(()).await;
+        }
+    }
+
+    /// Store the result of a task, returning the completers to wake.
+    /// Results for tasks that already finished (e.g. timed out) are dropped.
+    pub fn resolve_task(&mut self, id: u32, result: NetResult) -> Vec<ManualFutureCompleter<()>> {
+        if self.task_states.get(&id).is_some_and(|state| state.is_done()) {
+            return vec![];
+        }
         let (bytes_loaded, bytes_total) = self.task_states.get(&id)
             .map(|s| (s.bytes_loaded, s.bytes_total))
             .unwrap_or((0, 0));
@@ -52,13 +93,7 @@ impl NetManagerSharedState {
             bytes_total: if bytes_total > 0 { bytes_total } else { final_bytes },
         };
         self.task_states.insert(id, new_state);
-
-        let completers_for_task = self.task_completers.get_mut(&id);
-        if let Some(completers) = completers_for_task {
-            while let Some(completer) = completers.pop() {
-                completer.complete(()).await;
-            }
-        }
+        self.task_completers.remove(&id).unwrap_or_default()
     }
 
     pub fn add_completer(&mut self, task_id: u32, completer: ManualFutureCompleter<()>) {
@@ -74,7 +109,123 @@ impl NetManagerSharedState {
     }
 }
 
+impl Default for NetManager {
+    fn default() -> Self {
+        NetManager::new()
+    }
+}
+
 impl NetManager {
+    pub fn new() -> NetManager {
+        NetManager {
+            base_path: None,
+            override_base_path: None,
+            tasks: HashMap::new(),
+            task_states: HashMap::new(),
+            shared_state: Arc::new(Mutex::new(NetManagerSharedState::new())),
+            backend: default_net_backend(),
+            timeout_ms: None,
+            started_at: HashMap::new(),
+            request_log: Vec::new(),
+        }
+    }
+
+    /// Replace the backend, returning the previous one.
+    pub fn set_backend(&mut self, backend: Box<dyn NetBackend>) -> Box<dyn NetBackend> {
+        std::mem::replace(&mut self.backend, backend)
+    }
+
+    /// Move the backend clock forward (virtual-clock backends only).
+    pub fn advance(&mut self, ms: f64) {
+        self.backend.advance(ms);
+    }
+
+    /// Apply the backend events that are due and time out overdue tasks.
+    /// Returns the completers of the tasks that finished, to be woken by
+    /// the caller.
+    pub fn poll(&mut self) -> Vec<ManualFutureCompleter<()>> {
+        let events = self.backend.poll();
+        let now_ms = self.backend.now_ms();
+        let timed_out: Vec<u32> = match self.timeout_ms {
+            Some(timeout_ms) => self
+                .started_at
+                .iter()
+                .filter(|(_, started_at)| now_ms - **started_at >= timeout_ms)
+                .map(|(task_id, _)| *task_id)
+                .collect(),
+            None => vec![],
+        };
+
+        let mut completers = vec![];
+        let mut shared_state = self.shared_state.try_lock().unwrap();
+        for event in events {
+            match event {
+                NetEvent::Progress { task_id, bytes_loaded, bytes_total } => {
+                    shared_state.update_task_progress(task_id, bytes_loaded, bytes_total);
+                }
+                NetEvent::Done { task_id, result } => {
+                    self.started_at.remove(&task_id);
+                    replay::record_net_result(task_id, &result, false);
+                    completers.extend(shared_state.resolve_task(task_id, result));
+                }
+            }
+        }
+        for task_id in timed_out {
+            if self.started_at.remove(&task_id).is_some() {
+                debug!("Net task #{} timed out", task_id);
+                self.backend.cancel(task_id);
+                replay::record_net_result(task_id, &Err(NET_ERROR_TIMEOUT), false);
+                completers.extend(shared_state.resolve_task(task_id, Err(NET_ERROR_TIMEOUT)));
+            }
+        }
+        // Tasks fulfilled outside the backend (e.g. by the host) are done too.
+        self.started_at.retain(|task_id, _| {
+            !shared_state.task_states.get(task_id).is_some_and(|state| state.is_done())
+        });
+        completers
+    }
+
+    /// When the pending task `task_id` times out, on the backend clock
+    fn timeout_deadline(&self, task_id: u32) -> Option<f64> {
+        Some(self.started_at.get(&task_id)? + self.timeout_ms?)
+    }
+
+    fn get_tasks(&self) -> impl Iterator<Item = &NetTask> {
+        self.tasks.values().filter(|task| matches!(task.method, HttpMethod::Get))
+    }
+
+    fn start_task(&mut self, net_task: NetTask) -> u32 {
+        let task_id = net_task.id;
+        let now_ms = self.backend.now_ms();
+        self.request_log.push(NetRequestLogEntry {
+            task_id,
+            method: net_task.method.as_str().to_string(),
+            url: net_task.url.clone(),
+            resolved_url: net_task.resolved_url.to_string(),
+            body: net_task.post_data.clone(),
+            content_type: net_task.content_type.clone(),
+            time_ms: now_ms,
+        });
+
+        // Set task initial state
+        {
+            let mut shared_state = self.shared_state.try_lock().unwrap();
+            shared_state.update_task_state(task_id, NetTaskState { result: None, bytes_loaded: 0, bytes_total: 0 });
+        }
+
+        // Push the task and execute it
+        self.tasks.insert(task_id, net_task.clone());
+        self.started_at.insert(task_id, now_ms);
+        self.backend.start(&net_task, &self.shared_state);
+
+        // Apply answers that are available right away, so that callers
+        // checking netDone straight after the request see them. Nobody can
+        // be waiting on this task yet, so there are no completers to wake.
+        let completers = self.poll();
+        debug_assert!(completers.is_empty());
+        task_id
+    }
+
     pub fn set_base_path(&mut self, base_path: Url) {
         let sanitized_path = if !base_path.path().ends_with("/") {
             Url::parse(format!("{}/", base_path.to_string()).as_str()).unwrap()
@@ -144,13 +295,48 @@ impl NetManager {
     }
 
     pub async fn await_task(&mut self, task_id: u32) {
-        let state = self.get_task_state(Some(task_id));
-        if state.is_some() && state.unwrap().result.is_some() {
+        if !self.backend.has_virtual_clock() {
+            let state = self.get_task_state(Some(task_id));
+            if state.is_some() && state.unwrap().result.is_some() {
+                return;
+            } else {
+                let future = self.create_task_future(task_id);
+                future.await;
+            }
             return;
-        } else {
-            let future = self.create_task_future(task_id);
-            future.await;
         }
+
+        // Nothing happens on a virtual clock unless we move it, so skip
+        // ahead to the next event until the task is done.
+        while !self.is_task_done(Some(task_id)) && self.tasks.contains_key(&task_id) {
+            let next_ms = match (self.backend.next_event_ms(), self.timeout_deadline(task_id)) {
+                (Some(event_ms), Some(deadline_ms)) => Some(event_ms.min(deadline_ms)),
+                (event_ms, deadline_ms) => event_ms.or(deadline_ms),
+            };
+            match next_ms {
+                Some(next_ms) => self.backend.advance(next_ms - self.backend.now_ms()),
+                None => {
+                    warn!("Net task #{} will never complete, failing it", task_id);
+                    self.started_at.remove(&task_id);
+                    self.backend.cancel(task_id);
+                    self.fulfill(vec![(task_id, Err(NET_ERROR_TIMEOUT))]).await;
+                    break;
+                }
+            }
+            let completers = self.poll();
+            complete_all(completers).await;
+        }
+    }
+
+    async fn fulfill(&mut self, results: Vec<(u32, NetResult)>) {
+        let completers: Vec<_> = {
+            let mut shared_state = self.shared_state.lock().await;
+            results
+                .into_iter()
+                .flat_map(|(task_id, result)| shared_state.resolve_task(task_id, result))
+                .collect()
+        };
+        complete_all(completers).await;
     }
 
     pub fn preload_net_thing(&mut self, url: String) -> u32 {
@@ -184,8 +370,10 @@ impl NetManager {
             url
         };
 
-        // Check if the task already exists (by original URL) and return it if found
-        if let Some(existing_task) = find_task_with_url(&self.tasks, &url) {
+        // Check if the task already exists (by original URL) and return it if found.
+        // POST results are never reused for a GET of the same URL.
+        let decoded_url = percent_decode_str(&url).decode_utf8().map(|s| s.to_string()).unwrap_or_else(|_| url.clone());
+        if let Some(existing_task) = self.get_tasks().find(|task| task.url == decoded_url) {
             return existing_task.id;
         }
 
@@ -200,80 +388,10 @@ impl NetManager {
         };
 
         // Also check by resolved URL to catch relative vs absolute URL duplicates
-        if let Some(existing_task) = find_task_with_resolved_url(&self.tasks, &net_task.resolved_url) {
+        if let Some(existing_task) = self.get_tasks().find(|task| task.resolved_url == net_task.resolved_url) {
             return existing_task.id;
         }
-        let task_id = net_task.id;
-        let resolved_url_str = net_task.resolved_url.to_string();
-        let is_file_url = resolved_url_str.starts_with("file://");
-
-        // Set task initial state
-        {
-            let mut shared_shared = self.shared_state.try_lock().unwrap();
-            shared_shared.update_task_state(task_id, NetTaskState { result: None, bytes_loaded: 0, bytes_total: 0 });
-        }
-
-        // Push the task
-        self.tasks.insert(task_id, net_task.clone());
-
-        // For file:// URLs, don't execute the fetch task - wait for JS to provide data
-        if is_file_url {
-            #[cfg(target_arch = "wasm32")]
-            {
-                // Emit event to request file data from Electron
-                let window = web_sys::window().unwrap();
-                let event_init = web_sys::CustomEventInit::new();
-                let detail = js_sys::Object::new();
-                js_sys::Reflect::set(&detail, &"taskId".into(), &task_id.into()).unwrap();
-                js_sys::Reflect::set(&detail, &"url".into(), &resolved_url_str.into()).unwrap();
-                event_init.set_detail(&detail);
-
-                let event =
-                    web_sys::CustomEvent::new_with_event_init_dict("dirplayer:netRequest", &event_init)
-                        .unwrap();
-                window.dispatch_event(&event).unwrap();
-            }
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                // In native (test) mode, read the file directly from disk
-                // and fulfill the task immediately (no spawn_local) so that
-                // await_task callers don't block on a future that never runs.
-                let file_path = resolved_url_str.strip_prefix("file://").unwrap_or(&resolved_url_str);
-                let result: super::net_task::NetResult = match std::fs::read(file_path) {
-                    Ok(bytes) => Ok(bytes),
-                    Err(_) => Err(-1),
-                };
-                let mut shared_state = self.shared_state.try_lock().unwrap();
-                let final_bytes = match &result {
-                    Ok(bytes) => bytes.len() as u64,
-                    Err(_) => 0,
-                };
-                let new_state = super::net_task::NetTaskState {
-                    result: Some(result),
-                    bytes_loaded: final_bytes,
-                    bytes_total: final_bytes,
-                };
-                shared_state.task_states.insert(task_id, new_state);
-            }
-        } else {
-            // Execute normal HTTP fetch
-            let shared_state_arc = Arc::clone(&self.shared_state);
-            async_std::task::spawn_local(async move {
-                Self::execute_task(task_id.clone(), net_task, shared_state_arc).await;
-            });
-        }
-
-        task_id
-    }
-
-    async fn execute_task(
-        id: u32,
-        task: NetTask,
-        shared_state_arc: Arc<Mutex<NetManagerSharedState>>,
-    ) {
-        let result = fetch_net_task(&task, Arc::clone(&shared_state_arc)).await;
-        let mut shared_state = shared_state_arc.lock().await;
-        shared_state.fulfill_task(id, result).await;
+        self.start_task(net_task)
     }
 
     // pub fn get_base_path(&self) -> String {
@@ -292,26 +410,23 @@ impl NetManager {
                 post_data,
             )
         };
-        let task_id = net_task.id;
-
-        // Set task initial state
-        {
-            let mut shared_shared = self.shared_state.try_lock().unwrap();
-            shared_shared.update_task_state(task_id, NetTaskState { result: None, bytes_loaded: 0, bytes_total: 0 });
-        }
-
-        // Push the task and execute it
-        self.tasks.insert(task_id, net_task.clone());
-
-        let shared_state_arc = Arc::clone(&self.shared_state);
-        async_std::task::spawn_local(async move {
-            Self::execute_task(task_id.clone(), net_task, shared_state_arc).await;
-        });
+        self.start_task(net_task)
+    }
+}
 
-        task_id
+/// Wake everything waiting on the given tasks.
+pub async fn complete_all(completers: Vec<ManualFutureCompleter<()>>) {
+    for completer in completers {
+        completer.complete(()).await;
     }
 }
 
+/// Apply the network events that are due. Called once per frame.
+pub async fn poll_net_tasks() {
+    let completers = reserve_player_mut(|player| player.net_manager.poll());
+    complete_all(completers).await;
+}
+
 fn normalize_task_url(url: &str, base_path: Option<&Url>) -> Url {
     let slash_norm = url.replace("\\", "/");
     let parsed_path = Path::new(slash_norm.as_str());
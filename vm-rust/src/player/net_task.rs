@@ -15,6 +15,23 @@ use crate::player::net_manager::NetManagerSharedState;
 
 pub type NetResult = Result<Vec<u8>, i32>;
 
+/// Error codes reported by `netError` and `getStreamStatus`, as in Director.
+pub const NET_ERROR_CONNECTION_FAILED: i32 = 4146;
+pub const NET_ERROR_EARLY_CLOSE: i32 = 4150;
+pub const NET_ERROR_TIMEOUT: i32 = 4154;
+pub const NET_ERROR_PROTOCOL: i32 = 4156;
+pub const NET_ERROR_BAD_URL: i32 = 4159;
+pub const NET_ERROR_NOT_FOUND: i32 = 4165;
+
+/// The net error for an HTTP status, or `None` if the request succeeded.
+pub fn error_for_status(status: u16) -> Option<i32> {
+    match status {
+        200..=299 => None,
+        404 | 410 => Some(NET_ERROR_NOT_FOUND),
+        _ => Some(NET_ERROR_PROTOCOL),
+    }
+}
+
 /// Tracks the last streamStatus phase reported for a net task.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum StreamStatusPhase {
@@ -39,6 +56,7 @@ pub struct NetTask {
     pub resolved_url: Url,
     pub method: HttpMethod,
     pub post_data: Option<String>,
+    pub content_type: Option<String>,
 }
 
 #[derive(Clone)]
@@ -47,6 +65,15 @@ pub enum HttpMethod {
     Post,
 }
 
+impl HttpMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+        }
+    }
+}
+
 impl NetTask {
     pub fn new<'b>(id: u32, url: &str, resolved_url: &Url) -> NetTask {
         return NetTask {
@@ -55,6 +82,7 @@ impl NetTask {
             resolved_url: resolved_url.clone().to_owned(),
             method: HttpMethod::Get,
             post_data: None,
+            content_type: None,
         };
     }
 
@@ -65,6 +93,10 @@ impl NetTask {
             resolved_url: resolved_url.to_owned(),
             method: HttpMethod::Post,
             post_data: Some(post_data),
+            // Form-encoded so servers populate $_POST (PHP) / request.form
+            // (others). Without this, fetch() defaults string bodies to
+            // text/plain, which most server-side form parsers ignore.
+            content_type: Some("application/x-www-form-urlencoded".to_string()),
         }
     }
 }
@@ -86,7 +118,7 @@ pub async fn fetch_net_task(
     );
 
     // Normal HTTP(S) fetch
-    // Note: file:// URLs are handled in preload_net_thing and never reach this function
+    // Note: file:// URLs are handled by BrowserNetBackend and never reach this function
     let window = web_sys::window().unwrap();
 
     let mut url_string = task.resolved_url.to_string();
@@ -96,7 +128,10 @@ pub async fn fetch_net_task(
         .to_string();
 
     let request = match task.method {
-        HttpMethod::Get => web_sys::Request::new_with_str(&url_string.as_str()).unwrap(),
+        HttpMethod::Get => match web_sys::Request::new_with_str(url_string.as_str()) {
+            Ok(request) => request,
+            Err(_) => return Err(NET_ERROR_BAD_URL),
+        },
         HttpMethod::Post => {
             let mut opts = web_sys::RequestInit::new();
             opts.method("POST");
@@ -105,30 +140,29 @@ pub async fn fetch_net_task(
                 opts.body(Some(&JsValue::from_str(post_data)));
             }
 
-            // Set Content-Type to form-urlencoded so servers populate
-            // $_POST (PHP) / request.form (others). Without this, fetch()
-            // defaults string bodies to text/plain, which most server-side
-            // form parsers ignore.
-            let headers = web_sys::Headers::new().unwrap();
-            headers
-                .set("Content-Type", "application/x-www-form-urlencoded")
-                .unwrap();
-            opts.headers(&headers);
-
-            web_sys::Request::new_with_str_and_init(&url_string.as_str(), &opts).unwrap()
+            if let Some(content_type) = &task.content_type {
+                let headers = web_sys::Headers::new().unwrap();
+                headers.set("Content-Type", content_type).unwrap();
+                opts.set_headers(&headers);
+            }
+
+            match web_sys::Request::new_with_str_and_init(url_string.as_str(), &opts) {
+                Ok(request) => request,
+                Err(_) => return Err(NET_ERROR_BAD_URL),
+            }
         }
     };
 
     let resp_result = JsFuture::from(window.fetch_with_request(&request)).await;
     let resp_value = match resp_result {
         Ok(v) => v,
-        Err(_) => return Err(4),
+        Err(_) => return Err(NET_ERROR_CONNECTION_FAILED),
     };
 
     assert!(resp_value.is_instance_of::<Response>());
     let resp: Response = resp_value.dyn_into().unwrap();
-    if resp.status() != 200 {
-        return Err(4);
+    if let Some(error) = error_for_status(resp.status()) {
+        return Err(error);
     }
 
     // Get Content-Length for bytesTotal
@@ -156,7 +190,7 @@ pub async fn fetch_net_task(
             let chunk_result = JsFuture::from(reader.read()).await;
             let chunk = match chunk_result {
                 Ok(v) => v,
-                Err(_) => return Err(4),
+                Err(_) => return Err(NET_ERROR_EARLY_CLOSE),
             };
 
             let done = js_sys::Reflect::get(&chunk, &"done".into())
@@ -0,0 +1,283 @@
+//! Palette effects (palette channel and `puppetPalette`).
+//!
+//! Besides selecting a palette, the palette channel can fade the stage to
+//! black or white and back, cycle a range of palette entries, or blend to a
+//! new palette over time. `puppetPalette` overrides the channel's palette
+//! until it is cleared. Effects are armed when the playhead enters a frame,
+//! advanced by [`update`] before each render and handed to the cast manager
+//! as a [`PaletteEffect`], which the renderers' palette lookups apply to
+//! indexed colours.
+
+use crate::director::chunks::score::PaletteChannelData;
+use crate::player::{
+    bitmap::{
+        bitmap::{resolve_palette_table, PaletteRef},
+        palette_map::{PaletteBlend, PaletteEffect},
+    },
+    testing_shared::now_ms,
+    DirPlayer,
+};
+
+/// Duration of one half (out or in) of a fade at palette speed `speed`.
+/// Speed runs from 1 (slowest, two seconds) to 60 (one tick).
+pub fn fade_half_ms(speed: u8) -> f64 {
+    2000.0 * (61 - speed.clamp(1, 60) as u32) as f64 / 60.0
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaletteFade {
+    pub color: (u8, u8, u8),
+    pub start_ms: f64,
+    pub half_ms: f64,
+}
+
+impl PaletteFade {
+    /// How far the screen is faded at `now`: rises to 1.0 over the first
+    /// half and falls back to 0.0 over the second. `None` once finished.
+    pub fn amount(&self, now: f64) -> Option<f32> {
+        let elapsed = (now - self.start_ms).max(0.0);
+        if self.half_ms <= 0.0 || elapsed >= self.half_ms * 2.0 {
+            return None;
+        }
+        let t = elapsed / self.half_ms;
+        Some(if t <= 1.0 { t } else { 2.0 - t } as f32)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorCycle {
+    pub first: u8,
+    pub last: u8,
+    pub step_ms: f64,
+    pub start_ms: f64,
+    /// Steps after which cycling stops, from the channel's cycle count.
+    pub max_steps: Option<u32>,
+    pub auto_reverse: bool,
+}
+
+impl ColorCycle {
+    pub fn from_channel(data: &PaletteChannelData, now: f64) -> Option<ColorCycle> {
+        if !data.is_color_cycling() {
+            return None;
+        }
+        let first = data.first_color.min(data.last_color);
+        let last = data.first_color.max(data.last_color);
+        if first == last {
+            return None;
+        }
+        let auto_reverse = data.flags & PaletteChannelData::AUTO_REVERSE != 0;
+        let mut cycle = ColorCycle {
+            first,
+            last,
+            step_ms: 1000.0 / data.speed.max(1) as f64,
+            start_ms: now,
+            max_steps: None,
+            auto_reverse,
+        };
+        if data.cycle_count > 0 {
+            cycle.max_steps = Some(data.cycle_count as u32 * cycle.period());
+        }
+        Some(cycle)
+    }
+
+    fn len(&self) -> u32 {
+        (self.last - self.first) as u32 + 1
+    }
+
+    /// Steps in one full cycle.
+    fn period(&self) -> u32 {
+        if self.auto_reverse {
+            (self.len() - 1) * 2
+        } else {
+            self.len()
+        }
+    }
+
+    fn same_range(&self, other: &ColorCycle) -> bool {
+        self.first == other.first && self.last == other.last && self.auto_reverse == other.auto_reverse
+    }
+
+    /// Rotation of the range at `now`.
+    pub fn offset(&self, now: f64) -> u8 {
+        let mut steps = ((now - self.start_ms).max(0.0) / self.step_ms) as u32;
+        if let Some(max_steps) = self.max_steps {
+            steps = steps.min(max_steps);
+        }
+        let position = steps % self.period();
+        let position = if self.auto_reverse && position >= self.len() {
+            self.period() - position
+        } else {
+            position
+        };
+        position as u8
+    }
+
+    pub fn is_finished(&self, now: f64) -> bool {
+        self.max_steps
+            .is_some_and(|max_steps| (now - self.start_ms) / self.step_ms >= max_steps as f64)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaletteTransition {
+    pub palette: PaletteRef,
+    /// Colours of the palette being replaced.
+    pub from: Vec<(u8, u8, u8)>,
+    pub start_ms: f64,
+    pub duration_ms: f64,
+}
+
+impl PaletteTransition {
+    pub fn progress(&self, now: f64) -> f32 {
+        if self.duration_ms <= 0.0 {
+            return 1.0;
+        }
+        ((now - self.start_ms) / self.duration_ms).clamp(0.0, 1.0) as f32
+    }
+}
+
+#[derive(Default)]
+pub struct PaletteEffectManager {
+    /// Set by `puppetPalette`; overrides the palette channel until cleared.
+    pub puppet: Option<PaletteRef>,
+    pub fade: Option<PaletteFade>,
+    pub cycle: Option<ColorCycle>,
+    pub transition: Option<PaletteTransition>,
+    /// Palette shown on the current frame, to blend channel changes from.
+    shown: Option<PaletteRef>,
+}
+
+impl PaletteEffectManager {
+    pub fn is_active(&self) -> bool {
+        self.fade.is_some() || self.cycle.is_some() || self.transition.is_some()
+    }
+
+    pub fn clear(&mut self) {
+        *self = PaletteEffectManager::default();
+    }
+}
+
+/// The palette of the current frame: the puppet palette if one is set,
+/// otherwise the palette channel.
+pub fn active_palette(player: &DirPlayer) -> PaletteRef {
+    palette_for_frame(player, player.movie.current_frame)
+}
+
+fn palette_for_frame(player: &DirPlayer, frame: u32) -> PaletteRef {
+    player
+        .palette_effects
+        .puppet
+        .clone()
+        .unwrap_or_else(|| player.movie.score.get_frame_palette(frame))
+}
+
+fn palette_colors(player: &DirPlayer, palette: &PaletteRef) -> Vec<(u8, u8, u8)> {
+    resolve_palette_table(&player.movie.cast_manager.palettes(), palette, 8)
+}
+
+/// Blend from the palette currently shown to `palette` over `duration_ms`.
+fn start_transition(player: &mut DirPlayer, palette: &PaletteRef, duration_ms: f64) {
+    let previous = player.palette_effects.shown.clone();
+    player.palette_effects.shown = Some(palette.clone());
+    let Some(previous) = previous.filter(|previous| previous != palette) else {
+        return;
+    };
+    if duration_ms <= 0.0 {
+        player.palette_effects.transition = None;
+        return;
+    }
+    let from = palette_colors(player, &previous);
+    player.palette_effects.transition = Some(PaletteTransition {
+        palette: palette.clone(),
+        from,
+        start_ms: now_ms(),
+        duration_ms,
+    });
+}
+
+/// Called when the playhead moves to `frame`; arms the frame's palette
+/// channel effects.
+pub fn start_frame_effects(player: &mut DirPlayer, frame: u32) {
+    let now = now_ms();
+    let data = player.movie.score.get_frame_palette_effect(frame).cloned().unwrap_or_default();
+    let palette = palette_for_frame(player, frame);
+
+    if let Some(color) = data.fade_color() {
+        player.palette_effects.fade = Some(PaletteFade {
+            color,
+            start_ms: now,
+            half_ms: fade_half_ms(data.speed),
+        });
+    }
+
+    // Keep a running cycle going across frames that repeat the same range.
+    let cycle = ColorCycle::from_channel(&data, now);
+    player.palette_effects.cycle = match (player.palette_effects.cycle.take(), cycle) {
+        (Some(running), Some(cycle)) if running.same_range(&cycle) => Some(running),
+        (_, cycle) => cycle,
+    };
+
+    let duration_ms = if data.fade_color().is_none() && !data.is_color_cycling() && data.speed > 0 {
+        fade_half_ms(data.speed)
+    } else {
+        0.0
+    };
+    start_transition(player, &palette, duration_ms);
+}
+
+/// `puppetPalette`: show `palette` (or hand control back to the score when
+/// `None`), blending to it over `frames` frames or at `speed`.
+pub fn set_puppet_palette(player: &mut DirPlayer, palette: Option<PaletteRef>, speed: u8, frames: u32) {
+    player.palette_effects.puppet = palette;
+    let palette = active_palette(player);
+    let duration_ms = if frames > 0 {
+        frames as f64 * 1000.0 / player.get_fps().max(1) as f64
+    } else if speed > 0 {
+        fade_half_ms(speed)
+    } else {
+        0.0
+    };
+    start_transition(player, &palette, duration_ms);
+    player.stage_dirty = true;
+}
+
+/// Advance the running effects and publish them to the cast manager.
+/// Renderers call this before drawing a frame.
+pub fn update(player: &mut DirPlayer) {
+    let now = now_ms();
+    let manager = &mut player.palette_effects;
+    let mut effect = PaletteEffect::default();
+
+    if let Some(cycle) = &manager.cycle {
+        let offset = cycle.offset(now);
+        if offset != 0 {
+            effect.cycles.push((cycle.first, cycle.last, offset));
+        }
+    }
+    if let Some(fade) = &manager.fade {
+        match fade.amount(now) {
+            Some(amount) => effect.fade = Some((fade.color, amount)),
+            None => manager.fade = None,
+        }
+    }
+    if let Some(transition) = &manager.transition {
+        let progress = transition.progress(now);
+        if progress >= 1.0 {
+            manager.transition = None;
+        } else {
+            effect.blend = Some(PaletteBlend {
+                palette: transition.palette.clone(),
+                from: transition.from.clone(),
+                amount: progress,
+            });
+        }
+    }
+
+    let animating = manager.fade.is_some()
+        || manager.transition.is_some()
+        || manager.cycle.as_ref().is_some_and(|cycle| !cycle.is_finished(now));
+    player.movie.cast_manager.set_palette_effect(effect);
+    if animating {
+        player.stage_dirty = true;
+    }
+}
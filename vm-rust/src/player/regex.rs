@@ -0,0 +1,1131 @@
+//! Backtracking regular expression engine.
+//!
+//! Implements the Perl-compatible subset that Lingo text processing relies
+//! on: character classes (including POSIX `[:alpha:]` names), greedy, lazy
+//! and counted quantifiers, capturing, non-capturing and named groups,
+//! backreferences, lookahead and lookbehind, anchors, word boundaries and
+//! the `i`, `m`, `s` and `x` modifiers (also inline, as `(?i)`).
+//!
+//! Positions are char indices, matching Lingo's `char` chunks. Patterns
+//! compile to a small program that is run with an explicit backtrack stack,
+//! so long subjects don't overflow the Rust stack. Matching is bounded by a
+//! step budget; a pathological pattern returns an error instead of hanging
+//! the movie.
+//!
+//! With [`RegexFlags::ecma`] set the engine follows ECMA-262 3rd edition
+//! instead, for the jsLingo `RegExp` object: `.`, `^` and `$` know all four
+//! line terminators, `$` only matches at the very end, `\w` and `\b` are
+//! ASCII-only and a backreference to a group that didn't take part matches
+//! the empty string.
+
+/// Steps after which a match attempt is abandoned.
+const MAX_STEPS: usize = 5_000_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RegexFlags {
+    pub ignore_case: bool,
+    pub multiline: bool,
+    pub dot_all: bool,
+    pub extended: bool,
+    /// ECMA-262 semantics (see the module docs).
+    pub ecma: bool,
+}
+
+impl RegexFlags {
+    /// Parse Perl-style modifier letters. Letters that don't affect
+    /// compilation, such as `g`, are ignored.
+    pub fn parse(flags: &str) -> RegexFlags {
+        let mut result = RegexFlags::default();
+        for flag in flags.chars() {
+            result.set(flag, true);
+        }
+        result
+    }
+
+    fn set(&mut self, flag: char, value: bool) -> bool {
+        match flag.to_ascii_lowercase() {
+            'i' => self.ignore_case = value,
+            'm' => self.multiline = value,
+            's' => self.dot_all = value,
+            'x' => self.extended = value,
+            _ => return false,
+        }
+        true
+    }
+}
+
+type CharPredicate = fn(char) -> bool;
+
+#[derive(Clone, Debug)]
+enum ClassItem {
+    Range(char, char),
+    Predicate(CharPredicate, bool),
+}
+
+#[derive(Clone, Debug)]
+struct CharClass {
+    items: Vec<ClassItem>,
+    negated: bool,
+}
+
+impl CharClass {
+    fn predicate(predicate: CharPredicate, negated: bool) -> CharClass {
+        CharClass {
+            items: vec![ClassItem::Predicate(predicate, false)],
+            negated,
+        }
+    }
+
+    fn contains(&self, c: char) -> bool {
+        let found = self.items.iter().any(|item| match item {
+            ClassItem::Range(from, to) => (*from..=*to).contains(&c),
+            ClassItem::Predicate(predicate, negated) => predicate(c) != *negated,
+        });
+        found != self.negated
+    }
+
+    fn matches(&self, c: char, ignore_case: bool) -> bool {
+        if self.contains(c) {
+            return true;
+        }
+        ignore_case && (self.contains(fold_case(c)) || c.to_uppercase().any(|upper| self.contains(upper)))
+    }
+}
+
+fn is_digit(c: char) -> bool {
+    c.is_ascii_digit()
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_ascii_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+fn is_space(c: char) -> bool {
+    c.is_whitespace()
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn chars_equal(a: char, b: char, ignore_case: bool) -> bool {
+    a == b || (ignore_case && fold_case(a) == fold_case(b))
+}
+
+fn posix_class(name: &str) -> Option<CharPredicate> {
+    Some(match name {
+        "alpha" => |c: char| c.is_alphabetic(),
+        "digit" => is_digit,
+        "alnum" => |c: char| c.is_alphanumeric(),
+        "space" => is_space,
+        "upper" => |c: char| c.is_uppercase(),
+        "lower" => |c: char| c.is_lowercase(),
+        "punct" => |c: char| c.is_ascii_punctuation(),
+        "xdigit" => |c: char| c.is_ascii_hexdigit(),
+        "word" => is_word,
+        "blank" => |c: char| c == ' ' || c == '\t',
+        "cntrl" => |c: char| c.is_control(),
+        "print" => |c: char| !c.is_control(),
+        "graph" => |c: char| !c.is_control() && !c.is_whitespace(),
+        _ => return None,
+    })
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Empty,
+    Char(char, bool),
+    Any(bool),
+    Class(CharClass, bool),
+    LineStart,
+    LineEnd,
+    TextStart,
+    TextEnd,
+    TextEndNewline,
+    WordBoundary(bool),
+    Group(Box<Node>, Option<usize>),
+    Look {
+        node: Box<Node>,
+        behind: bool,
+        negate: bool,
+    },
+    Backref(usize, bool),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+impl Node {
+    fn is_single_char(&self) -> bool {
+        matches!(self, Node::Char(..) | Node::Any(_) | Node::Class(..))
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    flags: RegexFlags,
+    group_count: usize,
+    group_names: Vec<(String, usize)>,
+}
+
+impl Parser {
+    fn error<T>(&self, message: &str) -> Result<T, String> {
+        Err(format!("{} at position {}", message, self.pos))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_extended(&mut self) {
+        if !self.flags.extended {
+            return;
+        }
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.pos += 1;
+            } else if c == '#' {
+                while let Some(c) = self.peek() {
+                    self.pos += 1;
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node, String> {
+        // Inline modifiers only last until the end of the enclosing group.
+        let flags = self.flags;
+        let mut alternatives = vec![self.parse_concat()?];
+        while self.eat('|') {
+            alternatives.push(self.parse_concat()?);
+        }
+        self.flags = flags;
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            Node::Alt(alternatives)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, String> {
+        let mut nodes = vec![];
+        loop {
+            self.skip_extended();
+            match self.peek() {
+                None | Some('|') | Some(')') => break,
+                _ => {}
+            }
+            let Some(atom) = self.parse_atom()? else {
+                continue;
+            };
+            self.skip_extended();
+            let node = self.parse_quantifier(atom)?;
+            nodes.push(node);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    /// Parse a `{n}`, `{n,}` or `{n,m}` quantifier at the current position.
+    /// Returns `None`, consuming nothing, if the brace is a literal.
+    fn parse_braces(&mut self) -> Option<(usize, Option<usize>)> {
+        let start = self.pos;
+        self.pos += 1;
+        let read_number = |parser: &mut Parser| {
+            let digits_start = parser.pos;
+            while parser.peek().is_some_and(|c| c.is_ascii_digit()) {
+                parser.pos += 1;
+            }
+            parser.chars[digits_start..parser.pos].iter().collect::<String>().parse::<usize>().ok()
+        };
+        let result = match read_number(self) {
+            Some(min) if self.eat('}') => Some((min, Some(min))),
+            Some(min) if self.eat(',') => {
+                let max = read_number(self);
+                self.eat('}').then_some((min, max))
+            }
+            _ => None,
+        };
+        if result.is_none() {
+            self.pos = start;
+        }
+        result
+    }
+
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('{') => match self.parse_braces() {
+                Some(bounds) => bounds,
+                None => return Ok(atom),
+            },
+            Some(c @ ('*' | '+' | '?')) => {
+                self.pos += 1;
+                match c {
+                    '*' => (0, None),
+                    '+' => (1, None),
+                    _ => (0, Some(1)),
+                }
+            }
+            _ => return Ok(atom),
+        };
+        if max.is_some_and(|max| max < min) {
+            return self.error("Quantifier range out of order");
+        }
+        if matches!(
+            atom,
+            Node::LineStart | Node::LineEnd | Node::TextStart | Node::TextEnd | Node::TextEndNewline | Node::WordBoundary(_)
+        ) {
+            return self.error("Nothing to repeat");
+        }
+        let greedy = !self.eat('?');
+        // Possessive quantifiers are treated as greedy.
+        self.eat('+');
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<Option<Node>, String> {
+        let c = self.peek().unwrap();
+        self.pos += 1;
+        let node = match c {
+            '(' => return self.parse_group(),
+            '[' => Node::Class(self.parse_class()?, self.flags.ignore_case),
+            '.' if self.flags.ecma && !self.flags.dot_all => {
+                Node::Class(CharClass::predicate(is_line_terminator, true), false)
+            }
+            '.' => Node::Any(self.flags.dot_all),
+            '^' if self.flags.multiline => Node::LineStart,
+            '^' => Node::TextStart,
+            '$' if self.flags.multiline => Node::LineEnd,
+            '$' if self.flags.ecma => Node::TextEnd,
+            '$' => Node::TextEndNewline,
+            '\\' => self.parse_escape()?,
+            '*' | '+' | '?' => {
+                self.pos -= 1;
+                return self.error("Nothing to repeat");
+            }
+            '{' => {
+                self.pos -= 1;
+                if self.parse_braces().is_some() {
+                    return self.error("Nothing to repeat");
+                }
+                self.pos += 1;
+                Node::Char('{', false)
+            }
+            c => Node::Char(c, self.flags.ignore_case),
+        };
+        Ok(Some(node))
+    }
+
+    fn parse_group(&mut self) -> Result<Option<Node>, String> {
+        let mut capture = true;
+        let mut name = None;
+        let mut look = None;
+        if self.eat('?') {
+            match self.peek() {
+                Some(':') => {
+                    self.pos += 1;
+                    capture = false;
+                }
+                Some('=') | Some('!') => {
+                    look = Some((false, self.peek() == Some('!')));
+                    self.pos += 1;
+                    capture = false;
+                }
+                Some('<') if matches!(self.peek_at(1), Some('=') | Some('!')) => {
+                    look = Some((true, self.peek_at(1) == Some('!')));
+                    self.pos += 2;
+                    capture = false;
+                }
+                Some('<') | Some('P') | Some('\'') => {
+                    if self.peek() == Some('P') {
+                        self.pos += 1;
+                    }
+                    let close = if self.eat('\'') {
+                        '\''
+                    } else if self.eat('<') {
+                        '>'
+                    } else {
+                        return self.error("Invalid group name");
+                    };
+                    let start = self.pos;
+                    while self.peek().is_some_and(|c| c != close) {
+                        self.pos += 1;
+                    }
+                    let group_name: String = self.chars[start..self.pos].iter().collect();
+                    if !self.eat(close) || group_name.is_empty() {
+                        return self.error("Invalid group name");
+                    }
+                    name = Some(group_name);
+                }
+                _ => {
+                    // Inline modifiers: (?imsx-imsx) or (?imsx-imsx:...)
+                    let mut flags = self.flags;
+                    let mut value = true;
+                    loop {
+                        match self.peek() {
+                            Some('-') => value = false,
+                            Some(')') => {
+                                self.pos += 1;
+                                self.flags = flags;
+                                return Ok(None);
+                            }
+                            Some(':') => break,
+                            Some(c) if flags.set(c, value) => {}
+                            _ => return self.error("Unknown group type"),
+                        }
+                        self.pos += 1;
+                    }
+                    self.pos += 1;
+                    let outer = self.flags;
+                    self.flags = flags;
+                    let inner = self.parse_alternation()?;
+                    self.flags = outer;
+                    if !self.eat(')') {
+                        return self.error("Missing )");
+                    }
+                    return Ok(Some(Node::Group(Box::new(inner), None)));
+                }
+            }
+        }
+
+        let index = if capture {
+            self.group_count += 1;
+            if let Some(name) = name {
+                self.group_names.push((name, self.group_count));
+            }
+            Some(self.group_count)
+        } else {
+            None
+        };
+        let inner = self.parse_alternation()?;
+        if !self.eat(')') {
+            return self.error("Missing )");
+        }
+        Ok(Some(match look {
+            Some((behind, negate)) => Node::Look {
+                node: Box::new(inner),
+                behind,
+                negate,
+            },
+            None => Node::Group(Box::new(inner), index),
+        }))
+    }
+
+    fn parse_hex(&mut self, digits: usize) -> Result<char, String> {
+        let braced = self.eat('{');
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) && (braced || self.pos - start < digits) {
+            self.pos += 1;
+        }
+        let hex: String = self.chars[start..self.pos].iter().collect();
+        if braced && !self.eat('}') {
+            return self.error("Invalid hex escape");
+        }
+        let value = u32::from_str_radix(&hex, 16).unwrap_or(0);
+        char::from_u32(value).map_or_else(|| self.error("Invalid hex escape"), Ok)
+    }
+
+    /// Escapes that stand for a single character, shared by classes and atoms.
+    fn parse_char_escape(&mut self, c: char) -> Result<char, String> {
+        Ok(match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'f' => '\x0C',
+            'v' => '\x0B',
+            'e' => '\x1B',
+            'a' => '\x07',
+            '0' => '\0',
+            'x' => self.parse_hex(2)?,
+            'u' => self.parse_hex(4)?,
+            c => c,
+        })
+    }
+
+    fn class_escape(&self, c: char) -> Option<(CharPredicate, bool)> {
+        let word: CharPredicate = if self.flags.ecma { is_ascii_word } else { is_word };
+        match c {
+            'd' => Some((is_digit, false)),
+            'D' => Some((is_digit, true)),
+            'w' => Some((word, false)),
+            'W' => Some((word, true)),
+            's' => Some((is_space, false)),
+            'S' => Some((is_space, true)),
+            _ => None,
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<Node, String> {
+        let Some(c) = self.peek() else {
+            return self.error("Trailing backslash");
+        };
+        self.pos += 1;
+        let ignore_case = self.flags.ignore_case;
+        if let Some((predicate, negated)) = self.class_escape(c) {
+            return Ok(Node::Class(CharClass::predicate(predicate, negated), false));
+        }
+        Ok(match c {
+            'b' => Node::WordBoundary(true),
+            'B' => Node::WordBoundary(false),
+            'A' => Node::TextStart,
+            'z' => Node::TextEnd,
+            'Z' => Node::TextEndNewline,
+            '1'..='9' => {
+                let mut index = c.to_digit(10).unwrap() as usize;
+                while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+                    let next = index * 10 + digit as usize;
+                    if next > self.group_count {
+                        break;
+                    }
+                    index = next;
+                    self.pos += 1;
+                }
+                Node::Backref(index, ignore_case)
+            }
+            'k' => {
+                let close = match self.peek() {
+                    Some('<') => '>',
+                    Some('{') => '}',
+                    Some('\'') => '\'',
+                    _ => return self.error("Invalid named backreference"),
+                };
+                self.pos += 1;
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c != close) {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                self.eat(close);
+                match self.group_names.iter().find(|(group_name, _)| *group_name == name) {
+                    Some((_, index)) => Node::Backref(*index, ignore_case),
+                    None => return self.error("Unknown group name"),
+                }
+            }
+            c => Node::Char(self.parse_char_escape(c)?, ignore_case),
+        })
+    }
+
+    fn parse_class_char(&mut self) -> Result<Option<char>, String> {
+        match self.peek() {
+            None => self.error("Missing ]"),
+            Some('\\') => {
+                let c = self.peek_at(1);
+                if c.is_some_and(|c| self.class_escape(c).is_some()) {
+                    return Ok(None);
+                }
+                self.pos += 2;
+                match c {
+                    Some('b') => Ok(Some('\x08')),
+                    Some(c) => self.parse_char_escape(c).map(Some),
+                    None => self.error("Missing ]"),
+                }
+            }
+            Some(c) => {
+                self.pos += 1;
+                Ok(Some(c))
+            }
+        }
+    }
+
+    fn parse_class(&mut self) -> Result<CharClass, String> {
+        let negated = self.eat('^');
+        let mut items = vec![];
+        let mut first = true;
+        loop {
+            match self.peek() {
+                None => return self.error("Missing ]"),
+                Some(']') if !first => {
+                    self.pos += 1;
+                    break;
+                }
+                Some('[') if self.peek_at(1) == Some(':') => {
+                    let start = self.pos + 2;
+                    let end = (start..self.chars.len()).find(|&i| self.chars[i] == ':');
+                    if let Some(end) = end.filter(|&end| self.chars.get(end + 1) == Some(&']')) {
+                        let name: String = self.chars[start..end].iter().collect();
+                        let (name, negated) = match name.strip_prefix('^') {
+                            Some(name) => (name.to_string(), true),
+                            None => (name, false),
+                        };
+                        let Some(predicate) = posix_class(&name) else {
+                            return self.error("Unknown POSIX class");
+                        };
+                        items.push(ClassItem::Predicate(predicate, negated));
+                        self.pos = end + 2;
+                        first = false;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            first = false;
+
+            let Some(from) = self.parse_class_char()? else {
+                self.pos += 1;
+                let (predicate, negated) = self.class_escape(self.peek().unwrap()).unwrap();
+                self.pos += 1;
+                items.push(ClassItem::Predicate(predicate, negated));
+                continue;
+            };
+            if self.peek() == Some('-') && self.peek_at(1).is_some_and(|c| c != ']') {
+                let start = self.pos;
+                self.pos += 1;
+                match self.parse_class_char()? {
+                    Some(to) if to >= from => {
+                        items.push(ClassItem::Range(from, to));
+                        continue;
+                    }
+                    Some(_) => return self.error("Character class range out of order"),
+                    None => self.pos = start,
+                }
+            }
+            items.push(ClassItem::Range(from, from));
+        }
+        Ok(CharClass { items, negated })
+    }
+}
+
+/// Capture positions of a successful match, as char index ranges. Group 0
+/// is the whole match; groups that did not take part are `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Match {
+    pub groups: Vec<Option<(usize, usize)>>,
+}
+
+impl Match {
+    pub fn start(&self) -> usize {
+        self.groups[0].unwrap().0
+    }
+
+    pub fn end(&self) -> usize {
+        self.groups[0].unwrap().1
+    }
+
+    pub fn group(&self, index: usize) -> Option<(usize, usize)> {
+        self.groups.get(index).copied().flatten()
+    }
+
+    pub fn group_text(&self, text: &[char], index: usize) -> Option<String> {
+        self.group(index).map(|(start, end)| text[start..end].iter().collect())
+    }
+}
+
+type Captures = Vec<Option<(usize, usize)>>;
+
+/// One instruction of a compiled pattern. Jump targets are indices into
+/// the program.
+#[derive(Clone, Debug)]
+enum Inst {
+    /// Consume one char matching a `Char`, `Any` or `Class` node.
+    Single(Node),
+    /// Zero-width anchor or word boundary.
+    Assert(Node),
+    /// Continue at `first`, backtracking to `second`.
+    Split { first: usize, second: usize },
+    Jump(usize),
+    GroupStart(usize),
+    GroupEnd(usize),
+    Backref(usize, bool),
+    /// Lookaround whose body follows, up to a `LookMatch`, continuing at
+    /// `next` afterwards.
+    Look { behind: bool, negate: bool, next: usize },
+    LookMatch,
+    /// Repetition of a single-char node, matched without a backtrack entry
+    /// per char.
+    SingleRepeat { node: Node, min: usize, max: Option<usize>, greedy: bool },
+    /// Reset the iteration counter of a repetition.
+    RepeatInit(usize),
+    /// Decide between another iteration (the `RepeatBody` that follows)
+    /// and leaving the loop at `exit`.
+    RepeatLoop { counter: usize, min: usize, max: Option<usize>, greedy: bool, exit: usize },
+    /// Remember where an iteration started.
+    RepeatBody(usize),
+    /// Count a finished iteration and go back to the `RepeatLoop` at `repeat`.
+    RepeatEnd { counter: usize, min: usize, repeat: usize },
+    Match,
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+    counters: usize,
+}
+
+impl Compiler {
+    fn emit(&mut self, inst: Inst) -> usize {
+        self.program.push(inst);
+        self.program.len() - 1
+    }
+
+    fn compile(&mut self, node: &Node) {
+        match node {
+            Node::Empty => {}
+            Node::Char(..) | Node::Any(_) | Node::Class(..) => {
+                self.emit(Inst::Single(node.clone()));
+            }
+            Node::LineStart
+            | Node::LineEnd
+            | Node::TextStart
+            | Node::TextEnd
+            | Node::TextEndNewline
+            | Node::WordBoundary(_) => {
+                self.emit(Inst::Assert(node.clone()));
+            }
+            Node::Group(inner, None) => self.compile(inner),
+            Node::Group(inner, Some(index)) => {
+                self.emit(Inst::GroupStart(*index));
+                self.compile(inner);
+                self.emit(Inst::GroupEnd(*index));
+            }
+            Node::Look { node, behind, negate } => {
+                let look = self.emit(Inst::Look { behind: *behind, negate: *negate, next: 0 });
+                self.compile(node);
+                self.emit(Inst::LookMatch);
+                let end = self.program.len();
+                if let Inst::Look { next, .. } = &mut self.program[look] {
+                    *next = end;
+                }
+            }
+            Node::Backref(index, ignore_case) => {
+                self.emit(Inst::Backref(*index, *ignore_case));
+            }
+            Node::Concat(nodes) => nodes.iter().for_each(|node| self.compile(node)),
+            Node::Alt(alternatives) => {
+                let mut jumps = vec![];
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i + 1 == alternatives.len() {
+                        self.compile(alternative);
+                        break;
+                    }
+                    let split = self.emit(Inst::Split { first: self.program.len() + 1, second: 0 });
+                    self.compile(alternative);
+                    jumps.push(self.emit(Inst::Jump(0)));
+                    let next = self.program.len();
+                    if let Inst::Split { second, .. } = &mut self.program[split] {
+                        *second = next;
+                    }
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat { node, min, max, greedy } if node.is_single_char() => {
+                self.emit(Inst::SingleRepeat { node: (**node).clone(), min: *min, max: *max, greedy: *greedy });
+            }
+            Node::Repeat { node, min, max, greedy } => {
+                let counter = self.counters;
+                self.counters += 1;
+                self.emit(Inst::RepeatInit(counter));
+                let repeat = self.emit(Inst::RepeatLoop { counter, min: *min, max: *max, greedy: *greedy, exit: 0 });
+                self.emit(Inst::RepeatBody(counter));
+                self.compile(node);
+                self.emit(Inst::RepeatEnd { counter, min: *min, repeat });
+                let end = self.program.len();
+                if let Inst::RepeatLoop { exit, .. } = &mut self.program[repeat] {
+                    *exit = end;
+                }
+            }
+        }
+    }
+}
+
+/// What to undo, or where to resume, when a path fails.
+enum Backtrack {
+    Resume { pc: usize, pos: usize },
+    /// Resume a `SingleRepeat` at `pc` having consumed `count` chars from
+    /// `start`, the next count to try when this one fails lying towards
+    /// `last`.
+    SingleRepeat { pc: usize, start: usize, count: usize, last: usize },
+    Capture(usize, Option<(usize, usize)>),
+    GroupStart(usize, usize),
+    /// Iteration count and iteration start of a repetition.
+    Counter(usize, (usize, usize)),
+}
+
+struct Matcher<'t> {
+    text: &'t [char],
+    program: &'t [Inst],
+    steps: usize,
+    ecma: bool,
+    group_starts: Vec<usize>,
+    counters: Vec<(usize, usize)>,
+}
+
+impl<'t> Matcher<'t> {
+    fn new(regex: &'t Regex, text: &'t [char]) -> Matcher<'t> {
+        Matcher {
+            text,
+            program: &regex.program,
+            steps: 0,
+            ecma: regex.ecma,
+            group_starts: vec![0; regex.group_count + 1],
+            counters: vec![(0, 0); regex.counters],
+        }
+    }
+
+    fn single(&self, node: &Node, pos: usize) -> bool {
+        let Some(&c) = self.text.get(pos) else {
+            return false;
+        };
+        match node {
+            Node::Char(expected, ignore_case) => chars_equal(c, *expected, *ignore_case),
+            Node::Any(dot_all) => *dot_all || c != '\n',
+            Node::Class(class, ignore_case) => class.matches(c, *ignore_case),
+            _ => false,
+        }
+    }
+
+    fn is_word_at(&self, pos: usize) -> bool {
+        let word = if self.ecma { is_ascii_word } else { is_word };
+        self.text.get(pos).is_some_and(|&c| word(c))
+    }
+
+    fn is_line_break(&self, c: char) -> bool {
+        if self.ecma { is_line_terminator(c) } else { c == '\n' }
+    }
+
+    fn assertion(&self, node: &Node, pos: usize) -> bool {
+        let len = self.text.len();
+        match node {
+            Node::LineStart => pos == 0 || self.is_line_break(self.text[pos - 1]),
+            Node::LineEnd => pos == len || self.is_line_break(self.text[pos]),
+            Node::TextStart => pos == 0,
+            Node::TextEnd => pos == len,
+            Node::TextEndNewline => pos == len || (pos + 1 == len && self.text[pos] == '\n'),
+            Node::WordBoundary(expected) => {
+                let before = pos > 0 && self.is_word_at(pos - 1);
+                (before != self.is_word_at(pos)) == *expected
+            }
+            _ => false,
+        }
+    }
+
+    fn backref(&self, index: usize, ignore_case: bool, pos: usize, caps: &Captures) -> Option<usize> {
+        let Some((start, end)) = caps.get(index).copied().flatten() else {
+            return self.ecma.then_some(pos);
+        };
+        let len = end - start;
+        if pos + len > self.text.len() {
+            return None;
+        }
+        (0..len)
+            .all(|i| chars_equal(self.text[pos + i], self.text[start + i], ignore_case))
+            .then_some(pos + len)
+    }
+
+    /// Run the program from `pc` at `pos` until it reaches `Match` or
+    /// `LookMatch`, returning the end position. Backtracking uses an
+    /// explicit stack, so the Rust stack only grows with lookaround
+    /// nesting. A lookbehind body passes `end` to only accept matches that
+    /// finish there. On failure `caps` is left as it was.
+    fn run(&mut self, mut pc: usize, mut pos: usize, caps: &mut Captures, end: Option<usize>) -> Result<Option<usize>, String> {
+        let program = self.program;
+        let mut stack: Vec<Backtrack> = vec![];
+        loop {
+            self.steps += 1;
+            if self.steps > MAX_STEPS {
+                return Err("pattern too complex: step limit exceeded".to_string());
+            }
+            let advanced = match &program[pc] {
+                Inst::Single(node) => {
+                    let matched = self.single(node, pos);
+                    pc += 1;
+                    pos += 1;
+                    matched
+                }
+                Inst::Assert(node) => {
+                    pc += 1;
+                    self.assertion(node, pos)
+                }
+                Inst::Split { first, second } => {
+                    stack.push(Backtrack::Resume { pc: *second, pos });
+                    pc = *first;
+                    true
+                }
+                Inst::Jump(target) => {
+                    pc = *target;
+                    true
+                }
+                Inst::GroupStart(index) => {
+                    stack.push(Backtrack::GroupStart(*index, self.group_starts[*index]));
+                    self.group_starts[*index] = pos;
+                    pc += 1;
+                    true
+                }
+                Inst::GroupEnd(index) => {
+                    stack.push(Backtrack::Capture(*index, caps[*index]));
+                    caps[*index] = Some((self.group_starts[*index], pos));
+                    pc += 1;
+                    true
+                }
+                Inst::Backref(index, ignore_case) => match self.backref(*index, *ignore_case, pos, caps) {
+                    Some(next) => {
+                        pc += 1;
+                        pos = next;
+                        true
+                    }
+                    None => false,
+                },
+                Inst::Look { behind, negate, next } => {
+                    let mut inner_caps = caps.clone();
+                    let mut matched = false;
+                    if *behind {
+                        for start in (0..=pos).rev() {
+                            if self.run(pc + 1, start, &mut inner_caps, Some(pos))?.is_some() {
+                                matched = true;
+                                break;
+                            }
+                        }
+                    } else {
+                        matched = self.run(pc + 1, pos, &mut inner_caps, None)?.is_some();
+                    }
+                    pc = *next;
+                    if matched && !*negate {
+                        for (index, value) in inner_caps.into_iter().enumerate() {
+                            if caps[index] != value {
+                                stack.push(Backtrack::Capture(index, caps[index]));
+                                caps[index] = value;
+                            }
+                        }
+                    }
+                    matched != *negate
+                }
+                Inst::LookMatch | Inst::Match => {
+                    if end.is_none_or(|end| end == pos) {
+                        return Ok(Some(pos));
+                    }
+                    false
+                }
+                Inst::SingleRepeat { node, min, max, greedy } => {
+                    let limit = max.unwrap_or(usize::MAX);
+                    let mut count = 0;
+                    while count < limit && self.single(node, pos + count) {
+                        count += 1;
+                    }
+                    pc += 1;
+                    if count < *min {
+                        false
+                    } else {
+                        let (first, last) = if *greedy { (count, *min) } else { (*min, count) };
+                        if first != last {
+                            let next = if *greedy { first - 1 } else { first + 1 };
+                            stack.push(Backtrack::SingleRepeat { pc, start: pos, count: next, last });
+                        }
+                        pos += first;
+                        true
+                    }
+                }
+                Inst::RepeatInit(counter) => {
+                    stack.push(Backtrack::Counter(*counter, self.counters[*counter]));
+                    self.counters[*counter] = (0, pos);
+                    pc += 1;
+                    true
+                }
+                Inst::RepeatLoop { counter, min, max, greedy, exit } => {
+                    let count = self.counters[*counter].0;
+                    let can_exit = count >= *min;
+                    let can_repeat = max.is_none_or(|max| count < max);
+                    match (can_repeat, can_exit) {
+                        (false, false) => false,
+                        (false, true) => {
+                            pc = *exit;
+                            true
+                        }
+                        (true, false) => {
+                            pc += 1;
+                            true
+                        }
+                        (true, true) => {
+                            if *greedy {
+                                stack.push(Backtrack::Resume { pc: *exit, pos });
+                                pc += 1;
+                            } else {
+                                stack.push(Backtrack::Resume { pc: pc + 1, pos });
+                                pc = *exit;
+                            }
+                            true
+                        }
+                    }
+                }
+                Inst::RepeatBody(counter) => {
+                    stack.push(Backtrack::Counter(*counter, self.counters[*counter]));
+                    self.counters[*counter].1 = pos;
+                    pc += 1;
+                    true
+                }
+                Inst::RepeatEnd { counter, min, repeat } => {
+                    let (count, start) = self.counters[*counter];
+                    // An iteration that consumes nothing can't make progress
+                    // once the minimum is met, so stop there instead of
+                    // looping forever.
+                    if pos == start && count >= *min {
+                        false
+                    } else {
+                        stack.push(Backtrack::Counter(*counter, self.counters[*counter]));
+                        self.counters[*counter].0 = count + 1;
+                        pc = *repeat;
+                        true
+                    }
+                }
+            };
+            if advanced {
+                continue;
+            }
+            loop {
+                match stack.pop() {
+                    None => return Ok(None),
+                    Some(Backtrack::Resume { pc: resume_pc, pos: resume_pos }) => {
+                        pc = resume_pc;
+                        pos = resume_pos;
+                        break;
+                    }
+                    Some(Backtrack::SingleRepeat { pc: resume_pc, start, count, last }) => {
+                        if count != last {
+                            let next = if count > last { count - 1 } else { count + 1 };
+                            stack.push(Backtrack::SingleRepeat { pc: resume_pc, start, count: next, last });
+                        }
+                        pc = resume_pc;
+                        pos = start + count;
+                        break;
+                    }
+                    Some(Backtrack::Capture(index, value)) => caps[index] = value,
+                    Some(Backtrack::GroupStart(index, value)) => self.group_starts[index] = value,
+                    Some(Backtrack::Counter(counter, value)) => self.counters[counter] = value,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Regex {
+    program: Vec<Inst>,
+    counters: usize,
+    group_count: usize,
+    group_names: Vec<(String, usize)>,
+    ecma: bool,
+}
+
+impl Regex {
+    pub fn new(pattern: &str, flags: RegexFlags) -> Result<Regex, String> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            flags,
+            group_count: 0,
+            group_names: vec![],
+        };
+        let root = parser.parse_alternation()?;
+        if parser.pos < parser.chars.len() {
+            return parser.error("Unmatched )");
+        }
+        let mut compiler = Compiler { program: vec![], counters: 0 };
+        compiler.compile(&root);
+        compiler.emit(Inst::Match);
+        Ok(Regex {
+            program: compiler.program,
+            counters: compiler.counters,
+            group_count: parser.group_count,
+            group_names: parser.group_names,
+            ecma: flags.ecma,
+        })
+    }
+
+    /// Number of capturing groups, not counting the whole match.
+    pub fn group_count(&self) -> usize {
+        self.group_count
+    }
+
+    pub fn group_names(&self) -> &[(String, usize)] {
+        &self.group_names
+    }
+
+    fn match_with(&self, matcher: &mut Matcher, pos: usize) -> Result<Option<Match>, String> {
+        let mut caps: Captures = vec![None; self.group_count + 1];
+        Ok(matcher.run(0, pos, &mut caps, None)?.map(|end| {
+            caps[0] = Some((pos, end));
+            Match { groups: caps }
+        }))
+    }
+
+    /// Match anchored at exactly `pos`. Fails if the step budget runs out.
+    pub fn match_at(&self, text: &[char], pos: usize) -> Result<Option<Match>, String> {
+        self.match_with(&mut Matcher::new(self, text), pos)
+    }
+
+    /// First match starting at or after `start`. Fails if the step budget
+    /// runs out.
+    pub fn find_at(&self, text: &[char], start: usize) -> Result<Option<Match>, String> {
+        let mut matcher = Matcher::new(self, text);
+        for pos in start..=text.len() {
+            if let Some(found) = self.match_with(&mut matcher, pos)? {
+                return Ok(Some(found));
+            }
+        }
+        Ok(None)
+    }
+
+    /// All non-overlapping matches from `start`, advancing past empty
+    /// matches one character at a time.
+    pub fn find_all(&self, text: &[char], start: usize) -> Result<Vec<Match>, String> {
+        let mut matches = vec![];
+        let mut pos = start;
+        while pos <= text.len() {
+            let Some(found) = self.find_at(text, pos)? else {
+                break;
+            };
+            pos = if found.end() == found.start() {
+                found.end() + 1
+            } else {
+                found.end()
+            };
+            matches.push(found);
+        }
+        Ok(matches)
+    }
+
+    pub fn is_match(&self, text: &str) -> Result<bool, String> {
+        let chars: Vec<char> = text.chars().collect();
+        Ok(self.find_at(&chars, 0)?.is_some())
+    }
+}
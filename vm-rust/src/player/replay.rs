@@ -0,0 +1,506 @@
+//! Deterministic input recording and replay.
+//!
+//! A recording captures every external input that reaches the player (mouse,
+//! keys, IME, stage size, host timeouts and network results) with the frame
+//! it arrived on, plus the random seeds and the clock the run started at.
+//! Replaying the file drives a freshly loaded movie through the same run.
+//!
+//! While a session is active, time comes from a virtual clock that moves a
+//! fixed step per frame instead of the wall clock, so `the ticks`, `the
+//! timer`, timeouts and tempo waits read the same values on every run.
+
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+
+use async_std::sync::Mutex;
+use base64::Engine;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{datetime_from_ms, datetime_to_ms};
+
+use super::{
+    commands::{apply_input_state, apply_player_command, PlayerVMCommand},
+    keyboard_events,
+    net_backend::{NetBackend, NetEvent},
+    net_manager::NetManagerSharedState,
+    net_task::{NetResult, NetTask},
+    reserve_player_mut, testing_shared, DirPlayer, ScriptErrorCode,
+};
+
+pub const REPLAY_FORMAT: &str = "dirplayer-replay";
+pub const REPLAY_VERSION: u32 = 1;
+
+/// An input from outside the movie.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ReplayInput {
+    MouseDown { x: i32, y: i32 },
+    MouseUp { x: i32, y: i32 },
+    MouseMove { x: i32, y: i32 },
+    RightMouseDown { x: i32, y: i32 },
+    RightMouseUp { x: i32, y: i32 },
+    KeyDown { key: String, code: u16 },
+    KeyUp { key: String, code: u16 },
+    ActivateApplication,
+    DeactivateApplication,
+    SetStageSize { width: u32, height: u32 },
+    /// A timeout fired by the host's timer (browser only).
+    TimeoutTriggered { name: String },
+    ImeCompositionStart,
+    ImeCompositionUpdate { text: String },
+    ImeCompositionEnd { text: String },
+    PasteText { text: String },
+    /// A network task finished. `data` is base64, `error` the net error code.
+    NetTaskDone { task_id: u32, data: Option<String>, error: Option<i32> },
+}
+
+impl ReplayInput {
+    /// The input carried by `command`, if it is one.
+    pub fn from_command(command: &PlayerVMCommand) -> Option<ReplayInput> {
+        let input = match command {
+            PlayerVMCommand::MouseDown((x, y)) => ReplayInput::MouseDown { x: *x, y: *y },
+            PlayerVMCommand::MouseUp((x, y)) => ReplayInput::MouseUp { x: *x, y: *y },
+            PlayerVMCommand::MouseMove((x, y)) => ReplayInput::MouseMove { x: *x, y: *y },
+            PlayerVMCommand::RightMouseDown((x, y)) => ReplayInput::RightMouseDown { x: *x, y: *y },
+            PlayerVMCommand::RightMouseUp((x, y)) => ReplayInput::RightMouseUp { x: *x, y: *y },
+            PlayerVMCommand::KeyDown(key, code) => ReplayInput::KeyDown { key: key.clone(), code: *code },
+            PlayerVMCommand::KeyUp(key, code) => ReplayInput::KeyUp { key: key.clone(), code: *code },
+            PlayerVMCommand::ActivateApplication => ReplayInput::ActivateApplication,
+            PlayerVMCommand::DeactivateApplication => ReplayInput::DeactivateApplication,
+            PlayerVMCommand::SetStageSize(width, height) => {
+                ReplayInput::SetStageSize { width: *width, height: *height }
+            }
+            PlayerVMCommand::TimeoutTriggered(name) => ReplayInput::TimeoutTriggered { name: name.clone() },
+            _ => return None,
+        };
+        Some(input)
+    }
+
+    fn into_command(self) -> Option<PlayerVMCommand> {
+        let command = match self {
+            ReplayInput::MouseDown { x, y } => PlayerVMCommand::MouseDown((x, y)),
+            ReplayInput::MouseUp { x, y } => PlayerVMCommand::MouseUp((x, y)),
+            ReplayInput::MouseMove { x, y } => PlayerVMCommand::MouseMove((x, y)),
+            ReplayInput::RightMouseDown { x, y } => PlayerVMCommand::RightMouseDown((x, y)),
+            ReplayInput::RightMouseUp { x, y } => PlayerVMCommand::RightMouseUp((x, y)),
+            ReplayInput::KeyDown { key, code } => PlayerVMCommand::KeyDown(key, code),
+            ReplayInput::KeyUp { key, code } => PlayerVMCommand::KeyUp(key, code),
+            ReplayInput::ActivateApplication => PlayerVMCommand::ActivateApplication,
+            ReplayInput::DeactivateApplication => PlayerVMCommand::DeactivateApplication,
+            ReplayInput::SetStageSize { width, height } => PlayerVMCommand::SetStageSize(width, height),
+            ReplayInput::TimeoutTriggered { name } => PlayerVMCommand::TimeoutTriggered(name),
+            _ => return None,
+        };
+        Some(command)
+    }
+
+    fn net_task_done(task_id: u32, result: &NetResult) -> ReplayInput {
+        let (data, error) = match result {
+            Ok(bytes) => (Some(base64::engine::general_purpose::STANDARD.encode(bytes)), None),
+            Err(code) => (None, Some(*code)),
+        };
+        ReplayInput::NetTaskDone { task_id, data, error }
+    }
+}
+
+/// One recorded input. It is applied at the start of the first frame after
+/// `frame`, except for network results, which are applied when the network
+/// is polled during `frame`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayEvent {
+    pub frame: u32,
+    /// The virtual clock when the input arrived.
+    pub clock_ms: f64,
+    pub input: ReplayInput,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayFile {
+    pub format: String,
+    pub version: u32,
+    /// Seed of the generator behind `random()`.
+    pub rng_seed: u64,
+    /// `the randomSeed` when recording started.
+    pub random_seed: Option<i32>,
+    /// The virtual clock when recording started (ms since the epoch).
+    pub start_ms: f64,
+    /// How far the virtual clock moves each frame.
+    pub frame_ms: f64,
+    /// `the milliSeconds` and `the timer` (in ms) when recording started.
+    pub system_elapsed_ms: f64,
+    pub timer_elapsed_ms: f64,
+    /// Whether timeouts were fired by the host (and so are in `events`)
+    /// rather than by the player's clock.
+    pub host_timeouts: bool,
+    /// Frames run while recording.
+    pub frame_count: u32,
+    pub events: Vec<ReplayEvent>,
+}
+
+impl ReplayFile {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<ReplayFile, String> {
+        let file: ReplayFile =
+            serde_json::from_slice(bytes).map_err(|err| format!("Invalid replay file: {}", err))?;
+        if file.format != REPLAY_FORMAT {
+            return Err(format!("Not a replay file (format '{}')", file.format));
+        }
+        if file.version > REPLAY_VERSION {
+            return Err(format!("Unsupported replay version {}", file.version));
+        }
+        Ok(file)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Recording,
+    Replaying,
+}
+
+struct Session {
+    mode: Mode,
+    /// Frames started since the session began.
+    frame: u32,
+    clock_ms: f64,
+    /// The file being recorded, or the one being replayed (with the inputs
+    /// still to apply in `pending`).
+    file: ReplayFile,
+    pending: VecDeque<ReplayEvent>,
+    /// The network backend and timeout replaced for the replay.
+    saved_net: Option<(Box<dyn NetBackend>, Option<f64>)>,
+}
+
+thread_local! {
+    static SESSION: RefCell<Option<Session>> = const { RefCell::new(None) };
+}
+
+fn with_session<T>(f: impl FnOnce(&mut Session) -> T) -> Option<T> {
+    SESSION.with(|session| session.borrow_mut().as_mut().map(f))
+}
+
+fn mode() -> Option<Mode> {
+    with_session(|session| session.mode)
+}
+
+pub fn is_recording() -> bool {
+    mode() == Some(Mode::Recording)
+}
+
+pub fn is_replaying() -> bool {
+    mode() == Some(Mode::Replaying)
+}
+
+/// The virtual clock, while a session is active.
+pub fn virtual_now_ms() -> Option<f64> {
+    with_session(|session| session.clock_ms)
+}
+
+/// Frames started since the session began (0 without a session).
+pub fn current_frame() -> u32 {
+    with_session(|session| session.frame).unwrap_or(0)
+}
+
+/// Whether a replay finished applying its recorded inputs and frames.
+pub fn is_replay_finished() -> bool {
+    with_session(|session| {
+        session.mode == Mode::Replaying
+            && session.pending.is_empty()
+            && session.frame >= session.file.frame_count
+    })
+    .unwrap_or(false)
+}
+
+/// Drop any session, e.g. when the player is replaced.
+pub fn reset() {
+    SESSION.with(|session| session.borrow_mut().take());
+}
+
+/// Move the player's timestamps by `delta_ms` so the times elapsed since
+/// them stay the same when the clock jumps.
+fn shift_clock(player: &mut DirPlayer, delta_ms: f64) {
+    let delta = chrono::Duration::microseconds((delta_ms * 1000.0).round() as i64);
+    player.system_start_time += delta;
+    player.start_time += delta;
+    if player.last_mouse_down_time > 0 {
+        player.last_mouse_down_time += delta_ms as i64;
+    }
+    if let Some(until) = player.delay_until.as_mut() {
+        *until += delta;
+    }
+    for timeout in player.timeout_manager.timeouts.values_mut() {
+        timeout.next_fire_ms += delta_ms;
+    }
+}
+
+fn frame_ms(player: &DirPlayer) -> f64 {
+    let tempo = player.movie.get_effective_tempo();
+    if tempo > 0 { 1000.0 / tempo as f64 } else { 1000.0 / 30.0 }
+}
+
+/// Start recording inputs, reseeding `random()` with `rng_seed`.
+pub fn start_recording(player: &mut DirPlayer, rng_seed: u64) {
+    stop_replay(player);
+    let start_ms = testing_shared::now_ms();
+    player.rng = rand::rngs::SmallRng::seed_from_u64(rng_seed);
+    let file = ReplayFile {
+        format: REPLAY_FORMAT.to_string(),
+        version: REPLAY_VERSION,
+        rng_seed,
+        random_seed: player.movie.random_seed,
+        start_ms,
+        frame_ms: frame_ms(player),
+        system_elapsed_ms: start_ms - datetime_to_ms(player.system_start_time),
+        timer_elapsed_ms: start_ms - datetime_to_ms(player.start_time),
+        host_timeouts: cfg!(target_arch = "wasm32"),
+        frame_count: 0,
+        events: vec![],
+    };
+    SESSION.with(|session| {
+        *session.borrow_mut() = Some(Session {
+            mode: Mode::Recording,
+            frame: 0,
+            clock_ms: start_ms,
+            file,
+            pending: VecDeque::new(),
+            saved_net: None,
+        });
+    });
+}
+
+/// Stop recording, returning the replay file. `None` if not recording.
+pub fn stop_recording(player: &mut DirPlayer) -> Option<Vec<u8>> {
+    if !is_recording() {
+        return None;
+    }
+    let mut session = SESSION.with(|session| session.borrow_mut().take())?;
+    session.file.frame_count = session.frame;
+    shift_clock(player, testing_shared::now_ms() - session.clock_ms);
+    Some(session.file.to_bytes())
+}
+
+/// Drive the player from a replay file. The movie should be freshly loaded,
+/// in the state the recording started from. Live inputs are ignored until
+/// `stop_replay`.
+pub fn start_replay(player: &mut DirPlayer, bytes: &[u8]) -> Result<(), String> {
+    let file = ReplayFile::from_bytes(bytes)?;
+    stop_replay(player);
+    stop_recording(player);
+
+    shift_clock(player, file.start_ms - testing_shared::now_ms());
+    player.system_start_time = datetime_from_ms(file.start_ms - file.system_elapsed_ms);
+    player.start_time = datetime_from_ms(file.start_ms - file.timer_elapsed_ms);
+    player.rng = rand::rngs::SmallRng::seed_from_u64(file.rng_seed);
+    player.movie.random_seed = file.random_seed;
+
+    let (net_events, pending): (Vec<_>, Vec<_>) = file
+        .events
+        .iter()
+        .cloned()
+        .partition(|event| matches!(event.input, ReplayInput::NetTaskDone { .. }));
+    let backend = player.net_manager.set_backend(Box::new(ReplayNetBackend::new(net_events)));
+    // Recorded timeouts arrive as results, so don't time tasks out again.
+    let timeout_ms = player.net_manager.timeout_ms.take();
+
+    SESSION.with(|session| {
+        *session.borrow_mut() = Some(Session {
+            mode: Mode::Replaying,
+            frame: 0,
+            clock_ms: file.start_ms,
+            pending: pending.into(),
+            file,
+            saved_net: Some((backend, timeout_ms)),
+        });
+    });
+    Ok(())
+}
+
+/// End a replay and hand the player back to live input and the wall clock.
+pub fn stop_replay(player: &mut DirPlayer) {
+    if !is_replaying() {
+        return;
+    }
+    let Some(session) = SESSION.with(|session| session.borrow_mut().take()) else {
+        return;
+    };
+    if let Some((backend, timeout_ms)) = session.saved_net {
+        player.net_manager.set_backend(backend);
+        player.net_manager.timeout_ms = timeout_ms;
+    }
+    shift_clock(player, testing_shared::now_ms() - session.clock_ms);
+}
+
+fn record(frame_of: impl FnOnce(u32) -> u32, input: ReplayInput) {
+    with_session(|session| {
+        if session.mode == Mode::Recording {
+            session.file.events.push(ReplayEvent {
+                frame: frame_of(session.frame),
+                clock_ms: session.clock_ms,
+                input,
+            });
+        }
+    });
+}
+
+/// Pass a live input through the session: recorded while recording, and
+/// swallowed while replaying (returns false) since the replay supplies
+/// the inputs.
+pub fn accept_input(input: ReplayInput) -> bool {
+    match mode() {
+        Some(Mode::Replaying) => false,
+        Some(Mode::Recording) => {
+            record(|frame| frame, input);
+            true
+        }
+        None => true,
+    }
+}
+
+/// Record a network result. Results from the backend's poll are applied
+/// at the same point of the same frame on replay; `arrived_async` ones came
+/// in while the frame loop was busy elsewhere and are applied from the next
+/// frame's poll.
+pub fn record_net_result(task_id: u32, result: &NetResult, arrived_async: bool) {
+    let input = ReplayInput::net_task_done(task_id, result);
+    record(|frame| if arrived_async { frame + 1 } else { frame }, input);
+}
+
+/// Whether `fire_pending_timeouts` should stay quiet because the replay
+/// supplies the timeouts the host fired.
+pub fn replays_host_timeouts() -> bool {
+    with_session(|session| session.mode == Mode::Replaying && session.file.host_timeouts)
+        .unwrap_or(false)
+}
+
+/// Start a frame: when replaying, apply the inputs that arrived after the
+/// previous frame started, at the time they arrived; then move the virtual
+/// clock on.
+pub async fn begin_frame() {
+    // A recording made natively fires timeouts from the clock, which the
+    // browser frame loop doesn't do on its own.
+    #[cfg(target_arch = "wasm32")]
+    if with_session(|session| session.mode == Mode::Replaying && !session.file.host_timeouts)
+        .unwrap_or(false)
+    {
+        super::fire_pending_timeouts().await;
+    }
+    let Some((frame_clock_ms, due)) = with_session(|session| {
+        session.frame += 1;
+        let mut due = vec![];
+        if session.mode == Mode::Replaying {
+            while session.pending.front().is_some_and(|event| event.frame < session.frame) {
+                due.push(session.pending.pop_front().unwrap());
+            }
+        }
+        (session.clock_ms + session.file.frame_ms, due)
+    }) else {
+        return;
+    };
+    for event in due {
+        with_session(|session| session.clock_ms = event.clock_ms);
+        apply_input(event.input).await;
+    }
+    with_session(|session| session.clock_ms = frame_clock_ms);
+}
+
+async fn apply_input(input: ReplayInput) {
+    match input {
+        ReplayInput::ImeCompositionStart => {
+            reserve_player_mut(keyboard_events::ime_composition_start);
+        }
+        ReplayInput::ImeCompositionUpdate { text } => {
+            reserve_player_mut(|player| keyboard_events::ime_composition_update(player, &text));
+        }
+        ReplayInput::ImeCompositionEnd { text } => {
+            reserve_player_mut(|player| keyboard_events::ime_composition_end(player, &text));
+        }
+        ReplayInput::PasteText { text } => {
+            reserve_player_mut(|player| keyboard_events::paste_text(player, &text));
+        }
+        input => {
+            let Some(command) = input.into_command() else {
+                return;
+            };
+            reserve_player_mut(|player| apply_input_state(player, &command));
+            if let Err(err) = apply_player_command(command).await
+                && err.code != ScriptErrorCode::Abort
+            {
+                reserve_player_mut(|player| player.on_script_error(&err));
+            }
+        }
+    }
+}
+
+/// Stands in for the network while replaying: nothing is fetched, and each
+/// task gets the result it got in the recording, on the same frame.
+struct ReplayNetBackend {
+    results: Vec<ReplayEvent>,
+    started: HashSet<u32>,
+    skip_ahead: bool,
+}
+
+impl ReplayNetBackend {
+    fn new(results: Vec<ReplayEvent>) -> ReplayNetBackend {
+        ReplayNetBackend { results, started: HashSet::new(), skip_ahead: false }
+    }
+
+    fn is_waiting(&self, event: &ReplayEvent) -> bool {
+        matches!(event.input, ReplayInput::NetTaskDone { task_id, .. } if self.started.contains(&task_id))
+    }
+}
+
+impl NetBackend for ReplayNetBackend {
+    fn start(&mut self, task: &NetTask, _shared_state: &Arc<Mutex<NetManagerSharedState>>) {
+        self.started.insert(task.id);
+    }
+
+    fn poll(&mut self) -> Vec<NetEvent> {
+        let frame = current_frame();
+        let skip_ahead = std::mem::take(&mut self.skip_ahead);
+        let (due, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut self.results)
+            .into_iter()
+            .partition(|event| self.is_waiting(event) && (skip_ahead || event.frame <= frame));
+        self.results = rest;
+        due.into_iter()
+            .filter_map(|event| match event.input {
+                ReplayInput::NetTaskDone { task_id, data, error } => {
+                    let result = match (data, error) {
+                        (Some(data), _) => base64::engine::general_purpose::STANDARD
+                            .decode(data)
+                            .map_err(|_| super::net_task::NET_ERROR_NOT_FOUND),
+                        (None, error) => Err(error.unwrap_or(super::net_task::NET_ERROR_NOT_FOUND)),
+                    };
+                    Some(NetEvent::Done { task_id, result })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn has_virtual_clock(&self) -> bool {
+        true
+    }
+
+    /// `NetManager::await_task` skips to the next event by advancing the
+    /// clock to it, which is no time at all here: it hands over the results
+    /// of the started tasks early. Per-frame advances are ignored, since
+    /// results are delivered by frame.
+    fn advance(&mut self, ms: f64) {
+        if ms <= 0.0 {
+            self.skip_ahead = true;
+        }
+    }
+
+    fn next_event_ms(&self) -> Option<f64> {
+        self.results.iter().any(|event| self.is_waiting(event)).then(|| self.now_ms())
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "replay"
+    }
+}
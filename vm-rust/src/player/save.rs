@@ -0,0 +1,138 @@
+//! `saveMovie` and `save castLib`: write the loaded movie or an external cast
+//! back out as an uncompressed `.dir` / `.cst`, including scripts whose text
+//! was replaced at runtime through `scriptText`. Other runtime changes to
+//! members, such as edited bitmaps or field text, are not written: the
+//! writer keeps their chunks as they were loaded.
+
+use binary_reader::BinaryReader;
+use log::warn;
+
+use crate::director::{
+    cast::CastDef,
+    chunks::{lctx::ScriptContextChunk, script_names::ScriptNamesChunk},
+    file::DirectorFile,
+    utils::FOURCC,
+    writer::{write_to_vec, RIFXWriter},
+};
+
+use super::{
+    cast_lib::CastLib,
+    cast_member::CastMemberType,
+    DirPlayer, ScriptError,
+};
+
+/// The name Director gives the uncompressed counterpart of a file:
+/// `.dcr` becomes `.dir` and `.cct` becomes `.cst`.
+pub fn uncompressed_file_name(file_name: &str) -> String {
+    match file_name.rsplit_once('.') {
+        Some((stem, ext)) if ext.eq_ignore_ascii_case("dcr") => format!("{stem}.dir"),
+        Some((stem, ext)) if ext.eq_ignore_ascii_case("cct") => format!("{stem}.cst"),
+        _ => file_name.to_string(),
+    }
+}
+
+fn writer_for(file: &DirectorFile) -> Result<RIFXWriter, ScriptError> {
+    RIFXWriter::from_director_file(file).map_err(|e| ScriptError::new(format!("Cannot save {}: {}", file.file_name, e)))
+}
+
+/// Serialize the current movie file, with the runtime script changes of
+/// its internal casts applied.
+pub fn movie_file_bytes(player: &DirPlayer) -> Result<Vec<u8>, ScriptError> {
+    let file = player.movie.file.as_ref().ok_or_else(|| ScriptError::new("No movie loaded".to_string()))?;
+    let mut writer = writer_for(file)?;
+    for (index, cast_entry) in file.cast_entries.iter().enumerate() {
+        let Some(cast_def) = file.casts.iter().find(|cast| cast.id == cast_entry.id) else {
+            continue;
+        };
+        if let Ok(cast_lib) = player.movie.cast_manager.get_cast(index as u32 + 1)
+            && !cast_lib.is_external
+        {
+            apply_script_changes(&mut writer, cast_def, cast_lib).map_err(ScriptError::new)?;
+        }
+    }
+    writer.write().map_err(ScriptError::new)
+}
+
+/// Serialize the external cast loaded into cast library `number`.
+pub fn cast_lib_file_bytes(player: &DirPlayer, number: u32) -> Result<Vec<u8>, ScriptError> {
+    let cast_lib = player.movie.cast_manager.get_cast(number)?;
+    if !cast_lib.is_external {
+        return Err(ScriptError::new(format!("castLib {} is not an external cast", number)));
+    }
+    let file = player
+        .dir_cache
+        .get(cast_lib.file_name.as_str())
+        .or_else(|| player.dir_cache.values().find(|file| file.file_name == cast_lib.file_name))
+        .ok_or_else(|| ScriptError::new(format!("castLib {} is not loaded", number)))?;
+    let mut writer = writer_for(file)?;
+    if let Some(cast_def) = file.casts.first() {
+        apply_script_changes(&mut writer, cast_def, cast_lib).map_err(ScriptError::new)?;
+    }
+    writer.write().map_err(ScriptError::new)
+}
+
+/// Write scripts recompiled through `scriptText` over the `Lscr` chunks they
+/// replaced, refresh the name table and store the new source text.
+fn apply_script_changes(writer: &mut RIFXWriter, cast_def: &CastDef, cast_lib: &CastLib) -> Result<(), String> {
+    let (Some(file_lctx), Some(lctx), Some(lctx_section_id)) =
+        (cast_def.lctx.as_ref(), cast_lib.lctx.as_ref(), cast_def.lctx_section_id)
+    else {
+        return Ok(());
+    };
+
+    let lctx_section_id = writer.section_id(lctx_section_id);
+    let lctx_chunk = writer.chunk(lctx_section_id).ok_or("Script context chunk is missing")?;
+    let context = ScriptContextChunk::from_reader(&mut BinaryReader::from_vec(&lctx_chunk.data), cast_def.dir_version)?;
+
+    let mut changed = false;
+    for (number, member) in &cast_lib.members {
+        let CastMemberType::Script(script_member) = &member.member_type else {
+            continue;
+        };
+        let original_id = cast_def
+            .members
+            .get(number)
+            .and_then(|def| def.chunk.member_info.as_ref())
+            .map_or(0, |info| info.header.script_id);
+        if script_member.script_id == original_id {
+            continue;
+        }
+        let (Some(original), Some(script)) =
+            (file_lctx.scripts.get(&original_id), lctx.scripts.get(&script_member.script_id))
+        else {
+            warn!("Script of member {} was added at runtime and is not saved", number);
+            continue;
+        };
+
+        let script_section = context.section_map.iter().filter(|entry| entry.section_id > -1).find_map(|entry| {
+            let id = writer.section_id(entry.section_id as u32);
+            let script_number = writer.chunk(id)?.data.get(18..20)?;
+            (u16::from_be_bytes([script_number[0], script_number[1]]) == original.script_number).then_some(id)
+        });
+        let Some(script_section) = script_section else {
+            warn!("No Lscr chunk found for the script of member {}", number);
+            continue;
+        };
+        let mut script = script.clone();
+        script.script_number = original.script_number;
+        writer.set_script(script_section, &script, cast_def.capital_x)?;
+
+        let member_section = cast_def
+            .section_to_member
+            .iter()
+            .filter(|(_, (member_number, _))| member_number == number)
+            .map(|(section, _)| writer.section_id(*section))
+            .find(|section| writer.chunk(*section).is_some_and(|chunk| chunk.fourcc == FOURCC("CASt")));
+        if let Some(section) = member_section {
+            writer.set_member_script_text(section, &script_member.script_text)?;
+        }
+        changed = true;
+    }
+
+    if changed {
+        let names = ScriptNamesChunk { names: lctx.names.to_vec() };
+        let data = write_to_vec(binary_rw::Endian::Big, |w| names.write(w))?;
+        writer.set_chunk(writer.section_id(context.lnam_section_id), FOURCC("Lnam"), data);
+    }
+    Ok(())
+}
@@ -0,0 +1,1852 @@
+//! Save states: snapshot the VM state of the running movie and restore it
+//! later in the same movie. A snapshot holds the datums and script
+//! instances reachable from globals, sprites, timeouts, sound channels and
+//! script properties, the images and dates they refer to, the score's
+//! sprite channels, timeouts, sound channel positions, and the cast member
+//! state scripts change at runtime: name, reg point, text, and the image of
+//! bitmap members. Other member properties and media keep their current
+//! values on restore.
+//!
+//! Snapshots are taken between frames: a suspended handler can't be written
+//! out, so saving or restoring while handlers are on the call stack fails.
+//! Movies in windows aren't part of a snapshot either, so saving fails while
+//! a window has a movie loaded. Restored datums and script instances get
+//! fresh allocator ids, with the references between them kept. Datums
+//! backed by other subsystems (xtra instances, XML, 3D and Flash objects)
+//! can't be saved and make the snapshot fail.
+
+use std::{collections::VecDeque, sync::Arc};
+
+use fxhash::{FxHashMap, FxHashSet};
+use num::{FromPrimitive, ToPrimitive};
+use rand::{Rng, SeedableRng};
+
+use crate::{
+    director::lingo::datum::{Datum, DatumType, StringChunkExpr, StringChunkSource, StringChunkType},
+    js_api::JsApi,
+    utils::local_now,
+};
+
+use super::{
+    allocator::{DatumAllocatorTrait, ScriptInstanceAllocatorTrait},
+    bitmap::{
+        bitmap::{Bitmap, BuiltInPalette, PaletteRef},
+        manager::BitmapRef,
+        mask::BitmapMask,
+    },
+    cast_lib::CastMemberRef,
+    cast_member::CastMemberType,
+    ci_string::CiString,
+    datum_ref::{DatumId, DatumRef},
+    handlers::datum_handlers::{
+        date::DateObject,
+        sound_channel::{SoundChannel, SoundSegment, SoundStatus},
+    },
+    script::{ScriptInstance, ScriptInstanceId},
+    script_ref::ScriptInstanceRef,
+    sprite::{ColorRef, CursorRef, Sprite},
+    testing_shared,
+    timeout::Timeout,
+    DirPlayer, ScriptError,
+};
+
+pub const SAVE_STATE_MAGIC: &[u8; 4] = b"DPSS";
+pub const SAVE_STATE_VERSION: u32 = 2;
+
+/// A datum as stored in a save state. References to other datums and
+/// script instances are their ids at the time of the snapshot; 0 is VOID.
+pub enum SavedDatum {
+    Int(i32),
+    Float(f64),
+    String(String),
+    Symbol(String),
+    StringChunk {
+        source: SavedChunkSource,
+        chunk_type: StringChunkType,
+        start: i32,
+        end: i32,
+        item_delimiter: char,
+        value: String,
+    },
+    List(DatumType, Vec<DatumId>, bool),
+    PropList(Vec<(DatumId, DatumId)>, bool),
+    CastLib(u32),
+    Stage,
+    Window(String),
+    ScriptRef(CastMemberRef),
+    ScriptInstance(ScriptInstanceId),
+    CastMember(CastMemberRef),
+    SpriteRef(i16),
+    Rect([f64; 4], u8),
+    Point([f64; 2], u8),
+    SoundChannel(u16),
+    SoundRef(u16),
+    CursorRef(CursorRef),
+    TimeoutRef(String),
+    TimeoutFactory,
+    TimeoutInstance {
+        name: String,
+        duration: i32,
+        callback: DatumId,
+        target: DatumId,
+        script_instance: Option<DatumId>,
+    },
+    ColorRef(ColorRef),
+    Xtra(String),
+    PlayerRef,
+    MovieRef,
+    MouseRef,
+    Vector([f64; 3]),
+    Null,
+    JavaScript(Vec<u8>),
+    Transform3d([f64; 16]),
+    /// An image, by its bitmap ref at the time of the snapshot. The pixels
+    /// are in `SaveState::bitmaps`.
+    Bitmap(BitmapRef),
+    /// A date, as milliseconds since the epoch.
+    Date(i64),
+}
+
+pub enum SavedChunkSource {
+    Datum(DatumId),
+    Member(CastMemberRef),
+}
+
+pub struct SavedScriptInstance {
+    pub id: ScriptInstanceId,
+    pub script: CastMemberRef,
+    pub ancestor: Option<ScriptInstanceId>,
+    pub properties: Vec<(String, DatumId)>,
+    pub begin_sprite_called: bool,
+}
+
+pub struct SavedChannel {
+    pub number: usize,
+    pub name: String,
+    pub scripted: bool,
+    /// The sprite, with an empty `script_instance_list`.
+    pub sprite: Sprite,
+    pub script_instances: Vec<ScriptInstanceId>,
+}
+
+pub struct SavedTimeout {
+    pub name: String,
+    pub period: u32,
+    pub handler: String,
+    pub target: DatumId,
+    pub is_scheduled: bool,
+    /// Time left until the timeout fires.
+    pub remaining_ms: f64,
+}
+
+pub struct SavedSoundChannel {
+    pub index: usize,
+    pub member: Option<DatumId>,
+    pub status: SoundStatus,
+    pub position_ms: f64,
+    pub volume: f64,
+    pub pan: f64,
+    pub loop_count: i32,
+    pub loops_remaining: i32,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub loop_start_time: f64,
+    pub loop_end_time: f64,
+    pub playlist: Vec<DatumId>,
+    /// (member, loop count, loops remaining)
+    pub playlist_segments: Vec<(DatumId, i32, i32)>,
+    pub current_segment_index: Option<usize>,
+    pub queued_members: Vec<DatumId>,
+}
+
+pub struct SavedCastMember {
+    pub member_ref: CastMemberRef,
+    pub name: String,
+    pub reg_point: (i32, i32),
+    /// Text of field, text and button members.
+    pub text: Option<String>,
+    /// Image and bitmap reg point of bitmap members. The pixels are in
+    /// `SaveState::bitmaps`.
+    pub image: Option<(BitmapRef, (i16, i16))>,
+}
+
+/// The pixels and format of a bitmap.
+pub struct SavedBitmap {
+    pub width: u16,
+    pub height: u16,
+    pub bit_depth: u8,
+    pub original_bit_depth: u8,
+    pub data: Vec<u8>,
+    pub palette_ref: PaletteRef,
+    /// One bit per pixel.
+    pub matte: Option<Vec<bool>>,
+    pub use_alpha: bool,
+    pub trim_white_space: bool,
+    pub was_trimmed: bool,
+}
+
+impl SavedBitmap {
+    fn new(bitmap: &Bitmap) -> SavedBitmap {
+        SavedBitmap {
+            width: bitmap.width,
+            height: bitmap.height,
+            bit_depth: bitmap.bit_depth,
+            original_bit_depth: bitmap.original_bit_depth,
+            data: bitmap.data.clone(),
+            palette_ref: bitmap.palette_ref.clone(),
+            matte: bitmap.matte.as_ref().map(|matte| matte.data.iter().by_vals().collect()),
+            use_alpha: bitmap.use_alpha,
+            trim_white_space: bitmap.trim_white_space,
+            was_trimmed: bitmap.was_trimmed,
+        }
+    }
+
+    fn to_bitmap(&self) -> Bitmap {
+        let matte = self.matte.as_ref().map(|bits| {
+            let mut matte = BitmapMask::new(self.width, self.height, false);
+            for (mut pixel, bit) in matte.data.iter_mut().zip(bits) {
+                *pixel = *bit;
+            }
+            Arc::new(matte)
+        });
+        Bitmap {
+            width: self.width,
+            height: self.height,
+            bit_depth: self.bit_depth,
+            original_bit_depth: self.original_bit_depth,
+            data: self.data.clone(),
+            palette_ref: self.palette_ref.clone(),
+            matte,
+            use_alpha: self.use_alpha,
+            trim_white_space: self.trim_white_space,
+            was_trimmed: self.was_trimmed,
+            version: 0,
+        }
+    }
+}
+
+pub struct SavedScriptProperties {
+    pub script: CastMemberRef,
+    pub properties: Vec<(String, DatumId)>,
+}
+
+pub struct SaveState {
+    pub version: u32,
+    pub movie_file_name: String,
+    pub current_frame: u32,
+    pub puppet_tempo: u32,
+    pub exit_lock: bool,
+    pub item_delimiter: char,
+    /// Seed `random()` continues from, in both the saved and restored run.
+    pub rng_seed: u64,
+    pub random_seed: Option<i32>,
+    pub timer_elapsed_ms: f64,
+    pub datums: Vec<(DatumId, SavedDatum)>,
+    pub script_instances: Vec<SavedScriptInstance>,
+    pub globals: Vec<(String, DatumId)>,
+    pub frame_script_instance: Option<ScriptInstanceId>,
+    pub channels: Vec<SavedChannel>,
+    pub timeouts: Vec<SavedTimeout>,
+    pub sound_channels: Vec<SavedSoundChannel>,
+    pub cast_members: Vec<SavedCastMember>,
+    pub script_properties: Vec<SavedScriptProperties>,
+    /// Bitmaps of image datums and bitmap members, by their bitmap ref.
+    pub bitmaps: Vec<(BitmapRef, SavedBitmap)>,
+}
+
+/// Snapshot the VM state and serialize it.
+pub fn save_state(player: &mut DirPlayer) -> Result<Vec<u8>, ScriptError> {
+    Ok(SaveState::capture(player)?.to_bytes())
+}
+
+/// Replace the VM state with a serialized snapshot of the same movie.
+pub fn restore_state(player: &mut DirPlayer, bytes: &[u8]) -> Result<(), ScriptError> {
+    SaveState::from_bytes(bytes)?.restore(player)
+}
+
+fn no_handlers_running(player: &DirPlayer, action: &str) -> Result<(), ScriptError> {
+    if player.scope_count > 0 {
+        return Err(ScriptError::new(format!("Cannot {} while handlers are running", action)));
+    }
+    Ok(())
+}
+
+type CapturedObjects = (Vec<(DatumId, SavedDatum)>, Vec<SavedScriptInstance>);
+
+/// Collects the datums and script instances reachable from the roots.
+struct Capture<'a> {
+    player: &'a DirPlayer,
+    datum_queue: Vec<DatumId>,
+    seen_datums: FxHashSet<DatumId>,
+    instance_queue: Vec<ScriptInstanceId>,
+    seen_instances: FxHashSet<ScriptInstanceId>,
+    bitmaps: FxHashSet<BitmapRef>,
+}
+
+impl<'a> Capture<'a> {
+    fn datum(&mut self, datum_ref: &DatumRef) -> DatumId {
+        let id = datum_ref.unwrap();
+        if id != 0 && self.seen_datums.insert(id) {
+            self.datum_queue.push(id);
+        }
+        id
+    }
+
+    fn datums(&mut self, datum_refs: &[DatumRef]) -> Vec<DatumId> {
+        datum_refs.iter().map(|datum_ref| self.datum(datum_ref)).collect()
+    }
+
+    fn instance(&mut self, instance_ref: &ScriptInstanceRef) -> ScriptInstanceId {
+        let id = instance_ref.id();
+        if self.seen_instances.insert(id) {
+            self.instance_queue.push(id);
+        }
+        id
+    }
+
+    fn properties<'p>(
+        &mut self,
+        properties: impl Iterator<Item = (&'p CiString, &'p DatumRef)>,
+    ) -> Vec<(String, DatumId)> {
+        properties.map(|(name, value)| (name.as_str().to_string(), self.datum(value))).collect()
+    }
+
+    fn saved_datum(&mut self, datum: &Datum) -> Result<SavedDatum, ScriptError> {
+        let saved = match datum {
+            Datum::Int(n) => SavedDatum::Int(*n),
+            Datum::Float(n) => SavedDatum::Float(*n),
+            Datum::String(s) => SavedDatum::String(s.clone()),
+            Datum::Symbol(s) => SavedDatum::Symbol(s.clone()),
+            Datum::StringChunk(source, expr, value) => SavedDatum::StringChunk {
+                source: match source {
+                    StringChunkSource::Datum(datum_ref) => SavedChunkSource::Datum(self.datum(datum_ref)),
+                    StringChunkSource::Member(member_ref) => SavedChunkSource::Member(member_ref.clone()),
+                },
+                chunk_type: expr.chunk_type.clone(),
+                start: expr.start,
+                end: expr.end,
+                item_delimiter: expr.item_delimiter,
+                value: value.clone(),
+            },
+            Datum::List(list_type, items, sorted) => {
+                let items = items.iter().map(|item| self.datum(item)).collect();
+                SavedDatum::List(list_type.clone(), items, *sorted)
+            }
+            Datum::PropList(pairs, sorted) => {
+                let pairs = pairs.iter().map(|(key, value)| (self.datum(key), self.datum(value))).collect();
+                SavedDatum::PropList(pairs, *sorted)
+            }
+            Datum::CastLib(number) => SavedDatum::CastLib(*number),
+            Datum::Stage => SavedDatum::Stage,
+            Datum::Window(name) => SavedDatum::Window(name.clone()),
+            Datum::ScriptRef(member_ref) => SavedDatum::ScriptRef(member_ref.clone()),
+            Datum::ScriptInstanceRef(instance_ref) => SavedDatum::ScriptInstance(self.instance(instance_ref)),
+            Datum::CastMember(member_ref) => SavedDatum::CastMember(member_ref.clone()),
+            Datum::SpriteRef(number) => SavedDatum::SpriteRef(*number),
+            Datum::Rect(values, flags) => SavedDatum::Rect(*values, *flags),
+            Datum::Point(values, flags) => SavedDatum::Point(*values, *flags),
+            Datum::SoundChannel(number) => SavedDatum::SoundChannel(*number),
+            Datum::SoundRef(number) => SavedDatum::SoundRef(*number),
+            Datum::CursorRef(cursor) => SavedDatum::CursorRef(cursor.clone()),
+            Datum::TimeoutRef(name) => SavedDatum::TimeoutRef(name.clone()),
+            Datum::TimeoutFactory => SavedDatum::TimeoutFactory,
+            Datum::TimeoutInstance { name, duration, callback, target, script_instance } => {
+                SavedDatum::TimeoutInstance {
+                    name: name.clone(),
+                    duration: *duration,
+                    callback: self.datum(callback),
+                    target: self.datum(target),
+                    script_instance: script_instance.as_ref().map(|instance| self.datum(instance)),
+                }
+            }
+            Datum::ColorRef(color) => SavedDatum::ColorRef(color.clone()),
+            Datum::Xtra(name) => SavedDatum::Xtra(name.clone()),
+            Datum::PlayerRef => SavedDatum::PlayerRef,
+            Datum::MovieRef => SavedDatum::MovieRef,
+            Datum::MouseRef => SavedDatum::MouseRef,
+            Datum::Vector(values) => SavedDatum::Vector(*values),
+            Datum::Null => SavedDatum::Null,
+            Datum::JavaScript(bytes) => SavedDatum::JavaScript(bytes.clone()),
+            Datum::Transform3d(values) => SavedDatum::Transform3d(*values),
+            Datum::BitmapRef(bitmap_ref) => {
+                if self.player.bitmap_manager.get_bitmap(*bitmap_ref).is_none() {
+                    return Err(ScriptError::new(format!("Bitmap {} is not allocated", bitmap_ref)));
+                }
+                self.bitmaps.insert(*bitmap_ref);
+                SavedDatum::Bitmap(*bitmap_ref)
+            }
+            Datum::DateRef(date_id) => {
+                let date = self
+                    .player
+                    .date_objects
+                    .get(date_id)
+                    .ok_or_else(|| ScriptError::new(format!("Date {} does not exist", date_id)))?;
+                SavedDatum::Date(date.timestamp_ms)
+            }
+            _ => {
+                return Err(ScriptError::new(format!(
+                    "Cannot save a {} datum in a save state",
+                    datum.type_str()
+                )))
+            }
+        };
+        Ok(saved)
+    }
+
+    /// Follow the references from the roots collected so far.
+    fn drain(&mut self) -> Result<CapturedObjects, ScriptError> {
+        let player = self.player;
+        let mut datums = vec![];
+        let mut instances = vec![];
+        loop {
+            if let Some(id) = self.datum_queue.pop() {
+                let entry = player
+                    .allocator
+                    .datums
+                    .get(id)
+                    .ok_or_else(|| ScriptError::new(format!("Datum {} is not allocated", id)))?;
+                datums.push((id, self.saved_datum(&entry.datum)?));
+            } else if let Some(id) = self.instance_queue.pop() {
+                let instance = &player
+                    .allocator
+                    .get_script_instance_entry(id)
+                    .ok_or_else(|| ScriptError::new(format!("Script instance {} is not allocated", id)))?
+                    .script_instance;
+                instances.push(SavedScriptInstance {
+                    id,
+                    script: instance.script.clone(),
+                    ancestor: instance.ancestor.as_ref().map(|ancestor| self.instance(ancestor)),
+                    properties: self.properties(instance.properties.iter()),
+                    begin_sprite_called: instance.begin_sprite_called,
+                });
+            } else {
+                break;
+            }
+        }
+        datums.sort_by_key(|(id, _)| *id);
+        instances.sort_by_key(|instance| instance.id);
+        Ok((datums, instances))
+    }
+}
+
+impl SaveState {
+    pub fn capture(player: &mut DirPlayer) -> Result<SaveState, ScriptError> {
+        no_handlers_running(player, "save the VM state")?;
+        if let Some(window) = player.windows.windows.iter().find(|window| window.is_loaded()) {
+            return Err(ScriptError::new(format!(
+                "Cannot save the VM state while window \"{}\" has a movie loaded",
+                window.name
+            )));
+        }
+        // Reseed `random()` so the saved run and a restored one continue
+        // with the same numbers.
+        let rng_seed = player.rng.random::<u64>();
+        player.rng = rand::rngs::SmallRng::seed_from_u64(rng_seed);
+        let timer_elapsed_ms = (local_now() - player.start_time)
+            .num_microseconds()
+            .unwrap_or_default() as f64
+            / 1000.0;
+
+        let player: &DirPlayer = player;
+        let mut capture = Capture {
+            player,
+            datum_queue: vec![],
+            seen_datums: FxHashSet::default(),
+            instance_queue: vec![],
+            seen_instances: FxHashSet::default(),
+            bitmaps: FxHashSet::default(),
+        };
+
+        let mut globals: Vec<(String, DatumId)> = player
+            .globals
+            .iter()
+            .map(|(name, value)| (name.clone(), capture.datum(value)))
+            .collect();
+        globals.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let frame_script_instance = player
+            .movie
+            .frame_script_instance
+            .as_ref()
+            .map(|instance| capture.instance(instance));
+
+        let channels = player
+            .movie
+            .score
+            .channels
+            .iter()
+            .map(|channel| {
+                let script_instances = channel
+                    .sprite
+                    .script_instance_list
+                    .iter()
+                    .map(|instance| capture.instance(instance))
+                    .collect();
+                let mut sprite = channel.sprite.clone();
+                sprite.script_instance_list.clear();
+                SavedChannel {
+                    number: channel.number,
+                    name: channel.name.clone(),
+                    scripted: channel.scripted,
+                    sprite,
+                    script_instances,
+                }
+            })
+            .collect();
+
+        let now_ms = testing_shared::now_ms();
+        let mut timeouts: Vec<SavedTimeout> = player
+            .timeout_manager
+            .timeouts
+            .values()
+            .map(|timeout| SavedTimeout {
+                name: timeout.name.clone(),
+                period: timeout.period,
+                handler: timeout.handler.clone(),
+                target: capture.datum(&timeout.target_ref),
+                is_scheduled: timeout.is_scheduled,
+                remaining_ms: (timeout.next_fire_ms - now_ms).max(0.0),
+            })
+            .collect();
+        timeouts.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut sound_channels = vec![];
+        for index in 0..player.sound_manager.num_channels() {
+            let Some(channel_rc) = player.sound_manager.get_channel(index) else {
+                continue;
+            };
+            let channel = channel_rc.borrow();
+            sound_channels.push(SavedSoundChannel {
+                index,
+                member: channel.member.as_ref().map(|member| capture.datum(member)),
+                status: channel.status.clone(),
+                position_ms: channel.current_time_ms(),
+                volume: channel.volume,
+                pan: channel.pan,
+                loop_count: channel.loop_count,
+                loops_remaining: channel.loops_remaining,
+                start_time: channel.start_time,
+                end_time: channel.end_time,
+                loop_start_time: channel.loop_start_time,
+                loop_end_time: channel.loop_end_time,
+                playlist: capture.datums(&channel.playlist),
+                playlist_segments: channel
+                    .playlist_segments
+                    .iter()
+                    .map(|segment| (capture.datum(&segment.member_ref), segment.loop_count, segment.loops_remaining))
+                    .collect(),
+                current_segment_index: channel.current_segment_index,
+                queued_members: capture.datums(&channel.queued_members),
+            });
+        }
+
+        let mut cast_members = vec![];
+        let mut script_properties = vec![];
+        for cast in &player.movie.cast_manager.casts {
+            for (number, member) in &cast.members {
+                let text = match &member.member_type {
+                    CastMemberType::Field(field) => Some(field.text.clone()),
+                    CastMemberType::Text(text) => Some(text.text.clone()),
+                    CastMemberType::Button(button) => Some(button.field.text.clone()),
+                    _ => None,
+                };
+                let image = match &member.member_type {
+                    CastMemberType::Bitmap(bitmap)
+                        if player.bitmap_manager.get_bitmap(bitmap.image_ref).is_some() =>
+                    {
+                        capture.bitmaps.insert(bitmap.image_ref);
+                        Some((bitmap.image_ref, bitmap.reg_point))
+                    }
+                    _ => None,
+                };
+                cast_members.push(SavedCastMember {
+                    member_ref: CastMemberRef { cast_lib: cast.number as i32, cast_member: *number as i32 },
+                    name: member.name.clone(),
+                    reg_point: member.reg_point,
+                    text,
+                    image,
+                });
+            }
+            for (number, script) in &cast.scripts {
+                let properties = script.properties.borrow();
+                if !properties.is_empty() {
+                    script_properties.push(SavedScriptProperties {
+                        script: CastMemberRef { cast_lib: cast.number as i32, cast_member: *number as i32 },
+                        properties: capture.properties(properties.iter()),
+                    });
+                }
+            }
+        }
+        let member_key = |member_ref: &CastMemberRef| (member_ref.cast_lib, member_ref.cast_member);
+        cast_members.sort_by_key(|member| member_key(&member.member_ref));
+        script_properties.sort_by_key(|script| member_key(&script.script));
+
+        let (datums, script_instances) = capture.drain()?;
+        let mut bitmaps: Vec<(BitmapRef, SavedBitmap)> = capture
+            .bitmaps
+            .iter()
+            .filter_map(|bitmap_ref| {
+                let bitmap = player.bitmap_manager.get_bitmap(*bitmap_ref)?;
+                Some((*bitmap_ref, SavedBitmap::new(bitmap)))
+            })
+            .collect();
+        bitmaps.sort_by_key(|(bitmap_ref, _)| *bitmap_ref);
+        Ok(SaveState {
+            version: SAVE_STATE_VERSION,
+            movie_file_name: player.movie.file_name.clone(),
+            current_frame: player.movie.current_frame,
+            puppet_tempo: player.movie.puppet_tempo,
+            exit_lock: player.movie.exit_lock,
+            item_delimiter: player.movie.item_delimiter,
+            rng_seed,
+            random_seed: player.movie.random_seed,
+            timer_elapsed_ms,
+            datums,
+            script_instances,
+            globals,
+            frame_script_instance,
+            channels,
+            timeouts,
+            sound_channels,
+            cast_members,
+            script_properties,
+            bitmaps,
+        })
+    }
+
+    pub fn restore(&self, player: &mut DirPlayer) -> Result<(), ScriptError> {
+        no_handlers_running(player, "restore a save state")?;
+        if self.movie_file_name != player.movie.file_name {
+            return Err(ScriptError::new(format!(
+                "The save state is for {}, not {}",
+                self.movie_file_name, player.movie.file_name
+            )));
+        }
+        for instance in &self.script_instances {
+            if player.movie.cast_manager.get_script_by_ref(&instance.script).is_none() {
+                return Err(ScriptError::new(format!(
+                    "Script member {} of castLib {} no longer exists",
+                    instance.script.cast_member, instance.script.cast_lib
+                )));
+            }
+        }
+
+        let objects = self.allocate(player)?;
+
+        // Everything that can fail is done; swap the roots over. The old
+        // values are released as their holders are replaced.
+        let globals = self
+            .globals
+            .iter()
+            .map(|(name, id)| Ok((name.clone(), objects.datum(*id)?)))
+            .collect::<Result<FxHashMap<_, _>, ScriptError>>()?;
+        let frame_script_instance = self.frame_script_instance.map(|id| objects.instance(id)).transpose()?;
+        let mut sprite_instances = vec![];
+        for channel in &self.channels {
+            sprite_instances.push(objects.instances(&channel.script_instances)?);
+        }
+        let mut timeout_targets = vec![];
+        for timeout in &self.timeouts {
+            timeout_targets.push(objects.datum(timeout.target)?);
+        }
+        let mut script_properties = vec![];
+        for script in &self.script_properties {
+            script_properties.push(objects.properties(&script.properties)?);
+        }
+
+        player.watch_manager.clear_object_watchpoints();
+        player.debug_datum_refs.clear();
+        for scope in player.scopes.iter_mut() {
+            scope.reset();
+        }
+        player.globals = globals;
+        player.actor_list_generation = player.actor_list_generation.wrapping_add(1);
+
+        player.next_frame = None;
+        player.movie.current_frame = self.current_frame;
+        player.movie.puppet_tempo = self.puppet_tempo;
+        player.movie.exit_lock = self.exit_lock;
+        player.movie.item_delimiter = self.item_delimiter;
+        player.movie.random_seed = self.random_seed;
+        player.movie.frame_script_instance = frame_script_instance;
+        player.rng = rand::rngs::SmallRng::seed_from_u64(self.rng_seed);
+        player.start_time = local_now() - chrono::Duration::microseconds((self.timer_elapsed_ms * 1000.0) as i64);
+
+        for (saved, script_instances) in self.channels.iter().zip(sprite_instances) {
+            let Some(channel) = player.movie.score.channels.get_mut(saved.number) else {
+                continue;
+            };
+            channel.name = saved.name.clone();
+            channel.scripted = saved.scripted;
+            channel.sprite = saved.sprite.clone();
+            channel.sprite.script_instance_list = script_instances;
+        }
+        player.clear_script_instance_list_caches();
+        player.invalidate_active_stage_filmloop_cache();
+        player.movie.score.invalidate_render_channel_cache();
+
+        player.timeout_manager.clear();
+        let now_ms = testing_shared::now_ms();
+        for (saved, target_ref) in self.timeouts.iter().zip(timeout_targets) {
+            let mut timeout = Timeout {
+                name: saved.name.clone(),
+                period: saved.period,
+                handler: saved.handler.clone(),
+                target_ref,
+                is_scheduled: false,
+                next_fire_ms: 0.0,
+            };
+            if saved.is_scheduled {
+                timeout.schedule();
+                timeout.next_fire_ms = now_ms + saved.remaining_ms;
+            }
+            player.timeout_manager.add_timeout(timeout);
+        }
+
+        for saved in &self.sound_channels {
+            restore_sound_channel(player, saved, &objects)?;
+        }
+
+        let saved_bitmaps: FxHashMap<BitmapRef, &SavedBitmap> =
+            self.bitmaps.iter().map(|(bitmap_ref, bitmap)| (*bitmap_ref, bitmap)).collect();
+        for saved in &self.cast_members {
+            let Some(member) = player.movie.cast_manager.find_member_by_ref_mut(&saved.member_ref) else {
+                continue;
+            };
+            member.name = saved.name.clone();
+            member.reg_point = saved.reg_point;
+            if let (Some((image_ref, reg_point)), CastMemberType::Bitmap(bitmap_member)) =
+                (saved.image, &mut member.member_type)
+                && bitmap_member.image_ref == image_ref
+                && let Some(bitmap) = saved_bitmaps.get(&image_ref)
+            {
+                bitmap_member.reg_point = reg_point;
+                bitmap_member.info.width = bitmap.width;
+                bitmap_member.info.height = bitmap.height;
+                player.bitmap_manager.replace_bitmap(image_ref, bitmap.to_bitmap());
+            }
+            let Some(text) = saved.text.as_ref() else {
+                continue;
+            };
+            match &mut member.member_type {
+                CastMemberType::Field(field) if field.text != *text => field.set_text_preserving_caret(text.clone()),
+                CastMemberType::Text(member) if member.text != *text => member.set_text_preserving_caret(text.clone()),
+                CastMemberType::Button(button) if button.field.text != *text => {
+                    button.field.set_text_preserving_caret(text.clone())
+                }
+                _ => {}
+            }
+        }
+        player.movie.cast_manager.invalidate_member_name_cache();
+
+        let mut saved_properties: FxHashMap<(i32, i32), FxHashMap<CiString, DatumRef>> = self
+            .script_properties
+            .iter()
+            .zip(script_properties)
+            .map(|(script, properties)| ((script.script.cast_lib, script.script.cast_member), properties))
+            .collect();
+        for cast in &player.movie.cast_manager.casts {
+            for (number, script) in &cast.scripts {
+                let properties = saved_properties
+                    .remove(&(cast.number as i32, *number as i32))
+                    .unwrap_or_default();
+                *script.properties.borrow_mut() = properties;
+            }
+        }
+
+        JsApi::dispatch_frame_changed(player.movie.current_frame);
+        JsApi::dispatch_score_changed();
+        JsApi::dispatch_global_list(player);
+        Ok(())
+    }
+
+    /// Allocate the saved datums and script instances. Objects that refer
+    /// to others are allocated first and filled in once everything exists,
+    /// so cycles are restored too.
+    fn allocate(&self, player: &mut DirPlayer) -> Result<Objects, ScriptError> {
+        let mut objects = Objects {
+            datums: FxHashMap::default(),
+            instances: FxHashMap::default(),
+            bitmaps: FxHashMap::default(),
+        };
+        // Member images are restored in place and keep their refs. Other
+        // images become new bitmaps owned by their datums.
+        let member_images: FxHashSet<BitmapRef> = self
+            .cast_members
+            .iter()
+            .filter_map(|member| member.image.map(|(image_ref, _)| image_ref))
+            .collect();
+        for (bitmap_ref, bitmap) in &self.bitmaps {
+            let restored_ref = if member_images.contains(bitmap_ref) {
+                *bitmap_ref
+            } else {
+                player.bitmap_manager.add_ephemeral_bitmap(bitmap.to_bitmap())
+            };
+            objects.bitmaps.insert(*bitmap_ref, restored_ref);
+        }
+        for saved in &self.script_instances {
+            let instance_id = player.allocator.get_free_script_instance_id();
+            let instance = ScriptInstance {
+                instance_id,
+                script: saved.script.clone(),
+                ancestor: None,
+                properties: FxHashMap::default(),
+                begin_sprite_called: saved.begin_sprite_called,
+                window: None,
+            };
+            let instance_ref = player.allocator.alloc_script_instance(instance);
+            objects.instances.insert(saved.id, instance_ref);
+        }
+        for (id, saved) in &self.datums {
+            let datum = if saved.has_refs() { Datum::Null } else { saved.to_datum(player, &objects)? };
+            objects.datums.insert(*id, player.alloc_datum(datum));
+        }
+
+        for (id, saved) in self.datums.iter().filter(|(_, saved)| saved.has_refs()) {
+            let datum = saved.to_datum(player, &objects)?;
+            *player.allocator.get_datum_mut(&objects.datums[id]) = datum;
+        }
+        for saved in &self.script_instances {
+            let ancestor = saved.ancestor.map(|id| objects.instance(id)).transpose()?;
+            let properties = objects.properties(&saved.properties)?;
+            let instance = player.allocator.get_script_instance_mut(&objects.instances[&saved.id]);
+            instance.ancestor = ancestor;
+            instance.properties = properties;
+        }
+        Ok(objects)
+    }
+}
+
+fn restore_sound_channel(player: &mut DirPlayer, saved: &SavedSoundChannel, objects: &Objects) -> Result<(), ScriptError> {
+    let Some(channel_rc) = player.sound_manager.get_channel(saved.index) else {
+        return Ok(());
+    };
+    let member = saved.member.map(|id| objects.datum(id)).transpose()?;
+    let playlist_segments = saved
+        .playlist_segments
+        .iter()
+        .map(|(member, loop_count, loops_remaining)| {
+            Ok(SoundSegment {
+                member_ref: objects.datum(*member)?,
+                loop_count: *loop_count,
+                loops_remaining: *loops_remaining,
+            })
+        })
+        .collect::<Result<Vec<_>, ScriptError>>()?;
+    let playing_member = member.clone().or_else(|| {
+        saved
+            .current_segment_index
+            .and_then(|index| playlist_segments.get(index))
+            .map(|segment| segment.member_ref.clone())
+    });
+    {
+        let mut channel = channel_rc.borrow_mut();
+        channel.stop();
+        channel.member = member;
+        let _ = channel.set_volume(saved.volume);
+        let _ = channel.set_pan(saved.pan);
+        channel.playlist = objects.datums(&saved.playlist)?;
+        channel.playlist_segments = playlist_segments;
+        channel.current_segment_index = saved.current_segment_index;
+        channel.queued_members = objects.datums(&saved.queued_members)?;
+    }
+    let was_playing = matches!(saved.status, SoundStatus::Playing | SoundStatus::Paused);
+    if let (true, Some(member_ref)) = (was_playing, playing_member) {
+        SoundChannel::resume_at(channel_rc.clone(), player, &member_ref, saved.position_ms)?;
+        if saved.status == SoundStatus::Paused {
+            channel_rc.borrow_mut().pause();
+        }
+    }
+    let mut channel = channel_rc.borrow_mut();
+    channel.loop_count = saved.loop_count;
+    channel.loops_remaining = saved.loops_remaining;
+    channel.start_time = saved.start_time;
+    channel.end_time = saved.end_time;
+    channel.loop_start_time = saved.loop_start_time;
+    channel.loop_end_time = saved.loop_end_time;
+    Ok(())
+}
+
+/// The restored datums, script instances and bitmaps, by their saved ids.
+struct Objects {
+    datums: FxHashMap<DatumId, DatumRef>,
+    instances: FxHashMap<ScriptInstanceId, ScriptInstanceRef>,
+    bitmaps: FxHashMap<BitmapRef, BitmapRef>,
+}
+
+impl Objects {
+    fn datum(&self, id: DatumId) -> Result<DatumRef, ScriptError> {
+        if id == 0 {
+            return Ok(DatumRef::Void);
+        }
+        self.datums
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| ScriptError::new(format!("The save state refers to missing datum {}", id)))
+    }
+
+    fn datums(&self, ids: &[DatumId]) -> Result<Vec<DatumRef>, ScriptError> {
+        ids.iter().map(|id| self.datum(*id)).collect()
+    }
+
+    fn instance(&self, id: ScriptInstanceId) -> Result<ScriptInstanceRef, ScriptError> {
+        self.instances
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| ScriptError::new(format!("The save state refers to missing script instance {}", id)))
+    }
+
+    fn instances(&self, ids: &[ScriptInstanceId]) -> Result<Vec<ScriptInstanceRef>, ScriptError> {
+        ids.iter().map(|id| self.instance(*id)).collect()
+    }
+
+    fn bitmap(&self, bitmap_ref: BitmapRef) -> Result<BitmapRef, ScriptError> {
+        self.bitmaps
+            .get(&bitmap_ref)
+            .copied()
+            .ok_or_else(|| ScriptError::new(format!("The save state refers to missing bitmap {}", bitmap_ref)))
+    }
+
+    fn properties(&self, properties: &[(String, DatumId)]) -> Result<FxHashMap<CiString, DatumRef>, ScriptError> {
+        properties
+            .iter()
+            .map(|(name, id)| Ok((CiString::from(name.as_str()), self.datum(*id)?)))
+            .collect()
+    }
+}
+
+impl SavedDatum {
+    fn has_refs(&self) -> bool {
+        matches!(
+            self,
+            SavedDatum::List(..)
+                | SavedDatum::PropList(..)
+                | SavedDatum::TimeoutInstance { .. }
+                | SavedDatum::StringChunk { source: SavedChunkSource::Datum(..), .. }
+        )
+    }
+
+    fn to_datum(&self, player: &mut DirPlayer, objects: &Objects) -> Result<Datum, ScriptError> {
+        let datum = match self {
+            SavedDatum::Int(n) => Datum::Int(*n),
+            SavedDatum::Float(n) => Datum::Float(*n),
+            SavedDatum::String(s) => Datum::String(s.clone()),
+            SavedDatum::Symbol(s) => Datum::Symbol(s.clone()),
+            SavedDatum::StringChunk { source, chunk_type, start, end, item_delimiter, value } => Datum::StringChunk(
+                match source {
+                    SavedChunkSource::Datum(id) => StringChunkSource::Datum(objects.datum(*id)?),
+                    SavedChunkSource::Member(member_ref) => StringChunkSource::Member(member_ref.clone()),
+                },
+                StringChunkExpr {
+                    chunk_type: chunk_type.clone(),
+                    start: *start,
+                    end: *end,
+                    item_delimiter: *item_delimiter,
+                },
+                value.clone(),
+            ),
+            SavedDatum::List(list_type, items, sorted) => {
+                Datum::List(list_type.clone(), objects.datums(items)?.into(), *sorted)
+            }
+            SavedDatum::PropList(pairs, sorted) => {
+                let pairs = pairs
+                    .iter()
+                    .map(|(key, value)| Ok((objects.datum(*key)?, objects.datum(*value)?)))
+                    .collect::<Result<VecDeque<_>, ScriptError>>()?;
+                Datum::PropList(pairs, *sorted)
+            }
+            SavedDatum::CastLib(number) => Datum::CastLib(*number),
+            SavedDatum::Stage => Datum::Stage,
+            SavedDatum::Window(name) => Datum::Window(name.clone()),
+            SavedDatum::ScriptRef(member_ref) => Datum::ScriptRef(member_ref.clone()),
+            SavedDatum::ScriptInstance(id) => Datum::ScriptInstanceRef(objects.instance(*id)?),
+            SavedDatum::CastMember(member_ref) => Datum::CastMember(member_ref.clone()),
+            SavedDatum::SpriteRef(number) => Datum::SpriteRef(*number),
+            SavedDatum::Rect(values, flags) => Datum::Rect(*values, *flags),
+            SavedDatum::Point(values, flags) => Datum::Point(*values, *flags),
+            SavedDatum::SoundChannel(number) => Datum::SoundChannel(*number),
+            SavedDatum::SoundRef(number) => Datum::SoundRef(*number),
+            SavedDatum::CursorRef(cursor) => Datum::CursorRef(cursor.clone()),
+            SavedDatum::TimeoutRef(name) => Datum::TimeoutRef(name.clone()),
+            SavedDatum::TimeoutFactory => Datum::TimeoutFactory,
+            SavedDatum::TimeoutInstance { name, duration, callback, target, script_instance } => {
+                Datum::TimeoutInstance {
+                    name: name.clone(),
+                    duration: *duration,
+                    callback: objects.datum(*callback)?,
+                    target: objects.datum(*target)?,
+                    script_instance: script_instance.map(|id| objects.datum(id)).transpose()?,
+                }
+            }
+            SavedDatum::ColorRef(color) => Datum::ColorRef(color.clone()),
+            SavedDatum::Xtra(name) => Datum::Xtra(name.clone()),
+            SavedDatum::PlayerRef => Datum::PlayerRef,
+            SavedDatum::MovieRef => Datum::MovieRef,
+            SavedDatum::MouseRef => Datum::MouseRef,
+            SavedDatum::Vector(values) => Datum::Vector(*values),
+            SavedDatum::Null => Datum::Null,
+            SavedDatum::JavaScript(bytes) => Datum::JavaScript(bytes.clone()),
+            SavedDatum::Transform3d(values) => Datum::Transform3d(*values),
+            SavedDatum::Bitmap(bitmap_ref) => Datum::BitmapRef(objects.bitmap(*bitmap_ref)?),
+            SavedDatum::Date(timestamp_ms) => {
+                let date_id = player.allocator.get_free_script_instance_id();
+                player.date_objects.insert(date_id, DateObject::from_timestamp(date_id, *timestamp_ms));
+                Datum::DateRef(date_id)
+            }
+        };
+        Ok(datum)
+    }
+}
+
+// Binary format: the magic and a u32 version, then the sections in the
+// order of the `SaveState` fields. Numbers are little-endian, strings and
+// sequences are prefixed with a u32 length.
+
+#[derive(Default)]
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn i16(&mut self, value: i16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn len(&mut self, len: usize) {
+        self.u32(len as u32);
+    }
+
+    fn id(&mut self, id: usize) {
+        self.u32(id as u32);
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.len(bytes.len());
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn str(&mut self, value: &str) {
+        self.bytes(value.as_bytes());
+    }
+
+    fn f64s(&mut self, values: &[f64]) {
+        for value in values {
+            self.f64(*value);
+        }
+    }
+
+    fn option<T>(&mut self, value: Option<T>, write: impl FnOnce(&mut Self, T)) {
+        self.bool(value.is_some());
+        if let Some(value) = value {
+            write(self, value);
+        }
+    }
+
+    fn seq<T>(&mut self, values: &[T], mut write: impl FnMut(&mut Self, &T)) {
+        self.len(values.len());
+        for value in values {
+            write(self, value);
+        }
+    }
+
+    fn member_ref(&mut self, member_ref: &CastMemberRef) {
+        self.i32(member_ref.cast_lib);
+        self.i32(member_ref.cast_member);
+    }
+
+    fn color(&mut self, color: &ColorRef) {
+        match color {
+            ColorRef::Rgb(r, g, b) => {
+                self.u8(0);
+                self.bytes.extend_from_slice(&[*r, *g, *b]);
+            }
+            ColorRef::PaletteIndex(index) => {
+                self.u8(1);
+                self.u8(*index);
+            }
+        }
+    }
+
+    fn cursor(&mut self, cursor: &CursorRef) {
+        match cursor {
+            CursorRef::System(id) => {
+                self.u8(0);
+                self.i32(*id);
+            }
+            CursorRef::Member(members) => {
+                self.u8(1);
+                self.seq(members, |e, member| e.i32(*member));
+            }
+        }
+    }
+
+    fn properties(&mut self, properties: &[(String, DatumId)]) {
+        self.seq(properties, |e, (name, id)| {
+            e.str(name);
+            e.id(*id);
+        });
+    }
+
+    fn datum(&mut self, datum: &SavedDatum) {
+        match datum {
+            SavedDatum::Int(n) => {
+                self.u8(0);
+                self.i32(*n);
+            }
+            SavedDatum::Float(n) => {
+                self.u8(1);
+                self.f64(*n);
+            }
+            SavedDatum::String(s) => {
+                self.u8(2);
+                self.str(s);
+            }
+            SavedDatum::Symbol(s) => {
+                self.u8(3);
+                self.str(s);
+            }
+            SavedDatum::StringChunk { source, chunk_type, start, end, item_delimiter, value } => {
+                self.u8(4);
+                match source {
+                    SavedChunkSource::Datum(id) => {
+                        self.u8(0);
+                        self.id(*id);
+                    }
+                    SavedChunkSource::Member(member_ref) => {
+                        self.u8(1);
+                        self.member_ref(member_ref);
+                    }
+                }
+                self.u8(match chunk_type {
+                    StringChunkType::Item => 0,
+                    StringChunkType::Word => 1,
+                    StringChunkType::Char => 2,
+                    StringChunkType::Line => 3,
+                });
+                self.i32(*start);
+                self.i32(*end);
+                self.u32(*item_delimiter as u32);
+                self.str(value);
+            }
+            SavedDatum::List(list_type, items, sorted) => {
+                self.u8(5);
+                self.u8(match list_type {
+                    DatumType::ArgList => 1,
+                    DatumType::ArgListNoRet => 2,
+                    DatumType::XmlChildNodes => 3,
+                    _ => 0,
+                });
+                self.seq(items, |e, id| e.id(*id));
+                self.bool(*sorted);
+            }
+            SavedDatum::PropList(pairs, sorted) => {
+                self.u8(6);
+                self.seq(pairs, |e, (key, value)| {
+                    e.id(*key);
+                    e.id(*value);
+                });
+                self.bool(*sorted);
+            }
+            SavedDatum::CastLib(number) => {
+                self.u8(7);
+                self.u32(*number);
+            }
+            SavedDatum::Stage => self.u8(8),
+            SavedDatum::Window(name) => {
+                self.u8(9);
+                self.str(name);
+            }
+            SavedDatum::ScriptRef(member_ref) => {
+                self.u8(10);
+                self.member_ref(member_ref);
+            }
+            SavedDatum::ScriptInstance(id) => {
+                self.u8(11);
+                self.u32(*id);
+            }
+            SavedDatum::CastMember(member_ref) => {
+                self.u8(12);
+                self.member_ref(member_ref);
+            }
+            SavedDatum::SpriteRef(number) => {
+                self.u8(13);
+                self.i16(*number);
+            }
+            SavedDatum::Rect(values, flags) => {
+                self.u8(14);
+                self.f64s(values);
+                self.u8(*flags);
+            }
+            SavedDatum::Point(values, flags) => {
+                self.u8(15);
+                self.f64s(values);
+                self.u8(*flags);
+            }
+            SavedDatum::SoundChannel(number) => {
+                self.u8(16);
+                self.u16(*number);
+            }
+            SavedDatum::SoundRef(number) => {
+                self.u8(17);
+                self.u16(*number);
+            }
+            SavedDatum::CursorRef(cursor) => {
+                self.u8(18);
+                self.cursor(cursor);
+            }
+            SavedDatum::TimeoutRef(name) => {
+                self.u8(19);
+                self.str(name);
+            }
+            SavedDatum::TimeoutFactory => self.u8(20),
+            SavedDatum::TimeoutInstance { name, duration, callback, target, script_instance } => {
+                self.u8(21);
+                self.str(name);
+                self.i32(*duration);
+                self.id(*callback);
+                self.id(*target);
+                self.option(*script_instance, |e, id| e.id(id));
+            }
+            SavedDatum::ColorRef(color) => {
+                self.u8(22);
+                self.color(color);
+            }
+            SavedDatum::Xtra(name) => {
+                self.u8(23);
+                self.str(name);
+            }
+            SavedDatum::PlayerRef => self.u8(24),
+            SavedDatum::MovieRef => self.u8(25),
+            SavedDatum::MouseRef => self.u8(26),
+            SavedDatum::Vector(values) => {
+                self.u8(27);
+                self.f64s(values);
+            }
+            SavedDatum::Null => self.u8(28),
+            SavedDatum::JavaScript(bytes) => {
+                self.u8(29);
+                self.bytes(bytes);
+            }
+            SavedDatum::Transform3d(values) => {
+                self.u8(30);
+                self.f64s(values);
+            }
+            SavedDatum::Bitmap(bitmap_ref) => {
+                self.u8(31);
+                self.u32(*bitmap_ref);
+            }
+            SavedDatum::Date(timestamp_ms) => {
+                self.u8(32);
+                self.u64(*timestamp_ms as u64);
+            }
+        }
+    }
+
+    fn palette_ref(&mut self, palette_ref: &PaletteRef) {
+        match palette_ref {
+            PaletteRef::BuiltIn(palette) => {
+                self.u8(0);
+                self.i16(palette.to_i16().unwrap());
+            }
+            PaletteRef::Member(member_ref) => {
+                self.u8(1);
+                self.member_ref(member_ref);
+            }
+            PaletteRef::Default => self.u8(2),
+        }
+    }
+
+    fn bitmap(&mut self, bitmap: &SavedBitmap) {
+        self.u16(bitmap.width);
+        self.u16(bitmap.height);
+        self.u8(bitmap.bit_depth);
+        self.u8(bitmap.original_bit_depth);
+        self.bytes(&bitmap.data);
+        self.palette_ref(&bitmap.palette_ref);
+        self.option(bitmap.matte.as_ref(), |e, bits| {
+            e.len(bits.len());
+            let packed: Vec<u8> = bits
+                .chunks(8)
+                .map(|byte| byte.iter().enumerate().fold(0, |acc, (i, bit)| acc | ((*bit as u8) << i)))
+                .collect();
+            e.bytes(&packed);
+        });
+        self.bool(bitmap.use_alpha);
+        self.bool(bitmap.trim_white_space);
+        self.bool(bitmap.was_trimmed);
+    }
+
+    fn sprite(&mut self, sprite: &Sprite) {
+        self.len(sprite.number);
+        self.str(&sprite.name);
+        self.bool(sprite.puppet);
+        self.bool(sprite.visible);
+        self.i32(sprite.stretch);
+        self.i32(sprite.loc_h);
+        self.i32(sprite.loc_v);
+        self.i32(sprite.loc_z);
+        self.i32(sprite.width);
+        self.i32(sprite.height);
+        self.i32(sprite.ink);
+        self.i32(sprite.blend);
+        self.f64(sprite.rotation);
+        self.f64(sprite.skew);
+        self.bool(sprite.flip_h);
+        self.bool(sprite.flip_v);
+        self.i32(sprite.back_color);
+        self.color(&sprite.color);
+        self.color(&sprite.bg_color);
+        self.option(sprite.member.as_ref(), |e, member_ref| e.member_ref(member_ref));
+        self.option(sprite.cursor_ref.as_ref(), |e, cursor| e.cursor(cursor));
+        self.bool(sprite.editable);
+        self.bool(sprite.moveable);
+        self.i32(sprite.constraint);
+        self.bool(sprite.trails);
+        self.bool(sprite.entered);
+        self.bool(sprite.exited);
+        self.option(sprite.quad.as_ref(), |e, quad| {
+            for (x, y) in quad {
+                e.i32(*x);
+                e.i32(*y);
+            }
+        });
+        self.i32(sprite.fore_color);
+        self.bool(sprite.has_fore_color);
+        self.bool(sprite.has_back_color);
+        self.bool(sprite.has_visible_mod);
+        self.bool(sprite.has_blend_mod);
+        self.bool(sprite.has_size_tweened);
+        self.bool(sprite.has_size_changed);
+        self.bool(sprite.bitmap_size_owned_by_sprite);
+        self.i32(sprite.base_loc_h);
+        self.i32(sprite.base_loc_v);
+        self.i32(sprite.base_width);
+        self.i32(sprite.base_height);
+        self.f64(sprite.base_rotation);
+        self.i32(sprite.base_blend);
+        self.f64(sprite.base_skew);
+        self.color(&sprite.base_color);
+        self.color(&sprite.base_bg_color);
+        self.option(sprite.w3d_camera.as_ref(), |e, camera| e.str(camera));
+        self.seq(&sprite.w3d_cameras, |e, camera| e.str(camera));
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+type DecodeResult<T> = Result<T, String>;
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> DecodeResult<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.bytes.len());
+        let Some(end) = end else {
+            return Err("The save state is truncated".to_string());
+        };
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> DecodeResult<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> DecodeResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> DecodeResult<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn u32(&mut self) -> DecodeResult<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> DecodeResult<i32> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn i16(&mut self) -> DecodeResult<i16> {
+        Ok(i16::from_le_bytes(self.array()?))
+    }
+
+    fn u16(&mut self) -> DecodeResult<u16> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> DecodeResult<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> DecodeResult<f64> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    fn len(&mut self) -> DecodeResult<usize> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn id(&mut self) -> DecodeResult<usize> {
+        self.len()
+    }
+
+    fn bytes(&mut self) -> DecodeResult<Vec<u8>> {
+        let len = self.len()?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn str(&mut self) -> DecodeResult<String> {
+        String::from_utf8(self.bytes()?).map_err(|_| "The save state has an invalid string".to_string())
+    }
+
+    fn char(&mut self) -> DecodeResult<char> {
+        char::from_u32(self.u32()?).ok_or_else(|| "The save state has an invalid character".to_string())
+    }
+
+    fn f64s<const N: usize>(&mut self) -> DecodeResult<[f64; N]> {
+        let mut values = [0.0; N];
+        for value in values.iter_mut() {
+            *value = self.f64()?;
+        }
+        Ok(values)
+    }
+
+    fn option<T>(&mut self, read: impl FnOnce(&mut Self) -> DecodeResult<T>) -> DecodeResult<Option<T>> {
+        if self.bool()? { Ok(Some(read(self)?)) } else { Ok(None) }
+    }
+
+    fn seq<T>(&mut self, mut read: impl FnMut(&mut Self) -> DecodeResult<T>) -> DecodeResult<Vec<T>> {
+        let len = self.len()?;
+        // Every element takes at least a byte, which bounds bogus lengths.
+        if len > self.bytes.len() - self.pos {
+            return Err("The save state is truncated".to_string());
+        }
+        (0..len).map(|_| read(self)).collect()
+    }
+
+    fn member_ref(&mut self) -> DecodeResult<CastMemberRef> {
+        Ok(CastMemberRef { cast_lib: self.i32()?, cast_member: self.i32()? })
+    }
+
+    fn color(&mut self) -> DecodeResult<ColorRef> {
+        match self.u8()? {
+            0 => Ok(ColorRef::Rgb(self.u8()?, self.u8()?, self.u8()?)),
+            1 => Ok(ColorRef::PaletteIndex(self.u8()?)),
+            tag => Err(format!("Unknown color tag {}", tag)),
+        }
+    }
+
+    fn cursor(&mut self) -> DecodeResult<CursorRef> {
+        match self.u8()? {
+            0 => Ok(CursorRef::System(self.i32()?)),
+            1 => Ok(CursorRef::Member(self.seq(Self::i32)?)),
+            tag => Err(format!("Unknown cursor tag {}", tag)),
+        }
+    }
+
+    fn properties(&mut self) -> DecodeResult<Vec<(String, DatumId)>> {
+        self.seq(|d| Ok((d.str()?, d.id()?)))
+    }
+
+    fn datum(&mut self) -> DecodeResult<SavedDatum> {
+        let datum = match self.u8()? {
+            0 => SavedDatum::Int(self.i32()?),
+            1 => SavedDatum::Float(self.f64()?),
+            2 => SavedDatum::String(self.str()?),
+            3 => SavedDatum::Symbol(self.str()?),
+            4 => SavedDatum::StringChunk {
+                source: match self.u8()? {
+                    0 => SavedChunkSource::Datum(self.id()?),
+                    1 => SavedChunkSource::Member(self.member_ref()?),
+                    tag => return Err(format!("Unknown string chunk source {}", tag)),
+                },
+                chunk_type: match self.u8()? {
+                    0 => StringChunkType::Item,
+                    1 => StringChunkType::Word,
+                    2 => StringChunkType::Char,
+                    3 => StringChunkType::Line,
+                    tag => return Err(format!("Unknown string chunk type {}", tag)),
+                },
+                start: self.i32()?,
+                end: self.i32()?,
+                item_delimiter: self.char()?,
+                value: self.str()?,
+            },
+            5 => {
+                let list_type = match self.u8()? {
+                    1 => DatumType::ArgList,
+                    2 => DatumType::ArgListNoRet,
+                    3 => DatumType::XmlChildNodes,
+                    _ => DatumType::List,
+                };
+                SavedDatum::List(list_type, self.seq(Self::id)?, self.bool()?)
+            }
+            6 => SavedDatum::PropList(self.seq(|d| Ok((d.id()?, d.id()?)))?, self.bool()?),
+            7 => SavedDatum::CastLib(self.u32()?),
+            8 => SavedDatum::Stage,
+            9 => SavedDatum::Window(self.str()?),
+            10 => SavedDatum::ScriptRef(self.member_ref()?),
+            11 => SavedDatum::ScriptInstance(self.u32()?),
+            12 => SavedDatum::CastMember(self.member_ref()?),
+            13 => SavedDatum::SpriteRef(self.i16()?),
+            14 => SavedDatum::Rect(self.f64s()?, self.u8()?),
+            15 => SavedDatum::Point(self.f64s()?, self.u8()?),
+            16 => SavedDatum::SoundChannel(self.u16()?),
+            17 => SavedDatum::SoundRef(self.u16()?),
+            18 => SavedDatum::CursorRef(self.cursor()?),
+            19 => SavedDatum::TimeoutRef(self.str()?),
+            20 => SavedDatum::TimeoutFactory,
+            21 => SavedDatum::TimeoutInstance {
+                name: self.str()?,
+                duration: self.i32()?,
+                callback: self.id()?,
+                target: self.id()?,
+                script_instance: self.option(Self::id)?,
+            },
+            22 => SavedDatum::ColorRef(self.color()?),
+            23 => SavedDatum::Xtra(self.str()?),
+            24 => SavedDatum::PlayerRef,
+            25 => SavedDatum::MovieRef,
+            26 => SavedDatum::MouseRef,
+            27 => SavedDatum::Vector(self.f64s()?),
+            28 => SavedDatum::Null,
+            29 => SavedDatum::JavaScript(self.bytes()?),
+            30 => SavedDatum::Transform3d(self.f64s()?),
+            31 => SavedDatum::Bitmap(self.u32()?),
+            32 => SavedDatum::Date(self.u64()? as i64),
+            tag => return Err(format!("Unknown datum tag {}", tag)),
+        };
+        Ok(datum)
+    }
+
+    fn palette_ref(&mut self) -> DecodeResult<PaletteRef> {
+        match self.u8()? {
+            0 => {
+                let id = self.i16()?;
+                BuiltInPalette::from_i16(id)
+                    .map(PaletteRef::BuiltIn)
+                    .ok_or_else(|| format!("Unknown built-in palette {}", id))
+            }
+            1 => Ok(PaletteRef::Member(self.member_ref()?)),
+            2 => Ok(PaletteRef::Default),
+            tag => Err(format!("Unknown palette tag {}", tag)),
+        }
+    }
+
+    fn bitmap(&mut self) -> DecodeResult<SavedBitmap> {
+        Ok(SavedBitmap {
+            width: self.u16()?,
+            height: self.u16()?,
+            bit_depth: self.u8()?,
+            original_bit_depth: self.u8()?,
+            data: self.bytes()?,
+            palette_ref: self.palette_ref()?,
+            matte: self.option(|d| {
+                let len = d.len()?;
+                let packed = d.bytes()?;
+                if packed.len() != len.div_ceil(8) {
+                    return Err("The save state has an invalid matte".to_string());
+                }
+                Ok((0..len).map(|i| packed[i / 8] & (1 << (i % 8)) != 0).collect())
+            })?,
+            use_alpha: self.bool()?,
+            trim_white_space: self.bool()?,
+            was_trimmed: self.bool()?,
+        })
+    }
+
+    fn sprite(&mut self) -> DecodeResult<Sprite> {
+        Ok(Sprite {
+            number: self.len()?,
+            name: self.str()?,
+            puppet: self.bool()?,
+            visible: self.bool()?,
+            stretch: self.i32()?,
+            loc_h: self.i32()?,
+            loc_v: self.i32()?,
+            loc_z: self.i32()?,
+            width: self.i32()?,
+            height: self.i32()?,
+            ink: self.i32()?,
+            blend: self.i32()?,
+            rotation: self.f64()?,
+            skew: self.f64()?,
+            flip_h: self.bool()?,
+            flip_v: self.bool()?,
+            back_color: self.i32()?,
+            color: self.color()?,
+            bg_color: self.color()?,
+            member: self.option(Self::member_ref)?,
+            script_instance_list: vec![],
+            cursor_ref: self.option(Self::cursor)?,
+            editable: self.bool()?,
+            moveable: self.bool()?,
+            constraint: self.i32()?,
+            trails: self.bool()?,
+            entered: self.bool()?,
+            exited: self.bool()?,
+            quad: self.option(|d| {
+                let mut quad = [(0, 0); 4];
+                for point in quad.iter_mut() {
+                    *point = (d.i32()?, d.i32()?);
+                }
+                Ok(quad)
+            })?,
+            fore_color: self.i32()?,
+            has_fore_color: self.bool()?,
+            has_back_color: self.bool()?,
+            has_visible_mod: self.bool()?,
+            has_blend_mod: self.bool()?,
+            has_size_tweened: self.bool()?,
+            has_size_changed: self.bool()?,
+            bitmap_size_owned_by_sprite: self.bool()?,
+            base_loc_h: self.i32()?,
+            base_loc_v: self.i32()?,
+            base_width: self.i32()?,
+            base_height: self.i32()?,
+            base_rotation: self.f64()?,
+            base_blend: self.i32()?,
+            base_skew: self.f64()?,
+            base_color: self.color()?,
+            base_bg_color: self.color()?,
+            w3d_camera: self.option(Self::str)?,
+            w3d_cameras: self.seq(Self::str)?,
+        })
+    }
+}
+
+fn sound_status_tag(status: &SoundStatus) -> u8 {
+    match status {
+        SoundStatus::Idle => 0,
+        SoundStatus::Loading => 1,
+        SoundStatus::Queued => 2,
+        SoundStatus::Playing => 3,
+        SoundStatus::Paused => 4,
+    }
+}
+
+fn sound_status(tag: u8) -> DecodeResult<SoundStatus> {
+    match tag {
+        0 => Ok(SoundStatus::Idle),
+        1 => Ok(SoundStatus::Loading),
+        2 => Ok(SoundStatus::Queued),
+        3 => Ok(SoundStatus::Playing),
+        4 => Ok(SoundStatus::Paused),
+        _ => Err(format!("Unknown sound status {}", tag)),
+    }
+}
+
+impl SaveState {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut e = Encoder::default();
+        e.bytes.extend_from_slice(SAVE_STATE_MAGIC);
+        e.u32(self.version);
+        e.str(&self.movie_file_name);
+        e.u32(self.current_frame);
+        e.u32(self.puppet_tempo);
+        e.bool(self.exit_lock);
+        e.u32(self.item_delimiter as u32);
+        e.u64(self.rng_seed);
+        e.option(self.random_seed, |e, seed| e.i32(seed));
+        e.f64(self.timer_elapsed_ms);
+        e.seq(&self.datums, |e, (id, datum)| {
+            e.id(*id);
+            e.datum(datum);
+        });
+        e.seq(&self.script_instances, |e, instance| {
+            e.u32(instance.id);
+            e.member_ref(&instance.script);
+            e.option(instance.ancestor, |e, id| e.u32(id));
+            e.properties(&instance.properties);
+            e.bool(instance.begin_sprite_called);
+        });
+        e.properties(&self.globals);
+        e.option(self.frame_script_instance, |e, id| e.u32(id));
+        e.seq(&self.channels, |e, channel| {
+            e.len(channel.number);
+            e.str(&channel.name);
+            e.bool(channel.scripted);
+            e.sprite(&channel.sprite);
+            e.seq(&channel.script_instances, |e, id| e.u32(*id));
+        });
+        e.seq(&self.timeouts, |e, timeout| {
+            e.str(&timeout.name);
+            e.u32(timeout.period);
+            e.str(&timeout.handler);
+            e.id(timeout.target);
+            e.bool(timeout.is_scheduled);
+            e.f64(timeout.remaining_ms);
+        });
+        e.seq(&self.sound_channels, |e, channel| {
+            e.len(channel.index);
+            e.option(channel.member, |e, id| e.id(id));
+            e.u8(sound_status_tag(&channel.status));
+            e.f64(channel.position_ms);
+            e.f64(channel.volume);
+            e.f64(channel.pan);
+            e.i32(channel.loop_count);
+            e.i32(channel.loops_remaining);
+            e.f64(channel.start_time);
+            e.f64(channel.end_time);
+            e.f64(channel.loop_start_time);
+            e.f64(channel.loop_end_time);
+            e.seq(&channel.playlist, |e, id| e.id(*id));
+            e.seq(&channel.playlist_segments, |e, (id, loop_count, loops_remaining)| {
+                e.id(*id);
+                e.i32(*loop_count);
+                e.i32(*loops_remaining);
+            });
+            e.option(channel.current_segment_index, |e, index| e.len(index));
+            e.seq(&channel.queued_members, |e, id| e.id(*id));
+        });
+        e.seq(&self.cast_members, |e, member| {
+            e.member_ref(&member.member_ref);
+            e.str(&member.name);
+            e.i32(member.reg_point.0);
+            e.i32(member.reg_point.1);
+            e.option(member.text.as_deref(), |e, text| e.str(text));
+            e.option(member.image, |e, (image_ref, reg_point)| {
+                e.u32(image_ref);
+                e.i16(reg_point.0);
+                e.i16(reg_point.1);
+            });
+        });
+        e.seq(&self.script_properties, |e, script| {
+            e.member_ref(&script.script);
+            e.properties(&script.properties);
+        });
+        e.seq(&self.bitmaps, |e, (bitmap_ref, bitmap)| {
+            e.u32(*bitmap_ref);
+            e.bitmap(bitmap);
+        });
+        e.bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<SaveState, ScriptError> {
+        Self::decode(&mut Decoder { bytes, pos: 0 }).map_err(|err| ScriptError::new(format!("Invalid save state: {}", err)))
+    }
+
+    fn decode(d: &mut Decoder) -> DecodeResult<SaveState> {
+        if d.take(SAVE_STATE_MAGIC.len())? != SAVE_STATE_MAGIC {
+            return Err("not a save state".to_string());
+        }
+        let version = d.u32()?;
+        if version != SAVE_STATE_VERSION {
+            return Err(format!("unsupported version {} (expected {})", version, SAVE_STATE_VERSION));
+        }
+        let state = SaveState {
+            version,
+            movie_file_name: d.str()?,
+            current_frame: d.u32()?,
+            puppet_tempo: d.u32()?,
+            exit_lock: d.bool()?,
+            item_delimiter: d.char()?,
+            rng_seed: d.u64()?,
+            random_seed: d.option(Decoder::i32)?,
+            timer_elapsed_ms: d.f64()?,
+            datums: d.seq(|d| Ok((d.id()?, d.datum()?)))?,
+            script_instances: d.seq(|d| {
+                Ok(SavedScriptInstance {
+                    id: d.u32()?,
+                    script: d.member_ref()?,
+                    ancestor: d.option(Decoder::u32)?,
+                    properties: d.properties()?,
+                    begin_sprite_called: d.bool()?,
+                })
+            })?,
+            globals: d.properties()?,
+            frame_script_instance: d.option(Decoder::u32)?,
+            channels: d.seq(|d| {
+                Ok(SavedChannel {
+                    number: d.len()?,
+                    name: d.str()?,
+                    scripted: d.bool()?,
+                    sprite: d.sprite()?,
+                    script_instances: d.seq(Decoder::u32)?,
+                })
+            })?,
+            timeouts: d.seq(|d| {
+                Ok(SavedTimeout {
+                    name: d.str()?,
+                    period: d.u32()?,
+                    handler: d.str()?,
+                    target: d.id()?,
+                    is_scheduled: d.bool()?,
+                    remaining_ms: d.f64()?,
+                })
+            })?,
+            sound_channels: d.seq(|d| {
+                Ok(SavedSoundChannel {
+                    index: d.len()?,
+                    member: d.option(Decoder::id)?,
+                    status: sound_status(d.u8()?)?,
+                    position_ms: d.f64()?,
+                    volume: d.f64()?,
+                    pan: d.f64()?,
+                    loop_count: d.i32()?,
+                    loops_remaining: d.i32()?,
+                    start_time: d.f64()?,
+                    end_time: d.f64()?,
+                    loop_start_time: d.f64()?,
+                    loop_end_time: d.f64()?,
+                    playlist: d.seq(Decoder::id)?,
+                    playlist_segments: d.seq(|d| Ok((d.id()?, d.i32()?, d.i32()?)))?,
+                    current_segment_index: d.option(Decoder::len)?,
+                    queued_members: d.seq(Decoder::id)?,
+                })
+            })?,
+            cast_members: d.seq(|d| {
+                Ok(SavedCastMember {
+                    member_ref: d.member_ref()?,
+                    name: d.str()?,
+                    reg_point: (d.i32()?, d.i32()?),
+                    text: d.option(Decoder::str)?,
+                    image: d.option(|d| Ok((d.u32()?, (d.i16()?, d.i16()?))))?,
+                })
+            })?,
+            script_properties: d.seq(|d| {
+                Ok(SavedScriptProperties {
+                    script: d.member_ref()?,
+                    properties: d.properties()?,
+                })
+            })?,
+            bitmaps: d.seq(|d| Ok((d.u32()?, d.bitmap()?)))?,
+        };
+        if d.pos != d.bytes.len() {
+            return Err("trailing data".to_string());
+        }
+        Ok(state)
+    }
+}
@@ -8,7 +8,7 @@ use std::collections::{HashMap, HashSet, VecDeque};
 use crate::{
     console_warn,
     director::{
-        chunks::score::{FrameLabel, ScoreFrameChannelData, SoundChannelData, TempoChannelData},
+        chunks::score::{FrameLabel, PaletteChannelData, ScoreFrameChannelData, SoundChannelData, TempoChannelData},
         file::DirectorFile,
         lingo::datum::{datum_bool, Datum, DatumType},
     },
@@ -30,6 +30,7 @@ use super::{
     cast_lib::{cast_member_ref, CastMemberRef, NULL_CAST_MEMBER_REF},
     cast_member::CastMemberType,
     datum_ref::DatumRef,
+    debug::watch,
     geometry::{IntRect, IntRectTuple},
     handlers::datum_handlers::{
         cast_member_ref::CastMemberRefHandlers,
@@ -41,6 +42,7 @@ use super::{
     script::{script_get_prop_opt, script_set_prop},
     script_ref::ScriptInstanceRef,
     sprite::{ColorRef, CursorRef, Sprite},
+    tempo_wait::TempoWaitMode,
     DirPlayer, ScriptError, PLAYER_OPT,
 };
 
@@ -107,6 +109,8 @@ pub struct Score {
     pub sound_channel_data: Vec<(u32, u16, SoundChannelData)>,
     pub tempo_channel_data: Vec<(u32, TempoChannelData)>,
     pub palette_channel_data: Vec<(u32, i16, i16)>,
+    pub palette_effect_data: Vec<(u32, PaletteChannelData)>,
+    pub transition_channel_data: Vec<(u32, i16, i16)>,
     pub frame_labels: Vec<FrameLabel>,
     pub sound_channel_triggered: HashMap<u16, u32>,
     pub keyframes_cache: Arc<HashMap<u16, ChannelKeyframes>>,
@@ -128,6 +132,9 @@ pub struct Score {
     pub sorted_channels_cache: RefCell<Option<(u32, u64, Vec<usize>)>>,
     /// Incremented when runtime sprite state changes in a way that affects render inclusion/order.
     pub render_channel_cache_generation: u64,
+    /// Channels edited by score recording. Like D5 scores, their sprites are
+    /// updated from channel_initialization_data every frame.
+    pub recorded_channels: HashSet<u32>,
 }
 
 fn get_sprite_rect(player: &DirPlayer, sprite_id: i16) -> IntRectTuple {
@@ -222,6 +229,23 @@ pub(crate) fn convert_raw_blend(raw: u8, dir_version: u16) -> i32 {
     }
 }
 
+/// Inverse of `convert_raw_blend`, for writing sprites back into score data.
+pub(crate) fn convert_blend_to_raw(blend: i32, dir_version: u16) -> u8 {
+    let blend = blend.clamp(0, 100);
+    if dir_version > 600 {
+        match blend {
+            100 => 0,
+            // 255 would read back as opaque
+            0 => 254,
+            _ => (255 - (blend * 255 + 99) / 100) as u8,
+        }
+    } else if blend == 100 {
+        0
+    } else {
+        blend as u8
+    }
+}
+
 impl Score {
     pub fn empty() -> Score {
         Score {
@@ -231,6 +255,8 @@ impl Score {
             sound_channel_data: vec![],
             tempo_channel_data: vec![],
             palette_channel_data: vec![],
+            palette_effect_data: vec![],
+            transition_channel_data: vec![],
             sprite_spans: vec![],
             sound_channel_triggered: HashMap::new(),
             keyframes_cache: Arc::new(HashMap::new()),
@@ -242,6 +268,7 @@ impl Score {
             active_channels_cache: RefCell::new(HashMap::new()),
             sorted_channels_cache: RefCell::new(None),
             render_channel_cache_generation: 0,
+            recorded_channels: HashSet::new(),
         }
     }
 
@@ -852,7 +879,8 @@ impl Score {
         // In D5, sprite properties (member, position, ink, etc.) can change every frame
         // via delta-compressed score data. Update already-entered, non-puppeted sprites
         // from the current frame's channel_initialization_data.
-        if self.needs_per_frame_updates {
+        // Channels edited by score recording are updated the same way.
+        if self.needs_per_frame_updates || !self.recorded_channels.is_empty() {
             // Collect updates first to avoid borrow conflicts
             let updates: Vec<(i16, ScoreFrameChannelData)> = self.channel_initialization_data
                 .iter()
@@ -864,6 +892,9 @@ impl Score {
                     if channel_number < 1 {
                         return None; // Skip frame scripts and effect channels
                     }
+                    if !self.needs_per_frame_updates && !self.recorded_channels.contains(&channel_number) {
+                        return None;
+                    }
                     let sprite_num = channel_number as i16;
                     // Skip if this sprite was just entered above (already initialized)
                     if spans_to_enter.iter().any(|s| s.channel_number == channel_number) {
@@ -2487,12 +2518,15 @@ impl Score {
         // Clear previous sprite_spans so they don't accumulate across movie transitions
         self.sprite_spans.clear();
         self.sprite_details.clear();
+        self.recorded_channels.clear();
         self.invalidate_span_channel_cache();
 
         self.channel_initialization_data = score_chunk.frame_data.frame_channel_data.clone();
         self.sound_channel_data = score_chunk.frame_data.sound_channel_data.clone();
         self.tempo_channel_data = score_chunk.frame_data.tempo_channel_data.clone();
         self.palette_channel_data = score_chunk.frame_data.palette_channel_data.clone();
+        self.palette_effect_data = score_chunk.frame_data.palette_effect_data.clone();
+        self.transition_channel_data = score_chunk.frame_data.transition_channel_data.clone();
         self.keyframes_cache = Arc::new(build_all_keyframes_cache(
             &score_chunk.frame_data.frame_channel_data,
             &score_chunk.frame_intervals
@@ -2940,18 +2974,9 @@ impl Score {
                 let fps = tempo_data.tempo_cue_point;
                 if fps > 0 { Some(fps as u32) } else { None }
             }
-            247 => {
-                // Delay mode: tempo_cue_point is delay in seconds
-                // Convert to a very low FPS to approximate the delay
-                // (actual wait-for-delay should be handled separately)
-                None // Fall back to movie frame rate; delay handled elsewhere
-            }
-            248 => {
-                // Wait for mouse click - not an FPS value
-                None
-            }
-            254 | 255 => {
-                // Wait for sound channel - not an FPS value
+            247 | 248 | 254 | 255 => {
+                // Delay / wait for click / wait for sound or cue point:
+                // not an FPS value, the frame loop blocks via tempo_wait.
                 None
             }
             1..=120 => {
@@ -2966,6 +2991,36 @@ impl Score {
         }
     }
 
+    /// Wait mode (delay, click, sound, cue point) in the tempo channel of
+    /// `frame`. Unlike the frame rate, waits only apply to their own frame.
+    pub fn get_frame_tempo_wait(&self, frame: u32) -> Option<TempoWaitMode> {
+        self.tempo_channel_data
+            .iter()
+            .find(|(frame_idx, _)| *frame_idx + 1 == frame)
+            .and_then(|(_, td)| TempoWaitMode::from_tempo_data(td))
+    }
+
+    /// Transition member placed in the transition channel of `frame`.
+    /// Unlike tempo and palette, transitions do not persist across frames.
+    pub fn get_frame_transition(&self, frame: u32) -> Option<CastMemberRef> {
+        self.transition_channel_data
+            .iter()
+            .find(|(frame_idx, _, _)| *frame_idx + 1 == frame)
+            .map(|(_, cast_lib, member)| CastMemberRef {
+                cast_lib: (*cast_lib).max(1) as i32,
+                cast_member: *member as i32,
+            })
+    }
+
+    /// Palette channel effect settings authored on `frame` itself.
+    pub fn get_frame_palette_effect(&self, frame: u32) -> Option<&PaletteChannelData> {
+        let index = frame.checked_sub(1)?;
+        self.palette_effect_data
+            .iter()
+            .find(|(frame_idx, _)| *frame_idx == index)
+            .map(|(_, data)| data)
+    }
+
     pub fn get_frame_palette(&self, frame: u32) -> PaletteRef {
         self.palette_channel_data
             .iter()
@@ -3628,6 +3683,15 @@ fn sprite_set_prop_is_noop(
 }
 
 pub fn sprite_set_prop(sprite_id: i16, prop_name: &str, value: Datum) -> Result<(), ScriptError> {
+    watch::observe_sprite(
+        sprite_id,
+        prop_name,
+        |player| sprite_get_prop(player, sprite_id, prop_name),
+        || apply_sprite_prop(sprite_id, prop_name, value),
+    )
+}
+
+fn apply_sprite_prop(sprite_id: i16, prop_name: &str, value: Datum) -> Result<(), ScriptError> {
     if sprite_set_prop_is_noop(sprite_id, prop_name, &value)? {
         return Ok(());
     }
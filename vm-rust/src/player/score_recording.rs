@@ -0,0 +1,657 @@
+//! Score recording: `beginRecording` ... `endRecording` sessions that edit
+//! the stage score at runtime.
+//!
+//! Edits go into the same structures `Score::load_from_score_chunk` fills
+//! (`channel_initialization_data`, the effect channel vectors and
+//! `sprite_spans`), so recorded frames play back like authored ones.
+//! `updateFrame` writes the live sprite channels plus the frame channels set
+//! through `the frameScript`, `the frameTempo`, `the frameSound1` etc. into
+//! the current frame and moves the playhead on. Channels touched by an edit
+//! are replayed from their per-frame data rather than authored tweens (see
+//! `Score::recorded_channels`).
+
+use std::sync::Arc;
+
+use crate::director::chunks::score::{PaletteChannelData, ScoreFrameChannelData, SoundChannelData, TempoChannelData};
+use crate::director::lingo::datum::Datum;
+use crate::js_api::JsApi;
+
+use super::{
+    allocator::ScriptInstanceAllocatorTrait,
+    cast_lib::CastMemberRef,
+    movie::Movie,
+    score::{convert_blend_to_raw, Score, ScoreBehaviorReference, ScoreSpriteSpan},
+    sprite::ColorRef,
+    DirPlayer, ScriptError,
+};
+
+/// Sound channel indexes in `sound_channel_data` (D6+ layout).
+const SOUND1_CHANNEL_INDEX: u16 = 4;
+const SOUND2_CHANNEL_INDEX: u16 = 3;
+
+/// The frame-level channels of one frame.
+#[derive(Clone, Default)]
+pub struct FrameChannels {
+    pub script: Option<CastMemberRef>,
+    pub tempo: Option<TempoChannelData>,
+    pub sound1: Option<u8>,
+    pub sound2: Option<u8>,
+    pub transition: Option<(i16, i16)>,
+    pub palette: Option<(i16, i16)>,
+    pub palette_effect: Option<PaletteChannelData>,
+}
+
+impl FrameChannels {
+    pub fn read(score: &Score, frame: u32) -> FrameChannels {
+        let index = frame.saturating_sub(1);
+        let sound = |channel_index: u16| {
+            score
+                .sound_channel_data
+                .iter()
+                .find(|(f, c, _)| *f == index && *c == channel_index)
+                .map(|(_, _, data)| data.cast_member)
+        };
+        FrameChannels {
+            script: score.get_script_in_frame(frame).map(|script| CastMemberRef {
+                cast_lib: (script.cast_lib as i32).max(1),
+                cast_member: script.cast_member as i32,
+            }),
+            tempo: score.tempo_channel_data.iter().find(|(f, _)| *f == index).map(|(_, data)| data.clone()),
+            sound1: sound(SOUND1_CHANNEL_INDEX),
+            sound2: sound(SOUND2_CHANNEL_INDEX),
+            transition: score
+                .transition_channel_data
+                .iter()
+                .find(|(f, _, _)| *f == index)
+                .map(|(_, lib, member)| (*lib, *member)),
+            palette: score
+                .palette_channel_data
+                .iter()
+                .find(|(f, _, _)| *f == index)
+                .map(|(_, lib, member)| (*lib, *member)),
+            palette_effect: score.get_frame_palette_effect(frame).cloned(),
+        }
+    }
+
+    fn write(&self, score: &mut Score, frame: u32) {
+        let index = frame - 1;
+        score.set_frame_script(frame, self.script.clone());
+
+        score.tempo_channel_data.retain(|(f, _)| *f != index);
+        if let Some(tempo) = &self.tempo {
+            score.tempo_channel_data.push((index, tempo.clone()));
+        }
+        score.sound_channel_data.retain(|(f, _, _)| *f != index);
+        for (channel_index, member) in [(SOUND1_CHANNEL_INDEX, self.sound1), (SOUND2_CHANNEL_INDEX, self.sound2)] {
+            if let Some(cast_member) = member {
+                score.sound_channel_data.push((index, channel_index, SoundChannelData { cast_member }));
+            }
+        }
+        score.transition_channel_data.retain(|(f, _, _)| *f != index);
+        if let Some((lib, member)) = self.transition {
+            score.transition_channel_data.push((index, lib, member));
+        }
+        score.palette_channel_data.retain(|(f, _, _)| *f != index);
+        if let Some((lib, member)) = self.palette {
+            score.palette_channel_data.push((index, lib, member));
+        }
+        score.palette_effect_data.retain(|(f, _)| *f != index);
+        if let Some(effect) = &self.palette_effect {
+            score.palette_effect_data.push((index, effect.clone()));
+        }
+
+        sort_entries(&mut score.tempo_channel_data);
+        sort_entries(&mut score.sound_channel_data);
+        sort_entries(&mut score.transition_channel_data);
+        sort_entries(&mut score.palette_channel_data);
+        sort_entries(&mut score.palette_effect_data);
+    }
+}
+
+/// An open recording session. Frame channels set from Lingo are pending
+/// until `updateFrame` writes them into `frame`.
+pub struct ScoreRecording {
+    pub frame: u32,
+    pub frame_channels: FrameChannels,
+}
+
+/// Entries of the per-frame score vectors, keyed by 0-based frame index.
+trait FrameEntry: Clone {
+    fn frame_index(&self) -> u32;
+    fn frame_index_mut(&mut self) -> &mut u32;
+}
+
+impl<A: Clone> FrameEntry for (u32, A) {
+    fn frame_index(&self) -> u32 {
+        self.0
+    }
+    fn frame_index_mut(&mut self) -> &mut u32 {
+        &mut self.0
+    }
+}
+
+impl<A: Clone, B: Clone> FrameEntry for (u32, A, B) {
+    fn frame_index(&self) -> u32 {
+        self.0
+    }
+    fn frame_index_mut(&mut self) -> &mut u32 {
+        &mut self.0
+    }
+}
+
+fn sort_entries<T: FrameEntry>(entries: &mut [T]) {
+    entries.sort_by_key(|entry| entry.frame_index());
+}
+
+/// Copy the entries of frame index `index` into `index + 1`, shifting later
+/// frames up.
+fn duplicate_entries<T: FrameEntry>(entries: &mut Vec<T>, index: u32) {
+    let mut copies = vec![];
+    for entry in entries.iter_mut() {
+        let frame_index = entry.frame_index_mut();
+        if *frame_index > index {
+            *frame_index += 1;
+        } else if *frame_index == index {
+            let mut copy = entry.clone();
+            *copy.frame_index_mut() += 1;
+            copies.push(copy);
+        }
+    }
+    entries.extend(copies);
+    sort_entries(entries);
+}
+
+/// Drop the entries of frame index `index`, shifting later frames down.
+fn delete_entries<T: FrameEntry>(entries: &mut Vec<T>, index: u32) {
+    entries.retain(|entry| entry.frame_index() != index);
+    for entry in entries.iter_mut() {
+        let frame_index = entry.frame_index_mut();
+        if *frame_index > index {
+            *frame_index -= 1;
+        }
+    }
+}
+
+fn behavior_key(scripts: &[ScoreBehaviorReference]) -> Vec<(u16, u16)> {
+    scripts.iter().map(|script| (script.cast_lib, script.cast_member)).collect()
+}
+
+fn channel_index(channel: u32) -> u16 {
+    if channel == 0 { 0 } else { (channel + 5) as u16 }
+}
+
+impl Score {
+    /// Write sprite channel `channel` of `frame`. `None` empties the channel.
+    pub fn record_sprite_channel(
+        &mut self,
+        frame: u32,
+        channel: u32,
+        data: Option<ScoreFrameChannelData>,
+        scripts: Vec<ScoreBehaviorReference>,
+    ) {
+        let index = channel_index(channel);
+        self.channel_initialization_data
+            .retain(|(f, c, _)| !(*f + 1 == frame && *c == index));
+        self.carve_span(channel, frame);
+        if let Some(data) = data {
+            self.channel_initialization_data.push((frame - 1, index, data));
+            sort_entries(&mut self.channel_initialization_data);
+            self.sprite_spans.push(ScoreSpriteSpan {
+                channel_number: channel,
+                start_frame: frame,
+                end_frame: frame,
+                scripts,
+            });
+            self.merge_spans_at(channel, frame);
+        }
+        self.mark_recorded(channel);
+    }
+
+    /// Put `script` in the script channel of `frame`, or clear it.
+    pub fn set_frame_script(&mut self, frame: u32, script: Option<CastMemberRef>) {
+        self.channel_initialization_data
+            .retain(|(f, c, _)| !(*f + 1 == frame && *c == 0));
+        self.carve_span(0, frame);
+        if let Some(script) = script {
+            self.sprite_spans.push(ScoreSpriteSpan {
+                channel_number: 0,
+                start_frame: frame,
+                end_frame: frame,
+                scripts: vec![ScoreBehaviorReference {
+                    cast_lib: script.cast_lib as u16,
+                    cast_member: script.cast_member as u16,
+                    parameter: vec![],
+                }],
+            });
+            self.merge_spans_at(0, frame);
+        }
+    }
+
+    /// Duplicate `frame` into a new frame right after it.
+    pub fn insert_frame(&mut self, frame: u32) {
+        let index = frame - 1;
+        duplicate_entries(&mut self.channel_initialization_data, index);
+        duplicate_entries(&mut self.sound_channel_data, index);
+        duplicate_entries(&mut self.tempo_channel_data, index);
+        duplicate_entries(&mut self.palette_channel_data, index);
+        duplicate_entries(&mut self.palette_effect_data, index);
+        duplicate_entries(&mut self.transition_channel_data, index);
+
+        self.mark_channels_from(frame);
+        for span in &mut self.sprite_spans {
+            if span.start_frame > frame {
+                span.start_frame += 1;
+                span.end_frame += 1;
+            } else if span.end_frame >= frame {
+                span.end_frame += 1;
+            }
+        }
+        for label in &mut self.frame_labels {
+            if label.frame_num > frame as i32 {
+                label.frame_num += 1;
+            }
+        }
+        self.frame_count = Some(self.frame_count.unwrap_or(0).max(frame) + 1);
+    }
+
+    /// Remove `frame`; the frames after it move up by one.
+    pub fn delete_frame(&mut self, frame: u32) {
+        let index = frame - 1;
+        delete_entries(&mut self.channel_initialization_data, index);
+        delete_entries(&mut self.sound_channel_data, index);
+        delete_entries(&mut self.tempo_channel_data, index);
+        delete_entries(&mut self.palette_channel_data, index);
+        delete_entries(&mut self.palette_effect_data, index);
+        delete_entries(&mut self.transition_channel_data, index);
+
+        self.mark_channels_from(frame);
+        self.sprite_spans
+            .retain(|span| !(span.start_frame == frame && span.end_frame == frame));
+        for span in &mut self.sprite_spans {
+            if span.start_frame > frame {
+                span.start_frame -= 1;
+                span.end_frame -= 1;
+            } else if span.end_frame >= frame {
+                span.end_frame -= 1;
+            }
+        }
+        self.frame_labels.retain(|label| label.frame_num != frame as i32);
+        for label in &mut self.frame_labels {
+            if label.frame_num > frame as i32 {
+                label.frame_num -= 1;
+            }
+        }
+        if let Some(frame_count) = self.frame_count.filter(|count| *count >= frame) {
+            self.frame_count = Some(frame_count.saturating_sub(1).max(1));
+        }
+    }
+
+    /// Empty every channel of `frame`, effect channels included.
+    pub fn clear_frame(&mut self, frame: u32) {
+        let index = frame - 1;
+        self.channel_initialization_data.retain(|(f, _, _)| *f != index);
+        self.sound_channel_data.retain(|(f, _, _)| *f != index);
+        self.tempo_channel_data.retain(|(f, _)| *f != index);
+        self.palette_channel_data.retain(|(f, _, _)| *f != index);
+        self.palette_effect_data.retain(|(f, _)| *f != index);
+        self.transition_channel_data.retain(|(f, _, _)| *f != index);
+
+        let channels: Vec<u32> = self
+            .sprite_spans
+            .iter()
+            .filter(|span| Self::is_span_in_frame(span, frame))
+            .map(|span| span.channel_number)
+            .collect();
+        for channel in channels {
+            self.carve_span(channel, frame);
+            self.mark_recorded(channel);
+        }
+    }
+
+    /// Remove `frame` from the channel's span covering it, splitting the
+    /// span in two if the frame is in the middle.
+    fn carve_span(&mut self, channel: u32, frame: u32) {
+        let Some(position) = self
+            .sprite_spans
+            .iter()
+            .position(|span| span.channel_number == channel && Self::is_span_in_frame(span, frame))
+        else {
+            return;
+        };
+        let span = self.sprite_spans.remove(position);
+        if span.start_frame < frame {
+            self.sprite_spans.push(ScoreSpriteSpan { end_frame: frame - 1, ..span.clone() });
+        }
+        if span.end_frame > frame {
+            self.sprite_spans.push(ScoreSpriteSpan { start_frame: frame + 1, ..span });
+        }
+    }
+
+    /// Join the span starting at `frame` with its neighbours when they hold
+    /// the same member and behaviors, so a sprite recorded over consecutive
+    /// frames stays one sprite.
+    fn merge_spans_at(&mut self, channel: u32, frame: u32) {
+        self.merge_spans_across(channel, frame - 1);
+        let end = self
+            .sprite_spans
+            .iter()
+            .find(|span| span.channel_number == channel && Self::is_span_in_frame(span, frame))
+            .map(|span| span.end_frame);
+        if let Some(end) = end {
+            self.merge_spans_across(channel, end);
+        }
+    }
+
+    /// Merge the span ending at `end` with the one starting at `end + 1`.
+    fn merge_spans_across(&mut self, channel: u32, end: u32) {
+        let find = |score: &Score, predicate: &dyn Fn(&ScoreSpriteSpan) -> bool| {
+            score.sprite_spans.iter().position(|span| span.channel_number == channel && predicate(span))
+        };
+        let (Some(first), Some(second)) = (
+            find(self, &|span| span.end_frame == end),
+            find(self, &|span| span.start_frame == end + 1),
+        ) else {
+            return;
+        };
+        let same_content = self.span_member(&self.sprite_spans[first]) == self.span_member(&self.sprite_spans[second])
+            && behavior_key(&self.sprite_spans[first].scripts) == behavior_key(&self.sprite_spans[second].scripts);
+        if !same_content {
+            return;
+        }
+        let end_frame = self.sprite_spans[second].end_frame;
+        self.sprite_spans[first].end_frame = end_frame;
+        self.sprite_spans.remove(second);
+    }
+
+    /// The member a span starts with.
+    fn span_member(&self, span: &ScoreSpriteSpan) -> Option<(u16, u16)> {
+        let index = channel_index(span.channel_number);
+        self.channel_initialization_data
+            .iter()
+            .find(|(f, c, _)| *f + 1 == span.start_frame && *c == index)
+            .map(|(_, _, data)| (data.cast_lib, data.cast_member))
+    }
+
+    /// Replay `channel` from its per-frame data from now on; authored tweens
+    /// no longer line up with the edited frames.
+    fn mark_recorded(&mut self, channel: u32) {
+        if channel == 0 {
+            return;
+        }
+        self.recorded_channels.insert(channel);
+        if self.keyframes_cache.contains_key(&(channel as u16)) {
+            Arc::make_mut(&mut self.keyframes_cache).remove(&(channel as u16));
+        }
+    }
+
+    /// Mark every channel with a sprite at or after `frame`, for edits that
+    /// shift frames.
+    fn mark_channels_from(&mut self, frame: u32) {
+        let channels: Vec<u32> = self
+            .sprite_spans
+            .iter()
+            .filter(|span| span.end_frame >= frame)
+            .map(|span| span.channel_number)
+            .collect();
+        for channel in channels {
+            self.mark_recorded(channel);
+        }
+    }
+
+    fn score_edited(&mut self) {
+        self.invalidate_span_channel_cache();
+        JsApi::dispatch_score_changed();
+    }
+}
+
+fn require_recording(player: &mut DirPlayer, command: &str) -> Result<u32, ScriptError> {
+    if player.movie.score_recording.is_none() {
+        return Err(ScriptError::new(format!("{command} is only allowed during a score recording session")));
+    }
+    // `go` moves the playhead straight away while recording.
+    if let Some(frame) = player.next_frame.take() {
+        player.movie.current_frame = frame;
+    }
+    Ok(player.movie.current_frame)
+}
+
+/// Move the playhead to `frame` and load its frame channels for editing.
+fn move_playhead(player: &mut DirPlayer, frame: u32) {
+    let frame_channels = FrameChannels::read(&player.movie.score, frame);
+    player.movie.current_frame = frame;
+    player.movie.score_recording = Some(ScoreRecording { frame, frame_channels });
+}
+
+pub fn begin_recording(player: &mut DirPlayer) {
+    if let Some(frame) = player.next_frame.take() {
+        player.movie.current_frame = frame;
+    }
+    move_playhead(player, player.movie.current_frame);
+}
+
+pub fn end_recording(player: &mut DirPlayer) {
+    player.movie.score_recording = None;
+}
+
+/// `updateFrame`: record the sprite and frame channels into the current
+/// frame, then move to the next frame.
+pub fn update_frame(player: &mut DirPlayer) -> Result<(), ScriptError> {
+    let frame = require_recording(player, "updateFrame")?;
+    let frame_channels = pending_frame_channels(&mut player.movie).clone();
+
+    for channel in 1..player.movie.score.channels.len() as u32 {
+        let recorded = sprite_channel_data(player, channel);
+        let score = &mut player.movie.score;
+        let had_sprite = score
+            .sprite_spans
+            .iter()
+            .any(|span| span.channel_number == channel && Score::is_span_in_frame(span, frame));
+        if recorded.is_none() && !had_sprite {
+            continue;
+        }
+        let (data, scripts) = match recorded {
+            Some((data, scripts)) => (Some(data), scripts),
+            None => (None, vec![]),
+        };
+        score.record_sprite_channel(frame, channel, data, scripts);
+    }
+
+    let score = &mut player.movie.score;
+    frame_channels.write(score, frame);
+    score.frame_count = Some(score.frame_count.unwrap_or(0).max(frame));
+    score.score_edited();
+    move_playhead(player, frame + 1);
+    Ok(())
+}
+
+/// `insertFrame` / `duplicateFrame`: duplicate the current frame after
+/// itself and make the copy current.
+pub fn insert_frame(player: &mut DirPlayer, command: &str) -> Result<(), ScriptError> {
+    let frame = require_recording(player, command)?;
+    let frame_channels = pending_frame_channels(&mut player.movie).clone();
+    let score = &mut player.movie.score;
+    frame_channels.write(score, frame);
+    score.insert_frame(frame);
+    score.score_edited();
+    move_playhead(player, frame + 1);
+    Ok(())
+}
+
+/// `deleteFrame`: remove the current frame; the next one takes its place.
+pub fn delete_frame(player: &mut DirPlayer) -> Result<(), ScriptError> {
+    let frame = require_recording(player, "deleteFrame")?;
+    let score = &mut player.movie.score;
+    score.delete_frame(frame);
+    score.score_edited();
+    move_playhead(player, frame);
+    Ok(())
+}
+
+/// `clearFrame`: empty every channel of the current frame.
+pub fn clear_frame(player: &mut DirPlayer) -> Result<(), ScriptError> {
+    let frame = require_recording(player, "clearFrame")?;
+    let score = &mut player.movie.score;
+    score.clear_frame(frame);
+    score.set_frame_script(frame, None);
+    score.score_edited();
+    move_playhead(player, frame);
+    Ok(())
+}
+
+/// Frame channels being edited, reloaded if the playhead moved since.
+fn pending_frame_channels(movie: &mut Movie) -> &mut FrameChannels {
+    let frame = movie.current_frame;
+    let recording = movie.score_recording.as_mut().unwrap();
+    if recording.frame != frame {
+        recording.frame = frame;
+        recording.frame_channels = FrameChannels::read(&movie.score, frame);
+    }
+    &mut recording.frame_channels
+}
+
+/// Score data for the sprite currently in `channel`, or `None` if the
+/// channel is empty.
+fn sprite_channel_data(
+    player: &DirPlayer,
+    channel: u32,
+) -> Option<(ScoreFrameChannelData, Vec<ScoreBehaviorReference>)> {
+    let sprite = &player.movie.score.channels.get(channel as usize)?.sprite;
+    let member_ref = sprite.member.as_ref().filter(|member| member.cast_member > 0)?;
+    let dir_version = player.movie.dir_version;
+    let is_shape = player
+        .movie
+        .cast_manager
+        .find_member_by_ref(member_ref)
+        .is_some_and(|member| member.member_type.type_string() == "shape");
+
+    let mut data = ScoreFrameChannelData {
+        sprite_type: 1,
+        ink: if is_shape && dir_version > 700 { (sprite.ink * 5) as u8 } else { sprite.ink as u8 },
+        cast_lib: member_ref.cast_lib as u16,
+        cast_member: member_ref.cast_member as u16,
+        pos_x: sprite.loc_h as i16,
+        pos_y: sprite.loc_v as i16,
+        width: sprite.width.max(0) as u16,
+        height: sprite.height.max(0) as u16,
+        blend: convert_blend_to_raw(sprite.blend, dir_version),
+        rotation: sprite.rotation,
+        skew: sprite.skew,
+        moveable: sprite.moveable,
+        editable: sprite.editable,
+        trails: sprite.trails,
+        sprite_flags: 0x01 | if sprite.flip_h { 0x20 } else { 0 } | if sprite.flip_v { 0x40 } else { 0 },
+        ..Default::default()
+    };
+    match sprite.color {
+        ColorRef::PaletteIndex(index) => data.fore_color = index,
+        ColorRef::Rgb(r, g, b) => {
+            (data.fore_color, data.fore_color_g, data.fore_color_b) = (r, g, b);
+            data.color_flag |= 1;
+        }
+    }
+    match sprite.bg_color {
+        ColorRef::PaletteIndex(index) => data.back_color = index,
+        ColorRef::Rgb(r, g, b) => {
+            (data.back_color, data.back_color_g, data.back_color_b) = (r, g, b);
+            data.color_flag |= 2;
+        }
+    }
+
+    let scripts = sprite
+        .script_instance_list
+        .iter()
+        .filter_map(|instance_ref| player.allocator.get_script_instance_opt(instance_ref))
+        .map(|instance| ScoreBehaviorReference {
+            cast_lib: instance.script.cast_lib as u16,
+            cast_member: instance.script.cast_member as u16,
+            parameter: vec![],
+        })
+        .collect();
+    Some((data, scripts))
+}
+
+fn member_number(member: &Option<CastMemberRef>) -> i32 {
+    member.as_ref().map_or(0, |member| member.cast_member)
+}
+
+/// `the frameScript`, `the frameTempo`, `the frameSound1`, `the frameSound2`,
+/// `the frameTransition` and `the framePalette` of the current frame. While
+/// recording these are the values pending for `updateFrame`.
+pub fn get_frame_channel_prop(movie: &Movie, prop: &str) -> Result<Datum, ScriptError> {
+    let frame_channels = match &movie.score_recording {
+        Some(recording) if recording.frame == movie.current_frame => recording.frame_channels.clone(),
+        _ => FrameChannels::read(&movie.score, movie.current_frame),
+    };
+    let value = match prop.to_lowercase().as_str() {
+        "framescript" => member_number(&frame_channels.script),
+        "frametempo" => match &frame_channels.tempo {
+            Some(tempo) if tempo.tempo == 246 => tempo.tempo_cue_point as i32,
+            Some(tempo) if (1..=120).contains(&tempo.tempo) => tempo.tempo as i32,
+            _ => movie
+                .score
+                .get_frame_tempo(movie.current_frame)
+                .unwrap_or(movie.frame_rate as u32) as i32,
+        },
+        "framesound1" => frame_channels.sound1.map_or(0, i32::from),
+        "framesound2" => frame_channels.sound2.map_or(0, i32::from),
+        "frametransition" => frame_channels.transition.map_or(0, |(_, member)| member as i32),
+        "framepalette" => frame_channels.palette.map_or(0, |(_, member)| member as i32),
+        _ => return Err(ScriptError::new(format!("Cannot get movie prop {prop}"))),
+    };
+    Ok(Datum::Int(value))
+}
+
+/// Member given as a member reference or a member number in cast 1.
+/// 0 or VOID clears the channel.
+fn member_value(value: &Datum) -> Result<Option<CastMemberRef>, ScriptError> {
+    match value {
+        Datum::Void => Ok(None),
+        Datum::CastMember(member_ref) if member_ref.cast_member > 0 => Ok(Some(member_ref.clone())),
+        Datum::CastMember(_) => Ok(None),
+        value => match value.int_value()? {
+            0 => Ok(None),
+            number => Ok(Some(CastMemberRef { cast_lib: 1, cast_member: number })),
+        },
+    }
+}
+
+pub fn set_frame_channel_prop(movie: &mut Movie, prop: &str, value: &Datum) -> Result<(), ScriptError> {
+    if movie.score_recording.is_none() {
+        return Err(ScriptError::new(format!(
+            "the {prop} can only be set during a score recording session"
+        )));
+    }
+    let frame_channels = pending_frame_channels(movie);
+    match prop.to_lowercase().as_str() {
+        "framescript" => frame_channels.script = member_value(value)?,
+        "frametempo" => {
+            frame_channels.tempo = match value.int_value()? {
+                fps if fps <= 0 => None,
+                fps => Some(TempoChannelData {
+                    tempo: if fps <= 120 { fps as u8 } else { 246 },
+                    tempo_cue_point: if fps <= 120 { 0 } else { fps as u16 },
+                    sprite_list_idx: 0,
+                    color_tempo: 0,
+                    wait_flags: 0,
+                    channel_flags: 0,
+                    frame_data: 0,
+                }),
+            }
+        }
+        "framesound1" => frame_channels.sound1 = member_value(value)?.map(|member| member.cast_member as u8),
+        "framesound2" => frame_channels.sound2 = member_value(value)?.map(|member| member.cast_member as u8),
+        "frametransition" => {
+            frame_channels.transition =
+                member_value(value)?.map(|member| (member.cast_lib as i16, member.cast_member as i16))
+        }
+        "framepalette" => {
+            frame_channels.palette = match value {
+                // Negative numbers are built-in palettes
+                Datum::Int(number) if *number < 0 => Some((0, *number as i16)),
+                value => member_value(value)?.map(|member| (member.cast_lib as i16, member.cast_member as i16)),
+            }
+        }
+        _ => return Err(ScriptError::new(format!("Cannot set movie prop {prop}"))),
+    }
+    Ok(())
+}
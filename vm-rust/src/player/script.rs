@@ -7,7 +7,6 @@ use log::warn;
 use crate::director::{
     chunks::{handler::HandlerDef, script::ScriptChunk},
     enums::ScriptType,
-    file::get_variable_multiplier,
     lingo::{datum::Datum, script::ScriptContext},
 };
 
@@ -18,6 +17,7 @@ use super::{
     bytecode::handler_manager::BytecodeHandlerContext,
     cast_lib::{player_cast_lib_set_prop, CastMemberRef},
     datum_formatting::{format_concrete_datum, format_datum},
+    debug::watch,
     handlers::{
         datum_handlers::{
             bitmap::BitmapDatumHandlers, cast_member_ref::CastMemberRefHandlers,
@@ -28,7 +28,7 @@ use super::{
             timeout::TimeoutDatumHandlers, void::VoidDatumHandlers,
             date::DateDatumHandlers, math::MathDatumHandlers,
             vector::VectorDatumHandlers, xml::XmlDatumHandlers,
-            float::FloatDatumHandlers,
+            float::FloatDatumHandlers, window::WindowDatumHandlers,
             cast_member::shockwave3d::Shockwave3dMemberHandlers,
         },
         types::TypeUtils,
@@ -38,7 +38,7 @@ use super::{
     score::{sprite_get_prop, sprite_set_prop},
     script_ref::ScriptInstanceRef,
     stage::{get_stage_prop, set_stage_prop},
-    DatumRef, DirPlayer, ScriptError,
+    window, DatumRef, DirPlayer, ScriptError,
 };
 
 #[derive(Clone)]
@@ -61,6 +61,9 @@ pub struct ScriptInstance {
     pub ancestor: Option<ScriptInstanceRef>,
     pub properties: FxHashMap<CiString, DatumRef>,
     pub begin_sprite_called: bool,
+    /// Window whose movie created the instance, or `None` for the stage.
+    /// `script` refers to a cast in that movie.
+    pub window: Option<u32>,
 }
 
 impl ScriptInstance {
@@ -69,6 +72,7 @@ impl ScriptInstance {
         script_ref: CastMemberRef,
         script_def: &Script,
         lctx: &ScriptContext,
+        window: Option<u32>,
     ) -> ScriptInstance {
         let mut properties = FxHashMap::default();
 
@@ -88,6 +92,7 @@ impl ScriptInstance {
             ancestor: None,
             properties,
             begin_sprite_called: false,
+            window,
         }
     }
 }
@@ -292,6 +297,18 @@ pub fn script_set_prop(
     prop_name: &str,
     value_ref: &DatumRef,
     required: bool,
+) -> Result<(), ScriptError> {
+    watch::observe_property(player, script_instance_ref, prop_name, |player| {
+        set_instance_prop(player, script_instance_ref, prop_name, value_ref, required)
+    })
+}
+
+fn set_instance_prop(
+    player: &mut DirPlayer,
+    script_instance_ref: &ScriptInstanceRef,
+    prop_name: &str,
+    value_ref: &DatumRef,
+    required: bool,
 ) -> Result<(), ScriptError> {
     // Check virtual script handler first
     match super::virtual_scripts::VirtualScriptRegistry::try_set_instance_prop(player, script_instance_ref, prop_name, value_ref) {
@@ -395,17 +412,8 @@ pub fn get_current_handler_def<'a>(
     return unsafe { &*ctx.handler_def_ptr };
 }
 
-pub fn get_current_variable_multiplier(player: &DirPlayer, ctx: &BytecodeHandlerContext) -> u32 {
-    let script = get_current_script(player, ctx);
-    if let Some(script) = script {
-        let cast = player
-            .movie
-            .cast_manager
-            .get_cast(script.member_ref.cast_lib as u32)
-            .unwrap();
-        return get_variable_multiplier(cast.capital_x, cast.dir_version);
-    }
-    panic!("No current script found");
+pub fn get_current_variable_multiplier(_: &DirPlayer, ctx: &BytecodeHandlerContext) -> u32 {
+    ctx.variable_multiplier
 }
 
 pub fn get_lctx<'a>(
@@ -432,15 +440,13 @@ pub fn get_lctx_for_script<'a>(
 }
 
 pub fn get_name<'a>(
-    player: &'a DirPlayer,
+    _: &'a DirPlayer,
     ctx: &'a BytecodeHandlerContext,
     name_id: u16,
 ) -> Option<&'a String> {
-    let lctx = get_lctx(player, ctx);
-    if let Some(lctx) = lctx {
-        return Some(&lctx.names[name_id as usize]);
-    }
-    None
+    // Read the names through the context rather than the current movie, so
+    // handlers keep resolving names while another window's movie is active.
+    ctx.names.get(name_id as usize)
 }
 
 pub async fn player_set_obj_prop(
@@ -468,7 +474,10 @@ pub async fn player_set_obj_prop(
             // TODO should we really pass a clone of the value here?
             CastMemberRefHandlers::set_prop(&member_ref, prop_name, value_clone)
         }
-        Datum::Stage => reserve_player_mut(|player| set_stage_prop(player, &prop_name, value_ref)),
+        Datum::Stage => reserve_player_mut(|player| {
+            window::with_stage_context(player, |player| set_stage_prop(player, prop_name, value_ref))
+        }),
+        Datum::Window(name) => WindowDatumHandlers::set_prop(&name, prop_name, value_clone).await,
         Datum::BitmapRef(bitmap_ref) => reserve_player_mut(|player| {
             BitmapDatumHandlers::set_bitmap_ref_prop(player, bitmap_ref, prop_name, value_ref)
         }),
@@ -479,7 +488,7 @@ pub async fn player_set_obj_prop(
             => reserve_player_mut(|player| {
             TimeoutDatumHandlers::set_prop(player, obj_ref, prop_name, value_ref)
         }),
-        Datum::PropList(..) => reserve_player_mut(|player| {
+        Datum::PropList(..) => watch::observe_list(obj_ref, || reserve_player_mut(|player| {
             let key_ref = player.alloc_datum(Datum::Symbol(prop_name.to_owned()));
             PropListUtils::set_prop(
                 obj_ref,
@@ -489,7 +498,7 @@ pub async fn player_set_obj_prop(
                 false,
                 prop_name,
             )
-        }),
+        })),
         Datum::Rect(..) => reserve_player_mut(|player| {
             RectDatumHandlers::set_prop(player, obj_ref, prop_name, value_ref)
         }),
@@ -628,7 +637,11 @@ pub fn get_obj_prop(
             &player.allocator,
         )?)),
         Datum::Stage => {
-            let result = get_stage_prop(player, &prop_name)?;
+            let result = window::with_stage_context(player, |player| get_stage_prop(player, prop_name))?;
+            Ok(player.alloc_datum(result))
+        }
+        Datum::Window(name) => {
+            let result = WindowDatumHandlers::get_prop(player, &name, prop_name)?;
             Ok(player.alloc_datum(result))
         }
         Datum::Rect(..) => {
@@ -758,6 +771,10 @@ pub fn get_obj_prop(
                     }
                     Ok(player.alloc_datum(Datum::Int(0)))
                 }
+                _ if matches!(prop_name.to_ascii_lowercase().as_str(),
+                    "hyperlink" | "hyperlinkrange" | "hyperlinkstate") => {
+                    StringChunkHandlers::get_chunk_hyperlink_prop(player, obj_ref, prop_name)
+                }
                 _ if matches!(prop_name.to_ascii_lowercase().as_str(),
                     "fixedlinespace" | "topspacing" | "bottomspacing"
                     | "font" | "fontsize" | "fontstyle"
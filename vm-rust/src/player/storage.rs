@@ -0,0 +1,782 @@
+//! Persistent storage for FileIO files and getPref/setPref prefs
+//!
+//! Each movie keeps its files in its own namespace, named after where the
+//! movie was loaded from (see [`storage_namespace`]). Prefs are shared by
+//! every movie, as Director keeps them per projector rather than per movie
+//! file, and live in [`PREFS_NAMESPACE`]. [`PlayerStorage`] holds both in
+//! memory so Lingo reads are synchronous and writes each entry through to
+//! a [`StorageBackend`]:
+//! - Browser - IndexedDB, or localStorage where IndexedDB is unavailable
+//! - Directory - one file per entry under `<root>/<namespace>/<files|prefs>/`
+//! - Memory - nothing survives the process (the native default)
+//!
+//! The whole store can be exported to and imported from a ZIP archive laid
+//! out like the directory store, so players can back up their saved games
+//! and tests can start from a seeded save.
+
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    future::Future,
+    io::Read,
+    path::PathBuf,
+    pin::Pin,
+    rc::Rc,
+};
+
+use log::warn;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use url::Url;
+
+use super::{xtra::fileio::borrow_fileio_manager_mut, DirPlayer};
+
+pub type StorageFuture<T> = Pin<Box<dyn Future<Output = Result<T, String>>>>;
+
+/// Characters that can't appear in a file name on common file systems.
+const NAME_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b'%')
+    .add(b'/')
+    .add(b'\\')
+    .add(b':')
+    .add(b'*')
+    .add(b'?')
+    .add(b'"')
+    .add(b'<')
+    .add(b'>')
+    .add(b'|');
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageKind {
+    /// Files written through FileIO or Buddy API
+    File,
+    /// Values of `setPref`
+    Pref,
+}
+
+impl StorageKind {
+    fn folder(self) -> &'static str {
+        match self {
+            StorageKind::File => "files",
+            StorageKind::Pref => "prefs",
+        }
+    }
+
+    fn from_folder(folder: &str) -> Option<StorageKind> {
+        match folder {
+            "files" => Some(StorageKind::File),
+            "prefs" => Some(StorageKind::Pref),
+            _ => None,
+        }
+    }
+}
+
+/// Everything one movie has stored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StorageNamespace {
+    pub files: BTreeMap<String, Vec<u8>>,
+    pub prefs: BTreeMap<String, String>,
+}
+
+impl StorageNamespace {
+    fn insert(&mut self, kind: StorageKind, name: String, data: Vec<u8>) {
+        match kind {
+            StorageKind::File => {
+                self.files.insert(name, data);
+            }
+            StorageKind::Pref => {
+                self.prefs.insert(name, String::from_utf8_lossy(&data).into_owned());
+            }
+        }
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (StorageKind, &str, &[u8])> {
+        let files = self.files.iter().map(|(name, data)| (StorageKind::File, name.as_str(), data.as_slice()));
+        let prefs = self.prefs.iter().map(|(name, value)| (StorageKind::Pref, name.as_str(), value.as_bytes()));
+        files.chain(prefs)
+    }
+}
+
+/// Namespace of the `setPref` values, shared by every movie.
+pub const PREFS_NAMESPACE: &str = "_prefs";
+
+/// The namespace for the movie at `movie_path`, a URL or file path: its
+/// host, directory and name without extension, lowercased and encoded as
+/// one path segment. Movies with the same file name on different hosts or
+/// in different directories keep separate files.
+pub fn storage_namespace(movie_path: &str) -> String {
+    // A single-letter scheme is a Windows drive letter, not a URL.
+    let location = match Url::parse(movie_path) {
+        Ok(url) if url.scheme().len() > 1 => {
+            let host = url.host_str().unwrap_or_default();
+            let path = percent_decode_str(url.path()).decode_utf8_lossy();
+            match url.port() {
+                Some(port) => format!("{}:{}{}", host, port, path),
+                None => format!("{}{}", host, path),
+            }
+        }
+        _ => movie_path.replace('\\', "/"),
+    };
+    let (dir, name) = location.rsplit_once('/').unwrap_or(("", &location));
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    let key = [dir.trim_matches('/'), stem]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+        .to_lowercase();
+    if key.is_empty() {
+        "untitled".to_string()
+    } else {
+        utf8_percent_encode(&key, NAME_ENCODE_SET).to_string()
+    }
+}
+
+/// `<namespace>/<files|prefs>/<encoded name>`, the path of an entry in the
+/// directory store and in archives.
+fn entry_path(namespace: &str, kind: StorageKind, name: &str) -> String {
+    format!("{}/{}/{}", namespace, kind.folder(), utf8_percent_encode(name, NAME_ENCODE_SET))
+}
+
+fn parse_entry_path(path: &str) -> Option<(String, StorageKind, String)> {
+    let mut parts = path.splitn(3, '/');
+    let namespace = parts.next().filter(|namespace| !namespace.is_empty())?;
+    let kind = StorageKind::from_folder(parts.next()?)?;
+    let name = percent_decode_str(parts.next().filter(|name| !name.is_empty())?).decode_utf8().ok()?;
+    Some((namespace.to_string(), kind, name.into_owned()))
+}
+
+/// Common trait for everything that can persist movie storage.
+pub trait StorageBackend {
+    /// Read every entry stored under `namespace`.
+    fn load(&self, namespace: &str) -> StorageFuture<StorageNamespace>;
+
+    /// Names of the namespaces that have entries.
+    fn namespaces(&self) -> StorageFuture<Vec<String>>;
+
+    /// Store one entry. Failures are logged, as Lingo has no way to see them.
+    fn write(&self, namespace: &str, kind: StorageKind, name: &str, data: &[u8]);
+
+    fn remove(&self, namespace: &str, kind: StorageKind, name: &str);
+
+    /// Get the backend name for debugging
+    fn backend_name(&self) -> &'static str;
+}
+
+#[derive(Default)]
+pub struct MemoryStorageBackend {
+    namespaces: RefCell<BTreeMap<String, StorageNamespace>>,
+}
+
+impl StorageBackend for MemoryStorageBackend {
+    fn load(&self, namespace: &str) -> StorageFuture<StorageNamespace> {
+        let data = self.namespaces.borrow().get(namespace).cloned().unwrap_or_default();
+        Box::pin(std::future::ready(Ok(data)))
+    }
+
+    fn namespaces(&self) -> StorageFuture<Vec<String>> {
+        let namespaces = self.namespaces.borrow().keys().cloned().collect();
+        Box::pin(std::future::ready(Ok(namespaces)))
+    }
+
+    fn write(&self, namespace: &str, kind: StorageKind, name: &str, data: &[u8]) {
+        self.namespaces
+            .borrow_mut()
+            .entry(namespace.to_string())
+            .or_default()
+            .insert(kind, name.to_string(), data.to_vec());
+    }
+
+    fn remove(&self, namespace: &str, kind: StorageKind, name: &str) {
+        let mut namespaces = self.namespaces.borrow_mut();
+        let Some(data) = namespaces.get_mut(namespace) else {
+            return;
+        };
+        match kind {
+            StorageKind::File => data.files.remove(name).is_some(),
+            StorageKind::Pref => data.prefs.remove(name).is_some(),
+        };
+        if data.files.is_empty() && data.prefs.is_empty() {
+            namespaces.remove(namespace);
+        }
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "memory"
+    }
+}
+
+/// Keeps each entry in its own file under `root`, so saves can be inspected
+/// and seeded by hand.
+pub struct DirectoryStorageBackend {
+    pub root: PathBuf,
+}
+
+impl DirectoryStorageBackend {
+    pub fn new(root: impl Into<PathBuf>) -> DirectoryStorageBackend {
+        DirectoryStorageBackend { root: root.into() }
+    }
+
+    fn read_namespace(&self, namespace: &str) -> Result<StorageNamespace, String> {
+        let mut data = StorageNamespace::default();
+        for kind in [StorageKind::File, StorageKind::Pref] {
+            let dir = self.root.join(namespace).join(kind.folder());
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                let Ok(name) = percent_decode_str(&file_name).decode_utf8() else {
+                    continue;
+                };
+                let bytes = std::fs::read(entry.path())
+                    .map_err(|e| format!("Failed to read {}: {}", entry.path().display(), e))?;
+                data.insert(kind, name.into_owned(), bytes);
+            }
+        }
+        Ok(data)
+    }
+
+    fn list_namespaces(&self) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.root) else {
+            return vec![];
+        };
+        let mut namespaces: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        namespaces.sort();
+        namespaces
+    }
+}
+
+impl StorageBackend for DirectoryStorageBackend {
+    fn load(&self, namespace: &str) -> StorageFuture<StorageNamespace> {
+        Box::pin(std::future::ready(self.read_namespace(namespace)))
+    }
+
+    fn namespaces(&self) -> StorageFuture<Vec<String>> {
+        Box::pin(std::future::ready(Ok(self.list_namespaces())))
+    }
+
+    fn write(&self, namespace: &str, kind: StorageKind, name: &str, data: &[u8]) {
+        let path = self.root.join(entry_path(namespace, kind, name));
+        let result = std::fs::create_dir_all(path.parent().unwrap()).and_then(|_| std::fs::write(&path, data));
+        if let Err(e) = result {
+            warn!("Storage: failed to write {}: {}", path.display(), e);
+        }
+    }
+
+    fn remove(&self, namespace: &str, kind: StorageKind, name: &str) {
+        let path = self.root.join(entry_path(namespace, kind, name));
+        if let Err(e) = std::fs::remove_file(&path)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            warn!("Storage: failed to remove {}: {}", path.display(), e);
+        }
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "directory"
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod browser {
+    use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+    use base64::Engine;
+    use log::warn;
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use web_sys::{IdbDatabase, IdbKeyRange, IdbRequest, IdbTransactionMode};
+
+    use super::{
+        entry_path, parse_entry_path, StorageBackend, StorageFuture, StorageKind, StorageNamespace,
+        PREFS_NAMESPACE,
+    };
+
+    const DATABASE_NAME: &str = "dirplayer-storage";
+    const STORE_NAME: &str = "entries";
+    /// Prefix of localStorage keys when IndexedDB is unavailable.
+    const LOCAL_STORAGE_PREFIX: &str = "dirplayer/";
+    /// localStorage key prefix of prefs stored before this backend existed.
+    const LEGACY_PREF_PREFIX: &str = "dirplayer_pref_";
+
+    /// Not yet opened, opened, or unavailable (`Some(None)`).
+    type DatabaseCache = Rc<RefCell<Option<Option<IdbDatabase>>>>;
+
+    /// Stores entries in IndexedDB, keyed by their archive path. Falls back
+    /// to localStorage (base64 encoded) where IndexedDB can't be opened,
+    /// e.g. in some private browsing modes.
+    #[derive(Default)]
+    pub struct BrowserStorageBackend {
+        database: DatabaseCache,
+    }
+
+    /// Wait for an IndexedDB request to succeed or fail.
+    async fn request_result(request: &IdbRequest) -> Result<JsValue, JsValue> {
+        let promise = js_sys::Promise::new(&mut |resolve, reject| {
+            request.set_onsuccess(Some(&resolve));
+            request.set_onerror(Some(&reject));
+        });
+        wasm_bindgen_futures::JsFuture::from(promise).await?;
+        request.result()
+    }
+
+    async fn open_indexed_db() -> Result<IdbDatabase, JsValue> {
+        let factory = web_sys::window()
+            .ok_or_else(|| JsValue::from_str("no window"))?
+            .indexed_db()?
+            .ok_or_else(|| JsValue::from_str("IndexedDB is unavailable"))?;
+        let request = factory.open_with_u32(DATABASE_NAME, 1)?;
+        let upgrade_request = request.clone();
+        let on_upgrade = Closure::<dyn FnMut()>::new(move || {
+            if let Ok(db) = upgrade_request.result() {
+                let _ = db.unchecked_into::<IdbDatabase>().create_object_store(STORE_NAME);
+            }
+        });
+        request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+        let db = request_result(&request).await?;
+        Ok(db.unchecked_into())
+    }
+
+    async fn database(cache: DatabaseCache) -> Option<IdbDatabase> {
+        if let Some(db) = cache.borrow().clone() {
+            return db;
+        }
+        let db = match open_indexed_db().await {
+            Ok(db) => Some(db),
+            Err(e) => {
+                warn!("Storage: IndexedDB unavailable, using localStorage: {:?}", e);
+                None
+            }
+        };
+        *cache.borrow_mut() = Some(db.clone());
+        db
+    }
+
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+    }
+
+    fn local_storage_entries() -> Vec<(String, String)> {
+        let Some(storage) = local_storage() else {
+            return vec![];
+        };
+        let count = storage.length().unwrap_or(0);
+        (0..count)
+            .filter_map(|index| storage.key(index).ok().flatten())
+            .filter_map(|key| storage.get_item(&key).ok().flatten().map(|value| (key, value)))
+            .collect()
+    }
+
+    /// Entries whose path starts with `prefix`, from IndexedDB or localStorage.
+    async fn read_entries(cache: DatabaseCache, prefix: String) -> Result<Vec<(String, Vec<u8>)>, String> {
+        match database(cache).await {
+            Some(db) => read_indexed_db(&db, &prefix).await.map_err(|e| format!("{:?}", e)),
+            None => Ok(local_storage_entries()
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    let path = key.strip_prefix(LOCAL_STORAGE_PREFIX)?;
+                    let data = base64::engine::general_purpose::STANDARD.decode(value).ok()?;
+                    path.starts_with(&prefix).then(|| (path.to_string(), data))
+                })
+                .collect()),
+        }
+    }
+
+    async fn read_indexed_db(db: &IdbDatabase, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, JsValue> {
+        let store = db.transaction_with_str(STORE_NAME)?.object_store(STORE_NAME)?;
+        let range = IdbKeyRange::bound(&prefix.into(), &format!("{}\u{ffff}", prefix).into())?;
+        // Issue both requests before awaiting so the transaction stays active.
+        let keys_request = store.get_all_keys_with_key(&range)?;
+        let values_request = store.get_all_with_key(&range)?;
+        let keys: js_sys::Array = request_result(&keys_request).await?.unchecked_into();
+        let values: js_sys::Array = request_result(&values_request).await?.unchecked_into();
+        Ok(keys
+            .iter()
+            .zip(values.iter())
+            .filter_map(|(key, value)| Some((key.as_string()?, js_sys::Uint8Array::new(&value).to_vec())))
+            .collect())
+    }
+
+    fn write_indexed_db(db: &IdbDatabase, path: &str, data: Option<&[u8]>) -> Result<(), JsValue> {
+        let store = db
+            .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readwrite)?
+            .object_store(STORE_NAME)?;
+        match data {
+            Some(data) => store.put_with_key(&js_sys::Uint8Array::from(data).into(), &path.into())?,
+            None => store.delete(&path.into())?,
+        };
+        Ok(())
+    }
+
+    fn write_local_storage(path: &str, data: Option<&[u8]>) {
+        let Some(storage) = local_storage() else {
+            return;
+        };
+        let key = format!("{}{}", LOCAL_STORAGE_PREFIX, path);
+        let result = match data {
+            Some(data) => storage.set_item(&key, &base64::engine::general_purpose::STANDARD.encode(data)),
+            None => storage.remove_item(&key),
+        };
+        if let Err(e) = result {
+            warn!("Storage: failed to write {} to localStorage: {:?}", path, e);
+        }
+    }
+
+    /// Copy prefs `setPref` kept directly in localStorage into the store.
+    /// The old keys are left in place, and only fill in prefs the store
+    /// doesn't have, so a failed write is retried on the next load.
+    fn migrate_legacy_prefs(cache: &DatabaseCache, data: &mut StorageNamespace) {
+        for (key, value) in local_storage_entries() {
+            let Some(name) = key.strip_prefix(LEGACY_PREF_PREFIX) else {
+                continue;
+            };
+            if data.prefs.contains_key(name) {
+                continue;
+            }
+            let path = entry_path(PREFS_NAMESPACE, StorageKind::Pref, name);
+            store_entry(cache.clone(), path, Some(value.clone().into_bytes()));
+            data.prefs.insert(name.to_string(), value);
+        }
+    }
+
+    /// Write (or with `None`, delete) an entry in the background.
+    fn store_entry(cache: DatabaseCache, path: String, data: Option<Vec<u8>>) {
+        wasm_bindgen_futures::spawn_local(async move {
+            match database(cache).await {
+                Some(db) => {
+                    if let Err(e) = write_indexed_db(&db, &path, data.as_deref()) {
+                        warn!("Storage: failed to write {}: {:?}", path, e);
+                    }
+                }
+                None => write_local_storage(&path, data.as_deref()),
+            }
+        });
+    }
+
+    impl StorageBackend for BrowserStorageBackend {
+        fn load(&self, namespace: &str) -> StorageFuture<StorageNamespace> {
+            let cache = self.database.clone();
+            let namespace = namespace.to_string();
+            let prefix = format!("{}/", namespace);
+            Box::pin(async move {
+                let mut data = StorageNamespace::default();
+                for (path, bytes) in read_entries(cache.clone(), prefix).await? {
+                    if let Some((_, kind, name)) = parse_entry_path(&path) {
+                        data.insert(kind, name, bytes);
+                    }
+                }
+                if namespace == PREFS_NAMESPACE {
+                    migrate_legacy_prefs(&cache, &mut data);
+                }
+                Ok(data)
+            })
+        }
+
+        fn namespaces(&self) -> StorageFuture<Vec<String>> {
+            let cache = self.database.clone();
+            Box::pin(async move {
+                let namespaces: BTreeMap<String, ()> = read_entries(cache, String::new())
+                    .await?
+                    .into_iter()
+                    .filter_map(|(path, _)| parse_entry_path(&path).map(|(namespace, _, _)| (namespace, ())))
+                    .collect();
+                Ok(namespaces.into_keys().collect())
+            })
+        }
+
+        fn write(&self, namespace: &str, kind: StorageKind, name: &str, data: &[u8]) {
+            store_entry(self.database.clone(), entry_path(namespace, kind, name), Some(data.to_vec()));
+        }
+
+        fn remove(&self, namespace: &str, kind: StorageKind, name: &str) {
+            store_entry(self.database.clone(), entry_path(namespace, kind, name), None);
+        }
+
+        fn backend_name(&self) -> &'static str {
+            "browser"
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub use browser::BrowserStorageBackend;
+
+pub fn default_storage_backend() -> Rc<dyn StorageBackend> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        Rc::new(BrowserStorageBackend::default())
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Rc::new(MemoryStorageBackend::default())
+    }
+}
+
+/// Build an uncompressed ZIP archive of `entries` (path, data).
+fn write_zip(entries: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut zip = Vec::new();
+    let mut central_dir = Vec::new();
+    for (path, data) in entries {
+        let mut crc = flate2::Crc::new();
+        crc.update(data);
+        let offset = zip.len() as u32;
+        // Fields shared by the local header and the central directory entry:
+        // version needed, flags, method (stored), time, date, crc, sizes,
+        // name length, extra length.
+        let mut fields = Vec::new();
+        fields.extend_from_slice(&20u16.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        fields.extend_from_slice(&0x21u16.to_le_bytes());
+        fields.extend_from_slice(&crc.sum().to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(path.len() as u16).to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+
+        zip.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        zip.extend_from_slice(&fields);
+        zip.extend_from_slice(path.as_bytes());
+        zip.extend_from_slice(data);
+
+        central_dir.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        central_dir.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        central_dir.extend_from_slice(&fields);
+        central_dir.extend_from_slice(&[0; 10]); // comment length, disk, attributes
+        central_dir.extend_from_slice(&offset.to_le_bytes());
+        central_dir.extend_from_slice(path.as_bytes());
+    }
+    let central_dir_offset = zip.len() as u32;
+    zip.extend_from_slice(&central_dir);
+    zip.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    zip.extend_from_slice(&[0; 4]); // disk numbers
+    zip.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&(central_dir.len() as u32).to_le_bytes());
+    zip.extend_from_slice(&central_dir_offset.to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes()); // comment length
+    zip
+}
+
+/// Read the entries of a ZIP archive, stored or deflated.
+fn read_zip(zip: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let u16_at = |offset: usize| -> Result<usize, String> {
+        zip.get(offset..offset + 2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+            .ok_or_else(|| "Truncated archive".to_string())
+    };
+    let u32_at = |offset: usize| -> Result<usize, String> {
+        zip.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+            .ok_or_else(|| "Truncated archive".to_string())
+    };
+
+    let end = (0..zip.len().saturating_sub(21))
+        .rev()
+        .find(|&offset| zip[offset..].starts_with(&0x06054b50u32.to_le_bytes()))
+        .ok_or_else(|| "Not a ZIP archive".to_string())?;
+    let count = u16_at(end + 10)?;
+    let mut offset = u32_at(end + 16)?;
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if u32_at(offset)? != 0x02014b50 {
+            return Err("Corrupt ZIP central directory".to_string());
+        }
+        let method = u16_at(offset + 10)?;
+        let compressed_size = u32_at(offset + 20)?;
+        let name_len = u16_at(offset + 28)?;
+        let extra_len = u16_at(offset + 30)?;
+        let comment_len = u16_at(offset + 32)?;
+        let local_offset = u32_at(offset + 42)?;
+        let name = zip
+            .get(offset + 46..offset + 46 + name_len)
+            .ok_or_else(|| "Truncated archive".to_string())?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        offset += 46 + name_len + extra_len + comment_len;
+
+        let data_start = local_offset + 30 + u16_at(local_offset + 26)? + u16_at(local_offset + 28)?;
+        let raw = zip
+            .get(data_start..data_start + compressed_size)
+            .ok_or_else(|| format!("Truncated archive entry {}", name))?;
+        let data = match method {
+            0 => raw.to_vec(),
+            8 => {
+                let mut data = Vec::new();
+                flate2::read::DeflateDecoder::new(raw)
+                    .read_to_end(&mut data)
+                    .map_err(|e| format!("Failed to inflate {}: {}", name, e))?;
+                data
+            }
+            _ => return Err(format!("Unsupported compression method {} for {}", method, name)),
+        };
+        entries.push((name, data));
+    }
+    Ok(entries)
+}
+
+/// The storage of the running movie.
+pub struct PlayerStorage {
+    backend: Rc<dyn StorageBackend>,
+    /// Namespace of the current movie; empty until a movie is loaded, in
+    /// which case nothing is persisted.
+    pub namespace: String,
+    data: StorageNamespace,
+    prefs: BTreeMap<String, String>,
+}
+
+impl Default for PlayerStorage {
+    fn default() -> Self {
+        PlayerStorage::new(default_storage_backend())
+    }
+}
+
+impl PlayerStorage {
+    pub fn new(backend: Rc<dyn StorageBackend>) -> PlayerStorage {
+        PlayerStorage {
+            backend,
+            namespace: String::new(),
+            data: StorageNamespace::default(),
+            prefs: BTreeMap::new(),
+        }
+    }
+
+    pub fn set_backend(&mut self, backend: Rc<dyn StorageBackend>) {
+        self.backend = backend;
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.backend_name()
+    }
+
+    /// Switch to `namespace`, loading its entries and the shared prefs
+    /// from the backend.
+    pub async fn open(&mut self, namespace: &str) {
+        self.namespace = namespace.to_string();
+        self.data = self.load_or_default(namespace).await;
+        self.prefs = self.load_or_default(PREFS_NAMESPACE).await.prefs;
+    }
+
+    async fn load_or_default(&self, namespace: &str) -> StorageNamespace {
+        match self.backend.load(namespace).await {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Storage: failed to load {}: {}", namespace, e);
+                StorageNamespace::default()
+            }
+        }
+    }
+
+    pub fn files(&self) -> &BTreeMap<String, Vec<u8>> {
+        &self.data.files
+    }
+
+    pub fn pref(&self, name: &str) -> Option<&str> {
+        self.prefs.get(name).map(|value| value.as_str())
+    }
+
+    pub fn set_pref(&mut self, name: &str, value: &str) {
+        if !self.namespace.is_empty() {
+            self.backend.write(PREFS_NAMESPACE, StorageKind::Pref, name, value.as_bytes());
+        }
+        self.prefs.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn write_file(&mut self, name: &str, data: &[u8]) {
+        if !self.namespace.is_empty() {
+            self.backend.write(&self.namespace, StorageKind::File, name, data);
+        }
+        self.data.files.insert(name.to_string(), data.to_vec());
+    }
+
+    pub fn remove_file(&mut self, name: &str) {
+        if self.data.files.remove(name).is_some() && !self.namespace.is_empty() {
+            self.backend.remove(&self.namespace, StorageKind::File, name);
+        }
+    }
+
+    /// ZIP archive of every namespace in the store.
+    pub fn export_archive(&self) -> StorageFuture<Vec<u8>> {
+        let backend = self.backend.clone();
+        Box::pin(async move {
+            let mut entries = Vec::new();
+            for namespace in backend.namespaces().await? {
+                let data = backend.load(&namespace).await?;
+                for (kind, name, bytes) in data.entries() {
+                    entries.push((entry_path(&namespace, kind, name), bytes.to_vec()));
+                }
+            }
+            Ok(write_zip(&entries))
+        })
+    }
+
+    /// Replace the namespaces contained in `archive` with its contents,
+    /// keeping the others.
+    pub async fn import_archive(&mut self, archive: &[u8]) -> Result<(), String> {
+        let mut imported: BTreeMap<String, StorageNamespace> = BTreeMap::new();
+        for (path, data) in read_zip(archive)? {
+            if path.ends_with('/') {
+                continue;
+            }
+            match parse_entry_path(&path) {
+                Some((namespace, kind, name)) => imported.entry(namespace).or_default().insert(kind, name, data),
+                None => warn!("Storage: skipping unknown archive entry {}", path),
+            }
+        }
+        for (namespace, data) in imported {
+            let existing = self.backend.load(&namespace).await?;
+            for (kind, name, _) in existing.entries() {
+                self.backend.remove(&namespace, kind, name);
+            }
+            for (kind, name, bytes) in data.entries() {
+                self.backend.write(&namespace, kind, name, bytes);
+            }
+            // Browser writes finish in the background, so keep the imported
+            // data rather than reading it back.
+            if namespace == PREFS_NAMESPACE {
+                self.prefs = data.prefs;
+            } else if namespace == self.namespace {
+                self.data = data;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Open the storage namespace of the loaded movie and make its files
+/// visible to FileIO and Buddy API in place of the previous movie's.
+pub async fn open_movie_storage(player: &mut DirPlayer) {
+    let base_path = player.movie.base_path.trim_end_matches(['/', '\\']);
+    let movie_path = if base_path.is_empty() {
+        player.movie.file_name.clone()
+    } else {
+        format!("{}/{}", base_path, player.movie.file_name)
+    };
+    let old_files: Vec<String> = player.storage.files().keys().cloned().collect();
+    player.storage.open(&storage_namespace(&movie_path)).await;
+    replace_fileio_files(player, old_files);
+}
+
+/// Import a storage archive, updating the files the running movie sees.
+pub async fn import_movie_storage(player: &mut DirPlayer, archive: &[u8]) -> Result<(), String> {
+    let old_files: Vec<String> = player.storage.files().keys().cloned().collect();
+    player.storage.import_archive(archive).await?;
+    replace_fileio_files(player, old_files);
+    Ok(())
+}
+
+/// Remove `old_files` from the FileIO filesystem and add the files of the
+/// open namespace.
+fn replace_fileio_files(player: &DirPlayer, old_files: Vec<String>) {
+    borrow_fileio_manager_mut(|fileio| {
+        for name in old_files {
+            fileio.virtual_fs.remove(&name);
+        }
+        for (name, data) in player.storage.files() {
+            fileio.virtual_fs.insert(name.clone(), data.clone());
+        }
+    });
+}
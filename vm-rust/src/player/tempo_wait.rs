@@ -0,0 +1,171 @@
+//! Tempo channel wait modes.
+//!
+//! Besides a frame rate, the tempo channel can hold the playhead on a frame
+//! until a condition is met: a fixed delay, a mouse click or key press, the
+//! end of a sound, or a sound reaching a cue point. The wait is armed when
+//! the playhead enters the frame and checked by the frame loop before
+//! `exitFrame`; events and timeouts keep being dispatched meanwhile.
+
+use serde::Serialize;
+
+use crate::director::chunks::score::TempoChannelData;
+use crate::player::{cue_points, testing_shared::now_ms, DirPlayer};
+
+/// D6+ tempo codes (byte 6 of the tempo channel).
+pub const TEMPO_FPS: u8 = 246;
+pub const TEMPO_DELAY: u8 = 247;
+pub const TEMPO_WAIT_CLICK: u8 = 248;
+pub const TEMPO_WAIT_SOUND1: u8 = 254;
+pub const TEMPO_WAIT_SOUND2: u8 = 255;
+
+/// `tempo_cue_point` values for the special {Next} and {End} cue points.
+pub const CUE_POINT_NEXT: u16 = 0xFFFE;
+pub const CUE_POINT_END: u16 = 0xFFFF;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "index", rename_all = "camelCase")]
+pub enum CuePointTarget {
+    /// The next cue point after the wait started.
+    Next,
+    /// The end of the sound.
+    End,
+    /// A specific cue point (1-based, as in `cuePointNames`).
+    Index(u16),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TempoWaitMode {
+    Delay { seconds: u16 },
+    Click,
+    Sound { channel: u16 },
+    CuePoint { channel: u16, cue_point: CuePointTarget },
+}
+
+impl TempoWaitMode {
+    /// Decode the wait mode of a tempo channel entry. 254/255 wait on sound
+    /// channel 1/2; a non-zero `tempo_cue_point` turns them into a cue point
+    /// wait on that channel.
+    pub fn from_tempo_data(data: &TempoChannelData) -> Option<TempoWaitMode> {
+        match data.tempo {
+            TEMPO_DELAY => Some(TempoWaitMode::Delay { seconds: data.tempo_cue_point }),
+            TEMPO_WAIT_CLICK => Some(TempoWaitMode::Click),
+            TEMPO_WAIT_SOUND1 | TEMPO_WAIT_SOUND2 => {
+                let channel = if data.tempo == TEMPO_WAIT_SOUND1 { 1 } else { 2 };
+                match data.tempo_cue_point {
+                    0 => Some(TempoWaitMode::Sound { channel }),
+                    CUE_POINT_NEXT => Some(TempoWaitMode::CuePoint { channel, cue_point: CuePointTarget::Next }),
+                    CUE_POINT_END => Some(TempoWaitMode::CuePoint { channel, cue_point: CuePointTarget::End }),
+                    index => Some(TempoWaitMode::CuePoint { channel, cue_point: CuePointTarget::Index(index) }),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingTempoWait {
+    pub frame: u32,
+    pub mode: TempoWaitMode,
+    pub started_ms: f64,
+    /// Set by mouseDown/keyDown while a click wait is pending.
+    pub clicked: bool,
+    /// Sound position (ms) when the wait started, for {Next} cue points.
+    pub sound_start_ms: f64,
+}
+
+/// Arm the tempo wait of `frame`, replacing any wait left on the previous frame.
+pub fn arm_for_frame(player: &mut DirPlayer, frame: u32) {
+    player.tempo_wait = player
+        .movie
+        .score
+        .get_frame_tempo_wait(frame)
+        .map(|mode| {
+            let sound_start_ms = match &mode {
+                TempoWaitMode::CuePoint { channel, .. } => sound_position_ms(player, *channel),
+                _ => 0.0,
+            };
+            PendingTempoWait {
+                frame,
+                mode,
+                started_ms: now_ms(),
+                clicked: false,
+                sound_start_ms,
+            }
+        });
+}
+
+/// Called for mouseDown and keyDown.
+pub fn notify_click(player: &mut DirPlayer) {
+    if let Some(wait) = player.tempo_wait.as_mut() {
+        wait.clicked = true;
+    }
+}
+
+/// Returns true while the playhead must stay on the current frame.
+/// Clears the pending wait once its condition is met.
+pub fn is_waiting(player: &mut DirPlayer) -> bool {
+    let Some(wait) = player.tempo_wait.as_ref() else {
+        return false;
+    };
+    if wait.frame != player.movie.current_frame {
+        player.tempo_wait = None;
+        return false;
+    }
+    let done = match &wait.mode {
+        TempoWaitMode::Delay { seconds } => now_ms() >= wait.started_ms + *seconds as f64 * 1000.0,
+        TempoWaitMode::Click => wait.clicked,
+        TempoWaitMode::Sound { channel } => !is_sound_busy(player, *channel),
+        TempoWaitMode::CuePoint { channel, cue_point } => {
+            is_past_cue_point(player, *channel, cue_point, wait.sound_start_ms)
+        }
+    };
+    if done {
+        player.tempo_wait = None;
+    }
+    !done
+}
+
+fn is_sound_busy(player: &DirPlayer, channel: u16) -> bool {
+    player
+        .sound_manager
+        .get_channel((channel as usize).saturating_sub(1))
+        .is_some_and(|ch| ch.borrow().is_busy())
+}
+
+fn sound_position_ms(player: &DirPlayer, channel: u16) -> f64 {
+    player
+        .sound_manager
+        .get_channel((channel as usize).saturating_sub(1))
+        .map_or(0.0, |ch| ch.borrow().current_time_ms())
+}
+
+/// {End} waits for the sound to finish, {Next} for the first cue point past
+/// the position the wait started at, and a numbered cue point for that cue
+/// point. A sound that stops early releases the wait.
+fn is_past_cue_point(player: &DirPlayer, channel: u16, cue_point: &CuePointTarget, start_ms: f64) -> bool {
+    let Some(channel_rc) = player
+        .sound_manager
+        .get_channel((channel as usize).saturating_sub(1))
+    else {
+        return true;
+    };
+    let channel = channel_rc.borrow();
+    if !channel.is_busy() {
+        return true;
+    }
+    match cue_point {
+        CuePointTarget::End => false,
+        CuePointTarget::Index(index) => channel.is_past_cue_point(*index as i32),
+        CuePointTarget::Next => {
+            let position = channel.current_time_ms();
+            channel.sound_member.as_ref().is_some_and(|member| {
+                cue_points::crossed(&member.cue_points, start_ms, position)
+                    .next()
+                    .is_some()
+            })
+        }
+    }
+}
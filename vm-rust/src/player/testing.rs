@@ -1,4 +1,5 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::sync::Mutex;
 
 use async_std::channel;
@@ -6,10 +7,15 @@ use async_std::channel;
 use crate::director::file::read_director_file_bytes;
 pub use crate::director::static_datum::StaticDatum;
 use crate::player::{
+    audio::{encode_wav, mixer, BufferAudioOutput, DEFAULT_SAMPLE_RATE},
     bitmap::bitmap::{get_system_default_palette, Bitmap, PaletteRef},
     events::run_event_loop,
     fire_pending_timeouts,
-    reserve_player_mut, reserve_player_ref, run_single_frame,
+    net_backend::{NativeNetBackend, NetManifest},
+    net_manager::NetRequestLogEntry,
+    replay,
+    reserve_player_mut, reserve_player_ref, run_pending_goto_net_movie, run_single_frame,
+    storage::{import_movie_storage, DirectoryStorageBackend},
     DirPlayer, PlayerVMExecutionItem, PLAYER_OPT,
 };
 pub use crate::player::testing_shared::{TestHarness, SnapshotOutput};
@@ -35,12 +41,19 @@ impl TestPlayer {
         unsafe {
             crate::player::PLAYER_TX = Some(tx.clone());
             crate::player::PLAYER_EVENT_TX = Some(event_tx.clone());
+            crate::player::xtra::fileio::FILEIO_XTRA_MANAGER_OPT =
+                Some(crate::player::xtra::fileio::FileIoXtraManager::new());
             crate::player::xtra::multiuser::MULTIUSER_XTRA_MANAGER_OPT =
                 Some(crate::player::xtra::multiuser::MultiuserXtraManager::new());
             crate::player::xtra::xmlparser::XMLPARSER_XTRA_MANAGER_OPT =
                 Some(crate::player::xtra::xmlparser::XmlParserXtraManager::new());
+            crate::player::xtra::pregex::PREGEX_XTRA_MANAGER_OPT =
+                Some(crate::player::xtra::pregex::PRegExXtraManager::new());
+            crate::player::xtra::buddyapi::BUDDYAPI_XTRA_MANAGER_OPT =
+                Some(crate::player::xtra::buddyapi::BuddyApiXtraManager::new());
             PLAYER_OPT = Some(DirPlayer::new(tx.clone()));
         }
+        replay::reset();
 
         async_std::task::spawn_local(async move {
             run_event_loop(event_rx).await;
@@ -48,6 +61,99 @@ impl TestPlayer {
 
         TestPlayer { _tx: tx, _lock: lock }
     }
+
+    /// Keep the mixed audio in memory so `snapshot_audio` can return it.
+    pub fn capture_audio(&mut self) {
+        reserve_player_mut(|player| {
+            player
+                .sound_manager
+                .mixer
+                .set_output(Box::new(BufferAudioOutput::new(DEFAULT_SAMPLE_RATE)));
+        });
+    }
+
+    /// Answer network requests from a manifest of URL -> response mappings
+    /// (see [`NetManifest`]) instead of only from disk.
+    pub fn use_net_manifest(&mut self, manifest: NetManifest) {
+        reserve_player_mut(|player| {
+            player.net_manager.timeout_ms = manifest.timeout_ms;
+            player.net_manager.set_backend(Box::new(NativeNetBackend::new(manifest)));
+        });
+    }
+
+    /// Persist FileIO files and prefs as files under `dir` (see
+    /// [`DirectoryStorageBackend`]) instead of in memory.
+    pub fn use_storage_dir(&mut self, dir: impl Into<PathBuf>) {
+        reserve_player_mut(|player| {
+            player.storage.set_backend(Rc::new(DirectoryStorageBackend::new(dir)));
+        });
+    }
+
+    /// Seed the store from an archive made by `export_storage`.
+    pub async fn import_storage(&mut self, archive: &[u8]) -> Result<(), String> {
+        unsafe {
+            let player = PLAYER_OPT.as_mut().unwrap();
+            import_movie_storage(player, archive).await
+        }
+    }
+
+    pub async fn export_storage(&self) -> Result<Vec<u8>, String> {
+        let archive = reserve_player_ref(|player| player.storage.export_archive());
+        archive.await
+    }
+
+    /// Wait for a net task the way movie loading does, skipping the
+    /// network clock ahead as needed.
+    pub async fn await_net_task(&mut self, task_id: u32) {
+        unsafe {
+            let player = PLAYER_OPT.as_mut().unwrap();
+            player.net_manager.await_task(task_id).await;
+        }
+    }
+
+    /// The requests made so far, in order.
+    pub fn net_requests(&self) -> Vec<NetRequestLogEntry> {
+        reserve_player_ref(|player| player.net_manager.request_log.clone())
+    }
+
+    /// Record inputs from here on, reseeding `random()` with `rng_seed`
+    /// (see [`replay`]).
+    pub fn start_recording(&mut self, rng_seed: u64) {
+        reserve_player_mut(|player| replay::start_recording(player, rng_seed));
+    }
+
+    /// Stop recording and return the replay file.
+    pub fn stop_recording(&mut self) -> Vec<u8> {
+        reserve_player_mut(replay::stop_recording).expect("Not recording")
+    }
+
+    /// Drive the player from a file made by `stop_recording`. Start it at
+    /// the point the recording was started at, in a freshly loaded movie.
+    pub fn start_replay(&mut self, replay_file: &[u8]) -> Result<(), String> {
+        reserve_player_mut(|player| replay::start_replay(player, replay_file))
+    }
+
+    /// Step frames until the replay has applied all of its inputs and
+    /// frames, then hand the player back to live input.
+    pub async fn finish_replay(&mut self) {
+        while !replay::is_replay_finished() {
+            if !self.step_frame().await {
+                break;
+            }
+        }
+        reserve_player_mut(replay::stop_replay);
+    }
+
+    /// Take the audio mixed since the last call (or since `capture_audio`).
+    pub fn snapshot_audio(&mut self) -> AudioSnapshot {
+        reserve_player_mut(|player| {
+            let output = player.sound_manager.mixer.output_mut();
+            AudioSnapshot {
+                sample_rate: output.sample_rate(),
+                samples: output.take_samples(),
+            }
+        })
+    }
 }
 
 impl TestHarness for TestPlayer {
@@ -92,11 +198,17 @@ impl TestHarness for TestPlayer {
 
     async fn step_frame(&mut self) -> bool {
         fire_pending_timeouts().await;
+        run_pending_goto_net_movie().await;
         let (is_playing, _) = run_single_frame().await;
         let delay_ms = reserve_player_ref(|player| {
             let tempo = player.movie.get_effective_tempo();
             if tempo > 0 { 1000 / tempo } else { 33 }
         });
+        // Mix exactly one frame of audio so snapshots don't depend on timing.
+        reserve_player_mut(|player| {
+            mixer::render(player, delay_ms as f64);
+            player.net_manager.advance(delay_ms as f64);
+        });
         std::thread::sleep(std::time::Duration::from_millis(delay_ms as u64));
         is_playing
     }
@@ -106,7 +218,9 @@ impl TestHarness for TestPlayer {
             let w = player.movie.rect.width() as u16;
             let h = player.movie.rect.height() as u16;
             let mut bitmap = Bitmap::new(w, h, 32, 32, 0, PaletteRef::BuiltIn(get_system_default_palette()));
+            crate::player::palette_effects::update(player);
             render_stage_to_bitmap(player, &mut bitmap, None);
+            crate::player::transition::apply_to_frame(player, &mut bitmap.data, w as u32, h as u32);
             SnapshotOutput::Rgba {
                 width: w as u32,
                 height: h as u32,
@@ -268,3 +382,96 @@ impl StageSnapshot {
         Ok(())
     }
 }
+
+/// Mixed audio captured from a [`TestPlayer`], interleaved stereo.
+pub struct AudioSnapshot {
+    pub sample_rate: u32,
+    pub samples: Vec<f32>,
+}
+
+impl AudioSnapshot {
+    pub fn frame_count(&self) -> usize {
+        self.samples.len() / 2
+    }
+
+    pub fn peak(&self) -> f32 {
+        self.samples.iter().fold(0.0, |peak, s| peak.max(s.abs()))
+    }
+
+    pub fn rms(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let sum: f32 = self.samples.iter().map(|s| s * s).sum();
+        (sum / self.samples.len() as f32).sqrt()
+    }
+
+    pub fn to_wav(&self) -> Vec<u8> {
+        encode_wav(self.sample_rate, 2, &self.samples)
+    }
+
+    /// Compare against a reference WAV, like `StageSnapshot::assert_snapshot`.
+    ///
+    /// Files are stored as `snapshots/{suite}/native/{test}/{name}.wav`.
+    /// Samples may differ by at most `max_sample_diff` (in 16-bit steps).
+    pub fn assert_snapshot(&self, snapshot_path: &str, name: &str, max_sample_diff: u16) -> Result<(), String> {
+        let (suite, test) = snapshot_path.split_once('/')
+            .unwrap_or((snapshot_path, "default"));
+        let manifest_dir = env!("CARGO_MANIFEST_DIR");
+        let base = Path::new(manifest_dir).join("tests/snapshots");
+        let output_dir = base.join("output").join(suite).join("native").join(test);
+        let reference_dir = base.join("reference").join(suite).join("native").join(test);
+
+        std::fs::create_dir_all(&output_dir).unwrap();
+        std::fs::create_dir_all(&reference_dir).unwrap();
+
+        let file_name = format!("{}.wav", name);
+        let output_path = output_dir.join(&file_name);
+        let reference_path = reference_dir.join(&file_name);
+
+        let actual_wav = self.to_wav();
+        std::fs::write(&output_path, &actual_wav).unwrap();
+
+        if std::env::var("SNAPSHOT_UPDATE").unwrap_or_default() == "1" {
+            std::fs::write(&reference_path, &actual_wav).unwrap();
+            eprintln!("Updated reference: {}", reference_path.display());
+            return Ok(());
+        }
+
+        if !reference_path.exists() {
+            eprintln!(
+                "No reference for '{}'; actual saved to {}. \
+                 Run with SNAPSHOT_UPDATE=1 to create.",
+                name, output_path.display(),
+            );
+            return Ok(());
+        }
+
+        let reference_wav = std::fs::read(&reference_path).unwrap();
+        if reference_wav.len() != actual_wav.len() || reference_wav.get(..44) != actual_wav.get(..44) {
+            return Err(format!(
+                "Audio snapshot '{}' format or length differs: actual {} bytes vs reference {} bytes",
+                name, actual_wav.len(), reference_wav.len()
+            ));
+        }
+        let max_diff = actual_wav[44..]
+            .chunks_exact(2)
+            .zip(reference_wav[44..].chunks_exact(2))
+            .map(|(a, b)| {
+                let a = i16::from_le_bytes([a[0], a[1]]) as i32;
+                let b = i16::from_le_bytes([b[0], b[1]]) as i32;
+                (a - b).unsigned_abs()
+            })
+            .max()
+            .unwrap_or(0);
+        if max_diff > max_sample_diff as u32 {
+            return Err(format!(
+                "Audio snapshot '{}' differs from reference: max sample diff {} (threshold {})\n  \
+                 actual: {}\n  reference: {}",
+                name, max_diff, max_sample_diff,
+                output_path.display(), reference_path.display(),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -100,10 +100,16 @@ impl BrowserTestPlayer {
             crate::player::PLAYER_TX = Some(tx.clone());
             crate::player::PLAYER_EVENT_TX = Some(event_tx);
             PLAYER_OPT = Some(crate::player::DirPlayer::new(tx));
+            crate::player::xtra::fileio::FILEIO_XTRA_MANAGER_OPT =
+                Some(crate::player::xtra::fileio::FileIoXtraManager::new());
             crate::player::xtra::multiuser::MULTIUSER_XTRA_MANAGER_OPT =
                 Some(crate::player::xtra::multiuser::MultiuserXtraManager::new());
             crate::player::xtra::xmlparser::XMLPARSER_XTRA_MANAGER_OPT =
                 Some(crate::player::xtra::xmlparser::XmlParserXtraManager::new());
+            crate::player::xtra::pregex::PREGEX_XTRA_MANAGER_OPT =
+                Some(crate::player::xtra::pregex::PRegExXtraManager::new());
+            crate::player::xtra::buddyapi::BUDDYAPI_XTRA_MANAGER_OPT =
+                Some(crate::player::xtra::buddyapi::BuddyApiXtraManager::new());
             // Spawn fresh command and event loops for the new channels
             async_std::task::spawn_local(async move {
                 crate::player::commands::run_command_loop(rx).await;
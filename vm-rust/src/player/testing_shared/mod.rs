@@ -13,23 +13,33 @@ pub use snapshots::{SnapshotOutput, SnapshotContext, emit_snapshot};
 
 use crate::director::static_datum::StaticDatum;
 use crate::player::{
-    commands::{run_player_command, PlayerVMCommand},
+    commands::{apply_input_state, run_player_command, PlayerVMCommand},
     datum_ref::DatumRef,
     eval::eval_lingo_command,
-    reserve_player_mut, reserve_player_ref, run_movie_init_sequence,
+    reserve_player_mut, reserve_player_ref, run_movie_init_sequence, save_state,
     ScriptError,
 };
 
 const DEFAULT_TIMEOUT_SECS: f64 = 30.0;
 
 /// Get current time in milliseconds (works on both native and wasm).
+/// During an input recording or replay this is the replay clock.
 pub fn now_ms() -> f64 {
+    if let Some(ms) = crate::player::replay::virtual_now_ms() {
+        return ms;
+    }
     #[cfg(target_arch = "wasm32")]
     { js_sys::Date::now() }
     #[cfg(not(target_arch = "wasm32"))]
     { std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs_f64() * 1000.0 }
 }
 
+/// Deliver an input the way the host does: its state first, then the command.
+async fn send_input(command: PlayerVMCommand) {
+    reserve_player_mut(|player| apply_input_state(player, &command));
+    let _ = run_player_command(command).await;
+}
+
 /// Platform-specific operations implemented by each test harness.
 pub trait TestHarness {
     /// Resolve a relative asset path (e.g. "dcr_woodpecker/habbo.dcr") to
@@ -150,57 +160,42 @@ pub trait TestHarness {
         StepUntilBuilder::new(self, condition)
     }
 
+    /// Snapshot the VM state (see `player::save_state`).
+    fn save_state(&self) -> Result<Vec<u8>, ScriptError> {
+        reserve_player_mut(save_state::save_state)
+    }
+
+    /// Return to a snapshot taken with `save_state`.
+    fn restore_state(&mut self, state: &[u8]) -> Result<(), ScriptError> {
+        reserve_player_mut(|player| save_state::restore_state(player, state))
+    }
+
     // --- Input simulation ---
 
     async fn click(&mut self, x: i32, y: i32) {
-        reserve_player_mut(|player| {
-            player.mouse_loc = (x, y);
-            player.movie.mouse_down = true;
-        });
-        let _ = run_player_command(PlayerVMCommand::MouseDown((x, y))).await;
+        send_input(PlayerVMCommand::MouseDown((x, y))).await;
         self.step_frame().await;
-        reserve_player_mut(|player| {
-            player.mouse_loc = (x, y);
-            player.movie.mouse_down = false;
-        });
-        let _ = run_player_command(PlayerVMCommand::MouseUp((x, y))).await;
+        send_input(PlayerVMCommand::MouseUp((x, y))).await;
     }
 
     async fn mouse_down(&mut self, x: i32, y: i32) {
-        reserve_player_mut(|player| {
-            player.mouse_loc = (x, y);
-            player.movie.mouse_down = true;
-        });
-        let _ = run_player_command(PlayerVMCommand::MouseDown((x, y))).await;
+        send_input(PlayerVMCommand::MouseDown((x, y))).await;
     }
 
     async fn mouse_up(&mut self, x: i32, y: i32) {
-        reserve_player_mut(|player| {
-            player.mouse_loc = (x, y);
-            player.movie.mouse_down = false;
-        });
-        let _ = run_player_command(PlayerVMCommand::MouseUp((x, y))).await;
+        send_input(PlayerVMCommand::MouseUp((x, y))).await;
     }
 
     async fn mouse_move(&mut self, x: i32, y: i32) {
-        reserve_player_mut(|player| {
-            player.mouse_loc = (x, y);
-        });
-        let _ = run_player_command(PlayerVMCommand::MouseMove((x, y))).await;
+        send_input(PlayerVMCommand::MouseMove((x, y))).await;
     }
 
     async fn key_down(&mut self, key: &str, code: u16) {
-        reserve_player_mut(|player| {
-            player.keyboard_manager.key_down(key.to_string(), code);
-        });
-        let _ = run_player_command(PlayerVMCommand::KeyDown(key.to_string(), code)).await;
+        send_input(PlayerVMCommand::KeyDown(key.to_string(), code)).await;
     }
 
     async fn key_up(&mut self, key: &str, code: u16) {
-        reserve_player_mut(|player| {
-            player.keyboard_manager.key_up(key, code);
-        });
-        let _ = run_player_command(PlayerVMCommand::KeyUp(key.to_string(), code)).await;
+        send_input(PlayerVMCommand::KeyUp(key.to_string(), code)).await;
     }
 
     async fn key_press(&mut self, key: &str, code: u16) {
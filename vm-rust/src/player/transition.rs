@@ -0,0 +1,464 @@
+//! Score transitions (transition channel and `puppetTransition`).
+//!
+//! When the playhead enters a frame that has a transition member in its
+//! transition channel, or a puppet transition is pending, the stage as it
+//! was shown on the previous frame is captured. Renderers then hand every
+//! new frame to [`apply_to_frame`], which blends the captured image with the
+//! new one according to the transition type and the elapsed time, or ask
+//! [`source_map`] where each pixel comes from and compose on the GPU. The
+//! frame loop holds the playhead until the transition has finished,
+//! matching Director, where transitions block playback.
+
+use crate::director::enums::{TransitionInfo, TransitionType};
+use crate::player::{
+    bitmap::bitmap::{get_system_default_palette, Bitmap, PaletteRef},
+    geometry::IntRect,
+    testing_shared::now_ms,
+    DirPlayer,
+};
+use crate::rendering::render_stage_to_bitmap;
+
+/// Band size used by blinds, checkerboard and strip transitions.
+const BAND_SIZE: i32 = 16;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransitionSpec {
+    pub transition_type: TransitionType,
+    pub duration_ms: u32,
+    pub chunk_size: u8,
+    pub change_area: bool,
+}
+
+impl From<&TransitionInfo> for TransitionSpec {
+    fn from(info: &TransitionInfo) -> Self {
+        TransitionSpec {
+            transition_type: info.transition_type,
+            duration_ms: info.duration as u32,
+            chunk_size: info.chunk_size.max(1),
+            change_area: info.change_area,
+        }
+    }
+}
+
+pub struct ActiveTransition {
+    pub id: u32,
+    pub spec: TransitionSpec,
+    pub start_ms: f64,
+    /// Stage pixels (RGBA) shown before the transition started.
+    pub from: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// Region being transitioned. `None` until the first frame has been
+    /// composed (the changing area is only known once both images exist).
+    area: Option<Option<IntRect>>,
+}
+
+impl ActiveTransition {
+    pub fn progress(&self, now: f64) -> f64 {
+        if self.spec.duration_ms == 0 {
+            return 1.0;
+        }
+        ((now - self.start_ms) / self.spec.duration_ms as f64).clamp(0.0, 1.0)
+    }
+
+    pub fn remaining_ms(&self, now: f64) -> f64 {
+        (self.start_ms + self.spec.duration_ms as f64 - now).max(0.0)
+    }
+}
+
+#[derive(Default)]
+pub struct TransitionManager {
+    /// Set by `puppetTransition`; consumed by the next frame change.
+    pub puppet: Option<TransitionSpec>,
+    pub active: Option<ActiveTransition>,
+    next_id: u32,
+}
+
+impl TransitionManager {
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    pub fn remaining_ms(&self) -> f64 {
+        self.active.as_ref().map_or(0.0, |t| t.remaining_ms(now_ms()))
+    }
+
+    pub fn clear(&mut self) {
+        self.puppet = None;
+        self.active = None;
+    }
+}
+
+/// Resolve the transition that should play when entering `frame`.
+/// A pending puppet transition wins over the score and is consumed.
+fn transition_for_frame(player: &mut DirPlayer, frame: u32) -> Option<TransitionSpec> {
+    if let Some(spec) = player.transitions.puppet.take() {
+        return Some(spec);
+    }
+    let member_ref = player.movie.score.get_frame_transition(frame)?;
+    player
+        .movie
+        .cast_manager
+        .find_member_by_ref(&member_ref)
+        .and_then(|member| member.member_type.as_transition())
+        .map(|member| TransitionSpec::from(&member.info))
+}
+
+/// Called when the playhead is about to move to `frame`, while
+/// `current_frame` is still the outgoing frame, so the captured stage is the
+/// one being replaced.
+pub fn start_frame_transition(player: &mut DirPlayer, frame: u32) {
+    let Some(spec) = transition_for_frame(player, frame) else {
+        return;
+    };
+    if spec.transition_type == TransitionType::None || spec.duration_ms == 0 {
+        return;
+    }
+
+    let width = player.movie.rect.width().max(0) as u32;
+    let height = player.movie.rect.height().max(0) as u32;
+    if width == 0 || height == 0 {
+        return;
+    }
+    let from = render_stage(player, width, height);
+
+    let manager = &mut player.transitions;
+    manager.next_id += 1;
+    manager.active = Some(ActiveTransition {
+        id: manager.next_id,
+        spec,
+        start_ms: now_ms(),
+        from,
+        width,
+        height,
+        area: None,
+    });
+}
+
+/// Render the stage with the software renderer (RGBA, top-down rows).
+fn render_stage(player: &mut DirPlayer, width: u32, height: u32) -> Vec<u8> {
+    let mut bitmap = Bitmap::new(
+        width as u16,
+        height as u16,
+        32,
+        32,
+        0,
+        PaletteRef::BuiltIn(get_system_default_palette()),
+    );
+    render_stage_to_bitmap(player, &mut bitmap, None);
+    bitmap.data
+}
+
+/// Type, transitioned area and progress of the active transition for a
+/// `width`x`height` frame, ending it once it has finished or the stage
+/// size changed. `new_frame` is only called, once, for transitions limited
+/// to the changing area. Returns `None` while nothing has changed.
+fn current_step(
+    player: &mut DirPlayer,
+    width: u32,
+    height: u32,
+    new_frame: impl FnOnce(&mut DirPlayer) -> Vec<u8>,
+) -> Option<(TransitionSpec, IntRect, f64)> {
+    let active = player.transitions.active.as_ref()?;
+    if active.width != width
+        || active.height != height
+        || active.from.len() != (width * height * 4) as usize
+    {
+        player.transitions.active = None;
+        return None;
+    }
+
+    let progress = active.progress(now_ms());
+    if progress >= 1.0 {
+        player.transitions.active = None;
+        return None;
+    }
+
+    if active.area.is_none() {
+        let area = if active.spec.change_area {
+            let to = new_frame(player);
+            let from = &player.transitions.active.as_ref()?.from;
+            changed_area(from, &to, width, height)
+        } else {
+            Some(IntRect::from(0, 0, width as i32, height as i32))
+        };
+        player.transitions.active.as_mut()?.area = Some(area);
+    }
+    let active = player.transitions.active.as_ref()?;
+    let area = active.area.clone().flatten()?;
+    Some((active.spec.clone(), area, progress))
+}
+
+/// Blend the active transition into `data`, the freshly rendered stage
+/// (RGBA, top-down rows). Does nothing when no transition is playing.
+pub fn apply_to_frame(player: &mut DirPlayer, data: &mut [u8], width: u32, height: u32) {
+    let Some((spec, area, progress)) = current_step(player, width, height, |_| data.to_vec()) else {
+        return;
+    };
+    let Some(active) = player.transitions.active.as_ref() else {
+        return;
+    };
+    let to = data.to_vec();
+    compose(&spec, &active.from, &to, data, width, &area, progress);
+    player.stage_dirty = true;
+}
+
+/// Per-pixel sources of the active transition, for renderers that compose
+/// it on the GPU from textures of both frames. Each RGBA texel holds the
+/// source x in R and G and the source y in B and A (big endian), with the
+/// top bit of R set when the pixel comes from the new frame rather than
+/// from [`ActiveTransition::from`]. Pixels outside the transitioned area
+/// map to themselves in the new frame. Returns `None` when no transition
+/// is playing or nothing has changed.
+pub fn source_map(player: &mut DirPlayer, width: u32, height: u32) -> Option<Vec<u8>> {
+    let (spec, area, progress) =
+        current_step(player, width, height, |player| render_stage(player, width, height))?;
+
+    let mut map = vec![0u8; (width * height * 4) as usize];
+    for (i, texel) in map.chunks_exact_mut(4).enumerate() {
+        let (x, y) = ((i as u32 % width) as i32, (i as u32 / width) as i32);
+        encode_source(texel, Source::To(x, y));
+    }
+    for_each_source(&spec, &area, progress, |x, y, source| {
+        let source = match source {
+            Source::From(sx, sy) => Source::From(area.left + sx, area.top + sy),
+            Source::To(sx, sy) => Source::To(area.left + sx, area.top + sy),
+        };
+        let i = (((area.top + y) * width as i32 + area.left + x) * 4) as usize;
+        encode_source(&mut map[i..i + 4], source);
+    });
+    player.stage_dirty = true;
+    Some(map)
+}
+
+fn encode_source(texel: &mut [u8], source: Source) {
+    let (x, y, new_frame) = match source {
+        Source::From(x, y) => (x, y, 0),
+        Source::To(x, y) => (x, y, 0x8000),
+    };
+    texel[0..2].copy_from_slice(&((x as u16 & 0x7FFF) | new_frame).to_be_bytes());
+    texel[2..4].copy_from_slice(&(y as u16).to_be_bytes());
+}
+
+/// Bounding box of the pixels that differ between two frames.
+pub fn changed_area(from: &[u8], to: &[u8], width: u32, height: u32) -> Option<IntRect> {
+    let (w, h) = (width as i32, height as i32);
+    let (mut left, mut top, mut right, mut bottom) = (w, h, -1, -1);
+    for y in 0..h {
+        let row = (y * w * 4) as usize;
+        for x in 0..w {
+            let i = row + (x * 4) as usize;
+            if from[i..i + 4] != to[i..i + 4] {
+                left = left.min(x);
+                right = right.max(x);
+                top = top.min(y);
+                bottom = bottom.max(y);
+            }
+        }
+    }
+    if right < 0 {
+        None
+    } else {
+        Some(IntRect::from(left, top, right + 1, bottom + 1))
+    }
+}
+
+/// Deterministic hash used to order dissolve cells.
+fn cell_rank(a: i32, b: i32) -> f64 {
+    let mut x = (a as u32).wrapping_mul(0x9E37_79B1) ^ (b as u32).wrapping_mul(0x85EB_CA77);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x2C1B_3C6D);
+    x ^= x >> 12;
+    x = x.wrapping_mul(0x297A_2D39);
+    x ^= x >> 15;
+    (x & 0xFFFF) as f64 / 65536.0
+}
+
+/// 4x4 ordered-dither threshold, used by the pattern dissolve.
+fn bayer_rank(x: i32, y: i32) -> f64 {
+    const MATRIX: [u8; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+    MATRIX[((y & 3) * 4 + (x & 3)) as usize] as f64 / 16.0
+}
+
+/// Where a destination pixel of the transitioned area comes from.
+enum Source {
+    From(i32, i32),
+    To(i32, i32),
+}
+
+/// Compose one transition step into `out`. Pixels outside `area` show `to`.
+pub fn compose(
+    spec: &TransitionSpec,
+    from: &[u8],
+    to: &[u8],
+    out: &mut [u8],
+    width: u32,
+    area: &IntRect,
+    progress: f64,
+) {
+    out.copy_from_slice(to);
+    for_each_source(spec, area, progress, |x, y, source| {
+        let (sx, sy, buf) = match source {
+            Source::From(sx, sy) => (sx, sy, from),
+            Source::To(sx, sy) => (sx, sy, to),
+        };
+        let src = (((area.top + sy) * width as i32 + area.left + sx) * 4) as usize;
+        let dst = (((area.top + y) * width as i32 + area.left + x) * 4) as usize;
+        out[dst..dst + 4].copy_from_slice(&buf[src..src + 4]);
+    });
+}
+
+/// Call `f` with the source of every pixel of `area` for one transition
+/// step. Coordinates are relative to `area`.
+fn for_each_source(spec: &TransitionSpec, area: &IntRect, progress: f64, mut f: impl FnMut(i32, i32, Source)) {
+    use TransitionType::*;
+
+    let w = area.width().max(1);
+    let h = area.height().max(1);
+    let chunk = spec.chunk_size.max(1) as i32;
+    // Snap a travelled distance to whole chunks so large chunk sizes
+    // produce the stepped look they have in Director.
+    let step = |extent: i32| -> i32 {
+        let d = (progress * extent as f64) as i32;
+        (d / chunk * chunk).clamp(0, extent)
+    };
+    let (cx, cy) = (w / 2, h / 2);
+
+    for y in 0..h {
+        for x in 0..w {
+            let reveal = |show_new: bool| if show_new { Source::To(x, y) } else { Source::From(x, y) };
+            let source = match spec.transition_type {
+                None => Source::To(x, y),
+                WipeRight => reveal(x < step(w)),
+                WipeLeft => reveal(x >= w - step(w)),
+                WipeDown => reveal(y < step(h)),
+                WipeUp => reveal(y >= h - step(h)),
+                CenterOutHorizontal => reveal((x - cx).abs() * 2 < step(w)),
+                EdgesInHorizontal => reveal((x - cx).abs() * 2 >= w - step(w)),
+                CenterOutVertical => reveal((y - cy).abs() * 2 < step(h)),
+                EdgesInVertical => reveal((y - cy).abs() * 2 >= h - step(h)),
+                CenterOutSquare | ZoomOpen => {
+                    reveal((x - cx).abs() * 2 < step(w) && (y - cy).abs() * 2 < step(h))
+                }
+                EdgesInSquare | ZoomClose => {
+                    reveal((x - cx).abs() * 2 >= w - step(w) || (y - cy).abs() * 2 >= h - step(h))
+                }
+                PushLeft => {
+                    let d = step(w);
+                    if x < w - d { Source::From(x + d, y) } else { Source::To(x - (w - d), y) }
+                }
+                PushRight => {
+                    let d = step(w);
+                    if x >= d { Source::From(x - d, y) } else { Source::To(x + (w - d), y) }
+                }
+                PushDown => {
+                    let d = step(h);
+                    if y >= d { Source::From(x, y - d) } else { Source::To(x, y + (h - d)) }
+                }
+                PushUp => {
+                    let d = step(h);
+                    if y < h - d { Source::From(x, y + d) } else { Source::To(x, y - (h - d)) }
+                }
+                RevealUp | RevealUpRight | RevealRight | RevealDownRight | RevealDown
+                | RevealDownLeft | RevealLeft | RevealUpLeft => {
+                    // The old image slides away in the given direction.
+                    let (dx, dy) = direction(spec.transition_type);
+                    let (ox, oy) = (x - dx * step(w), y - dy * step(h));
+                    if ox >= 0 && ox < w && oy >= 0 && oy < h {
+                        Source::From(ox, oy)
+                    } else {
+                        Source::To(x, y)
+                    }
+                }
+                CoverDown | CoverDownLeft | CoverDownRight | CoverLeft | CoverRight | CoverUp
+                | CoverUpLeft | CoverUpRight => {
+                    // The new image slides in over the old one.
+                    let (dx, dy) = direction(spec.transition_type);
+                    let (nx, ny) = (x + dx * (w - step(w)), y + dy * (h - step(h)));
+                    if nx >= 0 && nx < w && ny >= 0 && ny < h {
+                        Source::To(nx, ny)
+                    } else {
+                        Source::From(x, y)
+                    }
+                }
+                DissolvePixelsFast | DissolvePixels | DissolveBitsFast | DissolveBits => {
+                    reveal(cell_rank(x / chunk, y / chunk) < progress)
+                }
+                DissolveBoxyRects => {
+                    let size = chunk.max(4);
+                    reveal(cell_rank(x / (size * 2), y / size) < progress)
+                }
+                DissolveBoxySquares => {
+                    let size = chunk.max(4);
+                    reveal(cell_rank(x / size, y / size) < progress)
+                }
+                DissolvePatterns => reveal(bayer_rank(x / chunk, y / chunk) < progress),
+                RandomRows => reveal(cell_rank(0, y / chunk) < progress),
+                RandomColumns => reveal(cell_rank(x / chunk, 0) < progress),
+                VenetianBlinds => reveal(y % BAND_SIZE < step(BAND_SIZE)),
+                VerticalBlinds => reveal(x % BAND_SIZE < step(BAND_SIZE)),
+                Checkerboard => {
+                    // Each half of the duration fills one colour of the board.
+                    let parity = ((x / BAND_SIZE) + (y / BAND_SIZE)) & 1;
+                    let local = progress * 2.0 - parity as f64;
+                    reveal(((y % BAND_SIZE) as f64) < local * BAND_SIZE as f64)
+                }
+                StripsBottomBuildLeft | StripsBottomBuildRight | StripsLeftBuildDown
+                | StripsLeftBuildUp | StripsRightBuildDown | StripsRightBuildUp
+                | StripsTopBuildLeft | StripsTopBuildRight => {
+                    reveal(strip_revealed(spec.transition_type, x, y, w, h, progress))
+                }
+            };
+            f(x, y, source);
+        }
+    }
+}
+
+/// Unit motion vector for directional reveal/cover transitions.
+fn direction(transition_type: TransitionType) -> (i32, i32) {
+    use TransitionType::*;
+    match transition_type {
+        RevealUp | CoverUp => (0, -1),
+        RevealUpRight | CoverUpRight => (1, -1),
+        RevealRight | CoverRight => (1, 0),
+        RevealDownRight | CoverDownRight => (1, 1),
+        RevealDown | CoverDown => (0, 1),
+        RevealDownLeft | CoverDownLeft => (-1, 1),
+        RevealLeft | CoverLeft => (-1, 0),
+        RevealUpLeft | CoverUpLeft => (-1, -1),
+        _ => (0, 0),
+    }
+}
+
+/// Strip transitions: the area is cut into strips that grow from one edge,
+/// starting one after another in the build direction.
+fn strip_revealed(transition_type: TransitionType, x: i32, y: i32, w: i32, h: i32, progress: f64) -> bool {
+    use TransitionType::*;
+    // (strips are columns, grow from bottom/right, build towards lower index)
+    let (columns, from_far_edge, build_reverse) = match transition_type {
+        StripsBottomBuildLeft => (true, true, true),
+        StripsBottomBuildRight => (true, true, false),
+        StripsTopBuildLeft => (true, false, true),
+        StripsTopBuildRight => (true, false, false),
+        StripsLeftBuildDown => (false, false, false),
+        StripsLeftBuildUp => (false, false, true),
+        StripsRightBuildDown => (false, true, false),
+        StripsRightBuildUp => (false, true, true),
+        _ => return true,
+    };
+    let (along, across, length, breadth) = if columns { (y, x, h, w) } else { (x, y, w, h) };
+    let count = (breadth + BAND_SIZE - 1) / BAND_SIZE;
+    let mut index = across / BAND_SIZE;
+    if build_reverse {
+        index = count - 1 - index;
+    }
+    // Strips start staggered over the first half and each takes half the duration.
+    let start = if count > 1 { index as f64 / (count - 1) as f64 * 0.5 } else { 0.0 };
+    let local = ((progress - start) * 2.0).clamp(0.0, 1.0);
+    let filled = (local * length as f64) as i32;
+    if from_far_edge {
+        along >= length - filled
+    } else {
+        along < filled
+    }
+}
@@ -133,6 +133,7 @@ impl VirtualScriptRegistry {
                 script_id: 0,
                 script_type,
                 name: name.to_string(),
+                script_text: String::new(),
             }),
             color: ColorRef::Rgb(0, 0, 0),
             bg_color: ColorRef::Rgb(255, 255, 255),
@@ -191,6 +192,7 @@ impl VirtualScriptRegistry {
             ancestor: None,
             properties,
             begin_sprite_called: false,
+            window: player.windows.current_window(),
         };
         let instance_ref = player.allocator.alloc_script_instance(instance);
         let datum_ref = player.alloc_datum(Datum::ScriptInstanceRef(instance_ref.clone()));
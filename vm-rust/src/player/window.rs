@@ -0,0 +1,1049 @@
+//! Movies in a window (MIAW).
+//!
+//! Each window owns a complete `Movie` together with the player state that
+//! belongs to a single movie (playhead flags, sprite focus, script instance
+//! caches, ...). The player itself only ever runs `player.movie`: to step,
+//! render or talk to a window, its `MovieContext` is swapped into the player
+//! and swapped back out afterwards. The context stack records every swap so
+//! that nested `tell` blocks and errors unwind in order.
+//!
+//! Globals are shared by all movies, except for the actorList which each
+//! movie keeps for itself. Script instances remember the movie that created
+//! them: their handlers are looked up in that movie's casts, and calling one
+//! swaps that movie in for the duration of the call.
+//!
+//! Windows are drawn on top of the stage canvas, so their rects are in stage
+//! coordinates.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    time::Duration,
+};
+
+use async_std::future::{self, timeout};
+use fxhash::FxHashMap;
+
+use crate::{
+    director::lingo::datum::{Datum, DatumType},
+    player::{
+        allocator::ScriptInstanceAllocatorTrait,
+        bitmap::{
+            bitmap::{get_system_default_palette, Bitmap, PaletteRef},
+            manager::BitmapRef,
+        },
+        commands::{run_player_command, PlayerVMCommand},
+        events::player_invoke_global_event,
+        geometry::IntRect,
+        movie::Movie,
+        reserve_player_mut, reserve_player_mut_async, reserve_player_ref, run_movie_frame,
+        run_movie_init_sequence,
+        score::ScoreRef,
+        scope::ScopeRef,
+        script::ScriptInstance,
+        script_ref::ScriptInstanceRef,
+        sprite::ColorRef,
+        tempo_wait::PendingTempoWait,
+        DatumRef, DirPlayer, ScriptError, ScriptErrorCode,
+    },
+    rendering::render_score_to_bitmap,
+};
+
+/// Player state that belongs to one movie rather than to the player.
+pub struct MovieContext {
+    pub movie: Movie,
+    actor_list: DatumRef,
+    next_frame: Option<u32>,
+    has_player_frame_changed: bool,
+    has_frame_changed_in_go: bool,
+    go_same_frame: bool,
+    go_direction: u8,
+    last_initialized_frame: Option<u32>,
+    current_frame_tempo: u32,
+    delay_until: Option<chrono::DateTime<chrono::Local>>,
+    tempo_wait: Option<PendingTempoWait>,
+    bg_color: ColorRef,
+    keyboard_focus_sprite: i16,
+    mouse_down_sprite: i16,
+    click_on_sprite: i16,
+    hovered_sprite: Option<i16>,
+    current_score_context: ScoreRef,
+    trails_bitmap: Option<Bitmap>,
+    script_instance_list_cache: FxHashMap<i16, DatumRef>,
+    script_instance_list_cache_owner: FxHashMap<usize, i16>,
+    script_instance_list_generation: FxHashMap<i16, u64>,
+    script_instance_list_ids_cache: FxHashMap<i16, (u64, Vec<ScriptInstanceRef>)>,
+    flash_frame_buffers: HashMap<(i32, i32), BitmapRef>,
+    w3d_frame_buffers: HashMap<(i32, i32), BitmapRef>,
+}
+
+impl MovieContext {
+    pub fn new(movie: Movie, actor_list: DatumRef) -> MovieContext {
+        MovieContext {
+            bg_color: movie.stage_color_ref.clone(),
+            movie,
+            actor_list,
+            next_frame: None,
+            has_player_frame_changed: false,
+            has_frame_changed_in_go: false,
+            go_same_frame: false,
+            go_direction: 0,
+            last_initialized_frame: None,
+            current_frame_tempo: 0,
+            delay_until: None,
+            tempo_wait: None,
+            keyboard_focus_sprite: -1,
+            mouse_down_sprite: -1,
+            click_on_sprite: 0,
+            hovered_sprite: None,
+            current_score_context: ScoreRef::Stage,
+            trails_bitmap: None,
+            script_instance_list_cache: FxHashMap::default(),
+            script_instance_list_cache_owner: FxHashMap::default(),
+            script_instance_list_generation: FxHashMap::default(),
+            script_instance_list_ids_cache: FxHashMap::default(),
+            flash_frame_buffers: HashMap::new(),
+            w3d_frame_buffers: HashMap::new(),
+        }
+    }
+
+    /// Exchange this context with the one the player is running.
+    fn swap_with(&mut self, player: &mut DirPlayer) {
+        use std::mem::swap;
+
+        swap(&mut self.movie, &mut player.movie);
+        swap(&mut self.next_frame, &mut player.next_frame);
+        swap(&mut self.has_player_frame_changed, &mut player.has_player_frame_changed);
+        swap(&mut self.has_frame_changed_in_go, &mut player.has_frame_changed_in_go);
+        swap(&mut self.go_same_frame, &mut player.go_same_frame);
+        swap(&mut self.go_direction, &mut player.go_direction);
+        swap(&mut self.last_initialized_frame, &mut player.last_initialized_frame);
+        swap(&mut self.current_frame_tempo, &mut player.current_frame_tempo);
+        swap(&mut self.delay_until, &mut player.delay_until);
+        swap(&mut self.tempo_wait, &mut player.tempo_wait);
+        swap(&mut self.bg_color, &mut player.bg_color);
+        swap(&mut self.keyboard_focus_sprite, &mut player.keyboard_focus_sprite);
+        swap(&mut self.mouse_down_sprite, &mut player.mouse_down_sprite);
+        swap(&mut self.click_on_sprite, &mut player.click_on_sprite);
+        swap(&mut self.hovered_sprite, &mut player.hovered_sprite);
+        swap(&mut self.current_score_context, &mut player.current_score_context);
+        swap(&mut self.trails_bitmap, &mut player.trails_bitmap);
+        swap(&mut self.script_instance_list_cache, &mut player.script_instance_list_cache);
+        swap(&mut self.script_instance_list_cache_owner, &mut player.script_instance_list_cache_owner);
+        swap(&mut self.script_instance_list_generation, &mut player.script_instance_list_generation);
+        swap(&mut self.script_instance_list_ids_cache, &mut player.script_instance_list_ids_cache);
+        swap(&mut self.flash_frame_buffers, &mut player.flash_frame_buffers);
+        swap(&mut self.w3d_frame_buffers, &mut player.w3d_frame_buffers);
+
+        let outgoing = player.globals.remove("actorList").unwrap_or(DatumRef::Void);
+        let incoming = std::mem::replace(&mut self.actor_list, outgoing);
+        if incoming != DatumRef::Void {
+            player.globals.insert("actorList".to_string(), incoming);
+        }
+        player.actor_list_generation = player.actor_list_generation.wrapping_add(1);
+        player.invalidate_behavior_channel_cache();
+        player.invalidate_active_stage_filmloop_cache();
+        player.stage_dirty = true;
+    }
+}
+
+/// `the titlebarOptions of window`.
+#[derive(Clone)]
+pub struct TitlebarOptions {
+    pub visible: bool,
+    pub closebox: bool,
+    pub minimizebox: bool,
+    pub maximizebox: bool,
+    pub side_title: bool,
+    pub icon: Datum,
+}
+
+impl Default for TitlebarOptions {
+    fn default() -> Self {
+        TitlebarOptions {
+            visible: true,
+            closebox: true,
+            minimizebox: true,
+            maximizebox: true,
+            side_title: false,
+            icon: Datum::Void,
+        }
+    }
+}
+
+pub struct MovieWindow {
+    pub id: u32,
+    pub name: String,
+    /// Movie file played in the window. Defaults to the window name.
+    pub file_name: String,
+    pub title: String,
+    pub titlebar_options: TitlebarOptions,
+    pub window_type: i32,
+    /// `the type of window` (#document, #tool, #dialog or #fullscreen).
+    pub kind: String,
+    /// Window frame. Defaults to the movie's own stage rect once loaded.
+    pub rect: Option<IntRect>,
+    /// Area the movie is drawn into, relative to the window origin.
+    /// `None` means the whole window.
+    pub draw_rect: Option<IntRect>,
+    pub visible: bool,
+    pub modal: bool,
+    pub is_open: bool,
+    pub layer: u32,
+    is_started: bool,
+    forget_pending: bool,
+    /// The window's movie; holds the outer movie while the window is entered.
+    context: Option<MovieContext>,
+}
+
+impl MovieWindow {
+    fn new(id: u32, name: &str) -> MovieWindow {
+        MovieWindow {
+            id,
+            name: name.to_string(),
+            file_name: name.to_string(),
+            title: name.to_string(),
+            titlebar_options: TitlebarOptions::default(),
+            window_type: -1,
+            kind: "document".to_string(),
+            rect: None,
+            draw_rect: None,
+            visible: false,
+            modal: false,
+            is_open: false,
+            layer: 0,
+            is_started: false,
+            forget_pending: false,
+            context: None,
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.context.is_some()
+    }
+
+    pub fn frame_rect(&self) -> IntRect {
+        self.rect.clone().unwrap_or(IntRect::from(0, 0, 0, 0))
+    }
+
+    /// The draw rect in stage coordinates.
+    pub fn stage_draw_rect(&self) -> IntRect {
+        let frame = self.frame_rect();
+        match &self.draw_rect {
+            Some(local) => IntRect::from(
+                frame.left + local.left,
+                frame.top + local.top,
+                frame.left + local.right,
+                frame.top + local.bottom,
+            ),
+            None => frame,
+        }
+    }
+}
+
+enum ContextEntry {
+    /// A window's movie was swapped in.
+    Window(u32),
+    /// The target was already current; nothing to undo.
+    Current,
+    /// Windows were left to get back to the stage. Re-entered on exit.
+    Stage(Vec<ContextFrame>),
+}
+
+struct ContextFrame {
+    /// Scope of the `tell` block that pushed this frame, if any.
+    scope: Option<ScopeRef>,
+    entry: ContextEntry,
+}
+
+#[derive(Default)]
+pub struct WindowManager {
+    pub windows: Vec<MovieWindow>,
+    /// Window with focus, or `None` when the stage is active.
+    pub active_window: Option<u32>,
+    context_stack: Vec<ContextFrame>,
+    next_id: u32,
+    next_layer: u32,
+    mouse_capture: Option<u32>,
+}
+
+impl WindowManager {
+    pub fn find(&self, name: &str) -> Option<&MovieWindow> {
+        self.windows.iter().find(|w| w.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut MovieWindow> {
+        self.windows.iter_mut().find(|w| w.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn get(&self, id: u32) -> Option<&MovieWindow> {
+        self.windows.iter().find(|w| w.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut MovieWindow> {
+        self.windows.iter_mut().find(|w| w.id == id)
+    }
+
+    /// Windows are created when they are first opened, never by merely
+    /// referencing them or reading or setting their properties.
+    pub fn get_or_create(&mut self, name: &str) -> &mut MovieWindow {
+        if let Some(index) = self.windows.iter().position(|w| w.name.eq_ignore_ascii_case(name)) {
+            return &mut self.windows[index];
+        }
+        self.next_id += 1;
+        self.windows.push(MovieWindow::new(self.next_id, name));
+        self.windows.last_mut().unwrap()
+    }
+
+    /// The window whose movie is running in the player, or `None` for the stage.
+    pub fn current_window(&self) -> Option<u32> {
+        for frame in self.context_stack.iter().rev() {
+            match frame.entry {
+                ContextEntry::Window(id) => return Some(id),
+                ContextEntry::Stage(_) => return None,
+                ContextEntry::Current => {}
+            }
+        }
+        None
+    }
+
+    /// Open windows, front to back.
+    pub fn open_windows_front_to_back(&self) -> Vec<&MovieWindow> {
+        let mut windows: Vec<&MovieWindow> = self.windows.iter().filter(|w| w.is_open).collect();
+        windows.sort_by_key(|w| std::cmp::Reverse(w.layer));
+        windows
+    }
+
+    pub fn front_window(&self) -> Option<&MovieWindow> {
+        self.open_windows_front_to_back().into_iter().find(|w| w.visible)
+    }
+
+    fn bring_to_front(&mut self, id: u32) {
+        self.next_layer += 1;
+        let layer = self.next_layer;
+        if let Some(window) = self.get_mut(id) {
+            window.layer = layer;
+        }
+    }
+
+    fn send_to_back(&mut self, id: u32) {
+        let min_layer = self.windows.iter().map(|w| w.layer).min().unwrap_or(0);
+        for window in self.windows.iter_mut() {
+            window.layer += 1;
+        }
+        self.next_layer += 1;
+        if let Some(window) = self.get_mut(id) {
+            window.layer = min_layer;
+        }
+    }
+
+    fn is_swapped_in(&self, id: u32) -> bool {
+        self.context_stack.iter().any(|f| matches!(f.entry, ContextEntry::Window(w) if w == id))
+    }
+
+    /// True while any context frame (active or suspended) refers to the window.
+    fn is_in_use(&self, id: u32) -> bool {
+        fn frames_use(frames: &[ContextFrame], id: u32) -> bool {
+            frames.iter().any(|f| match &f.entry {
+                ContextEntry::Window(w) => *w == id,
+                ContextEntry::Stage(saved) => frames_use(saved, id),
+                ContextEntry::Current => false,
+            })
+        }
+        frames_use(&self.context_stack, id)
+    }
+
+    /// Topmost open, visible, loaded window under a stage point, with the
+    /// point mapped into the window movie's coordinates.
+    fn window_at(&self, x: i32, y: i32) -> Option<(u32, (i32, i32))> {
+        self.open_windows_front_to_back()
+            .into_iter()
+            .filter(|w| w.visible && w.is_loaded())
+            .find(|w| {
+                let area = w.frame_rect().intersect(&w.stage_draw_rect());
+                x >= area.left && x < area.right && y >= area.top && y < area.bottom
+            })
+            .map(|w| (w.id, self.to_movie_point(w, x, y)))
+    }
+
+    /// Map a stage point into the movie coordinates of a window.
+    fn to_movie_point(&self, window: &MovieWindow, x: i32, y: i32) -> (i32, i32) {
+        let draw = window.stage_draw_rect();
+        let source = window
+            .context
+            .as_ref()
+            .map(|c| c.movie.rect.clone())
+            .unwrap_or(draw.clone());
+        if draw.width() <= 0 || draw.height() <= 0 {
+            return (x - draw.left, y - draw.top);
+        }
+        (
+            (x - draw.left) * source.width() / draw.width(),
+            (y - draw.top) * source.height() / draw.height(),
+        )
+    }
+}
+
+/// The movie a window plays, wherever it currently lives: in the window,
+/// in the player while the window is current, or in the slot of the window
+/// entered after it.
+pub fn window_movie(player: &DirPlayer, id: u32) -> Option<&Movie> {
+    let stack = &player.windows.context_stack;
+    let Some(pos) = stack.iter().position(|f| matches!(f.entry, ContextEntry::Window(w) if w == id)) else {
+        return player.windows.get(id)?.context.as_ref().map(|c| &c.movie);
+    };
+    let next_window = stack[pos + 1..].iter().find_map(|f| match f.entry {
+        ContextEntry::Window(w) => Some(w),
+        _ => None,
+    });
+    match next_window {
+        Some(next) => player.windows.get(next)?.context.as_ref().map(|c| &c.movie),
+        None => Some(&player.movie),
+    }
+}
+
+/// The stage movie, wherever it currently lives: in the player, or in the
+/// slot of the first window swapped in over it.
+pub fn stage_movie(player: &DirPlayer) -> &Movie {
+    let first_window = player.windows.context_stack.iter().find_map(|f| match f.entry {
+        ContextEntry::Window(w) => Some(w),
+        _ => None,
+    });
+    first_window
+        .and_then(|id| player.windows.get(id)?.context.as_ref())
+        .map_or(&player.movie, |c| &c.movie)
+}
+
+/// The movie that created a script instance.
+pub fn instance_movie<'a>(player: &'a DirPlayer, instance: &ScriptInstance) -> Option<&'a Movie> {
+    match instance.window {
+        Some(id) => window_movie(player, id),
+        None => Some(stage_movie(player)),
+    }
+}
+
+fn swap_window(player: &mut DirPlayer, id: u32) {
+    let Some(window) = player.windows.get_mut(id) else {
+        return;
+    };
+    let Some(mut context) = window.context.take() else {
+        return;
+    };
+    context.swap_with(player);
+    if let Some(window) = player.windows.get_mut(id) {
+        window.context = Some(context);
+    }
+}
+
+/// Make a window's movie the current movie until `leave_context`.
+pub fn enter_window(player: &mut DirPlayer, id: u32, scope: Option<ScopeRef>) -> Result<(), ScriptError> {
+    let Some(window) = player.windows.get(id) else {
+        return Err(ScriptError::new("Window no longer exists".to_string()));
+    };
+    if player.windows.current_window() == Some(id) {
+        enter_current(player, scope);
+        return Ok(());
+    }
+    if !window.is_loaded() {
+        return Err(ScriptError::new(format!("Window \"{}\" has no movie loaded", window.name)));
+    }
+    if player.windows.is_swapped_in(id) {
+        return Err(ScriptError::new(format!(
+            "Window \"{}\" is already running a tell block further up the call stack",
+            window.name
+        )));
+    }
+    swap_window(player, id);
+    player.windows.context_stack.push(ContextFrame { scope, entry: ContextEntry::Window(id) });
+    Ok(())
+}
+
+/// Make the stage movie current until `leave_context`.
+pub fn enter_stage(player: &mut DirPlayer, scope: Option<ScopeRef>) {
+    let mut saved = Vec::new();
+    while player.windows.current_window().is_some() {
+        let Some(frame) = player.windows.context_stack.pop() else {
+            break;
+        };
+        if let ContextEntry::Window(id) = frame.entry {
+            swap_window(player, id);
+        }
+        saved.push(frame);
+    }
+    let entry = if saved.is_empty() {
+        ContextEntry::Current
+    } else {
+        saved.reverse();
+        ContextEntry::Stage(saved)
+    };
+    player.windows.context_stack.push(ContextFrame { scope, entry });
+}
+
+/// Make the movie that created `instance_ref` current for a handler call on
+/// it. The frame is tagged with the scope the call is about to push, so it
+/// is left when that scope pops. Does nothing when the movie is already
+/// current.
+pub fn enter_instance_movie(player: &mut DirPlayer, instance_ref: &ScriptInstanceRef) -> Result<(), ScriptError> {
+    let Some(owner) = player.allocator.get_script_instance_opt(instance_ref).map(|i| i.window) else {
+        return Ok(());
+    };
+    if owner == player.windows.current_window() {
+        return Ok(());
+    }
+    let scope = Some(player.scope_count as ScopeRef);
+    match owner {
+        Some(id) => enter_window(player, id, scope),
+        None => {
+            enter_stage(player, scope);
+            Ok(())
+        }
+    }
+}
+
+/// Keep the current movie, but push a frame for `leave_context` to pop.
+pub fn enter_current(player: &mut DirPlayer, scope: Option<ScopeRef>) {
+    player.windows.context_stack.push(ContextFrame { scope, entry: ContextEntry::Current });
+}
+
+/// Undo the most recent `enter_window`, `enter_stage` or `enter_current`.
+pub fn leave_context(player: &mut DirPlayer) {
+    let Some(frame) = player.windows.context_stack.pop() else {
+        return;
+    };
+    match frame.entry {
+        ContextEntry::Window(id) => swap_window(player, id),
+        ContextEntry::Current => {}
+        ContextEntry::Stage(saved) => {
+            for frame in saved {
+                if let ContextEntry::Window(id) = frame.entry {
+                    swap_window(player, id);
+                }
+                player.windows.context_stack.push(frame);
+            }
+        }
+    }
+}
+
+/// `end tell`: leave the context entered by the matching `tell`.
+pub fn leave_tell(player: &mut DirPlayer, scope_ref: ScopeRef) {
+    if player.windows.context_stack.last().is_some_and(|f| f.scope == Some(scope_ref)) {
+        leave_context(player);
+    }
+}
+
+/// Leave `tell` contexts whose handler has returned or failed without
+/// reaching `end tell`. Called when a scope is popped.
+pub fn unwind_scope(player: &mut DirPlayer) {
+    while player
+        .windows
+        .context_stack
+        .last()
+        .is_some_and(|f| f.scope.is_some_and(|s| s >= player.scope_count as ScopeRef))
+    {
+        leave_context(player);
+    }
+}
+
+/// Swap every window movie back out. Used before the player is reset.
+pub fn leave_all(player: &mut DirPlayer) {
+    while !player.windows.context_stack.is_empty() {
+        leave_context(player);
+    }
+}
+
+/// Run `f` with the stage movie current.
+pub fn with_stage_context<T>(player: &mut DirPlayer, f: impl FnOnce(&mut DirPlayer) -> T) -> T {
+    enter_stage(player, None);
+    let result = f(player);
+    leave_context(player);
+    result
+}
+
+/// Wait until no window movie is swapped in. Loops that start work on the
+/// stage (frames, input, events) call this so they never run against a
+/// window's movie while a `tell` block elsewhere is suspended.
+pub async fn wait_for_stage_context() {
+    while reserve_player_ref(|player| player.windows.current_window().is_some()) {
+        let _ = timeout(Duration::from_millis(1), future::pending::<()>()).await;
+    }
+}
+
+async fn in_window<T>(id: u32, f: impl Future<Output = T>) -> Result<T, ScriptError> {
+    reserve_player_mut(|player| enter_window(player, id, None))?;
+    let result = f.await;
+    reserve_player_mut(leave_context);
+    Ok(result)
+}
+
+fn report_event_error(result: Result<DatumRef, ScriptError>) {
+    if let Err(err) = result
+        && err.code != ScriptErrorCode::HandlerNotFound
+        && err.code != ScriptErrorCode::Abort
+    {
+        reserve_player_mut(|player| player.on_script_error(&err));
+    }
+}
+
+/// Send a window event to the movie of a window (`Some`) or the stage (`None`).
+async fn dispatch_window_event(target: Option<u32>, handler_name: &str) {
+    match target {
+        Some(id) => {
+            if let Ok(result) = in_window(id, player_invoke_global_event(handler_name, &vec![])).await {
+                report_event_error(result);
+            }
+        }
+        None => {
+            reserve_player_mut(|player| enter_stage(player, None));
+            let result = player_invoke_global_event(handler_name, &vec![]).await;
+            reserve_player_mut(leave_context);
+            report_event_error(result);
+        }
+    }
+}
+
+pub fn find_window_id(name: &str) -> Option<u32> {
+    reserve_player_ref(|player| player.windows.find(name).map(|w| w.id))
+}
+
+pub fn create_window(name: &str) -> u32 {
+    reserve_player_mut(|player| player.windows.get_or_create(name).id)
+}
+
+fn movie_file_candidates(file_name: &str) -> Vec<String> {
+    let base_name = file_name.rsplit(['/', '\\']).next().unwrap_or(file_name);
+    if base_name.contains('.') {
+        vec![file_name.to_string()]
+    } else {
+        ["dcr", "dir", "dxr"].iter().map(|ext| format!("{}.{}", file_name, ext)).collect()
+    }
+}
+
+async fn load_window_movie(id: u32) -> Result<(), ScriptError> {
+    let Some((name, file_name)) =
+        reserve_player_ref(|player| player.windows.get(id).map(|w| (w.name.clone(), w.file_name.clone())))
+    else {
+        return Ok(());
+    };
+
+    let mut last_error = String::new();
+    for candidate in movie_file_candidates(&file_name) {
+        let result = reserve_player_mut_async(|player| {
+            Box::pin(async move { player.fetch_director_file(&candidate).await })
+        })
+        .await;
+        let dir = match result {
+            Ok(dir) => dir,
+            Err(err) => {
+                last_error = err;
+                continue;
+            }
+        };
+        let context = reserve_player_mut_async(|player| {
+            Box::pin(async move {
+                let mut movie = Movie::empty();
+                movie
+                    .load_from_file(dir, &mut player.net_manager, &mut player.bitmap_manager, &mut player.dir_cache)
+                    .await;
+                movie.cast_manager.load_fonts_into_manager(&mut player.font_manager);
+                let actor_list = player.alloc_datum(Datum::List(DatumType::List, VecDeque::new(), false));
+                MovieContext::new(movie, actor_list)
+            })
+        })
+        .await;
+        reserve_player_mut(|player| {
+            if let Some(window) = player.windows.get_mut(id) {
+                if window.rect.is_none() {
+                    window.rect = Some(context.movie.rect.clone());
+                }
+                window.context = Some(context);
+                window.is_started = false;
+            }
+        });
+        return Ok(());
+    }
+    Err(ScriptError::new(format!(
+        "Could not load the movie for window \"{}\": {}",
+        name, last_error
+    )))
+}
+
+/// `open window`: load and start the window's movie if needed, show it and
+/// give it focus.
+pub async fn open_window(id: u32) -> Result<(), ScriptError> {
+    let Some((is_open, is_loaded)) =
+        reserve_player_ref(|player| player.windows.get(id).map(|w| (w.is_open, w.is_loaded())))
+    else {
+        return Ok(());
+    };
+    if is_open {
+        activate_window(Some(id)).await;
+        return Ok(());
+    }
+    if !is_loaded {
+        load_window_movie(id).await?;
+    }
+
+    let needs_start = reserve_player_mut(|player| {
+        player.windows.bring_to_front(id);
+        player.stage_dirty = true;
+        let Some(window) = player.windows.get_mut(id) else {
+            return false;
+        };
+        window.is_open = true;
+        window.visible = true;
+        !std::mem::replace(&mut window.is_started, true)
+    });
+    if needs_start {
+        in_window(id, Box::pin(run_movie_init_sequence())).await?;
+    }
+    dispatch_window_event(Some(id), "openWindow").await;
+    activate_window(Some(id)).await;
+    Ok(())
+}
+
+/// `close window`: hide the window. Its movie stays loaded but stops playing.
+pub async fn close_window(id: u32) {
+    let is_open = reserve_player_ref(|player| player.windows.get(id).is_some_and(|w| w.is_open));
+    if !is_open {
+        return;
+    }
+    dispatch_window_event(Some(id), "closeWindow").await;
+    let next_active = reserve_player_mut(|player| {
+        let windows = &mut player.windows;
+        if let Some(window) = windows.get_mut(id) {
+            window.is_open = false;
+            window.visible = false;
+        }
+        if windows.mouse_capture == Some(id) {
+            windows.mouse_capture = None;
+        }
+        player.stage_dirty = true;
+        if player.windows.active_window == Some(id) {
+            Some(player.windows.front_window().map(|w| w.id))
+        } else {
+            None
+        }
+    });
+    if let Some(next_active) = next_active {
+        activate_window(next_active).await;
+    }
+}
+
+/// `forget window`: close the window and discard its movie.
+pub async fn forget_window(id: u32) {
+    close_window(id).await;
+    let in_use = reserve_player_mut(|player| {
+        let in_use = player.windows.is_in_use(id);
+        if in_use && let Some(window) = player.windows.get_mut(id) {
+            window.forget_pending = true;
+        }
+        in_use
+    });
+    if in_use {
+        return;
+    }
+    let is_started = reserve_player_ref(|player| player.windows.get(id).is_some_and(|w| w.is_started));
+    if is_started {
+        let _ = in_window(id, stop_window_movie()).await;
+    }
+    reserve_player_mut(|player| {
+        player.windows.windows.retain(|w| w.id != id);
+        if player.windows.active_window == Some(id) {
+            player.windows.active_window = None;
+        }
+    });
+}
+
+async fn stop_window_movie() {
+    report_event_error(player_invoke_global_event("stopMovie", &vec![]).await);
+    reserve_player_mut_async(|player| Box::pin(async move { player.end_all_sprites().await })).await;
+}
+
+/// Give focus to a window (`Some`) or the stage (`None`), sending
+/// deactivateWindow to the movie losing focus and activateWindow to the
+/// movie gaining it.
+pub async fn activate_window(target: Option<u32>) {
+    let previous = reserve_player_mut(|player| {
+        if let Some(id) = target {
+            player.windows.bring_to_front(id);
+            player.stage_dirty = true;
+        }
+        std::mem::replace(&mut player.windows.active_window, target)
+    });
+    if previous == target {
+        return;
+    }
+    if previous.is_none() || reserve_player_ref(|player| previous.and_then(|id| player.windows.get(id)).is_some()) {
+        dispatch_window_event(previous, "deactivateWindow").await;
+    }
+    dispatch_window_event(target, "activateWindow").await;
+}
+
+pub fn move_to_front(id: u32) {
+    reserve_player_mut(|player| {
+        player.windows.bring_to_front(id);
+        player.stage_dirty = true;
+    });
+}
+
+pub fn move_to_back(id: u32) {
+    reserve_player_mut(|player| {
+        player.windows.send_to_back(id);
+        player.stage_dirty = true;
+    });
+}
+
+/// Set the window frame, sending moveWindow and resizeWindow when the
+/// position or size changed.
+pub async fn set_window_rect(id: u32, rect: IntRect) {
+    let (moved, resized) = reserve_player_mut(|player| {
+        let Some(window) = player.windows.get_mut(id) else {
+            return (false, false);
+        };
+        let old = window.frame_rect();
+        let moved = old.left != rect.left || old.top != rect.top;
+        let resized = old.width() != rect.width() || old.height() != rect.height();
+        window.rect = Some(rect);
+        player.stage_dirty = true;
+        let is_open = window.is_open;
+        (moved && is_open, resized && is_open)
+    });
+    if moved {
+        dispatch_window_event(Some(id), "moveWindow").await;
+    }
+    if resized {
+        dispatch_window_event(Some(id), "resizeWindow").await;
+    }
+}
+
+/// Point a window at another movie file, replacing any movie it already
+/// plays.
+pub async fn set_window_file_name(id: u32, file_name: String) -> Result<(), ScriptError> {
+    let (was_loaded, was_open, is_started) = reserve_player_mut(|player| {
+        let Some(window) = player.windows.get_mut(id) else {
+            return (false, false, false);
+        };
+        window.file_name = file_name;
+        (window.is_loaded(), window.is_open, window.is_started)
+    });
+    if !was_loaded {
+        return Ok(());
+    }
+    if reserve_player_ref(|player| player.windows.is_in_use(id)) {
+        return Err(ScriptError::new(
+            "Cannot replace the movie of a window while it is running a tell block".to_string(),
+        ));
+    }
+    if is_started {
+        let _ = in_window(id, stop_window_movie()).await;
+    }
+    reserve_player_mut(|player| {
+        if let Some(window) = player.windows.get_mut(id) {
+            window.context = None;
+            window.is_started = false;
+            window.is_open = false;
+        }
+    });
+    if was_open {
+        open_window(id).await
+    } else {
+        load_window_movie(id).await
+    }
+}
+
+/// Run one frame of every open window's movie. Called by the frame loop
+/// after the stage movie has advanced.
+pub async fn step_windows() {
+    let ids: Vec<u32> = reserve_player_ref(|player| {
+        player
+            .windows
+            .windows
+            .iter()
+            .filter(|w| w.is_open && w.is_started && w.is_loaded())
+            .map(|w| w.id)
+            .collect()
+    });
+    for id in ids {
+        wait_for_stage_context().await;
+        let still_open = reserve_player_ref(|player| {
+            player.windows.get(id).is_some_and(|w| w.is_open && w.is_loaded())
+        });
+        if still_open {
+            let _ = in_window(id, Box::pin(run_movie_frame())).await;
+        }
+    }
+
+    let forgotten: Vec<u32> = reserve_player_ref(|player| {
+        player
+            .windows
+            .windows
+            .iter()
+            .filter(|w| w.forget_pending && !player.windows.is_in_use(w.id))
+            .map(|w| w.id)
+            .collect()
+    });
+    for id in forgotten {
+        forget_window(id).await;
+    }
+}
+
+/// Draw every open, visible window on top of the stage bitmap, back to front.
+pub fn composite_windows(player: &mut DirPlayer, bitmap: &mut Bitmap) {
+    let mut windows: Vec<(u32, u32)> = player
+        .windows
+        .windows
+        .iter()
+        .filter(|w| w.is_open && w.visible && w.is_loaded())
+        .map(|w| (w.layer, w.id))
+        .collect();
+    windows.sort();
+
+    for (_, id) in windows {
+        if enter_window(player, id, None).is_err() {
+            continue;
+        }
+        let source = IntRect::from_size(0, 0, player.movie.rect.width(), player.movie.rect.height());
+        let mut offscreen = Bitmap::new(
+            source.width().max(1) as u16,
+            source.height().max(1) as u16,
+            32,
+            32,
+            0,
+            PaletteRef::BuiltIn(get_system_default_palette()),
+        );
+        render_score_to_bitmap(player, &ScoreRef::Stage, &mut offscreen, None, source.clone());
+        leave_context(player);
+
+        let Some(window) = player.windows.get(id) else {
+            continue;
+        };
+        let draw = window.stage_draw_rect();
+        let visible = window.frame_rect().intersect(&draw);
+        if visible.width() <= 0 || visible.height() <= 0 || draw.width() <= 0 || draw.height() <= 0 {
+            continue;
+        }
+        let src_rect = IntRect::from(
+            (visible.left - draw.left) * source.width() / draw.width(),
+            (visible.top - draw.top) * source.height() / draw.height(),
+            (visible.right - draw.left) * source.width() / draw.width(),
+            (visible.bottom - draw.top) * source.height() / draw.height(),
+        );
+        let palettes = player.movie.cast_manager.palettes();
+        bitmap.copy_pixels(&palettes, &offscreen, visible, src_rect, &HashMap::new(), None);
+    }
+}
+
+enum InputRoute {
+    Stage,
+    ActivateStage,
+    Blocked,
+    Window { id: u32, command: PlayerVMCommand, activate: bool },
+}
+
+/// Send mouse and keyboard input to the window it belongs to. Mouse input
+/// goes to the window under the pointer (or the window that took the mouse
+/// down), keys go to the active window. Returns `None` when the stage should
+/// handle the command itself.
+pub async fn route_input_command(command: &PlayerVMCommand) -> Option<Result<DatumRef, ScriptError>> {
+    let stage_mouse_loc = match command {
+        PlayerVMCommand::MouseDown(point)
+        | PlayerVMCommand::MouseUp(point)
+        | PlayerVMCommand::MouseMove(point)
+        | PlayerVMCommand::RightMouseDown(point)
+        | PlayerVMCommand::RightMouseUp(point) => Some(*point),
+        _ => None,
+    };
+    let route = reserve_player_mut(|player| {
+        let windows = &mut player.windows;
+        if windows.windows.is_empty() || windows.current_window().is_some() {
+            return InputRoute::Stage;
+        }
+        let modal_open = windows.windows.iter().any(|w| w.is_open && w.modal);
+        let to_window = |windows: &WindowManager, id: u32, x: i32, y: i32| {
+            windows.get(id).map(|w| windows.to_movie_point(w, x, y))
+        };
+        match command {
+            PlayerVMCommand::MouseDown((x, y)) => match windows.window_at(*x, *y) {
+                Some((id, point)) => {
+                    windows.mouse_capture = Some(id);
+                    let activate = windows.active_window != Some(id);
+                    InputRoute::Window { id, command: PlayerVMCommand::MouseDown(point), activate }
+                }
+                None if modal_open => InputRoute::Blocked,
+                None if windows.active_window.is_some() => InputRoute::ActivateStage,
+                None => InputRoute::Stage,
+            },
+            PlayerVMCommand::MouseUp((x, y)) => match windows.mouse_capture.take() {
+                Some(id) => match to_window(windows, id, *x, *y) {
+                    Some(point) => InputRoute::Window { id, command: PlayerVMCommand::MouseUp(point), activate: false },
+                    None => InputRoute::Stage,
+                },
+                None if modal_open => InputRoute::Blocked,
+                None => InputRoute::Stage,
+            },
+            PlayerVMCommand::MouseMove((x, y)) => {
+                let target = match windows.mouse_capture {
+                    Some(id) => to_window(windows, id, *x, *y).map(|point| (id, point)),
+                    None => windows.window_at(*x, *y),
+                };
+                match target {
+                    Some((id, point)) => InputRoute::Window { id, command: PlayerVMCommand::MouseMove(point), activate: false },
+                    None => InputRoute::Stage,
+                }
+            }
+            PlayerVMCommand::RightMouseDown((x, y)) => match windows.window_at(*x, *y) {
+                Some((id, point)) => {
+                    let activate = windows.active_window != Some(id);
+                    InputRoute::Window { id, command: PlayerVMCommand::RightMouseDown(point), activate }
+                }
+                None if modal_open => InputRoute::Blocked,
+                None => InputRoute::Stage,
+            },
+            PlayerVMCommand::RightMouseUp((x, y)) => match windows.window_at(*x, *y) {
+                Some((id, point)) => InputRoute::Window { id, command: PlayerVMCommand::RightMouseUp(point), activate: false },
+                None if modal_open => InputRoute::Blocked,
+                None => InputRoute::Stage,
+            },
+            PlayerVMCommand::KeyDown(key, code) | PlayerVMCommand::KeyUp(key, code) => {
+                match windows.active_window.filter(|id| windows.get(*id).is_some_and(|w| w.is_open && w.is_loaded())) {
+                    Some(id) => {
+                        let command = if matches!(command, PlayerVMCommand::KeyDown(..)) {
+                            PlayerVMCommand::KeyDown(key.clone(), *code)
+                        } else {
+                            PlayerVMCommand::KeyUp(key.clone(), *code)
+                        };
+                        InputRoute::Window { id, command, activate: false }
+                    }
+                    None => InputRoute::Stage,
+                }
+            }
+            _ => InputRoute::Stage,
+        }
+    });
+
+    match route {
+        InputRoute::Stage => None,
+        InputRoute::Blocked => Some(Ok(DatumRef::Void)),
+        InputRoute::ActivateStage => {
+            activate_window(None).await;
+            None
+        }
+        InputRoute::Window { id, command, activate } => {
+            if activate {
+                activate_window(Some(id)).await;
+            }
+            if reserve_player_mut(|player| enter_window(player, id, None)).is_err() {
+                return None;
+            }
+            let result = Box::pin(run_player_command(command)).await;
+            reserve_player_mut(|player| {
+                leave_context(player);
+                // The window saw the pointer in its own coordinates.
+                if let Some(stage_loc) = stage_mouse_loc {
+                    player.mouse_loc = stage_loc;
+                }
+            });
+            Some(result)
+        }
+    }
+}
@@ -0,0 +1,532 @@
+//! Buddy API Xtra: the subset of system functions projector-era movies use.
+//!
+//! Buddy API adds global `ba*` handlers. File and folder functions work on
+//! the FileIO Xtra's `virtual_fs`, so files written by either Xtra are seen
+//! by both; folders exist when a file lives under them or they were made
+//! with `baCreateFolder`. Paths compare case-insensitively with `\` and `/`
+//! interchangeable, as on Windows. System information comes from
+//! [`BuddyApiConfig`]. Functions that would touch the host (running
+//! programs, registry writes, display changes) are never performed: they
+//! are logged and report success or failure as configured.
+
+use log::{debug, warn};
+use serde::Deserialize;
+
+use crate::{
+    director::lingo::datum::{Datum, DatumType},
+    player::{reserve_player_mut, DatumRef, DirPlayer, ScriptError},
+};
+
+use super::fileio::borrow_fileio_manager_mut;
+
+const GLOBAL_HANDLERS: &[&str] = &[
+    "basysfolder",
+    "bafileexists",
+    "bafolderexists",
+    "bacreatefolder",
+    "badeletefolder",
+    "badeletefile",
+    "bacopyfile",
+    "bacopyfileprogress",
+    "barenamefile",
+    "bafilesize",
+    "bafilelist",
+    "bafolderlist",
+    "bareadini",
+    "bawriteini",
+    "bareadregstring",
+    "bareadregnumber",
+    "bascreeninfo",
+    "baenvironment",
+    "baversion",
+    "bausername",
+    "bacomputername",
+    "badiskinfo",
+    "bashortfilename",
+    "balongfilename",
+    "bafindapp",
+    "barunprogram",
+    "bashell",
+    "baopenfile",
+    "baprintfile",
+    "bawriteregstring",
+    "bawriteregnumber",
+    "badeletereg",
+    "basetdisplay",
+    "baexitwindows",
+];
+
+/// Emulated machine reported to the movie, set from JSON with
+/// [`BuddyApiConfig::from_json`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BuddyApiConfig {
+    pub user_name: String,
+    pub computer_name: String,
+    /// `baVersion("os")`, e.g. `"WinXP"`.
+    pub os_version: String,
+    /// `baVersion("windows")`, e.g. `"5.1"`.
+    pub windows_version: String,
+    pub windows_folder: String,
+    pub screen_width: i32,
+    pub screen_height: i32,
+    pub screen_depth: i32,
+    /// Free space reported by `baDiskInfo`, in kilobytes.
+    pub disk_free_kb: i32,
+    /// Whether blocked operations report success to the movie.
+    pub blocked_operations_succeed: bool,
+}
+
+impl Default for BuddyApiConfig {
+    fn default() -> Self {
+        BuddyApiConfig {
+            user_name: "Player".to_string(),
+            computer_name: "DIRPLAYER".to_string(),
+            os_version: "WinXP".to_string(),
+            windows_version: "5.1".to_string(),
+            windows_folder: "C:\\WINDOWS\\".to_string(),
+            screen_width: 1024,
+            screen_height: 768,
+            screen_depth: 32,
+            disk_free_kb: 1024 * 1024,
+            blocked_operations_succeed: true,
+        }
+    }
+}
+
+impl BuddyApiConfig {
+    pub fn from_json(json: &str) -> Result<BuddyApiConfig, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid Buddy API config: {}", e))
+    }
+
+    /// Path of `baSysFolder(name)`, with a trailing backslash.
+    fn sys_folder(&self, name: &str) -> String {
+        let windows = with_trailing_separator(&self.windows_folder);
+        let profile = format!("C:\\Documents and Settings\\{}\\", self.user_name);
+        match name.to_lowercase().as_str() {
+            "windows" => windows,
+            "system" => format!("{}SYSTEM32\\", windows),
+            "fonts" => format!("{}Fonts\\", windows),
+            "temp" => format!("{}TEMP\\", windows),
+            "desktop" => format!("{}Desktop\\", profile),
+            "personal" | "mydocuments" => format!("{}My Documents\\", profile),
+            "appdata" => format!("{}Application Data\\", profile),
+            "localappdata" => format!("{}Local Settings\\Application Data\\", profile),
+            "favorites" => format!("{}Favorites\\", profile),
+            "startmenu" => format!("{}Start Menu\\", profile),
+            "programs" => format!("{}Start Menu\\Programs\\", profile),
+            "startup" => format!("{}Start Menu\\Programs\\Startup\\", profile),
+            "progfiles" | "programfiles" => "C:\\Program Files\\".to_string(),
+            "commonfiles" => "C:\\Program Files\\Common Files\\".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn environment(&self, variable: &str) -> String {
+        let windows = self.windows_folder.trim_end_matches(['\\', '/']).to_string();
+        match variable.to_uppercase().as_str() {
+            "USERNAME" => self.user_name.clone(),
+            "COMPUTERNAME" => self.computer_name.clone(),
+            "OS" => "Windows_NT".to_string(),
+            "WINDIR" | "SYSTEMROOT" => windows,
+            "TEMP" | "TMP" => self.sys_folder("temp").trim_end_matches('\\').to_string(),
+            "USERPROFILE" => format!("C:\\Documents and Settings\\{}", self.user_name),
+            "HOMEDRIVE" | "SYSTEMDRIVE" => "C:".to_string(),
+            "PROGRAMFILES" => "C:\\Program Files".to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BuddyApiXtraManager {
+    pub config: BuddyApiConfig,
+    /// Folders made with `baCreateFolder`, with `/` separators.
+    created_folders: Vec<String>,
+    pub instance_counter: u32,
+}
+
+fn with_trailing_separator(path: &str) -> String {
+    if path.ends_with(['\\', '/']) {
+        path.to_string()
+    } else {
+        format!("{}\\", path)
+    }
+}
+
+/// Use `/` as the only separator in `path`, without a trailing one.
+fn unify_separators(path: &str) -> String {
+    path.replace('\\', "/").trim_end_matches('/').to_string()
+}
+
+/// [`unify_separators`] and lowercase `path`. Only ASCII is folded so byte
+/// offsets into the normalized path are valid in the original.
+fn normalize_path(path: &str) -> String {
+    unify_separators(path).to_ascii_lowercase()
+}
+
+/// The last component of `path`.
+fn file_name(path: &str) -> &str {
+    path.trim_end_matches(['\\', '/']).rsplit(['\\', '/']).next().unwrap_or(path)
+}
+
+/// Match `name` against a DOS wildcard pattern such as `*.txt` or `save??.dat`.
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| wildcard_match(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && wildcard_match(rest, &name[1..]),
+        Some((c, rest)) => name.first().is_some_and(|n| n.eq_ignore_ascii_case(c)) && wildcard_match(rest, &name[1..]),
+    }
+}
+
+/// The `virtual_fs` key for `path`, which may differ from it in case or
+/// separators.
+fn find_file(path: &str) -> Option<String> {
+    let key = normalize_path(path);
+    borrow_fileio_manager_mut(|fileio| {
+        if fileio.virtual_fs.contains_key(path) {
+            return Some(path.to_string());
+        }
+        fileio.virtual_fs.keys().find(|name| normalize_path(name) == key).cloned()
+    })
+}
+
+fn read_file(path: &str) -> Option<Vec<u8>> {
+    let key = find_file(path)?;
+    borrow_fileio_manager_mut(|fileio| fileio.virtual_fs.get(&key).cloned())
+}
+
+fn write_file(path: &str, data: Vec<u8>) {
+    let key = find_file(path).unwrap_or_else(|| path.to_string());
+    borrow_fileio_manager_mut(|fileio| fileio.write_file(&key, data));
+}
+
+/// Find `key` in `section` of INI text, both case-insensitively.
+fn ini_value(text: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            in_section = name.trim().eq_ignore_ascii_case(section);
+        } else if in_section
+            && let Some((name, value)) = line.split_once('=')
+            && name.trim().eq_ignore_ascii_case(key)
+        {
+            return Some(value.trim().to_string());
+        }
+    }
+    None
+}
+
+/// Set `key` in `section` of INI text, adding the section or key as needed.
+fn set_ini_value(text: &str, section: &str, key: &str, value: &str) -> String {
+    let entry = format!("{}={}", key, value);
+    let mut lines: Vec<String> = text.lines().map(|line| line.to_string()).collect();
+    let section_start = lines.iter().position(|line| {
+        let line = line.trim();
+        line.strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+            .is_some_and(|name| name.trim().eq_ignore_ascii_case(section))
+    });
+    match section_start {
+        Some(start) => {
+            let end = lines[start + 1..]
+                .iter()
+                .position(|line| line.trim().starts_with('['))
+                .map_or(lines.len(), |offset| start + 1 + offset);
+            let existing = (start + 1..end).find(|&i| {
+                lines[i]
+                    .split_once('=')
+                    .is_some_and(|(name, _)| name.trim().eq_ignore_ascii_case(key))
+            });
+            match existing {
+                Some(i) => lines[i] = entry,
+                None => {
+                    // Insert after the section's last non-blank line.
+                    let insert_at = (start + 1..end)
+                        .rev()
+                        .find(|&i| !lines[i].trim().is_empty())
+                        .map_or(start + 1, |i| i + 1);
+                    lines.insert(insert_at, entry);
+                }
+            }
+        }
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{}]", section));
+            lines.push(entry);
+        }
+    }
+    let mut result = lines.join("\r\n");
+    result.push_str("\r\n");
+    result
+}
+
+fn string_arg(player: &DirPlayer, args: &[DatumRef], index: usize) -> Result<String, ScriptError> {
+    match args.get(index) {
+        Some(arg) => match player.get_datum(arg) {
+            Datum::Void => Ok(String::new()),
+            datum => datum.string_value(),
+        },
+        None => Ok(String::new()),
+    }
+}
+
+fn string_args(args: &[DatumRef], count: usize) -> Result<Vec<String>, ScriptError> {
+    reserve_player_mut(|player| (0..count).map(|index| string_arg(player, args, index)).collect())
+}
+
+fn alloc_int(value: i32) -> Result<DatumRef, ScriptError> {
+    reserve_player_mut(|player| Ok(player.alloc_datum(Datum::Int(value))))
+}
+
+fn alloc_string(value: String) -> Result<DatumRef, ScriptError> {
+    reserve_player_mut(|player| Ok(player.alloc_datum(Datum::String(value))))
+}
+
+fn alloc_string_list(values: Vec<String>) -> Result<DatumRef, ScriptError> {
+    reserve_player_mut(|player| {
+        let items = values
+            .into_iter()
+            .map(|value| player.alloc_datum(Datum::String(value)))
+            .collect();
+        Ok(player.alloc_datum(Datum::List(DatumType::List, items, false)))
+    })
+}
+
+impl BuddyApiXtraManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create_instance(&mut self, _args: &[DatumRef]) -> u32 {
+        self.instance_counter += 1;
+        self.instance_counter
+    }
+
+    pub fn has_global_handler(name: &str) -> bool {
+        GLOBAL_HANDLERS.contains(&name.to_lowercase().as_str())
+    }
+
+    fn folder_exists(&self, path: &str) -> bool {
+        let key = normalize_path(path);
+        // Drive roots such as `C:\` always exist.
+        if key.is_empty() || (key.len() == 2 && key.ends_with(':')) {
+            return true;
+        }
+        let prefix = format!("{}/", key);
+        let has_files = borrow_fileio_manager_mut(|fileio| {
+            fileio.virtual_fs.keys().any(|name| normalize_path(name).starts_with(&prefix))
+        });
+        has_files
+            || self.created_folders.iter().any(|folder| {
+                let folder = folder.to_ascii_lowercase();
+                folder == key || folder.starts_with(&prefix)
+            })
+    }
+
+    /// Names of the files (`folders == false`) or folders directly inside
+    /// `folder`, in sorted order.
+    fn list_folder(&self, folder: &str, folders: bool) -> Vec<String> {
+        let prefix = format!("{}/", normalize_path(folder));
+        let mut paths: Vec<String> = borrow_fileio_manager_mut(|fileio| {
+            fileio.virtual_fs.keys().map(|name| unify_separators(name)).collect()
+        });
+        if folders {
+            paths.extend(self.created_folders.iter().map(|folder| format!("{}/", folder)));
+        }
+        let mut names: Vec<String> = paths
+            .iter()
+            .filter(|path| path.to_ascii_lowercase().starts_with(&prefix))
+            .filter_map(|path| {
+                let rest = &path[prefix.len()..];
+                match rest.split_once('/') {
+                    Some((name, _)) if folders => Some(name.to_string()),
+                    None if !folders => Some(rest.to_string()),
+                    _ => None,
+                }
+            })
+            .filter(|name| !name.is_empty())
+            .collect();
+        names.sort_by_key(|name| name.to_lowercase());
+        names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        names
+    }
+
+    /// `baCopyFile(source, dest, overwrite)`: 0 on success, 1 if the source
+    /// doesn't exist, 2 if the destination is invalid and 6 if the
+    /// destination exists and `overwrite` doesn't allow replacing it.
+    fn copy_file(&self, source: &str, dest: &str, overwrite: &str) -> i32 {
+        let Some(data) = read_file(source) else {
+            return 1;
+        };
+        let dest = if self.folder_exists(dest) && find_file(dest).is_none() {
+            format!("{}{}", with_trailing_separator(dest), file_name(source))
+        } else {
+            dest.to_string()
+        };
+        if file_name(&dest).is_empty() {
+            return 2;
+        }
+        let dest_exists = find_file(&dest).is_some();
+        // Virtual files have no dates, so "IfNewer" never replaces.
+        if dest_exists && !overwrite.eq_ignore_ascii_case("always") {
+            return 6;
+        }
+        write_file(&dest, data);
+        0
+    }
+
+    /// Log a blocked operation and return the configured result.
+    fn blocked(&self, handler_name: &str, args: &[String], success: i32, failure: i32) -> i32 {
+        warn!("Buddy API: blocked {}({})", handler_name, args.join(", "));
+        if self.config.blocked_operations_succeed {
+            success
+        } else {
+            failure
+        }
+    }
+
+    fn screen_info(&self, info: &str) -> i32 {
+        let config = &self.config;
+        match info.to_lowercase().as_str() {
+            "width" | "right" => config.screen_width,
+            "height" | "bottom" => config.screen_height,
+            "depth" => config.screen_depth,
+            "pixelsperinch" => 96,
+            _ => 0,
+        }
+    }
+
+    fn disk_info(&self, drive: &str, info: &str) -> Result<DatumRef, ScriptError> {
+        if !drive.to_lowercase().starts_with('c') {
+            return alloc_int(0);
+        }
+        match info.to_lowercase().as_str() {
+            "type" => alloc_string("fixed".to_string()),
+            "size" => alloc_int(self.config.disk_free_kb.saturating_mul(4)),
+            _ => alloc_int(self.config.disk_free_kb),
+        }
+    }
+
+    pub fn call_global_handler(handler_name: &str, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        let manager = unsafe { BUDDYAPI_XTRA_MANAGER_OPT.as_mut().unwrap() };
+        let handler = handler_name.to_lowercase();
+        let s = string_args(args, args.len())?;
+        let arg = |index: usize| s.get(index).map_or("", |arg| arg.as_str());
+        debug!("Buddy API: {}({})", handler_name, s.join(", "));
+
+        match handler.as_str() {
+            "basysfolder" => alloc_string(manager.config.sys_folder(arg(0))),
+            "bafileexists" => alloc_int(find_file(arg(0)).is_some() as i32),
+            "bafolderexists" => alloc_int(manager.folder_exists(arg(0)) as i32),
+            "bacreatefolder" => {
+                if !manager.folder_exists(arg(0)) {
+                    manager.created_folders.push(unify_separators(arg(0)));
+                }
+                alloc_int(1)
+            }
+            "badeletefolder" => {
+                // Like the original, only empty folders can be deleted.
+                let key = normalize_path(arg(0));
+                let empty = manager.list_folder(arg(0), false).is_empty() && manager.list_folder(arg(0), true).is_empty();
+                let count = manager.created_folders.len();
+                if empty {
+                    manager.created_folders.retain(|folder| !folder.eq_ignore_ascii_case(&key));
+                }
+                alloc_int((manager.created_folders.len() < count) as i32)
+            }
+            "badeletefile" => {
+                let removed = find_file(arg(0))
+                    .is_some_and(|key| borrow_fileio_manager_mut(|fileio| fileio.remove_file(&key)));
+                alloc_int(removed as i32)
+            }
+            "bacopyfile" | "bacopyfileprogress" => alloc_int(manager.copy_file(arg(0), arg(1), arg(2))),
+            "barenamefile" => {
+                let renamed = match find_file(arg(0)) {
+                    Some(key) if find_file(arg(1)).is_none() => {
+                        borrow_fileio_manager_mut(|fileio| {
+                            let data = fileio.virtual_fs.get(&key).cloned().unwrap_or_default();
+                            fileio.remove_file(&key);
+                            fileio.write_file(arg(1), data);
+                        });
+                        true
+                    }
+                    _ => false,
+                };
+                alloc_int(renamed as i32)
+            }
+            "bafilesize" => alloc_int(read_file(arg(0)).map_or(-1, |data| data.len() as i32)),
+            "bafilelist" => {
+                // As in DOS, `*.*` also matches names without an extension.
+                let pattern = match arg(1) {
+                    "" | "*.*" => "*",
+                    pattern => pattern,
+                };
+                let pattern: Vec<char> = pattern.chars().collect();
+                let names = manager
+                    .list_folder(arg(0), false)
+                    .into_iter()
+                    .filter(|name| wildcard_match(&pattern, &name.chars().collect::<Vec<_>>()))
+                    .collect();
+                alloc_string_list(names)
+            }
+            "bafolderlist" => alloc_string_list(manager.list_folder(arg(0), true)),
+            "bareadini" => {
+                let text = read_file(arg(3)).map(|data| crate::io::encoding::decode_text_auto(&data));
+                let value = text.and_then(|text| ini_value(&text, arg(0), arg(1)));
+                alloc_string(value.unwrap_or_else(|| arg(2).to_string()))
+            }
+            "bawriteini" => {
+                let text = read_file(arg(3))
+                    .map(|data| crate::io::encoding::decode_text_auto(&data))
+                    .unwrap_or_default();
+                write_file(arg(3), set_ini_value(&text, arg(0), arg(1), arg(2)).into_bytes());
+                alloc_int(1)
+            }
+            // There is no registry, so reads return the default.
+            "bareadregstring" => alloc_string(arg(2).to_string()),
+            "bareadregnumber" => {
+                let default = reserve_player_mut(|player| match args.get(2) {
+                    Some(arg) => player.get_datum(arg).int_value(),
+                    None => Ok(0),
+                })?;
+                alloc_int(default)
+            }
+            "bascreeninfo" => alloc_int(manager.screen_info(arg(0))),
+            "baenvironment" => alloc_string(manager.config.environment(arg(0))),
+            "baversion" => {
+                let version = match arg(0).to_lowercase().as_str() {
+                    "os" => manager.config.os_version.clone(),
+                    "windows" => manager.config.windows_version.clone(),
+                    _ => "3.7".to_string(),
+                };
+                alloc_string(version)
+            }
+            "bausername" => alloc_string(manager.config.user_name.clone()),
+            "bacomputername" => alloc_string(manager.config.computer_name.clone()),
+            "badiskinfo" => manager.disk_info(arg(0), arg(1)),
+            "bashortfilename" | "balongfilename" => alloc_string(arg(0).to_string()),
+            "bafindapp" => alloc_string(String::new()),
+            // Windows reports success from ShellExecute as a value above 32
+            // and "file not found" as 2.
+            "barunprogram" | "bashell" | "baopenfile" | "baprintfile" => {
+                alloc_int(manager.blocked(handler_name, &s, 42, 2))
+            }
+            "bawriteregstring" | "bawriteregnumber" | "badeletereg" => alloc_int(manager.blocked(handler_name, &s, 1, 0)),
+            "basetdisplay" => alloc_int(manager.blocked(handler_name, &s, 0, -1)),
+            "baexitwindows" => alloc_int(manager.blocked(handler_name, &s, 1, 0)),
+            _ => Err(ScriptError::new(format!("No handler {} found for Buddy API xtra", handler_name))),
+        }
+    }
+}
+
+pub static mut BUDDYAPI_XTRA_MANAGER_OPT: Option<BuddyApiXtraManager> = None;
+
+pub fn borrow_buddyapi_manager_mut<T>(callback: impl FnOnce(&mut BuddyApiXtraManager) -> T) -> T {
+    let manager = unsafe { BUDDYAPI_XTRA_MANAGER_OPT.as_mut().unwrap() };
+    callback(manager)
+}
@@ -135,7 +135,19 @@ impl FileIoXtraManager {
         }
     }
 
-    pub fn create_instance(&mut self, _args: &Vec<DatumRef>) -> u32 {
+    /// Store file `name` in the virtual filesystem and persist it in the
+    /// movie's storage.
+    pub fn write_file(&mut self, name: &str, data: Vec<u8>) {
+        reserve_player_mut(|player| player.storage.write_file(name, &data));
+        self.virtual_fs.insert(name.to_string(), data);
+    }
+
+    pub fn remove_file(&mut self, name: &str) -> bool {
+        reserve_player_mut(|player| player.storage.remove_file(name));
+        self.virtual_fs.remove(name).is_some()
+    }
+
+    pub fn create_instance(&mut self, _args: &[DatumRef]) -> u32 {
         self.instance_counter += 1;
         self.instances
             .insert(self.instance_counter, FileIoXtraInstance::new());
@@ -149,7 +161,7 @@ impl FileIoXtraManager {
     pub async fn call_instance_async_handler(
         handler_name: &str,
         instance_id: u32,
-        args: &Vec<DatumRef>,
+        args: &[DatumRef],
     ) -> Result<DatumRef, ScriptError> {
         match handler_name.to_lowercase().as_str() {
             "openfile" => {
@@ -266,7 +278,7 @@ impl FileIoXtraManager {
     pub fn call_instance_handler(
         handler_name: &str,
         instance_id: u32,
-        args: &Vec<DatumRef>,
+        args: &[DatumRef],
     ) -> Result<DatumRef, ScriptError> {
         let manager = unsafe { FILEIO_XTRA_MANAGER_OPT.as_mut().unwrap() };
         let handler = handler_name.to_lowercase();
@@ -296,10 +308,8 @@ impl FileIoXtraManager {
                 let instance = manager.instances.get_mut(&instance_id).unwrap();
                 if instance.is_open && !instance.file_name.is_empty() {
                     // Persist to virtual filesystem
-                    manager.virtual_fs.insert(
-                        instance.file_name.clone(),
-                        instance.data.clone(),
-                    );
+                    let (file_name, data) = (instance.file_name.clone(), instance.data.clone());
+                    manager.write_file(&file_name, data);
                     // Re-borrow instance after virtual_fs insert
                     let instance = manager.instances.get_mut(&instance_id).unwrap();
                     instance.is_open = false;
@@ -309,7 +319,8 @@ impl FileIoXtraManager {
             "delete" => {
                 let instance = manager.instances.get_mut(&instance_id).unwrap();
                 if !instance.file_name.is_empty() {
-                    manager.virtual_fs.remove(&instance.file_name.clone());
+                    let file_name = instance.file_name.clone();
+                    manager.remove_file(&file_name);
                 }
                 Ok(DatumRef::Void)
             }
@@ -1,39 +1,386 @@
+//! Registry of the Xtras implemented by the player.
+//!
+//! Each Xtra implements [`Xtra`], declaring the names Lingo may use for it
+//! and its global, static, instance and async instance handlers. `xtra("name")`,
+//! `new(xtra "name")` and handler calls on Xtra instances are resolved
+//! through [`find_xtra`]. [`xtra_report`] compares the registry with the
+//! movie's `XTRl` list so movies that depend on unimplemented Xtras can be
+//! triaged at load instead of failing mid-game.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::Serialize;
+
 use crate::{
-    director::lingo::datum::XtraInstanceId,
+    director::{chunks::xtra_list::XtraListChunk, lingo::datum::XtraInstanceId},
     player::{DatumRef, ScriptError},
 };
 
+use super::buddyapi::{borrow_buddyapi_manager_mut, BuddyApiXtraManager};
 use super::fileio::{borrow_fileio_manager_mut, FileIoXtraManager};
 use super::multiuser::{borrow_multiuser_manager_mut, MultiuserXtraManager};
+use super::pregex::{borrow_pregex_manager_mut, PRegExXtraManager};
 use super::xmlparser::{borrow_xmlparser_manager_mut, XmlParserXtraManager};
 
+pub type XtraHandlerFuture<'a> = Pin<Box<dyn Future<Output = Result<DatumRef, ScriptError>> + 'a>>;
+
+pub trait Xtra: Sync {
+    /// Name reported by `the xtraList`.
+    fn name(&self) -> &'static str;
+
+    /// Normalized names (see [`normalize_xtra_name`]) this Xtra answers to,
+    /// covering both its Lingo name and its file name.
+    fn aliases(&self) -> &'static [&'static str];
+
+    fn create_instance(&self, args: &[DatumRef]) -> XtraInstanceId;
+
+    /// Handlers the Xtra adds to the global namespace, e.g. `PRegEx_Search`.
+    fn has_global_handler(&self, _handler_name: &str) -> bool {
+        false
+    }
+
+    fn call_global_handler(&self, handler_name: &str, _args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        Err(ScriptError::new(format!("No handler {} found", handler_name)))
+    }
+
+    /// Handlers callable on the Xtra itself, e.g. `interface(xtra "FileIO")`.
+    fn has_static_handler(&self, _handler_name: &str) -> bool {
+        false
+    }
+
+    fn call_static_handler(&self, handler_name: &str, _args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        Err(ScriptError::new(format!(
+            "No handler {} found for xtra {}",
+            handler_name,
+            self.name()
+        )))
+    }
+
+    fn call_instance_handler(
+        &self,
+        handler_name: &str,
+        instance_id: XtraInstanceId,
+        args: &[DatumRef],
+    ) -> Result<DatumRef, ScriptError>;
+
+    fn has_instance_async_handler(&self, _handler_name: &str) -> bool {
+        false
+    }
+
+    fn call_instance_async_handler<'a>(
+        &'a self,
+        handler_name: &'a str,
+        instance_id: XtraInstanceId,
+        args: &'a [DatumRef],
+    ) -> XtraHandlerFuture<'a>;
+}
+
+struct MultiuserXtra;
+
+impl Xtra for MultiuserXtra {
+    fn name(&self) -> &'static str {
+        "Multiusr"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["multiuser", "multiusr"]
+    }
+
+    fn create_instance(&self, args: &[DatumRef]) -> XtraInstanceId {
+        borrow_multiuser_manager_mut(|x| x.create_instance(args))
+    }
+
+    fn call_instance_handler(
+        &self,
+        handler_name: &str,
+        instance_id: XtraInstanceId,
+        args: &[DatumRef],
+    ) -> Result<DatumRef, ScriptError> {
+        MultiuserXtraManager::call_instance_handler(handler_name, instance_id, args)
+    }
+
+    fn has_instance_async_handler(&self, handler_name: &str) -> bool {
+        MultiuserXtraManager::has_instance_async_handler(handler_name)
+    }
+
+    fn call_instance_async_handler<'a>(
+        &'a self,
+        handler_name: &'a str,
+        instance_id: XtraInstanceId,
+        args: &'a [DatumRef],
+    ) -> XtraHandlerFuture<'a> {
+        Box::pin(MultiuserXtraManager::call_instance_async_handler(handler_name, instance_id, args))
+    }
+}
+
+struct XmlParserXtra;
+
+impl Xtra for XmlParserXtra {
+    fn name(&self) -> &'static str {
+        "XmlParser"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["xmlparser"]
+    }
+
+    fn create_instance(&self, args: &[DatumRef]) -> XtraInstanceId {
+        borrow_xmlparser_manager_mut(|x| x.create_instance(args))
+    }
+
+    fn call_instance_handler(
+        &self,
+        handler_name: &str,
+        instance_id: XtraInstanceId,
+        args: &[DatumRef],
+    ) -> Result<DatumRef, ScriptError> {
+        XmlParserXtraManager::call_instance_handler(handler_name, instance_id, args)
+    }
+
+    fn has_instance_async_handler(&self, handler_name: &str) -> bool {
+        XmlParserXtraManager::has_instance_async_handler(handler_name)
+    }
+
+    fn call_instance_async_handler<'a>(
+        &'a self,
+        handler_name: &'a str,
+        instance_id: XtraInstanceId,
+        args: &'a [DatumRef],
+    ) -> XtraHandlerFuture<'a> {
+        Box::pin(XmlParserXtraManager::call_instance_async_handler(handler_name, instance_id, args))
+    }
+}
+
+struct FileIoXtra;
+
+impl Xtra for FileIoXtra {
+    fn name(&self) -> &'static str {
+        "FileIO"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["fileio"]
+    }
+
+    fn create_instance(&self, args: &[DatumRef]) -> XtraInstanceId {
+        borrow_fileio_manager_mut(|x| x.create_instance(args))
+    }
+
+    fn has_static_handler(&self, handler_name: &str) -> bool {
+        matches!(handler_name.to_lowercase().as_str(), "interface" | "getosdirectory")
+    }
+
+    fn call_static_handler(&self, handler_name: &str, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        // Neither handler touches instance state.
+        FileIoXtraManager::call_instance_handler(handler_name, 0, args)
+    }
+
+    fn call_instance_handler(
+        &self,
+        handler_name: &str,
+        instance_id: XtraInstanceId,
+        args: &[DatumRef],
+    ) -> Result<DatumRef, ScriptError> {
+        FileIoXtraManager::call_instance_handler(handler_name, instance_id, args)
+    }
+
+    fn has_instance_async_handler(&self, handler_name: &str) -> bool {
+        FileIoXtraManager::has_instance_async_handler(handler_name)
+    }
+
+    fn call_instance_async_handler<'a>(
+        &'a self,
+        handler_name: &'a str,
+        instance_id: XtraInstanceId,
+        args: &'a [DatumRef],
+    ) -> XtraHandlerFuture<'a> {
+        Box::pin(FileIoXtraManager::call_instance_async_handler(handler_name, instance_id, args))
+    }
+}
+
+struct PRegExXtra;
+
+impl Xtra for PRegExXtra {
+    fn name(&self) -> &'static str {
+        "PRegEx"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["pregex"]
+    }
+
+    fn create_instance(&self, args: &[DatumRef]) -> XtraInstanceId {
+        borrow_pregex_manager_mut(|x| x.create_instance(args))
+    }
+
+    fn has_global_handler(&self, handler_name: &str) -> bool {
+        PRegExXtraManager::has_global_handler(handler_name)
+    }
+
+    fn call_global_handler(&self, handler_name: &str, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        PRegExXtraManager::call_global_handler(handler_name, args)
+    }
+
+    fn call_instance_handler(
+        &self,
+        handler_name: &str,
+        _instance_id: XtraInstanceId,
+        args: &[DatumRef],
+    ) -> Result<DatumRef, ScriptError> {
+        // PRegEx has no instance state; instances expose the global handlers.
+        PRegExXtraManager::call_global_handler(handler_name, args)
+    }
+
+    fn call_instance_async_handler<'a>(
+        &'a self,
+        handler_name: &'a str,
+        instance_id: XtraInstanceId,
+        _args: &'a [DatumRef],
+    ) -> XtraHandlerFuture<'a> {
+        Box::pin(async move {
+            Err(ScriptError::new(format!(
+                "No async handler {} found for PRegEx xtra instance #{}",
+                handler_name, instance_id
+            )))
+        })
+    }
+}
+
+struct BuddyApiXtra;
+
+impl Xtra for BuddyApiXtra {
+    fn name(&self) -> &'static str {
+        "BudAPI"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["budapi", "buddyapi"]
+    }
+
+    fn create_instance(&self, args: &[DatumRef]) -> XtraInstanceId {
+        borrow_buddyapi_manager_mut(|x| x.create_instance(args))
+    }
+
+    fn has_global_handler(&self, handler_name: &str) -> bool {
+        BuddyApiXtraManager::has_global_handler(handler_name)
+    }
+
+    fn call_global_handler(&self, handler_name: &str, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        BuddyApiXtraManager::call_global_handler(handler_name, args)
+    }
+
+    fn call_instance_handler(
+        &self,
+        handler_name: &str,
+        _instance_id: XtraInstanceId,
+        args: &[DatumRef],
+    ) -> Result<DatumRef, ScriptError> {
+        BuddyApiXtraManager::call_global_handler(handler_name, args)
+    }
+
+    fn call_instance_async_handler<'a>(
+        &'a self,
+        handler_name: &'a str,
+        instance_id: XtraInstanceId,
+        _args: &'a [DatumRef],
+    ) -> XtraHandlerFuture<'a> {
+        Box::pin(async move {
+            Err(ScriptError::new(format!(
+                "No async handler {} found for Buddy API xtra instance #{}",
+                handler_name, instance_id
+            )))
+        })
+    }
+}
+
+static XTRAS: &[&dyn Xtra] = &[&MultiuserXtra, &XmlParserXtra, &FileIoXtra, &PRegExXtra, &BuddyApiXtra];
+
+/// Asset, transport and sound Xtras whose functionality the player provides
+/// natively, so movies listing them in `XTRl` are not missing anything.
+const NATIVE_XTRAS: &[&str] = &[
+    "netlingo",
+    "ineturl",
+    "netfile",
+    "textxtra",
+    "textasst",
+    "textasset",
+    "fontxtra",
+    "fontasst",
+    "fontasset",
+    "flashasset",
+    "flashasst",
+    "shockwave3dasset",
+    "3dasset",
+    "havok",
+    "havokphysics",
+    "soundcontrol",
+    "swadecompression",
+    "swadcmpr",
+    "mixservices",
+    "soundimportexport",
+    "directsound",
+    "macromix",
+];
+
+pub fn registered_xtras() -> &'static [&'static dyn Xtra] {
+    XTRAS
+}
+
+/// Lowercase `name` and drop its file extension, spaces and punctuation, so
+/// `"Multiusr.x32"`, `"Text Asset"` and `"XmlParser"` compare by identity.
+pub fn normalize_xtra_name(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    let name = [".x32", ".xtr", ".cpt", ".dll"]
+        .iter()
+        .find_map(|extension| name.strip_suffix(extension))
+        .unwrap_or(&name);
+    name.chars().filter(|c| c.is_ascii_alphanumeric()).collect()
+}
+
+pub fn find_xtra(name: &str) -> Option<&'static dyn Xtra> {
+    let key = normalize_xtra_name(name);
+    XTRAS.iter().copied().find(|xtra| xtra.aliases().contains(&key.as_str()))
+}
+
+fn find_xtra_or_err(name: &str) -> Result<&'static dyn Xtra, ScriptError> {
+    find_xtra(name).ok_or_else(|| ScriptError::new(format!("Xtra {} not found", name)))
+}
+
 pub fn is_xtra_registered(name: &str) -> bool {
-    let name_lower = name.to_lowercase();
-    return name == "Multiuser" || name_lower == "xmlparser" || name_lower == "fileio";
+    find_xtra(name).is_some()
 }
 
 pub fn get_registered_xtra_names() -> Vec<&'static str> {
-    vec!["Multiusr", "XmlParser", "FileIO"]
+    XTRAS.iter().map(|xtra| xtra.name()).collect()
+}
+
+/// The registered Xtra that adds global handler `handler_name`, if any.
+pub fn find_global_handler_xtra(handler_name: &str) -> Option<&'static dyn Xtra> {
+    XTRAS.iter().copied().find(|xtra| xtra.has_global_handler(handler_name))
+}
+
+pub fn has_xtra_static_handler(xtra_name: &str, handler_name: &str) -> bool {
+    find_xtra(xtra_name).is_some_and(|xtra| xtra.has_static_handler(handler_name))
+}
+
+pub fn call_xtra_static_handler(
+    xtra_name: &str,
+    handler_name: &str,
+    args: &[DatumRef],
+) -> Result<DatumRef, ScriptError> {
+    find_xtra_or_err(xtra_name)?.call_static_handler(handler_name, args)
 }
 
 pub fn call_xtra_instance_handler(
     xtra_name: &str,
     instance_id: XtraInstanceId,
     handler_name: &str,
-    args: &Vec<DatumRef>,
+    args: &[DatumRef],
 ) -> Result<DatumRef, ScriptError> {
-    let xtra_name_lower = xtra_name.to_lowercase();
-    match xtra_name_lower.as_str() {
-        "multiuser" => {
-            return MultiuserXtraManager::call_instance_handler(handler_name, instance_id, args)
-        }
-        "xmlparser" => {
-            return XmlParserXtraManager::call_instance_handler(handler_name, instance_id, args)
-        }
-        "fileio" => {
-            return FileIoXtraManager::call_instance_handler(handler_name, instance_id, args)
-        }
-        _ => Err(ScriptError::new(format!(
+    match find_xtra(xtra_name) {
+        Some(xtra) => xtra.call_instance_handler(handler_name, instance_id, args),
+        None => Err(ScriptError::new(format!(
             "No handler {} found for xtra {} instance #{}",
             handler_name, xtra_name, instance_id
         ))),
@@ -44,35 +391,11 @@ pub async fn call_xtra_instance_async_handler(
     xtra_name: &str,
     instance_id: XtraInstanceId,
     handler_name: &str,
-    args: &Vec<DatumRef>,
+    args: &[DatumRef],
 ) -> Result<DatumRef, ScriptError> {
-    let xtra_name_lower = xtra_name.to_lowercase();
-    match xtra_name_lower.as_str() {
-        "multiuser" => {
-            return MultiuserXtraManager::call_instance_async_handler(
-                handler_name,
-                instance_id,
-                args,
-            )
-            .await
-        }
-        "xmlparser" => {
-            return XmlParserXtraManager::call_instance_async_handler(
-                handler_name,
-                instance_id,
-                args,
-            )
-            .await
-        }
-        "fileio" => {
-            return FileIoXtraManager::call_instance_async_handler(
-                handler_name,
-                instance_id,
-                args,
-            )
-            .await
-        }
-        _ => Err(ScriptError::new(format!(
+    match find_xtra(xtra_name) {
+        Some(xtra) => xtra.call_instance_async_handler(handler_name, instance_id, args).await,
+        None => Err(ScriptError::new(format!(
             "No async handler {} found for xtra {} instance #{}",
             handler_name, xtra_name, instance_id
         ))),
@@ -84,24 +407,72 @@ pub fn has_xtra_instance_async_handler(
     handler_name: &str,
     _instance_id: XtraInstanceId,
 ) -> bool {
-    let xtra_name_lower = xtra_name.to_lowercase();
-    match xtra_name_lower.as_str() {
-        "multiuser" => MultiuserXtraManager::has_instance_async_handler(handler_name),
-        "xmlparser" => XmlParserXtraManager::has_instance_async_handler(handler_name),
-        "fileio" => FileIoXtraManager::has_instance_async_handler(handler_name),
-        _ => false,
+    find_xtra(xtra_name).is_some_and(|xtra| xtra.has_instance_async_handler(handler_name))
+}
+
+pub fn create_xtra_instance(xtra_name: &str, args: &[DatumRef]) -> Result<XtraInstanceId, ScriptError> {
+    Ok(find_xtra_or_err(xtra_name)?.create_instance(args))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum XtraStatus {
+    /// Implemented in the registry with its Lingo interface.
+    Implemented,
+    /// Provided natively by the player (assets, networking, sound).
+    Native,
+    /// Required by the movie but not available.
+    Missing,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XtraReportEntry {
+    pub name: String,
+    pub status: XtraStatus,
+    pub urls: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XtraReport {
+    /// Every Xtra in the movie's `XTRl` chunk.
+    pub required: Vec<XtraReportEntry>,
+    /// Names of the required Xtras with [`XtraStatus::Missing`].
+    pub missing: Vec<String>,
+    pub registered: Vec<&'static str>,
+}
+
+pub fn xtra_status(name: &str) -> XtraStatus {
+    if find_xtra(name).is_some() {
+        XtraStatus::Implemented
+    } else if NATIVE_XTRAS.contains(&normalize_xtra_name(name).as_str()) {
+        XtraStatus::Native
+    } else {
+        XtraStatus::Missing
     }
 }
 
-pub fn create_xtra_instance(
-    xtra_name: &str,
-    args: &Vec<DatumRef>,
-) -> Result<XtraInstanceId, ScriptError> {
-    let xtra_name_lower = xtra_name.to_lowercase();
-    match xtra_name_lower.as_str() {
-        "multiuser" => Ok(borrow_multiuser_manager_mut(|x| x.create_instance(args))),
-        "xmlparser" => Ok(borrow_xmlparser_manager_mut(|x| x.create_instance(args))),
-        "fileio" => Ok(borrow_fileio_manager_mut(|x| x.create_instance(args))),
-        _ => Err(ScriptError::new(format!("Xtra {} not found", xtra_name))),
+/// Classify the Xtras listed in a movie's `XTRl` chunk.
+pub fn xtra_report(xtra_list: Option<&XtraListChunk>) -> XtraReport {
+    let required: Vec<XtraReportEntry> = xtra_list
+        .map(|list| list.entries.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|entry| XtraReportEntry {
+            name: entry.name.clone(),
+            status: xtra_status(&entry.name),
+            urls: entry.urls.clone(),
+        })
+        .collect();
+    let missing = required
+        .iter()
+        .filter(|entry| entry.status == XtraStatus::Missing)
+        .map(|entry| entry.name.clone())
+        .collect();
+    XtraReport {
+        required,
+        missing,
+        registered: get_registered_xtra_names(),
     }
 }
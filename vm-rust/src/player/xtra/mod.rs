@@ -1,4 +1,6 @@
+pub mod buddyapi;
 pub mod fileio;
 pub mod manager;
 pub mod multiuser;
+pub mod pregex;
 pub mod xmlparser;
@@ -2,8 +2,9 @@ pub mod blowfish;
 pub mod writer;
 pub mod reader;
 pub mod types;
+pub mod server;
 
-use std::collections::VecDeque;
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 use async_std::{channel::Sender, task::spawn_local};
 use binary_reader::BinaryReader;
 use fxhash::FxHashMap;
@@ -14,16 +15,24 @@ use wasm_bindgen::{closure::Closure, JsCast};
 use web_sys::{CloseEvent, Event, MessageEvent, WebSocket};
 
 // Use console::warn_1 directly for debugging since log level is set to Error
+#[cfg(target_arch = "wasm32")]
 macro_rules! multiuser_log {
     ($($arg:tt)*) => {
         web_sys::console::warn_1(&format!($($arg)*).into())
     };
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+macro_rules! multiuser_log {
+    ($($arg:tt)*) => {
+        log::warn!($($arg)*)
+    };
+}
+
 use crate::{
     director::{lingo::datum::{Datum, DatumType}, static_datum::{StaticDatum, static_datum_to_runtime}},
     player::{
-        DatumRef, ScriptError, events::player_dispatch_callback_event, reserve_player_mut, reserve_player_ref, xtra::multiuser::{blowfish::{DEFAULT_CIPHER_KEY, MUSBlowfish}}
+        DatumRef, ScriptError, events::player_dispatch_callback_event, reserve_player_mut, reserve_player_ref, xtra::multiuser::{blowfish::{DEFAULT_CIPHER_KEY, MUSBlowfish}, server::*}
     },
 };
 
@@ -49,6 +58,45 @@ pub struct MultiuserXtraInstance {
     pub connection_mode: MultiuserConnectionMode,
     pub sender_id: String,
     pub recv_buffer: Vec<u8>,
+    pub local_connection: Option<MultiuserLocalConnection>,
+}
+
+/// Connection to an in-process `MusServer` instead of a WebSocket.
+pub struct MultiuserLocalConnection {
+    pub server: Rc<RefCell<MusServer>>,
+    pub connection_id: MusConnectionId,
+}
+
+/// Expands the key passed to connectToNetServer the way the Xtra does.
+pub fn resolve_encryption_key(encryption_key: &str) -> Vec<u8> {
+    if encryption_key.is_empty() {
+        DEFAULT_CIPHER_KEY.to_vec()
+    } else if encryption_key.len() < 20 {
+        // DEFAULT_CIPHER_KEY + &encryption_key
+        let mut key = DEFAULT_CIPHER_KEY.to_vec();
+        key.extend_from_slice(encryption_key.as_bytes());
+        key
+    } else {
+        encryption_key.as_bytes().to_vec()
+    }
+}
+
+/// Encrypted Logon frame sent once the connection is open.
+pub fn logon_message_bytes(movie_id: &str, username: &str, password: &str, encryption_key: &str) -> Vec<u8> {
+    let message = MultiuserMessage {
+        error_code: 0,
+        recipients: vec!["System".to_string()],
+        sender_id: username.to_string(),
+        subject: "Logon".to_string(),
+        content: StaticDatum::List(vec![
+            StaticDatum::String(movie_id.to_string()),
+            StaticDatum::String(username.to_string()),
+            StaticDatum::String(password.to_string()),
+        ]),
+        time_stamp: 0,
+    };
+    let mut cipher = MUSBlowfish::new(&resolve_encryption_key(encryption_key));
+    message.to_bytes(Some(&mut cipher))
 }
 
 impl MultiuserXtraInstance {
@@ -107,10 +155,17 @@ impl MultiuserXtraInstance {
 pub struct MultiuserXtraManager {
     pub instances: FxHashMap<u32, MultiuserXtraInstance>,
     pub instance_counter: u32,
+    /// In-process servers keyed by "host:port", used by connectToNetServer
+    /// instead of opening a WebSocket.
+    pub local_servers: FxHashMap<String, Rc<RefCell<MusServer>>>,
+}
+
+fn local_server_key(host: &str, port: i32) -> String {
+    format!("{}:{}", host.to_lowercase(), port)
 }
 
 impl MultiuserXtraManager {
-    pub fn create_instance(&mut self, _: &Vec<DatumRef>) -> u32 {
+    pub fn create_instance(&mut self, _: &[DatumRef]) -> u32 {
         self.instance_counter += 1;
         self.instances.insert(
             self.instance_counter,
@@ -121,6 +176,7 @@ impl MultiuserXtraManager {
                 connection_mode: MultiuserConnectionMode::Binary,
                 sender_id: String::new(),
                 recv_buffer: Vec::new(),
+                local_connection: None,
             },
         );
         self.instance_counter
@@ -133,7 +189,7 @@ impl MultiuserXtraManager {
     pub async fn call_instance_async_handler(
         handler_name: &str,
         instance_id: u32,
-        _args: &Vec<DatumRef>,
+        _args: &[DatumRef],
     ) -> Result<DatumRef, ScriptError> {
         Err(ScriptError::new(format!(
             "No async handler {} found for Multiuser xtra instance #{}",
@@ -144,7 +200,7 @@ impl MultiuserXtraManager {
     pub fn call_instance_handler(
         handler_name: &str,
         instance_id: u32,
-        args: &Vec<DatumRef>,
+        args: &[DatumRef],
     ) -> Result<DatumRef, ScriptError> {
         match handler_name.to_lowercase().as_str() {
             "setnetbufferlimits" => Ok(DatumRef::Void),
@@ -224,6 +280,30 @@ impl MultiuserXtraManager {
                     }
                 })?;
 
+                let local_server = multiusr_manager.local_servers.get(&local_server_key(&host, port)).cloned();
+                if let Some(server) = local_server {
+                    let Some(MultiuserConnectionMode::Binary) = MultiuserConnectionMode::from_i32(mode) else {
+                        return Err(ScriptError::new(format!("Local Multiuser server only supports binary mode, got {}", mode)));
+                    };
+                    instance.sender_id = username.clone();
+                    instance.connection_mode = MultiuserConnectionMode::Binary;
+                    instance.socket_tx = None;
+                    let connection_id = server.borrow_mut().connect();
+                    instance.local_connection = Some(MultiuserLocalConnection { server: server.clone(), connection_id });
+                    instance.dispatch_message(MultiuserMessage {
+                        error_code: 0,
+                        recipients: vec!["*".to_string()],
+                        sender_id: "System".to_string(),
+                        subject: "ConnectToNetServer".to_string(),
+                        content: StaticDatum::Void,
+                        time_stamp: server.borrow().time_ms(),
+                    });
+                    let bytes = logon_message_bytes(&movie_id, &username, &password, &encryption_key);
+                    server.borrow_mut().receive(connection_id, &bytes);
+                    multiusr_manager.deliver_local_output();
+                    return Ok(DatumRef::Void);
+                }
+
                 let window_secure = web_sys::window()
                     .and_then(|w| w.location().protocol().ok())
                     .map_or(false, |p| p == "https:");
@@ -415,31 +495,8 @@ impl MultiuserXtraManager {
                         time_stamp: 0, // TODO timestamp
                     });
                     if let MultiuserConnectionMode::Binary = instance.connection_mode {
-                        let message = MultiuserMessage {
-                            error_code: 0,
-                            recipients: vec!["System".to_string()],
-                            sender_id: username.to_string(),
-                            subject: "Logon".to_string(),
-                            content: StaticDatum::List(vec![
-                                StaticDatum::String(movie_id.clone()),
-                                StaticDatum::String(username.clone()),
-                                StaticDatum::String(password.clone()),
-                            ]),
-                            time_stamp: 0,
-                        };
-                        let resolved_encryption_key: Vec<u8> = if encryption_key.is_empty() {
-                            DEFAULT_CIPHER_KEY.to_vec()
-                        } else if encryption_key.len() < 20 {
-                            // DEFAULT_CIPHER_KEY + &encryption_key
-                            let mut key = DEFAULT_CIPHER_KEY.to_vec();
-                            key.extend_from_slice(encryption_key.as_bytes());
-                            key
-                        } else {
-                            encryption_key.as_bytes().to_vec()
-                        };
-                        let mut cipher = MUSBlowfish::new(&resolved_encryption_key);
-                        let bytes = message.to_bytes(Some(&mut cipher));
-                        instance.socket_tx.as_ref().unwrap().try_send(bytes.to_vec()).unwrap();
+                        let bytes = logon_message_bytes(&movie_id, &username, &password, &encryption_key);
+                        instance.socket_tx.as_ref().unwrap().try_send(bytes).unwrap();
                     }
                 });
                 socket.set_onmessage(Some(onmessage_callback.as_ref().unchecked_ref()));
@@ -521,9 +578,9 @@ impl MultiuserXtraManager {
             "sendnetmessage" => {
                 let mut multiusr_manager = unsafe { MULTIUSER_XTRA_MANAGER_OPT.as_mut().unwrap() };
                 let instance = multiusr_manager.instances.get_mut(&instance_id).unwrap();
-                reserve_player_mut(|player| {
+                let result = reserve_player_mut(|player| {
                     // multiuser_log!("sendNetMessage: {:?}", msg_string);
-                    if let Some(tx) = &instance.socket_tx {
+                    if instance.socket_tx.is_some() || instance.local_connection.is_some() {
                         let msg_bytes = match instance.connection_mode {
                             MultiuserConnectionMode::Text => {
                                 let msg_data = player.get_datum(args.get(2).unwrap());
@@ -551,12 +608,18 @@ impl MultiuserXtraManager {
                                 message.to_bytes(None)
                             }
                         };
-                        tx.try_send(msg_bytes).unwrap();
+                        if let Some(local) = &instance.local_connection {
+                            local.server.borrow_mut().receive(local.connection_id, &msg_bytes);
+                        } else if let Some(tx) = &instance.socket_tx {
+                            tx.try_send(msg_bytes).unwrap();
+                        }
                         Ok(DatumRef::Void)
                     } else {
                         Err(ScriptError::new("Socket not connected".to_string()))
                     }
-                })
+                });
+                multiusr_manager.deliver_local_output();
+                result
             }
             "getnetaddresscookie" => {
                 reserve_player_mut(|player| {
@@ -583,6 +646,9 @@ impl MultiuserXtraManager {
                     // Drop the sender to close the send loop; the WebSocket
                     // close is handled by the browser when the socket is dropped
                     instance.socket_tx = None;
+                    if let Some(local) = instance.local_connection.take() {
+                        local.server.borrow_mut().disconnect(local.connection_id);
+                    }
                 }
                 Ok(DatumRef::Void)
             }
@@ -598,6 +664,17 @@ impl MultiuserXtraManager {
                     -3 => "Connection timed out",
                     -4 => "Invalid message",
                     -5 => "Not connected",
+                    MUS_ERROR_INVALID_USER_ID => "Invalid user ID",
+                    MUS_ERROR_INVALID_PASSWORD => "Invalid password",
+                    MUS_ERROR_BAD_PARAMETER => "Bad parameter",
+                    MUS_ERROR_NO_CURRENT_CONNECTION => "No current connection",
+                    MUS_ERROR_ERROR_LEAVING_GROUP => "Error leaving group",
+                    MUS_ERROR_INVALID_GROUP_NAME => "Invalid group name",
+                    MUS_ERROR_INVALID_SERVER_COMMAND => "Invalid server command",
+                    MUS_ERROR_NOT_PERMITTED_WITH_USER_LEVEL => "Not permitted with user level",
+                    MUS_ERROR_DATABASE_USER_ID_NOT_FOUND => "Database user ID not found",
+                    MUS_ERROR_DATABASE_DATA_RECORD_NOT_UNIQUE => "Database data record not unique",
+                    MUS_ERROR_DATABASE_DATA_NOT_FOUND => "Database data not found",
                     _ => "Unknown error",
                 };
                 reserve_player_mut(|player| {
@@ -628,6 +705,27 @@ impl MultiuserXtraManager {
         MultiuserXtraManager {
             instances: FxHashMap::default(),
             instance_counter: 0,
+            local_servers: FxHashMap::default(),
+        }
+    }
+
+    /// Serves connectToNetServer(host, port) from `server` in-process.
+    pub fn add_local_server(&mut self, host: &str, port: i32, server: MusServer) -> Rc<RefCell<MusServer>> {
+        let server = Rc::new(RefCell::new(server));
+        self.local_servers.insert(local_server_key(host, port), server.clone());
+        server
+    }
+
+    /// Hands everything the local servers sent to the connected instances.
+    pub fn deliver_local_output(&mut self) {
+        for instance in self.instances.values_mut() {
+            let Some(local) = &instance.local_connection else {
+                continue;
+            };
+            let bytes = local.server.borrow_mut().take_output(local.connection_id);
+            if !bytes.is_empty() {
+                instance.receive_binary_data(&bytes);
+            }
         }
     }
 }
@@ -0,0 +1,588 @@
+//! In-process Multiuser Server emulator.
+//!
+//! Speaks the binary SMUS protocol (the same frames produced by
+//! `MultiuserMessage::to_bytes`) so the Multiuser Xtra can be exercised
+//! without a live server. Clients are identified by a `MusConnectionId`;
+//! bytes written by a client go in through `receive`, and everything the
+//! server sends back is collected per connection and drained with
+//! `take_output`.
+//!
+//! Supported commands (sent to the "System" recipient):
+//! `system.group.join/leave/getUsers/getUserCount/getGroups`,
+//! `system.server.getTime/getVersion`, `system.movie.getUserCount`,
+//! `system.DBAdmin.createUser/deleteUser/setUserLevel/declareAttribute` and
+//! `system.DBPlayer.setAttribute/getAttribute/getAttributeNames/deleteAttribute`.
+//! Any other recipient is a user ID or an `@group` and is relayed as a peer
+//! message or group broadcast.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use binary_reader::BinaryReader;
+
+use crate::{
+    director::static_datum::StaticDatum,
+    player::xtra::multiuser::{
+        blowfish::MUSBlowfish,
+        reader::{MusReader, MUS_FRAME_HEADER_SIZE, MUS_HEADER},
+        resolve_encryption_key, MultiuserMessage,
+    },
+};
+
+pub type MusConnectionId = u32;
+
+// Error codes from the Multiuser Xtra's errorCode table, so movies that
+// compare against the documented values or call getNetErrorString see what
+// a real Shockwave Multiuser Server would send.
+pub const MUS_ERROR_INVALID_USER_ID: i32 = -2147216222;
+pub const MUS_ERROR_INVALID_PASSWORD: i32 = -2147216221;
+pub const MUS_ERROR_BAD_PARAMETER: i32 = -2147216217;
+pub const MUS_ERROR_NO_CURRENT_CONNECTION: i32 = -2147216215;
+pub const MUS_ERROR_ERROR_LEAVING_GROUP: i32 = -2147216201;
+pub const MUS_ERROR_INVALID_GROUP_NAME: i32 = -2147216200;
+pub const MUS_ERROR_INVALID_SERVER_COMMAND: i32 = -2147216199;
+pub const MUS_ERROR_NOT_PERMITTED_WITH_USER_LEVEL: i32 = -2147216198;
+pub const MUS_ERROR_DATABASE_USER_ID_NOT_FOUND: i32 = -2147216193;
+pub const MUS_ERROR_DATABASE_DATA_RECORD_NOT_UNIQUE: i32 = -2147216190;
+pub const MUS_ERROR_DATABASE_DATA_NOT_FOUND: i32 = -2147216186;
+
+/// Group every user joins on logon.
+pub const MUS_ALL_USERS_GROUP: &str = "@AllUsers";
+
+#[derive(Clone, Debug)]
+pub struct MusUserAccount {
+    pub user_id: String,
+    pub password: String,
+    pub user_level: i32,
+}
+
+#[derive(Clone, Debug)]
+pub struct MusServerConfig {
+    /// Content of the Logon reply.
+    pub description: String,
+    /// Key the clients pass to connectToNetServer ("" for the default key).
+    pub encryption_key: String,
+    /// Reject logons for user IDs that have no account.
+    pub require_accounts: bool,
+    pub default_user_level: i32,
+    /// Minimum user level for `system.DBAdmin.*` commands.
+    pub admin_user_level: i32,
+    pub accounts: Vec<MusUserAccount>,
+    /// DBPlayer attributes that can be set without a declareAttribute first.
+    pub declared_attributes: Vec<String>,
+}
+
+impl Default for MusServerConfig {
+    fn default() -> Self {
+        Self {
+            description: "dirplayer Multiuser Server emulator".to_string(),
+            encryption_key: String::new(),
+            require_accounts: false,
+            default_user_level: 20,
+            admin_user_level: 80,
+            accounts: vec![],
+            declared_attributes: vec![],
+        }
+    }
+}
+
+struct MusConnection {
+    user_id: Option<String>,
+    movie_id: String,
+    user_level: i32,
+    recv_buffer: Vec<u8>,
+    output: Vec<u8>,
+}
+
+struct MusGroup {
+    name: String,
+    members: Vec<MusConnectionId>,
+}
+
+pub struct MusServer {
+    pub config: MusServerConfig,
+    time_ms: u32,
+    connection_counter: MusConnectionId,
+    connections: BTreeMap<MusConnectionId, MusConnection>,
+    /// Groups keyed by (movie ID, lowercased group name).
+    groups: BTreeMap<(String, String), MusGroup>,
+    /// Accounts keyed by lowercased user ID.
+    accounts: BTreeMap<String, MusUserAccount>,
+    declared_attributes: BTreeSet<String>,
+    /// DBPlayer attributes keyed by (movie ID, lowercased user ID).
+    player_attributes: BTreeMap<(String, String), Vec<(String, StaticDatum)>>,
+}
+
+fn key(name: &str) -> String {
+    name.to_lowercase()
+}
+
+/// Looks up `name` in a property list, ignoring case and symbol/string.
+fn prop<'a>(content: &'a StaticDatum, name: &str) -> Option<&'a StaticDatum> {
+    let StaticDatum::PropList(pairs) = content else {
+        return None;
+    };
+    pairs.iter().find_map(|(k, v)| match k {
+        StaticDatum::Symbol(k) | StaticDatum::String(k) if k.eq_ignore_ascii_case(name) => Some(v),
+        _ => None,
+    })
+}
+
+fn text(datum: &StaticDatum) -> Option<String> {
+    match datum {
+        StaticDatum::String(s) | StaticDatum::Symbol(s) => Some(s.clone()),
+        StaticDatum::Int(i) => Some(i.to_string()),
+        _ => None,
+    }
+}
+
+impl MusServer {
+    pub fn new(config: MusServerConfig) -> MusServer {
+        let accounts = config
+            .accounts
+            .iter()
+            .map(|account| (key(&account.user_id), account.clone()))
+            .collect();
+        let declared_attributes = config.declared_attributes.iter().map(|name| key(name)).collect();
+        MusServer {
+            config,
+            time_ms: 0,
+            connection_counter: 0,
+            connections: BTreeMap::new(),
+            groups: BTreeMap::new(),
+            accounts,
+            declared_attributes,
+            player_attributes: BTreeMap::new(),
+        }
+    }
+
+    /// Server clock in milliseconds, used for timeStamps and getTime.
+    pub fn time_ms(&self) -> u32 {
+        self.time_ms
+    }
+
+    pub fn advance_time(&mut self, ms: u32) {
+        self.time_ms = self.time_ms.wrapping_add(ms);
+    }
+
+    pub fn connect(&mut self) -> MusConnectionId {
+        self.connection_counter += 1;
+        self.connections.insert(
+            self.connection_counter,
+            MusConnection {
+                user_id: None,
+                movie_id: String::new(),
+                user_level: 0,
+                recv_buffer: vec![],
+                output: vec![],
+            },
+        );
+        self.connection_counter
+    }
+
+    pub fn disconnect(&mut self, connection_id: MusConnectionId) {
+        self.connections.remove(&connection_id);
+        for group in self.groups.values_mut() {
+            group.members.retain(|id| *id != connection_id);
+        }
+        self.groups.retain(|_, group| !group.members.is_empty());
+    }
+
+    /// Bytes queued for a connection since the last call.
+    pub fn take_output(&mut self, connection_id: MusConnectionId) -> Vec<u8> {
+        self.connections
+            .get_mut(&connection_id)
+            .map(|connection| std::mem::take(&mut connection.output))
+            .unwrap_or_default()
+    }
+
+    pub fn user_id(&self, connection_id: MusConnectionId) -> Option<&str> {
+        self.connections.get(&connection_id)?.user_id.as_deref()
+    }
+
+    /// Logged-on user IDs that are members of `group` in `movie_id`.
+    pub fn group_members(&self, movie_id: &str, group: &str) -> Vec<String> {
+        self.groups
+            .get(&(movie_id.to_string(), key(group)))
+            .map(|group| {
+                group
+                    .members
+                    .iter()
+                    .filter_map(|id| self.user_id(*id).map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn player_attribute(&self, movie_id: &str, user_id: &str, name: &str) -> Option<&StaticDatum> {
+        self.player_attributes
+            .get(&(movie_id.to_string(), key(user_id)))?
+            .iter()
+            .find(|(attr, _)| attr.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Feeds bytes sent by a client and handles every complete frame.
+    pub fn receive(&mut self, connection_id: MusConnectionId, data: &[u8]) {
+        let Some(connection) = self.connections.get_mut(&connection_id) else {
+            return;
+        };
+        connection.recv_buffer.extend_from_slice(data);
+        loop {
+            let Some(connection) = self.connections.get_mut(&connection_id) else {
+                return;
+            };
+            let buffer = &mut connection.recv_buffer;
+            if buffer.len() < MUS_FRAME_HEADER_SIZE {
+                return;
+            }
+            if u16::from_be_bytes([buffer[0], buffer[1]]) != MUS_HEADER {
+                buffer.clear();
+                return;
+            }
+            let payload_size = u32::from_be_bytes([buffer[2], buffer[3], buffer[4], buffer[5]]) as usize;
+            let total_size = MUS_FRAME_HEADER_SIZE + payload_size;
+            if buffer.len() < total_size {
+                return;
+            }
+            let frame: Vec<u8> = buffer.drain(..total_size).collect();
+            let mut cipher = MUSBlowfish::new(&resolve_encryption_key(&self.config.encryption_key));
+            match BinaryReader::read_mus_message_payload(&frame[MUS_FRAME_HEADER_SIZE..], Some(&mut cipher)) {
+                Ok(message) => self.handle_message(connection_id, message),
+                Err(e) => log::warn!("MusServer: failed to read message: {}", e),
+            }
+        }
+    }
+
+    fn send(&mut self, connection_id: MusConnectionId, message: MultiuserMessage) {
+        if let Some(connection) = self.connections.get_mut(&connection_id) {
+            connection.output.extend(message.to_bytes(None));
+        }
+    }
+
+    fn reply(&mut self, connection_id: MusConnectionId, subject: &str, error_code: i32, content: StaticDatum) {
+        let recipient = self.user_id(connection_id).unwrap_or_default().to_string();
+        let message = MultiuserMessage {
+            error_code,
+            recipients: vec![recipient],
+            sender_id: "System".to_string(),
+            subject: subject.to_string(),
+            content,
+            time_stamp: self.time_ms,
+        };
+        self.send(connection_id, message);
+    }
+
+    fn handle_message(&mut self, connection_id: MusConnectionId, message: MultiuserMessage) {
+        if self.user_id(connection_id).is_none() {
+            if message.subject == "Logon" {
+                self.logon(connection_id, &message.content);
+            } else {
+                self.reply(connection_id, &message.subject, MUS_ERROR_NO_CURRENT_CONNECTION, StaticDatum::Void);
+            }
+            return;
+        }
+
+        let mut delivered = BTreeSet::new();
+        for recipient in &message.recipients {
+            if recipient.eq_ignore_ascii_case("System") {
+                self.command(connection_id, &message.subject, &message.content);
+            } else if recipient.starts_with('@') {
+                match self.group(connection_id, recipient) {
+                    Some(group) => delivered.extend(group.members.iter().copied()),
+                    None => self.reply(connection_id, &message.subject, MUS_ERROR_INVALID_GROUP_NAME, StaticDatum::String(recipient.clone())),
+                }
+            } else {
+                match self.find_user(connection_id, recipient) {
+                    Some(target) => {
+                        delivered.insert(target);
+                    }
+                    None => self.reply(connection_id, &message.subject, MUS_ERROR_INVALID_USER_ID, StaticDatum::String(recipient.clone())),
+                }
+            }
+        }
+
+        let sender_id = self.user_id(connection_id).unwrap_or_default().to_string();
+        for target in delivered {
+            let relayed = MultiuserMessage {
+                error_code: 0,
+                recipients: message.recipients.clone(),
+                sender_id: sender_id.clone(),
+                subject: message.subject.clone(),
+                content: message.content.clone(),
+                time_stamp: self.time_ms,
+            };
+            self.send(target, relayed);
+        }
+    }
+
+    fn logon(&mut self, connection_id: MusConnectionId, content: &StaticDatum) {
+        let fields = match content {
+            StaticDatum::List(items) if items.len() >= 2 => items.iter().map(|item| text(item).unwrap_or_default()).collect::<Vec<_>>(),
+            _ => {
+                self.reply(connection_id, "Logon", MUS_ERROR_BAD_PARAMETER, StaticDatum::Void);
+                return;
+            }
+        };
+        let movie_id = fields[0].clone();
+        let user_id = fields[1].clone();
+        let password = fields.get(2).cloned().unwrap_or_default();
+
+        let user_level = match self.accounts.get(&key(&user_id)) {
+            Some(account) if account.password == password => account.user_level,
+            Some(_) => {
+                self.reply(connection_id, "Logon", MUS_ERROR_INVALID_PASSWORD, StaticDatum::Void);
+                return;
+            }
+            None if self.config.require_accounts || user_id.is_empty() => {
+                self.reply(connection_id, "Logon", MUS_ERROR_INVALID_USER_ID, StaticDatum::Void);
+                return;
+            }
+            None => self.config.default_user_level,
+        };
+        let duplicate = self.connections.values().any(|connection| {
+            connection.movie_id == movie_id
+                && connection.user_id.as_deref().is_some_and(|id| id.eq_ignore_ascii_case(&user_id))
+        });
+        if duplicate {
+            self.reply(connection_id, "Logon", MUS_ERROR_INVALID_USER_ID, StaticDatum::Void);
+            return;
+        }
+
+        let connection = self.connections.get_mut(&connection_id).unwrap();
+        connection.user_id = Some(user_id);
+        connection.movie_id = movie_id;
+        connection.user_level = user_level;
+        self.join_group(connection_id, MUS_ALL_USERS_GROUP);
+        let description = StaticDatum::String(self.config.description.clone());
+        self.reply(connection_id, "Logon", 0, description);
+    }
+
+    fn movie_id(&self, connection_id: MusConnectionId) -> String {
+        self.connections.get(&connection_id).map(|c| c.movie_id.clone()).unwrap_or_default()
+    }
+
+    fn group(&self, connection_id: MusConnectionId, name: &str) -> Option<&MusGroup> {
+        self.groups.get(&(self.movie_id(connection_id), key(name)))
+    }
+
+    fn find_user(&self, connection_id: MusConnectionId, user_id: &str) -> Option<MusConnectionId> {
+        let movie_id = self.movie_id(connection_id);
+        self.connections.iter().find_map(|(id, connection)| {
+            let matches = connection.movie_id == movie_id
+                && connection.user_id.as_deref().is_some_and(|id| id.eq_ignore_ascii_case(user_id));
+            matches.then_some(*id)
+        })
+    }
+
+    fn join_group(&mut self, connection_id: MusConnectionId, name: &str) {
+        let group = self
+            .groups
+            .entry((self.movie_id(connection_id), key(name)))
+            .or_insert_with(|| MusGroup { name: name.to_string(), members: vec![] });
+        if !group.members.contains(&connection_id) {
+            group.members.push(connection_id);
+        }
+    }
+
+    fn command(&mut self, connection_id: MusConnectionId, subject: &str, content: &StaticDatum) {
+        let movie_id = self.movie_id(connection_id);
+        let result = match subject.to_lowercase().as_str() {
+            "system.group.join" => match text(content) {
+                Some(name) if name.starts_with('@') => {
+                    self.join_group(connection_id, &name);
+                    Ok(StaticDatum::String(name))
+                }
+                _ => Err(MUS_ERROR_INVALID_GROUP_NAME),
+            },
+            "system.group.leave" => {
+                let name = text(content).unwrap_or_default();
+                let group_key = (movie_id, key(&name));
+                match self.groups.get_mut(&group_key) {
+                    Some(group) if group.members.contains(&connection_id) => {
+                        group.members.retain(|id| *id != connection_id);
+                        if group.members.is_empty() {
+                            self.groups.remove(&group_key);
+                        }
+                        Ok(StaticDatum::String(name))
+                    }
+                    Some(_) => Err(MUS_ERROR_ERROR_LEAVING_GROUP),
+                    None => Err(MUS_ERROR_INVALID_GROUP_NAME),
+                }
+            }
+            "system.group.getusers" | "system.group.getusercount" => {
+                let name = text(content).unwrap_or_default();
+                match self.group(connection_id, &name) {
+                    Some(group) => {
+                        let group_name = StaticDatum::String(group.name.clone());
+                        let users = self.group_members(&movie_id, &name);
+                        let value = if subject.eq_ignore_ascii_case("system.group.getUsers") {
+                            (StaticDatum::Symbol("users".to_string()), StaticDatum::List(users.into_iter().map(StaticDatum::String).collect()))
+                        } else {
+                            (StaticDatum::Symbol("number".to_string()), StaticDatum::Int(users.len() as i32))
+                        };
+                        Ok(StaticDatum::PropList(vec![(StaticDatum::Symbol("group".to_string()), group_name), value]))
+                    }
+                    None => Err(MUS_ERROR_INVALID_GROUP_NAME),
+                }
+            }
+            "system.group.getgroups" => Ok(StaticDatum::List(
+                self.groups
+                    .iter()
+                    .filter(|((movie, _), _)| *movie == movie_id)
+                    .map(|(_, group)| StaticDatum::String(group.name.clone()))
+                    .collect(),
+            )),
+            "system.movie.getusercount" => Ok(StaticDatum::Int(
+                self.connections.values().filter(|c| c.user_id.is_some() && c.movie_id == movie_id).count() as i32,
+            )),
+            "system.server.gettime" => Ok(StaticDatum::Int(self.time_ms as i32)),
+            "system.server.getversion" => Ok(StaticDatum::String(self.config.description.clone())),
+            name if name.starts_with("system.dbadmin.") => self.db_admin(connection_id, &name["system.dbadmin.".len()..], content),
+            name if name.starts_with("system.dbplayer.") => self.db_player(connection_id, &name["system.dbplayer.".len()..], content),
+            _ => Err(MUS_ERROR_INVALID_SERVER_COMMAND),
+        };
+        match result {
+            Ok(content) => self.reply(connection_id, subject, 0, content),
+            Err(error_code) => self.reply(connection_id, subject, error_code, content.clone()),
+        }
+    }
+
+    fn is_admin(&self, connection_id: MusConnectionId) -> bool {
+        self.connections
+            .get(&connection_id)
+            .is_some_and(|connection| connection.user_level >= self.config.admin_user_level)
+    }
+
+    fn db_admin(&mut self, connection_id: MusConnectionId, command: &str, content: &StaticDatum) -> Result<StaticDatum, i32> {
+        if !self.is_admin(connection_id) {
+            return Err(MUS_ERROR_NOT_PERMITTED_WITH_USER_LEVEL);
+        }
+        let user_id = prop(content, "userID").and_then(text);
+        match command {
+            "createuser" => {
+                let user_id = user_id.ok_or(MUS_ERROR_BAD_PARAMETER)?;
+                let account = MusUserAccount {
+                    password: prop(content, "password").and_then(text).unwrap_or_default(),
+                    user_level: match prop(content, "userLevel") {
+                        Some(StaticDatum::Int(level)) => *level,
+                        _ => self.config.default_user_level,
+                    },
+                    user_id: user_id.clone(),
+                };
+                if self.accounts.contains_key(&key(&user_id)) {
+                    return Err(MUS_ERROR_DATABASE_DATA_RECORD_NOT_UNIQUE);
+                }
+                self.accounts.insert(key(&user_id), account);
+                Ok(content.clone())
+            }
+            "deleteuser" => {
+                let user_id = user_id.ok_or(MUS_ERROR_BAD_PARAMETER)?;
+                self.accounts.remove(&key(&user_id)).ok_or(MUS_ERROR_DATABASE_USER_ID_NOT_FOUND)?;
+                self.player_attributes.retain(|(_, user), _| *user != key(&user_id));
+                Ok(content.clone())
+            }
+            "setuserlevel" => {
+                let user_id = user_id.ok_or(MUS_ERROR_BAD_PARAMETER)?;
+                let Some(StaticDatum::Int(level)) = prop(content, "userLevel") else {
+                    return Err(MUS_ERROR_BAD_PARAMETER);
+                };
+                self.accounts.get_mut(&key(&user_id)).ok_or(MUS_ERROR_DATABASE_USER_ID_NOT_FOUND)?.user_level = *level;
+                Ok(content.clone())
+            }
+            "declareattribute" => {
+                let names = match prop(content, "attribute").ok_or(MUS_ERROR_BAD_PARAMETER)? {
+                    StaticDatum::List(items) => items.iter().filter_map(text).collect(),
+                    item => vec![text(item).ok_or(MUS_ERROR_BAD_PARAMETER)?],
+                };
+                self.declared_attributes.extend(names.iter().map(|name| key(name)));
+                Ok(content.clone())
+            }
+            _ => Err(MUS_ERROR_INVALID_SERVER_COMMAND),
+        }
+    }
+
+    fn db_player(&mut self, connection_id: MusConnectionId, command: &str, content: &StaticDatum) -> Result<StaticDatum, i32> {
+        let own_id = self.user_id(connection_id).unwrap_or_default().to_string();
+        // Only admins may touch another user's attributes
+        let user_id = match prop(content, "userID").and_then(text) {
+            Some(id) if !id.eq_ignore_ascii_case(&own_id) => {
+                if !self.is_admin(connection_id) {
+                    return Err(MUS_ERROR_NOT_PERMITTED_WITH_USER_LEVEL);
+                }
+                id
+            }
+            _ => own_id,
+        };
+        let record_key = (self.movie_id(connection_id), key(&user_id));
+        let attribute = prop(content, "attribute");
+        let attribute_names = || -> Vec<String> {
+            match attribute {
+                Some(StaticDatum::List(items)) => items.iter().filter_map(text).collect(),
+                Some(StaticDatum::PropList(pairs)) => pairs.iter().filter_map(|(k, _)| text(k)).collect(),
+                Some(item) => text(item).into_iter().collect(),
+                None => vec![],
+            }
+        };
+        let response = |attributes: Vec<(StaticDatum, StaticDatum)>| {
+            StaticDatum::PropList(vec![
+                (StaticDatum::Symbol("userID".to_string()), StaticDatum::String(user_id.clone())),
+                (StaticDatum::Symbol("attribute".to_string()), StaticDatum::PropList(attributes)),
+            ])
+        };
+        match command {
+            "setattribute" => {
+                let Some(StaticDatum::PropList(pairs)) = attribute else {
+                    return Err(MUS_ERROR_BAD_PARAMETER);
+                };
+                let values: Vec<(String, StaticDatum)> = pairs
+                    .iter()
+                    .map(|(name, value)| Ok((text(name).ok_or(MUS_ERROR_BAD_PARAMETER)?, value.clone())))
+                    .collect::<Result<_, i32>>()?;
+                if values.iter().any(|(name, _)| !self.declared_attributes.contains(&key(name))) {
+                    return Err(MUS_ERROR_DATABASE_DATA_NOT_FOUND);
+                }
+                let record = self.player_attributes.entry(record_key).or_default();
+                for (name, value) in values {
+                    match record.iter_mut().find(|(attr, _)| attr.eq_ignore_ascii_case(&name)) {
+                        Some(entry) => entry.1 = value,
+                        None => record.push((name, value)),
+                    }
+                }
+                Ok(response(pairs.clone()))
+            }
+            "getattribute" => {
+                let record = self.player_attributes.get(&record_key);
+                let values = attribute_names()
+                    .into_iter()
+                    .map(|name| {
+                        let value = record
+                            .and_then(|record| record.iter().find(|(attr, _)| attr.eq_ignore_ascii_case(&name)))
+                            .map(|(_, value)| value.clone())
+                            .ok_or(MUS_ERROR_DATABASE_DATA_NOT_FOUND)?;
+                        Ok((StaticDatum::Symbol(name), value))
+                    })
+                    .collect::<Result<_, i32>>()?;
+                Ok(response(values))
+            }
+            "getattributenames" => {
+                let names = self
+                    .player_attributes
+                    .get(&record_key)
+                    .map(|record| record.iter().map(|(name, _)| StaticDatum::Symbol(name.clone())).collect())
+                    .unwrap_or_default();
+                Ok(StaticDatum::PropList(vec![
+                    (StaticDatum::Symbol("userID".to_string()), StaticDatum::String(user_id.clone())),
+                    (StaticDatum::Symbol("attribute".to_string()), StaticDatum::List(names)),
+                ]))
+            }
+            "deleteattribute" => {
+                let names = attribute_names();
+                if let Some(record) = self.player_attributes.get_mut(&record_key) {
+                    record.retain(|(attr, _)| !names.iter().any(|name| name.eq_ignore_ascii_case(attr)));
+                }
+                Ok(content.clone())
+            }
+            _ => Err(MUS_ERROR_INVALID_SERVER_COMMAND),
+        }
+    }
+}
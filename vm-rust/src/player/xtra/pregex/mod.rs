@@ -0,0 +1,500 @@
+//! PRegEx Xtra: Perl-compatible regular expressions for Lingo.
+//!
+//! PRegEx adds global `PRegEx_*` handlers. Because Lingo strings are
+//! passed by value, the subject string is passed as the first item of a
+//! list, which the Xtra edits in place (`PRegEx_Replace`, `PRegEx_QuoteMeta`,
+//! ...). The last match and search position are kept per list, so
+//! `PRegEx_GetMatchString` and `PRegEx_SearchContinue` take the same list.
+//! That state is dropped once the list is freed. Match positions are 1-based
+//! char positions.
+
+use std::collections::VecDeque;
+
+use fxhash::FxHashMap;
+
+use crate::{
+    director::lingo::datum::{Datum, DatumType},
+    player::{
+        datum_ref::DatumId,
+        regex::{Match, Regex, RegexFlags},
+        reserve_player_mut, DatumRef, DirPlayer, ScriptError,
+    },
+};
+
+/// Compiled patterns kept for movies that run the same filter every frame.
+const MAX_CACHED_PATTERNS: usize = 64;
+
+const GLOBAL_HANDLERS: &[&str] = &[
+    "pregex_search",
+    "pregex_searchbegin",
+    "pregex_searchcontinue",
+    "pregex_replace",
+    "pregex_split",
+    "pregex_grep",
+    "pregex_translate",
+    "pregex_quotemeta",
+    "pregex_getmatchstring",
+    "pregex_getmatchstart",
+    "pregex_getmatchend",
+    "pregex_getmatchlen",
+    "pregex_getmatchbrcount",
+    "pregex_getpos",
+    "pregex_setpos",
+    "pregex_clear",
+];
+
+/// A subject list: its datum id plus allocation serial, so a list that
+/// reuses the id of a freed one doesn't pick up its search state.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct ListKey {
+    id: DatumId,
+    serial: u64,
+}
+
+impl ListKey {
+    fn is_live(&self, player: &DirPlayer) -> bool {
+        player.allocator.datum_serial(self.id) == Some(self.serial)
+    }
+}
+
+struct PRegExSearch {
+    regex: Regex,
+    /// Char index `PRegEx_SearchContinue` resumes from.
+    pos: usize,
+    last_match: Option<Match>,
+}
+
+#[derive(Default)]
+pub struct PRegExXtraManager {
+    searches: FxHashMap<ListKey, PRegExSearch>,
+    patterns: FxHashMap<(String, String), Regex>,
+    pub instance_counter: u32,
+}
+
+fn match_error(err: String) -> ScriptError {
+    ScriptError::new(format!("PRegEx: {}", err))
+}
+
+/// Where to resume after `found`; empty matches advance one char so a
+/// search can't stall.
+fn next_pos(found: &Match) -> usize {
+    if found.end() == found.start() {
+        found.end() + 1
+    } else {
+        found.end()
+    }
+}
+
+/// Expand `$n`, `${n}`, `\n` and `$&` in a replacement string.
+fn expand_replacement(template: &[char], found: &Match, text: &[char]) -> String {
+    let mut result = String::new();
+    let mut i = 0;
+    while i < template.len() {
+        let c = template[i];
+        let next = template.get(i + 1).copied();
+        match (c, next) {
+            ('\\' | '$', Some(digit)) if digit.is_ascii_digit() => {
+                result.push_str(&found.group_text(text, digit as usize - '0' as usize).unwrap_or_default());
+                i += 2;
+            }
+            ('$', Some('&')) => {
+                result.push_str(&found.group_text(text, 0).unwrap_or_default());
+                i += 2;
+            }
+            ('$', Some('{')) => {
+                let close = template[i..].iter().position(|&c| c == '}').map(|offset| i + offset);
+                let index = close.and_then(|close| template[i + 2..close].iter().collect::<String>().parse::<usize>().ok());
+                match (close, index) {
+                    (Some(close), Some(index)) => {
+                        result.push_str(&found.group_text(text, index).unwrap_or_default());
+                        i = close + 1;
+                    }
+                    _ => {
+                        result.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            ('\\', Some(escaped)) => {
+                result.push(match escaped {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    escaped => escaped,
+                });
+                i += 2;
+            }
+            _ => {
+                result.push(c);
+                i += 1;
+            }
+        }
+    }
+    result
+}
+
+/// Expand a `tr`-style character list such as `a-zA-Z_`.
+fn expand_char_list(list: &str) -> Vec<char> {
+    let chars: Vec<char> = list.chars().collect();
+    let mut result = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        if i + 2 < chars.len() && chars[i + 1] == '-' && chars[i] <= chars[i + 2] {
+            result.extend(chars[i]..=chars[i + 2]);
+            i += 3;
+        } else {
+            result.push(chars[i]);
+            i += 1;
+        }
+    }
+    result
+}
+
+fn list_key(player: &DirPlayer, list_ref: &DatumRef, handler_name: &str) -> Result<ListKey, ScriptError> {
+    match player.get_datum(list_ref) {
+        Datum::List(..) => {
+            let id = list_ref.unwrap();
+            let serial = player.allocator.datum_serial(id).unwrap_or_default();
+            Ok(ListKey { id, serial })
+        }
+        _ => Err(ScriptError::new(format!(
+            "{} expects a list containing the string as its first argument",
+            handler_name
+        ))),
+    }
+}
+
+/// The subject string: the first item of the list passed as `args[0]`.
+fn subject(player: &DirPlayer, args: &[DatumRef], handler_name: &str) -> Result<(ListKey, Vec<char>), ScriptError> {
+    let list_ref = args
+        .first()
+        .ok_or_else(|| ScriptError::new(format!("{} requires a list argument", handler_name)))?;
+    let id = list_key(player, list_ref, handler_name)?;
+    let items = player.get_datum(list_ref).to_list()?;
+    let text = match items.front() {
+        Some(item) => player.get_datum(item).string_value()?,
+        None => String::new(),
+    };
+    Ok((id, text.chars().collect()))
+}
+
+fn set_subject(player: &mut DirPlayer, list_ref: &DatumRef, text: String) -> Result<(), ScriptError> {
+    let text_ref = player.alloc_datum(Datum::String(text));
+    let (_, items, _) = player.get_datum_mut(list_ref).to_list_mut()?;
+    match items.front_mut() {
+        Some(item) => *item = text_ref,
+        None => items.push_back(text_ref),
+    }
+    Ok(())
+}
+
+fn string_arg(player: &DirPlayer, args: &[DatumRef], index: usize) -> Result<String, ScriptError> {
+    match args.get(index) {
+        Some(arg) => match player.get_datum(arg) {
+            Datum::Void => Ok(String::new()),
+            datum => datum.string_value(),
+        },
+        None => Ok(String::new()),
+    }
+}
+
+fn int_arg(player: &DirPlayer, args: &[DatumRef], index: usize, default: i32) -> Result<i32, ScriptError> {
+    match args.get(index) {
+        Some(arg) => match player.get_datum(arg) {
+            Datum::Void => Ok(default),
+            datum => datum.int_value(),
+        },
+        None => Ok(default),
+    }
+}
+
+impl PRegExXtraManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create_instance(&mut self, _args: &[DatumRef]) -> u32 {
+        self.instance_counter += 1;
+        self.instance_counter
+    }
+
+    /// Number of lists with search state, including freed ones not yet pruned.
+    pub fn search_count(&self) -> usize {
+        self.searches.len()
+    }
+
+    pub fn has_global_handler(name: &str) -> bool {
+        GLOBAL_HANDLERS.contains(&name.to_lowercase().as_str())
+    }
+
+    /// Compile `pattern` with Perl modifier letters `flags`; also returns
+    /// whether the `g` modifier was given.
+    fn compile(&mut self, pattern: &str, flags: &str) -> Result<(Regex, bool), ScriptError> {
+        let global = flags.contains(['g', 'G']);
+        let key = (pattern.to_string(), flags.to_string());
+        if let Some(regex) = self.patterns.get(&key) {
+            return Ok((regex.clone(), global));
+        }
+        let regex = Regex::new(pattern, RegexFlags::parse(flags))
+            .map_err(|err| ScriptError::new(format!("PRegEx: invalid pattern \"{}\": {}", pattern, err)))?;
+        if self.patterns.len() >= MAX_CACHED_PATTERNS {
+            self.patterns.clear();
+        }
+        self.patterns.insert(key, regex.clone());
+        Ok((regex, global))
+    }
+
+    fn search(&mut self, handler_name: &str, args: &[DatumRef], begin: bool) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let (id, text) = subject(player, args, handler_name)?;
+            let pattern = string_arg(player, args, 1)?;
+            let flags = string_arg(player, args, 2)?;
+            let (regex, global) = self.compile(&pattern, &flags)?;
+            let matches = if global && !begin {
+                regex.find_all(&text, 0).map_err(match_error)?
+            } else {
+                regex.find_at(&text, 0).map_err(match_error)?.into_iter().collect()
+            };
+            // Match data refers to the first match, so SearchContinue can
+            // walk the rest.
+            let first = matches.first().cloned();
+            let search = PRegExSearch {
+                pos: first.as_ref().map_or(text.len() + 1, next_pos),
+                regex,
+                last_match: first,
+            };
+            self.searches.retain(|key, _| key.is_live(player));
+            self.searches.insert(id, search);
+            Ok(player.alloc_datum(Datum::Int(matches.len() as i32)))
+        })
+    }
+
+    fn search_continue(&mut self, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let (id, text) = subject(player, args, "PRegEx_SearchContinue")?;
+            let search = self.searches.get_mut(&id).ok_or_else(|| {
+                ScriptError::new("PRegEx_SearchContinue called without PRegEx_SearchBegin".to_string())
+            })?;
+            let found = if search.pos <= text.len() {
+                search.regex.find_at(&text, search.pos).map_err(match_error)?
+            } else {
+                None
+            };
+            search.pos = found.as_ref().map_or(text.len() + 1, next_pos);
+            let result = found.is_some() as i32;
+            search.last_match = found;
+            Ok(player.alloc_datum(Datum::Int(result)))
+        })
+    }
+
+    fn replace(&mut self, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let (id, text) = subject(player, args, "PRegEx_Replace")?;
+            let pattern = string_arg(player, args, 1)?;
+            let flags = string_arg(player, args, 2)?;
+            let template: Vec<char> = string_arg(player, args, 3)?.chars().collect();
+            let (regex, global) = self.compile(&pattern, &flags)?;
+            let matches = if global {
+                regex.find_all(&text, 0).map_err(match_error)?
+            } else {
+                regex.find_at(&text, 0).map_err(match_error)?.into_iter().collect()
+            };
+
+            let mut result = String::new();
+            let mut copied = 0;
+            for found in &matches {
+                result.extend(&text[copied..found.start()]);
+                result.push_str(&expand_replacement(&template, found, &text));
+                copied = found.end();
+            }
+            result.extend(&text[copied..]);
+
+            if !matches.is_empty() {
+                set_subject(player, &args[0], result)?;
+            }
+            self.searches.remove(&id);
+            Ok(player.alloc_datum(Datum::Int(matches.len() as i32)))
+        })
+    }
+
+    /// Perl `split`: captured groups are included in the result and, without
+    /// a limit, trailing empty fields are dropped.
+    fn split(&mut self, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let (_, text) = subject(player, args, "PRegEx_Split")?;
+            let pattern = string_arg(player, args, 1)?;
+            let flags = string_arg(player, args, 2)?;
+            let limit = int_arg(player, args, 3, 0)?.max(0) as usize;
+            let (regex, _) = self.compile(&pattern, &flags)?;
+
+            let mut fields: Vec<String> = vec![];
+            let mut field_start = 0;
+            let mut field_count = 1;
+            for found in regex.find_all(&text, 0).map_err(match_error)? {
+                if limit > 0 && field_count >= limit {
+                    break;
+                }
+                let empty = found.start() == found.end();
+                if empty && (found.start() == 0 || found.start() >= text.len()) {
+                    continue;
+                }
+                fields.push(text[field_start..found.start()].iter().collect());
+                field_count += 1;
+                for group in 1..=regex.group_count() {
+                    fields.push(found.group_text(&text, group).unwrap_or_default());
+                }
+                field_start = found.end();
+            }
+            fields.push(text[field_start..].iter().collect());
+            if limit == 0 {
+                while fields.last().is_some_and(|field| field.is_empty()) {
+                    fields.pop();
+                }
+            }
+
+            let items: VecDeque<DatumRef> = fields
+                .into_iter()
+                .map(|field| player.alloc_datum(Datum::String(field)))
+                .collect();
+            Ok(player.alloc_datum(Datum::List(DatumType::List, items, false)))
+        })
+    }
+
+    /// Remove the items of the list that don't match, in place.
+    fn grep(&mut self, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let list_ref = args
+                .first()
+                .ok_or_else(|| ScriptError::new("PRegEx_Grep requires a list argument".to_string()))?;
+            list_key(player, list_ref, "PRegEx_Grep")?;
+            let pattern = string_arg(player, args, 1)?;
+            let flags = string_arg(player, args, 2)?;
+            let (regex, _) = self.compile(&pattern, &flags)?;
+
+            let mut kept = VecDeque::new();
+            for item in player.get_datum(list_ref).to_list()? {
+                let text = player.get_datum(item).string_value()?;
+                if regex.is_match(&text).map_err(match_error)? {
+                    kept.push_back(item.clone());
+                }
+            }
+            let count = kept.len() as i32;
+            *player.get_datum_mut(list_ref).to_list_mut()?.1 = kept;
+            Ok(player.alloc_datum(Datum::Int(count)))
+        })
+    }
+
+    /// Perl `tr`: replace each char of the search list with the char at the
+    /// same index in the replacement list, returning the number of chars
+    /// found. An empty replacement list only counts.
+    fn translate(&mut self, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let (_, text) = subject(player, args, "PRegEx_Translate")?;
+            let search = expand_char_list(&string_arg(player, args, 1)?);
+            let mut replace = expand_char_list(&string_arg(player, args, 2)?);
+            if replace.is_empty() {
+                replace = search.clone();
+            }
+
+            let mut count = 0;
+            let result: String = text
+                .iter()
+                .map(|c| match search.iter().position(|s| s == c) {
+                    Some(index) => {
+                        count += 1;
+                        replace[index.min(replace.len() - 1)]
+                    }
+                    None => *c,
+                })
+                .collect();
+            if count > 0 {
+                set_subject(player, &args[0], result)?;
+            }
+            Ok(player.alloc_datum(Datum::Int(count)))
+        })
+    }
+
+    fn quote_meta(&mut self, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let (_, text) = subject(player, args, "PRegEx_QuoteMeta")?;
+            let mut result = String::new();
+            for c in text {
+                if !(c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()) {
+                    result.push('\\');
+                }
+                result.push(c);
+            }
+            set_subject(player, &args[0], result.clone())?;
+            Ok(player.alloc_datum(Datum::String(result)))
+        })
+    }
+
+    fn match_info(&mut self, handler_name: &str, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let (id, text) = subject(player, args, handler_name)?;
+            let group = int_arg(player, args, 1, 0)?.max(0) as usize;
+            let search = self.searches.get(&id);
+            let found = search.and_then(|search| search.last_match.as_ref());
+            let span = found.and_then(|found| found.group(group));
+            let datum = match handler_name {
+                "pregex_getmatchstring" => match span {
+                    Some((start, end)) => Datum::String(text.get(start..end).unwrap_or_default().iter().collect()),
+                    None => Datum::Void,
+                },
+                "pregex_getmatchstart" => Datum::Int(span.map_or(0, |(start, _)| start as i32 + 1)),
+                "pregex_getmatchend" => Datum::Int(span.map_or(0, |(_, end)| end as i32)),
+                "pregex_getmatchlen" => Datum::Int(span.map_or(0, |(start, end)| (end - start) as i32)),
+                "pregex_getmatchbrcount" => Datum::Int(search.map_or(0, |search| search.regex.group_count() as i32)),
+                _ => Datum::Int(search.map_or(1, |search| search.pos as i32 + 1)),
+            };
+            Ok(player.alloc_datum(datum))
+        })
+    }
+
+    fn set_pos(&mut self, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        reserve_player_mut(|player| {
+            let (id, _) = subject(player, args, "PRegEx_SetPos")?;
+            let pos = int_arg(player, args, 1, 1)?.max(1) as usize - 1;
+            let search = self.searches.get_mut(&id).ok_or_else(|| {
+                ScriptError::new("PRegEx_SetPos called without a previous search".to_string())
+            })?;
+            search.pos = pos;
+            Ok(DatumRef::Void)
+        })
+    }
+
+    pub fn call_global_handler(handler_name: &str, args: &[DatumRef]) -> Result<DatumRef, ScriptError> {
+        let manager = unsafe { PREGEX_XTRA_MANAGER_OPT.as_mut().unwrap() };
+        let handler = handler_name.to_lowercase();
+        match handler.as_str() {
+            "pregex_search" => manager.search("PRegEx_Search", args, false),
+            "pregex_searchbegin" => manager.search("PRegEx_SearchBegin", args, true),
+            "pregex_searchcontinue" => manager.search_continue(args),
+            "pregex_replace" => manager.replace(args),
+            "pregex_split" => manager.split(args),
+            "pregex_grep" => manager.grep(args),
+            "pregex_translate" => manager.translate(args),
+            "pregex_quotemeta" => manager.quote_meta(args),
+            "pregex_getmatchstring"
+            | "pregex_getmatchstart"
+            | "pregex_getmatchend"
+            | "pregex_getmatchlen"
+            | "pregex_getmatchbrcount"
+            | "pregex_getpos" => manager.match_info(&handler, args),
+            "pregex_setpos" => manager.set_pos(args),
+            "pregex_clear" => {
+                let id = reserve_player_mut(|player| subject(player, args, "PRegEx_Clear"))?.0;
+                manager.searches.remove(&id);
+                Ok(DatumRef::Void)
+            }
+            _ => Err(ScriptError::new(format!("No handler {} found for PRegEx xtra", handler_name))),
+        }
+    }
+}
+
+pub static mut PREGEX_XTRA_MANAGER_OPT: Option<PRegExXtraManager> = None;
+
+pub fn borrow_pregex_manager_mut<T>(callback: impl FnOnce(&mut PRegExXtraManager) -> T) -> T {
+    let manager = unsafe { PREGEX_XTRA_MANAGER_OPT.as_mut().unwrap() };
+    callback(manager)
+}
@@ -311,7 +311,7 @@ impl XmlParserXtraManager {
         }
     }
 
-    pub fn create_instance(&mut self, _args: &Vec<DatumRef>) -> u32 {
+    pub fn create_instance(&mut self, _args: &[DatumRef]) -> u32 {
         self.instance_counter += 1;
         self.instances
             .insert(self.instance_counter, XmlParserXtraInstance::new());
@@ -326,7 +326,7 @@ impl XmlParserXtraManager {
     pub async fn call_instance_async_handler(
         handler_name: &str,
         instance_id: u32,
-        _args: &Vec<DatumRef>,
+        _args: &[DatumRef],
     ) -> Result<DatumRef, ScriptError> {
         Err(ScriptError::new(format!(
             "No async handler {} found for XmlParser xtra instance #{}",
@@ -337,7 +337,7 @@ impl XmlParserXtraManager {
     pub fn call_instance_handler(
         handler_name: &str,
         instance_id: u32,
-        args: &Vec<DatumRef>,
+        args: &[DatumRef],
     ) -> Result<DatumRef, ScriptError> {
         let manager = unsafe { XMLPARSER_XTRA_MANAGER_OPT.as_mut().unwrap() };
         let instance = manager.instances.get_mut(&instance_id).ok_or_else(|| {
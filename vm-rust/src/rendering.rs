@@ -30,7 +30,7 @@ use crate::{
             get_concrete_sprite_rect, get_score, get_score_sprite, get_sprite_at, ScoreRef,
         },
         sprite::{ColorRef, CursorRef, Sprite},
-        DirPlayer, PLAYER_OPT,
+        window, DirPlayer, PLAYER_OPT,
     },
 };
 
@@ -302,15 +302,18 @@ pub fn render_stage_to_bitmap(
     bitmap: &mut Bitmap,
     debug_sprite_num: Option<i16>,
 ) {
-    let palettes = player.movie.cast_manager.palettes();
-    render_score_to_bitmap(
-        player,
-        &ScoreRef::Stage,
-        bitmap,
-        debug_sprite_num,
-        IntRect::from_size(0, 0, player.movie.rect.width(), player.movie.rect.height()),
-    );
-    draw_cursor(player, bitmap, &palettes);
+    window::with_stage_context(player, |player| {
+        let palettes = player.movie.cast_manager.screen_palettes();
+        render_score_to_bitmap(
+            player,
+            &ScoreRef::Stage,
+            bitmap,
+            debug_sprite_num,
+            IntRect::from_size(0, 0, player.movie.rect.width(), player.movie.rect.height()),
+        );
+        window::composite_windows(player, bitmap);
+        draw_cursor(player, bitmap, &palettes);
+    })
 }
 
 /// Render a preview bitmap for a cast member. Returns `None` if the member type
@@ -338,7 +341,7 @@ pub fn render_preview_bitmap(
                 0,
                 PaletteRef::BuiltIn(get_system_default_palette()),
             );
-            let palettes = &player.movie.cast_manager.palettes();
+            let palettes = &player.movie.cast_manager.screen_palettes();
             bitmap.fill_relative_rect(
                 0, 0, 0, 0,
                 resolve_color_ref(
@@ -447,7 +450,7 @@ pub fn render_preview_bitmap(
             let width = cols * total_cell_w + grid_w;
             let height = rows * total_cell_h + grid_w;
 
-            let palettes = &player.movie.cast_manager.palettes();
+            let palettes = &player.movie.cast_manager.screen_palettes();
             let mut bitmap = Bitmap::new(
                 width, height, 32, 32, 0,
                 PaletteRef::BuiltIn(get_system_default_palette()),
@@ -792,7 +795,7 @@ fn render_filmloop_from_channel_data(
     use crate::player::bitmap::drawing::CopyPixelsParams;
     use crate::player::score::get_channel_number_from_index;
 
-    let palettes = player.movie.cast_manager.palettes();
+    let palettes = player.movie.cast_manager.screen_palettes();
 
     // The filmloop's own cast_lib is used as the default when channel data has cast_lib=65535
     let filmloop_cast_lib = member_ref.cast_lib;
@@ -1335,7 +1338,7 @@ fn render_filmloop_from_channel_data(
                     ((255.0 - data.blend as f32) * 100.0 / 255.0) as i32
                 };
 
-                let frame_palette = player.movie.score.get_frame_palette(player.movie.current_frame);
+                let frame_palette = crate::player::palette_effects::active_palette(player);
                 bitmap.draw_shape_with_sprite(&temp_sprite, &shape_member.shape_info, sprite_rect, &palettes, &frame_palette);
             }
             CastMemberType::VectorShape(vector_member) => {
@@ -1640,7 +1643,7 @@ pub fn render_score_to_bitmap_with_offset(
     offset: (i32, i32),
     parent_props: Option<FilmLoopParentProps>,
 ) {
-    let palettes = player.movie.cast_manager.palettes();
+    let palettes = player.movie.cast_manager.screen_palettes();
 
     // For filmloops, use transparent background so sprites composite correctly
     // onto the stage without a solid background color showing through.
@@ -1990,7 +1993,7 @@ pub fn render_score_to_bitmap_with_offset(
                     }
                 }
 
-                let frame_palette = player.movie.score.get_frame_palette(player.movie.current_frame);
+                let frame_palette = crate::player::palette_effects::active_palette(player);
                 debug!(
                     "  SHAPE RENDER: channel {} member {:?} type {:?} size {}x{} color {:?} bg {:?} ink {} blend {} filled={} lineThick={}",
                     channel_num, sprite.member, shape_member.shape_info.shape_type,
@@ -2668,6 +2671,7 @@ pub fn render_score_to_bitmap_with_offset(
                                 style,
                             }
                         }).collect();
+                        let spans_with_defaults = text_member.apply_hyperlink_styles(spans_with_defaults);
 
                         // Use native browser text rendering for smooth, anti-aliased text
                         if let Err(e) = FontMemberHandlers::render_native_text_to_bitmap(
@@ -2960,6 +2964,70 @@ pub fn render_score_to_bitmap_with_offset(
                     );
                 }
             }
+            CastMemberType::Shockwave3d(w3d) => {
+                // Rasterize the scene on the CPU into the same per-member frame
+                // buffer the WebGL2 renderer fills, then composite it like Flash.
+                let Some(scene) = w3d.parsed_scene.as_ref() else {
+                    continue;
+                };
+                let sprite = get_score_sprite(&player.movie, score_source, channel_num).unwrap();
+                let rect = get_concrete_sprite_rect(player, sprite);
+                let sprite_rect = IntRect::from(
+                    rect.left - offset.0,
+                    rect.top - offset.1,
+                    rect.right - offset.0,
+                    rect.bottom - offset.1,
+                );
+                let member_key = (member_ref.cast_lib, member_ref.cast_member);
+                let frame = crate::rendering_cpu::scene3d::render_scene_to_bitmap(
+                    member_key,
+                    scene,
+                    Some(&w3d.runtime_state),
+                    sprite.w3d_camera.as_deref(),
+                    &sprite.w3d_cameras,
+                    sprite_rect.width().max(1) as u32,
+                    sprite_rect.height().max(1) as u32,
+                );
+
+                let bitmap_ref = if let Some(&existing_ref) = player.w3d_frame_buffers.get(&member_key) {
+                    player.bitmap_manager.replace_bitmap(existing_ref, frame);
+                    existing_ref
+                } else {
+                    let bitmap_ref = player.bitmap_manager.add_bitmap(frame);
+                    player.w3d_frame_buffers.insert(member_key, bitmap_ref);
+                    bitmap_ref
+                };
+
+                if let Some(src_bitmap) = player.bitmap_manager.get_bitmap(bitmap_ref) {
+                    let src_rect = IntRect::from(0, 0, src_bitmap.width as i32, src_bitmap.height as i32);
+                    let dst_rect = sprite_rect;
+
+                    let params = CopyPixelsParams {
+                        blend: sprite.effective_blend(),
+                        ink: sprite.ink as u32,
+                        color: sprite.color.clone(),
+                        bg_color: sprite.bg_color.clone(),
+                        mask_image: None,
+                        is_text_rendering: false,
+                        rotation: sprite.rotation,
+                        skew: sprite.skew,
+                        sprite: Some(sprite),
+                        mask_offset: (0, 0),
+                        original_dst_rect: Some(dst_rect.clone()),
+                        bg_color_explicit: false,
+                        fore_color_explicit: false,
+                        ink9_mask_bitmap: None, ink9_mask_offset: (0, 0),
+                    };
+
+                    bitmap.copy_pixels_with_params(
+                        &palettes,
+                        src_bitmap,
+                        dst_rect,
+                        src_rect,
+                        &params,
+                    );
+                }
+            }
             _ => {}
         }
     }
@@ -3281,7 +3349,14 @@ impl PlayerCanvasRenderer {
             );
         }
         let bitmap = &mut self.bitmap;
+        crate::player::palette_effects::update(player);
         render_stage_to_bitmap(player, bitmap, self.debug_selected_channel_num);
+        crate::player::transition::apply_to_frame(
+            player,
+            &mut bitmap.data,
+            bitmap.width as u32,
+            bitmap.height as u32,
+        );
 
         if let Some(font) = player.font_manager.get_system_font() {
             let font_bitmap = player.bitmap_manager.get_bitmap(font.bitmap_ref).unwrap();
@@ -3314,7 +3389,7 @@ impl PlayerCanvasRenderer {
                 0,
                 0,
                 params,
-                &player.movie.cast_manager.palettes(),
+                &player.movie.cast_manager.screen_palettes(),
                 0,
                 0,
             );
@@ -0,0 +1,8 @@
+//! Software rendering helpers for DirPlayer
+//!
+//! The Canvas 2D renderer in the parent rendering.rs file composites sprites
+//! on the CPU. This module holds the pieces of that path that need their own
+//! rasterizer:
+//! - Shockwave 3D scenes (mirrors `rendering_gpu::webgl2::scene3d`)
+
+pub mod scene3d;
@@ -0,0 +1,1419 @@
+//! Shockwave 3D scene rasterizer for the software rendering path
+//!
+//! CPU counterpart of `rendering_gpu::webgl2::scene3d`: draws W3dScene data
+//! (models, shaders, texture layers, lights, fog, cameras, backdrops and
+//! overlays) into a 32-bit `Bitmap` so Shockwave3D sprites show up in the
+//! Canvas2D renderer and in `render_stage_to_bitmap` without a GPU.
+//!
+//! Camera, material and lighting resolution follow the WebGL2 renderer so
+//! both paths agree on what a scene looks like. Skeletal skinning, motions,
+//! particles, inker outlines and bloom are not rasterized; skinned models
+//! are drawn in their bind pose.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::director::chunks::w3d::render_helpers::{
+    find_shader_ci, get_runtime_transform, has_detached_ancestor, invert_transform, is_child_of,
+    mat4_multiply_col_major, node_shader_override, perspective, resolve_shader_candidate_ci,
+};
+use crate::director::chunks::w3d::texture::{decode_texture_rgba, generate_uvs_by_mode, DecodedTexture};
+use crate::director::chunks::w3d::types::*;
+use crate::player::bitmap::bitmap::{get_system_default_palette, Bitmap, PaletteRef};
+use crate::player::cast_member::{CameraOverlay, Shockwave3dRuntimeState};
+
+/// Non-ambient light limit, same as the WebGL2 shader.
+const MAX_LIGHTS: usize = 8;
+
+const DEFAULT_CLEAR_COLOR: [f32; 3] = [0.2, 0.2, 0.2];
+
+/// Director's default checkerboard for untextured primitives (2x2, nearest).
+const CHECKER_PIXELS: [u8; 16] = [
+    255, 255, 255, 255,   204, 102, 102, 255,  // row 0: white, pink-red
+    204, 102, 102, 255,   255, 255, 255, 255,  // row 1: pink-red, white
+];
+
+const IDENTITY_4X4: [f32; 16] = [
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 1.0, 0.0,
+    0.0, 0.0, 0.0, 1.0,
+];
+
+// Interpolated vertex attribute layout
+const ATTR_WORLD: usize = 0; // world position (3)
+const ATTR_NORMAL: usize = 3; // world normal (3)
+const ATTR_UV: usize = 6; // primary texcoord after CLOD remap (2)
+const ATTR_UV2: usize = 8; // secondary texcoord (2)
+const ATTR_COLOR: usize = 10; // vertex color (4)
+const ATTR_VIEW_DIST: usize = 14; // distance along the view axis (1)
+const ATTR_COUNT: usize = 15;
+
+thread_local! {
+    /// Decoded textures per member, so JPEG/DXT data is not decoded every frame.
+    static TEXTURE_CACHE: RefCell<HashMap<(i32, i32), HashMap<String, CachedTexture>>> =
+        RefCell::new(HashMap::new());
+}
+
+struct CachedTexture {
+    /// (data length, scene.texture_content_version) the entry was decoded from
+    version: (usize, u64),
+    texture: Option<Rc<DecodedTexture>>,
+}
+
+/// Render a Shockwave3D member's scene to a 32-bit bitmap of `width` x `height`.
+///
+/// `camera` is the sprite's primary camera (None = "DefaultView"); `extra_cameras`
+/// are drawn on top in order, clearing according to `camera_clear_at_render`.
+/// Backdrops are drawn behind each camera's geometry and overlays on top of all
+/// camera passes, as in the WebGL2 renderer.
+pub fn render_scene_to_bitmap(
+    member_key: (i32, i32),
+    scene: &W3dScene,
+    runtime_state: Option<&Shockwave3dRuntimeState>,
+    camera: Option<&str>,
+    extra_cameras: &[String],
+    width: u32,
+    height: u32,
+) -> Bitmap {
+    let textures = cached_textures(member_key, scene);
+    let checker = DecodedTexture {
+        width: 2,
+        height: 2,
+        rgba: CHECKER_PIXELS.to_vec(),
+        has_alpha: false,
+    };
+    let mut raster = Scene3dRasterizer::new(
+        scene, runtime_state, &textures, &checker, width.max(1), height.max(1),
+    );
+
+    raster.render_camera(camera, true);
+    for cam_name in extra_cameras {
+        let should_clear = runtime_state
+            .and_then(|rs| rs.camera_clear_at_render.get(&cam_name.to_ascii_lowercase()))
+            .copied()
+            .unwrap_or(true);
+        raster.render_camera(Some(cam_name), should_clear);
+    }
+
+    if let Some(rs) = runtime_state {
+        let mut cameras: Vec<&String> = rs.camera_overlays.keys().collect();
+        cameras.sort();
+        for cam in cameras {
+            raster.draw_overlays(&rs.camera_overlays[cam]);
+        }
+    }
+
+    raster.into_bitmap()
+}
+
+/// Decode (or reuse) every texture in the scene, keyed by lowercase name.
+fn cached_textures(member_key: (i32, i32), scene: &W3dScene) -> HashMap<String, Rc<DecodedTexture>> {
+    TEXTURE_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let member_cache = cache.entry(member_key).or_default();
+        member_cache.retain(|name, _| {
+            scene.texture_images.keys().any(|k| k.eq_ignore_ascii_case(name))
+        });
+
+        let mut textures = HashMap::new();
+        for (tex_name, image_data) in &scene.texture_images {
+            let lower = tex_name.to_lowercase();
+            let version = (image_data.len(), scene.texture_content_version);
+            let entry = member_cache.entry(lower.clone()).or_insert_with(|| CachedTexture {
+                version: (usize::MAX, u64::MAX),
+                texture: None,
+            });
+            if entry.version != version {
+                entry.version = version;
+                entry.texture = match decode_texture_rgba(image_data) {
+                    Ok(decoded) => Some(Rc::new(decoded)),
+                    Err(e) => {
+                        log::warn!("[3D-SW] Texture \"{}\" not decoded: {}", tex_name, e);
+                        None
+                    }
+                };
+            }
+            if let Some(tex) = &entry.texture {
+                textures.insert(lower, tex.clone());
+            }
+        }
+        textures
+    })
+}
+
+// ─── Per-pass setup ───
+
+struct CameraSetup {
+    name: String,
+    view: [f32; 16],
+    projection: [f32; 16],
+    position: [f32; 3],
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum LightKind {
+    Directional,
+    Point,
+    Spot,
+}
+
+struct LightParams {
+    kind: LightKind,
+    /// Direction towards the light for directional lights, world position otherwise
+    position: [f32; 3],
+    direction: [f32; 3],
+    color: [f32; 3],
+    attenuation: [f32; 3],
+    /// Cone angle in radians (0 = not a spot)
+    spot_angle: f32,
+}
+
+struct SceneLights {
+    lights: Vec<LightParams>,
+    global_ambient: [f32; 3],
+}
+
+struct FogParams {
+    near: f32,
+    far: f32,
+    color: [f32; 3],
+    mode: u8,
+}
+
+// ─── Surface description ───
+
+#[derive(Clone, Copy, PartialEq)]
+enum SurfaceBlend {
+    Opaque,
+    Alpha,
+    Additive,
+}
+
+struct TextureBinding<'t> {
+    texture: &'t DecodedTexture,
+    transform: [f32; 16],
+    wrap: (u8, u8),
+}
+
+struct LayerBinding<'t> {
+    texture: &'t DecodedTexture,
+    /// 1 = multiply, 2 = add
+    mode: u8,
+    intensity: f32,
+    wrap: (u8, u8),
+}
+
+struct SurfaceShading<'t> {
+    diffuse: [f32; 4],
+    ambient: [f32; 3],
+    specular: [f32; 3],
+    emissive: [f32; 3],
+    shininess: f32,
+    opacity: f32,
+    texture: Option<TextureBinding<'t>>,
+    layers: Vec<LayerBinding<'t>>,
+    first_blend_func: u8,
+    toon_steps: Option<f32>,
+}
+
+impl<'t> SurfaceShading<'t> {
+    fn from_material(mat: &W3dMaterial) -> Self {
+        // IFX maps material reflectivity to shader shininess (scaled by 100)
+        let shininess = if mat.shininess > 0.0 { mat.shininess } else { mat.reflectivity * 100.0 };
+        Self {
+            diffuse: mat.diffuse,
+            ambient: [mat.ambient[0], mat.ambient[1], mat.ambient[2]],
+            specular: [mat.specular[0], mat.specular[1], mat.specular[2]],
+            emissive: [mat.emissive[0], mat.emissive[1], mat.emissive[2]],
+            shininess,
+            opacity: mat.opacity,
+            texture: None,
+            layers: Vec::new(),
+            first_blend_func: 0,
+            toon_steps: None,
+        }
+    }
+
+    /// Material used when a shader override names no material.
+    fn override_fallback() -> Self {
+        Self {
+            diffuse: [0.8, 0.8, 0.8, 1.0],
+            ambient: [0.2, 0.2, 0.2],
+            specular: [0.0, 0.0, 0.0],
+            emissive: [0.0, 0.0, 0.0],
+            shininess: 0.0,
+            opacity: 1.0,
+            texture: None,
+            layers: Vec::new(),
+            first_blend_func: 0,
+            toon_steps: None,
+        }
+    }
+
+    fn scene_default(scene: &W3dScene) -> Self {
+        if let Some(mat) = scene.materials.first() {
+            return Self::from_material(mat);
+        }
+        Self {
+            diffuse: [0.5, 0.5, 0.5, 1.0],
+            ambient: [0.125, 0.125, 0.125],
+            specular: [1.0, 1.0, 1.0],
+            emissive: [0.0, 0.0, 0.0],
+            shininess: 0.0,
+            opacity: 1.0,
+            texture: None,
+            layers: Vec::new(),
+            first_blend_func: 0,
+            toon_steps: None,
+        }
+    }
+
+    fn apply_shader_mode(&mut self, shader: &W3dShader) {
+        if shader.shader_type == W3dShaderType::Painter {
+            let steps = if shader.toon_steps > 0 { shader.toon_steps as f32 } else { 3.0 };
+            self.toon_steps = Some(steps);
+        }
+    }
+
+    fn blend(&self, force_blend: bool) -> SurfaceBlend {
+        if self.first_blend_func == 1 {
+            SurfaceBlend::Additive
+        } else if self.opacity < 1.0 || force_blend {
+            SurfaceBlend::Alpha
+        } else {
+            SurfaceBlend::Opaque
+        }
+    }
+
+    fn is_transparent(&self) -> bool {
+        self.opacity < 0.999 || self.texture.as_ref().is_some_and(|t| t.texture.has_alpha)
+    }
+}
+
+/// Geometry of one mesh of a model resource, ready for transformation.
+struct MeshView<'m> {
+    positions: &'m [[f32; 3]],
+    normals: &'m [[f32; 3]],
+    tex_coords: Cow<'m, [[f32; 2]]>,
+    tex_coords2: Option<&'m [[f32; 2]]>,
+    colors: Option<&'m [[f32; 4]]>,
+    faces: &'m [[u32; 3]],
+}
+
+#[derive(Clone, Copy)]
+struct ClipVertex {
+    clip: [f32; 4],
+    attrs: [f32; ATTR_COUNT],
+}
+
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    z: f32,
+    inv_w: f32,
+    /// Attributes pre-divided by w for perspective-correct interpolation
+    attrs: [f32; ATTR_COUNT],
+}
+
+struct DrawState {
+    depth_write: bool,
+    /// Face culling: true = draw only front (counter-clockwise) faces
+    cull_back: bool,
+    blend: SurfaceBlend,
+}
+
+// ─── Rasterizer ───
+
+struct Scene3dRasterizer<'a> {
+    scene: &'a W3dScene,
+    runtime_state: Option<&'a Shockwave3dRuntimeState>,
+    textures: &'a HashMap<String, Rc<DecodedTexture>>,
+    checker: &'a DecodedTexture,
+    width: usize,
+    height: usize,
+    color: Vec<[f32; 4]>,
+    depth: Vec<f32>,
+}
+
+impl<'a> Scene3dRasterizer<'a> {
+    fn new(
+        scene: &'a W3dScene,
+        runtime_state: Option<&'a Shockwave3dRuntimeState>,
+        textures: &'a HashMap<String, Rc<DecodedTexture>>,
+        checker: &'a DecodedTexture,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let (width, height) = (width as usize, height as usize);
+        let [r, g, b] = DEFAULT_CLEAR_COLOR;
+        Self {
+            scene,
+            runtime_state,
+            textures,
+            checker,
+            width,
+            height,
+            color: vec![[r, g, b, 1.0]; width * height],
+            depth: vec![1.0; width * height],
+        }
+    }
+
+    fn into_bitmap(self) -> Bitmap {
+        let mut bitmap = Bitmap::new(
+            self.width as u16, self.height as u16, 32, 32, 8,
+            PaletteRef::BuiltIn(get_system_default_palette()),
+        );
+        let mut data = Vec::with_capacity(self.width * self.height * 4);
+        for px in &self.color {
+            for c in px {
+                data.push((c.clamp(0.0, 1.0) * 255.0 + 0.5) as u8);
+            }
+        }
+        bitmap.data = data;
+        bitmap.use_alpha = true;
+        bitmap
+    }
+
+    fn clear(&mut self, clear_color: bool) {
+        if clear_color {
+            let [r, g, b] = self.runtime_state
+                .and_then(|rs| rs.background_color)
+                .map(|(r, g, b)| [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0])
+                .unwrap_or(DEFAULT_CLEAR_COLOR);
+            self.color.fill([r, g, b, 1.0]);
+        }
+        self.depth.fill(1.0);
+    }
+
+    /// Draw one camera pass: clear, backdrops, opaque models, then transparent models.
+    fn render_camera(&mut self, camera: Option<&str>, clear_color: bool) {
+        self.clear(clear_color);
+        let cam = self.build_camera(camera);
+
+        if let Some(rs) = self.runtime_state
+            && let Some(backdrops) = lookup_ci(&rs.camera_backdrops, &cam.name)
+        {
+            self.draw_overlays(backdrops);
+        }
+
+        let lights = self.build_lights(&cam.name);
+        let fog = self.runtime_state.filter(|rs| rs.fog_enabled).map(|rs| FogParams {
+            near: rs.fog_near,
+            far: rs.fog_far,
+            color: [rs.fog_color.0, rs.fog_color.1, rs.fog_color.2],
+            mode: rs.fog_mode,
+        });
+
+        let model_nodes = self.visible_model_nodes(&cam.name);
+        if model_nodes.is_empty() {
+            self.draw_all_meshes_fallback(&cam, &lights, fog.as_ref());
+            return;
+        }
+
+        // Skybox nodes first so scene geometry draws over them
+        let mut sorted_nodes = model_nodes;
+        sorted_nodes.sort_by_key(|n| if is_skybox(n) { 0 } else { 1 });
+
+        let mut transparent_nodes: Vec<(&W3dNode, f32)> = Vec::new();
+        for node in sorted_nodes {
+            if self.node_visibility(node) == 0 {
+                continue; // #none
+            }
+            if self.node_is_transparent(node) {
+                let world = self.accumulate_transform(node);
+                let dx = world[12] - cam.position[0];
+                let dy = world[13] - cam.position[1];
+                let dz = world[14] - cam.position[2];
+                transparent_nodes.push((node, dx * dx + dy * dy + dz * dz));
+                continue;
+            }
+            self.draw_model_node(node, &cam, &lights, fog.as_ref(), false);
+        }
+
+        // Transparent geometry back-to-front, without depth writes
+        transparent_nodes.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        for (node, _) in transparent_nodes {
+            self.draw_model_node(node, &cam, &lights, fog.as_ref(), true);
+        }
+    }
+
+    // ─── Camera ───
+
+    fn build_camera(&self, camera: Option<&str>) -> CameraSetup {
+        let cam_name = camera.unwrap_or("DefaultView");
+        let view_node = self.scene.nodes.iter()
+            .find(|n| n.node_type == W3dNodeType::View && n.name.eq_ignore_ascii_case(cam_name))
+            .or_else(|| self.scene.nodes.iter().find(|n| n.node_type == W3dNodeType::View));
+
+        let (view, position) = if let Some(node) = view_node {
+            let world_t = self.accumulate_transform(node);
+            (invert_transform(&world_t), [world_t[12], world_t[13], world_t[14]])
+        } else {
+            // Default camera: looking at origin from a reasonable distance
+            let mut view = IDENTITY_4X4;
+            view[14] = -100.0;
+            (view, [0.0, 0.0, 100.0])
+        };
+
+        let aspect = self.width as f32 / self.height as f32;
+        let (fov, near, far, aspect) = if let Some(node) = view_node {
+            let mut f = node.far_plane;
+            if f > 100000.0 || f <= 0.0 { f = 10000.0; }
+            let mut n = node.near_plane;
+            if n <= 0.0 { n = 1.0; }
+            let cam_aspect = if node.screen_width > 0 && node.screen_height > 0 {
+                node.screen_width as f32 / node.screen_height as f32
+            } else {
+                aspect
+            };
+            (node.fov.to_radians(), n, f, cam_aspect)
+        } else {
+            (34.516f32.to_radians(), 1.0, 10000.0, aspect)
+        };
+
+        let cam_lower = cam_name.to_ascii_lowercase();
+        let is_ortho = self.runtime_state
+            .and_then(|rs| rs.camera_projection_mode.get(&cam_lower))
+            .is_some_and(|&m| m == 1);
+        let projection = if is_ortho {
+            let ortho_h = self.runtime_state
+                .and_then(|rs| rs.camera_ortho_height.get(&cam_lower))
+                .copied()
+                .unwrap_or(100.0);
+            let half_h = ortho_h * 0.5;
+            let half_w = half_h * aspect;
+            orthographic(-half_w, half_w, -half_h, half_h, near, far)
+        } else {
+            perspective(fov, aspect, near, far)
+        };
+
+        CameraSetup { name: cam_name.to_string(), view, projection, position }
+    }
+
+    // ─── Scene graph ───
+
+    fn visible_model_nodes(&self, cam_name: &str) -> Vec<&'a W3dNode> {
+        let scene = self.scene;
+        let detached: HashSet<&str> = self.runtime_state
+            .map(|rs| rs.detached_nodes.iter().map(|s| s.as_str()).collect())
+            .unwrap_or_default();
+        let root_filter = self.runtime_state
+            .and_then(|rs| rs.camera_root_nodes.get(&cam_name.to_ascii_lowercase()));
+
+        scene.nodes.iter()
+            .filter(|n| n.node_type == W3dNodeType::Model)
+            .filter(|n| !detached.contains(n.name.as_str()))
+            .filter(|n| match root_filter {
+                Some(root) => is_child_of(scene, &n.name, root),
+                None => !has_detached_ancestor(scene, &n.parent_name, &detached),
+            })
+            .collect()
+    }
+
+    fn node_visibility(&self, node: &W3dNode) -> u8 {
+        self.runtime_state
+            .and_then(|rs| rs.node_visibility.get(&node.name))
+            .copied()
+            .unwrap_or(1)
+    }
+
+    /// World transform of a node: parent chain with runtime overrides, root to leaf.
+    fn accumulate_transform(&self, node: &W3dNode) -> [f32; 16] {
+        let runtime_t = |n: &W3dNode| {
+            self.runtime_state
+                .and_then(|rs| get_runtime_transform(rs, &n.name))
+                .unwrap_or(n.transform)
+        };
+        let mut chain = vec![runtime_t(node)];
+        let mut current_parent = &node.parent_name;
+        while !current_parent.is_empty() && current_parent != "<world>" {
+            match self.scene.nodes.iter().find(|n| n.name == *current_parent) {
+                Some(parent) => {
+                    chain.push(runtime_t(parent));
+                    current_parent = &parent.parent_name;
+                }
+                None => break,
+            }
+        }
+        chain.into_iter().rev().fold(IDENTITY_4X4, |acc, t| mat4_multiply_col_major(&acc, &t))
+    }
+
+    fn build_lights(&self, cam_name: &str) -> SceneLights {
+        let scene = self.scene;
+        let mut result = SceneLights { lights: Vec::new(), global_ambient: [0.0; 3] };
+
+        if scene.lights.is_empty() {
+            // Default: one directional light from above-right
+            result.lights.push(LightParams {
+                kind: LightKind::Directional,
+                position: [0.5, 1.0, 0.7],
+                direction: [-0.5, -1.0, -0.7],
+                color: [1.0, 1.0, 1.0],
+                attenuation: [1.0, 0.0, 0.0],
+                spot_angle: 0.0,
+            });
+            return result;
+        }
+
+        let root_filter = self.runtime_state
+            .and_then(|rs| rs.camera_root_nodes.get(&cam_name.to_ascii_lowercase()));
+
+        // Ambient first, then directional, then spot/point so key lights aren't
+        // pushed out of the 8-light budget by effect lights.
+        let mut sorted_lights: Vec<&W3dLight> = scene.lights.iter().collect();
+        sorted_lights.sort_by_key(|l| match l.light_type {
+            W3dLightType::Ambient => 0,
+            W3dLightType::Directional => 1,
+            W3dLightType::Spot => 2,
+            W3dLightType::Point => 3,
+        });
+
+        for light in sorted_lights {
+            if !light.enabled {
+                continue;
+            }
+            if self.runtime_state.is_some_and(|rs| rs.detached_nodes.contains(&light.name)) {
+                continue;
+            }
+            if scene.nodes.iter().any(|n| n.name == light.name && n.parent_name.is_empty()) {
+                continue;
+            }
+            if let Some(root) = root_filter
+                && !is_child_of(scene, &light.name, root)
+            {
+                continue;
+            }
+
+            let kind = match light.light_type {
+                W3dLightType::Ambient => {
+                    for (acc, c) in result.global_ambient.iter_mut().zip(light.color) {
+                        *acc += c;
+                    }
+                    continue;
+                }
+                W3dLightType::Directional => LightKind::Directional,
+                W3dLightType::Point => LightKind::Point,
+                W3dLightType::Spot => LightKind::Spot,
+            };
+            if result.lights.len() >= MAX_LIGHTS {
+                continue;
+            }
+
+            let mut attenuation = light.attenuation;
+            if attenuation.iter().sum::<f32>() < 0.001 {
+                attenuation[0] = 1.0;
+            }
+
+            let light_node = scene.nodes.iter().find(|n| {
+                n.node_type == W3dNodeType::Light && (n.resource_name == light.name || n.name == light.name)
+            });
+            let (position, direction) = match light_node {
+                Some(node) => {
+                    let world_t = self.accumulate_transform(node);
+                    let dir = [-world_t[8], -world_t[9], -world_t[10]];
+                    if kind == LightKind::Directional {
+                        (dir, dir)
+                    } else {
+                        ([world_t[12], world_t[13], world_t[14]], dir)
+                    }
+                }
+                None => ([0.5, 1.0, 0.7], [-0.5, -1.0, -0.7]),
+            };
+
+            result.lights.push(LightParams {
+                kind,
+                position,
+                direction: normalize(direction),
+                color: light.color,
+                attenuation,
+                spot_angle: if kind == LightKind::Spot { light.spot_angle.to_radians() } else { 0.0 },
+            });
+        }
+        result
+    }
+
+    // ─── Materials ───
+
+    fn texture(&self, name: &str) -> Option<&'a DecodedTexture> {
+        if name.is_empty() {
+            return None;
+        }
+        self.textures.get(&name.to_lowercase()).map(|t| t.as_ref())
+    }
+
+    /// Resolve a shader's texture layers: layer 0 is the diffuse texture,
+    /// later layers become multiply/add blend layers (up to 2), tex_mode 6
+    /// (specular maps) are ignored.
+    fn apply_texture_layers(&self, surface: &mut SurfaceShading<'a>, layers: &[W3dTextureLayer]) -> bool {
+        let mut diffuse_name = String::new();
+        for (layer_idx, layer) in layers.iter().enumerate() {
+            let Some(tex) = self.texture(&layer.name) else { continue };
+            if layer.tex_mode == 6 {
+                continue;
+            }
+            let lower = layer.name.to_lowercase();
+            if layer_idx == 0 && surface.texture.is_none() {
+                surface.texture = Some(TextureBinding {
+                    texture: tex,
+                    transform: layer.tex_transform,
+                    wrap: (layer.repeat_s, layer.repeat_t),
+                });
+                diffuse_name = lower;
+                continue;
+            }
+            if surface.layers.len() < 2 && lower != diffuse_name {
+                let mode = if lower.contains("lightmap") && !lower.contains("shadow") {
+                    // Lightmap-only meshes (empty textureList[1]) multiply the material color
+                    let lightmap_only = layer_idx > 0 && layers[..layer_idx].iter().all(|prev| prev.name.is_empty());
+                    if lightmap_only { 1 } else { 2 }
+                } else {
+                    match layer.blend_func {
+                        1 => 2, // #add
+                        _ => 1, // #replace / #multiply
+                    }
+                };
+                surface.layers.push(LayerBinding {
+                    texture: tex,
+                    mode,
+                    intensity: layer.intensity,
+                    wrap: (layer.repeat_s, layer.repeat_t),
+                });
+            }
+        }
+        surface.texture.is_some()
+    }
+
+    fn use_checker(&self, surface: &mut SurfaceShading<'a>) {
+        surface.texture = Some(TextureBinding {
+            texture: self.checker,
+            transform: IDENTITY_4X4,
+            wrap: (1, 1),
+        });
+        surface.diffuse = [1.0, 1.0, 1.0, 1.0];
+    }
+
+    /// Material/texture resolution for one mesh, in the same order as the WebGL2 renderer:
+    /// per-mesh shader override → per-mesh resource binding → node shader → resource
+    /// binding names → node-level fallback → scene default material.
+    fn resolve_surface(&self, node: &W3dNode, res_info: Option<&ModelResourceInfo>, mesh_idx: usize) -> SurfaceShading<'a> {
+        let scene = self.scene;
+        let is_primitive = res_info.is_some_and(|r| r.primitive_type.is_some());
+
+        // 1. Per-mesh shader override (shaderList[i] = shader)
+        if let Some(override_name) = self.runtime_state.and_then(|rs| node_shader_override(rs, &node.name, Some(mesh_idx)))
+            && let Some(shader) = find_shader_ci(&scene.shaders, override_name)
+        {
+            let mat = shader_material(scene, shader);
+            let mut surface = mat.map(SurfaceShading::from_material).unwrap_or_else(SurfaceShading::override_fallback);
+            let mut tex_bound = self.apply_texture_layers(&mut surface, &shader.texture_layers);
+            if !tex_bound && is_primitive {
+                self.use_checker(&mut surface);
+                tex_bound = true;
+            }
+            if tex_bound && !shader.use_diffuse_with_texture {
+                surface.diffuse = [1.0, 1.0, 1.0, 1.0];
+            }
+            surface.first_blend_func = shader.texture_layers.first().map(|l| l.blend_func).unwrap_or(0);
+            surface.apply_shader_mode(shader);
+            return surface;
+        }
+
+        let effective_shader_name = self.runtime_state
+            .and_then(|rs| node_shader_override(rs, &node.name, None))
+            .cloned()
+            .unwrap_or_else(|| node.shader_name.clone());
+
+        if let Some(res_info) = res_info {
+            // 2. Candidate shader names for this mesh
+            let mut candidates: Vec<&str> = Vec::new();
+            for binding in &res_info.shader_bindings {
+                if let Some(name) = binding.mesh_bindings.get(mesh_idx).filter(|n| !n.is_empty()) {
+                    candidates.push(name);
+                }
+            }
+            if !effective_shader_name.is_empty() {
+                candidates.push(&effective_shader_name);
+            }
+            for binding in &res_info.shader_bindings {
+                if !binding.name.is_empty() {
+                    candidates.push(&binding.name);
+                }
+            }
+
+            let mut best: Option<(&W3dMaterial, Option<&W3dShader>)> = None;
+            for candidate in &candidates {
+                let shader = resolve_shader_candidate_ci(scene, candidate);
+                let mat = resolve_material_candidate_ci(scene, candidate)
+                    .or_else(|| shader.and_then(|s| shader_material(scene, s)));
+
+                // DefaultShader's white material would mask model-specific materials
+                let skip_default = candidate.eq_ignore_ascii_case("DefaultShader") && candidates.len() > 1;
+                if best.is_none() && !skip_default {
+                    best = mat.map(|m| (m, shader));
+                }
+
+                if let Some(shader) = shader {
+                    let mut surface = mat.map(SurfaceShading::from_material)
+                        .unwrap_or_else(|| SurfaceShading::scene_default(scene));
+                    if self.apply_texture_layers(&mut surface, &shader.texture_layers) {
+                        if !shader.use_diffuse_with_texture {
+                            surface.diffuse = [1.0, 1.0, 1.0, 1.0];
+                        }
+                        surface.first_blend_func = shader.texture_layers.first().map(|l| l.blend_func).unwrap_or(0);
+                        surface.apply_shader_mode(shader);
+                        return surface;
+                    }
+                }
+            }
+
+            // 3. No textured binding: best material (checker for primitives)
+            if let Some((mat, shader)) = best {
+                let mut surface = SurfaceShading::from_material(mat);
+                if is_primitive {
+                    self.use_checker(&mut surface);
+                }
+                if let Some(shader) = shader {
+                    surface.first_blend_func = shader.texture_layers.first().map(|l| l.blend_func).unwrap_or(0);
+                    surface.apply_shader_mode(shader);
+                }
+                return surface;
+            }
+        }
+
+        // 4. Node-level shader, then the resource's first binding
+        if let Some(shader) = find_shader_ci(&scene.shaders, &effective_shader_name)
+            && let Some(mat) = shader_material(scene, shader)
+        {
+            let mut surface = SurfaceShading::from_material(mat);
+            if self.apply_texture_layers(&mut surface, &shader.texture_layers) && !shader.use_diffuse_with_texture {
+                surface.diffuse = [1.0, 1.0, 1.0, 1.0];
+            }
+            surface.first_blend_func = shader.texture_layers.first().map(|l| l.blend_func).unwrap_or(0);
+            surface.apply_shader_mode(shader);
+            return surface;
+        }
+        if let Some(shader) = res_info
+            .and_then(|r| r.shader_bindings.first())
+            .and_then(|b| find_shader_ci(&scene.shaders, &b.name))
+            && let Some(mat) = find_material_ci(&scene.materials, &shader.material_name)
+        {
+            let mut surface = SurfaceShading::from_material(mat);
+            if self.apply_texture_layers(&mut surface, &shader.texture_layers) && !shader.use_diffuse_with_texture {
+                surface.diffuse = [1.0, 1.0, 1.0, 1.0];
+            }
+            return surface;
+        }
+
+        SurfaceShading::scene_default(scene)
+    }
+
+    fn node_is_transparent(&self, node: &W3dNode) -> bool {
+        let res_name = node_resource_name(node);
+        let res_info = self.scene.model_resources.get(res_name);
+        let mesh_count = self.resource_meshes(res_name).len().max(1);
+        (0..mesh_count).any(|idx| self.resolve_surface(node, res_info, idx).is_transparent())
+    }
+
+    // ─── Geometry ───
+
+    fn resource_meshes(&self, resource: &str) -> Vec<MeshView<'a>> {
+        let scene = self.scene;
+        let is_light_resource = scene.nodes.iter().any(|n| {
+            n.node_type == W3dNodeType::Light
+                && (n.model_resource_name == resource || (n.resource_name == resource && n.resource_name != "."))
+        });
+        if resource.is_empty() || is_light_resource {
+            return Vec::new();
+        }
+
+        let mut meshes = Vec::new();
+        if let Some(clod_meshes) = scene.clod_meshes.get(resource) {
+            let uv_gen_mode = scene.model_resources.get(resource).and_then(|r| r.uv_gen_mode);
+            for mesh in clod_meshes {
+                if mesh.positions.is_empty() || mesh.faces.is_empty() {
+                    continue;
+                }
+                // Use decoded texcoords, or generate UVs when missing or degenerate
+                let tex_coords = match mesh.tex_coords.first().filter(|tcs| !tcs.is_empty()) {
+                    Some(tcs) => {
+                        let all_same = tcs.len() > 1 && tcs.iter()
+                            .all(|t| (t[0] - tcs[0][0]).abs() < 0.001 && (t[1] - tcs[0][1]).abs() < 0.001);
+                        if all_same {
+                            Cow::Owned(generate_uvs_by_mode(&mesh.positions, uv_gen_mode))
+                        } else {
+                            Cow::Borrowed(tcs.as_slice())
+                        }
+                    }
+                    None => Cow::Owned(generate_uvs_by_mode(&mesh.positions, uv_gen_mode)),
+                };
+                meshes.push(MeshView {
+                    positions: &mesh.positions,
+                    normals: &mesh.normals,
+                    tex_coords,
+                    tex_coords2: mesh.tex_coords.get(1).filter(|t| !t.is_empty()).map(|t| t.as_slice()),
+                    colors: Some(mesh.diffuse_colors.as_slice())
+                        .filter(|c| !c.is_empty() && c.len() == mesh.positions.len()),
+                    faces: &mesh.faces,
+                });
+            }
+        }
+        for mesh in scene.raw_meshes.iter().filter(|m| m.name == resource) {
+            if mesh.positions.is_empty() || mesh.faces.is_empty() {
+                continue;
+            }
+            meshes.push(MeshView {
+                positions: &mesh.positions,
+                normals: &mesh.normals,
+                tex_coords: Cow::Borrowed(mesh.tex_coords.as_slice()),
+                tex_coords2: None,
+                colors: Some(mesh.vertex_colors.as_slice())
+                    .filter(|c| !c.is_empty() && c.len() == mesh.positions.len()),
+                faces: &mesh.faces,
+            });
+        }
+        meshes
+    }
+
+    fn draw_model_node(
+        &mut self,
+        node: &W3dNode,
+        cam: &CameraSetup,
+        lights: &SceneLights,
+        fog: Option<&FogParams>,
+        force_blend: bool,
+    ) {
+        let res_name = node_resource_name(node);
+        let res_info = self.scene.model_resources.get(res_name);
+
+        let mut world = self.accumulate_transform(node);
+        let has_skeleton = self.scene.skeletons.iter().any(|s| s.name == res_name && s.bones.len() > 1);
+        let has_runtime_override = self.runtime_state.is_some_and(|rs| rs.node_transforms.contains_key(&node.name));
+        if has_skeleton && !has_runtime_override {
+            // Passive W3D skinned content needs the Z-up -> render-basis correction
+            for col in 0..3 {
+                let o = col * 4;
+                let r1 = world[o + 1];
+                let r2 = world[o + 2];
+                world[o + 1] = r2;
+                world[o + 2] = -r1;
+            }
+        }
+
+        let skybox = is_skybox(node);
+        let vis_mode = if skybox { 3 } else { self.node_visibility(node) };
+
+        for (mesh_idx, mesh) in self.resource_meshes(res_name).iter().enumerate() {
+            let surface = self.resolve_surface(node, res_info, mesh_idx);
+            let state = DrawState {
+                depth_write: !skybox && !force_blend,
+                cull_back: vis_mode == 1,
+                blend: surface.blend(force_blend),
+            };
+            self.draw_mesh(mesh, &world, &surface, &state, cam, lights, fog);
+        }
+    }
+
+    /// No model nodes: draw every mesh with the identity transform and default material.
+    fn draw_all_meshes_fallback(&mut self, cam: &CameraSetup, lights: &SceneLights, fog: Option<&FogParams>) {
+        let surface = SurfaceShading::scene_default(self.scene);
+        let state = DrawState { depth_write: true, cull_back: true, blend: SurfaceBlend::Opaque };
+        let mut names: Vec<&String> = self.scene.clod_meshes.keys().collect();
+        names.extend(self.scene.raw_meshes.iter().map(|m| &m.name));
+        names.sort();
+        names.dedup();
+        for name in names {
+            for mesh in self.resource_meshes(name) {
+                self.draw_mesh(&mesh, &IDENTITY_4X4, &surface, &state, cam, lights, fog);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_mesh(
+        &mut self,
+        mesh: &MeshView,
+        world: &[f32; 16],
+        surface: &SurfaceShading,
+        state: &DrawState,
+        cam: &CameraSetup,
+        lights: &SceneLights,
+        fog: Option<&FogParams>,
+    ) {
+        let view_proj = mat4_multiply_col_major(&cam.projection, &cam.view);
+        let vertices: Vec<ClipVertex> = (0..mesh.positions.len()).map(|i| {
+            let p = mesh.positions[i];
+            let wp = transform_point(world, p);
+            let vp = transform_point(&cam.view, wp);
+            let clip = transform_point4(&view_proj, wp);
+            let n = mesh.normals.get(i).copied().unwrap_or([0.0, 0.0, 1.0]);
+            let wn = transform_vector(world, n);
+            // W3D CLOD UVs are in [-0.5, 0.5]; IFX flips V via the texture matrix
+            let uv = mesh.tex_coords.get(i).copied().unwrap_or([0.0, 0.0]);
+            let uv2 = mesh.tex_coords2.and_then(|t| t.get(i).copied()).unwrap_or(uv);
+            let color = mesh.colors.and_then(|c| c.get(i).copied()).unwrap_or([1.0, 1.0, 1.0, 1.0]);
+
+            let mut attrs = [0.0f32; ATTR_COUNT];
+            attrs[ATTR_WORLD..ATTR_WORLD + 3].copy_from_slice(&wp);
+            attrs[ATTR_NORMAL..ATTR_NORMAL + 3].copy_from_slice(&wn);
+            attrs[ATTR_UV] = uv[0] + 0.5;
+            attrs[ATTR_UV + 1] = 0.5 - uv[1];
+            attrs[ATTR_UV2] = uv2[0] + 0.5;
+            attrs[ATTR_UV2 + 1] = 0.5 - uv2[1];
+            attrs[ATTR_COLOR..ATTR_COLOR + 4].copy_from_slice(&color);
+            attrs[ATTR_VIEW_DIST] = -vp[2];
+            ClipVertex { clip, attrs }
+        }).collect();
+
+        let has_vertex_colors = mesh.colors.is_some();
+        for face in mesh.faces {
+            let (Some(a), Some(b), Some(c)) = (
+                vertices.get(face[0] as usize),
+                vertices.get(face[1] as usize),
+                vertices.get(face[2] as usize),
+            ) else {
+                continue;
+            };
+            for tri in clip_near(&[*a, *b, *c]) {
+                self.raster_triangle(&tri, surface, state, cam, lights, fog, has_vertex_colors);
+            }
+        }
+    }
+
+    fn to_screen(&self, v: &ClipVertex) -> ScreenVertex {
+        let inv_w = 1.0 / v.clip[3];
+        let mut attrs = v.attrs;
+        for a in attrs.iter_mut() {
+            *a *= inv_w;
+        }
+        ScreenVertex {
+            x: (v.clip[0] * inv_w * 0.5 + 0.5) * self.width as f32,
+            y: (0.5 - v.clip[1] * inv_w * 0.5) * self.height as f32,
+            z: v.clip[2] * inv_w,
+            inv_w,
+            attrs,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn raster_triangle(
+        &mut self,
+        tri: &[ClipVertex; 3],
+        surface: &SurfaceShading,
+        state: &DrawState,
+        cam: &CameraSetup,
+        lights: &SceneLights,
+        fog: Option<&FogParams>,
+        has_vertex_colors: bool,
+    ) {
+        let v = [self.to_screen(&tri[0]), self.to_screen(&tri[1]), self.to_screen(&tri[2])];
+
+        // Signed area in screen space (Y down): negative = counter-clockwise in NDC = front face
+        let area = (v[1].x - v[0].x) * (v[2].y - v[0].y) - (v[2].x - v[0].x) * (v[1].y - v[0].y);
+        if area.abs() < 1e-8 || (state.cull_back && area > 0.0) {
+            return;
+        }
+
+        let min_x = v.iter().map(|p| p.x).fold(f32::MAX, f32::min).floor().max(0.0) as usize;
+        let max_x = (v.iter().map(|p| p.x).fold(f32::MIN, f32::max).ceil() as isize).min(self.width as isize - 1);
+        let min_y = v.iter().map(|p| p.y).fold(f32::MAX, f32::min).floor().max(0.0) as usize;
+        let max_y = (v.iter().map(|p| p.y).fold(f32::MIN, f32::max).ceil() as isize).min(self.height as isize - 1);
+        if max_x < 0 || max_y < 0 {
+            return;
+        }
+
+        for py in min_y..=max_y as usize {
+            let sy = py as f32 + 0.5;
+            for px in min_x..=max_x as usize {
+                let sx = px as f32 + 0.5;
+                let w0 = ((v[2].x - v[1].x) * (sy - v[1].y) - (v[2].y - v[1].y) * (sx - v[1].x)) / area;
+                let w1 = ((v[0].x - v[2].x) * (sy - v[2].y) - (v[0].y - v[2].y) * (sx - v[2].x)) / area;
+                let w2 = 1.0 - w0 - w1;
+                if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                    continue;
+                }
+
+                let z = w0 * v[0].z + w1 * v[1].z + w2 * v[2].z;
+                let idx = py * self.width + px;
+                if !(-1.0..=1.0).contains(&z) || z > self.depth[idx] {
+                    continue;
+                }
+
+                let inv_w = w0 * v[0].inv_w + w1 * v[1].inv_w + w2 * v[2].inv_w;
+                let mut attrs = [0.0f32; ATTR_COUNT];
+                for (i, a) in attrs.iter_mut().enumerate() {
+                    *a = (w0 * v[0].attrs[i] + w1 * v[1].attrs[i] + w2 * v[2].attrs[i]) / inv_w;
+                }
+
+                let frag = shade_fragment(&attrs, surface, cam, lights, fog, has_vertex_colors);
+                if state.depth_write {
+                    self.depth[idx] = z;
+                }
+                blend_pixel(&mut self.color[idx], frag, state.blend);
+            }
+        }
+    }
+
+    // ─── Overlays / backdrops ───
+
+    /// Draw camera overlays or backdrops as textured 2D quads:
+    /// scale → rotate → translate (minus regPoint), nearest filtering.
+    fn draw_overlays(&mut self, overlays: &[CameraOverlay]) {
+        for overlay in overlays {
+            if overlay.source_texture.is_empty() || overlay.blend <= 0.0 {
+                continue;
+            }
+            let Some(tex) = self.textures.get(&overlay.source_texture_lower).cloned() else { continue };
+            let (tex_w, tex_h) = (tex.width as f32, tex.height as f32);
+            let opacity = (overlay.blend / 100.0) as f32;
+
+            let sx = (overlay.scale * overlay.scale_x) as f32;
+            let sy = (overlay.scale * overlay.scale_y) as f32;
+            if sx.abs() < 1e-6 || sy.abs() < 1e-6 {
+                continue;
+            }
+            let (x, y) = (overlay.loc[0] as f32, overlay.loc[1] as f32);
+            let (rx, ry) = (overlay.reg_point[0] as f32, overlay.reg_point[1] as f32);
+            let rot = (overlay.rotation as f32).to_radians();
+            let (sin_r, cos_r) = rot.sin_cos();
+
+            // Map a local quad point (texture pixels) to screen space
+            let to_screen = |lx: f32, ly: f32| {
+                let ox = (lx - rx) * sx;
+                let oy = (ly - ry) * sy;
+                (x + ox * cos_r - oy * sin_r, y + ox * sin_r + oy * cos_r)
+            };
+            let corners = [to_screen(0.0, 0.0), to_screen(tex_w, 0.0), to_screen(0.0, tex_h), to_screen(tex_w, tex_h)];
+            let min_x = corners.iter().map(|c| c.0).fold(f32::MAX, f32::min).floor().max(0.0) as usize;
+            let max_x = (corners.iter().map(|c| c.0).fold(f32::MIN, f32::max).ceil() as isize).min(self.width as isize - 1);
+            let min_y = corners.iter().map(|c| c.1).fold(f32::MAX, f32::min).floor().max(0.0) as usize;
+            let max_y = (corners.iter().map(|c| c.1).fold(f32::MIN, f32::max).ceil() as isize).min(self.height as isize - 1);
+            if max_x < 0 || max_y < 0 {
+                continue;
+            }
+
+            for py in min_y..=max_y as usize {
+                for px in min_x..=max_x as usize {
+                    // Inverse transform back into texture pixels
+                    let dx = px as f32 + 0.5 - x;
+                    let dy = py as f32 + 0.5 - y;
+                    let lx = (dx * cos_r + dy * sin_r) / sx + rx;
+                    let ly = (-dx * sin_r + dy * cos_r) / sy + ry;
+                    if lx < 0.0 || ly < 0.0 || lx >= tex_w || ly >= tex_h {
+                        continue;
+                    }
+                    let o = (ly as usize * tex.width as usize + lx as usize) * 4;
+                    let texel = &tex.rgba[o..o + 4];
+                    let frag = [
+                        texel[0] as f32 / 255.0,
+                        texel[1] as f32 / 255.0,
+                        texel[2] as f32 / 255.0,
+                        opacity * texel[3] as f32 / 255.0,
+                    ];
+                    blend_pixel(&mut self.color[py * self.width + px], frag, SurfaceBlend::Alpha);
+                }
+            }
+        }
+    }
+}
+
+// ─── Fragment shading (mirrors the WebGL2 fragment shader) ───
+
+fn shade_fragment(
+    attrs: &[f32; ATTR_COUNT],
+    surface: &SurfaceShading,
+    cam: &CameraSetup,
+    lights: &SceneLights,
+    fog: Option<&FogParams>,
+    has_vertex_colors: bool,
+) -> [f32; 4] {
+    let pos = [attrs[ATTR_WORLD], attrs[ATTR_WORLD + 1], attrs[ATTR_WORLD + 2]];
+    let n = normalize([attrs[ATTR_NORMAL], attrs[ATTR_NORMAL + 1], attrs[ATTR_NORMAL + 2]]);
+    let v = normalize(sub(cam.position, pos));
+    let uv = [attrs[ATTR_UV], attrs[ATTR_UV + 1]];
+    let uv2 = [attrs[ATTR_UV2], attrs[ATTR_UV2 + 1]];
+
+    let toon = |diff: f32| match surface.toon_steps {
+        Some(steps) if steps > 0.0 => (diff * steps + 0.5).floor() / steps,
+        _ => diff,
+    };
+    let apply_layers = |mut color: [f32; 3]| {
+        for layer in &surface.layers {
+            let s = sample_bilinear(layer.texture, uv2, layer.wrap);
+            for c in 0..3 {
+                color[c] = match layer.mode {
+                    1 => color[c] * (1.0 + (s[c] - 1.0) * layer.intensity),
+                    _ => color[c] + s[c] * layer.intensity,
+                };
+            }
+        }
+        color
+    };
+
+    let (mut result, alpha) = if let Some(binding) = &surface.texture {
+        let t = transform_uv(&binding.transform, uv);
+        let tex = sample_bilinear(binding.texture, t, binding.wrap);
+
+        // IFX fixed-function lighting, clamped before GL_MODULATE
+        let mut lighting = add(surface.emissive, mul(lights.global_ambient, surface.ambient));
+        for light in &lights.lights {
+            let (l, atten) = light_vector(light, pos, true);
+            let diff = toon(dot(n, l).abs());
+            lighting = add(lighting, scale(mul(light.color, rgb(surface.diffuse)), atten * diff));
+        }
+        let lighting = lighting.map(|c| c.clamp(0.0, 1.0));
+        (apply_layers(mul([tex[0], tex[1], tex[2]], lighting)), surface.opacity * tex[3])
+    } else {
+        let base = if has_vertex_colors {
+            [attrs[ATTR_COLOR], attrs[ATTR_COLOR + 1], attrs[ATTR_COLOR + 2]]
+        } else {
+            rgb(surface.diffuse)
+        };
+        let mut result = surface.emissive;
+        if !surface.layers.is_empty() {
+            // Lightmap-only shaders: the baked lightmap multiplies the material color
+            result = add(result, base);
+        } else {
+            result = add(result, mul(lights.global_ambient, surface.ambient));
+            for light in &lights.lights {
+                let (l, atten) = light_vector(light, pos, false);
+                let diff = toon(dot(n, l).abs());
+                result = add(result, scale(mul(light.color, base), atten * diff));
+                if surface.shininess > 0.0 && diff > 0.0 {
+                    let h = normalize(add(l, v));
+                    let spec = dot(n, h).max(0.0).powf(surface.shininess);
+                    result = add(result, scale(mul(light.color, surface.specular), spec * atten));
+                }
+            }
+        }
+        (apply_layers(result), surface.opacity * surface.diffuse[3])
+    };
+
+    if let Some(fog) = fog {
+        let dist = attrs[ATTR_VIEW_DIST];
+        let range = (fog.far - fog.near).max(1e-6);
+        let factor = match fog.mode {
+            0 => (fog.far - dist) / range,
+            1 => (-(2.0 / range) * dist).exp(),
+            _ => {
+                let density = 2.0 / range;
+                (-density * density * dist * dist).exp()
+            }
+        }
+        .clamp(0.0, 1.0);
+        for (r, f) in result.iter_mut().zip(fog.color) {
+            *r = f + (*r - f) * factor;
+        }
+    }
+
+    [result[0], result[1], result[2], alpha.clamp(0.0, 1.0)]
+}
+
+/// Direction to the light and its attenuation at `pos`. Textured surfaces use
+/// the light's own attenuation and spot cone; untextured ones use IFX's fixed
+/// falloff, as in the GPU shader.
+fn light_vector(light: &LightParams, pos: [f32; 3], textured: bool) -> ([f32; 3], f32) {
+    if light.kind == LightKind::Directional {
+        return (normalize(light.position), 1.0);
+    }
+    let to_light = sub(light.position, pos);
+    let dist = length(to_light).max(1e-6);
+    let l = scale(to_light, 1.0 / dist);
+    if !textured {
+        return (l, 1.0 / (1.0 + 0.01 * dist + 0.0001 * dist * dist));
+    }
+    let [c, li, q] = light.attenuation;
+    let mut atten = 1.0 / (c + li * dist + q * dist * dist).max(1e-6);
+    if light.spot_angle > 0.0 {
+        let spot_cos = dot(scale(l, -1.0), light.direction);
+        let cone_cos = (light.spot_angle * 0.5).cos();
+        if spot_cos < cone_cos {
+            atten = 0.0;
+        } else {
+            atten *= smoothstep(cone_cos, cone_cos + 0.1, spot_cos);
+        }
+    }
+    (l, atten)
+}
+
+fn blend_pixel(dst: &mut [f32; 4], src: [f32; 4], blend: SurfaceBlend) {
+    match blend {
+        SurfaceBlend::Opaque => *dst = [src[0], src[1], src[2], 1.0],
+        SurfaceBlend::Alpha => {
+            let a = src[3];
+            for c in 0..3 {
+                dst[c] = src[c] * a + dst[c] * (1.0 - a);
+            }
+            dst[3] = a + dst[3] * (1.0 - a);
+        }
+        SurfaceBlend::Additive => {
+            for c in 0..3 {
+                dst[c] = (dst[c] + src[c] * src[3]).min(1.0);
+            }
+        }
+    }
+}
+
+/// Bilinear texture sample with per-axis wrap (0 = clamp, otherwise repeat).
+fn sample_bilinear(tex: &DecodedTexture, uv: [f32; 2], wrap: (u8, u8)) -> [f32; 4] {
+    let (w, h) = (tex.width as i64, tex.height as i64);
+    let x = uv[0] * w as f32 - 0.5;
+    let y = uv[1] * h as f32 - 0.5;
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let wrap_coord = |c: i64, size: i64, mode: u8| {
+        if mode == 0 { c.clamp(0, size - 1) } else { c.rem_euclid(size) }
+    };
+    let texel = |tx: i64, ty: i64| {
+        let tx = wrap_coord(tx, w, wrap.0);
+        let ty = wrap_coord(ty, h, wrap.1);
+        let o = ((ty * w + tx) * 4) as usize;
+        let p = &tex.rgba[o..o + 4];
+        [p[0] as f32 / 255.0, p[1] as f32 / 255.0, p[2] as f32 / 255.0, p[3] as f32 / 255.0]
+    };
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let (t00, t10, t01, t11) = (texel(x0, y0), texel(x0 + 1, y0), texel(x0, y0 + 1), texel(x0 + 1, y0 + 1));
+    let mut out = [0.0f32; 4];
+    for c in 0..4 {
+        let top = t00[c] + (t10[c] - t00[c]) * fx;
+        let bottom = t01[c] + (t11[c] - t01[c]) * fx;
+        out[c] = top + (bottom - top) * fy;
+    }
+    out
+}
+
+// ─── Near-plane clipping ───
+
+/// Clip a triangle against the near plane (z >= -w) and fan the result.
+fn clip_near(tri: &[ClipVertex; 3]) -> Vec<[ClipVertex; 3]> {
+    let inside = |v: &ClipVertex| v.clip[2] + v.clip[3] >= 0.0 && v.clip[3] > 1e-6;
+    if tri.iter().all(inside) {
+        return vec![*tri];
+    }
+    let dist = |v: &ClipVertex| v.clip[2] + v.clip[3];
+    let mut poly: Vec<ClipVertex> = Vec::with_capacity(4);
+    for i in 0..3 {
+        let a = &tri[i];
+        let b = &tri[(i + 1) % 3];
+        let (da, db) = (dist(a), dist(b));
+        if da >= 0.0 {
+            poly.push(*a);
+        }
+        if (da >= 0.0) != (db >= 0.0) {
+            let t = da / (da - db);
+            let mut v = *a;
+            for k in 0..4 {
+                v.clip[k] = a.clip[k] + (b.clip[k] - a.clip[k]) * t;
+            }
+            for k in 0..ATTR_COUNT {
+                v.attrs[k] = a.attrs[k] + (b.attrs[k] - a.attrs[k]) * t;
+            }
+            poly.push(v);
+        }
+    }
+    poly.retain(|v| v.clip[3] > 1e-6);
+    (1..poly.len().saturating_sub(1))
+        .map(|i| [poly[0], poly[i], poly[i + 1]])
+        .collect()
+}
+
+// ─── Scene lookups (mirroring the WebGL2 renderer) ───
+
+fn node_resource_name(node: &W3dNode) -> &str {
+    if !node.model_resource_name.is_empty() {
+        &node.model_resource_name
+    } else {
+        &node.resource_name
+    }
+}
+
+fn is_skybox(node: &W3dNode) -> bool {
+    node.name.starts_with("SB_") && node.parent_name.contains("SkyBox")
+}
+
+fn lookup_ci<'m, V>(map: &'m HashMap<String, V>, name: &str) -> Option<&'m V> {
+    map.get(name)
+        .or_else(|| map.get(&name.to_ascii_lowercase()))
+        .or_else(|| map.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v))
+}
+
+fn find_material_ci<'s>(materials: &'s [W3dMaterial], name: &str) -> Option<&'s W3dMaterial> {
+    materials.iter().find(|m| m.name.eq_ignore_ascii_case(name))
+}
+
+/// Material for a shader: its material_name, else a material named after the shader.
+fn shader_material<'s>(scene: &'s W3dScene, shader: &W3dShader) -> Option<&'s W3dMaterial> {
+    Some(&shader.material_name)
+        .filter(|n| !n.is_empty())
+        .and_then(|n| find_material_ci(&scene.materials, n))
+        .or_else(|| find_material_ci(&scene.materials, &shader.name))
+}
+
+fn resolve_material_candidate_ci<'s>(scene: &'s W3dScene, candidate: &str) -> Option<&'s W3dMaterial> {
+    find_material_ci(&scene.materials, candidate).or_else(|| {
+        find_shader_ci(&scene.shaders, candidate)
+            .and_then(|s| find_material_ci(&scene.materials, &s.material_name))
+    })
+}
+
+// ─── Math helpers (column-major 4x4) ───
+
+fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> [f32; 16] {
+    let rl = right - left;
+    let tb = top - bottom;
+    let fn_ = far - near;
+    [
+        2.0 / rl, 0.0, 0.0, 0.0,
+        0.0, 2.0 / tb, 0.0, 0.0,
+        0.0, 0.0, -2.0 / fn_, 0.0,
+        -(right + left) / rl, -(top + bottom) / tb, -(far + near) / fn_, 1.0,
+    ]
+}
+
+fn transform_point(m: &[f32; 16], p: [f32; 3]) -> [f32; 3] {
+    [
+        m[0] * p[0] + m[4] * p[1] + m[8] * p[2] + m[12],
+        m[1] * p[0] + m[5] * p[1] + m[9] * p[2] + m[13],
+        m[2] * p[0] + m[6] * p[1] + m[10] * p[2] + m[14],
+    ]
+}
+
+fn transform_point4(m: &[f32; 16], p: [f32; 3]) -> [f32; 4] {
+    let [x, y, z] = transform_point(m, p);
+    [x, y, z, m[3] * p[0] + m[7] * p[1] + m[11] * p[2] + m[15]]
+}
+
+fn transform_vector(m: &[f32; 16], v: [f32; 3]) -> [f32; 3] {
+    [
+        m[0] * v[0] + m[4] * v[1] + m[8] * v[2],
+        m[1] * v[0] + m[5] * v[1] + m[9] * v[2],
+        m[2] * v[0] + m[6] * v[1] + m[10] * v[2],
+    ]
+}
+
+fn transform_uv(m: &[f32; 16], uv: [f32; 2]) -> [f32; 2] {
+    [
+        m[0] * uv[0] + m[4] * uv[1] + m[12],
+        m[1] * uv[0] + m[5] * uv[1] + m[13],
+    ]
+}
+
+fn rgb(c: [f32; 4]) -> [f32; 3] { [c[0], c[1], c[2]] }
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] { [a[0] + b[0], a[1] + b[1], a[2] + b[2]] }
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] { [a[0] - b[0], a[1] - b[1], a[2] - b[2]] }
+fn mul(a: [f32; 3], b: [f32; 3]) -> [f32; 3] { [a[0] * b[0], a[1] * b[1], a[2] * b[2]] }
+fn scale(a: [f32; 3], s: f32) -> [f32; 3] { [a[0] * s, a[1] * s, a[2] * s] }
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 { a[0] * b[0] + a[1] * b[1] + a[2] * b[2] }
+fn length(a: [f32; 3]) -> f32 { dot(a, a).sqrt() }
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    let len = length(a);
+    if len < 1e-8 { a } else { scale(a, 1.0 / len) }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
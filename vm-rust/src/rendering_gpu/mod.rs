@@ -10,7 +10,7 @@ pub mod webgl2;
 use wasm_bindgen::JsCast;
 use web_sys::HtmlCanvasElement;
 
-use crate::player::{bitmap::bitmap::Bitmap, DirPlayer};
+use crate::player::{bitmap::bitmap::Bitmap, window::with_stage_context, DirPlayer};
 
 /// Renderer backend selection
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -141,18 +141,21 @@ impl DynamicRenderer {
 }
 
 impl Renderer for DynamicRenderer {
+    // Always draw the stage movie, even when called from inside a `tell
+    // window` block. Canvas2D composites movies in a window on top of the
+    // stage; the WebGL2 backend does not draw them yet.
     fn draw_frame(&mut self, player: &mut DirPlayer) {
-        match self {
+        with_stage_context(player, |player| match self {
             DynamicRenderer::Canvas2D(r) => r.draw_frame(player),
             DynamicRenderer::WebGL2(r) => r.draw_frame(player),
-        }
+        })
     }
 
     fn capture_stage_bitmap(&mut self, player: &mut DirPlayer) -> Bitmap {
-        match self {
+        with_stage_context(player, |player| match self {
             DynamicRenderer::Canvas2D(r) => r.capture_stage_bitmap(player),
             DynamicRenderer::WebGL2(r) => r.capture_stage_bitmap(player),
-        }
+        })
     }
 
     fn reset_for_new_movie(&mut self) {
@@ -103,6 +103,21 @@ pub struct WebGL2Renderer {
     trails_size: (u32, u32),
     /// Shockwave 3D scene renderer
     scene3d: scene3d::Scene3dRenderer,
+    /// Textures the active score transition is composed from
+    transition_textures: Option<TransitionTextures>,
+}
+
+/// GPU copies of both frames of a score transition, plus the map saying
+/// which of them each stage pixel is taken from.
+struct TransitionTextures {
+    /// Id of the transition the outgoing frame was uploaded for
+    id: u32,
+    /// Outgoing frame, uploaded once when the transition starts
+    from: web_sys::WebGlTexture,
+    /// New frame, copied from the framebuffer every frame
+    to: web_sys::WebGlTexture,
+    /// Per-pixel sources from `player::transition::source_map`
+    map: web_sys::WebGlTexture,
 }
 
 impl WebGL2Renderer {
@@ -179,6 +194,7 @@ impl WebGL2Renderer {
             trails_texture: None,
             trails_size: (0, 0),
             scene3d: scene3d::Scene3dRenderer::new(),
+            transition_textures: None,
         })
     }
 
@@ -346,6 +362,128 @@ impl WebGL2Renderer {
         gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
     }
 
+    /// Create the textures for transition `id`, uploading its outgoing frame.
+    fn create_transition_textures(&self, id: u32, from: &[u8], width: u32, height: u32) -> Option<TransitionTextures> {
+        let gl = self.context.gl();
+        let from_tex = self.context.create_texture().ok()?;
+        let to = self.context.create_texture().ok()?;
+        let map = self.context.create_texture().ok()?;
+        let textures = TransitionTextures { id, from: from_tex, to, map };
+        if self.context.upload_texture_rgba(&textures.from, width, height, from).is_err() {
+            Self::delete_transition_textures(gl, textures);
+            return None;
+        }
+
+        // The new frame is filled by copyTexSubImage2D, so only allocate it.
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&textures.to));
+        let _ = gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA8 as i32,
+            width as i32,
+            height as i32,
+            0,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            None,
+        );
+        gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_MIN_FILTER, WebGl2RenderingContext::NEAREST as i32);
+        gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_MAG_FILTER, WebGl2RenderingContext::NEAREST as i32);
+        gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_WRAP_S, WebGl2RenderingContext::CLAMP_TO_EDGE as i32);
+        gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_WRAP_T, WebGl2RenderingContext::CLAMP_TO_EDGE as i32);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        Some(textures)
+    }
+
+    fn delete_transition_textures(gl: &WebGl2RenderingContext, textures: TransitionTextures) {
+        gl.delete_texture(Some(&textures.from));
+        gl.delete_texture(Some(&textures.to));
+        gl.delete_texture(Some(&textures.map));
+    }
+
+    /// Compose the active score transition over the stage drawn so far.
+    /// Both frames stay on the GPU: the outgoing frame is uploaded once when
+    /// the transition starts and the new one is copied from the framebuffer.
+    /// The shader then takes each pixel from one of them, following the
+    /// source map computed by `player::transition`.
+    fn draw_transition(&mut self, player: &mut DirPlayer) {
+        let (width, height) = self.size;
+        let Some(map) = crate::player::transition::source_map(player, width, height) else {
+            if !player.transitions.is_active()
+                && let Some(textures) = self.transition_textures.take()
+            {
+                Self::delete_transition_textures(self.context.gl(), textures);
+            }
+            return;
+        };
+        let Some(active) = player.transitions.active.as_ref() else {
+            return;
+        };
+
+        if self.transition_textures.as_ref().is_none_or(|textures| textures.id != active.id) {
+            if let Some(textures) = self.transition_textures.take() {
+                Self::delete_transition_textures(self.context.gl(), textures);
+            }
+            self.transition_textures = self.create_transition_textures(active.id, &active.from, width, height);
+        }
+        let Some(textures) = self.transition_textures.as_ref() else {
+            return;
+        };
+        if self.context.upload_texture_rgba(&textures.map, width, height, &map).is_err() {
+            return;
+        }
+
+        let gl = self.context.gl();
+        gl.bind_framebuffer(WebGl2RenderingContext::READ_FRAMEBUFFER, None);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&textures.to));
+        gl.copy_tex_sub_image_2d(WebGl2RenderingContext::TEXTURE_2D, 0, 0, 0, 0, 0, width as i32, height as i32);
+
+        let program = self.shader_manager.use_transition_program(&self.context);
+        if let Some(ref loc) = program.shader.u_projection {
+            gl.uniform_matrix4fv_with_f32_array(Some(loc), false, &self.projection_matrix);
+        }
+        if let Some(ref loc) = program.shader.u_sprite_rect {
+            gl.uniform4f(Some(loc), 0.0, 0.0, width as f32, height as f32);
+        }
+        if let Some(ref loc) = program.shader.u_tex_rect {
+            gl.uniform4f(Some(loc), 0.0, 0.0, 1.0, 1.0);
+        }
+        if let Some(ref loc) = program.shader.u_flip {
+            gl.uniform2f(Some(loc), 0.0, 0.0);
+        }
+        if let Some(ref loc) = program.shader.u_rotation {
+            gl.uniform1f(Some(loc), 0.0);
+        }
+        if let Some(ref loc) = program.shader.u_skew_flip {
+            gl.uniform1f(Some(loc), 0.0);
+        }
+        if let Some(ref loc) = program.shader.u_skew {
+            gl.uniform1f(Some(loc), 0.0);
+        }
+        if let Some(ref loc) = program.shader.u_rotation_center {
+            gl.uniform2f(Some(loc), 0.0, 0.0);
+        }
+        let units = [(&program.u_from, &textures.from), (&program.u_to, &textures.to), (&program.u_map, &textures.map)];
+        for (unit, (loc, tex)) in units.into_iter().enumerate() {
+            gl.active_texture(WebGl2RenderingContext::TEXTURE0 + unit as u32);
+            gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(tex));
+            if let Some(loc) = loc {
+                gl.uniform1i(Some(loc), unit as i32);
+            }
+        }
+
+        // Every pixel is replaced, so draw without blending.
+        gl.disable(WebGl2RenderingContext::BLEND);
+        self.quad.draw(gl);
+        gl.enable(WebGl2RenderingContext::BLEND);
+
+        for unit in (0..3).rev() {
+            gl.active_texture(WebGl2RenderingContext::TEXTURE0 + unit);
+            gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        }
+        self.shader_manager.clear_active();
+    }
+
     /// Draw the current frame
     pub fn draw_frame(&mut self, player: &mut DirPlayer) {
         self.frame_count += 1;
@@ -357,6 +495,8 @@ impl WebGL2Renderer {
         crate::player::handlers::datum_handlers::shockwave3d_object::sync_persistent_transforms(player);
         crate::player::handlers::datum_handlers::shockwave3d_object::sync_shader_texture_lists(player);
 
+        crate::player::palette_effects::update(player);
+
         // Check if palettes changed and clear texture cache if so
         // This handles external cast loading where palette members may load after initial render
         let current_palette_version = player.movie.cast_manager.palette_version();
@@ -466,6 +606,9 @@ impl WebGL2Renderer {
             self.destroy_trails_fbo();
         }
 
+        // Blend in the previous frame while a score transition is playing
+        self.draw_transition(player);
+
         // Draw custom cursor sprite
         self.draw_cursor(player);
 
@@ -599,7 +742,7 @@ impl WebGL2Renderer {
         };
 
         // Build RGBA data with mask applied to alpha
-        let palettes = player.movie.cast_manager.palettes();
+        let palettes = player.movie.cast_manager.screen_palettes();
         let w = cursor_bitmap.width as u32;
         let h = cursor_bitmap.height as u32;
         let mut rgba = vec![0u8; (w * h * 4) as usize];
@@ -711,7 +854,7 @@ impl WebGL2Renderer {
 
     /// Get stage background color as normalized floats
     fn get_stage_bg_color(&self, player: &DirPlayer) -> (f32, f32, f32) {
-        let palettes = player.movie.cast_manager.palettes();
+        let palettes = player.movie.cast_manager.screen_palettes();
         let (r, g, b) = resolve_color_ref(
             &palettes,
             &player.bg_color,
@@ -760,7 +903,7 @@ impl WebGL2Renderer {
         // Bitmap::new initializes 32-bit data to 255 (white/opaque)
         // We'll make white pixels transparent after rendering text
 
-        let palettes = player.movie.cast_manager.palettes();
+        let palettes = player.movie.cast_manager.screen_palettes();
 
         // Set up copy parameters for text rendering with background transparent ink
         let params = CopyPixelsParams { mask_offset: (0, 0),
@@ -1298,7 +1441,7 @@ impl WebGL2Renderer {
                     );
                 }
                 let mut resolved: Vec<(String, Vec<u8>)> = Vec::new();
-                let palettes = player.movie.cast_manager.palettes();
+                let palettes = player.movie.cast_manager.screen_palettes();
                 for tex_name in &placeholder_names {
                     let tex_name_str = tex_name.to_string();
                     let found_ref = player.movie.cast_manager.find_member_ref_by_name(&tex_name_str);
@@ -1443,8 +1586,8 @@ impl WebGL2Renderer {
                         return;
                     }
 
-                    let palettes = player.movie.cast_manager.palettes();
-                    let frame_palette = player.movie.score.get_frame_palette(player.movie.current_frame);
+                    let palettes = player.movie.cast_manager.screen_palettes();
+                    let frame_palette = crate::player::palette_effects::active_palette(player);
                     let fg_rgb = resolve_color_ref(
                         &palettes,
                         &fg_color,
@@ -1818,7 +1961,7 @@ impl WebGL2Renderer {
                     // Resolve PaletteIndex to RGB so span color assignment gets real colors
                     let text_fg_color = match &text_fg_color {
                         ColorRef::PaletteIndex(_) => {
-                            let palettes = player.movie.cast_manager.palettes();
+                            let palettes = player.movie.cast_manager.screen_palettes();
                             let (r, g, b) = resolve_color_ref(
                                 &palettes,
                                 &text_fg_color,
@@ -1840,7 +1983,7 @@ impl WebGL2Renderer {
                     };
                     let text_bg_color = match &effective_bg {
                         ColorRef::PaletteIndex(_) => {
-                            let palettes = player.movie.cast_manager.palettes();
+                            let palettes = player.movie.cast_manager.screen_palettes();
                             let (r, g, b) = resolve_color_ref(
                                 &palettes,
                                 &effective_bg,
@@ -1950,6 +2093,8 @@ impl WebGL2Renderer {
                             }
                         }).collect())
                     };
+                    let styled_spans_with_defaults =
+                        styled_spans_with_defaults.map(|spans| text_member.apply_hyperlink_styles(spans));
 
                     // Build cache key from the final styled spans that will actually be rendered.
                     let styled_spans_ref = styled_spans_with_defaults.as_ref().map(|s| s.as_slice());
@@ -2459,7 +2604,7 @@ impl WebGL2Renderer {
         };
 
         // Resolve colors to RGB for shader uniforms and colorize
-        let palettes = player.movie.cast_manager.palettes();
+        let palettes = player.movie.cast_manager.screen_palettes();
         // Sprite foreColor/backColor palette indices are resolved against the bitmap's palette,
         // so they work together correctly (e.g., index 248/255 in a custom 256-color palette).
         let bg_color_rgb = resolve_color_ref(
@@ -2775,7 +2920,7 @@ impl WebGL2Renderer {
                 btn_bitmap.use_alpha = true;
                 btn_bitmap.data.fill(0); // Start fully transparent
 
-                let palettes = player.movie.cast_manager.palettes();
+                let palettes = player.movie.cast_manager.screen_palettes();
 
                 // Only push buttons invert everything; radio/checkbox keep black text
                 let is_push = matches!(button_type, ButtonType::PushButton);
@@ -3018,7 +3163,7 @@ impl WebGL2Renderer {
                 shape_bitmap.use_alpha = true;
                 shape_bitmap.data.fill(0);
 
-                let palettes = player.movie.cast_manager.palettes();
+                let palettes = player.movie.cast_manager.screen_palettes();
                 let filled = shape_info.fill_type != 0;
                 let thickness = if filled {
                     (shape_info.line_thickness as i32).max(1)
@@ -3089,7 +3234,7 @@ impl WebGL2Renderer {
                 shape_bitmap.use_alpha = true;
                 shape_bitmap.data.fill(0);
 
-                let palettes = player.movie.cast_manager.palettes();
+                let palettes = player.movie.cast_manager.screen_palettes();
                 let dst_rect = IntRect::from(0, 0, w, h);
                 shape_bitmap.draw_vector_shape(&vector_member, dst_rect, &palettes, 1.0);
 
@@ -4237,7 +4382,7 @@ impl WebGL2Renderer {
                 .map(|b| b.matte.is_none() && !(b.original_bit_depth == 32 && b.use_alpha))
                 .unwrap_or(false);
             if needs_matte {
-                let palettes = player.movie.cast_manager.palettes();
+                let palettes = player.movie.cast_manager.screen_palettes();
                 if let Some(bitmap) = player.bitmap_manager.get_bitmap_mut(image_ref) {
                     bitmap.create_matte(&palettes);
                 }
@@ -4295,7 +4440,7 @@ impl WebGL2Renderer {
         }
 
         // Convert bitmap to RGBA format with ink for matte computation
-        let palettes = player.movie.cast_manager.palettes();
+        let palettes = player.movie.cast_manager.screen_palettes();
 
         // Only log on the very first frame for any new texture
         let _is_first_creation = self.frame_count == 1 && !self.texture_cache.has(&cache_key);
@@ -4751,7 +4896,7 @@ impl WebGL2Renderer {
         // Background detection then simply checks alpha==0.
         text_bitmap.data.fill(0);
 
-        let palettes = player.movie.cast_manager.palettes();
+        let palettes = player.movie.cast_manager.screen_palettes();
 
         // Check glyph preference to allow runtime switching
         let glyph_pref = get_glyph_preference();
@@ -15,6 +15,11 @@ use log::debug;
 use super::context::WebGL2Context;
 use super::mesh3d::Mesh3dBuffers;
 use crate::{
+    director::chunks::w3d::render_helpers::{
+        find_shader_ci, get_runtime_transform, has_detached_ancestor, invert_transform, is_child_of,
+        mat4_multiply_col_major, node_shader_override, perspective, resolve_shader_candidate_ci,
+    },
+    director::chunks::w3d::texture::{decode_texture_rgba, generate_uvs_by_mode},
     director::chunks::w3d::types::*,
     console_warn,
 };
@@ -1459,13 +1464,13 @@ void main() {
 
                     if let Some(ref root) = root_node_filter {
                         // Camera has rootNode: only render nodes in that subtree
-                        self.is_child_of(scene, &n.name, root)
+                        is_child_of(scene, &n.name, root)
                     } else {
                         // No rootNode: render world-visible models only.
                         // Skip models whose parent (or ancestor) is detached — they belong
                         // to a different camera's rootNode subtree (e.g., overlay HUD models
                         // parented to a detached "overlays" camera).
-                        !self.has_detached_ancestor(scene, &n.parent_name, &detached_nodes)
+                        !has_detached_ancestor(scene, &n.parent_name, &detached_nodes)
                     }
                 })
                 .collect();
@@ -2055,19 +2060,6 @@ void main() {
     }
 
     /// Get the opacity of a model node's material (for transparency sorting).
-    /// Look up a per-model shader override.  Returns the first available:
-    /// mesh-specific index → index 0 fallback → None.
-    fn node_shader_override<'a>(
-        rs: &'a crate::player::cast_member::Shockwave3dRuntimeState,
-        node_name: &str,
-        mesh_idx: Option<usize>,
-    ) -> Option<&'a String> {
-        rs.node_shaders.get(node_name).and_then(|m| {
-            mesh_idx.and_then(|idx| m.get(&idx))
-                .or_else(|| m.get(&0))
-        })
-    }
-
     fn get_model_opacity(
         &self,
         scene: &W3dScene,
@@ -2076,11 +2068,11 @@ void main() {
     ) -> f32 {
         // 1. Check node-level shader override
         let effective_shader_name = runtime_state
-            .and_then(|rs| Self::node_shader_override(rs, &model_node.name, None))
+            .and_then(|rs| node_shader_override(rs, &model_node.name, None))
             .cloned()
             .unwrap_or_else(|| model_node.shader_name.clone());
         if !effective_shader_name.is_empty() {
-            if let Some(w3d_shader) = Self::find_shader_ci(&scene.shaders, &effective_shader_name) {
+            if let Some(w3d_shader) = find_shader_ci(&scene.shaders, &effective_shader_name) {
                 if let Some(mat) = Self::find_material_ci(&scene.materials, &w3d_shader.material_name) {
                     return mat.opacity;
                 }
@@ -2098,7 +2090,7 @@ void main() {
         if let Some(res_info) = scene.model_resources.get(resource) {
             for binding in &res_info.shader_bindings {
                 for shader_name in &binding.mesh_bindings {
-                    if let Some(w3d_shader) = Self::find_shader_ci(&scene.shaders, shader_name) {
+                    if let Some(w3d_shader) = find_shader_ci(&scene.shaders, shader_name) {
                         let mat = if !w3d_shader.material_name.is_empty() {
                             Self::find_material_ci(&scene.materials, &w3d_shader.material_name)
                         } else {
@@ -2117,7 +2109,7 @@ void main() {
         if let Some(rs) = runtime_state {
             if let Some(shader_map) = rs.node_shaders.get(&model_node.name) {
                 for shader_name in shader_map.values() {
-                    if let Some(w3d_shader) = Self::find_shader_ci(&scene.shaders, shader_name) {
+                    if let Some(w3d_shader) = find_shader_ci(&scene.shaders, shader_name) {
                         let mat = if !w3d_shader.material_name.is_empty() {
                             Self::find_material_ci(&scene.materials, &w3d_shader.material_name)
                         } else {
@@ -2179,7 +2171,7 @@ void main() {
 
         // Check if any shader's texture layers reference an alpha texture
         for shader_name in &shader_names {
-            if let Some(w3d_shader) = Self::find_shader_ci(&scene.shaders, shader_name) {
+            if let Some(w3d_shader) = find_shader_ci(&scene.shaders, shader_name) {
                 for layer in &w3d_shader.texture_layers {
                     if gpu_data.alpha_textures.contains(&layer.name.to_lowercase()) {
                         return true;
@@ -2689,9 +2681,9 @@ void main() {
         let has_inker = scene.nodes.iter().any(|n| {
             if n.node_type != W3dNodeType::Model { return false; }
             let shader_name = runtime_state
-                .and_then(|rs| Self::node_shader_override(rs, &n.name, None))
+                .and_then(|rs| node_shader_override(rs, &n.name, None))
                 .unwrap_or(&n.shader_name);
-            Self::find_shader_ci(&scene.shaders, shader_name)
+            find_shader_ci(&scene.shaders, shader_name)
                 .map(|s| s.shader_type == W3dShaderType::Inker)
                 .unwrap_or(false)
         });
@@ -2713,9 +2705,9 @@ void main() {
 
         for model_node in scene.nodes.iter().filter(|n| n.node_type == W3dNodeType::Model) {
             let shader_name = runtime_state
-                .and_then(|rs| Self::node_shader_override(rs, &model_node.name, None))
+                .and_then(|rs| node_shader_override(rs, &model_node.name, None))
                 .unwrap_or(&model_node.shader_name);
-            let w3d_shader = match Self::find_shader_ci(&scene.shaders, shader_name) {
+            let w3d_shader = match find_shader_ci(&scene.shaders, shader_name) {
                 Some(s) if s.shader_type == W3dShaderType::Inker => s,
                 _ => continue,
             };
@@ -2750,32 +2742,16 @@ void main() {
         Ok(())
     }
 
-    /// Case-insensitive shader lookup (W3D files have inconsistent casing).
-    fn find_shader_ci<'a>(shaders: &'a [W3dShader], name: &str) -> Option<&'a W3dShader> {
-        shaders.iter().find(|s| s.name.eq_ignore_ascii_case(name))
-    }
-
     /// Case-insensitive material lookup.
     fn find_material_ci<'a>(materials: &'a [W3dMaterial], name: &str) -> Option<&'a W3dMaterial> {
         materials.iter().find(|m| m.name.eq_ignore_ascii_case(name))
     }
 
-    /// Find the first shader that references a material by name.
-    fn find_shader_for_material_ci<'a>(scene: &'a W3dScene, material_name: &str) -> Option<&'a W3dShader> {
-        scene.shaders.iter().find(|s| s.material_name.eq_ignore_ascii_case(material_name))
-    }
-
-    /// Resolve a candidate name to a shader, allowing either shader names or material names.
-    fn resolve_shader_candidate_ci<'a>(scene: &'a W3dScene, candidate: &str) -> Option<&'a W3dShader> {
-        Self::find_shader_ci(&scene.shaders, candidate)
-            .or_else(|| Self::find_shader_for_material_ci(scene, candidate))
-    }
-
     /// Resolve a candidate name to a material, allowing either material names or shader names.
     fn resolve_material_candidate_ci<'a>(scene: &'a W3dScene, candidate: &str) -> Option<&'a W3dMaterial> {
         Self::find_material_ci(&scene.materials, candidate)
             .or_else(|| {
-                Self::find_shader_ci(&scene.shaders, candidate)
+                find_shader_ci(&scene.shaders, candidate)
                     .and_then(|s| Self::find_material_ci(&scene.materials, &s.material_name))
             })
     }
@@ -2955,12 +2931,12 @@ void main() {
 
         // Check runtime shader override first
         let effective_shader_name = runtime_state
-            .and_then(|rs| Self::node_shader_override(rs, &model_node.name, None))
+            .and_then(|rs| node_shader_override(rs, &model_node.name, None))
             .cloned()
             .unwrap_or_else(|| model_node.shader_name.clone());
 
         if !effective_shader_name.is_empty() {
-            if let Some(w3d_shader) = Self::find_shader_ci(&scene.shaders, &effective_shader_name) {
+            if let Some(w3d_shader) = find_shader_ci(&scene.shaders, &effective_shader_name) {
                 // Find material: try shader's material_name, then shader name itself
                 let mat = if !w3d_shader.material_name.is_empty() {
                     Self::find_material_ci(&scene.materials, &w3d_shader.material_name)
@@ -2989,7 +2965,7 @@ void main() {
             if let Some(res_info) = scene.model_resources.get(resource) {
                 if let Some(binding) = res_info.shader_bindings.first() {
                     // Resolve binding name → shader → material
-                    if let Some(w3d_shader) = Self::find_shader_ci(&scene.shaders, &binding.name) {
+                    if let Some(w3d_shader) = find_shader_ci(&scene.shaders, &binding.name) {
                         if let Some(mat) = Self::find_material_ci(&scene.materials, &w3d_shader.material_name) {
                             self.set_material_uniforms(gl, shader, mat);
                             mat_found = true;
@@ -3014,7 +2990,7 @@ void main() {
         }
 
         // Set shader mode based on shader type (NPR support)
-        let w3d_shader_opt = Self::find_shader_ci(&scene.shaders, &effective_shader_name);
+        let w3d_shader_opt = find_shader_ci(&scene.shaders, &effective_shader_name);
         if let Some(w3d_shader) = w3d_shader_opt {
             use crate::director::chunks::w3d::types::W3dShaderType;
             match w3d_shader.shader_type {
@@ -3053,10 +3029,10 @@ void main() {
     /// Get the first texture layer's blend_func for a model node
     fn get_first_blend_func(&self, scene: &W3dScene, node: &W3dNode, runtime_state: Option<&crate::player::cast_member::Shockwave3dRuntimeState>) -> u8 {
         let effective_shader = runtime_state
-            .and_then(|rs| Self::node_shader_override(rs, &node.name, None))
+            .and_then(|rs| node_shader_override(rs, &node.name, None))
             .cloned()
             .unwrap_or_else(|| node.shader_name.clone());
-        Self::find_shader_ci(&scene.shaders, &effective_shader)
+        find_shader_ci(&scene.shaders, &effective_shader)
             .and_then(|s| s.texture_layers.first())
             .map(|l| l.blend_func)
             .unwrap_or(0)
@@ -3077,9 +3053,9 @@ void main() {
     ) -> bool {
         // Check per-mesh shader override first (from Lingo shaderList[I] = shaderRef)
         if let Some(override_name) = runtime_state
-            .and_then(|rs| Self::node_shader_override(rs, &model_node.name, Some(mesh_idx)))
+            .and_then(|rs| node_shader_override(rs, &model_node.name, Some(mesh_idx)))
         {
-            if let Some(w3d_shader) = Self::find_shader_ci(&scene.shaders, override_name) {
+            if let Some(w3d_shader) = find_shader_ci(&scene.shaders, override_name) {
                 let mat = if !w3d_shader.material_name.is_empty() {
                     Self::find_material_ci(&scene.materials, &w3d_shader.material_name)
                 } else { None }
@@ -3138,7 +3114,7 @@ void main() {
         }
 
         let effective_shader_name = runtime_state
-            .and_then(|rs| Self::node_shader_override(rs, &model_node.name, None))
+            .and_then(|rs| node_shader_override(rs, &model_node.name, None))
             .cloned()
             .unwrap_or_else(|| model_node.shader_name.clone());
         if !effective_shader_name.is_empty() {
@@ -3159,7 +3135,7 @@ void main() {
                 continue;
             }
 
-            let w3d_shader = Self::resolve_shader_candidate_ci(scene, candidate);
+            let w3d_shader = resolve_shader_candidate_ci(scene, candidate);
             let mat = Self::resolve_material_candidate_ci(scene, candidate)
                 .or_else(|| {
                     w3d_shader.and_then(|s| {
@@ -3415,38 +3391,6 @@ void main() {
         gl.uniform1i(shader.u_has_texture.as_ref(), 0);
     }
 
-    /// Check if a node is a child (direct or indirect) of a given root node
-    fn is_child_of(&self, scene: &W3dScene, node_name: &str, root_name: &str) -> bool {
-        if node_name == root_name { return true; }
-        let mut current = node_name;
-        for _ in 0..20 { // max depth to prevent infinite loops
-            if let Some(node) = scene.nodes.iter().find(|n| n.name == current) {
-                if node.parent_name == root_name { return true; }
-                if node.parent_name.is_empty() { return false; }
-                current = &node.parent_name;
-            } else {
-                return false;
-            }
-        }
-        false
-    }
-
-    /// Check if any ancestor in the parent chain is in the detached set
-    fn has_detached_ancestor(&self, scene: &W3dScene, parent_name: &str, detached: &std::collections::HashSet<&str>) -> bool {
-        if parent_name.is_empty() || parent_name == "World" { return false; }
-        if detached.contains(parent_name) { return true; }
-        // Walk up parent chain
-        for _ in 0..10 {
-            if let Some(node) = scene.nodes.iter().find(|n| n.name == parent_name) {
-                if node.parent_name.is_empty() || node.parent_name == "World" { return false; }
-                if detached.contains(node.parent_name.as_str()) { return true; }
-                return self.has_detached_ancestor(scene, &node.parent_name, detached);
-            }
-            return false;
-        }
-        false
-    }
-
     /// Build view matrix from scene's ViewNode (or default camera)
     fn build_view_matrix(
         &self,
@@ -3627,7 +3571,7 @@ void main() {
                 if let Some(ref cam) = self.active_camera {
                     if let Some(rs) = runtime_state {
                         if let Some(root) = rs.camera_root_nodes.get(&cam.to_ascii_lowercase()) {
-                            if !self.is_child_of(scene, &light.name, root) {
+                            if !is_child_of(scene, &light.name, root) {
                                 continue;
                             }
                         }
@@ -3724,64 +3668,14 @@ void main() {
 /// Decode image data (raw RGBA, DXT, JPEG/PNG) and upload as a WebGL2 texture.
 /// Free function to avoid borrow conflicts when called during incremental updates.
 fn decode_and_upload_texture_impl(context: &WebGL2Context, data: &[u8]) -> Option<(WebGlTexture, u32, u32, bool)> {
-    if data.len() < 4 { return None; }
-
-    // Detection priority: JPEG/PNG magic → DXT header → raw RGBA (our own format)
-    // Raw RGBA must be checked LAST because its 8-byte header (u32 w, u32 h) can
-    // accidentally match the first bytes of DXT/JPEG/PNG data, causing misidentification
-    // (e.g., a DXT texture whose first 8 bytes happen to decode as valid small dimensions).
-    let (width, height, rgba_data) = if data.len() >= 2
-        && (data[0] == 0xFF && data[1] == 0xD8       // JPEG magic
-            || data[0] == 0x89 && data[1] == 0x50)    // PNG magic
-    {
-        let img = match image::load_from_memory(data) {
-            Ok(img) => img.to_rgba8(),
-            Err(e) => {
-                let header: Vec<String> = data.iter().take(8).map(|b| format!("{:02X}", b)).collect();
-                console_warn!(
-                    "[3D-TEX-DECODE] Failed to decode {} bytes, header=[{}]: {}",
-                    data.len(), header.join(" "), e
-                );
-                return None;
-            }
-        };
-        let w = img.width();
-        let h = img.height();
-        (w, h, img.into_raw())
-    } else if is_dxt_texture(data) {
-        // DXT compressed texture — decode to RGBA
-        match decode_dxt_to_rgba(data) {
-            Some((w, h, rgba)) => (w, h, rgba),
-            None => return None,
+    let decoded = match decode_texture_rgba(data) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            console_warn!("[3D-TEX-DECODE] {}", e);
+            return None;
         }
-    } else if data.len() >= 8 {
-        // Raw RGBA format (from newTexture #fromImageObject):
-        // first 4 bytes = width LE, next 4 bytes = height LE, rest = RGBA
-        let w = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
-        let h = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
-        let expected = 8 + (w as usize) * (h as usize) * 4;
-        if w > 0 && w <= 4096 && h > 0 && h <= 4096 && data.len() == expected {
-            (w, h, data[8..].to_vec())
-        } else {
-            // Last resort: try image library decode for other formats
-            let img = match image::load_from_memory(data) {
-                Ok(img) => img.to_rgba8(),
-                Err(e) => {
-                    let header: Vec<String> = data.iter().take(8).map(|b| format!("{:02X}", b)).collect();
-                    console_warn!(
-                        "[3D-TEX-DECODE] Failed to decode {} bytes, header=[{}]: {}",
-                        data.len(), header.join(" "), e
-                    );
-                    return None;
-                }
-            };
-            let w = img.width();
-            let h = img.height();
-            (w, h, img.into_raw())
-        }
-    } else {
-        return None;
     };
+    let (width, height, rgba_data) = (decoded.width, decoded.height, decoded.rgba);
 
     let gl = context.gl();
     let texture = gl.create_texture()?;
@@ -3794,16 +3688,6 @@ fn decode_and_upload_texture_impl(context: &WebGL2Context, data: &[u8]) -> Optio
 
     gl.pixel_storei(WebGl2RenderingContext::UNPACK_PREMULTIPLY_ALPHA_WEBGL, 0);
 
-    // Verify data size matches expected
-    let expected_size = (width as usize) * (height as usize) * 4;
-    if rgba_data.len() != expected_size {
-        console_warn!(
-            "[3D-TEX] Size mismatch! {}x{} expects {} bytes but got {}",
-            width, height, expected_size, rgba_data.len()
-        );
-        return None;
-    }
-
     let upload_result = gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
         WebGl2RenderingContext::TEXTURE_2D,
         0,
@@ -3820,105 +3704,7 @@ fn decode_and_upload_texture_impl(context: &WebGL2Context, data: &[u8]) -> Optio
     }
     gl.generate_mipmap(WebGl2RenderingContext::TEXTURE_2D);
     gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
-    // Detect if texture has meaningful alpha (any pixel alpha < 250)
-    let has_alpha = rgba_data.chunks(4).any(|p| p[3] < 250);
-    Some((texture, width, height, has_alpha))
-}
-
-// ─── DXT texture decompression ───
-
-/// Check if data looks like a DXT compressed texture.
-/// IFX stores DXT textures with a small header: width(u16), height(u16), format(u8), then blocks.
-fn is_dxt_texture(data: &[u8]) -> bool {
-    if data.len() < 5 { return false; }
-    let w = u16::from_le_bytes([data[0], data[1]]) as u32;
-    let h = u16::from_le_bytes([data[2], data[3]]) as u32;
-    if w == 0 || h == 0 || w > 4096 || h > 4096 { return false; }
-    // DXT1: 8 bytes per 4x4 block = 0.5 bytes per pixel
-    let blocks_w = (w + 3) / 4;
-    let blocks_h = (h + 3) / 4;
-    let dxt1_size = (blocks_w * blocks_h * 8) as usize;
-    let dxt3_5_size = (blocks_w * blocks_h * 16) as usize;
-    // Check if data matches DXT1 or DXT3/5 size (with 5-byte header)
-    data.len() == 5 + dxt1_size || data.len() == 5 + dxt3_5_size
-}
-
-/// Decode DXT compressed texture to RGBA. Returns (width, height, rgba_pixels).
-fn decode_dxt_to_rgba(data: &[u8]) -> Option<(u32, u32, Vec<u8>)> {
-    if data.len() < 5 { return None; }
-    let w = u16::from_le_bytes([data[0], data[1]]) as u32;
-    let h = u16::from_le_bytes([data[2], data[3]]) as u32;
-    let _format = data[4];
-    let block_data = &data[5..];
-
-    let blocks_w = (w + 3) / 4;
-    let blocks_h = (h + 3) / 4;
-    let dxt1_expected = (blocks_w * blocks_h * 8) as usize;
-    let is_dxt1 = block_data.len() == dxt1_expected;
-
-    let mut rgba = vec![0u8; (w * h * 4) as usize];
-
-    for by in 0..blocks_h {
-        for bx in 0..blocks_w {
-            let block_idx = (by * blocks_w + bx) as usize;
-            if is_dxt1 {
-                let offset = block_idx * 8;
-                if offset + 8 > block_data.len() { break; }
-                decode_dxt1_block(&block_data[offset..offset+8], &mut rgba, bx * 4, by * 4, w, h);
-            } else {
-                // DXT3/DXT5: skip 8-byte alpha block, decode 8-byte color block
-                let offset = block_idx * 16;
-                if offset + 16 > block_data.len() { break; }
-                decode_dxt1_block(&block_data[offset+8..offset+16], &mut rgba, bx * 4, by * 4, w, h);
-            }
-        }
-    }
-
-    Some((w, h, rgba))
-}
-
-/// Decode a single DXT1 4x4 color block into RGBA pixels.
-fn decode_dxt1_block(block: &[u8], rgba: &mut [u8], start_x: u32, start_y: u32, img_w: u32, img_h: u32) {
-    let c0 = u16::from_le_bytes([block[0], block[1]]);
-    let c1 = u16::from_le_bytes([block[2], block[3]]);
-
-    let r0 = ((c0 >> 11) & 0x1F) as u8;
-    let g0 = ((c0 >> 5) & 0x3F) as u8;
-    let b0 = (c0 & 0x1F) as u8;
-    let r1 = ((c1 >> 11) & 0x1F) as u8;
-    let g1 = ((c1 >> 5) & 0x3F) as u8;
-    let b1 = (c1 & 0x1F) as u8;
-
-    // Expand to 8-bit
-    let colors: [[u8; 4]; 4] = if c0 > c1 {
-        [
-            [(r0 << 3) | (r0 >> 2), (g0 << 2) | (g0 >> 4), (b0 << 3) | (b0 >> 2), 255],
-            [(r1 << 3) | (r1 >> 2), (g1 << 2) | (g1 >> 4), (b1 << 3) | (b1 >> 2), 255],
-            [((2*r0 as u16 + r1 as u16)/3) as u8 * 8 / 8, ((2*g0 as u16 + g1 as u16)/3) as u8 * 4 / 4, ((2*b0 as u16 + b1 as u16)/3) as u8 * 8 / 8, 255],
-            [((r0 as u16 + 2*r1 as u16)/3) as u8 * 8 / 8, ((g0 as u16 + 2*g1 as u16)/3) as u8 * 4 / 4, ((b0 as u16 + 2*b1 as u16)/3) as u8 * 8 / 8, 255],
-        ]
-    } else {
-        [
-            [(r0 << 3) | (r0 >> 2), (g0 << 2) | (g0 >> 4), (b0 << 3) | (b0 >> 2), 255],
-            [(r1 << 3) | (r1 >> 2), (g1 << 2) | (g1 >> 4), (b1 << 3) | (b1 >> 2), 255],
-            [((r0 as u16 + r1 as u16)/2) as u8 * 8 / 8, ((g0 as u16 + g1 as u16)/2) as u8 * 4 / 4, ((b0 as u16 + b1 as u16)/2) as u8 * 8 / 8, 255],
-            [0, 0, 0, 0], // Transparent black for DXT1 with alpha
-        ]
-    };
-
-    for py in 0..4u32 {
-        for px in 0..4u32 {
-            let x = start_x + px;
-            let y = start_y + py;
-            if x >= img_w || y >= img_h { continue; }
-            let bit_idx = (py * 4 + px) * 2;
-            let byte_idx = 4 + (bit_idx / 8) as usize;
-            let bit_offset = bit_idx % 8;
-            let color_idx = ((block[byte_idx] >> bit_offset) & 3) as usize;
-            let pixel_offset = ((y * img_w + x) * 4) as usize;
-            rgba[pixel_offset..pixel_offset+4].copy_from_slice(&colors[color_idx]);
-        }
-    }
+    Some((texture, width, height, decoded.has_alpha))
 }
 
 // ─── Bone data helpers ───
@@ -3984,30 +3770,6 @@ fn keyframe_to_column_major_matrix(kf: &crate::director::chunks::w3d::types::W3d
     ]
 }
 
-/// Case-insensitive lookup in node_transforms (Director is case-insensitive for node names).
-fn get_runtime_transform(rs: &crate::player::cast_member::Shockwave3dRuntimeState, name: &str) -> Option<[f32; 16]> {
-    if let Some(m) = rs.node_transforms.get(name) {
-        return Some(*m);
-    }
-    for (key, val) in &rs.node_transforms {
-        if key.eq_ignore_ascii_case(name) {
-            return Some(*val);
-        }
-    }
-    None
-}
-
-fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> [f32; 16] {
-    let f = 1.0 / (fov_y / 2.0).tan();
-    let nf = 1.0 / (near - far);
-    [
-        f / aspect, 0.0, 0.0, 0.0,
-        0.0, f, 0.0, 0.0,
-        0.0, 0.0, (far + near) * nf, -1.0,
-        0.0, 0.0, 2.0 * far * near * nf, 0.0,
-    ]
-}
-
 fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> [f32; 16] {
     let rl = right - left;
     let tb = top - bottom;
@@ -4046,129 +3808,3 @@ fn row_major_to_column_major(m: &[f32; 16]) -> [f32; 16] {
     ]
 }
 
-/// Invert a 4x4 affine transform and output column-major for OpenGL.
-/// Director/IFX transforms are stored in COLUMN-MAJOR order:
-///   m[0..3] = column 0 (X-axis), m[4..7] = column 1 (Y-axis),
-///   m[8..11] = column 2 (Z-axis), m[12..15] = column 3 (translation)
-/// Used for view matrix: view = inverse(camera_world_transform).
-fn invert_transform(m: &[f32; 16]) -> [f32; 16] {
-    // Column-major: R[row][col] = m[col*4 + row]
-    // R as a math matrix:
-    //   R[0][0]=m[0]  R[0][1]=m[4]  R[0][2]=m[8]
-    //   R[1][0]=m[1]  R[1][1]=m[5]  R[1][2]=m[9]
-    //   R[2][0]=m[2]  R[2][1]=m[6]  R[2][2]=m[10]
-    let tx = m[12]; let ty = m[13]; let tz = m[14];
-
-    // R^T: swap rows and columns
-    // R^T[0][0]=m[0]  R^T[0][1]=m[1]  R^T[0][2]=m[2]
-    // R^T[1][0]=m[4]  R^T[1][1]=m[5]  R^T[1][2]=m[6]
-    // R^T[2][0]=m[8]  R^T[2][1]=m[9]  R^T[2][2]=m[10]
-
-    // -R^T * t (using R^T rows)
-    let itx = -(m[0] * tx + m[1] * ty + m[2] * tz);
-    let ity = -(m[4] * tx + m[5] * ty + m[6] * tz);
-    let itz = -(m[8] * tx + m[9] * ty + m[10] * tz);
-
-    // Output column-major: columns of R^T
-    [
-        m[0], m[4], m[8],  0.0,  // R^T column 0
-        m[1], m[5], m[9],  0.0,  // R^T column 1
-        m[2], m[6], m[10], 0.0,  // R^T column 2
-        itx,  ity,  itz,   1.0,  // translation
-    ]
-}
-
-/// Generate spherical UV coordinates from vertex positions.
-fn generate_spherical_uvs(positions: &[[f32; 3]]) -> Vec<[f32; 2]> {
-    if positions.is_empty() {
-        return Vec::new();
-    }
-    // Compute center
-    let n = positions.len() as f32;
-    let cx = positions.iter().map(|p| p[0]).sum::<f32>() / n;
-    let cy = positions.iter().map(|p| p[1]).sum::<f32>() / n;
-    let cz = positions.iter().map(|p| p[2]).sum::<f32>() / n;
-
-    positions.iter().map(|p| {
-        let dx = p[0] - cx;
-        let dy = p[1] - cy;
-        let dz = p[2] - cz;
-        let len = (dx * dx + dy * dy + dz * dz).sqrt().max(1e-8);
-        let nx = dx / len;
-        let ny = dy / len;
-        let nz = dz / len;
-        let u = 0.5 + nz.atan2(nx) / (2.0 * std::f32::consts::PI);
-        let v = 0.5 - ny.asin() / std::f32::consts::PI;
-        [u, v]
-    }).collect()
-}
-
-/// Generate cylindrical UV coordinates from vertex positions.
-fn generate_cylindrical_uvs(positions: &[[f32; 3]]) -> Vec<[f32; 2]> {
-    if positions.is_empty() {
-        return Vec::new();
-    }
-    let n = positions.len() as f32;
-    let cx = positions.iter().map(|p| p[0]).sum::<f32>() / n;
-    let cz = positions.iter().map(|p| p[2]).sum::<f32>() / n;
-    let min_y = positions.iter().map(|p| p[1]).fold(f32::MAX, f32::min);
-    let max_y = positions.iter().map(|p| p[1]).fold(f32::MIN, f32::max);
-    let height = (max_y - min_y).max(0.001);
-
-    positions.iter().map(|p| {
-        let dx = p[0] - cx;
-        let dz = p[2] - cz;
-        let u = 0.5 + dz.atan2(dx) / (2.0 * std::f32::consts::PI);
-        let v = (p[1] - min_y) / height;
-        [u, v]
-    }).collect()
-}
-
-/// Generate UVs using the specified mode: 0=planar, 1=spherical, 2=cylindrical, 3=reflection.
-fn generate_uvs_by_mode(positions: &[[f32; 3]], mode: Option<u8>) -> Vec<[f32; 2]> {
-    match mode {
-        Some(1) => generate_spherical_uvs(positions),
-        Some(2) => generate_cylindrical_uvs(positions),
-        _ => generate_planar_uvs(positions), // 0=planar or default
-    }
-}
-
-/// Generate planar UV coordinates from vertex positions (bounding-box normalized).
-fn generate_planar_uvs(positions: &[[f32; 3]]) -> Vec<[f32; 2]> {
-    if positions.is_empty() {
-        return Vec::new();
-    }
-    // Find bounding box
-    let mut min_x = f32::MAX;
-    let mut max_x = f32::MIN;
-    let mut min_y = f32::MAX;
-    let mut max_y = f32::MIN;
-    for p in positions {
-        if p[0] < min_x { min_x = p[0]; }
-        if p[0] > max_x { max_x = p[0]; }
-        if p[1] < min_y { min_y = p[1]; }
-        if p[1] > max_y { max_y = p[1]; }
-    }
-    let range_x = (max_x - min_x).max(0.001);
-    let range_y = (max_y - min_y).max(0.001);
-
-    positions.iter().map(|p| {
-        [(p[0] - min_x) / range_x, (p[1] - min_y) / range_y]
-    }).collect()
-}
-
-/// Multiply two column-major 4x4 matrices: result = A * B
-fn mat4_multiply_col_major(a: &[f32; 16], b: &[f32; 16]) -> [f32; 16] {
-    let mut r = [0.0f32; 16];
-    for col in 0..4 {
-        for row in 0..4 {
-            r[col * 4 + row] =
-                a[0 * 4 + row] * b[col * 4 + 0] +
-                a[1 * 4 + row] * b[col * 4 + 1] +
-                a[2 * 4 + row] * b[col * 4 + 2] +
-                a[3 * 4 + row] * b[col * 4 + 3];
-        }
-    }
-    r
-}
-
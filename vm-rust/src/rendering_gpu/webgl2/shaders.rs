@@ -87,10 +87,21 @@ pub struct ShaderProgram {
     pub u_skew: Option<WebGlUniformLocation>,
 }
 
+/// Program that composes a score transition from textures of the outgoing
+/// and the new frame, following a per-pixel source map (see
+/// `player::transition::source_map`).
+pub struct TransitionProgram {
+    pub shader: ShaderProgram,
+    pub u_from: Option<WebGlUniformLocation>,
+    pub u_to: Option<WebGlUniformLocation>,
+    pub u_map: Option<WebGlUniformLocation>,
+}
+
 /// Manages shader programs for different ink modes
 pub struct ShaderManager {
     programs: HashMap<InkMode, ShaderProgram>,
     active_ink: Option<InkMode>,
+    transition: TransitionProgram,
 }
 
 impl ShaderManager {
@@ -115,6 +126,7 @@ impl ShaderManager {
         Ok(Self {
             programs,
             active_ink: None,
+            transition: Self::compile_transition(context)?,
         })
     }
 
@@ -149,6 +161,13 @@ impl ShaderManager {
         self.active_ink = None;
     }
 
+    /// Use the score transition program
+    pub fn use_transition_program(&mut self, context: &WebGL2Context) -> &TransitionProgram {
+        context.gl().use_program(Some(&self.transition.shader.program));
+        self.active_ink = None;
+        &self.transition
+    }
+
     /// Common vertex shader for all ink modes
     fn vertex_shader_source() -> &'static str {
         r#"#version 300 es
@@ -640,6 +659,47 @@ void main() {
         Self::compile_program(context, Self::vertex_shader_source(), frag_source)
     }
 
+    /// Compile the score transition shader.
+    /// Each map texel holds the source x in R/G and y in B/A, with the top
+    /// bit of R set for pixels of the new frame. The outgoing frame and the
+    /// map are uploaded top-down; the new frame is copied from the
+    /// framebuffer and so is stored bottom-up.
+    fn compile_transition(context: &WebGL2Context) -> Result<TransitionProgram, JsValue> {
+        let frag_source = r#"#version 300 es
+precision highp float;
+precision highp int;
+
+uniform sampler2D u_from;
+uniform sampler2D u_to;
+uniform sampler2D u_map;
+
+out vec4 fragColor;
+
+void main() {
+    ivec2 size = textureSize(u_map, 0);
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    pixel.y = size.y - 1 - pixel.y;
+
+    uvec4 texel = uvec4(texelFetch(u_map, pixel, 0) * 255.0 + 0.5);
+    ivec2 source = ivec2(int(((texel.r & 127u) << 8) | texel.g), int((texel.b << 8) | texel.a));
+    if ((texel.r & 128u) != 0u) {
+        fragColor = texelFetch(u_to, ivec2(source.x, size.y - 1 - source.y), 0);
+    } else {
+        fragColor = texelFetch(u_from, source, 0);
+    }
+}
+"#;
+
+        let shader = Self::compile_program(context, Self::vertex_shader_source(), frag_source)?;
+        let gl = context.gl();
+        Ok(TransitionProgram {
+            u_from: gl.get_uniform_location(&shader.program, "u_from"),
+            u_to: gl.get_uniform_location(&shader.program, "u_to"),
+            u_map: gl.get_uniform_location(&shader.program, "u_map"),
+            shader,
+        })
+    }
+
     /// Compile and link a shader program
     fn compile_program(
         context: &WebGL2Context,
@@ -78,8 +78,25 @@ fn ticks_since_epoch(endtime: DateTime<Local>) -> i64 {
             .unwrap()
 }
 
+/// The current time: the replay clock during a recording or replay
+/// session, the wall clock otherwise.
+pub fn local_now() -> DateTime<Local> {
+    match crate::player::replay::virtual_now_ms() {
+        Some(ms) => datetime_from_ms(ms),
+        None => Local::now(),
+    }
+}
+
+pub fn datetime_from_ms(ms: f64) -> DateTime<Local> {
+    DateTime::from_timestamp_micros((ms * 1000.0).round() as i64).unwrap().with_timezone(&Local)
+}
+
+pub fn datetime_to_ms(time: DateTime<Local>) -> f64 {
+    time.timestamp_micros() as f64 / 1000.0
+}
+
 pub fn get_elapsed_ticks(start_time: DateTime<chrono::Local>) -> i32 {
-    let current_ticks = ticks_since_epoch(chrono::Local::now());
+    let current_ticks = ticks_since_epoch(local_now());
     let start_ticks = ticks_since_epoch(start_time);
     (current_ticks - start_ticks) as i32
 }
@@ -0,0 +1,146 @@
+use std::rc::Rc;
+
+use vm_rust::director::lingo::datum::Datum;
+use vm_rust::player::audio::decode::{decode_ima_adpcm, decode_pcm, DecodedSound};
+use vm_rust::player::audio::mixer::{self, pan_frame, Mixer, Voice};
+use vm_rust::player::audio::{encode_wav, BufferAudioOutput};
+use vm_rust::player::handlers::datum_handlers::sound_channel::SoundStatus;
+use vm_rust::player::reserve_player_mut;
+use vm_rust::player::testing::run_test;
+
+use crate::common::load_test_movie;
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 1e-5, "{} != {}", actual, expected);
+}
+
+fn constant_sound(sample_rate: u32, channels: u16, frames: usize, value: f32) -> Rc<DecodedSound> {
+    Rc::new(DecodedSound {
+        sample_rate,
+        channels,
+        samples: vec![value; frames * channels as usize],
+    })
+}
+
+#[test]
+fn test_decode_pcm() {
+    assert_eq!(decode_pcm(&[0, 128, 255], 8, false), vec![-1.0, 0.0, 127.0 / 128.0]);
+    assert_eq!(decode_pcm(&[0x40, 0x00], 16, true), vec![0.5]);
+    assert_eq!(decode_pcm(&[0x00, 0x40], 16, false), vec![0.5]);
+}
+
+#[test]
+fn test_decode_ima_adpcm() {
+    // Nibble 7 at index 0: diff = 7/8 + 7 + 7/2 + 7/4 = 0 + 7 + 3 + 1
+    let samples = decode_ima_adpcm(&[0x07], 0, 0);
+    assert_eq!(samples[0], 11);
+    // Index advanced by 8 to step 16; nibble 0 adds step/8
+    assert_eq!(samples[1], 13);
+    // Sign bit subtracts
+    assert_eq!(decode_ima_adpcm(&[0x0F], 0, 0)[0], -11);
+}
+
+#[test]
+fn test_encode_wav_header() {
+    let wav = encode_wav(22050, 2, &[0.0, 1.0, -1.0, 0.5]);
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(&wav[8..12], b"WAVE");
+    assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 22050);
+    assert_eq!(u32::from_le_bytes(wav[40..44].try_into().unwrap()), 8);
+    assert_eq!(wav.len(), 44 + 8);
+    assert_eq!(i16::from_le_bytes([wav[46], wav[47]]), 32767);
+    assert_eq!(i16::from_le_bytes([wav[48], wav[49]]), -32767);
+}
+
+#[test]
+fn test_voice_mixes_until_end() {
+    let mut voice = Voice::new(constant_sound(100, 2, 10, 0.5), 0.0, 0.0);
+    let mut out = vec![0.0; 16 * 2];
+    let (written, finished) = voice.mix(&mut out, 100, (1.0, 1.0), 0.0);
+    assert_eq!(written, 10);
+    assert!(finished);
+    assert_close(out[0], 0.5);
+    assert_close(out[19], 0.5);
+    assert_eq!(out[20], 0.0);
+}
+
+#[test]
+fn test_voice_resamples_and_honours_start_time() {
+    // 50 Hz source played at 100 Hz lasts twice as many output frames.
+    let mut voice = Voice::new(constant_sound(50, 1, 10, 0.25), 100.0, 0.0);
+    assert_eq!(voice.position_ms(), 100.0);
+    let mut out = vec![0.0; 32 * 2];
+    let (written, finished) = voice.mix(&mut out, 100, (1.0, 1.0), 0.0);
+    assert_eq!(written, 10);
+    assert!(finished);
+    voice.rewind();
+    assert_eq!(voice.position_ms(), 100.0);
+}
+
+#[test]
+fn test_voice_gain_ramp() {
+    let mut voice = Voice::new(constant_sound(100, 2, 100, 1.0), 0.0, 0.0);
+    let mut out = vec![0.0; 4 * 2];
+    voice.mix(&mut out, 100, (0.0, 1.0), 0.0);
+    for (frame, expected) in [0.0, 0.25, 0.5, 0.75].into_iter().enumerate() {
+        assert_close(out[frame * 2], expected);
+    }
+}
+
+#[test]
+fn test_equal_power_pan() {
+    let (l, r) = pan_frame(1.0, 1.0, true, 0.0);
+    assert_close(l, std::f32::consts::FRAC_1_SQRT_2);
+    assert_close(r, std::f32::consts::FRAC_1_SQRT_2);
+    let (l, r) = pan_frame(1.0, 1.0, true, 1.0);
+    assert_close(l, 0.0);
+    assert_close(r, 1.0);
+    // Stereo sources pass through unchanged when centred
+    let (l, r) = pan_frame(0.3, -0.2, false, 0.0);
+    assert_close(l, 0.3);
+    assert_close(r, -0.2);
+    let (l, r) = pan_frame(0.3, -0.2, false, -1.0);
+    assert_close(l, 0.1);
+    assert_close(r, 0.0);
+}
+
+#[test]
+fn test_mixer_output() {
+    let mut mixer = Mixer::default();
+    assert_eq!(mixer.output().backend_name(), "null");
+    mixer.set_output(Box::new(BufferAudioOutput::new(8000)));
+    assert_eq!(mixer.sample_rate(), 8000);
+    mixer.output_mut().write(&[0.1, 0.2]);
+    assert_eq!(mixer.output().buffered_samples(), Some(&[0.1f32, 0.2][..]));
+    assert_eq!(mixer.output_mut().take_samples(), vec![0.1, 0.2]);
+    assert!(mixer.output().buffered_samples().unwrap().is_empty());
+}
+
+#[test]
+fn test_looping_voice_with_nothing_to_play_stops() {
+    run_test(async {
+        let _player = load_test_movie("on startMovie\nend\n").await;
+        reserve_player_mut(|player| {
+            // A startTime past the end and a zero sample rate both leave
+            // the voice finished before it plays a single frame.
+            for voice in [
+                Voice::new(constant_sound(100, 1, 10, 0.5), 5000.0, 0.0),
+                Voice::new(constant_sound(0, 1, 10, 0.5), 0.0, 0.0),
+            ] {
+                let member = player.alloc_datum(Datum::Int(1));
+                let channel = player.sound_manager.get_channel(0).unwrap();
+                {
+                    let mut channel = channel.borrow_mut();
+                    channel.member = Some(member);
+                    channel.loop_count = 0;
+                    channel.voice = Some(voice);
+                    channel.status = SoundStatus::Playing;
+                }
+                mixer::render(player, 100.0);
+                let channel = channel.borrow();
+                assert_eq!(channel.status, SoundStatus::Idle);
+                assert!(channel.voice.is_none());
+            }
+        });
+    });
+}
@@ -0,0 +1,124 @@
+use vm_rust::player::cast_lib::CastMemberRef;
+use vm_rust::player::datum_formatting::format_datum;
+use vm_rust::player::debug::{BreakpointOptions, HitCondition};
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::testing_shared::TestHarness;
+use vm_rust::player::{reserve_player_mut, reserve_player_ref};
+
+use crate::common::load_test_movie;
+
+const SOURCE: &str = "\
+global gTotal
+
+on addUp n
+  gTotal = gTotal + n
+  return gTotal
+end
+";
+
+async fn start_player() -> TestPlayer {
+    let player = load_test_movie(SOURCE).await;
+    player.eval("gTotal = 0").await.unwrap();
+    reserve_player_ref(|player| player.console.clear());
+    player
+}
+
+fn script_name() -> String {
+    reserve_player_ref(|player| {
+        let script_ref = CastMemberRef { cast_lib: 1, cast_member: 1 };
+        player.movie.cast_manager.get_script_by_ref(&script_ref).unwrap().name.clone()
+    })
+}
+
+async fn add_up(player: &TestPlayer, values: &[i32]) {
+    for n in values {
+        player.eval(&format!("addUp({})", n)).await.unwrap();
+    }
+}
+
+fn console_lines() -> Vec<String> {
+    let output = reserve_player_ref(|player| player.console.read());
+    output.lines().map(str::to_owned).collect()
+}
+
+#[test]
+fn test_hit_condition_parse() {
+    assert_eq!(HitCondition::parse("").unwrap(), HitCondition::Always);
+    assert_eq!(HitCondition::parse("3").unwrap(), HitCondition::Equal(3));
+    assert_eq!(HitCondition::parse("== 3").unwrap(), HitCondition::Equal(3));
+    assert_eq!(HitCondition::parse(">=2").unwrap(), HitCondition::AtLeast(2));
+    assert_eq!(HitCondition::parse("%4").unwrap(), HitCondition::Multiple(4));
+    assert!(HitCondition::parse("%0").is_err());
+    assert!(HitCondition::parse("often").is_err());
+    assert_eq!(HitCondition::AtLeast(2).to_string(), ">=2");
+}
+
+#[test]
+fn test_conditional_logpoint() {
+    run_test(async {
+        let player = start_player().await;
+        let options = BreakpointOptions {
+            condition: Some("n > 2".to_string()),
+            log_message: Some("n={n} total={gTotal} {{literal}}".to_string()),
+            ..Default::default()
+        };
+        let script = script_name();
+        reserve_player_mut(|player| {
+            player.breakpoint_manager.set_breakpoint_options(script, "addUp".to_string(), 0, options);
+        });
+
+        add_up(&player, &[1, 3, 5]).await;
+
+        assert_eq!(console_lines(), vec!["n=3 total=1 {literal}", "n=5 total=4 {literal}"]);
+        reserve_player_ref(|player| {
+            // Logpoints never pause, and only hits with a true condition count.
+            assert!(player.current_breakpoint.is_none());
+            assert_eq!(player.breakpoint_manager.breakpoints[0].hit_count, 2);
+        });
+    });
+}
+
+#[test]
+fn test_handler_entry_hit_condition() {
+    run_test(async {
+        let player = start_player().await;
+        let options = BreakpointOptions {
+            hit_condition: HitCondition::Multiple(2),
+            log_message: Some("entered with {n}".to_string()),
+            ..Default::default()
+        };
+        reserve_player_mut(|player| {
+            // Handler names match case-insensitively, like Lingo calls do.
+            player.breakpoint_manager.add_handler_breakpoint("ADDUP".to_string(), options);
+        });
+
+        add_up(&player, &[10, 20, 30, 40, 50]).await;
+
+        assert_eq!(console_lines(), vec!["entered with 20", "entered with 40"]);
+        reserve_player_ref(|player| assert_eq!(player.breakpoint_manager.handler_breakpoints[0].hit_count, 5));
+    });
+}
+
+#[test]
+fn test_one_shot_breakpoint_with_ignore_count() {
+    run_test(async {
+        let player = start_player().await;
+        let options = BreakpointOptions {
+            ignore_count: 1,
+            log_message: Some("second call: {n}".to_string()),
+            one_shot: true,
+            ..Default::default()
+        };
+        let script = script_name();
+        reserve_player_mut(|player| {
+            player.breakpoint_manager.set_breakpoint_options(script, "addUp".to_string(), 0, options);
+        });
+
+        add_up(&player, &[1, 2, 3]).await;
+
+        assert_eq!(console_lines(), vec!["second call: 2"]);
+        reserve_player_ref(|player| assert!(player.breakpoint_manager.breakpoints.is_empty()));
+        let total = reserve_player_ref(|player| format_datum(player.globals.get("gTotal").unwrap(), player));
+        assert_eq!(total, "6");
+    });
+}
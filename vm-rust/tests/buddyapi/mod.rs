@@ -0,0 +1,54 @@
+use vm_rust::player::testing::run_test;
+use vm_rust::player::xtra::buddyapi::{borrow_buddyapi_manager_mut, BuddyApiConfig};
+use vm_rust::player::xtra::fileio::borrow_fileio_manager_mut;
+
+use crate::common::{eval_result, load_test_movie};
+
+#[test]
+fn test_buddy_api_handlers() {
+    run_test(async {
+        let player = load_test_movie("global gResult\n").await;
+
+        borrow_fileio_manager_mut(|fileio| {
+            fileio.virtual_fs.insert("C:\\Game\\Saves\\slot1.sav".to_string(), b"level=3".to_vec());
+            fileio.virtual_fs.insert("C:\\Game\\Saves\\notes.txt".to_string(), vec![]);
+            fileio.virtual_fs.insert("C:\\Game\\game.ini".to_string(), b"[Options]\r\nSound=1\r\n".to_vec());
+        });
+
+        assert_eq!(eval_result(&player, "baSysFolder(\"temp\")").await, "\"C:\\WINDOWS\\TEMP\\\"");
+        assert_eq!(eval_result(&player, "[baFileExists(\"c:/game/saves/SLOT1.SAV\"), baFileExists(\"C:\\Game\\nope.sav\")]").await, "[1, 0]");
+        assert_eq!(eval_result(&player, "[baFolderExists(\"C:\\Game\\Saves\\\"), baFolderExists(\"C:\\Other\")]").await, "[1, 0]");
+        assert_eq!(eval_result(&player, "baFileList(\"C:\\Game\\Saves\", \"*.sav\")").await, "[\"slot1.sav\"]");
+        assert_eq!(eval_result(&player, "baFileSize(\"C:\\Game\\Saves\\slot1.sav\")").await, "7");
+
+        assert_eq!(eval_result(&player, "baCreateFolder(\"C:\\Game\\Replays\")").await, "1");
+        assert_eq!(eval_result(&player, "baFolderList(\"C:\\Game\")").await, "[\"Replays\", \"Saves\"]");
+        assert_eq!(eval_result(&player, "baCopyFile(\"C:\\Game\\Saves\\slot1.sav\", \"C:\\Game\\Replays\", \"Always\")").await, "0");
+        assert_eq!(eval_result(&player, "baCopyFileProgress(\"C:\\Game\\Saves\\slot1.sav\", \"C:\\Game\\Replays\\slot1.sav\", \"IfNotExist\", \"\", \"\", 0)").await, "6");
+        assert_eq!(eval_result(&player, "baCopyFile(\"C:\\Game\\missing.sav\", \"C:\\Game\\Replays\", \"Always\")").await, "1");
+        assert_eq!(eval_result(&player, "baDeleteFolder(\"C:\\Game\\Replays\")").await, "0");
+        assert_eq!(eval_result(&player, "baDeleteFile(\"C:\\Game\\Replays\\slot1.sav\")").await, "1");
+        assert_eq!(eval_result(&player, "baDeleteFolder(\"C:\\Game\\Replays\")").await, "1");
+
+        assert_eq!(eval_result(&player, "baReadIni(\"options\", \"sound\", \"0\", \"C:\\Game\\game.ini\")").await, "\"1\"");
+        assert_eq!(eval_result(&player, "baReadIni(\"Options\", \"Music\", \"on\", \"C:\\Game\\game.ini\")").await, "\"on\"");
+        eval_result(&player, "baWriteIni(\"Options\", \"Music\", \"off\", \"C:\\Game\\game.ini\")").await;
+        eval_result(&player, "baWriteIni(\"Player\", \"Name\", \"Ann\", \"C:\\Game\\game.ini\")").await;
+        let ini = borrow_fileio_manager_mut(|fileio| fileio.virtual_fs.get("C:\\Game\\game.ini").cloned().unwrap());
+        assert_eq!(String::from_utf8(ini).unwrap(), "[Options]\r\nSound=1\r\nMusic=off\r\n\r\n[Player]\r\nName=Ann\r\n");
+
+        assert_eq!(eval_result(&player, "[baScreenInfo(\"width\"), baScreenInfo(\"height\"), baScreenInfo(\"depth\")]").await, "[1024, 768, 32]");
+        assert_eq!(eval_result(&player, "[baVersion(\"os\"), baEnvironment(\"username\"), baUserName()]").await, "[\"WinXP\", \"Player\", \"Player\"]");
+
+        // Dangerous operations are never performed, only reported.
+        assert_eq!(eval_result(&player, "baRunProgram(\"C:\\WINDOWS\\notepad.exe\", \"normal\", 0)").await, "42");
+        assert_eq!(eval_result(&player, "baWriteRegString(\"Software\\Game\", \"Key\", \"Value\", \"HKEY_CURRENT_USER\")").await, "1");
+        assert_eq!(eval_result(&player, "baReadRegString(\"Software\\Game\", \"Key\", \"default\", \"HKEY_CURRENT_USER\")").await, "\"default\"");
+
+        let config = BuddyApiConfig::from_json(r#"{ "userName": "Ann", "screenWidth": 800, "blockedOperationsSucceed": false }"#).unwrap();
+        borrow_buddyapi_manager_mut(|manager| manager.config = config);
+        assert_eq!(eval_result(&player, "[baUserName(), baScreenInfo(\"width\"), baScreenInfo(\"height\")]").await, "[\"Ann\", 800, 768]");
+        assert_eq!(eval_result(&player, "baRunProgram(\"C:\\WINDOWS\\notepad.exe\", \"normal\", 0)").await, "2");
+        assert_eq!(eval_result(&player, "baWriteRegString(\"Software\\Game\", \"Key\", \"Value\", \"HKEY_CURRENT_USER\")").await, "0");
+    });
+}
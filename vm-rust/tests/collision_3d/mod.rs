@@ -0,0 +1,121 @@
+use vm_rust::director::chunks::w3d::collision::{
+    detect_contact, model_collision_shape, translated_local_transform, CollisionMode,
+};
+use vm_rust::director::chunks::w3d::types::{W3dNode, W3dNodeType, W3dRawMesh, W3dScene};
+
+fn translation(x: f32, y: f32, z: f32) -> [f32; 16] {
+    [
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        x, y, z, 1.0,
+    ]
+}
+
+/// A 20x20x20 cube centred on the origin (vertices only need to bound it).
+fn cube_mesh(name: &str) -> W3dRawMesh {
+    let mut positions = Vec::new();
+    for x in [-10.0, 10.0] {
+        for y in [-10.0, 10.0] {
+            for z in [-10.0, 10.0] {
+                positions.push([x, y, z]);
+            }
+        }
+    }
+    W3dRawMesh {
+        name: name.to_string(),
+        chain_index: 0,
+        positions,
+        normals: Vec::new(),
+        tex_coords: Vec::new(),
+        vertex_colors: Vec::new(),
+        // Top face (+Y) split into two triangles
+        faces: vec![[2, 6, 7], [2, 7, 3]],
+    }
+}
+
+fn scene_with_cubes(positions: &[(&str, [f32; 3], &str)]) -> W3dScene {
+    let mut scene = W3dScene::default();
+    scene.raw_meshes.push(cube_mesh("cubeRes"));
+    for (name, pos, parent) in positions {
+        scene.nodes.push(W3dNode {
+            name: name.to_string(),
+            parent_name: parent.to_string(),
+            resource_name: "cubeRes".to_string(),
+            node_type: W3dNodeType::Model,
+            transform: translation(pos[0], pos[1], pos[2]),
+            ..Default::default()
+        });
+    }
+    scene
+}
+
+fn shape(scene: &W3dScene, name: &str, mode: CollisionMode) -> vm_rust::director::chunks::w3d::collision::CollisionShape {
+    let node = scene.nodes.iter().find(|n| n.name == name).unwrap();
+    model_collision_shape(scene, node, None, mode).unwrap()
+}
+
+#[test]
+fn test_collision_box_overlap_normal_and_depth() {
+    let scene = scene_with_cubes(&[("a", [0.0, 0.0, 0.0], "World"), ("b", [15.0, 0.0, 0.0], "World")]);
+    let a = shape(&scene, "a", CollisionMode::Box);
+    let b = shape(&scene, "b", CollisionMode::Box);
+
+    let contact = detect_contact(&a, &b).expect("cubes overlap by 5 units");
+    assert_eq!(contact.normal, [1.0, 0.0, 0.0]);
+    assert!((contact.depth - 5.0).abs() < 1e-4);
+    assert!((contact.point[0] - 7.5).abs() < 1e-4);
+
+    let reversed = detect_contact(&b, &a).unwrap();
+    assert_eq!(reversed.normal, [-1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn test_collision_sphere_mode_uses_bounding_sphere() {
+    // Boxes 21 units apart along the diagonal of XY don't touch, but their
+    // bounding spheres (radius ~17.3) do.
+    let scene = scene_with_cubes(&[("a", [0.0, 0.0, 0.0], "World"), ("b", [21.0, 21.0, 0.0], "World")]);
+    assert!(detect_contact(&shape(&scene, "a", CollisionMode::Box), &shape(&scene, "b", CollisionMode::Box)).is_none());
+
+    let contact = detect_contact(&shape(&scene, "a", CollisionMode::Sphere), &shape(&scene, "b", CollisionMode::Sphere))
+        .expect("bounding spheres overlap");
+    assert!((contact.normal[0] - contact.normal[1]).abs() < 1e-4);
+    assert!(contact.normal[0] > 0.0);
+
+    let far = scene_with_cubes(&[("a", [0.0, 0.0, 0.0], "World"), ("b", [40.0, 0.0, 0.0], "World")]);
+    assert!(detect_contact(&shape(&far, "a", CollisionMode::Sphere), &shape(&far, "b", CollisionMode::Sphere)).is_none());
+}
+
+#[test]
+fn test_collision_mesh_mode_tests_triangles() {
+    // "b" sits just above the top face triangles of "a".
+    let scene = scene_with_cubes(&[("a", [0.0, 0.0, 0.0], "World"), ("b", [0.0, 25.0, 0.0], "World")]);
+    let a = shape(&scene, "a", CollisionMode::Mesh);
+    let b = shape(&scene, "b", CollisionMode::Sphere);
+    assert_eq!(a.triangles.len(), 2);
+
+    let contact = detect_contact(&a, &b).expect("sphere of b reaches the top face of a");
+    assert!((contact.normal[1] - 1.0).abs() < 1e-4);
+    assert!((contact.point[1] - 10.0).abs() < 1e-4);
+
+    let flipped = detect_contact(&b, &a).unwrap();
+    assert!((flipped.normal[1] + 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_collision_resolution_respects_parent_transform() {
+    let mut scene = scene_with_cubes(&[("child", [5.0, 0.0, 0.0], "group")]);
+    scene.nodes.push(W3dNode {
+        name: "group".to_string(),
+        parent_name: "World".to_string(),
+        node_type: W3dNodeType::Group,
+        transform: translation(100.0, 0.0, 0.0),
+        ..Default::default()
+    });
+    let child = shape(&scene, "child", CollisionMode::Box);
+    assert_eq!(child.center, [105.0, 0.0, 0.0]);
+
+    let node = scene.nodes.iter().find(|n| n.name == "child").unwrap();
+    let local = translated_local_transform(&scene, node, None, [0.0, 3.0, 0.0]);
+    assert_eq!([local[12], local[13], local[14]], [5.0, 3.0, 0.0]);
+}
@@ -0,0 +1,167 @@
+//! Fixtures shared by the integration tests: a minimal movie builder and
+//! helpers to load it into a `TestPlayer`.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use binary_reader::{BinaryReader, Endian};
+use vm_rust::director::chunks::config::ConfigChunk;
+use vm_rust::director::chunks::script_names::ScriptNamesChunk;
+use vm_rust::director::lingo::compiler::compile_script;
+use vm_rust::director::utils::FOURCC;
+use vm_rust::director::writer::{write_to_vec, RIFXWriter};
+use vm_rust::player::datum_formatting::format_datum;
+use vm_rust::player::net_backend::NetManifest;
+use vm_rust::player::reserve_player_ref;
+use vm_rust::player::testing::TestPlayer;
+use vm_rust::player::testing_shared::TestHarness;
+
+pub const DIRECTOR_VERSION: u16 = 1851;
+pub const VERSION: u16 = 1000;
+pub const MULTIPLIER: u32 = 8;
+
+static NEXT_MOVIE_ID: AtomicU32 = AtomicU32::new(0);
+
+pub fn config_bytes(protection: u16) -> Vec<u8> {
+    let mut raw = vec![0u8; 100];
+    raw[0..2].copy_from_slice(&100u16.to_be_bytes());
+    raw[8..10].copy_from_slice(&480u16.to_be_bytes());
+    raw[10..12].copy_from_slice(&640u16.to_be_bytes());
+    raw[12..14].copy_from_slice(&1u16.to_be_bytes());
+    raw[14..16].copy_from_slice(&1u16.to_be_bytes());
+    raw[36..38].copy_from_slice(&DIRECTOR_VERSION.to_be_bytes());
+    raw[58..60].copy_from_slice(&protection.to_be_bytes());
+    let mut config = ConfigChunk::from_reader(&mut BinaryReader::from_vec(&raw), VERSION, Endian::Big).unwrap();
+    config.checksum = config.compute_checksum(Endian::Big);
+    write_to_vec(binary_rw::Endian::Big, |w| config.write(w)).unwrap()
+}
+
+/// A movie script member (script type 3) with its source text and an
+/// optional member name.
+pub fn cast_member_bytes(script_text: &str, name: Option<&str>) -> Vec<u8> {
+    let mut items = vec![script_text.as_bytes().to_vec()];
+    if let Some(name) = name {
+        let mut name_item = vec![name.len() as u8];
+        name_item.extend_from_slice(name.as_bytes());
+        items.push(name_item);
+    }
+
+    let mut info = vec![];
+    for value in [20u32, 0, 0, 0, 1] {
+        info.extend_from_slice(&value.to_be_bytes());
+    }
+    info.extend_from_slice(&(items.len() as u16).to_be_bytes());
+    let mut offset = 0u32;
+    info.extend_from_slice(&offset.to_be_bytes());
+    for item in &items {
+        offset += item.len() as u32;
+        info.extend_from_slice(&offset.to_be_bytes());
+    }
+    for item in &items {
+        info.extend_from_slice(item);
+    }
+
+    let mut data = vec![];
+    data.extend_from_slice(&11u32.to_be_bytes());
+    data.extend_from_slice(&(info.len() as u32).to_be_bytes());
+    data.extend_from_slice(&2u32.to_be_bytes());
+    data.extend_from_slice(&info);
+    data.extend_from_slice(&3u16.to_be_bytes());
+    data
+}
+
+pub fn script_context_bytes(lnam_id: u32, lscr_id: u32) -> Vec<u8> {
+    let mut data = vec![0u8; 42];
+    data[8..12].copy_from_slice(&1u32.to_be_bytes());
+    data[12..16].copy_from_slice(&1u32.to_be_bytes());
+    data[16..18].copy_from_slice(&42u16.to_be_bytes());
+    data[32..36].copy_from_slice(&lnam_id.to_be_bytes());
+    data[36..38].copy_from_slice(&1u16.to_be_bytes());
+    data.extend_from_slice(&0u32.to_be_bytes());
+    data.extend_from_slice(&(lscr_id as i32).to_be_bytes());
+    data.extend_from_slice(&0u16.to_be_bytes());
+    data.extend_from_slice(&4u16.to_be_bytes());
+    data
+}
+
+/// An MCsL chunk listing one internal cast (id 1024) holding member 1.
+pub fn cast_list_bytes() -> Vec<u8> {
+    let mut name = vec![8u8];
+    name.extend_from_slice(b"Internal");
+    let mut range = vec![];
+    range.extend_from_slice(&1u16.to_be_bytes());
+    range.extend_from_slice(&1u16.to_be_bytes());
+    range.extend_from_slice(&1024u32.to_be_bytes());
+    let items = [vec![], name, vec![], 0u16.to_be_bytes().to_vec(), range];
+
+    let mut data = vec![];
+    data.extend_from_slice(&12u32.to_be_bytes());
+    data.extend_from_slice(&0u16.to_be_bytes());
+    data.extend_from_slice(&1u16.to_be_bytes());
+    data.extend_from_slice(&4u16.to_be_bytes());
+    data.extend_from_slice(&0u16.to_be_bytes());
+    data.extend_from_slice(&(items.len() as u16).to_be_bytes());
+    let mut offset = 0u32;
+    for item in &items {
+        data.extend_from_slice(&offset.to_be_bytes());
+        offset += item.len() as u32;
+    }
+    data.extend_from_slice(&offset.to_be_bytes());
+    for item in &items {
+        data.extend_from_slice(item);
+    }
+    data
+}
+
+/// A movie whose only cast member is a movie script.
+pub fn movie_bytes(source: &str) -> Vec<u8> {
+    let mut names = vec![];
+    let mut script = compile_script(source, &mut names, VERSION, MULTIPLIER).unwrap();
+    script.script_number = 0;
+
+    let mut writer = RIFXWriter::new(Endian::Big, FOURCC("MV93"), DIRECTOR_VERSION);
+    writer.add_chunk(FOURCC("DRCF"), config_bytes(0), None);
+    writer.add_chunk(FOURCC("CAS*"), 6u32.to_be_bytes().to_vec(), Some(1024));
+    writer.add_chunk(FOURCC("CASt"), cast_member_bytes(source, None), None);
+    writer.add_chunk(FOURCC("Lctx"), script_context_bytes(8, 9), Some(1024));
+    let names = ScriptNamesChunk { names };
+    writer.add_chunk(FOURCC("Lnam"), write_to_vec(binary_rw::Endian::Big, |w| names.write(w)).unwrap(), None);
+    writer.add_chunk(FOURCC("Lscr"), vec![], None);
+    writer.add_chunk(FOURCC("MCsL"), cast_list_bytes(), None);
+    writer.set_script(9, &script, false).unwrap();
+    writer.write().unwrap()
+}
+
+/// Load a movie built from `source` into `player` under `file_name`. The
+/// file only lives in a temp directory for the duration of the load.
+pub async fn load_test_movie_as(player: &mut TestPlayer, file_name: &str, source: &str) {
+    let id = NEXT_MOVIE_ID.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!("dirplayer_test_{}_{}", std::process::id(), id));
+    load_test_movie_at(player, &dir.join(file_name), source).await;
+    std::fs::remove_dir_all(dir).ok();
+}
+
+/// Write a movie built from `source` to `path` and load it into `player`.
+pub async fn load_test_movie_at(player: &mut TestPlayer, path: &Path, source: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, movie_bytes(source)).unwrap();
+    player.use_net_manifest(NetManifest { root: None, timeout_ms: None, routes: vec![] });
+    player.load_movie(path.to_str().unwrap()).await;
+}
+
+/// A fresh player running a movie built from `source`.
+pub async fn load_test_movie(source: &str) -> TestPlayer {
+    let mut player = TestPlayer::new();
+    load_test_movie_as(&mut player, "test.dir", source).await;
+    player
+}
+
+/// Evaluate `expr` into `gResult` and return it formatted. The movie must
+/// declare `global gResult`.
+pub async fn eval_result(player: &TestPlayer, expr: &str) -> String {
+    player.eval(&format!("gResult = {}", expr)).await.unwrap();
+    reserve_player_ref(|player| {
+        let value = player.globals.get("gResult").unwrap();
+        format_datum(value, player)
+    })
+}
@@ -0,0 +1,235 @@
+use binary_reader::{BinaryReader, Endian};
+use vm_rust::director::chunks::cue_points::CuePointsChunk;
+use vm_rust::director::chunks::sound::SoundChunk;
+use vm_rust::director::enums::SoundInfo;
+use vm_rust::player::audio::mixer;
+use vm_rust::player::cast_member::{CastMember, CastMemberType, SoundCuePoint, SoundMember};
+use vm_rust::player::cue_points::{crossed, dispatch_cue_passed};
+use vm_rust::player::reserve_player_mut;
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::testing_shared::TestHarness;
+
+use crate::common::{eval_result, load_test_movie};
+
+fn cupt_bytes(points: &[(u32, &str)]) -> Vec<u8> {
+    let mut data = vec![0, 0];
+    data.extend_from_slice(&(points.len() as u16).to_be_bytes());
+    for (time, name) in points {
+        data.extend_from_slice(&time.to_be_bytes());
+        let mut name_field = [0u8; 32];
+        name_field[0] = name.len() as u8;
+        name_field[1..1 + name.len()].copy_from_slice(name.as_bytes());
+        data.extend_from_slice(&name_field);
+    }
+    data
+}
+
+/// Chunks of a RIFF or IFF file: id, size in the file's byte order, body
+/// padded to an even length.
+fn container(form: &[u8; 4], kind: &[u8; 4], chunks: &[(&[u8; 4], Vec<u8>)], big_endian: bool) -> Vec<u8> {
+    let size = |len: usize| {
+        if big_endian { (len as u32).to_be_bytes() } else { (len as u32).to_le_bytes() }
+    };
+    let mut body = kind.to_vec();
+    for (id, data) in chunks {
+        body.extend_from_slice(*id);
+        body.extend_from_slice(&size(data.len()));
+        body.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            body.push(0);
+        }
+    }
+    let mut file = form.to_vec();
+    file.extend_from_slice(&size(body.len()));
+    file.extend(body);
+    file
+}
+
+fn wave_bytes(sample_rate: u32, cues: &[(u32, u32)], labels: &[(u32, &str)]) -> Vec<u8> {
+    let mut fmt = vec![1, 0, 1, 0];
+    fmt.extend_from_slice(&sample_rate.to_le_bytes());
+    fmt.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    fmt.extend_from_slice(&[2, 0, 16, 0]);
+    let mut cue = (cues.len() as u32).to_le_bytes().to_vec();
+    for (id, offset) in cues {
+        cue.extend_from_slice(&id.to_le_bytes());
+        cue.extend_from_slice(&offset.to_le_bytes());
+        cue.extend_from_slice(b"data");
+        cue.extend_from_slice(&[0; 8]);
+        cue.extend_from_slice(&offset.to_le_bytes());
+    }
+    let mut list = b"adtl".to_vec();
+    for (id, label) in labels {
+        let mut labl = id.to_le_bytes().to_vec();
+        labl.extend_from_slice(label.as_bytes());
+        labl.push(0);
+        list.extend_from_slice(b"labl");
+        list.extend_from_slice(&(labl.len() as u32).to_le_bytes());
+        if labl.len() % 2 == 1 {
+            labl.push(0);
+        }
+        list.extend(labl);
+    }
+    container(
+        b"RIFF",
+        b"WAVE",
+        &[(b"fmt ", fmt), (b"cue ", cue), (b"LIST", list), (b"data", vec![0; 8])],
+        false,
+    )
+}
+
+fn read(data: &[u8]) -> CuePointsChunk {
+    let mut reader = BinaryReader::from_u8(data);
+    reader.set_endian(Endian::Little);
+    CuePointsChunk::from_reader(&mut reader).unwrap()
+}
+
+fn cue(time_ms: u32, name: &str) -> SoundCuePoint {
+    SoundCuePoint {
+        time_ms,
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn test_parse_cupt() {
+    let chunk = read(&cupt_bytes(&[(11025, "verse"), (22050, "chorus")]));
+    assert_eq!(chunk.points.len(), 2);
+    assert_eq!(chunk.points[0].time, 11025);
+    assert_eq!(chunk.points[0].name, "verse");
+    assert_eq!(chunk.points[1].name, "chorus");
+}
+
+#[test]
+fn test_truncated_cupt_is_an_error() {
+    let mut data = cupt_bytes(&[(100, "a")]);
+    data.truncate(20);
+    let mut reader = BinaryReader::from_u8(&data);
+    assert!(CuePointsChunk::from_reader(&mut reader).is_err());
+}
+
+#[test]
+fn test_cue_points_converted_to_ms_and_sorted() {
+    let chunk = read(&cupt_bytes(&[(22050, "late"), (11025, "early")]));
+    let points = SoundMember::cue_points_from_chunk(&chunk, 22050);
+    assert_eq!(points, vec![cue(500, "early"), cue(1000, "late")]);
+}
+
+#[test]
+fn test_crossed_cue_points() {
+    let points = vec![cue(0, "start"), cue(500, "a"), cue(1000, "b"), cue(1500, "c")];
+    let passed: Vec<i32> = crossed(&points, -0.5, 0.0).map(|(i, _)| i).collect();
+    assert_eq!(passed, vec![1]);
+    let passed: Vec<i32> = crossed(&points, 0.0, 1200.0).map(|(i, _)| i).collect();
+    assert_eq!(passed, vec![2, 3]);
+    // A cue point is reported once even if a poll lands exactly on it.
+    let passed: Vec<i32> = crossed(&points, 1000.0, 1400.0).map(|(i, _)| i).collect();
+    assert!(passed.is_empty());
+}
+
+#[test]
+fn test_wave_cue_points_are_read_from_sound_data() {
+    let data = wave_bytes(11025, &[(1, 11025), (2, 22050), (3, 33075)], &[(2, "chorus"), (1, "verse")]);
+    let chunk = CuePointsChunk::from_sound_data(&data).unwrap();
+    assert_eq!(chunk.sample_rate, Some(11025));
+    let names: Vec<&str> = chunk.points.iter().map(|point| point.name.as_str()).collect();
+    assert_eq!(names, vec!["verse", "chorus", ""]);
+    // Times are counted at the WAV file's rate, not the member's.
+    let points = SoundMember::cue_points_from_chunk(&chunk, 22050);
+    assert_eq!(points, vec![cue(1000, "verse"), cue(2000, "chorus"), cue(3000, "")]);
+}
+
+#[test]
+fn test_aiff_markers_are_read_from_sound_data() {
+    // 22050 Hz as an 80-bit extended float.
+    let mut comm = vec![0, 1, 0, 0, 0, 0, 0, 16];
+    comm.extend_from_slice(&[0x40, 0x0D, 0xAC, 0x44, 0, 0, 0, 0, 0, 0]);
+    let mut mark = vec![0, 2];
+    for (id, position, name) in [(1u16, 22050u32, "intro"), (2, 44100, "end")] {
+        mark.extend_from_slice(&id.to_be_bytes());
+        mark.extend_from_slice(&position.to_be_bytes());
+        mark.push(name.len() as u8);
+        mark.extend_from_slice(name.as_bytes());
+        if name.len() % 2 == 0 {
+            mark.push(0);
+        }
+    }
+    let data = container(b"FORM", b"AIFF", &[(b"COMM", comm), (b"MARK", mark)], true);
+    let chunk = CuePointsChunk::from_sound_data(&data).unwrap();
+    assert_eq!(chunk.sample_rate, Some(22050));
+    let points = SoundMember::cue_points_from_chunk(&chunk, 0);
+    assert_eq!(points, vec![cue(1000, "intro"), cue(2000, "end")]);
+}
+
+#[test]
+fn test_plain_sound_data_has_no_embedded_cue_points() {
+    assert!(CuePointsChunk::from_sound_data(&[0; 64]).is_none());
+    assert!(CuePointsChunk::from_sound_data(&wave_bytes(11025, &[], &[])).is_none());
+}
+
+const CUE_SOURCE: &str = "global gResult, gCues
+
+on cuePassed channelID, number, name
+  gCues.add([channelID, number, name])
+end
+";
+
+/// Put a one second, 1 kHz sound with cue points at 100, 200 and 300 ms
+/// in member 2.
+async fn load_cue_movie() -> TestPlayer {
+    let player = load_test_movie(CUE_SOURCE).await;
+    reserve_player_mut(|player| {
+        let sound = SoundMember {
+            info: SoundInfo {
+                sample_rate: 1000,
+                sample_size: 16,
+                channels: 1,
+                sample_count: 1000,
+                duration: 1000,
+                loop_enabled: false,
+            },
+            sound: SoundChunk::new(vec![0; 2000]),
+            cue_points: vec![cue(100, "intro"), cue(200, "verse"), cue(300, "verse")],
+        };
+        player.movie.cast_manager.casts[0].insert_member(2, CastMember::new(2, CastMemberType::Sound(sound)));
+    });
+    player.eval("gCues = []").await.unwrap();
+    player
+}
+
+#[test]
+fn test_cue_point_names_and_times() {
+    run_test(async {
+        let player = load_cue_movie().await;
+        assert_eq!(eval_result(&player, "member(2).cuePointNames").await, "[\"intro\", \"verse\", \"verse\"]");
+        assert_eq!(eval_result(&player, "member(2).cuePointTimes").await, "[100, 200, 300]");
+    });
+}
+
+#[test]
+fn test_cue_passed_is_dispatched_as_playback_crosses_cue_points() {
+    run_test(async {
+        let player = load_cue_movie().await;
+        player.eval("sound(1).play(member(2))").await.unwrap();
+
+        reserve_player_mut(|player| mixer::render(player, 150.0));
+        dispatch_cue_passed().await;
+        assert_eq!(eval_result(&player, "gCues").await, "[[#sound1, 1, \"intro\"]]");
+        assert_eq!(eval_result(&player, "sound(1).isPastCuePoint(1)").await, "1");
+        assert_eq!(eval_result(&player, "sound(1).isPastCuePoint(2)").await, "0");
+        assert_eq!(eval_result(&player, "sound(1).mostRecentCuePoint").await, "1");
+
+        reserve_player_mut(|player| mixer::render(player, 200.0));
+        dispatch_cue_passed().await;
+        assert_eq!(
+            eval_result(&player, "gCues").await,
+            "[[#sound1, 1, \"intro\"], [#sound1, 2, \"verse\"], [#sound1, 3, \"verse\"]]"
+        );
+        assert_eq!(eval_result(&player, "sound(1).isPastCuePoint(\"verse\")").await, "2");
+        assert_eq!(eval_result(&player, "sound(1).mostRecentCuePoint").await, "3");
+
+        // Nothing new is crossed, so nothing is dispatched again.
+        dispatch_cue_passed().await;
+        assert_eq!(eval_result(&player, "gCues.count").await, "3");
+    });
+}
@@ -0,0 +1,108 @@
+use vm_rust::player::cycle_collector::collect_cycles;
+use vm_rust::player::datum_ref::DatumRef;
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::testing_shared::TestHarness;
+use vm_rust::player::{reserve_player_mut, reserve_player_ref};
+
+use crate::common::{eval_result, load_test_movie};
+
+const SOURCE: &str = "\
+property pName, pItems
+global gList, gObj, gParent, gChild, gKept, gResult
+
+on tick me
+  pName = pName & \"!\"
+end
+";
+
+async fn eval_all(player: &TestPlayer, commands: &[&str]) {
+    for command in commands {
+        player.eval(command).await.unwrap();
+    }
+}
+
+fn live_counts() -> (usize, usize) {
+    reserve_player_ref(|player| {
+        (player.allocator.datum_count(), player.allocator.script_instance_count())
+    })
+}
+
+#[test]
+fn test_unreachable_cycles_are_freed() {
+    run_test(async {
+        let player = load_test_movie(SOURCE).await;
+        reserve_player_mut(collect_cycles);
+        let (datums_before, instances_before) = live_counts();
+
+        eval_all(&player, &[
+            // A list and a script instance holding each other
+            "gList = [1, \"two\"]",
+            "gObj = script(1).new()",
+            "gObj.pItems = gList",
+            "append(gList, gObj)",
+            // A parent holding a child whose ancestor is the parent
+            "gParent = script(1).new()",
+            "gChild = script(1).new()",
+            "gChild.ancestor = gParent",
+            "gParent.pItems = [\"child\": gChild]",
+            // A list that contains itself
+            "gResult = [3]",
+            "append(gResult, gResult)",
+        ]).await;
+        eval_all(&player, &["gList = 0", "gObj = 0", "gParent = 0", "gChild = 0", "gResult = 0"]).await;
+        let (_, leaked_instances) = live_counts();
+        assert_eq!(leaked_instances, instances_before + 3);
+
+        let collection = reserve_player_mut(collect_cycles);
+        assert_eq!(collection.freed_script_instances, 3);
+        assert!(collection.freed_datums >= 4, "freed {}", collection.freed_datums);
+        assert_eq!(live_counts(), (datums_before, instances_before));
+
+        reserve_player_ref(|player| {
+            let stats = &player.cycle_collector.stats;
+            assert_eq!(stats.runs, 2);
+            assert_eq!(stats.total_freed_script_instances, 3);
+        });
+    });
+}
+
+#[test]
+fn test_reachable_cycles_survive() {
+    run_test(async {
+        let player = load_test_movie(SOURCE).await;
+        // The test movie has no score, so give it some sprite channels.
+        reserve_player_mut(|player| player.movie.score.set_channel_count(10));
+        eval_all(&player, &[
+            "gList = [1, \"two\"]",
+            "gObj = script(1).new()",
+            "gObj.pName = \"a\"",
+            "gObj.pItems = gList",
+            "append(gList, gObj)",
+            "gKept = [:]",
+            "gKept[#self] = gKept",
+            // Only a sprite and a timeout hold these cycles
+            "gParent = script(1).new()",
+            "gParent.pItems = [gParent]",
+            "sprite(5).scriptInstanceList = [gParent]",
+            "gChild = script(1).new()",
+            "gChild.pItems = [gChild]",
+            "timeout(\"tick\").new(1000, #tick, gChild)",
+            "gParent = 0",
+            "gChild = 0",
+        ]).await;
+
+        let collection = reserve_player_mut(collect_cycles);
+        assert_eq!(collection.freed_script_instances, 0);
+        assert_eq!(collection.freed_datums, 0);
+
+        assert_eq!(eval_result(&player, "gList[3].pItems = gList").await, "1");
+        assert_eq!(eval_result(&player, "gKept.self = gKept").await, "1");
+        assert_eq!(eval_result(&player, "count(sprite(5).scriptInstanceList[1].pItems)").await, "1");
+        player.eval("tick(gList[3])").await.unwrap();
+        assert_eq!(eval_result(&player, "gObj.pName").await, "\"a!\"");
+        reserve_player_ref(|player| {
+            let target = &player.timeout_manager.timeouts["tick"].target_ref;
+            assert!(matches!(target, DatumRef::Ref(..)));
+        });
+    });
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use vm_rust::director::chunks::script::ScriptChunk;
+use vm_rust::director::lingo::compiler::compile_script;
+use vm_rust::director::lingo::decompiler::decompile_handler;
+use vm_rust::director::lingo::script::ScriptContext;
+use vm_rust::player::reserve_player_ref;
+use vm_rust::player::testing::run_test;
+use vm_rust::player::testing_shared::TestHarness;
+
+use crate::common::{eval_result, load_test_movie};
+
+const VERSION: u16 = 1150;
+const MULTIPLIER: u32 = 8;
+
+fn compile(source: &str) -> (ScriptContext, ScriptChunk) {
+    let mut names = vec![];
+    let chunk = compile_script(source, &mut names, VERSION, MULTIPLIER).unwrap();
+    let lctx = ScriptContext { names: Rc::new(names), scripts: HashMap::new() };
+    (lctx, chunk)
+}
+
+/// Decompile every handler of `chunk` back to script text.
+fn decompile(lctx: &ScriptContext, chunk: &ScriptChunk) -> String {
+    let mut out = String::new();
+    for handler in &chunk.handlers {
+        let decompiled = decompile_handler(handler, chunk, lctx, VERSION, MULTIPLIER);
+        out.push_str(&format!("on {}", decompiled.name));
+        if !decompiled.arguments.is_empty() {
+            out.push_str(&format!(" {}", decompiled.arguments.join(", ")));
+        }
+        out.push('\n');
+        for line in &decompiled.lines {
+            out.push_str(&"  ".repeat(line.indent as usize + 1));
+            out.push_str(&line.text);
+            out.push('\n');
+        }
+        out.push_str("end\n");
+    }
+    out
+}
+
+fn assert_round_trip(source: &str) {
+    let (lctx, chunk) = compile(source);
+    assert_eq!(decompile(&lctx, &chunk), source);
+}
+
+fn bytecode(chunk: &ScriptChunk) -> Vec<Vec<(u16, i64)>> {
+    chunk
+        .handlers
+        .iter()
+        .map(|h| h.bytecode_array.iter().map(|b| (b.opcode as u16, b.obj)).collect())
+        .collect()
+}
+
+#[test]
+fn test_round_trip_statements() {
+    assert_round_trip(
+        "on test a, b
+  x = a + b * 2
+  if x > 3 then
+    put x
+  else
+    y = \"hi\"
+  end if
+  repeat with i = 1 to 10
+    x = x + i
+  end repeat
+  repeat with i = 10 down to 1
+    x = x - i
+  end repeat
+  repeat while x < 100
+    x = x * 2
+    if x = 8 then
+      exit repeat
+    end if
+  end repeat
+  return x
+end
+",
+    );
+}
+
+#[test]
+fn test_round_trip_case_and_lists() {
+    assert_round_trip(
+        "on test x
+  case x of
+    1:
+      put 1
+    2, 3:
+      put \"b\"
+    otherwise:
+      put \"c\"
+  end case
+  l = [#a: 1, #b: -2.5]
+  put l[1] & l.a
+  repeat with v in [1, 2]
+    put v
+  end repeat
+end
+",
+    );
+}
+
+#[test]
+fn test_round_trip_chunks_and_objects() {
+    assert_round_trip(
+        "on test
+  put char 1 to 3 of \"hello\" into y
+  put \"!\" after word 2 of y
+  delete char 1 of y
+  put the number of chars in y
+  sprite(1).locH = 5
+  sprite(2).locV = the mouseV
+  member(\"x\").text = field(\"y\")
+end
+",
+    );
+}
+
+#[test]
+fn test_properties_globals_and_local_calls() {
+    let source = "property pName
+global gCount
+
+on new me
+  pName = \"x\"
+  gCount = gCount + 1
+  helper(me, 2)
+  return me
+end
+
+on helper me, n
+  global gOther
+  gOther = n
+end
+";
+    let (lctx, chunk) = compile(source);
+    let names = |ids: &[u16]| ids.iter().map(|id| lctx.names[*id as usize].clone()).collect::<Vec<_>>();
+    assert_eq!(names(&chunk.property_name_ids), vec!["pName"]);
+    assert_eq!(names(&chunk.handlers[0].global_name_ids), vec!["gCount"]);
+    assert_eq!(names(&chunk.handlers[1].global_name_ids), vec!["gCount", "gOther"]);
+    assert_eq!(names(&chunk.handlers[1].argument_name_ids), vec!["me", "n"]);
+    assert!(chunk.handlers[0].local_name_ids.is_empty());
+    assert_eq!(
+        decompile(&lctx, &chunk),
+        "on new me
+  pName = \"x\"
+  gCount = gCount + 1
+  helper(me, 2)
+  return me
+end
+on helper me, n
+  gOther = n
+end
+"
+    );
+}
+
+#[test]
+fn test_legacy_syntax() {
+    let (lctx, chunk) = compile(
+        "on mouseUp
+  set the locH of sprite 1 to the mouseH
+  if the text of field \"f\" contains \"a\" then beep
+  go to frame \"start\"
+  put \"x\" into field \"f\"
+end",
+    );
+    assert_eq!(
+        decompile(&lctx, &chunk),
+        "on mouseUp
+  sprite(1).locH = the mouseH
+  if field(\"f\").text contains \"a\" then
+    beep
+  end if
+  go \"start\"
+  put \"x\" into field(\"f\")
+end
+"
+    );
+}
+
+#[test]
+fn test_recompiling_decompiled_output_is_stable() {
+    let (lctx, chunk) = compile(
+        "on test a
+  repeat with i = 1 to a
+    if i mod 2 = 0 then next repeat
+    put item i of \"a,b,c\"
+  end repeat
+  case a of
+    #x: return 1
+  end case
+end",
+    );
+    let text = decompile(&lctx, &chunk);
+    let (_, recompiled) = compile(&text);
+    assert_eq!(bytecode(&recompiled), bytecode(&chunk));
+}
+
+fn compile_error(source: &str) -> String {
+    let mut names = vec![];
+    compile_script(source, &mut names, VERSION, MULTIPLIER).err().unwrap()
+}
+
+#[test]
+fn test_compile_errors_report_lines() {
+    let err = compile_error("on a\n  x = (1 +\nend");
+    assert!(err.starts_with("Line 2:"), "{}", err);
+    let err = compile_error("on a\n  if x then\n    y = 1\nend");
+    assert!(err.contains("'if'"), "{}", err);
+    let err = compile_error("on a\n  exit repeat\nend");
+    assert!(err.starts_with("Line 2:"), "{}", err);
+}
+
+#[test]
+fn test_names_are_shared_with_existing_context() {
+    let mut names = vec!["mouseup".to_string(), "other".to_string()];
+    let chunk = compile_script("on mouseUp\n  newName()\nend", &mut names, VERSION, MULTIPLIER).unwrap();
+    assert_eq!(chunk.handlers[0].name_id, 0);
+    assert_eq!(names, vec!["mouseup", "other", "newName"]);
+}
+
+#[test]
+fn test_handler_survives_replacing_its_own_script() {
+    const SOURCE: &str = "global gResult
+
+on rewrite
+  member(1).scriptText = \"global gResult\" & RETURN & \"on rewrite\" & RETURN & \"  gResult = #replaced\" & RETURN & \"end\"
+  do \"global gResult\" & RETURN & \"gResult = 1\" & RETURN & \"gResult = gResult + 1\"
+  gResult = [gResult, #kept]
+end
+";
+    run_test(async {
+        let player = load_test_movie(SOURCE).await;
+        player.eval("rewrite()").await.unwrap();
+        assert_eq!(eval_result(&player, "gResult").await, "[2, #kept]");
+        player.eval("rewrite()").await.unwrap();
+        assert_eq!(eval_result(&player, "gResult").await, "#replaced");
+    });
+}
+
+#[test]
+fn test_replacing_script_text_does_not_grow_the_context() {
+    run_test(async {
+        let player = load_test_movie("global gResult\non bump\n  gResult = 0\nend\n").await;
+        let script_count = || {
+            reserve_player_ref(|player| {
+                let cast = player.movie.cast_manager.get_cast(1).unwrap();
+                cast.lctx.as_ref().unwrap().scripts.len()
+            })
+        };
+        let initial = script_count();
+        for i in 1..=5 {
+            let source = format!("member(1).scriptText = \"global gResult\" & RETURN & \"on bump\" & RETURN & \"  gResult = {}\" & RETURN & \"end\"", i);
+            player.eval(&source).await.unwrap();
+        }
+        assert_eq!(script_count(), initial);
+        player.eval("bump()").await.unwrap();
+        assert_eq!(eval_result(&player, "gResult").await, "5");
+    });
+}
@@ -0,0 +1,246 @@
+use std::path::PathBuf;
+
+use vm_rust::director::lingo::datum::{Datum, DatumType};
+use vm_rust::player::datum_formatting::format_datum;
+use vm_rust::player::net_backend::NetManifest;
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::testing_shared::TestHarness;
+use vm_rust::player::{player_call_global_handler, reserve_player_mut};
+
+use crate::common::movie_bytes;
+
+const STAGE_SOURCE: &str = "global gLog, gResult, gObj
+
+on activateWindow
+  gLog.add(\"stage:activate\")
+end
+
+on deactivateWindow
+  gLog.add(\"stage:deactivate\")
+end
+
+on openTool
+  open window \"tool\"
+end
+
+on askTool
+  tell window \"tool\"
+    gResult = whoAmI()
+  end tell
+  gLog.add(\"back:\" & the movieName)
+end
+
+on moveTool
+  window(\"tool\").rect = rect(10, 20, 110, 70)
+end
+
+on closeTool
+  close window \"tool\"
+end
+
+on forgetTool
+  forget window \"tool\"
+end
+
+on windowInfo
+  return [count(the windowList), the activeWindow]
+end
+
+on makeToolObject
+  tell window \"tool\"
+    gObj = makeObject()
+  end tell
+end
+
+on askToolObject
+  return gObj.whoAmI()
+end
+
+on peekGhost
+  w = window(\"ghost\")
+  return [w, windowPresent(\"ghost\"), count(the windowList)]
+end
+
+on ghostVisible
+  return window(\"ghost\").visible
+end
+
+on moveGhost
+  window(\"ghost\").rect = rect(0, 0, 10, 10)
+end
+";
+
+const TOOL_SOURCE: &str = "global gLog
+
+on startMovie
+  gLog.add(\"tool:start\")
+end
+
+on openWindow
+  gLog.add(\"tool:open\")
+end
+
+on activateWindow
+  gLog.add(\"tool:activate\")
+end
+
+on deactivateWindow
+  gLog.add(\"tool:deactivate\")
+end
+
+on moveWindow
+  gLog.add(\"tool:move\")
+end
+
+on resizeWindow
+  gLog.add(\"tool:resize\")
+end
+
+on closeWindow
+  gLog.add(\"tool:close\")
+end
+
+on stopMovie
+  gLog.add(\"tool:stop\")
+end
+
+on whoAmI
+  return the movieName
+end
+
+on makeObject
+  return new(script 1)
+end
+";
+
+/// Write the stage and tool movies next to each other, so `window "tool"`
+/// finds tool.dir relative to the stage.
+fn write_movies(test_name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dirplayer_miaw_{}_{}", test_name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("stage.dir"), movie_bytes(STAGE_SOURCE)).unwrap();
+    std::fs::write(dir.join("tool.dir"), movie_bytes(TOOL_SOURCE)).unwrap();
+    dir.join("stage.dir")
+}
+
+async fn call(handler: &str) -> String {
+    let result = player_call_global_handler(handler, &vec![]).await.unwrap();
+    reserve_player_mut(|player| format_datum(&result, player))
+}
+
+/// Return the log entries added since the last call.
+fn take_log() -> String {
+    reserve_player_mut(|player| {
+        let log = player.globals.get("gLog").unwrap().clone();
+        let text = format_datum(&log, player);
+        let Datum::List(_, items, _) = player.get_datum_mut(&log) else {
+            panic!("gLog is not a list");
+        };
+        items.clear();
+        text
+    })
+}
+
+#[test]
+fn test_window_lifecycle_events_and_tell() {
+    let stage_path = write_movies("lifecycle");
+    run_test(async {
+        let mut player = TestPlayer::new();
+        player.use_net_manifest(NetManifest { root: None, timeout_ms: None, routes: vec![] });
+        player.load_movie(stage_path.to_str().unwrap()).await;
+        reserve_player_mut(|player| {
+            let log = player.alloc_datum(Datum::List(DatumType::List, Default::default(), false));
+            player.globals.insert("gLog".to_string(), log);
+        });
+
+        call("openTool").await;
+        assert_eq!(
+            take_log(),
+            "[\"tool:start\", \"tool:open\", \"stage:deactivate\", \"tool:activate\"]"
+        );
+        assert_eq!(call("windowInfo").await, "[1, (window \"tool\")]");
+
+        call("askTool").await;
+        assert_eq!(take_log(), "[\"back:stage.dir\"]");
+        reserve_player_mut(|player| {
+            let result = player.globals.get("gResult").unwrap();
+            assert_eq!(player.get_datum(result).string_value().unwrap(), "tool.dir");
+        });
+
+        call("moveTool").await;
+        assert_eq!(take_log(), "[\"tool:move\", \"tool:resize\"]");
+
+        assert!(player.step_frame().await);
+        assert_eq!(take_log(), "[]");
+
+        call("closeTool").await;
+        assert_eq!(
+            take_log(),
+            "[\"tool:close\", \"tool:deactivate\", \"stage:activate\"]"
+        );
+        assert_eq!(call("windowInfo").await, "[1, the stage]");
+
+        call("forgetTool").await;
+        assert_eq!(take_log(), "[\"tool:stop\"]");
+        assert_eq!(call("windowInfo").await, "[0, the stage]");
+    });
+    std::fs::remove_dir_all(stage_path.parent().unwrap()).ok();
+}
+
+#[test]
+fn test_objects_run_in_the_movie_that_created_them() {
+    let stage_path = write_movies("objects");
+    run_test(async {
+        let mut player = TestPlayer::new();
+        player.use_net_manifest(NetManifest { root: None, timeout_ms: None, routes: vec![] });
+        player.load_movie(stage_path.to_str().unwrap()).await;
+        reserve_player_mut(|player| {
+            let log = player.alloc_datum(Datum::List(DatumType::List, Default::default(), false));
+            player.globals.insert("gLog".to_string(), log);
+        });
+
+        call("openTool").await;
+        call("makeToolObject").await;
+        // Called from the stage, the object still runs its own movie's handler.
+        assert_eq!(call("askToolObject").await, "\"tool.dir\"");
+        reserve_player_mut(|player| assert_eq!(player.windows.current_window(), None));
+    });
+    std::fs::remove_dir_all(stage_path.parent().unwrap()).ok();
+}
+
+#[test]
+fn test_referencing_a_window_does_not_create_it() {
+    let stage_path = write_movies("reference");
+    run_test(async {
+        let mut player = TestPlayer::new();
+        player.use_net_manifest(NetManifest { root: None, timeout_ms: None, routes: vec![] });
+        player.load_movie(stage_path.to_str().unwrap()).await;
+
+        assert_eq!(call("peekGhost").await, "[(window \"ghost\"), 0, 0]");
+        // Report the error instead of pausing for the debugger.
+        reserve_player_mut(|player| player.break_on_error = false);
+        assert!(player.eval("ghostVisible()").await.is_err());
+        assert!(player.eval("moveGhost()").await.is_err());
+        assert_eq!(call("peekGhost").await, "[(window \"ghost\"), 0, 0]");
+    });
+    std::fs::remove_dir_all(stage_path.parent().unwrap()).ok();
+}
+
+#[test]
+fn test_save_state_fails_while_a_window_is_loaded() {
+    let stage_path = write_movies("save_state");
+    run_test(async {
+        let mut player = TestPlayer::new();
+        player.use_net_manifest(NetManifest { root: None, timeout_ms: None, routes: vec![] });
+        player.load_movie(stage_path.to_str().unwrap()).await;
+        reserve_player_mut(|player| {
+            let log = player.alloc_datum(Datum::List(DatumType::List, Default::default(), false));
+            player.globals.insert("gLog".to_string(), log);
+        });
+
+        call("openTool").await;
+        let err = player.save_state().unwrap_err();
+        assert!(err.message.contains("window \"tool\""), "{}", err.message);
+    });
+    std::fs::remove_dir_all(stage_path.parent().unwrap()).ok();
+}
@@ -1,4 +1,28 @@
 #[cfg(not(target_arch = "wasm32"))]
 mod lingo;
+mod common;
 mod e2e;
 mod multiuser;
+mod transition;
+mod tempo_wait;
+mod cue_points;
+mod audio;
+mod lingo_compiler;
+mod rifx_writer;
+mod net_backend;
+mod software_3d;
+mod collision_3d;
+mod miaw;
+mod score_recording;
+mod text_hyperlinks;
+mod mouse_events;
+mod palette_effects;
+mod xtra_registry;
+mod pregex;
+mod buddyapi;
+mod storage;
+mod breakpoints;
+mod watchpoints;
+mod replay;
+mod save_state;
+mod cycle_collector;
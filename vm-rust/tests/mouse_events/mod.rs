@@ -0,0 +1,86 @@
+use vm_rust::director::lingo::datum::{Datum, DatumType};
+use vm_rust::player::commands::{run_player_command, PlayerVMCommand};
+use vm_rust::player::datum_formatting::format_datum;
+use vm_rust::player::reserve_player_mut;
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::testing_shared::TestHarness;
+
+use crate::common::load_test_movie;
+
+const SOURCE: &str = "global gLog
+
+on mouseDown
+  gLog.add(\"mouseDown\")
+end
+
+on rightMouseDown
+  gLog.add(\"rightMouseDown:\" & the rightMouseDown)
+end
+
+on rightMouseUp
+  gLog.add(\"rightMouseUp:\" & the rightMouseDown)
+end
+
+on activateApplication
+  gLog.add(\"activate\")
+end
+
+on deactivateApplication
+  gLog.add(\"deactivate\")
+end
+";
+
+/// Load the test movie with an empty `gLog`.
+async fn start_player() -> TestPlayer {
+    let player = load_test_movie(SOURCE).await;
+    reserve_player_mut(|player| {
+        let log = player.alloc_datum(Datum::List(DatumType::List, Default::default(), false));
+        player.globals.insert("gLog".to_string(), log);
+    });
+    player
+}
+
+/// Run a command and return the log entries it added.
+async fn run(command: PlayerVMCommand) -> String {
+    run_player_command(command).await.unwrap();
+    reserve_player_mut(|player| {
+        let log = player.globals.get("gLog").unwrap().clone();
+        let text = format_datum(&log, player);
+        let Datum::List(_, items, _) = player.get_datum_mut(&log) else {
+            panic!("gLog is not a list");
+        };
+        items.clear();
+        text
+    })
+}
+
+#[test]
+fn test_right_button_events() {
+    run_test(async {
+        let player = start_player().await;
+
+        assert_eq!(run(PlayerVMCommand::RightMouseDown((10, 10))).await, "[\"rightMouseDown:1\"]");
+        assert_eq!(run(PlayerVMCommand::RightMouseUp((12, 10))).await, "[\"rightMouseUp:0\"]");
+        assert_eq!(reserve_player_mut(|player| player.mouse_loc), (12, 10));
+
+        // Control-click is a right click only with emulateMultiButtonMouse on.
+        reserve_player_mut(|player| player.keyboard_manager.key_down("Control".to_string(), 17));
+        assert_eq!(run(PlayerVMCommand::MouseDown((5, 5))).await, "[\"mouseDown\"]");
+        run(PlayerVMCommand::MouseUp((5, 5))).await;
+
+        player.eval("the emulateMultiButtonMouse = TRUE").await.unwrap();
+        assert_eq!(run(PlayerVMCommand::MouseDown((5, 5))).await, "[\"rightMouseDown:1\"]");
+        assert_eq!(run(PlayerVMCommand::MouseUp((5, 5))).await, "[\"rightMouseUp:0\"]");
+    });
+}
+
+#[test]
+fn test_application_focus_events() {
+    run_test(async {
+        let _player = start_player().await;
+
+        assert_eq!(run(PlayerVMCommand::DeactivateApplication).await, "[\"deactivate\"]");
+        assert_eq!(run(PlayerVMCommand::DeactivateApplication).await, "[]");
+        assert_eq!(run(PlayerVMCommand::ActivateApplication).await, "[\"activate\"]");
+    });
+}
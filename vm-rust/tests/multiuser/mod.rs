@@ -1,3 +1,4 @@
 mod binary_read;
 mod binary_write;
 mod blowfish;
+mod server;
@@ -0,0 +1,304 @@
+use binary_reader::BinaryReader;
+use vm_rust::director::lingo::datum::Datum;
+use vm_rust::director::static_datum::StaticDatum;
+use vm_rust::player::datum_ref::DatumRef;
+use vm_rust::player::reserve_player_mut;
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::xtra::multiuser::server::*;
+use vm_rust::player::xtra::multiuser::{
+    borrow_multiuser_manager_mut, logon_message_bytes, reader::MusReader, MultiuserMessage,
+    MultiuserXtraManager,
+};
+
+struct Client {
+    id: MusConnectionId,
+}
+
+impl Client {
+    fn logon(server: &mut MusServer, movie_id: &str, user_id: &str, password: &str) -> (Client, MultiuserMessage) {
+        let client = Client { id: server.connect() };
+        server.receive(client.id, &logon_message_bytes(movie_id, user_id, password, &server.config.encryption_key.clone()));
+        let mut replies = client.recv(server);
+        assert_eq!(replies.len(), 1);
+        (client, replies.remove(0))
+    }
+
+    fn send(&self, server: &mut MusServer, recipients: &[&str], subject: &str, content: StaticDatum) {
+        let message = MultiuserMessage {
+            error_code: 0,
+            recipients: recipients.iter().map(|r| r.to_string()).collect(),
+            sender_id: String::new(),
+            subject: subject.to_string(),
+            content,
+            time_stamp: 0,
+        };
+        // Split the frame to exercise reassembly
+        let bytes = message.to_bytes(None);
+        let (head, tail) = bytes.split_at(bytes.len() / 2);
+        server.receive(self.id, head);
+        server.receive(self.id, tail);
+    }
+
+    fn recv(&self, server: &mut MusServer) -> Vec<MultiuserMessage> {
+        let bytes = server.take_output(self.id);
+        let mut reader = BinaryReader::from_u8(&bytes);
+        let mut messages = vec![];
+        while reader.pos < bytes.len() {
+            messages.push(reader.read_mus_message(None).unwrap());
+        }
+        messages
+    }
+
+    fn command(&self, server: &mut MusServer, subject: &str, content: StaticDatum) -> MultiuserMessage {
+        self.send(server, &["System"], subject, content);
+        let mut replies = self.recv(server);
+        assert_eq!(replies.len(), 1, "expected one reply to {}", subject);
+        replies.remove(0)
+    }
+}
+
+fn string(s: &str) -> StaticDatum {
+    StaticDatum::String(s.to_string())
+}
+
+fn symbol(s: &str) -> StaticDatum {
+    StaticDatum::Symbol(s.to_string())
+}
+
+#[test]
+fn test_logon() {
+    let mut server = MusServer::new(MusServerConfig {
+        encryption_key: "secret".to_string(),
+        accounts: vec![MusUserAccount {
+            user_id: "admin".to_string(),
+            password: "pw".to_string(),
+            user_level: 100,
+        }],
+        ..Default::default()
+    });
+    server.advance_time(1500);
+
+    let (alice, reply) = Client::logon(&mut server, "game", "alice", "");
+    assert_eq!(reply.error_code, 0);
+    assert_eq!(reply.subject, "Logon");
+    assert_eq!(reply.sender_id, "System");
+    assert_eq!(reply.recipients, vec!["alice"]);
+    assert_eq!(reply.time_stamp, 1500);
+    assert_eq!(reply.content, string(&server.config.description));
+    assert_eq!(server.user_id(alice.id), Some("alice"));
+
+    let (_, reply) = Client::logon(&mut server, "game", "ALICE", "");
+    assert_eq!(reply.error_code, MUS_ERROR_INVALID_USER_ID);
+    let (_, reply) = Client::logon(&mut server, "other", "alice", "");
+    assert_eq!(reply.error_code, 0);
+    let (_, reply) = Client::logon(&mut server, "game", "admin", "wrong");
+    assert_eq!(reply.error_code, MUS_ERROR_INVALID_PASSWORD);
+    let (_, reply) = Client::logon(&mut server, "game", "admin", "pw");
+    assert_eq!(reply.error_code, 0);
+
+    // Encrypted with a different key, the logon content is garbage
+    let stranger = server.connect();
+    server.receive(stranger, &logon_message_bytes("game", "bob", "", "other key"));
+    assert_eq!(server.user_id(stranger), None);
+
+    let time = alice.command(&mut server, "system.server.getTime", StaticDatum::Void);
+    assert_eq!(time.content, StaticDatum::Int(1500));
+    let unknown = alice.command(&mut server, "system.bogus", StaticDatum::Void);
+    assert_eq!(unknown.error_code, MUS_ERROR_INVALID_SERVER_COMMAND);
+}
+
+#[test]
+fn test_groups_and_messaging() {
+    let mut server = MusServer::new(MusServerConfig::default());
+    let (alice, _) = Client::logon(&mut server, "game", "alice", "");
+    let (bob, _) = Client::logon(&mut server, "game", "bob", "");
+    let (carol, _) = Client::logon(&mut server, "lobby", "carol", "");
+
+    assert_eq!(server.group_members("game", MUS_ALL_USERS_GROUP), vec!["alice", "bob"]);
+    let joined = alice.command(&mut server, "system.group.join", string("@Table1"));
+    assert_eq!((joined.error_code, joined.content), (0, string("@Table1")));
+    bob.command(&mut server, "system.group.join", string("@table1"));
+
+    let users = alice.command(&mut server, "system.group.getUsers", string("@TABLE1"));
+    assert_eq!(
+        users.content,
+        StaticDatum::PropList(vec![
+            (symbol("group"), string("@Table1")),
+            (symbol("users"), StaticDatum::List(vec![string("alice"), string("bob")])),
+        ])
+    );
+    let count = carol.command(&mut server, "system.group.getUserCount", string("@Table1"));
+    assert_eq!(count.error_code, MUS_ERROR_INVALID_GROUP_NAME);
+
+    // Group broadcast reaches every member, including the sender
+    alice.send(&mut server, &["@Table1"], "move", StaticDatum::List(vec![StaticDatum::Int(3), StaticDatum::Int(4)]));
+    for client in [&alice, &bob] {
+        let received = client.recv(&mut server);
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].sender_id, "alice");
+        assert_eq!(received[0].subject, "move");
+        assert_eq!(received[0].content, StaticDatum::List(vec![StaticDatum::Int(3), StaticDatum::Int(4)]));
+    }
+    assert!(carol.recv(&mut server).is_empty());
+
+    // Peer messages are scoped to the sender's movie
+    bob.send(&mut server, &["alice", "carol"], "chat", string("hi"));
+    let received = alice.recv(&mut server);
+    assert_eq!((received[0].sender_id.as_str(), &received[0].content), ("bob", &string("hi")));
+    let errors = bob.recv(&mut server);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_code, MUS_ERROR_INVALID_USER_ID);
+    assert_eq!(errors[0].content, string("carol"));
+
+    let left = bob.command(&mut server, "system.group.leave", string("@Table1"));
+    assert_eq!(left.error_code, 0);
+    let left = bob.command(&mut server, "system.group.leave", string("@Table1"));
+    assert_eq!(left.error_code, MUS_ERROR_ERROR_LEAVING_GROUP);
+    alice.command(&mut server, "system.group.leave", string("@Table1"));
+    let groups = alice.command(&mut server, "system.group.getGroups", StaticDatum::Void);
+    assert_eq!(groups.content, StaticDatum::List(vec![string("@AllUsers")]));
+
+    server.disconnect(bob.id);
+    let count = alice.command(&mut server, "system.movie.getUserCount", StaticDatum::Void);
+    assert_eq!(count.content, StaticDatum::Int(1));
+}
+
+#[test]
+fn test_db_attributes() {
+    let mut server = MusServer::new(MusServerConfig {
+        require_accounts: true,
+        accounts: vec![MusUserAccount {
+            user_id: "admin".to_string(),
+            password: "pw".to_string(),
+            user_level: 80,
+        }],
+        ..Default::default()
+    });
+    let (admin, _) = Client::logon(&mut server, "game", "admin", "pw");
+    let (_, reply) = Client::logon(&mut server, "game", "dave", "1234");
+    assert_eq!(reply.error_code, MUS_ERROR_INVALID_USER_ID);
+
+    let create = admin.command(
+        &mut server,
+        "system.DBAdmin.createUser",
+        StaticDatum::PropList(vec![(symbol("userID"), string("dave")), (symbol("password"), string("1234"))]),
+    );
+    assert_eq!(create.error_code, 0);
+    let (dave, reply) = Client::logon(&mut server, "game", "dave", "1234");
+    assert_eq!(reply.error_code, 0);
+
+    let denied = dave.command(
+        &mut server,
+        "system.DBAdmin.declareAttribute",
+        StaticDatum::PropList(vec![(symbol("attribute"), symbol("score"))]),
+    );
+    assert_eq!(denied.error_code, MUS_ERROR_NOT_PERMITTED_WITH_USER_LEVEL);
+    let score = StaticDatum::PropList(vec![(symbol("attribute"), StaticDatum::PropList(vec![(symbol("score"), StaticDatum::Int(10))]))]);
+    let undeclared = dave.command(&mut server, "system.DBPlayer.setAttribute", score.clone());
+    assert_eq!(undeclared.error_code, MUS_ERROR_DATABASE_DATA_NOT_FOUND);
+
+    admin.command(
+        &mut server,
+        "system.DBAdmin.declareAttribute",
+        StaticDatum::PropList(vec![(symbol("attribute"), StaticDatum::List(vec![symbol("score")]))]),
+    );
+    assert_eq!(dave.command(&mut server, "system.DBPlayer.setAttribute", score).error_code, 0);
+    assert_eq!(server.player_attribute("game", "dave", "SCORE"), Some(&StaticDatum::Int(10)));
+
+    let get = dave.command(
+        &mut server,
+        "system.DBPlayer.getAttribute",
+        StaticDatum::PropList(vec![(symbol("attribute"), StaticDatum::List(vec![symbol("score")]))]),
+    );
+    assert_eq!(
+        get.content,
+        StaticDatum::PropList(vec![
+            (symbol("userID"), string("dave")),
+            (symbol("attribute"), StaticDatum::PropList(vec![(symbol("score"), StaticDatum::Int(10))])),
+        ])
+    );
+
+    // Other users' records need admin rights
+    let others = StaticDatum::PropList(vec![(symbol("userID"), string("dave")), (symbol("attribute"), symbol("score"))]);
+    let from_admin = admin.command(&mut server, "system.DBPlayer.getAttribute", others.clone());
+    assert_eq!(from_admin.error_code, 0);
+    admin.command(&mut server, "system.DBPlayer.deleteAttribute", others.clone());
+    let missing = admin.command(&mut server, "system.DBPlayer.getAttribute", others);
+    assert_eq!(missing.error_code, MUS_ERROR_DATABASE_DATA_NOT_FOUND);
+    let names = dave.command(&mut server, "system.DBPlayer.getAttributeNames", StaticDatum::Void);
+    assert_eq!(prop_value(&names.content, "attribute"), &StaticDatum::List(vec![]));
+}
+
+fn prop_value<'a>(content: &'a StaticDatum, name: &str) -> &'a StaticDatum {
+    let StaticDatum::PropList(pairs) = content else {
+        panic!("expected a property list");
+    };
+    &pairs.iter().find(|(k, _)| *k == symbol(name)).unwrap().1
+}
+
+fn call(instance_id: u32, handler: &str, args: Vec<Datum>) -> Datum {
+    let args: Vec<DatumRef> = reserve_player_mut(|player| args.into_iter().map(|arg| player.alloc_datum(arg)).collect());
+    let result = MultiuserXtraManager::call_instance_handler(handler, instance_id, &args).unwrap();
+    reserve_player_mut(|player| player.get_datum(&result).clone())
+}
+
+/// `(errorCode, senderID, subject, content)` of the next queued message.
+fn next_message(instance_id: u32) -> Option<(i32, String, String, Datum)> {
+    let Datum::PropList(props, _) = call(instance_id, "getNetMessage", vec![]) else {
+        return None;
+    };
+    reserve_player_mut(|player| {
+        let value = |index: usize| player.get_datum(&props[index].1).clone();
+        Some((
+            value(0).int_value().unwrap(),
+            value(2).string_value().unwrap(),
+            value(3).string_value().unwrap(),
+            value(4),
+        ))
+    })
+}
+
+fn connect(user_id: &str) -> u32 {
+    let instance_id = borrow_multiuser_manager_mut(|manager| manager.create_instance(&vec![]));
+    let args = vec![
+        Datum::String(user_id.to_string()),
+        Datum::String(String::new()),
+        Datum::String("localhost".to_string()),
+        Datum::Int(1626),
+        Datum::String("game".to_string()),
+    ];
+    call(instance_id, "connectToNetServer", args);
+    instance_id
+}
+
+#[test]
+fn test_xtra_connects_to_local_server() {
+    run_test(async {
+        let _player = TestPlayer::new();
+        let server = borrow_multiuser_manager_mut(|manager| manager.add_local_server("LocalHost", 1626, MusServer::new(MusServerConfig::default())));
+        let alice = connect("alice");
+        let bob = connect("bob");
+
+        let (_, _, subject, _) = next_message(alice).unwrap();
+        assert_eq!(subject, "ConnectToNetServer");
+        let (error, sender, subject, _) = next_message(alice).unwrap();
+        assert_eq!((error, sender.as_str(), subject.as_str()), (0, "System", "Logon"));
+        assert!(next_message(alice).is_none());
+        assert_eq!(server.borrow().group_members("game", MUS_ALL_USERS_GROUP), vec!["alice", "bob"]);
+        while next_message(bob).is_some() {}
+
+        call(alice, "sendNetMessage", vec![Datum::String("@AllUsers".to_string()), Datum::String("hello".to_string()), Datum::Int(42)]);
+        for instance_id in [alice, bob] {
+            let (error, sender, subject, content) = next_message(instance_id).unwrap();
+            assert_eq!((error, sender.as_str(), subject.as_str()), (0, "alice", "hello"));
+            assert_eq!(content.int_value().unwrap(), 42);
+        }
+
+        call(bob, "sendNetMessage", vec![Datum::String("System".to_string()), Datum::String("system.group.getUserCount".to_string()), Datum::String("@AllUsers".to_string())]);
+        let (_, _, subject, _) = next_message(bob).unwrap();
+        assert_eq!(subject, "system.group.getUserCount");
+
+        call(bob, "breakConnection", vec![]);
+        assert_eq!(server.borrow().group_members("game", MUS_ALL_USERS_GROUP), vec!["alice"]);
+    });
+}
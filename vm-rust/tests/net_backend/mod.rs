@@ -0,0 +1,251 @@
+use url::Url;
+use vm_rust::director::lingo::datum::Datum;
+use vm_rust::player::handlers::net::NetHandlers;
+use vm_rust::player::net_backend::{NetManifest, NetRoute};
+use vm_rust::player::net_manager::poll_net_tasks;
+use vm_rust::player::net_task::{
+    NET_ERROR_CONNECTION_FAILED, NET_ERROR_EARLY_CLOSE, NET_ERROR_NOT_FOUND, NET_ERROR_TIMEOUT,
+};
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::datum_ref::DatumRef;
+use vm_rust::player::{reserve_player_mut, ScriptError};
+
+type Handler = fn(&Vec<DatumRef>) -> Result<DatumRef, ScriptError>;
+
+fn new_player(routes: Vec<NetRoute>, timeout_ms: Option<f64>) -> TestPlayer {
+    let mut player = TestPlayer::new();
+    player.use_net_manifest(NetManifest { root: None, timeout_ms, routes });
+    reserve_player_mut(|player| {
+        player.net_manager.set_base_path(Url::parse("http://example.com/movies/").unwrap());
+    });
+    player
+}
+
+fn call(handler: Handler, args: Vec<Datum>) -> Datum {
+    let args = reserve_player_mut(|player| args.into_iter().map(|arg| player.alloc_datum(arg)).collect());
+    let result = handler(&args).unwrap();
+    reserve_player_mut(|player| player.get_datum(&result).clone())
+}
+
+fn request(handler: Handler, url: &str) -> i32 {
+    call(handler, vec![Datum::String(url.to_string())]).int_value().unwrap()
+}
+
+fn net_done(task_id: i32) -> bool {
+    call(NetHandlers::net_done, vec![Datum::Int(task_id)]).int_value().unwrap() != 0
+}
+
+fn net_error(task_id: i32) -> String {
+    match call(NetHandlers::net_error, vec![Datum::Int(task_id)]) {
+        Datum::Int(code) => code.to_string(),
+        datum => datum.string_value().unwrap(),
+    }
+}
+
+fn net_text(task_id: i32) -> String {
+    call(NetHandlers::net_text_result, vec![Datum::Int(task_id)]).string_value().unwrap()
+}
+
+/// `(state, bytesSoFar, bytesTotal)` from getStreamStatus
+fn stream_status(task_id: i32) -> (String, i32, i32) {
+    let Datum::PropList(props, _) = call(NetHandlers::get_stream_status, vec![Datum::Int(task_id)]) else {
+        panic!("getStreamStatus did not return a property list");
+    };
+    reserve_player_mut(|player| {
+        let value = |index: usize| player.get_datum(&props[index].1).clone();
+        (value(1).string_value().unwrap(), value(2).int_value().unwrap(), value(3).int_value().unwrap())
+    })
+}
+
+/// Move the network clock forward and apply what happened, like a frame.
+async fn advance(ms: f64) {
+    reserve_player_mut(|player| player.net_manager.advance(ms));
+    poll_net_tasks().await;
+}
+
+fn route(url: &str, body: &str) -> NetRoute {
+    NetRoute {
+        body: Some(body.to_string()),
+        ..NetRoute::new(url)
+    }
+}
+
+#[test]
+fn test_get_net_text_from_route() {
+    run_test(async {
+        let _player = new_player(vec![route("data/news.txt", "Hello")], None);
+        let task_id = request(NetHandlers::get_net_text, "data/news.txt");
+        assert!(net_done(task_id));
+        assert_eq!(net_error(task_id), "OK");
+        assert_eq!(net_text(task_id), "Hello");
+        assert_eq!(stream_status(task_id), ("Complete".to_string(), 5, 5));
+    });
+}
+
+#[test]
+fn test_delayed_and_chunked_response() {
+    run_test(async {
+        let slow = NetRoute {
+            delay_ms: 100.0,
+            chunk_bytes: Some(4),
+            chunk_interval_ms: 50.0,
+            ..route("big.txt", "0123456789")
+        };
+        let _player = new_player(vec![slow], None);
+        let task_id = request(NetHandlers::preload_net_thing, "http://example.com/movies/big.txt");
+        assert!(!net_done(task_id));
+        assert_eq!(net_error(task_id), "0");
+        assert_eq!(stream_status(task_id), ("Connecting".to_string(), 0, 0));
+
+        advance(99.0).await;
+        assert_eq!(stream_status(task_id), ("Connecting".to_string(), 0, 0));
+        advance(1.0).await;
+        assert_eq!(stream_status(task_id), ("InProgress".to_string(), 4, 10));
+        advance(50.0).await;
+        assert_eq!(stream_status(task_id), ("InProgress".to_string(), 8, 10));
+        advance(50.0).await;
+        assert!(net_done(task_id));
+        assert_eq!(stream_status(task_id), ("Complete".to_string(), 10, 10));
+        assert_eq!(net_text(task_id), "0123456789");
+    });
+}
+
+#[test]
+fn test_errors() {
+    run_test(async {
+        let routes = vec![
+            NetRoute {
+                status: Some(404),
+                ..NetRoute::new("missing.txt")
+            },
+            NetRoute {
+                error: Some(NET_ERROR_CONNECTION_FAILED),
+                delay_ms: 20.0,
+                ..NetRoute::new("refused.txt")
+            },
+            NetRoute {
+                partial_bytes: Some(3),
+                ..route("cut.txt", "abcdef")
+            },
+        ];
+        let _player = new_player(routes, None);
+        let missing = request(NetHandlers::get_net_text, "missing.txt");
+        let refused = request(NetHandlers::get_net_text, "refused.txt");
+        let cut = request(NetHandlers::get_net_text, "cut.txt");
+        let unrouted = request(NetHandlers::get_net_text, "http://elsewhere.example/x.txt");
+
+        assert_eq!(net_error(missing), NET_ERROR_NOT_FOUND.to_string());
+        assert_eq!(net_error(cut), NET_ERROR_EARLY_CLOSE.to_string());
+        assert_eq!(stream_status(cut), ("Error".to_string(), 3, 6));
+        assert_eq!(net_error(unrouted), NET_ERROR_CONNECTION_FAILED.to_string());
+        assert!(!net_done(refused));
+        advance(20.0).await;
+        assert_eq!(net_error(refused), NET_ERROR_CONNECTION_FAILED.to_string());
+        assert_eq!(net_text(refused), "");
+    });
+}
+
+#[test]
+fn test_timeout() {
+    run_test(async {
+        let hang = NetRoute {
+            hang: true,
+            ..NetRoute::new("hang.txt")
+        };
+        let late = NetRoute {
+            delay_ms: 2000.0,
+            ..route("late.txt", "too late")
+        };
+        let _player = new_player(vec![hang, late], Some(1000.0));
+        let hang = request(NetHandlers::get_net_text, "hang.txt");
+        let late = request(NetHandlers::get_net_text, "late.txt");
+        advance(999.0).await;
+        assert!(!net_done(hang));
+        advance(1.0).await;
+        assert_eq!(net_error(hang), NET_ERROR_TIMEOUT.to_string());
+        assert_eq!(net_error(late), NET_ERROR_TIMEOUT.to_string());
+        advance(1000.0).await;
+        assert_eq!(net_error(late), NET_ERROR_TIMEOUT.to_string());
+    });
+}
+
+#[test]
+fn test_post_net_text_is_logged() {
+    run_test(async {
+        let post_only = NetRoute {
+            method: Some("POST".to_string()),
+            ..route("login.php", "welcome")
+        };
+        let player = new_player(vec![post_only], None);
+        let form = reserve_player_mut(|player| {
+            let key = player.alloc_datum(Datum::String("name".to_string()));
+            let value = player.alloc_datum(Datum::String("a b".to_string()));
+            Datum::PropList([(key, value)].into(), false)
+        });
+        let posted = call(NetHandlers::post_net_text, vec![Datum::String("login.php".to_string()), form]).int_value().unwrap();
+        let fetched = request(NetHandlers::get_net_text, "login.php");
+        assert_eq!(net_text(posted), "welcome");
+        assert_eq!(net_error(fetched), NET_ERROR_CONNECTION_FAILED.to_string());
+
+        let log = player.net_requests();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].method, "POST");
+        assert_eq!(log[0].resolved_url, "http://example.com/movies/login.php");
+        assert_eq!(log[0].body.as_deref(), Some("name=a%20b"));
+        assert_eq!(log[0].content_type.as_deref(), Some("application/x-www-form-urlencoded"));
+        assert_eq!(log[1].method, "GET");
+        assert_eq!(log[1].body, None);
+    });
+}
+
+#[test]
+fn test_await_task_skips_ahead_on_virtual_clock() {
+    run_test(async {
+        let slow = NetRoute {
+            delay_ms: 60_000.0,
+            ..route("slow.txt", "done")
+        };
+        let hang = NetRoute {
+            hang: true,
+            ..NetRoute::new("hang.txt")
+        };
+        let mut player = new_player(vec![slow, hang], None);
+        let slow = request(NetHandlers::get_net_text, "slow.txt");
+        let hang = request(NetHandlers::get_net_text, "hang.txt");
+        player.await_net_task(slow as u32).await;
+        assert_eq!(reserve_player_mut(|player| player.net_manager.backend.now_ms()), 60_000.0);
+        player.await_net_task(hang as u32).await;
+        assert_eq!(net_text(slow), "done");
+        assert_eq!(net_error(hang), NET_ERROR_TIMEOUT.to_string());
+    });
+}
+
+#[test]
+fn test_manifest_file_and_root() {
+    let dir = std::env::temp_dir().join(format!("dirplayer_net_backend_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("site/data")).unwrap();
+    std::fs::write(dir.join("site/data/list.txt"), "from root").unwrap();
+    std::fs::write(dir.join("override.txt"), "from file").unwrap();
+    std::fs::write(
+        dir.join("manifest.json"),
+        r#"{ "root": "site", "timeoutMs": 500,
+             "routes": [{ "url": "data/special.txt", "file": "override.txt", "delayMs": 10 }] }"#,
+    )
+    .unwrap();
+
+    let manifest = NetManifest::load(&dir.join("manifest.json")).unwrap();
+    assert_eq!(manifest.timeout_ms, Some(500.0));
+    assert_eq!(manifest.routes[0].delay_ms, 10.0);
+    run_test(async {
+        let mut player = TestPlayer::new();
+        player.use_net_manifest(manifest);
+        let listed = request(NetHandlers::get_net_text, "http://example.com/data/list.txt");
+        let special = request(NetHandlers::get_net_text, "http://example.com/data/special.txt");
+        let absent = request(NetHandlers::get_net_text, "http://example.com/data/absent.txt");
+        assert_eq!(net_text(listed), "from root");
+        assert_eq!(net_error(absent), NET_ERROR_NOT_FOUND.to_string());
+        advance(10.0).await;
+        assert_eq!(net_text(special), "from file");
+    });
+    std::fs::remove_dir_all(&dir).unwrap();
+}
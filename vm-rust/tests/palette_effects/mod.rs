@@ -0,0 +1,127 @@
+use binary_reader::{BinaryReader, Endian};
+use vm_rust::director::chunks::score::PaletteChannelData;
+use vm_rust::player::bitmap::bitmap::{resolve_color_ref, BuiltInPalette, PaletteRef};
+use vm_rust::player::bitmap::palette_map::{PaletteEffect, PaletteMap};
+use vm_rust::player::cast_lib::CastMemberRef;
+use vm_rust::player::cast_member::{CastMember, CastMemberType, PaletteMember};
+use vm_rust::player::palette_effects::{self, ColorCycle, PaletteFade};
+use vm_rust::player::reserve_player_mut;
+use vm_rust::player::sprite::ColorRef;
+use vm_rust::player::testing::run_test;
+use vm_rust::player::testing_shared::TestHarness;
+
+use crate::common::{eval_result, load_test_movie};
+
+fn index_color(palettes: &PaletteMap, index: u8, bit_depth: u8) -> (u8, u8, u8) {
+    resolve_color_ref(
+        palettes,
+        &ColorRef::PaletteIndex(index),
+        &PaletteRef::BuiltIn(BuiltInPalette::Rainbow),
+        bit_depth,
+    )
+}
+
+#[test]
+fn test_effect_cycles_and_fades_indexed_colors() {
+    let plain = PaletteMap::new();
+    let mut effected = PaletteMap::new();
+    effected.effect = PaletteEffect {
+        cycles: vec![(10, 13, 1)],
+        ..Default::default()
+    };
+
+    assert_eq!(effected.effect.remap_index(10), 11);
+    assert_eq!(effected.effect.remap_index(13), 10);
+    assert_eq!(effected.effect.remap_index(9), 9);
+    assert_eq!(index_color(&effected, 10, 8), index_color(&plain, 11, 8));
+    // Cycling only applies to 8-bit content.
+    assert_eq!(index_color(&effected, 10, 4), index_color(&plain, 10, 4));
+
+    effected.effect = PaletteEffect {
+        fade: Some(((255, 255, 255), 1.0)),
+        ..Default::default()
+    };
+    assert_eq!(index_color(&effected, 40, 8), (255, 255, 255));
+    assert_eq!(
+        resolve_color_ref(&effected, &ColorRef::Rgb(1, 2, 3), &PaletteRef::Default, 8),
+        (1, 2, 3)
+    );
+    effected.effect.fade = Some(((0, 0, 0), 0.5));
+    assert_eq!(effected.effect.apply_fade((200, 100, 0)), (100, 50, 0));
+}
+
+#[test]
+fn test_palette_channel_cycle_and_fade_timing() {
+    let bytes = [30, 0x80 | 0x10, 20, 10, 0, 1, 0, 2];
+    let mut reader = BinaryReader::from_u8(&bytes);
+    reader.set_endian(Endian::Big);
+    let data = PaletteChannelData::read(&mut reader).unwrap();
+    assert_eq!((data.first_color, data.last_color, data.frame_count, data.cycle_count), (20, 10, 1, 2));
+    assert_eq!(data.fade_color(), None);
+
+    // Range 10-20 auto-reversing at 30 steps per second: one cycle is 20 steps.
+    let cycle = ColorCycle::from_channel(&data, 0.0).unwrap();
+    assert_eq!((cycle.first, cycle.last), (10, 20));
+    let step = 1000.0 / 30.0;
+    assert_eq!(cycle.offset(step * 3.5), 3);
+    assert_eq!(cycle.offset(step * 12.5), 8);
+    assert!(!cycle.is_finished(step * 39.5));
+    assert!(cycle.is_finished(step * 40.5));
+    assert_eq!(cycle.offset(step * 100.0), 0);
+
+    let fade = PaletteFade { color: (0, 0, 0), start_ms: 0.0, half_ms: palette_effects::fade_half_ms(60) };
+    assert_eq!(fade.half_ms, 2000.0 / 60.0);
+    assert_eq!(fade.amount(fade.half_ms / 2.0), Some(0.5));
+    assert_eq!(fade.amount(fade.half_ms), Some(1.0));
+    assert_eq!(fade.amount(fade.half_ms * 2.0), None);
+}
+
+#[test]
+fn test_puppet_palette_and_member_colors() {
+    run_test(async {
+        let player = load_test_movie("global gResult\n").await;
+        reserve_player_mut(|player| {
+            let palette = PaletteMember { colors: vec![(0, 0, 0); 256] };
+            player.movie.cast_manager.casts[0].insert_member(2, CastMember::new(2, CastMemberType::Palette(palette)));
+            player.is_playing = true;
+        });
+        let member_ref = CastMemberRef { cast_lib: 1, cast_member: 2 };
+
+        player.eval("puppetPalette(#grayscale)").await.unwrap();
+        reserve_player_mut(|player| {
+            assert_eq!(
+                palette_effects::active_palette(player),
+                PaletteRef::BuiltIn(BuiltInPalette::GrayScale)
+            );
+        });
+
+        // A transition over time blends from the previous palette.
+        player.eval("puppetPalette(member 2, 1)").await.unwrap();
+        reserve_player_mut(|player| {
+            assert_eq!(palette_effects::active_palette(player), PaletteRef::Member(member_ref.clone()));
+            palette_effects::update(player);
+            let effect = player.movie.cast_manager.palette_effect.borrow().clone();
+            let blend = effect.blend.expect("puppetPalette with a speed should blend");
+            assert_eq!(blend.palette, PaletteRef::Member(member_ref.clone()));
+            assert!(blend.amount < 0.5);
+        });
+
+        player.eval("puppetPalette(0)").await.unwrap();
+        reserve_player_mut(|player| {
+            assert_eq!(palette_effects::active_palette(player), player.movie.score.get_frame_palette(1));
+        });
+
+        player.eval("member(2).colors = [rgb(255, 0, 0), rgb(0, 0, 255)]").await.unwrap();
+        assert_eq!(eval_result(&player, "member(2).colors").await, "[rgb(255, 0, 0), rgb(0, 0, 255)]");
+        reserve_player_mut(|player| {
+            let palettes = player.movie.cast_manager.palettes();
+            let color = resolve_color_ref(
+                &palettes,
+                &ColorRef::PaletteIndex(1),
+                &PaletteRef::Member(member_ref.clone()),
+                8,
+            );
+            assert_eq!(color, (0, 0, 255));
+        });
+    });
+}
@@ -0,0 +1,119 @@
+use vm_rust::player::regex::{Regex, RegexFlags};
+use vm_rust::player::testing::run_test;
+use vm_rust::player::testing_shared::TestHarness;
+use vm_rust::player::xtra::pregex::borrow_pregex_manager_mut;
+
+use crate::common::{eval_result, load_test_movie};
+
+fn find(pattern: &str, flags: &str, text: &str) -> Option<Vec<Option<String>>> {
+    let regex = Regex::new(pattern, RegexFlags::parse(flags)).unwrap();
+    let chars: Vec<char> = text.chars().collect();
+    let found = regex.find_at(&chars, 0).unwrap()?;
+    Some((0..=regex.group_count()).map(|group| found.group_text(&chars, group)).collect())
+}
+
+fn whole(pattern: &str, flags: &str, text: &str) -> Option<String> {
+    find(pattern, flags, text).and_then(|groups| groups[0].clone())
+}
+
+#[test]
+fn test_regex_syntax() {
+    assert_eq!(whole(r"\d+", "", "abc 123 def"), Some("123".into()));
+    assert_eq!(whole(r"<.+>", "", "<a><b>"), Some("<a><b>".into()));
+    assert_eq!(whole(r"<.+?>", "", "<a><b>"), Some("<a>".into()));
+    assert_eq!(whole(r"a{2,3}", "", "aaaa"), Some("aaa".into()));
+    assert_eq!(whole(r"x{2}", "", "x{2}xx"), Some("xx".into()));
+    assert_eq!(whole(r"[^aeiou\s]+", "", "a quick"), Some("q".into()));
+    assert_eq!(whole(r"[[:upper:]][[:lower:]]+", "", "hello World"), Some("World".into()));
+    assert_eq!(whole(r"HELLO", "i", "say hello"), Some("hello".into()));
+    assert_eq!(whole(r"(?i)hello(?-i) World", "", "HELLO World"), Some("HELLO World".into()));
+    assert_eq!(whole(r"(?i)hello(?-i) World", "", "HELLO world"), None);
+    assert_eq!(whole(r"^b", "", "a\nb"), None);
+    assert_eq!(whole(r"^b$", "m", "a\nb\nc"), Some("b".into()));
+    assert_eq!(whole(r"a.b", "", "a\nb"), None);
+    assert_eq!(whole(r"a.b", "s", "a\nb"), Some("a\nb".into()));
+    assert_eq!(whole(r"\bcat\b", "", "concat cat"), Some("cat".into()));
+    assert_eq!(whole(" a b # comment\n c ", "x", "abc"), Some("abc".into()));
+    assert_eq!(whole(r"cat|dog", "", "hotdog"), Some("dog".into()));
+
+    assert_eq!(
+        find(r"(\w+)@(\w+)\.com", "", "mail bob@example.com now"),
+        Some(vec![Some("bob@example.com".into()), Some("bob".into()), Some("example".into())])
+    );
+    assert_eq!(find(r"(a)|(b)", "", "b"), Some(vec![Some("b".into()), None, Some("b".into())]));
+    assert_eq!(whole(r"(\w)\1", "", "abccd"), Some("cc".into()));
+    assert_eq!(whole(r"(?<q>['\x22]).*?\k<q>", "", r#"say "hi" now"#), Some("\"hi\"".into()));
+    assert_eq!(whole(r"\d+(?= dollars)", "", "5 euros 10 dollars"), Some("10".into()));
+    assert_eq!(whole(r"\b\d+\b(?! dollars)", "", "10 dollars 5 euros"), Some("5".into()));
+    assert_eq!(whole(r"(?<=\$)\d+", "", "cost: $42"), Some("42".into()));
+    assert_eq!(whole(r"(?<!\$)\b\d+", "", "$42 or 7"), Some("7".into()));
+    assert_eq!(whole(r"(ab)*c", "", "ababc"), Some("ababc".into()));
+    assert_eq!(whole(r"(a|)*b", "", "aab"), Some("aab".into()));
+
+    assert!(Regex::new("(abc", RegexFlags::default()).is_err());
+    assert!(Regex::new("abc)", RegexFlags::default()).is_err());
+    assert!(Regex::new("*a", RegexFlags::default()).is_err());
+    assert!(Regex::new("[z-a]", RegexFlags::default()).is_err());
+
+    // Repeated groups over long subjects don't recurse per iteration.
+    let text = "ab".repeat(100_000);
+    assert_eq!(whole(r"(ab)*", "", &text).map(|m| m.len()), Some(text.len()));
+    assert_eq!(whole(r"(?:a|b)+$", "", &text).map(|m| m.len()), Some(text.len()));
+
+    // Catastrophic backtracking is an error instead of a hang or a silent
+    // non-match.
+    let regex = Regex::new(r"(a*)*b", RegexFlags::default()).unwrap();
+    let chars: Vec<char> = "a".repeat(40).chars().collect();
+    assert!(regex.find_at(&chars, 0).is_err());
+}
+
+#[test]
+fn test_pregex_handlers() {
+    run_test(async {
+        let player = load_test_movie("global gText, gResult\n").await;
+
+        player.eval("gText = [\"Hello darn world, darn it\"]").await.unwrap();
+        assert_eq!(eval_result(&player, "PRegEx_Search(gText, \"d(ar)n\", \"g\")").await, "2");
+        assert_eq!(eval_result(&player, "[PRegEx_GetMatchString(gText, 0), PRegEx_GetMatchString(gText, 1), PRegEx_GetMatchStart(gText), PRegEx_GetMatchEnd(gText), PRegEx_GetMatchBRCount(gText)]").await, "[\"darn\", \"ar\", 7, 10, 1]");
+        assert_eq!(eval_result(&player, "[PRegEx_SearchContinue(gText), PRegEx_GetMatchStart(gText), PRegEx_SearchContinue(gText)]").await, "[1, 19, 0]");
+
+        assert_eq!(eval_result(&player, "PRegEx_Replace(gText, \"d(a)rn\", \"gi\", \"d$1ng\")").await, "2");
+        assert_eq!(eval_result(&player, "gText").await, "[\"Hello dang world, dang it\"]");
+
+        assert_eq!(eval_result(&player, "PRegEx_Split([\"a, b,,c,,\"], \",\\s*\", \"\")").await, "[\"a\", \"b\", \"\", \"c\"]");
+        assert_eq!(eval_result(&player, "PRegEx_Split([\"k1=v1;k2=v2\"], \"([=;])\", \"\", 3)").await, "[\"k1\", \"=\", \"v1\", \";\", \"k2=v2\"]");
+
+        player.eval("gText = [\"apple\", \"Banana\", \"cherry\", \"avocado\"]").await.unwrap();
+        assert_eq!(eval_result(&player, "PRegEx_Grep(gText, \"^a\", \"i\")").await, "2");
+        assert_eq!(eval_result(&player, "gText").await, "[\"apple\", \"avocado\"]");
+
+        player.eval("gText = [\"hello\"]").await.unwrap();
+        assert_eq!(eval_result(&player, "PRegEx_Translate(gText, \"a-y\", \"b-z\")").await, "5");
+        assert_eq!(eval_result(&player, "gText").await, "[\"ifmmp\"]");
+
+        player.eval("gText = [\"1+1=2?\"]").await.unwrap();
+        eval_result(&player, "PRegEx_QuoteMeta(gText)").await;
+        assert_eq!(eval_result(&player, "gText").await, r#"["1\+1\=2\?"]"#);
+
+        assert!(player.eval("PRegEx_Search(\"not a list\", \"a\", \"\")").await.is_err());
+        assert!(player.eval("PRegEx_Search([\"x\"], \"(\", \"\")").await.is_err());
+    });
+}
+
+#[test]
+fn test_pregex_state_does_not_outlive_its_list() {
+    run_test(async {
+        let player = load_test_movie("global gText, gResult\n").await;
+
+        player.eval("gText = [\"one two\"]").await.unwrap();
+        assert_eq!(eval_result(&player, "PRegEx_SearchBegin(gText, \"\\w+\", \"\")").await, "1");
+        player.eval("gText = [\"three\"]").await.unwrap();
+        assert_eq!(eval_result(&player, "PRegEx_GetMatchString(gText, 0)").await, "Void");
+        assert!(player.eval("PRegEx_SearchContinue(gText)").await.is_err());
+
+        for _ in 0..20 {
+            player.eval("PRegEx_Search([\"abc\"], \"b\", \"\")").await.unwrap();
+        }
+        assert!(borrow_pregex_manager_mut(|manager| manager.search_count()) <= 2);
+    });
+}
@@ -0,0 +1,108 @@
+use vm_rust::player::net_backend::{NetManifest, NetRoute};
+use vm_rust::player::replay::{ReplayFile, ReplayInput};
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::testing_shared::TestHarness;
+
+use crate::common::{eval_result, load_test_movie};
+
+const SOURCE: &str = "\
+global gLog, gTask, gResult
+
+on startMovie
+  gLog = []
+  gTask = getNetText(\"news.txt\")
+end
+
+on exitFrame
+  if gTask <> VOID then
+    if netDone(gTask) then
+      append(gLog, [#net, netTextResult(gTask), the ticks])
+      gTask = VOID
+    end if
+  end if
+end
+
+on mouseDown
+  append(gLog, [#click, the mouseH, the mouseV, random(1000), the ticks, the milliSeconds])
+end
+
+on keyDown
+  append(gLog, [#key, the key, random(1000), the timer])
+end
+";
+
+async fn start_player(routes: Vec<NetRoute>) -> TestPlayer {
+    let mut player = load_test_movie(SOURCE).await;
+    player.use_net_manifest(NetManifest { root: None, timeout_ms: None, routes });
+    player
+}
+
+/// Play a session with clicks, keys and a slow network request, with wall
+/// clock pauses that the recording must not depend on.
+async fn record_session() -> (Vec<u8>, String) {
+    let mut route = NetRoute::new("news.txt");
+    route.body = Some("hello".to_string());
+    route.delay_ms = 100.0;
+    let mut player = start_player(vec![route]).await;
+    player.start_recording(1234);
+    player.init_movie().await;
+
+    player.step_frames(2).await;
+    player.click(40, 50).await;
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    player.step_frames(3).await;
+    player.key_press("a", 0).await;
+    player.mouse_down(7, 8).await;
+    player.step_frames(4).await;
+
+    let replay_file = player.stop_recording();
+    let log = eval_result(&player, "gLog").await;
+    (replay_file, log)
+}
+
+#[test]
+fn test_replay_reproduces_the_recorded_run() {
+    let (replay_file, recorded_log) = {
+        let mut result = None;
+        run_test(async { result = Some(record_session().await) });
+        result.unwrap()
+    };
+    assert!(recorded_log.contains("[#net, \"hello\", "), "{}", recorded_log);
+    assert!(recorded_log.contains("[#click, 40, 50, "), "{}", recorded_log);
+    assert!(recorded_log.contains("[#key, \"a\", "), "{}", recorded_log);
+    assert!(recorded_log.contains("[#click, 7, 8, "), "{}", recorded_log);
+
+    run_test(async {
+        // No routes: the network results come from the recording.
+        let mut player = start_player(vec![]).await;
+        player.start_replay(&replay_file).unwrap();
+        player.init_movie().await;
+        player.finish_replay().await;
+        assert_eq!(eval_result(&player, "gLog").await, recorded_log);
+    });
+}
+
+#[test]
+fn test_replay_file_round_trips() {
+    let (replay_file, _) = {
+        let mut result = None;
+        run_test(async { result = Some(record_session().await) });
+        result.unwrap()
+    };
+    let file = ReplayFile::from_bytes(&replay_file).unwrap();
+    assert_eq!(file.rng_seed, 1234);
+    assert_eq!(file.frame_count, 11);
+    assert!(!file.host_timeouts);
+    assert_eq!(file.events[0].input, ReplayInput::MouseDown { x: 40, y: 50 });
+    assert_eq!(file.events[0].frame, 2);
+    assert!(file.events.iter().any(|event| matches!(
+        &event.input,
+        ReplayInput::NetTaskDone { data: Some(data), error: None, .. } if data == "aGVsbG8="
+    )));
+    assert_eq!(ReplayFile::from_bytes(&file.to_bytes()).unwrap().events, file.events);
+
+    assert!(ReplayFile::from_bytes(b"{}").is_err());
+    let mut newer = file.clone();
+    newer.version += 1;
+    assert!(ReplayFile::from_bytes(&newer.to_bytes()).is_err());
+}
@@ -0,0 +1,270 @@
+use std::io::Write;
+
+use binary_reader::Endian;
+use flate2::{write::ZlibEncoder, Compression};
+use vm_rust::director::chunks::key_table::KeyTableEntry;
+use vm_rust::director::chunks::script_names::ScriptNamesChunk;
+use vm_rust::director::file::{read_director_file_bytes, DirectorFile};
+use vm_rust::director::lingo::compiler::compile_script;
+use vm_rust::director::lingo::decompiler::decompile_script;
+use vm_rust::director::lingo::script::ScriptContext;
+use vm_rust::director::utils::FOURCC;
+use vm_rust::director::writer::{write_director_file, write_to_vec, RIFXWriter};
+
+use crate::common::{cast_member_bytes, config_bytes, script_context_bytes, DIRECTOR_VERSION, MULTIPLIER, VERSION};
+
+const SOURCE: &str = "property pCount
+
+on mouseUp me
+  pCount = pCount + 1
+  put \"clicked\" && pCount
+end
+";
+
+/// A movie with one internal cast holding a single script member.
+/// Chunk ids: KEY* 3, DRCF 4, CAS* 5, CASt 6, Lctx 7, Lnam 8, Lscr 9.
+fn build_movie(endian: Endian, script_text: &str, protection: u16) -> RIFXWriter {
+    let mut names = vec![];
+    let mut script = compile_script(SOURCE, &mut names, VERSION, MULTIPLIER).unwrap();
+    script.script_number = 0;
+
+    let mut writer = RIFXWriter::new(endian, FOURCC("MV93"), DIRECTOR_VERSION);
+    writer.add_chunk(FOURCC("DRCF"), config_bytes(protection), None);
+    let cast_id = writer.add_chunk(FOURCC("CAS*"), 6u32.to_be_bytes().to_vec(), Some(1024));
+    writer.add_chunk(FOURCC("CASt"), cast_member_bytes(script_text, Some("Clicker")), None);
+    writer.add_chunk(FOURCC("Lctx"), script_context_bytes(8, 9), Some(1024));
+    let names = ScriptNamesChunk { names };
+    writer.add_chunk(FOURCC("Lnam"), write_to_vec(binary_rw::Endian::Big, |w| names.write(w)).unwrap(), None);
+    writer.add_chunk(FOURCC("Lscr"), vec![], None);
+    writer.set_script(9, &script, false).unwrap();
+    assert_eq!(cast_id, 5);
+    writer
+}
+
+fn read(bytes: &Vec<u8>, file_name: &str) -> DirectorFile {
+    read_director_file_bytes(bytes, file_name, "http://localhost/").unwrap()
+}
+
+fn member_script_text(file: &DirectorFile) -> String {
+    let member = &file.casts[0].members[&1];
+    member.chunk.member_info.as_ref().unwrap().script_src_text.clone()
+}
+
+fn decompiled_scripts(file: &DirectorFile) -> String {
+    let lctx = file.casts[0].lctx.as_ref().unwrap();
+    decompile_script(&lctx.scripts[&1], lctx, VERSION, MULTIPLIER)
+}
+
+#[test]
+fn test_round_trip_is_byte_identical() {
+    for endian in [Endian::Big, Endian::Little] {
+        let bytes = build_movie(endian, SOURCE, 0).write().unwrap();
+        let file = read(&bytes, "test.dir");
+        assert_eq!(member_script_text(&file), SOURCE);
+        assert_eq!(file.casts[0].members[&1].chunk.member_info.as_ref().unwrap().name, "Clicker");
+        assert_eq!(write_director_file(&file).unwrap(), bytes);
+    }
+}
+
+#[test]
+fn test_written_script_decompiles_to_its_source() {
+    let bytes = build_movie(Endian::Big, SOURCE, 0).write().unwrap();
+    let file = read(&bytes, "test.dir");
+    let lctx = file.casts[0].lctx.as_ref().unwrap();
+    assert_eq!(lctx.names.iter().filter(|name| *name == "pCount").count(), 1);
+    assert_eq!(decompiled_scripts(&file), SOURCE);
+}
+
+fn var_int(mut value: u32) -> Vec<u8> {
+    let mut bytes = vec![(value & 0x7f) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.insert(0, (value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes
+}
+
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Pack the chunks of `writer` the way Afterburner does: `KEY*` and `DRCF`
+/// in the initial load segment, everything else as separate zlib streams.
+fn afterburn(writer: &RIFXWriter) -> Vec<u8> {
+    let mut chunks: Vec<(u32, u32, Vec<u8>)> = writer
+        .chunks()
+        .map(|(id, chunk)| (*id, chunk.fourcc, chunk.data.clone()))
+        .collect();
+    let key_table = write_to_vec(binary_rw::Endian::Big, |w| writer.key_table.write(w)).unwrap();
+    chunks.insert(0, (3, FOURCC("KEY*"), key_table));
+
+    let mut ils = vec![];
+    let mut body = vec![];
+    let mut entries = vec![];
+    for (id, fourcc, data) in &chunks {
+        if [FOURCC("KEY*"), FOURCC("DRCF")].contains(fourcc) {
+            ils.extend(var_int(*id));
+            ils.extend_from_slice(data);
+            entries.push((*id, 0, data.len(), data.len(), *fourcc));
+        } else {
+            let compressed = zlib(data);
+            entries.push((*id, body.len(), compressed.len(), data.len(), *fourcc));
+            body.extend(compressed);
+        }
+    }
+    let ils_compressed = zlib(&ils);
+    for entry in entries.iter_mut() {
+        if !(entry.4 == FOURCC("KEY*") || entry.4 == FOURCC("DRCF")) {
+            entry.1 += ils_compressed.len();
+        }
+    }
+    entries.insert(0, (2, 0, ils_compressed.len(), ils.len(), FOURCC("ILS ")));
+
+    let mut abmp = vec![];
+    abmp.extend(var_int(0));
+    abmp.extend(var_int(0));
+    abmp.extend(var_int(entries.len() as u32));
+    for (id, offset, comp_size, uncomp_size, fourcc) in &entries {
+        abmp.extend(var_int(*id));
+        abmp.extend(var_int(*offset as u32));
+        abmp.extend(var_int(*comp_size as u32));
+        abmp.extend(var_int(*uncomp_size as u32));
+        abmp.extend(var_int(0));
+        abmp.extend_from_slice(&fourcc.to_be_bytes());
+    }
+
+    let mut fcdr = vec![];
+    fcdr.extend_from_slice(&1u16.to_be_bytes());
+    for value in [0xAC99E904u32.to_be_bytes().to_vec(), 0x0070u16.to_be_bytes().to_vec(), 0x0B36u16.to_be_bytes().to_vec()] {
+        fcdr.extend(value);
+    }
+    fcdr.extend_from_slice(&0x00080000u32.to_be_bytes());
+    fcdr.extend_from_slice(&0x347A3707u32.to_be_bytes());
+    fcdr.extend_from_slice(b"zlib\0");
+
+    let mut out = vec![];
+    out.extend_from_slice(b"RIFX");
+    out.extend_from_slice(&0u32.to_be_bytes());
+    out.extend_from_slice(b"FGDM");
+    out.extend_from_slice(b"Fver");
+    let fver = var_int(0x400);
+    out.extend(var_int(fver.len() as u32));
+    out.extend(fver);
+    out.extend_from_slice(b"Fcdr");
+    let fcdr = zlib(&fcdr);
+    out.extend(var_int(fcdr.len() as u32));
+    out.extend(fcdr);
+    out.extend_from_slice(b"ABMP");
+    let mut abmp_section = vec![];
+    abmp_section.extend(var_int(0));
+    abmp_section.extend(var_int(abmp.len() as u32));
+    abmp_section.extend(zlib(&abmp));
+    out.extend(var_int(abmp_section.len() as u32));
+    out.extend(abmp_section);
+    out.extend_from_slice(b"FGEI");
+    out.extend(var_int(0));
+    out.extend(ils_compressed);
+    out.extend(body);
+    let len = out.len() as u32 - 8;
+    out[4..8].copy_from_slice(&len.to_be_bytes());
+    out
+}
+
+#[test]
+fn test_afterburned_movie_converts_to_uncompressed() {
+    let movie = build_movie(Endian::Big, SOURCE, 0);
+    let expected = movie.write().unwrap();
+    let file = read(&afterburn(&movie), "test.dcr");
+    assert!(file.after_burned);
+    assert_eq!(member_script_text(&file), SOURCE);
+    assert_eq!(write_director_file(&file).unwrap(), expected);
+}
+
+#[test]
+fn test_set_member_script_text() {
+    let bytes = build_movie(Endian::Little, SOURCE, 0).write().unwrap();
+    let mut writer = RIFXWriter::from_director_file(&read(&bytes, "test.dir")).unwrap();
+    writer.set_member_script_text(6, "on exitFrame\r  go the frame\rend").unwrap();
+    let file = read(&writer.write().unwrap(), "test.dir");
+    assert_eq!(member_script_text(&file), "on exitFrame\r  go the frame\rend");
+    assert_eq!(file.casts[0].members[&1].chunk.member_info.as_ref().unwrap().name, "Clicker");
+    assert_eq!(file.casts[0].members[&1].chunk.member_info.as_ref().unwrap().header.script_id, 1);
+}
+
+#[test]
+fn test_protected_movie_is_restored() {
+    let bytes = build_movie(Endian::Big, "", 23 * 4).write().unwrap();
+    let file = read(&bytes, "test.dir");
+    assert!(file.config.is_protected());
+    assert_eq!(member_script_text(&file), "");
+
+    let mut writer = RIFXWriter::from_director_file(&file).unwrap();
+    writer.unprotect().unwrap();
+    writer.restore_script_text(&file).unwrap();
+    let restored = read(&writer.write().unwrap(), "test.dir");
+    assert!(!restored.config.is_protected());
+    assert_eq!(restored.config.checksum, restored.config.compute_checksum(Endian::Big));
+    assert_eq!(member_script_text(&restored), decompiled_scripts(&file).replace('\n', "\r"));
+    assert!(member_script_text(&restored).starts_with("property pCount\r\ron mouseUp me\r"));
+}
+
+fn e2e_movie_paths() -> Vec<std::path::PathBuf> {
+    let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let public = manifest_dir.parent().unwrap().join("public");
+    let mut paths: Vec<_> = std::fs::read_dir(manifest_dir.join("tests/e2e/configs"))
+        .unwrap()
+        .filter_map(|entry| std::fs::read_to_string(entry.ok()?.path()).ok())
+        .filter_map(|config| {
+            let line = config.lines().find(|line| line.trim_start().starts_with("path"))?;
+            Some(public.join(line.split('"').nth(1)?))
+        })
+        .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Every e2e movie must survive a write/read cycle with identical chunk
+/// payloads, and writing it again must reproduce the same bytes.
+#[test]
+fn test_e2e_movies_round_trip() {
+    let mut round_tripped_movies = 0;
+    for path in e2e_movie_paths() {
+        // Not every e2e movie is checked in; the ones that are must round trip.
+        let Ok(bytes) = std::fs::read(&path) else {
+            continue;
+        };
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let file = read(&bytes, &name);
+        let writer = RIFXWriter::from_director_file(&file).unwrap();
+        let written = writer.write().unwrap();
+
+        let reread = read(&written, &name);
+        let rewriter = RIFXWriter::from_director_file(&reread).unwrap();
+        let original: Vec<_> = writer.chunks().map(|(id, chunk)| (*id, chunk.fourcc, &chunk.data)).collect();
+        let round_tripped: Vec<_> = rewriter.chunks().map(|(id, chunk)| (*id, chunk.fourcc, &chunk.data)).collect();
+        assert!(original == round_tripped, "{}: chunk payloads differ after a round trip", name);
+        assert!(rewriter.write().unwrap() == written, "{}: writing is not stable", name);
+
+        let mut keys: Vec<(u32, u32, u32)> = reread.key_table.as_ref().unwrap().entries.iter().map(key).collect();
+        let mut expected: Vec<(u32, u32, u32)> = writer.key_table.entries.iter().map(key).collect();
+        keys.sort();
+        expected.sort();
+        assert_eq!(keys, expected, "{}: key table differs", name);
+        assert_eq!(reread.casts.len(), file.casts.len(), "{}", name);
+        for (cast, reread_cast) in file.casts.iter().zip(&reread.casts) {
+            assert_eq!(cast.members.len(), reread_cast.members.len(), "{}", name);
+            let names = |lctx: Option<&ScriptContext>| lctx.map(|lctx| lctx.names.clone());
+            assert_eq!(names(cast.lctx.as_ref()), names(reread_cast.lctx.as_ref()), "{}", name);
+        }
+        round_tripped_movies += 1;
+    }
+    assert!(round_tripped_movies > 0, "no e2e movie was found to round trip");
+}
+
+fn key(entry: &KeyTableEntry) -> (u32, u32, u32) {
+    (entry.section_id, entry.cast_id, entry.fourcc)
+}
@@ -0,0 +1,151 @@
+use vm_rust::player::{reserve_player_mut, reserve_player_ref};
+use vm_rust::player::save_state::{SaveState, SAVE_STATE_VERSION};
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::testing_shared::TestHarness;
+
+use crate::common::{eval_result, load_test_movie};
+
+const SOURCE: &str = "\
+property pName, pItems
+global gList, gAlias, gObj, gResult, gImage, gDate, gMember
+
+on tick me
+  pName = pName & \"!\"
+end
+";
+
+async fn start_player() -> TestPlayer {
+    let player = load_test_movie(SOURCE).await;
+    // The test movie has no score, so give it some sprite channels.
+    reserve_player_mut(|player| player.movie.score.set_channel_count(10));
+    for command in [
+        "gList = [1, \"two\", #three, [4.5], rect(1, 2, 3, 4)]",
+        "gAlias = gList",
+        "gObj = script(1).new()",
+        "gObj.pName = \"a\"",
+        "gObj.pItems = gList",
+        "append(gList, gObj)",
+        "sprite(5).puppet = TRUE",
+        "sprite(5).locH = 40",
+        "member(1).name = \"saved\"",
+        "timeout(\"tick\").new(1000, #tick, gObj)",
+    ] {
+        player.eval(command).await.unwrap();
+    }
+    player
+}
+
+#[test]
+fn test_restore_returns_to_the_saved_state() {
+    run_test(async {
+        let mut player = start_player().await;
+        let saved_list = eval_result(&player, "gList").await;
+        let state = player.save_state().unwrap();
+
+        for command in [
+            "append(gList, 6)",
+            "gObj.pName = \"b\"",
+            "gAlias = 0",
+            "sprite(5).locH = 99",
+            "member(1).name = \"changed\"",
+            "timeout(\"tick\").forget()",
+        ] {
+            player.eval(command).await.unwrap();
+        }
+        player.restore_state(&state).unwrap();
+
+        // Script instances get fresh ids, so leave the offspring's out.
+        let without_ids = |list: &str| list.split("<offspring").next().unwrap().to_string();
+        assert_eq!(without_ids(&eval_result(&player, "gList").await), without_ids(&saved_list));
+        assert_eq!(eval_result(&player, "getPos(gList, gObj)").await, "6");
+        assert_eq!(eval_result(&player, "gObj.pName").await, "\"a\"");
+        assert_eq!(eval_result(&player, "sprite(5).locH").await, "40");
+        assert_eq!(eval_result(&player, "member(1).name").await, "\"saved\"");
+        reserve_player_ref(|player| {
+            let timeout = &player.timeout_manager.timeouts["tick"];
+            assert_eq!(timeout.period, 1000);
+            assert!(timeout.is_scheduled);
+        });
+
+        // Shared references and the list <-> instance cycle survive.
+        player.eval("append(gAlias, 7)").await.unwrap();
+        assert_eq!(eval_result(&player, "count(gList)").await, "7");
+        assert_eq!(eval_result(&player, "gList[6].pItems = gList").await, "1");
+        player.eval("tick(gList[6])").await.unwrap();
+        assert_eq!(eval_result(&player, "gObj.pName").await, "\"a!\"");
+    });
+}
+
+#[test]
+fn test_random_continues_the_same_after_restore() {
+    run_test(async {
+        let mut player = start_player().await;
+        let state = player.save_state().unwrap();
+        let numbers = "[random(1000), random(1000), random(1000), random(1000)]";
+        let first = eval_result(&player, numbers).await;
+        player.restore_state(&state).unwrap();
+        assert_eq!(eval_result(&player, numbers).await, first);
+    });
+}
+
+#[test]
+fn test_save_state_format_is_checked() {
+    run_test(async {
+        let mut player = start_player().await;
+        let state = player.save_state().unwrap();
+        let parsed = SaveState::from_bytes(&state).unwrap();
+        assert_eq!(parsed.version, SAVE_STATE_VERSION);
+        assert_eq!(parsed.movie_file_name, "test.dir");
+        assert_eq!(parsed.to_bytes(), state);
+
+        let mut newer = state.clone();
+        newer[4..8].copy_from_slice(&(SAVE_STATE_VERSION + 1).to_le_bytes());
+        let err = player.restore_state(&newer).unwrap_err();
+        assert!(err.message.contains("unsupported version"), "{}", err.message);
+        assert!(player.restore_state(&state[..state.len() - 1]).is_err());
+        assert!(player.restore_state(b"not a save state").is_err());
+    });
+}
+
+#[test]
+fn test_images_dates_and_member_images_are_restored() {
+    run_test(async {
+        let mut player = start_player().await;
+        for command in [
+            "gImage = image(2, 2, 32)",
+            "gDate = the systemDate",
+            "gMember = new(#bitmap, castLib 1)",
+            "gImage.setPixel(0, 0, rgb(255, 0, 0))",
+            "gMember.image = gImage",
+        ] {
+            player.eval(command).await.unwrap();
+        }
+        let pixel = eval_result(&player, "gImage.getPixel(0, 0)").await;
+        let date = eval_result(&player, "gDate").await;
+        let state = player.save_state().unwrap();
+
+        for command in [
+            "gImage.setPixel(0, 0, rgb(0, 0, 255))",
+            "gMember.image = image(8, 8, 32)",
+            "gDate = 0",
+        ] {
+            player.eval(command).await.unwrap();
+        }
+        player.restore_state(&state).unwrap();
+
+        assert_eq!(eval_result(&player, "gImage.getPixel(0, 0)").await, pixel);
+        assert_eq!(eval_result(&player, "gDate").await, date);
+        assert_eq!(eval_result(&player, "gMember.width").await, "2");
+        assert_eq!(eval_result(&player, "gMember.image.getPixel(0, 0)").await, pixel);
+    });
+}
+
+#[test]
+fn test_unsupported_datums_fail_the_snapshot() {
+    run_test(async {
+        let player = start_player().await;
+        player.eval("gResult = new(xtra(\"fileio\"))").await.unwrap();
+        let err = player.save_state().unwrap_err();
+        assert!(err.message.contains("Cannot save a "), "{}", err.message);
+    });
+}
@@ -0,0 +1,148 @@
+use vm_rust::director::chunks::score::ScoreFrameChannelData;
+use vm_rust::director::lingo::datum::Datum;
+use vm_rust::player::cast_lib::CastMemberRef;
+use vm_rust::player::handlers::movie::MovieHandlers;
+use vm_rust::player::score::Score;
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::testing_shared::TestHarness;
+use vm_rust::player::reserve_player_mut;
+
+fn member(number: i32) -> CastMemberRef {
+    CastMemberRef { cast_lib: 1, cast_member: number }
+}
+
+fn sprite_data(member: u16, loc_h: i16) -> ScoreFrameChannelData {
+    ScoreFrameChannelData { cast_lib: 1, cast_member: member, pos_x: loc_h, ..Default::default() }
+}
+
+/// `(channel, start, end)` of every span, sorted.
+fn spans(score: &Score) -> Vec<(u32, u32, u32)> {
+    let mut spans: Vec<_> = score
+        .sprite_spans
+        .iter()
+        .map(|span| (span.channel_number, span.start_frame, span.end_frame))
+        .collect();
+    spans.sort();
+    spans
+}
+
+/// locH recorded for sprite 1 in each frame, by 1-based frame number.
+fn recorded_loc_h(score: &Score) -> Vec<(u32, i16)> {
+    score
+        .channel_initialization_data
+        .iter()
+        .filter(|(_, channel_index, _)| *channel_index == 6)
+        .map(|(frame_index, _, data)| (frame_index + 1, data.pos_x))
+        .collect()
+}
+
+#[test]
+fn test_recorded_frames_join_into_one_sprite_span() {
+    let mut score = Score::empty();
+    for (frame, loc_h) in [(1, 10), (2, 20), (3, 30)] {
+        score.record_sprite_channel(frame, 1, Some(sprite_data(5, loc_h)), vec![]);
+    }
+    score.record_sprite_channel(4, 1, Some(sprite_data(6, 40)), vec![]);
+
+    assert_eq!(spans(&score), vec![(1, 1, 3), (1, 4, 4)]);
+    assert_eq!(recorded_loc_h(&score), vec![(1, 10), (2, 20), (3, 30), (4, 40)]);
+    assert!(score.recorded_channels.contains(&1));
+
+    // Emptying a frame in the middle splits the sprite
+    score.record_sprite_channel(2, 1, None, vec![]);
+    assert_eq!(spans(&score), vec![(1, 1, 1), (1, 3, 3), (1, 4, 4)]);
+}
+
+#[test]
+fn test_insert_delete_and_clear_frame_shift_the_score() {
+    let mut score = Score::empty();
+    for (frame, loc_h) in [(1, 10), (2, 20), (3, 30)] {
+        score.record_sprite_channel(frame, 1, Some(sprite_data(5, loc_h)), vec![]);
+    }
+    score.set_frame_script(3, Some(member(9)));
+    score.frame_count = Some(3);
+
+    score.insert_frame(2);
+    assert_eq!(spans(&score), vec![(0, 4, 4), (1, 1, 4)]);
+    assert_eq!(recorded_loc_h(&score), vec![(1, 10), (2, 20), (3, 20), (4, 30)]);
+    assert_eq!(score.frame_count, Some(4));
+
+    score.delete_frame(1);
+    assert_eq!(spans(&score), vec![(0, 3, 3), (1, 1, 3)]);
+    assert_eq!(recorded_loc_h(&score), vec![(1, 20), (2, 20), (3, 30)]);
+    assert_eq!(score.frame_count, Some(3));
+
+    score.clear_frame(3);
+    assert_eq!(spans(&score), vec![(1, 1, 2)]);
+    assert_eq!(recorded_loc_h(&score), vec![(1, 20), (2, 20)]);
+}
+
+fn recording_command(name: &str) {
+    MovieHandlers::score_recording(name, &[]).unwrap();
+}
+
+fn movie_prop(prop: &str) -> i32 {
+    reserve_player_mut(|player| player.movie.get_prop(prop).unwrap().int_value().unwrap())
+}
+
+fn set_movie_prop(prop: &str, value: Datum) -> Result<(), String> {
+    reserve_player_mut(|player| player.movie.set_prop(prop, value, &player.allocator)).map_err(|err| err.message)
+}
+
+#[test]
+fn test_recording_session_writes_frames_that_play_back() {
+    run_test(async {
+        let mut player = TestPlayer::new();
+        reserve_player_mut(|player| player.movie.score.set_channel_count(4));
+
+        assert!(set_movie_prop("frameTempo", Datum::Int(15)).is_err());
+        assert!(MovieHandlers::score_recording("updateFrame", &[]).is_err());
+
+        recording_command("beginRecording");
+        for loc_h in [100, 120, 140] {
+            reserve_player_mut(|player| {
+                let sprite = player.movie.score.get_sprite_mut(1);
+                sprite.member = Some(member(3));
+                sprite.loc_h = loc_h;
+                sprite.loc_v = 50;
+            });
+            if loc_h == 100 {
+                set_movie_prop("frameTempo", Datum::Int(15)).unwrap();
+                set_movie_prop("frameScript", Datum::Int(7)).unwrap();
+                assert_eq!(movie_prop("frameTempo"), 15);
+            }
+            recording_command("updateFrame");
+        }
+        recording_command("endRecording");
+
+        reserve_player_mut(|player| {
+            let score = &player.movie.score;
+            assert_eq!(player.movie.current_frame, 4);
+            assert_eq!(spans(score), vec![(0, 1, 1), (1, 1, 3)]);
+            assert_eq!(recorded_loc_h(score), vec![(1, 100), (2, 120), (3, 140)]);
+            assert_eq!(score.get_frame_tempo(2), Some(15));
+            assert_eq!(score.frame_count, Some(3));
+        });
+        assert_eq!(movie_prop("lastFrame"), 3);
+
+        // Play the recorded frames back from a clean stage. Member 7 does not
+        // exist in the empty test movie, so drop the frame script first.
+        reserve_player_mut(|player| {
+            player.movie.score.set_frame_script(1, None);
+            player.movie.score.get_sprite_mut(1).reset();
+            player.movie.current_frame = 1;
+            player.next_frame = Some(1);
+            player.is_playing = true;
+        });
+        let mut positions = vec![];
+        for _ in 0..3 {
+            player.step_frame().await;
+            positions.push(reserve_player_mut(|player| {
+                let sprite = player.movie.score.get_sprite(1).unwrap();
+                (player.movie.current_frame, sprite.loc_h)
+            }));
+        }
+        assert_eq!(positions, vec![(1, 100), (2, 120), (3, 140)]);
+        assert_eq!(movie_prop("frameScript"), 0);
+    });
+}
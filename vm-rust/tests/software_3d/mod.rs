@@ -0,0 +1,193 @@
+use vm_rust::director::chunks::w3d::types::{
+    W3dMaterial, W3dNode, W3dNodeType, W3dRawMesh, W3dScene, W3dShader, W3dTextureLayer,
+};
+use vm_rust::player::bitmap::bitmap::Bitmap;
+use vm_rust::player::cast_member::{CameraOverlay, Shockwave3dRuntimeState};
+use vm_rust::rendering_cpu::scene3d::render_scene_to_bitmap;
+
+const SIZE: u32 = 64;
+const MEMBER: (i32, i32) = (1, 1);
+const BACKGROUND: [u8; 3] = [51, 51, 51];
+
+fn translation(x: f32, y: f32, z: f32) -> [f32; 16] {
+    [
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        x, y, z, 1.0,
+    ]
+}
+
+fn pixel(bitmap: &Bitmap, x: usize, y: usize) -> [u8; 3] {
+    let o = (y * bitmap.width as usize + x) * 4;
+    [bitmap.data[o], bitmap.data[o + 1], bitmap.data[o + 2]]
+}
+
+fn raw_rgba_texture(width: u32, height: u32, pixels: &[[u8; 4]]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&width.to_le_bytes());
+    data.extend_from_slice(&height.to_le_bytes());
+    for p in pixels {
+        data.extend_from_slice(p);
+    }
+    data
+}
+
+/// A 20x20 quad in the XY plane facing +Z (or -Z when `reversed`).
+fn quad_mesh(name: &str, reversed: bool) -> W3dRawMesh {
+    let faces = if reversed { vec![[0, 2, 1], [0, 3, 2]] } else { vec![[0, 1, 2], [0, 2, 3]] };
+    W3dRawMesh {
+        name: name.to_string(),
+        chain_index: 0,
+        positions: vec![[-10.0, -10.0, 0.0], [10.0, -10.0, 0.0], [10.0, 10.0, 0.0], [-10.0, 10.0, 0.0]],
+        normals: vec![[0.0, 0.0, 1.0]; 4],
+        tex_coords: vec![[-0.5, -0.5], [0.5, -0.5], [0.5, 0.5], [-0.5, 0.5]],
+        vertex_colors: Vec::new(),
+        faces,
+    }
+}
+
+fn emissive_material(name: &str, color: [f32; 3]) -> W3dMaterial {
+    W3dMaterial {
+        name: name.to_string(),
+        ambient: [0.0, 0.0, 0.0, 1.0],
+        diffuse: [0.0, 0.0, 0.0, 1.0],
+        emissive: [color[0], color[1], color[2], 1.0],
+        ..Default::default()
+    }
+}
+
+fn model_node(name: &str, resource: &str, shader: &str, z: f32) -> W3dNode {
+    W3dNode {
+        name: name.to_string(),
+        parent_name: "World".to_string(),
+        resource_name: resource.to_string(),
+        node_type: W3dNodeType::Model,
+        transform: translation(0.0, 0.0, z),
+        shader_name: shader.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Scene with a DefaultView camera at z=100 looking down -Z.
+fn base_scene() -> W3dScene {
+    let mut scene = W3dScene::default();
+    scene.nodes.push(W3dNode {
+        name: "DefaultView".to_string(),
+        parent_name: "World".to_string(),
+        node_type: W3dNodeType::View,
+        transform: translation(0.0, 0.0, 100.0),
+        fov: 30.0,
+        ..Default::default()
+    });
+    scene
+}
+
+fn add_emissive_quad(scene: &mut W3dScene, name: &str, color: [f32; 3], z: f32, reversed: bool) {
+    let mat_name = format!("{}Mat", name);
+    scene.materials.push(emissive_material(&mat_name, color));
+    scene.shaders.push(W3dShader {
+        name: format!("{}Shader", name),
+        material_name: mat_name,
+        ..Default::default()
+    });
+    scene.raw_meshes.push(quad_mesh(&format!("{}Res", name), reversed));
+    scene.nodes.push(model_node(name, &format!("{}Res", name), &format!("{}Shader", name), z));
+}
+
+fn render(scene: &W3dScene, state: &Shockwave3dRuntimeState) -> Bitmap {
+    render_scene_to_bitmap(MEMBER, scene, Some(state), None, &[], SIZE, SIZE)
+}
+
+#[test]
+fn test_software_3d_draws_model_over_background() {
+    let mut scene = base_scene();
+    add_emissive_quad(&mut scene, "box", [1.0, 0.0, 0.0], 0.0, false);
+
+    let bitmap = render(&scene, &Shockwave3dRuntimeState::default());
+    assert_eq!((bitmap.width, bitmap.height, bitmap.bit_depth), (64, 64, 32));
+    assert_eq!(pixel(&bitmap, 32, 32), [255, 0, 0]);
+    assert_eq!(pixel(&bitmap, 2, 2), BACKGROUND);
+}
+
+#[test]
+fn test_software_3d_depth_test_keeps_nearest_surface() {
+    let mut scene = base_scene();
+    // Nearer quad is listed first; the farther one must not overwrite it.
+    add_emissive_quad(&mut scene, "near", [0.0, 1.0, 0.0], 10.0, false);
+    add_emissive_quad(&mut scene, "far", [1.0, 0.0, 0.0], 0.0, false);
+
+    let bitmap = render(&scene, &Shockwave3dRuntimeState::default());
+    assert_eq!(pixel(&bitmap, 32, 32), [0, 255, 0]);
+}
+
+#[test]
+fn test_software_3d_back_faces_follow_visibility() {
+    let mut scene = base_scene();
+    add_emissive_quad(&mut scene, "back", [1.0, 0.0, 0.0], 0.0, true);
+
+    let mut state = Shockwave3dRuntimeState::default();
+    let culled = render(&scene, &state);
+    assert_eq!(pixel(&culled, 32, 32), BACKGROUND);
+
+    state.node_visibility.insert("back".to_string(), 3); // #both
+    let both = render(&scene, &state);
+    assert_eq!(pixel(&both, 32, 32), [255, 0, 0]);
+}
+
+#[test]
+fn test_software_3d_background_color_and_fog() {
+    let mut scene = base_scene();
+    add_emissive_quad(&mut scene, "box", [1.0, 0.0, 0.0], 0.0, false);
+
+    let mut state = Shockwave3dRuntimeState::default();
+    state.background_color = Some((0, 0, 255));
+    state.fog_enabled = true;
+    state.fog_near = 0.0;
+    state.fog_far = 50.0;
+    state.fog_color = (1.0, 1.0, 1.0);
+    state.fog_mode = 0;
+
+    let bitmap = render(&scene, &state);
+    assert_eq!(pixel(&bitmap, 2, 2), [0, 0, 255]);
+    // The quad is 100 units away, past fog far: fully fogged.
+    assert_eq!(pixel(&bitmap, 32, 32), [255, 255, 255]);
+}
+
+#[test]
+fn test_software_3d_texture_layer_and_overlay() {
+    let mut scene = base_scene();
+    scene.texture_images.insert(
+        "Blue".to_string(),
+        raw_rgba_texture(2, 2, &[[0, 0, 255, 255]; 4]),
+    );
+    scene.texture_images.insert(
+        "Hud".to_string(),
+        raw_rgba_texture(2, 1, &[[255, 0, 0, 255], [0, 255, 0, 255]]),
+    );
+    scene.materials.push(emissive_material("texMat", [1.0, 1.0, 1.0]));
+    scene.shaders.push(W3dShader {
+        name: "texShader".to_string(),
+        material_name: "texMat".to_string(),
+        texture_layers: vec![W3dTextureLayer { name: "Blue".to_string(), ..Default::default() }],
+        ..Default::default()
+    });
+    scene.raw_meshes.push(quad_mesh("quadRes", false));
+    scene.nodes.push(model_node("quad", "quadRes", "texShader", 0.0));
+
+    let mut state = Shockwave3dRuntimeState::default();
+    state.camera_overlays.insert("defaultview".to_string(), vec![CameraOverlay {
+        source_texture: "Hud".to_string(),
+        source_texture_lower: "hud".to_string(),
+        loc: [4.0, 4.0],
+        blend: 100.0,
+        scale: 4.0,
+        ..Default::default()
+    }]);
+
+    let bitmap = render(&scene, &state);
+    assert_eq!(pixel(&bitmap, 32, 32), [0, 0, 255]);
+    assert_eq!(pixel(&bitmap, 5, 5), [255, 0, 0]);
+    assert_eq!(pixel(&bitmap, 10, 5), [0, 255, 0]);
+    assert_eq!(pixel(&bitmap, 5, 10), BACKGROUND);
+}
@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+
+use vm_rust::player::reserve_player_ref;
+use vm_rust::player::storage::storage_namespace;
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::testing_shared::TestHarness;
+
+use crate::common::{eval_result, load_test_movie_at};
+
+const SOURCE: &str = "global gResult, gFile\n";
+
+/// Storage is namespaced by where the movie was loaded from, so each test
+/// loads its movies from a directory of its own.
+async fn start_player(movie_path: &Path, storage_dir: Option<&Path>) -> TestPlayer {
+    let mut player = TestPlayer::new();
+    if let Some(dir) = storage_dir {
+        player.use_storage_dir(dir);
+    }
+    load_test_movie_at(&mut player, movie_path, SOURCE).await;
+    player
+}
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dirplayer_storage_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn movie_namespace() -> String {
+    reserve_player_ref(|player| player.storage.namespace.clone())
+}
+
+#[test]
+fn test_storage_namespace() {
+    assert_eq!(storage_namespace("Habbo.DCR"), "habbo");
+    assert_eq!(
+        storage_namespace("http://example.com/games/My Game.dir"),
+        "example.com%2Fgames%2Fmy game"
+    );
+    assert_eq!(storage_namespace("http://example.com:8080/main.dcr"), "example.com%3A8080%2Fmain");
+    assert_eq!(storage_namespace("file:///home/ann/main.dcr"), "home%2Fann%2Fmain");
+    assert_eq!(storage_namespace("C:\\Games\\main.dir"), "c%3A%2Fgames%2Fmain");
+    assert_eq!(storage_namespace(""), "untitled");
+}
+
+#[test]
+fn test_storage_survives_restart() {
+    run_test(async {
+        let dir = test_dir("restart");
+        let store = dir.join("store");
+        let movie = dir.join("movies").join("Saves.dir");
+
+        let namespace = {
+            let player = start_player(&movie, Some(&store)).await;
+            player.eval("setPref(\"hiscore\", \"1200\")").await.unwrap();
+            player.eval("gFile = new(xtra(\"fileio\"))").await.unwrap();
+            player.eval("createFile(gFile, \"C:\\Saves\\slot1.sav\")").await.unwrap();
+            player.eval("writeString(gFile, \"level=12\")").await.unwrap();
+            player.eval("closeFile(gFile)").await.unwrap();
+            player.eval("baWriteIni(\"Options\", \"Sound\", \"0\", \"game.ini\")").await.unwrap();
+            movie_namespace()
+        };
+        assert!(namespace.ends_with("%2Fmovies%2Fsaves"), "{}", namespace);
+        assert_eq!(std::fs::read_to_string(store.join("_prefs/prefs/hiscore")).unwrap(), "1200");
+        let files = store.join(&namespace).join("files");
+        assert_eq!(std::fs::read(files.join("C%3A%5CSaves%5Cslot1.sav")).unwrap(), b"level=12");
+
+        let player = start_player(&movie, Some(&store)).await;
+        assert_eq!(eval_result(&player, "getPref(\"hiscore\")").await, "\"1200\"");
+        assert_eq!(eval_result(&player, "getPref(\"missing\")").await, "Void");
+        assert_eq!(eval_result(&player, "baFileSize(\"C:\\Saves\\slot1.sav\")").await, "8");
+        assert_eq!(eval_result(&player, "baReadIni(\"Options\", \"Sound\", \"1\", \"game.ini\")").await, "\"0\"");
+        assert_eq!(eval_result(&player, "baDeleteFile(\"game.ini\")").await, "1");
+        assert!(!files.join("game.ini").exists());
+        drop(player);
+        std::fs::remove_dir_all(dir).ok();
+    });
+}
+
+#[test]
+fn test_movies_keep_their_files_and_share_prefs() {
+    run_test(async {
+        let dir = test_dir("movies");
+        let store = dir.join("store");
+        let first = dir.join("first").join("main.dir");
+        let second = dir.join("second").join("main.dir");
+
+        let mut player = start_player(&first, Some(&store)).await;
+        player.eval("setPref(\"volume\", \"3\")").await.unwrap();
+        player.eval("baWriteIni(\"Game\", \"Level\", \"7\", \"game.ini\")").await.unwrap();
+        let first_namespace = movie_namespace();
+
+        // A movie with the same name elsewhere doesn't see the first one's
+        // files, but does see its prefs.
+        load_test_movie_at(&mut player, &second, SOURCE).await;
+        assert_ne!(movie_namespace(), first_namespace);
+        assert_eq!(eval_result(&player, "baFileExists(\"game.ini\")").await, "0");
+        assert_eq!(eval_result(&player, "getPref(\"volume\")").await, "\"3\"");
+        player.eval("baWriteIni(\"Game\", \"Level\", \"1\", \"game.ini\")").await.unwrap();
+
+        load_test_movie_at(&mut player, &first, SOURCE).await;
+        assert_eq!(eval_result(&player, "baReadIni(\"Game\", \"Level\", \"\", \"game.ini\")").await, "\"7\"");
+        drop(player);
+        std::fs::remove_dir_all(dir).ok();
+    });
+}
+
+#[test]
+fn test_storage_archive_round_trip() {
+    run_test(async {
+        let dir = test_dir("archive");
+        let movie = dir.join("Quest.dir");
+        let archive = {
+            let player = start_player(&movie, None).await;
+            player.eval("setPref(\"player\", \"Ann\")").await.unwrap();
+            player.eval("gFile = new(xtra(\"fileio\"))").await.unwrap();
+            player.eval("createFile(gFile, \"quest.sav\")").await.unwrap();
+            player.eval("writeString(gFile, \"chapter 3\")").await.unwrap();
+            player.eval("closeFile(gFile)").await.unwrap();
+            player.export_storage().await.unwrap()
+        };
+        assert_eq!(&archive[..4], b"PK\x03\x04");
+
+        // A fresh player starts from the seeded save.
+        let mut player = start_player(&movie, None).await;
+        assert_eq!(eval_result(&player, "getPref(\"player\")").await, "Void");
+        player.eval("setPref(\"stale\", \"1\")").await.unwrap();
+        player.import_storage(&archive).await.unwrap();
+        assert_eq!(eval_result(&player, "getPref(\"player\")").await, "\"Ann\"");
+        assert_eq!(eval_result(&player, "getPref(\"stale\")").await, "Void");
+        assert_eq!(eval_result(&player, "baFileSize(\"quest.sav\")").await, "9");
+        assert_eq!(player.export_storage().await.unwrap(), archive);
+
+        assert!(player.import_storage(b"not a zip").await.is_err());
+        drop(player);
+        std::fs::remove_dir_all(dir).ok();
+    });
+}
@@ -0,0 +1,120 @@
+use vm_rust::director::chunks::score::TempoChannelData;
+use vm_rust::player::commands::{run_player_command, PlayerVMCommand};
+use vm_rust::player::tempo_wait::{CuePointTarget, TempoWaitMode};
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::testing_shared::TestHarness;
+use vm_rust::player::{reserve_player_mut, reserve_player_ref};
+
+use crate::common::load_test_movie;
+
+fn tempo(tempo: u8, tempo_cue_point: u16) -> TempoChannelData {
+    TempoChannelData {
+        tempo,
+        tempo_cue_point,
+        sprite_list_idx: 0,
+        color_tempo: 0,
+        wait_flags: 0,
+        channel_flags: 0,
+        frame_data: 0,
+    }
+}
+
+#[test]
+fn test_fps_is_not_a_wait() {
+    assert_eq!(TempoWaitMode::from_tempo_data(&tempo(30, 0)), None);
+    assert_eq!(TempoWaitMode::from_tempo_data(&tempo(246, 15)), None);
+}
+
+#[test]
+fn test_delay_and_click() {
+    assert_eq!(
+        TempoWaitMode::from_tempo_data(&tempo(247, 3)),
+        Some(TempoWaitMode::Delay { seconds: 3 })
+    );
+    assert_eq!(TempoWaitMode::from_tempo_data(&tempo(248, 0)), Some(TempoWaitMode::Click));
+}
+
+#[test]
+fn test_sound_and_cue_point_waits() {
+    assert_eq!(
+        TempoWaitMode::from_tempo_data(&tempo(254, 0)),
+        Some(TempoWaitMode::Sound { channel: 1 })
+    );
+    assert_eq!(
+        TempoWaitMode::from_tempo_data(&tempo(255, 0)),
+        Some(TempoWaitMode::Sound { channel: 2 })
+    );
+    assert_eq!(
+        TempoWaitMode::from_tempo_data(&tempo(254, 2)),
+        Some(TempoWaitMode::CuePoint { channel: 1, cue_point: CuePointTarget::Index(2) })
+    );
+    assert_eq!(
+        TempoWaitMode::from_tempo_data(&tempo(255, 0xFFFE)),
+        Some(TempoWaitMode::CuePoint { channel: 2, cue_point: CuePointTarget::Next })
+    );
+    assert_eq!(
+        TempoWaitMode::from_tempo_data(&tempo(255, 0xFFFF)),
+        Some(TempoWaitMode::CuePoint { channel: 2, cue_point: CuePointTarget::End })
+    );
+}
+
+#[test]
+fn test_d5_tempo_codes_are_normalised() {
+    assert_eq!(TempoChannelData::from_d5_tempo(0), None);
+    assert_eq!(TempoChannelData::from_d5_tempo(15), Some(tempo(15, 0)));
+    assert_eq!(TempoChannelData::from_d5_tempo(128), Some(tempo(248, 0)));
+    assert_eq!(TempoChannelData::from_d5_tempo(135), Some(tempo(254, 0)));
+    assert_eq!(TempoChannelData::from_d5_tempo(134), Some(tempo(255, 0)));
+    assert_eq!(TempoChannelData::from_d5_tempo(253), Some(tempo(247, 3)));
+    let decoded = TempoChannelData::from_d5_tempo(255).and_then(|data| TempoWaitMode::from_tempo_data(&data));
+    assert_eq!(decoded, Some(TempoWaitMode::Delay { seconds: 1 }));
+}
+
+/// Start a scoreless movie with `data` in the tempo channel of frame 2.
+async fn start_player(data: TempoChannelData) -> TestPlayer {
+    let mut player = load_test_movie("").await;
+    reserve_player_mut(|player| player.movie.score.tempo_channel_data = vec![(1, data)]);
+    player.init_movie().await;
+    player
+}
+
+async fn step_to_frame_2(player: &mut TestPlayer) {
+    while player.current_frame() < 2 {
+        player.step_frame().await;
+    }
+    assert_eq!(player.current_frame(), 2);
+    assert!(reserve_player_ref(|player| player.tempo_wait.is_some()));
+}
+
+#[test]
+fn test_click_wait_holds_the_playhead() {
+    run_test(async {
+        let mut player = start_player(tempo(248, 0)).await;
+        step_to_frame_2(&mut player).await;
+
+        player.step_frames(3).await;
+        assert_eq!(player.current_frame(), 2);
+
+        run_player_command(PlayerVMCommand::MouseDown((10, 10))).await.unwrap();
+        run_player_command(PlayerVMCommand::MouseUp((10, 10))).await.unwrap();
+        player.step_frame().await;
+        assert_eq!(player.current_frame(), 3);
+        assert!(reserve_player_ref(|player| player.tempo_wait.is_none()));
+    });
+}
+
+#[test]
+fn test_delay_wait_expires() {
+    run_test(async {
+        let mut player = start_player(tempo(247, 2)).await;
+        step_to_frame_2(&mut player).await;
+
+        player.step_frames(3).await;
+        assert_eq!(player.current_frame(), 2);
+
+        // Move the start of the wait back instead of sleeping for the delay.
+        reserve_player_mut(|player| player.tempo_wait.as_mut().unwrap().started_ms -= 2000.0);
+        player.step_frame().await;
+        assert_eq!(player.current_frame(), 3);
+    });
+}
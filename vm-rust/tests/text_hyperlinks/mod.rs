@@ -0,0 +1,165 @@
+use fxhash::FxHashMap;
+use vm_rust::director::chunks::xmedia_styled_text::parse_xmed;
+use vm_rust::director::enums::TextInfo;
+use vm_rust::director::lingo::datum::Datum;
+use vm_rust::player::cast_lib::{CastLib, CastLibState, CastMemberRef};
+use vm_rust::player::cast_member::{CastMember, CastMemberType, TextMember};
+use vm_rust::player::handlers::datum_handlers::cast_member::font::{HtmlParser, HtmlStyle, StyledSpan};
+use vm_rust::player::handlers::datum_handlers::cast_member::text::TextMemberHandlers;
+use vm_rust::player::hyperlinks::{set_hyperlink, HyperlinkState, TextHyperlink};
+use vm_rust::player::reserve_player_mut;
+use vm_rust::player::testing::{run_test, TestPlayer};
+
+fn links(links: &[TextHyperlink]) -> Vec<(usize, usize, &str)> {
+    links.iter().map(|link| (link.start, link.end, link.data.as_str())).collect()
+}
+
+/// One XMED section: 20-char hex header followed by its bytes.
+fn xmed_section(key: u16, data: &[u8]) -> Vec<u8> {
+    let mut bytes = format!("{:04X}{:08X}{:04X}{:04X}", key, data.len(), 0, 0).into_bytes();
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+/// Packer-encoded hex numbers, each behind a control byte.
+fn packed_nums(nums: &[i32]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for num in nums {
+        bytes.push(0x04);
+        bytes.extend(format!("{:X}", num).into_bytes());
+    }
+    bytes
+}
+
+/// Put a text member into a fresh cast 1 and return its ref.
+fn insert_text_member(member: TextMember) -> CastMemberRef {
+    reserve_player_mut(|player| {
+        let mut cast = CastLib {
+            name: "Internal".to_string(),
+            file_name: String::new(),
+            number: 1,
+            is_external: false,
+            state: CastLibState::Loaded,
+            lctx: None,
+            members: FxHashMap::default(),
+            scripts: FxHashMap::default(),
+            preload_mode: 0,
+            capital_x: false,
+            dir_version: 1201,
+            palette_id_offset: 0,
+        };
+        cast.insert_member(1, CastMember::new(1, CastMemberType::Text(member)));
+        player.movie.cast_manager.casts = vec![cast];
+    });
+    CastMemberRef { cast_lib: 1, cast_member: 1 }
+}
+
+fn member_hyperlinks(member_ref: &CastMemberRef) -> Vec<(usize, usize, String)> {
+    reserve_player_mut(|player| {
+        let member = player.movie.cast_manager.find_member_by_ref(member_ref).unwrap();
+        let CastMemberType::Text(text) = &member.member_type else {
+            panic!("expected a text member");
+        };
+        text.hyperlinks.iter().map(|link| (link.start, link.end, link.data.clone())).collect()
+    })
+}
+
+#[test]
+fn test_html_anchor_spans_nested_styles() {
+    let (spans, hyperlinks) = HtmlParser::parse_html_with_links(
+        "<html><body>See <a href=\"page2\">the <b>next</b> page</a> now</body></html>",
+    )
+    .unwrap();
+    let text: String = spans.iter().map(|span| span.text.as_str()).collect();
+    assert_eq!(text, "See the next page now");
+    assert_eq!(links(&hyperlinks), vec![(4, 17, "page2")]);
+}
+
+#[test]
+fn test_set_hyperlink_clips_and_removes() {
+    let mut hyperlinks = vec![TextHyperlink::new(0, 10, "a".to_string())];
+
+    set_hyperlink(&mut hyperlinks, 3, 6, "b");
+    assert_eq!(links(&hyperlinks), vec![(0, 3, "a"), (3, 6, "b"), (6, 10, "a")]);
+
+    set_hyperlink(&mut hyperlinks, 2, 8, "");
+    assert_eq!(links(&hyperlinks), vec![(0, 2, "a"), (8, 10, "a")]);
+}
+
+#[test]
+fn test_xmed_hyperlink_section() {
+    let text = b"Go to page two";
+    let mut text_section = format!("\x00{},", text.len()).into_bytes();
+    text_section.extend_from_slice(text);
+    text_section.push(0x03);
+
+    let mut link_section = packed_nums(&[6, 14, 0, 0]);
+    link_section.extend_from_slice(b"\x008,page two");
+
+    let mut data = xmed_section(0xFFFF, b"000000");
+    data.extend(xmed_section(0x0002, &text_section));
+    data.extend(xmed_section(0x0019, &link_section));
+
+    let styled = parse_xmed(&data).unwrap();
+    assert_eq!(styled.text, "Go to page two");
+    assert_eq!(styled.hyperlinks.len(), 1);
+    assert_eq!((styled.hyperlinks[0].start, styled.hyperlinks[0].end), (6, 14));
+    assert_eq!(styled.hyperlinks[0].data, "page two");
+}
+
+#[test]
+fn test_hypertext_styles_follow_link_state() {
+    let mut member = TextMember::new();
+    member.text = "ab link cd".to_string();
+    member.info = Some(TextInfo { use_hypertext_styles: true, ..Default::default() });
+    member.hyperlinks = vec![TextHyperlink::new(3, 7, "x".to_string())];
+    let spans = vec![StyledSpan { text: member.text.clone(), style: HtmlStyle::default() }];
+
+    let styled = member.apply_hyperlink_styles(spans.clone());
+    let link_span = styled.iter().find(|span| span.text == "link").unwrap();
+    assert!(link_span.style.underline);
+    assert_eq!(link_span.style.color, Some(0x0000FF));
+    assert!(styled.iter().filter(|span| span.text != "link").all(|span| !span.style.underline));
+
+    member.hyperlinks[0].state = HyperlinkState::Visited;
+    let styled = member.apply_hyperlink_styles(spans.clone());
+    let link_span = styled.iter().find(|span| span.text == "link").unwrap();
+    assert_eq!(link_span.style.color, Some(0x800080));
+
+    member.info = Some(TextInfo::default());
+    let styled = member.apply_hyperlink_styles(spans);
+    assert!(styled.iter().all(|span| !span.style.underline));
+}
+
+#[test]
+fn test_member_setters_update_hyperlinks() {
+    run_test(async {
+        let _player = TestPlayer::new();
+        let member_ref = insert_text_member(TextMember::new());
+
+        TextMemberHandlers::set_prop(
+            &member_ref,
+            "html",
+            Datum::String("<html><body>Go <a href=\"home\">home</a></body></html>".to_string()),
+        )
+        .unwrap();
+        assert_eq!(member_hyperlinks(&member_ref), vec![(3, 7, "home".to_string())]);
+
+        let html = reserve_player_mut(|player| TextMemberHandlers::get_prop(player, &member_ref, "html")).unwrap();
+        let Datum::String(html) = html else { panic!("html should be a string") };
+        assert!(html.contains("<a href=\"home\">home</a>"), "{}", html);
+
+        TextMemberHandlers::set_prop(
+            &member_ref,
+            "rtf",
+            Datum::String(
+                r#"{\rtf1\ansi Read {\field{\*\fldinst HYPERLINK "docs"}{\fldrslt the docs}} today}"#.to_string(),
+            ),
+        )
+        .unwrap();
+        assert_eq!(member_hyperlinks(&member_ref), vec![(5, 13, "docs".to_string())]);
+
+        TextMemberHandlers::set_prop(&member_ref, "text", Datum::String("plain".to_string())).unwrap();
+        assert!(member_hyperlinks(&member_ref).is_empty());
+    });
+}
@@ -0,0 +1,93 @@
+use vm_rust::director::enums::{TransitionInfo, TransitionType};
+use vm_rust::player::geometry::IntRect;
+use vm_rust::player::transition::{changed_area, compose, TransitionSpec};
+
+const W: u32 = 8;
+const H: u32 = 4;
+
+fn solid(value: u8) -> Vec<u8> {
+    vec![value; (W * H * 4) as usize]
+}
+
+fn pixel(data: &[u8], x: u32, y: u32) -> u8 {
+    data[((y * W + x) * 4) as usize]
+}
+
+fn spec(transition_type: TransitionType) -> TransitionSpec {
+    TransitionSpec {
+        transition_type,
+        duration_ms: 1000,
+        chunk_size: 1,
+        change_area: false,
+    }
+}
+
+fn run(transition_type: TransitionType, progress: f64) -> Vec<u8> {
+    let from = solid(0);
+    let to = solid(255);
+    let mut out = solid(7);
+    let area = IntRect::from(0, 0, W as i32, H as i32);
+    compose(&spec(transition_type), &from, &to, &mut out, W, &area, progress);
+    out
+}
+
+#[test]
+fn test_transition_info_from_bytes() {
+    // unk, chunkSize, transType (Dissolve boxy squares), flags (whole stage), duration
+    let info = TransitionInfo::from(&[0u8, 4, 25, 1, 0x03, 0xE8][..]);
+    assert_eq!(info.chunk_size, 4);
+    assert_eq!(info.transition_type, TransitionType::DissolveBoxySquares);
+    assert!(!info.change_area);
+    assert_eq!(info.duration, 1000);
+
+    let info = TransitionInfo::from(&[0u8, 1, 200, 0, 0, 10][..]);
+    assert_eq!(info.transition_type, TransitionType::None);
+    assert!(info.change_area);
+}
+
+#[test]
+fn test_wipe_right_halfway() {
+    let out = run(TransitionType::WipeRight, 0.5);
+    for y in 0..H {
+        for x in 0..W {
+            let expected = if x < W / 2 { 255 } else { 0 };
+            assert_eq!(pixel(&out, x, y), expected, "pixel ({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn test_progress_bounds() {
+    assert!(run(TransitionType::DissolvePixelsFast, 0.0).iter().all(|&b| b == 0));
+    assert!(run(TransitionType::CoverLeft, 1.0).iter().all(|&b| b == 255));
+}
+
+#[test]
+fn test_push_left_moves_both_images() {
+    let mut from = solid(0);
+    // Mark the rightmost column of the old image.
+    for y in 0..H {
+        from[((y * W + W - 1) * 4) as usize] = 100;
+    }
+    let to = solid(255);
+    let mut out = solid(7);
+    let area = IntRect::from(0, 0, W as i32, H as i32);
+    compose(&spec(TransitionType::PushLeft), &from, &to, &mut out, W, &area, 0.25);
+
+    // Old image shifted left by 2 px; the new image fills the last 2 columns.
+    assert_eq!(pixel(&out, W - 3, 0), 100);
+    assert_eq!(pixel(&out, W - 2, 0), 255);
+    assert_eq!(pixel(&out, 0, 0), 0);
+}
+
+#[test]
+fn test_changed_area() {
+    let from = solid(0);
+    let mut to = solid(0);
+    assert!(changed_area(&from, &to, W, H).is_none());
+
+    to[((1 * W + 2) * 4) as usize] = 1;
+    to[((2 * W + 5) * 4) as usize] = 1;
+    let area = changed_area(&from, &to, W, H).unwrap();
+    assert_eq!((area.left, area.top, area.right, area.bottom), (2, 1, 6, 3));
+}
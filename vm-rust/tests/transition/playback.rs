@@ -0,0 +1,136 @@
+use vm_rust::director::enums::{TransitionInfo, TransitionType};
+use vm_rust::player::bitmap::bitmap::{get_system_default_palette, Bitmap, PaletteRef};
+use vm_rust::player::cast_member::{CastMember, CastMemberType, TransitionMember};
+use vm_rust::player::sprite::ColorRef;
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::testing_shared::TestHarness;
+use vm_rust::player::transition::{start_frame_transition, TransitionSpec};
+use vm_rust::player::{reserve_player_mut, reserve_player_ref};
+use vm_rust::rendering::render_stage_to_bitmap;
+
+use crate::common::load_test_movie;
+
+fn set_stage_color(r: u8, g: u8, b: u8) {
+    reserve_player_mut(|player| player.bg_color = ColorRef::Rgb(r, g, b));
+}
+
+fn render_stage() -> Vec<u8> {
+    reserve_player_mut(|player| {
+        let (width, height) = (player.movie.rect.width() as u16, player.movie.rect.height() as u16);
+        let mut bitmap = Bitmap::new(width, height, 32, 32, 0, PaletteRef::BuiltIn(get_system_default_palette()));
+        render_stage_to_bitmap(player, &mut bitmap, None);
+        bitmap.data
+    })
+}
+
+async fn start_player() -> TestPlayer {
+    let mut player = load_test_movie("").await;
+    player.init_movie().await;
+    player
+}
+
+/// Step until the playhead leaves the current frame, returning the stage
+/// as it looked just before.
+async fn step_to_next_frame(player: &mut TestPlayer) -> Vec<u8> {
+    let frame = player.current_frame();
+    loop {
+        let stage = render_stage();
+        player.step_frame().await;
+        if player.current_frame() != frame {
+            return stage;
+        }
+    }
+}
+
+fn active_spec() -> Option<TransitionSpec> {
+    reserve_player_ref(|player| player.transitions.active.as_ref().map(|active| active.spec.clone()))
+}
+
+#[test]
+fn test_puppet_transition_plays_on_the_next_frame_change() {
+    run_test(async {
+        let mut player = start_player().await;
+        player.eval("puppetTransition 1, 4").await.unwrap();
+        let puppet = reserve_player_ref(|player| player.transitions.puppet.clone());
+        assert_eq!(
+            puppet,
+            Some(TransitionSpec {
+                transition_type: TransitionType::WipeRight,
+                duration_ms: 1000,
+                chunk_size: 1,
+                change_area: false,
+            })
+        );
+        assert!(active_spec().is_none());
+
+        step_to_next_frame(&mut player).await;
+        assert_eq!(active_spec(), puppet);
+        assert!(reserve_player_ref(|player| player.transitions.puppet.is_none()));
+    });
+}
+
+#[test]
+fn test_score_transition_channel_starts_a_transition() {
+    run_test(async {
+        let mut player = load_test_movie("").await;
+        // unk, chunkSize, transType (Wipe down), flags (whole stage), duration 2000ms
+        let info = TransitionInfo::from(&[0u8, 2, 3, 1, 0x07, 0xD0][..]);
+        reserve_player_mut(|player| {
+            let member = CastMember::new(2, CastMemberType::Transition(TransitionMember { info }));
+            player.movie.cast_manager.casts[0].insert_member(2, member);
+            player.movie.score.transition_channel_data = vec![(1, 1, 2)];
+        });
+        player.init_movie().await;
+        assert!(active_spec().is_none());
+
+        step_to_next_frame(&mut player).await;
+        assert_eq!(player.current_frame(), 2);
+        assert_eq!(
+            active_spec(),
+            Some(TransitionSpec {
+                transition_type: TransitionType::WipeDown,
+                duration_ms: 2000,
+                chunk_size: 2,
+                change_area: false,
+            })
+        );
+    });
+}
+
+#[test]
+fn test_transition_captures_the_outgoing_frame() {
+    run_test(async {
+        let mut player = start_player().await;
+        set_stage_color(255, 0, 0);
+        player.eval("puppetTransition 1, 4").await.unwrap();
+
+        let outgoing = step_to_next_frame(&mut player).await;
+        let captured = reserve_player_ref(|player| player.transitions.active.as_ref().unwrap().from.clone());
+        assert_eq!(captured, outgoing);
+        assert_eq!(captured[0..4], [255, 0, 0, 255]);
+    });
+}
+
+#[test]
+fn test_start_frame_transition_keeps_the_stage_it_replaces() {
+    run_test(async {
+        let player = start_player().await;
+        set_stage_color(255, 0, 0);
+        player.eval("puppetTransition 1, 4").await.unwrap();
+        reserve_player_mut(|player| start_frame_transition(player, 2));
+
+        set_stage_color(0, 0, 255);
+        let captured = reserve_player_ref(|player| player.transitions.active.as_ref().unwrap().from.clone());
+        assert!(captured.chunks_exact(4).all(|pixel| pixel == [255, 0, 0, 255]));
+        assert_ne!(captured, render_stage());
+    });
+}
+
+#[test]
+fn test_start_frame_transition_ignores_frames_without_a_transition() {
+    run_test(async {
+        let _player = start_player().await;
+        reserve_player_mut(|player| start_frame_transition(player, 2));
+        assert!(active_spec().is_none());
+    });
+}
@@ -0,0 +1,121 @@
+use vm_rust::player::debug::watch::{WatchAction, WatchTarget};
+use vm_rust::player::testing::{run_test, TestPlayer};
+use vm_rust::player::testing_shared::TestHarness;
+use vm_rust::player::{reserve_player_mut, reserve_player_ref};
+
+use crate::common::{eval_result, load_test_movie};
+
+const SOURCE: &str = "\
+property pCount
+global gTotal, gList, gProps, gObj, gResult
+
+on addUp n
+  gTotal = gTotal + n
+end
+
+on bump me
+  pCount = pCount + 1
+end
+
+on grow
+  append(gList, 3)
+  gList.add(4)
+  gProps.b = 2
+end
+";
+
+async fn start_player() -> TestPlayer {
+    let player = load_test_movie(SOURCE).await;
+    player.eval("gTotal = 0").await.unwrap();
+    reserve_player_ref(|player| player.console.clear());
+    player
+}
+
+fn watch(target: WatchTarget, action: WatchAction) -> u32 {
+    reserve_player_mut(|player| player.watch_manager.add_watchpoint(target, action))
+}
+
+fn console_lines() -> Vec<String> {
+    let output = reserve_player_ref(|player| player.console.read());
+    output.lines().map(str::to_owned).collect()
+}
+
+#[test]
+fn test_global_logpoint_reports_values_and_location() {
+    run_test(async {
+        let player = start_player().await;
+        watch(WatchTarget::Global("GTOTAL".to_string()), WatchAction::Log);
+
+        player.eval("addUp(2)").await.unwrap();
+        player.eval("addUp(0)").await.unwrap();
+        player.eval("addUp(3)").await.unwrap();
+
+        let lines = console_lines();
+        // Assigning an unchanged value isn't reported.
+        assert_eq!(lines.len(), 2, "{:?}", lines);
+        assert!(lines[0].starts_with("-- Watchpoint global GTOTAL: 0 -> 2 in addUp of "), "{}", lines[0]);
+        assert!(lines[0].contains(" at bytecode "), "{}", lines[0]);
+        assert!(lines[1].starts_with("-- Watchpoint global GTOTAL: 2 -> 5 "), "{}", lines[1]);
+        reserve_player_ref(|player| assert_eq!(player.watch_manager.watchpoints[0].hit_count, 2));
+    });
+}
+
+#[test]
+fn test_property_and_list_logpoints() {
+    run_test(async {
+        let player = start_player().await;
+        player.eval("gObj = script(1).new()").await.unwrap();
+        player.eval("gObj.pCount = 0").await.unwrap();
+        player.eval("gList = [1, 2]").await.unwrap();
+        player.eval("gProps = [#a: 1]").await.unwrap();
+        let instance = reserve_player_ref(|player| {
+            let instance_ref = player.globals.get("gObj").unwrap();
+            player.get_datum(instance_ref).to_script_instance_ref().unwrap().clone()
+        });
+        watch(
+            WatchTarget::Property { instance, prop_name: "pCount".to_string() },
+            WatchAction::Log,
+        );
+        watch(WatchTarget::List(player.get_global_ref("gList").unwrap()), WatchAction::Log);
+        watch(WatchTarget::List(player.get_global_ref("gProps").unwrap()), WatchAction::Log);
+        reserve_player_ref(|player| player.console.clear());
+
+        player.eval("gObj.bump()").await.unwrap();
+        player.eval("grow()").await.unwrap();
+
+        let lines = console_lines();
+        assert_eq!(lines.len(), 4, "{:?}", lines);
+        assert!(lines[0].contains("property pCount of script instance") && lines[0].contains(": 0 -> 1 in bump of "), "{}", lines[0]);
+        assert!(lines[1].contains(": [1, 2] -> [1, 2, 3] in grow of "), "{}", lines[1]);
+        assert!(lines[2].contains(": [1, 2, 3] -> [1, 2, 3, 4] in grow of "), "{}", lines[2]);
+        assert!(lines[3].contains(": [#a: 1] -> [#a: 1, #b: 2] in grow of "), "{}", lines[3]);
+    });
+}
+
+#[test]
+fn test_break_watchpoint_pauses_after_the_change() {
+    run_test(async {
+        let player = start_player().await;
+        let id = watch(WatchTarget::Global("gTotal".to_string()), WatchAction::Break);
+
+        let inspect_and_resume = async {
+            while reserve_player_ref(|player| player.current_breakpoint.is_none()) {
+                async_std::task::yield_now().await;
+            }
+            reserve_player_ref(|player| {
+                let breakpoint = player.current_breakpoint.as_ref().unwrap();
+                let hit = breakpoint.watch_hit.as_ref().unwrap();
+                assert_eq!(hit.watchpoint_id, id);
+                assert_eq!((hit.old_value.as_str(), hit.new_value.as_str()), ("0", "4"));
+                assert_eq!(hit.handler_name.as_deref(), Some("addUp"));
+                assert_eq!(breakpoint.breakpoint.handler_name, "addUp");
+                assert_eq!(Some(breakpoint.bytecode_index), hit.bytecode_index);
+            });
+            reserve_player_mut(|player| player.resume_breakpoint());
+        };
+
+        let (result, ()) = futures::join!(player.eval("addUp(4)"), inspect_and_resume);
+        result.unwrap();
+        assert_eq!(eval_result(&player, "gTotal").await, "4");
+    });
+}
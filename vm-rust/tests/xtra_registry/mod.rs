@@ -0,0 +1,72 @@
+use vm_rust::director::chunks::xtra_list::XtraListChunk;
+use vm_rust::player::testing::run_test;
+use vm_rust::player::xtra::manager::{find_xtra, normalize_xtra_name, xtra_report, XtraStatus};
+
+use crate::common::{eval_result, load_test_movie};
+
+fn pascal(text: &str) -> Vec<u8> {
+    let mut bytes = vec![text.len() as u8];
+    bytes.extend_from_slice(text.as_bytes());
+    bytes
+}
+
+fn xtra_entry(guid_byte: u8, flags: u32, strings: &[&str]) -> Vec<u8> {
+    let mut body = vec![guid_byte; 16];
+    body.extend(flags.to_be_bytes());
+    for text in strings {
+        body.extend(pascal(text));
+    }
+    let mut entry = (body.len() as u32).to_be_bytes().to_vec();
+    entry.extend(body);
+    entry
+}
+
+#[test]
+fn test_xtra_list_chunk_and_report() {
+    let mut data = 3u32.to_be_bytes().to_vec();
+    data.extend(xtra_entry(0xA7, 1, &["Multiusr.x32", "http://download.macromedia.com/xtras/Multiusr.x32"]));
+    data.extend(xtra_entry(0x9F, 0, &["TextXtra"]));
+    data.extend(xtra_entry(0x18, 0, &["OSControl.x32"]));
+
+    let list = XtraListChunk::from_bytes(&data).unwrap();
+    let names: Vec<&str> = list.entries.iter().map(|entry| entry.name.as_str()).collect();
+    assert_eq!(names, ["Multiusr", "TextXtra", "OSControl"]);
+    assert_eq!(list.entries[0].guid, [0xA7; 16]);
+    assert_eq!(list.entries[0].flags, 1);
+    assert_eq!(list.entries[0].urls, ["http://download.macromedia.com/xtras/Multiusr.x32"]);
+    assert!(list.entries[1].urls.is_empty());
+
+    // Truncated entries and strings that overrun their entry are errors.
+    assert!(XtraListChunk::from_bytes(&data[..data.len() - 4]).is_err());
+    let mut overrun = 1u32.to_be_bytes().to_vec();
+    overrun.extend(xtra_entry(0, 0, &["Multiusr"]));
+    overrun[4 + 4 + 16 + 4] = 200;
+    assert!(XtraListChunk::from_bytes(&overrun).is_err());
+
+    let report = xtra_report(Some(&list));
+    let statuses: Vec<XtraStatus> = report.required.iter().map(|entry| entry.status).collect();
+    assert_eq!(statuses, [XtraStatus::Implemented, XtraStatus::Native, XtraStatus::Missing]);
+    assert_eq!(report.missing, ["OSControl"]);
+    assert!(xtra_report(None).required.is_empty());
+}
+
+#[test]
+fn test_find_xtra_by_alias() {
+    assert_eq!(normalize_xtra_name("Text Asset.X32"), "textasset");
+    assert_eq!(find_xtra("Multiuser").unwrap().name(), "Multiusr");
+    assert_eq!(find_xtra("multiusr.x32").unwrap().name(), "Multiusr");
+    assert_eq!(find_xtra("FILEIO").unwrap().name(), "FileIO");
+    assert_eq!(find_xtra("pregex.x32").unwrap().name(), "PRegEx");
+    assert_eq!(find_xtra("BuddyAPI.x32").unwrap().name(), "BudAPI");
+    assert!(find_xtra("NoSuchXtra").is_none());
+}
+
+#[test]
+fn test_xtra_static_handlers() {
+    run_test(async {
+        let player = load_test_movie("global gResult\n").await;
+
+        assert_eq!(eval_result(&player, "getOSDirectory(xtra(\"FileIO\"))").await, "\"/\"");
+        assert!(eval_result(&player, "xtra(\"fileio\").interface()").await.contains("xtra FileIO"));
+    });
+}